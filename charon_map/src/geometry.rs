@@ -3,9 +3,12 @@
 use std::f64::consts::PI;
 use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "skia")]
 use skia_safe::{ISize, Point as SkiaPoint};
+#[cfg(feature = "valhalla")]
 use valhalla::LatLon;
+#[cfg(feature = "valhalla")]
 use valhalla::proto::Location;
 
 use crate::tiles::{MAX_ZOOM, TILE_SIZE, TileIndex};
@@ -19,11 +22,14 @@ const ZERO_PIXEL_SIZE: f64 = EARTH_EQUATOR / TILE_SIZE as f64;
 /// 2D object position.
 #[derive(PartialEq, Eq, Copy, Clone, Default, Debug)]
 pub struct Point<T = i32> {
+    /// Horizontal position, increasing to the right.
     pub x: T,
+    /// Vertical position, increasing downwards.
     pub y: T,
 }
 
 impl<T> Point<T> {
+    /// Create a new point.
     pub fn new(x: T, y: T) -> Self {
         Self { x, y }
     }
@@ -74,18 +80,21 @@ impl From<Point> for Point<f64> {
     }
 }
 
+#[cfg(feature = "skia")]
 impl From<Point> for SkiaPoint {
     fn from(point: Point) -> Self {
         Self::new(point.x as f32, point.y as f32)
     }
 }
 
+#[cfg(feature = "skia")]
 impl From<Point<f64>> for SkiaPoint {
     fn from(point: Point<f64>) -> Self {
         Self::new(point.x as f32, point.y as f32)
     }
 }
 
+#[cfg(feature = "skia")]
 impl From<Point<f32>> for SkiaPoint {
     fn from(point: Point<f32>) -> Self {
         Self::new(point.x, point.y)
@@ -169,11 +178,14 @@ impl Div<f64> for Point<f64> {
 /// 2D object size.
 #[derive(Hash, PartialEq, Eq, Copy, Clone, Default, Debug)]
 pub struct Size<T = u32> {
+    /// Horizontal extent.
     pub width: T,
+    /// Vertical extent.
     pub height: T,
 }
 
 impl<T> Size<T> {
+    /// Create a new size.
     pub fn new(width: T, height: T) -> Self {
         Self { width, height }
     }
@@ -209,6 +221,7 @@ impl From<Size> for Size<f64> {
     }
 }
 
+#[cfg(feature = "skia")]
 impl From<Size> for ISize {
     fn from(size: Size) -> Self {
         ISize::new(size.width as i32, size.height as i32)
@@ -246,13 +259,16 @@ impl<T: Sub<Output = T>> Sub<Size<T>> for Size<T> {
 }
 
 /// Point in geographical space.
-#[derive(Serialize, PartialEq, Default, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, PartialEq, Default, Copy, Clone, Debug)]
 pub struct GeoPoint {
+    /// Latitude in degrees, positive north of the equator.
     pub lat: f64,
+    /// Longitude in degrees, positive east of the prime meridian.
     pub lon: f64,
 }
 
 impl GeoPoint {
+    /// Create a new point from latitude and longitude in degrees.
     pub fn new(lat: f64, long: f64) -> Self {
         Self { lon: long, lat }
     }
@@ -315,8 +331,41 @@ impl GeoPoint {
         let c = 2. * a.sqrt().atan2((1. - a).sqrt());
         (EARTH_RADIUS * c).round() as u32
     }
+
+    /// Calculate the initial bearing in degrees towards another point.
+    ///
+    /// The bearing is measured clockwise from north, within `0..360`.
+    pub fn bearing(&self, other: Self) -> f64 {
+        let slat = self.lat.to_radians();
+        let olat = other.lat.to_radians();
+        let delta_lon = (other.lon - self.lon).to_radians();
+
+        let y = delta_lon.sin() * olat.cos();
+        let x = slat.cos() * olat.sin() - slat.sin() * olat.cos() * delta_lon.cos();
+        y.atan2(x).to_degrees().rem_euclid(360.)
+    }
+
+    /// Get the point at a distance in meters along a bearing.
+    ///
+    /// The bearing is measured clockwise from north.
+    pub fn destination(&self, bearing: f64, distance: f64) -> Self {
+        const EARTH_RADIUS: f64 = 6_371_000.;
+
+        let lat = self.lat.to_radians();
+        let lon = self.lon.to_radians();
+        let bearing = bearing.to_radians();
+        let angle = distance / EARTH_RADIUS;
+
+        let dest_lat = (lat.sin() * angle.cos() + lat.cos() * angle.sin() * bearing.cos()).asin();
+        let dest_lon = lon
+            + (bearing.sin() * angle.sin() * lat.cos())
+                .atan2(angle.cos() - lat.sin() * dest_lat.sin());
+
+        Self::new(dest_lat.to_degrees(), (dest_lon.to_degrees() + 540.).rem_euclid(360.) - 180.)
+    }
 }
 
+#[cfg(feature = "valhalla")]
 impl From<GeoPoint> for Location {
     fn from(point: GeoPoint) -> Self {
         let latlon = LatLon(point.lat, point.lon);
@@ -402,6 +451,40 @@ pub fn zoom_for_distance(lat: f64, meters: f64, pixels: f64) -> u8 {
     zoom.clamp(0., MAX_ZOOM as f64).floor() as u8
 }
 
+/// Get points along a path which are spaced `interval` meters apart.
+///
+/// The first and last point of the path are always included.
+pub fn sample_path(path: impl IntoIterator<Item = GeoPoint>, interval: f64) -> Vec<GeoPoint> {
+    let mut path = path.into_iter();
+    let mut last = match path.next() {
+        Some(first) => first,
+        None => return Vec::new(),
+    };
+
+    let mut samples = vec![last];
+    let mut remaining = interval;
+    for point in path {
+        // Interpolate samples between nodes further apart than the interval.
+        let mut distance = last.distance(point) as f64;
+        while distance >= remaining {
+            last = last.destination(last.bearing(point), remaining);
+            samples.push(last);
+
+            distance -= remaining;
+            remaining = interval;
+        }
+
+        remaining -= distance;
+        last = point;
+    }
+
+    if remaining < interval {
+        samples.push(last);
+    }
+
+    samples
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,6 +533,32 @@ mod tests {
         assert_eq!(a.distance(b), 1_275_570);
     }
 
+    #[test]
+    fn bearing() {
+        let origin = GeoPoint::new(0., 0.);
+        assert_eq!(origin.bearing(GeoPoint::new(1., 0.)), 0.);
+        assert_eq!(origin.bearing(GeoPoint::new(0., 1.)), 90.);
+        assert_eq!(origin.bearing(GeoPoint::new(-1., 0.)), 180.);
+        assert_eq!(origin.bearing(GeoPoint::new(0., -1.)), 270.);
+    }
+
+    #[test]
+    fn destination() {
+        let origin = GeoPoint::new(52.52, 13.405);
+        for bearing in [0., 90., 200.] {
+            let destination = origin.destination(bearing, 1000.);
+            assert_eq!(origin.distance(destination), 1000);
+
+            // Compare bearings across north, where 359.99° is next to 0°.
+            let delta = (origin.bearing(destination) - bearing + 180.).rem_euclid(360.) - 180.;
+            assert!(delta.abs() < 0.01);
+        }
+
+        // Wrap around the antimeridian.
+        let destination = GeoPoint::new(0., 179.99).destination(90., 10_000.);
+        assert!((destination.lon + 179.92).abs() < 0.01);
+    }
+
     #[test]
     fn meters_per_pixel() {
         for lat in 0..90 {
@@ -513,4 +622,27 @@ mod tests {
 
         assert_eq!(zoom_for_distance(80., 53., 2.), 10);
     }
+
+    #[test]
+    fn path_sampling() {
+        assert!(sample_path([], 100.).is_empty());
+
+        let start = GeoPoint::new(52.52, 13.405);
+        assert_eq!(sample_path([start], 100.), [start]);
+
+        // Long edges are split at the interval.
+        let end = start.destination(90., 250.);
+        let samples = sample_path([start, end], 100.);
+        assert_eq!(samples.len(), 4);
+        assert_eq!(samples[0], start);
+        assert_eq!(samples[3], end);
+        assert!(samples[1].distance(start).abs_diff(100) <= 1);
+        assert!(samples[2].distance(start).abs_diff(200) <= 1);
+
+        // Short edges are merged.
+        let path: Vec<_> = (0..10).map(|i| start.destination(0., i as f64 * 20.)).collect();
+        let samples = sample_path(path.iter().copied(), 100.);
+        assert_eq!(samples.len(), 3);
+        assert_eq!(samples[2], path[9]);
+    }
 }
@@ -0,0 +1,124 @@
+//! Touch input handling.
+//!
+//! Physical touch positions are compared against the `max_tap_distance`,
+//! which is the square of the maximum distance in pixels a touch point can
+//! move before it is considered a drag instead of a tap.
+
+use std::time::Instant;
+
+use crate::geometry::Point;
+
+/// Check if a touch point moved far enough from its start to be a drag.
+pub fn is_drag(start: Point<f64>, point: Point<f64>, max_tap_distance: f64) -> bool {
+    let delta = point - start;
+    delta.x.powi(2) + delta.y.powi(2) > max_tap_distance
+}
+
+/// Deceleration of kinetic scrolling.
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub struct Inertia {
+    /// Milliseconds per velocity tick.
+    pub interval: u16,
+    /// Percentage of velocity retained each tick.
+    pub friction: f64,
+}
+
+/// Velocity of kinetic scrolling after a drag.
+#[derive(Default)]
+pub struct Velocity {
+    last_tick: Option<Instant>,
+    velocity: Point<f64>,
+}
+
+impl Velocity {
+    /// Check if there is any velocity active.
+    pub fn is_moving(&self) -> bool {
+        self.velocity != Point::default()
+    }
+
+    /// Set the velocity.
+    pub fn set(&mut self, velocity: Point<f64>) {
+        self.velocity = velocity;
+        self.last_tick = None;
+    }
+
+    /// Reset all velocity.
+    pub fn stop(&mut self) {
+        self.velocity = Point::default();
+    }
+
+    /// Apply and update the current velocity.
+    ///
+    /// Returns the distance traveled since the last call, or `None` while the
+    /// velocity is stopped or was just set.
+    pub fn apply(&mut self, inertia: Inertia) -> Option<Point<f64>> {
+        // No-op without velocity.
+        if self.velocity == Point::default() {
+            return None;
+        }
+
+        // Initialize velocity on the first tick.
+        //
+        // This avoids applying velocity while the user is still interacting.
+        let last_tick = match self.last_tick.take() {
+            Some(last_tick) => last_tick,
+            None => {
+                self.last_tick = Some(Instant::now());
+                return None;
+            },
+        };
+
+        // Calculate velocity steps since last tick.
+        let now = Instant::now();
+        let interval = (now - last_tick).as_micros() as f64 / (inertia.interval as f64 * 1_000.);
+
+        // Update velocity and calculate the expected delta.
+        let apply = |velocity: &mut f64| {
+            let delta =
+                *velocity * (1. - inertia.friction.powf(interval + 1.)) / (1. - inertia.friction);
+            *velocity *= inertia.friction.powf(interval);
+            delta
+        };
+        let x = apply(&mut self.velocity.x);
+        let y = apply(&mut self.velocity.y);
+
+        // Request next tick if velocity is significant.
+        if self.velocity.x.abs() > 1. || self.velocity.y.abs() > 1. {
+            self.last_tick = Some(now);
+        } else {
+            self.velocity = Point::default();
+        }
+
+        Some(Point::new(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drag_distance() {
+        let start = Point::new(10., 10.);
+        assert!(!is_drag(start, Point::new(13., 14.), 25.));
+        assert!(is_drag(start, Point::new(13., 15.), 25.));
+    }
+
+    #[test]
+    fn velocity_decay() {
+        let inertia = Inertia { interval: 30, friction: 0.5 };
+        let mut velocity = Velocity::default();
+        assert_eq!(velocity.apply(inertia), None);
+
+        // Velocity is only applied after the first tick.
+        velocity.set(Point::new(100., 0.));
+        assert_eq!(velocity.apply(inertia), None);
+        assert!(velocity.is_moving());
+
+        let delta = velocity.apply(inertia).unwrap();
+        assert!(delta.x > 0. && delta.y == 0.);
+
+        velocity.stop();
+        assert!(!velocity.is_moving());
+    }
+}
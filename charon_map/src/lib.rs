@@ -0,0 +1,48 @@
+//! Map rendering primitives shared by Charon and other Wayland map clients.
+//!
+//! This crate contains the parts of Charon's map widget which are independent
+//! of its application state:
+//!
+//!  - [`geometry`] for screen and geographic coordinates, including the
+//!    conversion between geographic coordinates and map tiles
+//!  - [`tiles`] for addressing map tiles and positioning them on the screen
+//!  - [`source`] for resolving tile URLs from tileserver templates
+//!  - [`view`] for the position and zoom of a map view
+//!  - [`marker`] for touch handling of map markers
+//!  - [`input`] for tap detection and kinetic scrolling
+//!
+//! All coordinates on the screen are physical pixels, with the origin in the
+//! top-left corner.
+//!
+//! # Features
+//!
+//!  - `skia`: conversions to [Skia](https://skia.org) points and sizes
+//!  - `valhalla`: conversion of geographic points to [Valhalla] locations
+//!  - `profiling`: instrumentation for the [profiling] crate
+//!
+//! [Valhalla]: https://github.com/valhalla/valhalla
+//! [profiling]: https://crates.io/crates/profiling
+//!
+//! # Examples
+//!
+//! ```
+//! use charon_map::geometry::{GeoPoint, Point, Size};
+//! use charon_map::source::tile_url;
+//! use charon_map::view::MapPosition;
+//!
+//! // Center the view on Berlin and drag it 100 pixels to the right.
+//! let mut position = MapPosition::new(GeoPoint::new(52.52, 13.405), 14);
+//! position.move_by(Point::new(100., 0.));
+//!
+//! // Get the URLs of all visible tiles.
+//! for (tile, _screen_point) in position.tiles(Size::new(720, 1440)) {
+//!     let _url = tile_url("https://tile.openstreetmap.org/{z}/{x}/{y}.png", tile);
+//! }
+//! ```
+
+pub mod geometry;
+pub mod input;
+pub mod marker;
+pub mod source;
+pub mod tiles;
+pub mod view;
@@ -0,0 +1,42 @@
+//! Touchable map markers.
+
+use crate::geometry::Point;
+use crate::tiles::{TileIndex, TileIter};
+
+/// Check if a physical screen point is within a marker's touch radius.
+///
+/// The marker's tile must use the same zoom level as the `tiles` iterator.
+pub fn touches(
+    tiles: &TileIter,
+    marker: (TileIndex, Point),
+    point: Point<f64>,
+    radius: f64,
+) -> bool {
+    let (tile, offset) = marker;
+    let marker_point: Point<f64> = tiles.tile_point(tile, offset).into();
+
+    let delta = marker_point - point;
+    delta.x.hypot(delta.y) <= radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Size;
+
+    #[test]
+    fn marker_touch() {
+        let tile = TileIndex::new(8504, 5473, 14);
+        let tiles = TileIter::new(Size::new(300, 300), tile, Point::new(128, 128), 1.);
+
+        // The view is centered on the marker.
+        let marker = (tile, Point::new(128, 128));
+        assert!(touches(&tiles, marker, Point::new(150., 150.), 10.));
+        assert!(touches(&tiles, marker, Point::new(156., 158.), 10.));
+        assert!(!touches(&tiles, marker, Point::new(157., 158.), 10.));
+
+        // Markers in neighboring tiles are offset by the tile size.
+        let marker = (TileIndex::new(8505, 5473, 14), Point::new(0, 128));
+        assert!(touches(&tiles, marker, Point::new(278., 150.), 1.));
+    }
+}
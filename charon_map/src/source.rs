@@ -0,0 +1,98 @@
+//! Raster tile sources.
+//!
+//! Tile sources are described by URL templates, which are resolved to the
+//! URL of each individual tile.
+
+use std::fmt::Write;
+
+use crate::tiles::TileIndex;
+
+/// Get a tile's URL from a tileserver template.
+///
+/// Supported variables are `{x}`, `{y}`, `{z}`, `{-y}` for the TMS scheme,
+/// `{quadkey}`, and subdomain lists like `{a-c}` or `{a,b,c}`. Unknown
+/// variables are left unchanged.
+pub fn tile_url(template: &str, index: TileIndex) -> String {
+    let mut url = String::with_capacity(template.len());
+
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        url.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let Some(end) = rest.find('}') else { break };
+        let variable = &rest[1..end];
+
+        let _ = match variable {
+            "x" => write!(url, "{}", index.x),
+            "y" => write!(url, "{}", index.y),
+            "z" => write!(url, "{}", index.z),
+            "-y" => write!(url, "{}", (1u32 << index.z) - 1 - index.y),
+            "quadkey" => write!(url, "{}", quadkey(index)),
+            _ => match subdomain(variable, index) {
+                Some(subdomain) => write!(url, "{subdomain}"),
+                None => write!(url, "{}", &rest[..=end]),
+            },
+        };
+
+        rest = &rest[end + 1..];
+    }
+    url.push_str(rest);
+
+    url
+}
+
+/// Get a tile's Bing Maps quadkey.
+fn quadkey(index: TileIndex) -> String {
+    (1..=index.z)
+        .rev()
+        .map(|level| {
+            let mask = 1 << (level - 1);
+            let digit = (index.x & mask != 0) as u8 + 2 * (index.y & mask != 0) as u8;
+            char::from(b'0' + digit)
+        })
+        .collect()
+}
+
+/// Get a tile's subdomain from a list like `a,b,c` or a range like `a-c`.
+///
+/// Subdomains are rotated based on the tile's position, ensuring that each
+/// tile is always requested from the same subdomain.
+fn subdomain(variable: &str, index: TileIndex) -> Option<String> {
+    let subdomains: Vec<String> = if variable.contains(',') {
+        variable.split(',').map(str::to_string).collect()
+    } else {
+        let (first, last) = variable.split_once('-')?;
+        let (mut first_chars, mut last_chars) = (first.chars(), last.chars());
+        let (first, last) = (first_chars.next()?, last_chars.next()?);
+        if first_chars.next().is_some() || last_chars.next().is_some() || first > last {
+            return None;
+        }
+        (first..=last).map(String::from).collect()
+    };
+
+    let position = index.x as usize + index.y as usize;
+    Some(subdomains[position % subdomains.len()].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_url_templates() {
+        let index = TileIndex::new(3, 5, 3);
+
+        let url = tile_url("https://tile.example.org/{z}/{x}/{y}.png?key={key}", index);
+        assert_eq!(url, "https://tile.example.org/3/3/5.png?key={key}");
+
+        assert_eq!(tile_url("/{z}/{x}/{-y}.png", index), "/3/3/2.png");
+        assert_eq!(tile_url("/{quadkey}.jpeg", index), "/213.jpeg");
+
+        assert_eq!(tile_url("https://{a-c}.example.org", index), "https://c.example.org");
+        assert_eq!(tile_url("https://{t0,t1}.example.org", index), "https://t0.example.org");
+        assert_eq!(tile_url("https://{c-a}.example.org", index), "https://{c-a}.example.org");
+
+        assert_eq!(tile_url("/{z}/{x", index), "/3/{x");
+    }
+}
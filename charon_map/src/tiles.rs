@@ -0,0 +1,329 @@
+//! Map tile addressing.
+
+use std::collections::HashSet;
+use std::f64::consts::SQRT_2;
+use std::iter;
+
+use crate::geometry::{self, GeoPoint, Point, Size};
+
+/// Width and height of a single tile.
+pub const TILE_SIZE: i32 = 256;
+
+/// Maximum tile zoom level.
+pub const MAX_ZOOM: u8 = 19;
+
+/// Iterator over positioned tiles.
+pub struct TileIter {
+    tile_index: TileIndex,
+    origin: Point,
+
+    max_tiles_x: u32,
+    max_tiles_y: u32,
+    tile_count: u32,
+    tile_size: i32,
+
+    screen_size: Size,
+    scale: f64,
+
+    index: u32,
+}
+
+impl TileIter {
+    /// Create an iterator over all tiles visible on the screen.
+    ///
+    /// The screen is centered on the pixel `offset` within `tile_index`, with
+    /// all tiles scaled by the sub-tile zoom `scale`.
+    pub fn new(screen_size: Size, mut tile_index: TileIndex, offset: Point, scale: f64) -> Self {
+        // Get position of the tile's top-left on the screen.
+        let x_offset = (offset.x as f64 * scale).round() as i32;
+        let y_offset = (offset.y as f64 * scale).round() as i32;
+        let x_origin = screen_size.width as i32 / 2 - x_offset;
+        let y_origin = screen_size.height as i32 / 2 - y_offset;
+
+        // Get top-left tile's indices and offset.
+        let tile_size = (TILE_SIZE as f64 * scale).round() as i32;
+        let x_delta = ((x_origin + tile_size - 1) / tile_size).min(tile_index.x as i32);
+        tile_index.x -= x_delta as u32;
+        let y_delta = ((y_origin + tile_size - 1) / tile_size).min(tile_index.y as i32);
+        tile_index.y -= y_delta as u32;
+        let origin = Point::new(x_origin - x_delta * tile_size, y_origin - y_delta * tile_size);
+
+        // Calculate maximum tile indices.
+
+        let tile_count = 1 << tile_index.z as u32;
+
+        let available_x = screen_size.width as i32 - origin.x;
+        let tiles_x = ((available_x + tile_size - 1) / tile_size) as u32;
+        let max_tiles_x = tiles_x.min(tile_count - tile_index.x);
+
+        let available_y = screen_size.height as i32 - origin.y;
+        let tiles_y = ((available_y + tile_size - 1) / tile_size) as u32;
+        let max_tiles_y = tiles_y.min(tile_count - tile_index.y);
+
+        Self {
+            max_tiles_x,
+            max_tiles_y,
+            screen_size,
+            tile_index,
+            tile_count,
+            tile_size,
+            origin,
+            scale,
+            index: Default::default(),
+        }
+    }
+
+    /// Get iterator over tile indices surrounding the viewport.
+    pub fn border_tiles(&self) -> impl Iterator<Item = TileIndex> {
+        let min_x = self.tile_index.x.saturating_sub(1);
+        let max_x = self.tile_index.x + self.max_tiles_x;
+
+        let min_y = self.tile_index.y.saturating_sub(1);
+        let max_y = self.tile_index.y + self.max_tiles_y;
+
+        let x_range = min_x..(max_x + 1).min(self.tile_count);
+        let y_range = min_y..(max_y + 1).min(self.tile_count);
+
+        // Use empty ranges to skip rows/columns outside the tileset.
+        let top_range = if self.tile_index.x > 0 { x_range.clone() } else { 0..0 };
+        let bottom_range = if max_y < self.tile_count { x_range } else { 0..0 };
+        let left_range = if self.tile_index.y > 0 { y_range.clone() } else { 0..0 };
+        let right_range = if max_x < self.tile_count { y_range } else { 0..0 };
+
+        (top_range.zip(iter::repeat(min_y)))
+            .chain(bottom_range.zip(iter::repeat(max_y)))
+            .chain(iter::repeat(min_x).zip(left_range))
+            .chain(iter::repeat(max_x).zip(right_range))
+            .map(|(x, y)| TileIndex::new(x, y, self.tile_index.z))
+    }
+
+    /// Get physical position of a map point on the screen.
+    ///
+    /// The supplied `tile_index` must have a `z` coordinate matching the
+    /// iterator's `z` coordinate.
+    pub fn screen_point(&self, tile_index: TileIndex, offset: Point) -> Option<Point> {
+        let point = self.tile_point(tile_index, offset);
+
+        // Check whether point is visible.
+        if point.x < 0
+            || point.y < 0
+            || point.x >= self.screen_size.width as i32
+            || point.y >= self.screen_size.height as i32
+        {
+            None
+        } else {
+            Some(point)
+        }
+    }
+
+    /// Get physical position of a map point in screen coordinates.
+    ///
+    /// The supplied `tile_index` must have a `z` coordinate matching the
+    /// iterator's `z` coordinate.
+    pub fn tile_point(&self, tile_index: TileIndex, offset: Point) -> Point {
+        debug_assert_eq!(tile_index.z, self.tile_index.z);
+
+        let x_delta = tile_index.x as i32 - self.tile_index.x as i32;
+        let y_delta = tile_index.y as i32 - self.tile_index.y as i32;
+
+        // Apply fractional scale to tile offset.
+        let mut point = self.origin + offset * self.scale;
+
+        point.x += x_delta * self.tile_size;
+        point.y += y_delta * self.tile_size;
+
+        point
+    }
+
+    /// Target width and height of the tile.
+    pub fn tile_size(&self) -> i32 {
+        self.tile_size
+    }
+
+    /// Physical size of the area covered by this iterator.
+    pub fn screen_size(&self) -> Size {
+        self.screen_size
+    }
+}
+
+impl Iterator for TileIter {
+    type Item = (TileIndex, Point);
+
+    /// Get the next tile and its screen position.
+    fn next(&mut self) -> Option<Self::Item> {
+        let x_delta = self.index % self.max_tiles_x;
+        let y_delta = self.index / self.max_tiles_x;
+
+        // Stop if there's no more tiles available.
+        if y_delta >= self.max_tiles_y {
+            return None;
+        }
+
+        self.index += 1;
+
+        let tile_x = self.tile_index.x + x_delta;
+        let tile_y = self.tile_index.y + y_delta;
+        let index = TileIndex::new(tile_x, tile_y, self.tile_index.z);
+
+        let x = self.origin.x + x_delta as i32 * self.tile_size;
+        let y = self.origin.y + y_delta as i32 * self.tile_size;
+        let point = Point::new(x, y);
+
+        Some((index, point))
+    }
+}
+
+/// Get all tiles within a distance of a path, up to a maximum zoom level.
+///
+/// Tiles are ordered by their first appearance along the path.
+pub fn corridor_tiles(
+    path: impl IntoIterator<Item = GeoPoint>,
+    radius: f64,
+    max_zoom: u8,
+) -> Vec<TileIndex> {
+    let mut tiles = Vec::new();
+    let mut known = HashSet::new();
+    for point in geometry::sample_path(path, radius) {
+        let north_west = point.destination(315., radius * SQRT_2);
+        let south_east = point.destination(135., radius * SQRT_2);
+
+        for zoom in 0..=max_zoom {
+            let (min, _) = north_west.tile(zoom);
+            let (max, _) = south_east.tile(zoom);
+
+            for x in min.x..=max.x {
+                for y in min.y..=max.y {
+                    let index = TileIndex::new(x, y, zoom);
+                    if known.insert(index) {
+                        tiles.push(index);
+                    }
+                }
+            }
+        }
+    }
+    tiles
+}
+
+/// Index uniquely identifying a map tile.
+#[derive(Default, Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct TileIndex {
+    /// Tile column, starting at the antimeridian in the west.
+    pub x: u32,
+    /// Tile row, starting in the north.
+    pub y: u32,
+    /// Tile zoom level.
+    pub z: u8,
+}
+
+impl TileIndex {
+    /// Create a new tile index.
+    pub fn new(x: u32, y: u32, z: u8) -> Self {
+        Self { x, y, z }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_tile_iter() {
+        let size = Size::new(TILE_SIZE as u32, TILE_SIZE as u32);
+        let index = TileIndex::new(8504, 5473, 14);
+        let offset = Point::new(128, 128);
+
+        let mut iter = TileIter::new(size, index, offset, 1.);
+
+        let (iter_index, point) = iter.next().unwrap();
+        assert_eq!(iter_index, index);
+        assert_eq!(point, Point::new(0, 0));
+
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn partial_tiles_iter() {
+        let size = Size::new(300, 300);
+        let index = TileIndex::new(8504, 5473, 14);
+        let offset = Point::new(128, 128);
+
+        let mut iter = TileIter::new(size, index, offset, 1.);
+
+        for i in 0..9 {
+            let tile_x = (8503 + i % 3) as u32;
+            let tile_y = (5472 + i / 3) as u32;
+
+            let x = -234 + i % 3 * TILE_SIZE;
+            let y = -234 + i / 3 * TILE_SIZE;
+
+            let (iter_index, point) = iter.next().unwrap();
+            assert_eq!(iter_index, TileIndex::new(tile_x, tile_y, index.z));
+            assert_eq!(point, Point::new(x, y));
+        }
+    }
+
+    #[test]
+    fn map_border_iter() {
+        let size = Size::new(300, 300);
+        let index = TileIndex::new(0, 0, 0);
+        let offset = Point::new(128, 128);
+
+        let mut iter = TileIter::new(size, index, offset, 1.);
+
+        let (iter_index, point) = iter.next().unwrap();
+        assert_eq!(iter_index, index);
+        assert_eq!(point, Point::new(22, 22));
+
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn surrounding_tiles() {
+        let size = Size::new(TILE_SIZE as u32, TILE_SIZE as u32);
+        let index = TileIndex::new(1, 1, 14);
+        let offset = Point::new(128, 128);
+
+        let iter = TileIter::new(size, index, offset, 1.);
+        let mut border_tiles = iter.border_tiles();
+
+        // Top row.
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 0, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(1, 0, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 0, 14));
+
+        // Bottom row.
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 2, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(1, 2, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 2, 14));
+
+        // Left column.
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 0, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 1, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 2, 14));
+
+        // Right column.
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 0, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 1, 14));
+        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 2, 14));
+    }
+
+    #[test]
+    fn route_corridor_tiles() {
+        let start = GeoPoint::new(52.52, 13.405);
+        let tiles = corridor_tiles([start], 100., 2);
+        let expected = [TileIndex::new(0, 0, 0), TileIndex::new(1, 0, 1), TileIndex::new(2, 1, 2)];
+        assert_eq!(tiles, expected);
+
+        // Every tile is only downloaded once.
+        let end = start.destination(90., 20_000.);
+        let tiles = corridor_tiles([start, end], 1_000., 14);
+        let unique: HashSet<_> = tiles.iter().collect();
+        assert_eq!(unique.len(), tiles.len());
+
+        // The corridor covers the entire path.
+        for i in 0..=20 {
+            let point = start.destination(90., i as f64 * 1_000.);
+            assert!(tiles.contains(&point.tile(14).0));
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Map view state.
+
+use crate::geometry::{GeoPoint, Point, Size};
+use crate::tiles::{TILE_SIZE, TileIndex, TileIter};
+
+/// Position and zoom of a map view.
+///
+/// The view is centered on a pixel within a tile of the current tile zoom
+/// level. Zoom levels between two tile zoom levels are rendered by scaling the
+/// tiles of the closest level, see [`Self::zoom_scale`].
+#[derive(PartialEq, Copy, Clone, Default, Debug)]
+pub struct MapPosition {
+    /// Tile at the center of the view.
+    pub tile: TileIndex,
+    /// Pixel offset of the view's center within [`Self::tile`].
+    pub offset: Point,
+    /// Fractional zoom relative to the tile's zoom level, between -1 and 1.
+    pub zoom: f64,
+}
+
+impl MapPosition {
+    /// Create a view centered on a geographic point.
+    pub fn new(point: GeoPoint, zoom: u8) -> Self {
+        let (tile, offset) = point.tile(zoom);
+        Self { tile, offset, zoom: 0. }
+    }
+
+    /// Get the geographic point at the center of the view.
+    pub fn center(&self) -> GeoPoint {
+        GeoPoint::from_tile(self.tile, self.offset)
+    }
+
+    /// Get the current zoom level, including its fractional part.
+    pub fn zoom_level(&self) -> f64 {
+        self.tile.z as f64 + self.zoom
+    }
+
+    /// Get the current sub-tile zoom level.
+    ///
+    /// A value of 1.5 means tiles should be rendered at 150% of their size.
+    /// This value will always be between 0.5 and 2.0, never reaching either
+    /// bound.
+    pub fn zoom_scale(&self) -> f64 {
+        (2f64.powf(self.zoom) * 100.).round() / 100.
+    }
+
+    /// Get an iterator over all tiles visible in a view of a physical size.
+    pub fn tiles(&self, size: Size) -> TileIter {
+        TileIter::new(size, self.tile, self.offset, self.zoom_scale())
+    }
+
+    /// Convert a physical point relative to the view's center to a tile and
+    /// offset.
+    ///
+    /// Points outside of the map are clamped to its border.
+    pub fn center_point_tile(&self, point: Point<f64>) -> (TileIndex, Point) {
+        let mut tile = self.tile;
+        let mut offset = self.offset;
+
+        // Apply sub-tile scale, since the cursor is in tile coordinates.
+        let scale = self.zoom_scale();
+        let x = (point.x / scale).round() as i32;
+        let y = (point.y / scale).round() as i32;
+
+        let max_tile = (1 << tile.z) - 1;
+        let offset_x = offset.x + x;
+        let offset_y = offset.y + y;
+
+        // Calculate tile index.
+        let tile_x = tile.x as i32 + offset_x.div_euclid(TILE_SIZE);
+        let tile_y = tile.y as i32 + offset_y.div_euclid(TILE_SIZE);
+        tile.x = tile_x.clamp(0, max_tile) as u32;
+        tile.y = tile_y.clamp(0, max_tile) as u32;
+
+        // Calculate tile offset.
+        let clamp_offset = |tile: i32, offset: i32| {
+            if tile > max_tile {
+                255
+            } else if tile < 0 {
+                0
+            } else {
+                offset.rem_euclid(TILE_SIZE)
+            }
+        };
+        offset.x = clamp_offset(tile_x, offset_x);
+        offset.y = clamp_offset(tile_y, offset_y);
+
+        (tile, offset)
+    }
+
+    /// Move the view by a physical pixel delta.
+    pub fn move_by(&mut self, delta: Point<f64>) {
+        (self.tile, self.offset) = self.center_point_tile(delta * -1.);
+    }
+
+    /// Zoom the view by a percentage.
+    ///
+    /// A value of `2.5` will increase the resolution of the current map by
+    /// `2.5`, which will increase the tileset zoom level by 1.
+    ///
+    /// The `focus` is a physical point relative to the view's center, which
+    /// is kept in the same place on the screen. The tile zoom level is kept
+    /// within `min_zoom..=max_zoom`.
+    pub fn zoom_by(&mut self, zoom: f64, focus: Point<f64>, min_zoom: u8, max_zoom: u8) {
+        let map_delta = (1. / zoom).log2() - self.zoom;
+        let map_delta_trunc = map_delta.trunc() as i32;
+
+        // Calculate offset required to keep zoom focus stationary.
+        //
+        // Rounding and precision here means the focus point isn't kept precisely in the
+        // same position, which is fine for our usecase.
+        //
+        // The rounding is required to avoid insignificant negative changes being
+        // floored to integer offset changes and causing the map to move around when
+        // moving the zoom points without changing their distance.
+        let mut focus_offset = focus / self.zoom_scale() * (zoom - 1.);
+        focus_offset.x = focus_offset.x.round();
+        focus_offset.y = focus_offset.y.round();
+
+        // Convert position within tile back to fractional tile indices.
+        let tile_x =
+            self.tile.x as f64 + (self.offset.x as f64 + focus_offset.x) / TILE_SIZE as f64;
+        let tile_y =
+            self.tile.y as f64 + (self.offset.y as f64 + focus_offset.y) / TILE_SIZE as f64;
+        let tile_z = self.tile.z as i32;
+
+        // Calculate new fractional tile indices.
+        let (min_zoom, max_zoom) = (min_zoom as i32, max_zoom as i32);
+        let tile_delta = map_delta_trunc.clamp(-(max_zoom - tile_z), tile_z - min_zoom);
+        let new_tile_x = tile_x * 2f64.powi(-tile_delta);
+        let new_tile_y = tile_y * 2f64.powi(-tile_delta);
+
+        // Convert fractional, to integer tile indices and offset.
+        let x_offset = (new_tile_x.fract() * TILE_SIZE as f64).floor() as i32;
+        let y_offset = (new_tile_y.fract() * TILE_SIZE as f64).floor() as i32;
+        self.tile.x = new_tile_x.trunc() as u32;
+        self.tile.y = new_tile_y.trunc() as u32;
+        self.tile.z = (tile_z - tile_delta) as u8;
+        self.offset = Point::new(x_offset, y_offset);
+
+        // Clamp scale fraction to 199/49% when clamped.
+        self.zoom = if map_delta_trunc != tile_delta {
+            0.999f64.copysign(-map_delta_trunc as f64)
+        } else {
+            -map_delta.fract()
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn move_view() {
+        let mut position = MapPosition::new(GeoPoint::new(51.157800, 6.865500), 14);
+        assert_eq!(position.tile, TileIndex::new(8504, 5473, 14));
+        assert_eq!(position.offset, Point::new(116, 144));
+
+        position.move_by(Point::new(120., -20.));
+        assert_eq!(position.tile, TileIndex::new(8503, 5473, 14));
+        assert_eq!(position.offset, Point::new(252, 164));
+
+        // Positions are clamped to the map's border.
+        position.move_by(Point::new(-1e9, 1e9));
+        assert_eq!(position.tile, TileIndex::new(16383, 0, 14));
+        assert_eq!(position.offset, Point::new(255, 0));
+    }
+
+    #[test]
+    fn zoom_view() {
+        let center = GeoPoint::new(51.157800, 6.865500);
+        let mut position = MapPosition::new(center, 14);
+
+        // Zooming by a fraction of a level only scales the tiles.
+        position.zoom_by(1.5, Point::default(), 0, 19);
+        assert_eq!(position.tile.z, 14);
+        assert!((position.zoom_scale() - 1.5).abs() < 0.01);
+
+        // Full zoom levels switch to the next tile zoom level.
+        position.zoom_by(2., Point::default(), 0, 19);
+        assert_eq!(position.tile.z, 15);
+        assert!((position.zoom_level() - (15. + 1.5f64.log2())).abs() < 0.01);
+
+        // Without a focus point, the center only moves by rounding errors.
+        assert!(position.center().distance(center) < 10);
+
+        // Zoom is clamped to the tile zoom limits.
+        position.zoom_by(16., Point::default(), 0, 15);
+        assert_eq!(position.tile.z, 15);
+        assert_eq!(position.zoom, 0.999);
+    }
+}
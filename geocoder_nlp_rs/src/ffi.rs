@@ -55,6 +55,12 @@ mod ffi {
         fn get_phone(self: &GeoResult) -> &CxxString;
         fn get_postal_code(self: &GeoResult) -> &CxxString;
         fn get_website(self: &GeoResult) -> &CxxString;
+        fn get_hierarchy(self: &GeoResult) -> &CxxVector<GeoHierarchyLevel>;
+
+        type GeoHierarchyLevel;
+
+        fn get_name(self: &GeoHierarchyLevel) -> &CxxString;
+        fn get_type(self: &GeoHierarchyLevel) -> &CxxString;
     }
 
     #[namespace = "GeoNLP"]
@@ -487,6 +487,42 @@ impl<'a> SearchResult<'a> {
         self.result.get_address().to_string_lossy()
     }
 
+    /// Structured address of the result's entity.
+    ///
+    /// The address components are assigned based on the entity types of the
+    /// result's parents in geocoder-nlp's administrative hierarchy. See
+    /// [`Address`] for details.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use geocoder_nlp::Geocoder;
+    ///
+    /// let mut geocoder = Geocoder::new("/tmp/postal", "/tmp/postal", "/tmp/geocoder").unwrap();
+    ///
+    /// // Get the address at the specified lat/lon in a 10 meter radius.
+    /// let mut results = geocoder.reverse(42.224966, -8.670664, 10.).unwrap();
+    ///
+    /// if let Some(result) = results.next() {
+    ///     let address = result.structured_address();
+    ///     println!("Street: {:?} {:?}", address.street, address.house_number);
+    ///     println!("City: {:?}", address.city);
+    ///     println!("Country: {:?}", address.country);
+    /// }
+    /// ```
+    pub fn structured_address(&self) -> Address {
+        let levels = self
+            .result
+            .get_hierarchy()
+            .iter()
+            .map(|level| AddressLevel {
+                name: level.get_name().to_string_lossy().into(),
+                entity_type: level.get_type().to_string_lossy().into(),
+            })
+            .collect();
+        Address::new(&self.title(), &self.entity_type(), levels, &self.postal_code())
+    }
+
     /// OSM tag of the result's entity.
     pub fn entity_type(&self) -> Cow<'a, str> {
         self.result.get_type().to_string_lossy()
@@ -522,6 +558,7 @@ impl<'a> Debug for SearchResult<'a> {
             .field("title", &self.title())
             .field("postal_code", &self.postal_code())
             .field("address", &self.address())
+            .field("structured_address", &self.structured_address())
             .field("entity_type", &self.entity_type())
             .field("phone", &self.phone())
             .field("website", &self.website())
@@ -529,3 +566,154 @@ impl<'a> Debug for SearchResult<'a> {
             .finish()
     }
 }
+
+/// Structured address of a geocoding result.
+///
+/// Geocoder-nlp describes an entity's location through its parents in the
+/// administrative hierarchy, ordered from the most specific level like the
+/// street to the least specific level, which is usually the country. All of
+/// these levels are available in [`Self::levels`].
+///
+/// The remaining fields are assigned based on the OSM tag of each level:
+///  - The street is the first `highway` level
+///  - The city is the first `place` level for a city, town, village, or hamlet
+///  - The region is the first `place` level for a state, province, or region
+///  - The country is the first `place=country` level
+///
+/// Since entities representing a house number are titled by it, the house
+/// number is taken from the entity's title for buildings and addresses.
+///
+/// Components which cannot be determined are left empty rather than guessed.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct Address {
+    /// House number of the entity.
+    pub house_number: Option<String>,
+    /// Street of the entity.
+    pub street: Option<String>,
+    /// City, town, or village of the entity.
+    pub city: Option<String>,
+    /// State or region of the entity.
+    pub region: Option<String>,
+    /// Country of the entity.
+    pub country: Option<String>,
+    /// Postal code of the entity.
+    pub postal_code: Option<String>,
+    /// All administrative levels, from the most to the least specific one.
+    pub levels: Vec<AddressLevel>,
+}
+
+impl Address {
+    /// Assign address components from an entity's hierarchy.
+    fn new(title: &str, entity_type: &str, levels: Vec<AddressLevel>, postal_code: &str) -> Self {
+        let mut address = Self::default();
+
+        // Use the title as house number for building and address entities.
+        let title = title.trim();
+        let is_building = entity_type.starts_with("building_") || entity_type.starts_with("addr_");
+        if is_building && title.starts_with(|c: char| c.is_ascii_digit()) {
+            address.house_number = Some(title.into());
+        }
+
+        let find_level = |types: &[&str]| {
+            levels
+                .iter()
+                .find(|level| types.iter().any(|ty| level.entity_type.starts_with(ty)))
+                .map(|level| level.name.clone())
+        };
+
+        address.street = find_level(&["highway_"]);
+        address.city = find_level(&["place_city", "place_town", "place_village", "place_hamlet"]);
+        address.region = find_level(&["place_state", "place_province", "place_region"]);
+        address.country = find_level(&["place_country"]);
+
+        let postal_code = postal_code.trim();
+        if !postal_code.is_empty() {
+            address.postal_code = Some(postal_code.into());
+        }
+
+        address.levels = levels;
+
+        address
+    }
+}
+
+/// Single level of an entity's administrative hierarchy.
+#[derive(Default, Clone, PartialEq, Eq, Debug)]
+pub struct AddressLevel {
+    /// Name of the level's entity.
+    pub name: String,
+    /// OSM tag of the level's entity, like `place_city`.
+    pub entity_type: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(&str, &str)]) -> Vec<AddressLevel> {
+        levels
+            .iter()
+            .map(|(name, entity_type)| AddressLevel {
+                name: name.to_string(),
+                entity_type: entity_type.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn poi_address() {
+        let hierarchy = levels(&[
+            ("Main Street", "highway_residential"),
+            ("Berlin", "place_city"),
+            ("Germany", "place_country"),
+        ]);
+        let address = Address::new("Cafe", "amenity_cafe", hierarchy.clone(), "");
+
+        assert_eq!(address, Address {
+            street: Some("Main Street".into()),
+            city: Some("Berlin".into()),
+            country: Some("Germany".into()),
+            levels: hierarchy,
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn house_number_address() {
+        let hierarchy = levels(&[
+            ("Rúa Real", "highway_pedestrian"),
+            ("Casco Vello", "place_suburb"),
+            ("Vigo", "place_city"),
+            ("Galicia", "place_state"),
+            ("España", "place_country"),
+        ]);
+        let address = Address::new(" 12b ", "building_yes", hierarchy.clone(), " 36202 ");
+
+        assert_eq!(address, Address {
+            house_number: Some("12b".into()),
+            street: Some("Rúa Real".into()),
+            city: Some("Vigo".into()),
+            region: Some("Galicia".into()),
+            country: Some("España".into()),
+            postal_code: Some("36202".into()),
+            levels: hierarchy,
+        });
+    }
+
+    #[test]
+    fn numeric_poi_title() {
+        let hierarchy = levels(&[("Berlin", "place_city")]);
+        let address = Address::new("25hours Hotel", "tourism_hotel", hierarchy, "");
+
+        assert_eq!(address.house_number, None);
+        assert_eq!(address.city, Some("Berlin".into()));
+    }
+
+    #[test]
+    fn unknown_levels() {
+        let hierarchy = levels(&[("Somewhere", "boundary_administrative")]);
+        let address = Address::new("Peak", "natural_peak", hierarchy.clone(), "");
+
+        assert_eq!(address, Address { levels: hierarchy, ..Default::default() });
+    }
+}
@@ -0,0 +1,13 @@
+CREATE TABLE favorite (
+    id INTEGER NOT NULL PRIMARY KEY,
+
+    lat REAL NOT NULL,
+    lon REAL NOT NULL,
+
+    title TEXT NOT NULL,
+    address TEXT NOT NULL,
+
+    ctime INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    UNIQUE (lat, lon)
+);
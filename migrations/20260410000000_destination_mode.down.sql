@@ -0,0 +1 @@
+DROP TABLE destination_mode;
@@ -0,0 +1,8 @@
+CREATE TABLE destination_mode (
+    lat INTEGER NOT NULL,
+    lon INTEGER NOT NULL,
+
+    mode INTEGER NOT NULL,
+
+    PRIMARY KEY (lat, lon)
+);
@@ -0,0 +1 @@
+DROP TABLE output_zoom;
@@ -0,0 +1,5 @@
+CREATE TABLE output_zoom (
+    name TEXT NOT NULL PRIMARY KEY,
+
+    zoom INTEGER NOT NULL
+);
@@ -0,0 +1,7 @@
+CREATE TABLE search_history (
+    query TEXT NOT NULL PRIMARY KEY,
+
+    atime INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE INDEX search_history_atime ON search_history (atime);
@@ -0,0 +1,15 @@
+CREATE TABLE route_cache (
+    origin_lat INTEGER NOT NULL,
+    origin_lon INTEGER NOT NULL,
+    target_lat INTEGER NOT NULL,
+    target_lon INTEGER NOT NULL,
+    mode INTEGER NOT NULL,
+
+    route TEXT NOT NULL,
+
+    ctime INTEGER NOT NULL DEFAULT (unixepoch()),
+
+    PRIMARY KEY (origin_lat, origin_lon, target_lat, target_lon, mode)
+);
+
+CREATE INDEX route_cache_ctime ON route_cache (ctime);
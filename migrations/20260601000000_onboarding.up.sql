@@ -0,0 +1,9 @@
+CREATE TABLE onboarding (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+    online INTEGER NOT NULL DEFAULT 1,
+
+    ctime INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+-- Skip onboarding for installations which were already in use.
+INSERT INTO onboarding (id) SELECT 0 WHERE EXISTS (SELECT 1 FROM tile);
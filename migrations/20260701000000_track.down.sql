@@ -0,0 +1,2 @@
+DROP TABLE track_point;
+DROP TABLE track;
@@ -0,0 +1,18 @@
+CREATE TABLE track (
+    id INTEGER NOT NULL PRIMARY KEY,
+
+    distance INTEGER NOT NULL DEFAULT 0,
+
+    ctime INTEGER NOT NULL DEFAULT (unixepoch())
+);
+
+CREATE TABLE track_point (
+    track_id INTEGER NOT NULL REFERENCES track (id) ON DELETE CASCADE,
+
+    lat REAL NOT NULL,
+    lon REAL NOT NULL,
+
+    time INTEGER NOT NULL
+);
+
+CREATE INDEX track_point_track_id ON track_point (track_id, time);
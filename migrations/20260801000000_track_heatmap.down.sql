@@ -0,0 +1,4 @@
+DROP INDEX track_point_cell;
+
+ALTER TABLE track_point DROP COLUMN cell_y;
+ALTER TABLE track_point DROP COLUMN cell_x;
@@ -0,0 +1,4 @@
+ALTER TABLE track_point ADD COLUMN cell_x INTEGER;
+ALTER TABLE track_point ADD COLUMN cell_y INTEGER;
+
+CREATE INDEX track_point_cell ON track_point (cell_x, cell_y);
@@ -0,0 +1,5 @@
+CREATE TABLE camera_mode (
+    id INTEGER PRIMARY KEY CHECK (id = 0),
+
+    mode INTEGER NOT NULL
+);
@@ -0,0 +1,82 @@
+//! Audio playback.
+
+use std::env;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use tracing::{error, warn};
+
+use crate::dbus::mpris::Ducking;
+
+/// Sound theme event played for maneuver alerts.
+const CHIME_SOUND: &str = "message";
+
+/// XDG sound theme used for event sounds.
+const SOUND_THEME: &str = "freedesktop";
+
+/// Audio players and their media role arguments, in order of preference.
+///
+/// The media role allows the sound server to duck other streams on its own.
+const PLAYERS: [(&str, &str); 2] =
+    [("pw-play", "--media-role=Notification"), ("paplay", "--property=media.role=event")];
+
+/// Play the maneuver alert chime in the background.
+pub fn play_chime() {
+    let path = match sound_path(CHIME_SOUND) {
+        Some(path) => path,
+        None => {
+            error!("Missing {CHIME_SOUND:?} sound in {SOUND_THEME:?} sound theme");
+            return;
+        },
+    };
+
+    tokio::spawn(async move {
+        // Lower the volume of other media players while the chime is playing.
+        let ducking = Ducking::start()
+            .await
+            .inspect_err(|err| warn!("Failed to duck media players: {err}"))
+            .ok();
+
+        if let Err(err) = tokio::task::spawn_blocking(move || play_file(&path)).await {
+            error!("Chime playback task failed: {err}");
+        }
+
+        if let Some(ducking) = ducking {
+            ducking.restore().await;
+        }
+    });
+}
+
+/// Play a sound file, blocking until playback is done.
+fn play_file(path: &Path) {
+    for (player, role_arg) in PLAYERS {
+        let mut command = Command::new(player);
+        command.arg(role_arg).arg(path).stdout(Stdio::null()).stderr(Stdio::null());
+
+        match command.status() {
+            Ok(status) if !status.success() => error!("Chime playback failed: {status}"),
+            Ok(_) => (),
+            // Try the next player if this one isn't installed.
+            Err(err) if err.kind() == ErrorKind::NotFound => continue,
+            Err(err) => error!("Failed to run {player}: {err}"),
+        }
+
+        return;
+    }
+
+    error!("Chime playback failed: neither pw-play nor paplay are installed");
+}
+
+/// Find an event sound in the XDG sound theme.
+fn sound_path(name: &str) -> Option<PathBuf> {
+    let data_dirs = env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+
+    dirs::data_dir().into_iter().chain(env::split_paths(&data_dirs)).find_map(|dir| {
+        let path = dir.join("sounds").join(SOUND_THEME).join("stereo").join(format!("{name}.oga"));
+        path.exists().then_some(path)
+    })
+}
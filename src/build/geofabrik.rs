@@ -0,0 +1,111 @@
+//! Geofabrik region boundaries.
+
+use std::collections::HashMap;
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+/// URL for the geofabrik region index, including geometries.
+const GEOFABRIK_INDEX_URL: &str = "https://download.geofabrik.de/index-v1.json";
+
+/// Base URL of all geofabrik downloads.
+const GEOFABRIK_BASE: &str = "https://download.geofabrik.de/";
+
+/// Suffix of geofabrik's PBF download URLs.
+const PBF_SUFFIX: &str = "-latest.osm.pbf";
+
+/// Geographic bounding box of a region.
+#[derive(Serialize, Copy, Clone, Debug)]
+pub struct Bounds {
+    min_lat: f64,
+    min_lon: f64,
+    max_lat: f64,
+    max_lon: f64,
+}
+
+impl Bounds {
+    /// Get the bounding box of a list of polygon rings.
+    fn from_rings<'a>(rings: impl Iterator<Item = &'a Vec<[f64; 2]>>) -> Option<Self> {
+        let mut points = rings.flatten().peekable();
+        let [lon, lat] = **points.peek()?;
+        let mut bounds = Self { min_lat: lat, min_lon: lon, max_lat: lat, max_lon: lon };
+
+        for [lon, lat] in points {
+            bounds.min_lat = bounds.min_lat.min(*lat);
+            bounds.min_lon = bounds.min_lon.min(*lon);
+            bounds.max_lat = bounds.max_lat.max(*lat);
+            bounds.max_lon = bounds.max_lon.max(*lon);
+        }
+
+        Some(bounds)
+    }
+}
+
+/// Load region bounding boxes from geofabrik.org.
+///
+/// The returned map is keyed by the region's path, which matches the region
+/// IDs used by modrana.
+pub fn region_bounds() -> HashMap<String, Bounds> {
+    // We use `curl` here instead of reqwest since the latter causes some
+    // cross-compilation build issues.
+    let output = Command::new("curl").arg(GEOFABRIK_INDEX_URL).output().unwrap();
+    if !output.status.success() {
+        panic!("geofabrik.org region index download failed");
+    }
+
+    // Parse stdout as json response.
+    let response = str::from_utf8(&output.stdout).unwrap();
+    let index: Index = serde_json::from_str(response).expect("failed to parse geofabrik index");
+
+    index
+        .features
+        .into_iter()
+        .filter_map(|feature| {
+            // Extract region path from its PBF download URL.
+            let pbf_url = feature.properties.urls.pbf;
+            let path = pbf_url.strip_prefix(GEOFABRIK_BASE)?.strip_suffix(PBF_SUFFIX)?;
+
+            let bounds = match feature.geometry? {
+                Geometry::Polygon { coordinates } => Bounds::from_rings(coordinates.iter()),
+                Geometry::MultiPolygon { coordinates } => {
+                    Bounds::from_rings(coordinates.iter().flatten())
+                },
+            }?;
+
+            Some((path.to_string(), bounds))
+        })
+        .collect()
+}
+
+/// Format of the geofabrik.org index-v1.json.
+#[derive(Deserialize, Debug)]
+struct Index {
+    features: Vec<Feature>,
+}
+
+/// Geofabrik region feature.
+#[derive(Deserialize, Debug)]
+struct Feature {
+    properties: Properties,
+    geometry: Option<Geometry>,
+}
+
+/// Geofabrik region metadata.
+#[derive(Deserialize, Debug)]
+struct Properties {
+    urls: Urls,
+}
+
+/// Geofabrik region download URLs.
+#[derive(Deserialize, Debug)]
+struct Urls {
+    pbf: String,
+}
+
+/// Geofabrik region boundary.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type")]
+enum Geometry {
+    Polygon { coordinates: Vec<Vec<[f64; 2]>> },
+    MultiPolygon { coordinates: Vec<Vec<Vec<[f64; 2]>>> },
+}
@@ -7,9 +7,11 @@ use std::process::Command;
 use gl_generator::{Api, Fallbacks, GlobalGenerator, Profile, Registry};
 use serde::Serialize;
 
+use crate::geofabrik::region_bounds;
 use crate::modrana::Countries;
 use crate::region::Region;
 
+mod geofabrik;
 mod modrana;
 mod region;
 
@@ -38,14 +40,18 @@ struct Regions {
     postal_global_base: String,
     valhalla_base: String,
     geocoder_base: String,
+
+    checksums: HashMap<String, String>,
 }
 
 impl Regions {
     fn new() -> Self {
         let mut modrana = Countries::new();
+        let region_bounds = region_bounds();
         let tile_sizes = tile_sizes();
+        let checksums = tile_checksums();
 
-        let world_region = Region::world(&mut modrana, &tile_sizes);
+        let world_region = Region::world(&mut modrana, &tile_sizes, &region_bounds);
 
         let postal_country_base = format!("{}/{}", modrana.url.base, modrana.url.postal_country);
         let postal_global_base = format!(
@@ -56,7 +62,14 @@ impl Regions {
             format!("{}/{}/valhalla/packages", modrana.url.base, modrana.url.valhalla);
         let geocoder_base = format!("{}/{}", modrana.url.base, modrana.url.geocoder_nlp);
 
-        Self { postal_country_base, postal_global_base, valhalla_base, geocoder_base, world_region }
+        Self {
+            postal_country_base,
+            postal_global_base,
+            valhalla_base,
+            geocoder_base,
+            world_region,
+            checksums,
+        }
     }
 }
 
@@ -74,3 +87,26 @@ pub fn tile_sizes() -> HashMap<String, u64> {
     let response = str::from_utf8(&output.stdout).unwrap();
     serde_json::from_str(response).expect("failed to parse tile index")
 }
+
+/// Load tile archive checksums from catacomb.org, indexed by URL.
+///
+/// Since checksums are optional, a failed download only emits a warning.
+pub fn tile_checksums() -> HashMap<String, String> {
+    let url = format!("{TILE_URL_BASE}/SHA256SUMS");
+    let output = Command::new("curl").arg("--fail").arg(&url).output().unwrap();
+    if !output.status.success() {
+        println!("cargo:warning=catacombing.org tile checksum download failed");
+        return HashMap::new();
+    }
+
+    // Parse `sha256sum` output, with paths relative to the tile server.
+    let response = str::from_utf8(&output.stdout).unwrap();
+    response
+        .lines()
+        .filter_map(|line| line.split_once(char::is_whitespace))
+        .map(|(checksum, path)| {
+            let path = path.trim_start().trim_start_matches(['*', '.', '/']);
+            (format!("{TILE_URL_BASE}/{path}"), checksum.into())
+        })
+        .collect()
+}
@@ -7,6 +7,7 @@ use indexmap::IndexMap;
 use serde::Serialize;
 
 use crate::TILE_URL_BASE;
+use crate::geofabrik::Bounds;
 use crate::modrana::Countries;
 
 /// Fixed mapping between region paths and unique IDs.
@@ -23,6 +24,7 @@ pub struct Region {
     geocoder_path: Option<String>,
     postal_path: Option<String>,
     tiles_url: Option<String>,
+    bounds: Option<Bounds>,
 
     // Complete size of this region and all of its children.
     storage_size: u64,
@@ -37,7 +39,11 @@ pub struct Region {
 
 impl Region {
     /// Get the root region of the world.
-    pub fn world(modrana: &mut Countries, tile_sizes: &HashMap<String, u64>) -> Self {
+    pub fn world(
+        modrana: &mut Countries,
+        tile_sizes: &HashMap<String, u64>,
+        region_bounds: &HashMap<String, Bounds>,
+    ) -> Self {
         let postal_global_size =
             str::parse::<u64>(&modrana.postal_global.postal_global.size).unwrap();
 
@@ -56,6 +62,7 @@ impl Region {
             tiles_size: Default::default(),
             tiles_url: Default::default(),
             regions: Default::default(),
+            bounds: Default::default(),
         };
 
         // Convert flat modrana data map to the region tree.
@@ -84,6 +91,7 @@ impl Region {
                     Region {
                         id,
                         name: name.into(),
+                        bounds: region_bounds.get(absolute_id).copied(),
                         valhalla_packages: Default::default(),
                         geocoder_path: Default::default(),
                         geocoder_size: Default::default(),
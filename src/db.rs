@@ -1,21 +1,57 @@
 //! SQLite database handling.
 
-use std::fs;
+use std::collections::HashMap;
 use std::path::PathBuf;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime};
+use std::{fs, mem};
 
-use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteJournalMode};
-use sqlx::{Pool, QueryBuilder};
+use sqlx::migrate::MigrateError;
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteJournalMode, SqlitePoolOptions, SqliteRow};
+use sqlx::{FromRow, Pool, QueryBuilder, Row};
 use tokio::sync::SetOnce;
-use tracing::error;
+use tracing::{error, info, warn};
 
 use crate::Error;
+use crate::config::CameraMode;
+use crate::geometry::GeoPoint;
+use crate::router::{CachedRoute, Mode as RouteMode, Route, RoutingQuery};
 use crate::tiles::{OFFLINE_TILESERVER, TileIndex};
 
+/// Precision of destination coordinates, as fraction of a degree.
+///
+/// This results in a resolution of roughly 100 meters, which is enough to
+/// consider two routes as going to the same destination.
+const DESTINATION_PRECISION: f64 = 1_000.;
+
+/// Precision of cached route endpoints, as fraction of a degree.
+///
+/// This results in a resolution of roughly 10 meters, which keeps the offset
+/// of cached routes well below the GPS rerouting distance.
+const ROUTE_PRECISION: f64 = 10_000.;
+
+/// Zoom level of the tile grid used to aggregate visited track locations.
+///
+/// Cells at this zoom level are roughly 150 meters wide at the equator.
+pub const HEATMAP_ZOOM: u8 = 18;
+
+/// Maximum number of stored search queries.
+const MAX_SEARCH_HISTORY: i64 = 100;
+
+/// SQLite result code for a database file locked by another connection.
+const SQLITE_BUSY: i32 = 5;
+
+/// SQLite result code for a table locked by another connection.
+const SQLITE_LOCKED: i32 = 6;
+
 /// Reference counted database pool.
 #[derive(Clone)]
 pub struct Db {
-    pool: Arc<SetOnce<Pool<Sqlite>>>,
+    pool: Arc<SetOnce<RwLock<Pool<Sqlite>>>>,
+    options: SqliteConnectOptions,
+    read_only: Arc<AtomicBool>,
+    in_memory: Arc<AtomicBool>,
 }
 
 impl Db {
@@ -34,25 +70,123 @@ impl Db {
         }
 
         let options = SqliteConnectOptions::new()
-            .filename(db_path)
+            .filename(&db_path)
             .journal_mode(SqliteJournalMode::Wal)
             .create_if_missing(true);
+        let read_only_options = SqliteConnectOptions::new().filename(db_path).read_only(true);
 
         // Initialize DB connection in the background.
         let pool = Arc::new(SetOnce::new());
+        let read_only = Arc::new(AtomicBool::new(false));
+        let in_memory = Arc::new(AtomicBool::new(false));
+        let future_options = options.clone();
         let future_pool = pool.clone();
+        let future_read_only = read_only.clone();
+        let future_in_memory = in_memory.clone();
         tokio::spawn(async move {
-            if let Err(err) = Self::init_pool(options, future_pool).await {
-                error!("Failed to initialize SQLite pool: {err}");
-            }
+            let result = match Self::connect(future_options).await {
+                // Fall back to reading existing data if another process is writing to the DB.
+                Err(err) if is_locked(&err) => {
+                    warn!("Database is locked, falling back to read-only mode: {err}");
+                    future_read_only.store(true, Ordering::Relaxed);
+
+                    Pool::connect_with(read_only_options).await.map_err(Error::from)
+                },
+                result => result,
+            };
+
+            // Keep the app usable for this session if the database can't be opened.
+            let pool = match result {
+                Ok(pool) => pool,
+                Err(err) => {
+                    error!("Failed to open database, falling back to in-memory storage: {err}");
+                    future_read_only.store(false, Ordering::Relaxed);
+                    future_in_memory.store(true, Ordering::Relaxed);
+
+                    match Self::connect_in_memory().await {
+                        Ok(pool) => pool,
+                        Err(err) => {
+                            error!("Failed to initialize in-memory SQLite pool: {err}");
+                            return;
+                        },
+                    }
+                },
+            };
+
+            let _ = future_pool.set(RwLock::new(pool));
         });
 
-        Ok(Self { pool })
+        Ok(Self { pool, options, read_only, in_memory })
+    }
+
+    /// Check if the database was opened without write access.
+    ///
+    /// This happens when another process is holding a lock on the database.
+    pub fn read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Create a temporary database, which is discarded on drop.
+    #[cfg(test)]
+    pub async fn new_in_memory() -> Result<Self, Error> {
+        let pool = Self::connect_in_memory().await?;
+
+        Ok(Self {
+            pool: Arc::new(SetOnce::new_with(Some(RwLock::new(pool)))),
+            options: SqliteConnectOptions::new().in_memory(true),
+            in_memory: Arc::new(AtomicBool::new(true)),
+            read_only: Default::default(),
+        })
+    }
+
+    /// Check if the database failed to open and data is only kept in memory.
+    pub fn in_memory(&self) -> bool {
+        self.in_memory.load(Ordering::Relaxed)
+    }
+
+    /// Retry opening the database after falling back to in-memory storage.
+    ///
+    /// Data stored in memory since the fallback is discarded.
+    pub async fn retry(&self) -> Result<(), Error> {
+        let Some(lock) = self.pool.get() else { return Ok(()) };
+        if !self.in_memory() {
+            return Ok(());
+        }
+
+        let pool = Self::connect(self.options.clone()).await?;
+        let old_pool = mem::replace(&mut *lock.write().unwrap(), pool);
+        self.in_memory.store(false, Ordering::Relaxed);
+        info!("Reopened database after in-memory fallback");
+
+        old_pool.close().await;
+
+        Ok(())
     }
 
     /// Get access to the underlying pool.
-    pub async fn pool(&self) -> &Pool<Sqlite> {
-        self.pool.wait().await
+    pub async fn pool(&self) -> Pool<Sqlite> {
+        self.pool.wait().await.read().unwrap().clone()
+    }
+
+    /// Get access to the underlying pool for modifying the database.
+    ///
+    /// All writes must go through this, to reject them while the database is
+    /// [read-only](Self::read_only).
+    pub async fn write_pool(&self) -> Result<Pool<Sqlite>, Error> {
+        let pool = self.pool().await;
+        if self.read_only() {
+            return Err(Error::DatabaseReadOnly);
+        }
+        Ok(pool)
+    }
+
+    /// Get the number of database connections currently executing queries.
+    pub fn active_queries(&self) -> usize {
+        let pool = match self.pool.get() {
+            Some(pool) => pool.read().unwrap(),
+            None => return 0,
+        };
+        pool.size() as usize - pool.num_idle()
     }
 
     /// Get the storage path for the sqlite DB.
@@ -79,7 +213,7 @@ impl Db {
         });
         query.push(" ON CONFLICT DO NOTHING ");
 
-        query.build().execute(self.pool().await).await?;
+        query.build().execute(&self.write_pool().await?).await?;
 
         Ok(())
     }
@@ -103,14 +237,14 @@ impl Db {
              unixepoch() ",
         );
 
-        query.build().execute(self.pool().await).await?;
+        query.build().execute(&self.write_pool().await?).await?;
 
         Ok(())
     }
 
     /// Delete all offline tiles for a region
     pub async fn delete_offline_tiles(&self, region_id: u32) -> Result<(), Error> {
-        let pool = self.pool().await;
+        let pool = &self.write_pool().await?;
 
         // Delete the tiles from the dedicated offline tiles table.
         sqlx::query("DELETE FROM offline_tile WHERE region_id = $1")
@@ -132,9 +266,420 @@ impl Db {
         Ok(())
     }
 
+    /// Get storage statistics for all cached tiles.
+    pub async fn tile_cache_stats(&self) -> Result<TileCacheStats, Error> {
+        let pool = &self.pool().await;
+
+        #[rustfmt::skip]
+        let sources = sqlx::query_as(
+            "SELECT tileserver, COUNT(*) AS tiles, SUM(LENGTH(data)) AS size, \
+                    MIN(atime) AS oldest_atime, MAX(atime) AS newest_atime \
+                FROM tile GROUP BY tileserver ORDER BY size DESC",
+        )
+        .fetch_all(pool)
+        .await?;
+
+        let zoom_counts = sqlx::query_as("SELECT z, COUNT(*) FROM tile GROUP BY z ORDER BY z")
+            .fetch_all(pool)
+            .await?;
+
+        Ok(TileCacheStats { sources, zoom_counts })
+    }
+
+    /// Delete all cached tiles of a tileserver.
+    ///
+    /// Offline tiles are only removed with their region.
+    pub async fn delete_tiles(&self, tileserver: &str) -> Result<(), Error> {
+        sqlx::query("DELETE FROM tile WHERE tileserver = $1 AND tileserver != $2")
+            .bind(tileserver)
+            .bind(OFFLINE_TILESERVER)
+            .execute(&self.write_pool().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get all favorite locations.
+    pub async fn favorites(&self) -> Result<Vec<Favorite>, Error> {
+        let query = "SELECT lat, lon, title, address FROM favorite ORDER BY ctime DESC";
+        Ok(sqlx::query_as(query).fetch_all(&self.pool().await).await?)
+    }
+
+    /// Add a new favorite location.
+    pub async fn insert_favorite(&self, favorite: &Favorite) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO favorite (lat, lon, title, address) VALUES ($1, $2, $3, $4) ON CONFLICT \
+             DO UPDATE SET title = excluded.title, address = excluded.address",
+        )
+        .bind(favorite.point.lat)
+        .bind(favorite.point.lon)
+        .bind(&favorite.title)
+        .bind(&favorite.address)
+        .execute(&self.write_pool().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a favorite location.
+    pub async fn delete_favorite(&self, point: GeoPoint) -> Result<(), Error> {
+        sqlx::query("DELETE FROM favorite WHERE lat = $1 AND lon = $2")
+            .bind(point.lat)
+            .bind(point.lon)
+            .execute(&self.write_pool().await?)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get the last used travel mode for all destinations.
+    pub async fn destination_modes(&self) -> Result<HashMap<DestinationKey, RouteMode>, Error> {
+        let rows = sqlx::query("SELECT lat, lon, mode FROM destination_mode")
+            .fetch_all(&self.pool().await)
+            .await?;
+
+        let mut modes = HashMap::new();
+        for row in rows {
+            let key = DestinationKey { lat: row.try_get("lat")?, lon: row.try_get("lon")? };
+            // Ignore travel modes which are no longer supported.
+            if let Ok(mode) = RouteMode::try_from(row.try_get::<i64, _>("mode")?) {
+                modes.insert(key, mode);
+            }
+        }
+
+        Ok(modes)
+    }
+
+    /// Update the last used travel mode for a destination.
+    pub async fn set_destination_mode(
+        &self,
+        key: DestinationKey,
+        mode: RouteMode,
+    ) -> Result<(), Error> {
+        sqlx::query(
+            "INSERT INTO destination_mode (lat, lon, mode) VALUES ($1, $2, $3) ON CONFLICT DO \
+             UPDATE SET mode = excluded.mode",
+        )
+        .bind(key.lat)
+        .bind(key.lon)
+        .bind(mode as i64)
+        .execute(&self.write_pool().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the preferred map zoom level for all outputs.
+    pub async fn output_zooms(&self) -> Result<HashMap<String, u8>, Error> {
+        let query = "SELECT name, zoom FROM output_zoom";
+        let rows = sqlx::query(query).fetch_all(&self.pool().await).await?;
+
+        let mut zooms = HashMap::new();
+        for row in rows {
+            zooms.insert(row.try_get("name")?, row.try_get("zoom")?);
+        }
+
+        Ok(zooms)
+    }
+
+    /// Update the preferred map zoom level for an output.
+    pub async fn set_output_zoom(&self, name: &str, zoom: u8) -> Result<(), Error> {
+        #[rustfmt::skip]
+        sqlx::query(
+            "INSERT INTO output_zoom (name, zoom) VALUES ($1, $2)
+                ON CONFLICT DO UPDATE SET zoom = excluded.zoom"
+        )
+        .bind(name)
+        .bind(zoom)
+        .execute(&self.write_pool().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the last selected navigation camera mode.
+    pub async fn camera_mode(&self) -> Result<Option<CameraMode>, Error> {
+        let query = "SELECT mode FROM camera_mode";
+        let mode: Option<i64> =
+            sqlx::query_scalar(query).fetch_optional(&self.pool().await).await?;
+        Ok(mode.and_then(|mode| CameraMode::try_from(mode).ok()))
+    }
+
+    /// Update the last selected navigation camera mode.
+    pub async fn set_camera_mode(&self, mode: CameraMode) -> Result<(), Error> {
+        #[rustfmt::skip]
+        sqlx::query(
+            "INSERT INTO camera_mode (id, mode) VALUES (0, $1)
+                ON CONFLICT DO UPDATE SET mode = excluded.mode"
+        )
+        .bind(mode as i64)
+        .execute(&self.write_pool().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the online services preference chosen during onboarding.
+    ///
+    /// Returns `None` if the first-run onboarding was not completed yet.
+    pub async fn onboarding(&self) -> Result<Option<bool>, Error> {
+        let query = "SELECT online FROM onboarding";
+        Ok(sqlx::query_scalar(query).fetch_optional(&self.pool().await).await?)
+    }
+
+    /// Mark the first-run onboarding as completed.
+    pub async fn set_onboarding_done(&self, online: bool) -> Result<(), Error> {
+        let pool = &self.write_pool().await?;
+
+        #[rustfmt::skip]
+        sqlx::query(
+            "INSERT INTO onboarding (id, online) VALUES (0, $1)
+                ON CONFLICT DO UPDATE SET online = excluded.online"
+        )
+        .bind(online)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get the most recently submitted search queries.
+    pub async fn search_history(&self) -> Result<Vec<String>, Error> {
+        let query = "SELECT query FROM search_history ORDER BY atime DESC LIMIT $1";
+        Ok(sqlx::query_scalar(query).bind(MAX_SEARCH_HISTORY).fetch_all(&self.pool().await).await?)
+    }
+
+    /// Add a search query to the search history.
+    ///
+    /// This also removes the oldest entries once the history is full.
+    pub async fn insert_search_history(&self, query: &str) -> Result<(), Error> {
+        let pool = &self.write_pool().await?;
+
+        #[rustfmt::skip]
+        sqlx::query(
+            "INSERT INTO search_history (query) VALUES ($1)
+                ON CONFLICT DO UPDATE SET atime = excluded.atime"
+        )
+        .bind(query)
+        .execute(pool)
+        .await?;
+
+        #[rustfmt::skip]
+        sqlx::query(
+            "DELETE FROM search_history WHERE query NOT IN (
+                SELECT query FROM search_history ORDER BY atime DESC LIMIT $1
+            )"
+        )
+        .bind(MAX_SEARCH_HISTORY)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get all cached routes created within the `ttl`.
+    pub async fn cached_routes(
+        &self,
+        ttl: Duration,
+    ) -> Result<HashMap<RouteKey, CachedRoute>, Error> {
+        #[rustfmt::skip]
+        let rows = sqlx::query(
+            "SELECT origin_lat, origin_lon, target_lat, target_lon, mode, route, ctime
+                FROM route_cache WHERE ctime >= unixepoch() - $1"
+        )
+        .bind(ttl.as_secs() as i64)
+        .fetch_all(&self.pool().await)
+        .await?;
+
+        let mut routes = HashMap::new();
+        for row in rows {
+            // Ignore travel modes which are no longer supported.
+            let mode = match RouteMode::try_from(row.try_get::<i64, _>("mode")?) {
+                Ok(mode) => mode,
+                Err(_) => continue,
+            };
+
+            // Ignore routes stored in an outdated format.
+            let route: Route = match serde_json::from_str(row.try_get("route")?) {
+                Ok(route) => route,
+                Err(err) => {
+                    warn!("Ignoring invalid cached route: {err}");
+                    continue;
+                },
+            };
+
+            let key = RouteKey {
+                origin_lat: row.try_get("origin_lat")?,
+                origin_lon: row.try_get("origin_lon")?,
+                target_lat: row.try_get("target_lat")?,
+                target_lon: row.try_get("target_lon")?,
+                mode,
+            };
+            let ctime = SystemTime::UNIX_EPOCH
+                + Duration::from_secs(row.try_get::<i64, _>("ctime")? as u64);
+
+            routes.insert(key, CachedRoute { route: Arc::new(route), ctime });
+        }
+
+        Ok(routes)
+    }
+
+    /// Add a route to the route cache.
+    pub async fn insert_cached_route(&self, key: RouteKey, route: &Route) -> Result<(), Error> {
+        let route = serde_json::to_string(route)?;
+
+        #[rustfmt::skip]
+        sqlx::query(
+            "INSERT INTO route_cache (origin_lat, origin_lon, target_lat, target_lon, mode, route)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT DO UPDATE SET route = excluded.route, ctime = excluded.ctime"
+        )
+        .bind(key.origin_lat)
+        .bind(key.origin_lon)
+        .bind(key.target_lat)
+        .bind(key.target_lon)
+        .bind(key.mode as i64)
+        .bind(route)
+        .execute(&self.write_pool().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove expired routes and the oldest routes exceeding `max_routes`.
+    pub async fn prune_route_cache(&self, ttl: Duration, max_routes: usize) -> Result<(), Error> {
+        #[rustfmt::skip]
+        sqlx::query(
+            "DELETE FROM route_cache WHERE ctime < unixepoch() - $1 OR rowid NOT IN (
+                SELECT rowid FROM route_cache ORDER BY ctime DESC LIMIT $2
+            )"
+        )
+        .bind(ttl.as_secs() as i64)
+        .bind(max_routes as i64)
+        .execute(&self.write_pool().await?)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get all recorded GPS tracks, most recent first.
+    pub async fn tracks(&self) -> Result<Vec<Track>, Error> {
+        #[rustfmt::skip]
+        let query =
+            "SELECT track.id, track.distance, track.ctime, COUNT(track_point.track_id) AS points,
+                    COALESCE(MAX(track_point.time) - MIN(track_point.time), 0) AS duration
+                FROM track LEFT JOIN track_point ON track_point.track_id = track.id
+                GROUP BY track.id ORDER BY track.id DESC";
+        Ok(sqlx::query_as(query).fetch_all(&self.pool().await).await?)
+    }
+
+    /// Create a new GPS track.
+    ///
+    /// Returns the ID of the new track.
+    pub async fn insert_track(&self) -> Result<i64, Error> {
+        let query = "INSERT INTO track DEFAULT VALUES";
+        let result = sqlx::query(query).execute(&self.write_pool().await?).await?;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Append a point to a GPS track.
+    ///
+    /// The `distance` to the previous point in meters is added to the track's
+    /// total distance.
+    pub async fn insert_track_point(
+        &self,
+        track_id: i64,
+        point: TrackPoint,
+        distance: u32,
+    ) -> Result<(), Error> {
+        let pool = &self.write_pool().await?;
+
+        let (cell, _) = point.point.tile(HEATMAP_ZOOM);
+        sqlx::query(
+            "INSERT INTO track_point (track_id, lat, lon, time, cell_x, cell_y)
+                VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(track_id)
+        .bind(point.point.lat)
+        .bind(point.point.lon)
+        .bind(point.time)
+        .bind(cell.x)
+        .bind(cell.y)
+        .execute(pool)
+        .await?;
+
+        sqlx::query("UPDATE track SET distance = distance + $1 WHERE id = $2")
+            .bind(distance)
+            .bind(track_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Get all points of a GPS track, in the order they were recorded.
+    pub async fn track_points(&self, track_id: i64) -> Result<Vec<TrackPoint>, Error> {
+        let query = "SELECT lat, lon, time FROM track_point WHERE track_id = $1 ORDER BY time";
+        Ok(sqlx::query_as(query).bind(track_id).fetch_all(&self.pool().await).await?)
+    }
+
+    /// Get the number of recorded track points within each visited area.
+    ///
+    /// Areas are the tiles at `zoom`, which is limited to [`HEATMAP_ZOOM`].
+    pub async fn track_heatmap(&self, zoom: u8) -> Result<Vec<HeatmapCell>, Error> {
+        let pool = &self.pool().await;
+
+        // Assign cells to points recorded before they were stored with each point.
+        let query = "SELECT rowid, lat, lon FROM track_point WHERE cell_x IS NULL";
+        let points: Vec<(i64, f64, f64)> = sqlx::query_as(query).fetch_all(pool).await?;
+        if !points.is_empty() && !self.read_only() {
+            let mut transaction = self.write_pool().await?.begin().await?;
+            for (rowid, lat, lon) in points {
+                let (cell, _) = GeoPoint::new(lat, lon).tile(HEATMAP_ZOOM);
+                sqlx::query("UPDATE track_point SET cell_x = $1, cell_y = $2 WHERE rowid = $3")
+                    .bind(cell.x)
+                    .bind(cell.y)
+                    .bind(rowid)
+                    .execute(&mut *transaction)
+                    .await?;
+            }
+            transaction.commit().await?;
+        }
+
+        // Merge cells into the tiles of the requested zoom level.
+        let shift = HEATMAP_ZOOM - zoom.min(HEATMAP_ZOOM);
+        #[rustfmt::skip]
+        let query =
+            "SELECT cell_x >> $1 AS x, cell_y >> $1 AS y, COUNT(*) AS count
+                FROM track_point WHERE cell_x IS NOT NULL
+                GROUP BY cell_x >> $1, cell_y >> $1";
+        let cells: Vec<HeatmapCell> = sqlx::query_as(query).bind(shift).fetch_all(pool).await?;
+
+        Ok(cells)
+    }
+
+    /// Delete a GPS track with all its points.
+    pub async fn delete_track(&self, track_id: i64) -> Result<(), Error> {
+        let pool = &self.write_pool().await?;
+
+        sqlx::query("DELETE FROM track_point WHERE track_id = $1")
+            .bind(track_id)
+            .execute(pool)
+            .await?;
+        sqlx::query("DELETE FROM track WHERE id = $1").bind(track_id).execute(pool).await?;
+
+        Ok(())
+    }
+
     /// Close the SQLite database connection.
     pub async fn close(&self) {
-        let pool = self.pool().await;
+        let pool = &match self.write_pool().await {
+            Ok(pool) => pool,
+            Err(_) => {
+                self.pool().await.close().await;
+                return;
+            },
+        };
 
         // Store query planner optimization details on exit.
         //
@@ -156,18 +701,203 @@ impl Db {
         pool.close().await;
     }
 
-    /// Asynchronously initialize the database pool.
-    async fn init_pool(
-        options: SqliteConnectOptions,
-        setter: Arc<SetOnce<Pool<Sqlite>>>,
-    ) -> Result<(), Error> {
+    /// Open the database and run all pending migrations.
+    async fn connect(options: SqliteConnectOptions) -> Result<Pool<Sqlite>, Error> {
         let pool = Pool::connect_with(options).await?;
 
         // Run database migrations.
         sqlx::migrate!("./migrations").run(&pool).await?;
 
-        let _ = setter.set(pool);
+        Ok(pool)
+    }
 
-        Ok(())
+    /// Create a temporary database, which is discarded on exit.
+    async fn connect_in_memory() -> Result<Pool<Sqlite>, Error> {
+        // Every in-memory connection has its own database, so the pool must keep
+        // using a single connection for its entire lifetime.
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .min_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(SqliteConnectOptions::new().in_memory(true))
+            .await?;
+
+        // Run database migrations.
+        sqlx::migrate!("./migrations").run(&pool).await?;
+
+        Ok(pool)
+    }
+}
+
+/// Check if an error was caused by another connection locking the database.
+fn is_locked(err: &Error) -> bool {
+    let err = match err {
+        Error::Sql(err)
+        | Error::SqlMigrate(MigrateError::Execute(err))
+        | Error::SqlMigrate(MigrateError::ExecuteMigration(err, _)) => err,
+        _ => return false,
+    };
+
+    let code = match err {
+        sqlx::Error::Database(err) => err.code(),
+        _ => None,
+    };
+
+    // Compare only the primary result code, ignoring extended result codes.
+    let code = code.and_then(|code| code.parse::<i32>().ok());
+    code.is_some_and(|code| matches!(code & 0xFF, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
+/// User-defined favorite location.
+#[derive(Clone, Debug)]
+pub struct Favorite {
+    pub point: GeoPoint,
+    pub title: String,
+    pub address: String,
+}
+
+/// Recorded GPS track.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Track {
+    pub id: i64,
+    /// Total distance in meters.
+    pub distance: u32,
+    /// Number of recorded points.
+    pub points: u32,
+    /// Time between the first and last recorded point in seconds.
+    pub duration: i64,
+    /// Unix time of the recording start.
+    pub ctime: i64,
+}
+
+/// Single GPS track location.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TrackPoint {
+    pub point: GeoPoint,
+    /// Unix time of the GPS fix.
+    pub time: i64,
+}
+
+/// Number of recorded track points within a tile.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct HeatmapCell {
+    pub x: u32,
+    pub y: u32,
+    pub count: u32,
+}
+
+/// Storage statistics of the tile cache.
+#[derive(Clone, Default, Debug)]
+pub struct TileCacheStats {
+    /// Statistics for each tileserver, largest first.
+    pub sources: Vec<TileSourceStats>,
+    /// Number of tiles for each zoom level.
+    pub zoom_counts: Vec<(u8, u64)>,
+}
+
+/// Storage statistics of a single tileserver.
+#[derive(Clone, Debug)]
+pub struct TileSourceStats {
+    pub tileserver: String,
+    pub tiles: u64,
+    /// Total tile data size in bytes.
+    pub size: u64,
+    /// Unix time of the least recent tile access.
+    pub oldest_atime: i64,
+    /// Unix time of the most recent tile access.
+    pub newest_atime: i64,
+}
+
+/// Rounded coordinates identifying a routing destination.
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct DestinationKey {
+    lat: i32,
+    lon: i32,
+}
+
+impl From<GeoPoint> for DestinationKey {
+    fn from(point: GeoPoint) -> Self {
+        Self {
+            lat: (point.lat * DESTINATION_PRECISION).round() as i32,
+            lon: (point.lon * DESTINATION_PRECISION).round() as i32,
+        }
+    }
+}
+
+/// Rounded trip endpoints identifying a cached route.
+#[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
+pub struct RouteKey {
+    origin_lat: i32,
+    origin_lon: i32,
+    target_lat: i32,
+    target_lon: i32,
+    mode: RouteMode,
+}
+
+impl RouteKey {
+    /// Get the cache key for a routing query.
+    ///
+    /// Returns `None` for queries with intermediate waypoints, since those
+    /// are not cached.
+    pub fn new(query: &RoutingQuery) -> Option<Self> {
+        if !query.via.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            origin_lat: (query.origin.lat * ROUTE_PRECISION).round() as i32,
+            origin_lon: (query.origin.lon * ROUTE_PRECISION).round() as i32,
+            target_lat: (query.target.lat * ROUTE_PRECISION).round() as i32,
+            target_lon: (query.target.lon * ROUTE_PRECISION).round() as i32,
+            mode: query.mode,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for TileSourceStats {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            tileserver: row.try_get("tileserver")?,
+            tiles: row.try_get("tiles")?,
+            size: row.try_get("size")?,
+            oldest_atime: row.try_get("oldest_atime")?,
+            newest_atime: row.try_get("newest_atime")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Track {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            id: row.try_get("id")?,
+            distance: row.try_get("distance")?,
+            points: row.try_get("points")?,
+            duration: row.try_get("duration")?,
+            ctime: row.try_get("ctime")?,
+        })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for TrackPoint {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let point = GeoPoint::new(row.try_get("lat")?, row.try_get("lon")?);
+        Ok(Self { point, time: row.try_get("time")? })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for HeatmapCell {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        Ok(Self { x: row.try_get("x")?, y: row.try_get("y")?, count: row.try_get("count")? })
+    }
+}
+
+impl FromRow<'_, SqliteRow> for Favorite {
+    fn from_row(row: &SqliteRow) -> Result<Self, sqlx::Error> {
+        let point = GeoPoint::new(row.try_get("lat")?, row.try_get("lon")?);
+        let title = row.try_get("title")?;
+        let address = row.try_get("address")?;
+
+        Ok(Self { point, title, address })
     }
 }
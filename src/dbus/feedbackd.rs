@@ -0,0 +1,55 @@
+//! feedbackd DBus interface.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::Value;
+use zbus::{Connection, proxy};
+
+use crate::Error;
+
+/// Application ID reported to feedbackd.
+const APP_ID: &str = "Charon";
+
+/// Feedback theme event used for maneuver alerts.
+const MANEUVER_EVENT: &str = "message-new-instant";
+
+/// Feedback theme event used for button presses.
+const BUTTON_EVENT: &str = "button-pressed";
+
+/// Trigger haptic feedback for an upcoming maneuver.
+pub async fn maneuver_feedback() -> Result<(), Error> {
+    trigger_feedback(MANEUVER_EVENT).await
+}
+
+/// Trigger haptic feedback for a button press.
+pub async fn button_feedback() -> Result<(), Error> {
+    trigger_feedback(BUTTON_EVENT).await
+}
+
+/// Trigger a feedbackd theme event.
+async fn trigger_feedback(event: &str) -> Result<(), Error> {
+    let connection = Connection::session().await?;
+    let feedback = FeedbackProxy::new(&connection).await?;
+
+    // Use the quiet profile to skip feedbackd's own sounds.
+    let hints = HashMap::from([("profile", Value::from("quiet"))]);
+    feedback.trigger_feedback(APP_ID, event, hints, -1).await?;
+
+    Ok(())
+}
+
+#[proxy(
+    interface = "org.sigxcpu.Feedback",
+    default_service = "org.sigxcpu.Feedback",
+    default_path = "/org/sigxcpu/Feedback"
+)]
+pub trait Feedback {
+    /// TriggerFeedback method
+    fn trigger_feedback(
+        &self,
+        app_id: &str,
+        event: &str,
+        hints: HashMap<&str, Value<'_>>,
+        timeout: i32,
+    ) -> zbus::Result<u32>;
+}
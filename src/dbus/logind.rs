@@ -0,0 +1,62 @@
+//! systemd-logind DBus interface.
+
+use std::future;
+
+use futures_lite::stream::StreamExt;
+use tracing::{error, info};
+use zbus::{Connection, proxy};
+
+use crate::Error;
+
+/// logind system sleep source.
+pub struct SleepSource {
+    stream: PrepareForSleepStream,
+}
+
+impl SleepSource {
+    pub async fn new(connection: &Connection) -> Result<Self, Error> {
+        let manager = ManagerProxy::new(connection).await?;
+        let stream = manager.receive_prepare_for_sleep().await?;
+
+        info!("Started logind sleep listener");
+
+        Ok(Self { stream })
+    }
+
+    /// Wait for the system to go to sleep.
+    pub async fn suspended(&mut self) {
+        self.wait_for(true).await;
+        info!("System is going to sleep");
+    }
+
+    /// Wait for the system to wake up.
+    pub async fn resumed(&mut self) {
+        self.wait_for(false).await;
+        info!("System has woken up");
+    }
+
+    /// Wait for a sleep signal with the expected state.
+    async fn wait_for(&mut self, start: bool) {
+        while let Some(signal) = self.stream.next().await {
+            match signal.args() {
+                Ok(args) if args.start == start => return,
+                Ok(_) => (),
+                Err(err) => error!("Invalid logind PrepareForSleep signal: {err}"),
+            }
+        }
+
+        // Never return if logind went away.
+        future::pending().await
+    }
+}
+
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+pub trait Manager {
+    /// PrepareForSleep signal
+    #[zbus(signal)]
+    fn prepare_for_sleep(&self, start: bool) -> zbus::Result<()>;
+}
@@ -8,14 +8,30 @@ use zbus::Connection;
 
 use crate::Error;
 use crate::dbus::iio_sensor_proxy::IioCompassSource;
+use crate::dbus::logind::SleepSource;
 use crate::dbus::modem_manager::ModemGpsSource;
 use crate::geometry::GeoPoint;
 
+pub mod feedbackd;
 mod iio_sensor_proxy;
+mod logind;
 pub mod modem_manager;
+pub mod mpris;
+pub mod open_uri;
+pub mod screensaver;
+
+/// DBus system update.
+pub enum DbusEvent {
+    /// GPS location and compass heading changed.
+    Location(Option<GeoPoint>, Option<f64>),
+    /// System is about to go to sleep.
+    Suspend,
+    /// System has woken up from sleep.
+    Resume,
+}
 
 /// Listen for DBus updates.
-pub async fn dbus_listen(tx: Sender<(Option<GeoPoint>, Option<f64>)>) -> Result<(), Error> {
+pub async fn dbus_listen(tx: Sender<DbusEvent>) -> Result<(), Error> {
     let connection = Connection::system().await?;
 
     // Create modem GPS listener.
@@ -28,12 +44,18 @@ pub async fn dbus_listen(tx: Sender<(Option<GeoPoint>, Option<f64>)>) -> Result<
         .ok()
         .flatten();
 
+    // Create logind suspend listener.
+    let mut sleep_source = SleepSource::new(&connection)
+        .await
+        .inspect_err(|err| warn!("Failed to initialize logind sleep source: {err}"))
+        .ok();
+
     let mut location = gps_source.location().await;
     let mut heading = None;
 
     loop {
         // Publish current location.
-        if tx.send((location, heading)).is_err() {
+        if tx.send(DbusEvent::Location(location, heading)).is_err() {
             // If the channel was closed, we terminate.
             return Ok(());
         }
@@ -55,9 +77,45 @@ pub async fn dbus_listen(tx: Sender<(Option<GeoPoint>, Option<f64>)>) -> Result<
             }
         };
 
+        let suspend_future = async {
+            match &mut sleep_source {
+                Some(sleep_source) => sleep_source.suspended().await,
+                None => future::pending().await,
+            }
+        };
+
         tokio::select! {
             _ = gps_source.listen(&connection) => location = gps_source.location().await,
             new_heading = compass_future => heading = Some(new_heading),
+            _ = suspend_future => {
+                if tx.send(DbusEvent::Suspend).is_err() {
+                    return Ok(());
+                }
+
+                // Release the compass while asleep.
+                if let Some(compass_source) = &mut compass_source {
+                    compass_source.pause().await;
+                }
+                heading = None;
+
+                if let Some(sleep_source) = &mut sleep_source {
+                    sleep_source.resumed().await;
+                }
+
+                // Recreate the GPS source, since modem streams might not survive suspend.
+                //
+                // If the modem is not ready yet, the old source is kept to avoid losing GPS
+                // for the rest of the session.
+                match ModemGpsSource::new(&connection).await {
+                    Ok(new_source) => gps_source = new_source,
+                    Err(err) => warn!("Failed to recreate GPS source after resume: {err}"),
+                }
+                location = gps_source.location().await;
+
+                if tx.send(DbusEvent::Resume).is_err() {
+                    return Ok(());
+                }
+            },
         }
     }
 }
@@ -0,0 +1,74 @@
+//! MPRIS DBus interface.
+
+use tracing::{error, warn};
+use zbus::fdo::DBusProxy;
+use zbus::proxy::CacheProperties;
+use zbus::{Connection, proxy};
+
+use crate::Error;
+
+/// Bus name prefix of all MPRIS media players.
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
+/// Volume of ducked media players, relative to their original volume.
+const DUCKED_VOLUME: f64 = 0.3;
+
+/// Media players with temporarily lowered volume.
+pub struct Ducking {
+    players: Vec<(PlayerProxy<'static>, f64)>,
+}
+
+impl Ducking {
+    /// Lower the volume of all playing media players.
+    pub async fn start() -> Result<Self, Error> {
+        let connection = Connection::session().await?;
+        let names = DBusProxy::new(&connection).await?.list_names().await?;
+
+        let mut players = Vec::new();
+        for name in names.into_iter().filter(|name| name.starts_with(MPRIS_PREFIX)) {
+            let player = PlayerProxy::builder(&connection)
+                .destination(name.into_inner())?
+                .cache_properties(CacheProperties::No)
+                .build()
+                .await?;
+
+            // Ignore paused players and players without volume control.
+            if player.playback_status().await.ok().as_deref() != Some("Playing") {
+                continue;
+            }
+            let volume = match player.volume().await {
+                Ok(volume) => volume,
+                Err(_) => continue,
+            };
+
+            match player.set_volume(volume * DUCKED_VOLUME).await {
+                Ok(()) => players.push((player, volume)),
+                Err(err) => warn!("Failed to lower media player volume: {err}"),
+            }
+        }
+
+        Ok(Self { players })
+    }
+
+    /// Restore the original volume of all ducked media players.
+    pub async fn restore(self) {
+        for (player, volume) in self.players {
+            if let Err(err) = player.set_volume(volume).await {
+                error!("Failed to restore media player volume: {err}");
+            }
+        }
+    }
+}
+
+#[proxy(interface = "org.mpris.MediaPlayer2.Player", default_path = "/org/mpris/MediaPlayer2")]
+pub trait Player {
+    /// PlaybackStatus property
+    #[zbus(property)]
+    fn playback_status(&self) -> zbus::Result<String>;
+
+    /// Volume property
+    #[zbus(property)]
+    fn volume(&self) -> zbus::Result<f64>;
+    #[zbus(property)]
+    fn set_volume(&self, value: f64) -> zbus::Result<()>;
+}
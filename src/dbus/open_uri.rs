@@ -0,0 +1,34 @@
+//! Desktop portal OpenURI interface.
+
+use std::collections::HashMap;
+
+use zbus::zvariant::{OwnedObjectPath, Value};
+use zbus::{Connection, proxy};
+
+use crate::Error;
+
+/// Open a URI in the user's preferred application.
+pub async fn open_uri(uri: &str) -> Result<(), Error> {
+    let connection = Connection::session().await?;
+    let portal = OpenURIProxy::new(&connection).await?;
+
+    portal.open_uri("", uri, HashMap::new()).await?;
+
+    Ok(())
+}
+
+#[proxy(
+    interface = "org.freedesktop.portal.OpenURI",
+    default_service = "org.freedesktop.portal.Desktop",
+    default_path = "/org/freedesktop/portal/desktop"
+)]
+pub trait OpenURI {
+    /// OpenURI method
+    #[zbus(name = "OpenURI")]
+    fn open_uri(
+        &self,
+        parent_window: &str,
+        uri: &str,
+        options: HashMap<&str, Value<'_>>,
+    ) -> zbus::Result<OwnedObjectPath>;
+}
@@ -0,0 +1,52 @@
+//! ScreenSaver DBus interface.
+
+use tokio::sync::oneshot;
+use tracing::{error, info};
+use zbus::{Connection, proxy};
+
+use crate::Error;
+
+/// Application name reported to the screensaver.
+const APP_NAME: &str = "Charon";
+
+/// Inhibit the screensaver until the returned sender is dropped.
+pub fn inhibit(reason: &'static str) -> oneshot::Sender<()> {
+    let (release_tx, release_rx) = oneshot::channel();
+
+    tokio::spawn(async move {
+        if let Err(err) = inhibit_until(reason, release_rx).await {
+            error!("Failed to inhibit screensaver: {err}");
+        }
+    });
+
+    release_tx
+}
+
+/// Inhibit the screensaver until `release` is signaled or dropped.
+async fn inhibit_until(reason: &str, release: oneshot::Receiver<()>) -> Result<(), Error> {
+    let connection = Connection::session().await?;
+    let screensaver = ScreenSaverProxy::new(&connection).await?;
+
+    let cookie = screensaver.inhibit(APP_NAME, reason).await?;
+    info!("Inhibited screensaver");
+
+    let _ = release.await;
+
+    screensaver.un_inhibit(cookie).await?;
+    info!("Released screensaver inhibition");
+
+    Ok(())
+}
+
+#[proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+pub trait ScreenSaver {
+    /// Inhibit method
+    fn inhibit(&self, application_name: &str, reason_for_inhibit: &str) -> zbus::Result<u32>;
+
+    /// UnInhibit method
+    fn un_inhibit(&self, cookie: u32) -> zbus::Result<()>;
+}
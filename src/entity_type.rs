@@ -3,18 +3,77 @@
 use std::collections::HashMap;
 use std::sync::OnceLock;
 
-/// Get a map with all known entity types and their human-readable names.
-pub fn entity_types() -> &'static HashMap<&'static str, &'static str> {
-    static ENTITY_TYPES: OnceLock<HashMap<&'static str, &'static str>> = OnceLock::new();
+/// Get a map with all known entity types.
+pub fn entity_types() -> &'static HashMap<&'static str, EntityType> {
+    static ENTITY_TYPES: OnceLock<HashMap<&'static str, EntityType>> = OnceLock::new();
     ENTITY_TYPES.get_or_init(|| {
         let mut entity_types = HashMap::new();
         for (tag, name) in TUPLES {
-            entity_types.insert(tag, name);
+            let category = Category::from_tag(tag);
+            entity_types.insert(tag, EntityType { name, category });
         }
         entity_types
     })
 }
 
+/// Human-readable OSM entity type.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct EntityType {
+    pub name: &'static str,
+    pub category: Category,
+}
+
+/// Coarse grouping of entity types.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Category {
+    Transport,
+    Lodging,
+    Health,
+    Nature,
+    Place,
+    Food,
+    Shop,
+    Favorite,
+    Other,
+}
+
+impl Category {
+    /// Get the category of an OSM tag.
+    fn from_tag(tag: &str) -> Self {
+        let (key, value) = tag.split_once('_').unwrap_or((tag, ""));
+        match (key, value) {
+            (
+                "amenity",
+                "bar" | "bbq" | "biergarten" | "cafe" | "fast_food" | "food_court" | "ice_cream"
+                | "pub" | "restaurant",
+            ) => Self::Food,
+            (
+                "amenity",
+                "clinic" | "dentist" | "doctors" | "hospital" | "pharmacy" | "veterinary",
+            )
+            | ("emergency", _) => Self::Health,
+            (
+                "tourism",
+                "alpine_hut" | "apartment" | "bed_and_breakfast" | "cabin" | "camp_site"
+                | "caravan_site" | "chalet" | "guest_house" | "hostel" | "hotel" | "motel"
+                | "wilderness_hut",
+            ) => Self::Lodging,
+            (
+                "amenity",
+                "bicycle_parking" | "bicycle_rental" | "bus_station" | "car_rental" | "car_sharing"
+                | "charging_station" | "ferry_terminal" | "fuel" | "motorcycle_parking" | "parking"
+                | "parking_entrance" | "parking_space" | "taxi",
+            )
+            | ("aerialway" | "aeroway" | "highway" | "railway", _) => Self::Transport,
+            ("leisure", "garden" | "nature_reserve" | "park")
+            | ("mountain" | "natural" | "water" | "waterway", _) => Self::Nature,
+            ("boundary" | "place", _) => Self::Place,
+            ("shop", _) => Self::Shop,
+            _ => Self::Other,
+        }
+    }
+}
+
 /// List of OSM tuples based on:
 /// <https://github.com/openstreetmap/openstreetmap-website/blob/master/config/locales/en.yml>
 static TUPLES: [(&str, &str); 754] = [
@@ -773,3 +832,21 @@ static TUPLES: [(&str, &str); 754] = [
     ("waterway", "Waterway"),
     ("waterway_weir", "Weir"),
 ];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tag_categories() {
+        let entity_types = entity_types();
+        assert_eq!(entity_types["amenity_pharmacy"].category, Category::Health);
+        assert_eq!(entity_types["amenity_pharmacy"].name, "Pharmacy");
+        assert_eq!(entity_types["amenity_restaurant"].category, Category::Food);
+        assert_eq!(entity_types["tourism_hotel"].category, Category::Lodging);
+        assert_eq!(entity_types["railway_station"].category, Category::Transport);
+        assert_eq!(entity_types["place_city"].category, Category::Place);
+        assert_eq!(entity_types["shop"].category, Category::Shop);
+        assert_eq!(entity_types["amenity_bench"].category, Category::Other);
+    }
+}
@@ -1,36 +1,58 @@
 //! Geocoding abstraction layer.
 
 use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::mem;
 use std::sync::{Arc, mpsc};
+use std::time::Duration;
 
 use calloop::channel::Event;
 use calloop::{LoopHandle, channel};
+#[cfg(feature = "offline-search")]
 use geocoder_nlp::SearchReference;
 use reqwest::Client;
+use tokio::sync::watch;
+#[cfg(feature = "offline-search")]
+use tracing::error;
 
-use crate::config::Config;
-use crate::geometry::GeoPoint;
+use crate::config::{Config, ReverseSearch};
+use crate::entity_type::EntityType;
+use crate::geometry::{self, GeoPoint};
 use crate::region::Regions;
+use crate::router::Route;
 use crate::ui::view::search::QueryId;
 use crate::{Error, State};
 
 mod geojson;
+#[cfg(feature = "offline-search")]
 mod nlp;
 mod photon;
+mod transliterate;
 
 /// Multi-provider geocoder.
 pub struct Geocoder {
     photon_query_tx: Option<mpsc::Sender<QueryEvent>>,
-    nlp_query_tx: mpsc::Sender<QueryEvent>,
+    #[cfg(feature = "offline-search")]
+    nlp_query_tx: Option<mpsc::Sender<nlp::Event>>,
 
     result_tx: channel::Sender<(QueryId, QueryResultEvent)>,
+    last_query_tx: watch::Sender<QueryId>,
     photon_url: Arc<String>,
+    photon_timeout: Duration,
+    transliterate_titles: bool,
+    reverse_config: ReverseSearch,
+    rank_weights: RankWeights,
     client: Client,
 
     results: Vec<QueryResult>,
+    last_event: Option<QueryEvent>,
     last_query: QueryId,
+    sorted_query: Option<QueryId>,
     photon_searching: bool,
     nlp_searching: bool,
+    nlp_loading: bool,
+    #[cfg(feature = "offline-search")]
+    nlp_unavailable: bool,
 }
 
 impl Geocoder {
@@ -42,6 +64,10 @@ impl Geocoder {
     ) -> Result<Self, Error> {
         let (result_tx, result_rx) = channel::channel();
 
+        // Notify geocoders about new queries, to cancel outdated requests.
+        let last_query = QueryId::new();
+        let (last_query_tx, _) = watch::channel(last_query);
+
         // Handle new geocoding results.
         event_loop.insert_source(result_rx, |event, _, state| {
             let search_view = state.window.views.search();
@@ -56,25 +82,34 @@ impl Geocoder {
 
             match query_event {
                 // Update search results.
-                QueryResultEvent::Results(results) => {
+                QueryResultEvent::Results(mut results) => {
+                    if geocoder.transliterate_titles {
+                        for result in &mut results {
+                            if let Some(title) = transliterate::to_latin(&result.title) {
+                                result.title = title;
+                            }
+                        }
+                    }
+
                     // Add results and sort them with the best match first.
+                    let weights = geocoder.rank_weights;
                     geocoder.results.extend(results);
-                    geocoder.results.sort_unstable_by(|a, b| match (a.rank, b.rank) {
-                        (QueryResultRank::Photon(a), QueryResultRank::Photon(b)) => a.cmp(&b),
-                        (QueryResultRank::Photon(_), QueryResultRank::Nlp(_)) => Ordering::Less,
-                        (QueryResultRank::Nlp(a), QueryResultRank::Nlp(b)) => a.total_cmp(&b),
-                        (QueryResultRank::Nlp(_), QueryResultRank::Photon(_)) => Ordering::Greater,
-                    });
+                    geocoder.results.sort_by(|a, b| weights.cmp(a, b));
                 },
                 // Mark current Photon search as done.
                 QueryResultEvent::PhotonDone => geocoder.photon_searching = false,
                 // Mark current Geocoder NLP search as done.
+                #[cfg(feature = "offline-search")]
                 QueryResultEvent::NlpDone => geocoder.nlp_searching = false,
             }
 
             // Notify user about geocoding failure.
             if !geocoder.searching() && geocoder.results.is_empty() {
-                search_view.set_error("No Entity Found");
+                if geocoder.offline_available() {
+                    search_view.set_error("No Entity Found");
+                } else {
+                    search_view.set_error("No Entity Found (Offline Search Unavailable)");
+                }
             }
 
             search_view.set_dirty();
@@ -82,47 +117,194 @@ impl Geocoder {
         })?;
 
         // Spawn Geocoder NLP thread.
-        let (nlp_query_tx, nlp_query_rx) = mpsc::channel::<QueryEvent>();
-        nlp::Geocoder::spawn(regions, nlp_query_rx, result_tx.clone())?;
+        #[cfg(feature = "offline-search")]
+        let nlp_query_tx = {
+            // Show dataset loading progress in the search view.
+            let (loading_tx, loading_rx) = channel::channel();
+            event_loop.insert_source(loading_rx, |event, _, state| {
+                if let Event::Msg(loading) = event {
+                    let search_view = state.window.views.search();
+                    search_view.geocoder_mut().nlp_loading = loading;
+                    search_view.set_dirty();
+                    state.window.unstall();
+                }
+            })?;
+
+            // Notify the search view when no offline dataset could be loaded.
+            let (available_tx, available_rx) = channel::channel();
+            event_loop.insert_source(available_rx, |event, _, state| {
+                if let Event::Msg(available) = event {
+                    let search_view = state.window.views.search();
+                    search_view.geocoder_mut().nlp_unavailable = !available;
+                    search_view.set_dirty();
+                    state.window.unstall();
+                }
+            })?;
+
+            // Offer matching offline entities as search suggestions.
+            let (suggestion_tx, suggestion_rx) = channel::channel();
+            event_loop.insert_source(suggestion_rx, |event, _, state| {
+                if let Event::Msg(suggestions) = event {
+                    state.window.views.search().set_offline_suggestions(suggestions);
+                    state.window.unstall();
+                }
+            })?;
+
+            // Disable offline search if its thread cannot be started, instead of failing
+            // to start the application.
+            let (nlp_query_tx, nlp_query_rx) = mpsc::channel::<nlp::Event>();
+            let last_query_rx = last_query_tx.subscribe();
+            match nlp::Geocoder::spawn(
+                regions,
+                nlp_query_rx,
+                result_tx.clone(),
+                suggestion_tx,
+                loading_tx,
+                available_tx,
+                last_query_rx,
+            ) {
+                Ok(()) => Some(nlp_query_tx),
+                Err(err) => {
+                    error!("Offline search unavailable: {err}");
+                    None
+                },
+            }
+        };
+        #[cfg(not(feature = "offline-search"))]
+        {
+            let _ = regions;
+            tracing::info!("Offline search unavailable: compiled without `offline-search` feature");
+        }
 
         // Spawn Photon geocoder.
         let photon_query_tx = (!config.search.photon_url.is_empty()).then(|| {
             let (photon_query_tx, photon_query_rx) = mpsc::channel::<QueryEvent>();
-            photon::Geocoder::spawn(client.clone(), config, photon_query_rx, result_tx.clone());
+            photon::Geocoder::spawn(
+                client.clone(),
+                config,
+                photon_query_rx,
+                result_tx.clone(),
+                last_query_tx.subscribe(),
+            );
             photon_query_tx
         });
 
         Ok(Self {
             photon_query_tx,
+            #[cfg(feature = "offline-search")]
             nlp_query_tx,
             result_tx,
             client,
             photon_url: config.search.photon_url.clone(),
-            last_query: QueryId::new(),
+            photon_timeout: *config.search.photon_timeout,
+            transliterate_titles: config.search.transliterate_titles,
+            reverse_config: config.search.reverse.clone(),
+            rank_weights: RankWeights::new(config),
+            last_query_tx,
+            last_query,
+            last_event: Default::default(),
+            sorted_query: Default::default(),
             photon_searching: Default::default(),
             nlp_searching: Default::default(),
+            nlp_loading: Default::default(),
+            #[cfg(feature = "offline-search")]
+            nlp_unavailable: Default::default(),
             results: Default::default(),
         })
     }
 
     /// Submit a search query.
     pub fn search(&mut self, query: SearchQuery) {
+        // Allow finding Latin-script entities with queries in other scripts.
+        #[cfg(feature = "offline-search")]
+        let query = SearchQuery { transliterated: transliterate::to_latin(&query.text), ..query };
+
         self.query(QueryEvent::Search(query));
     }
 
+    /// Request offline entity titles starting with a partial search query.
+    ///
+    /// Suggestions are reported to the search view once available.
+    pub fn suggest(&mut self, text: &str) {
+        #[cfg(feature = "offline-search")]
+        if let Some(nlp_query_tx) = &self.nlp_query_tx {
+            let _ = nlp_query_tx.send(nlp::Event::Suggest(text.into()));
+        }
+        #[cfg(not(feature = "offline-search"))]
+        let _ = text;
+    }
+
     /// Submit a reverse geocoding query.
-    pub fn reverse(&mut self, query: ReverseQuery) {
+    pub fn reverse(&mut self, mut query: ReverseQuery) {
+        query.radius = self.reverse_config.radius;
+        query.max_results = self.reverse_config.max_results as usize;
+        query.preferred_types = self.reverse_config.preferred_types.0.clone();
+
         self.query(QueryEvent::Reverse(query));
     }
 
+    /// Submit a search for places to stop along a route.
+    pub fn search_corridor(&mut self, query: CorridorQuery) {
+        self.query(QueryEvent::Corridor(query));
+    }
+
+    /// Submit the last query again.
+    ///
+    /// Returns `false` if there is no query that could be repeated.
+    pub fn retry(&mut self) -> bool {
+        let mut event = match self.last_event.clone() {
+            Some(event) => event,
+            None => return false,
+        };
+        event.renew_id();
+        self.query(event);
+        true
+    }
+
+    /// Check if the last query can be submitted again.
+    pub fn can_retry(&self) -> bool {
+        self.last_event.is_some()
+    }
+
     /// Clear the current search.
     pub fn reset(&mut self) {
-        self.last_query = QueryId::new();
+        self.set_last_query(QueryId::new());
+        self.last_event = None;
         self.photon_searching = false;
         self.nlp_searching = false;
         self.results.clear();
     }
 
+    /// Replace the current search with favorite locations.
+    ///
+    /// Returns the query ID used for updating the favorites' order.
+    pub fn show_favorites(&mut self, favorites: Vec<QueryResult>) -> QueryId {
+        self.reset();
+        self.results = favorites;
+        self.last_query
+    }
+
+    /// Sort the results by their travel time in seconds.
+    ///
+    /// Unreachable results are moved to the end, without changing their order.
+    ///
+    /// Since the travel times are in the original result order, only the first
+    /// travel times received for a query are used.
+    pub fn sort_by_travel_time(&mut self, id: QueryId, times: &[Option<u64>]) {
+        // Ignore travel times for outdated or already sorted results.
+        if id != self.last_query
+            || self.sorted_query == Some(id)
+            || times.len() != self.results.len()
+        {
+            return;
+        }
+        self.sorted_query = Some(id);
+
+        let mut results: Vec<_> = mem::take(&mut self.results).into_iter().zip(times).collect();
+        results.sort_by_key(|(_, time)| time.unwrap_or(u64::MAX));
+        self.results = results.into_iter().map(|(result, _)| result).collect();
+    }
+
     /// Get current query results.
     pub fn results(&self) -> &[QueryResult] {
         &self.results
@@ -133,14 +315,41 @@ impl Geocoder {
         self.photon_searching || self.nlp_searching
     }
 
+    /// Check if offline search datasets are still being loaded.
+    pub fn offline_loading(&self) -> bool {
+        self.nlp_loading
+    }
+
+    /// Check if the offline geocoder is able to process queries.
+    ///
+    /// This is `false` if the geocoder thread is not running, or if no dataset
+    /// of any installed region could be loaded.
+    pub fn offline_available(&self) -> bool {
+        #[cfg(feature = "offline-search")]
+        {
+            self.nlp_query_tx.is_some() && !self.nlp_unavailable
+        }
+        #[cfg(not(feature = "offline-search"))]
+        {
+            false
+        }
+    }
+
     /// Handle config updates.
     pub fn update_config(&mut self, config: &Config) {
-        // Restart Photon geocoder on URL change.
-        if config.search.photon_url != self.photon_url {
+        self.transliterate_titles = config.search.transliterate_titles;
+        self.reverse_config = config.search.reverse.clone();
+        self.rank_weights = RankWeights::new(config);
+
+        // Restart Photon geocoder on URL or timeout change.
+        if config.search.photon_url != self.photon_url
+            || *config.search.photon_timeout != self.photon_timeout
+        {
             // Drop old router first, to improve log order.
             self.photon_query_tx = None;
 
             self.photon_url = config.search.photon_url.clone();
+            self.photon_timeout = *config.search.photon_timeout;
             self.photon_query_tx = (!config.search.photon_url.is_empty()).then(|| {
                 let (photon_query_tx, photon_query_rx) = mpsc::channel::<QueryEvent>();
                 photon::Geocoder::spawn(
@@ -148,23 +357,55 @@ impl Geocoder {
                     config,
                     photon_query_rx,
                     self.result_tx.clone(),
+                    self.last_query_tx.subscribe(),
                 );
                 photon_query_tx
             });
         }
     }
 
+    /// Reload offline geocoder datasets after installed regions changed.
+    pub fn reload_offline_datasets(&mut self) {
+        #[cfg(feature = "offline-search")]
+        if let Some(nlp_query_tx) = &self.nlp_query_tx {
+            let _ = nlp_query_tx.send(nlp::Event::Reload);
+        }
+    }
+
     /// Submit any type of query to all geocoders.
     fn query(&mut self, query: QueryEvent) {
-        self.last_query = query.id();
-        self.photon_searching = true;
-        self.nlp_searching = true;
+        let id = query.id();
+        self.set_last_query(id);
+        self.last_event = Some(query.clone());
+        self.photon_searching = false;
+        self.nlp_searching = false;
         self.results.clear();
 
+        #[cfg(feature = "offline-search")]
+        if let Some(nlp_query_tx) = &self.nlp_query_tx {
+            self.nlp_searching = nlp_query_tx.send(nlp::Event::Query(query.clone())).is_ok();
+
+            // Disable offline search if its thread has stopped unexpectedly.
+            if !self.nlp_searching {
+                error!("Offline search unavailable: geocoder thread has stopped");
+                self.nlp_query_tx = None;
+            }
+        }
+
         if let Some(query_tx) = &self.photon_query_tx {
-            let _ = query_tx.send(query.clone());
+            self.photon_searching = query_tx.send(query).is_ok();
+        }
+
+        // Report the missing results if no geocoder is processing the query.
+        if !self.searching() {
+            let _ = self.result_tx.send((id, QueryResultEvent::Results(Vec::new())));
         }
-        let _ = self.nlp_query_tx.send(query);
+    }
+
+    /// Update the active query, cancelling requests for all previous queries.
+    fn set_last_query(&mut self, id: QueryId) {
+        self.last_query = id;
+        self.last_query_tx.send_replace(id);
     }
 }
 
@@ -173,6 +414,7 @@ impl Geocoder {
 pub enum QueryEvent {
     Search(SearchQuery),
     Reverse(ReverseQuery),
+    Corridor(CorridorQuery),
 }
 
 impl QueryEvent {
@@ -180,6 +422,16 @@ impl QueryEvent {
         match self {
             Self::Search(search_query) => search_query.id,
             Self::Reverse(reverse_query) => reverse_query.id,
+            Self::Corridor(corridor_query) => corridor_query.id,
+        }
+    }
+
+    /// Assign a new ID to this query.
+    fn renew_id(&mut self) {
+        match self {
+            Self::Search(search_query) => search_query.id = QueryId::new(),
+            Self::Reverse(reverse_query) => reverse_query.id = QueryId::new(),
+            Self::Corridor(corridor_query) => corridor_query.id = QueryId::new(),
         }
     }
 }
@@ -190,6 +442,9 @@ pub struct SearchQuery {
     id: QueryId,
     text: String,
     reference_point: Option<GeoPoint>,
+    #[cfg(feature = "offline-search")]
+    transliterated: Option<String>,
+    #[cfg_attr(not(feature = "offline-search"), allow(dead_code))]
     reference_zoom: Option<u8>,
 }
 
@@ -199,6 +454,8 @@ impl SearchQuery {
             id: QueryId::new(),
             text: query.into(),
             reference_point: Default::default(),
+            #[cfg(feature = "offline-search")]
+            transliterated: Default::default(),
             reference_zoom: Default::default(),
         }
     }
@@ -210,6 +467,7 @@ impl SearchQuery {
     }
 
     /// Get query's reference point in NLP's [`SearchReference`] format.
+    #[cfg(feature = "offline-search")]
     fn reference_nlp(&self) -> Option<SearchReference> {
         let point = self.reference_point?;
         let mut reference = SearchReference::new(point.lat, point.lon);
@@ -220,17 +478,88 @@ impl SearchQuery {
     }
 }
 
+/// Maximum reverse geocoding search radius in meters.
+///
+/// This is required since Geocoder NLP will just search through EVERY available
+/// entry otherwise, which tends to be pathological beyond certain sizes.
+const MAX_REVERSE_RADIUS: f64 = 1_000.;
+
 /// Reverse geocoding query.
 #[derive(Clone)]
 pub struct ReverseQuery {
     id: QueryId,
     point: GeoPoint,
     zoom: u8,
+    /// Search radius in pixels.
+    radius: f64,
+    max_results: usize,
+    preferred_types: Arc<Vec<String>>,
 }
 
 impl ReverseQuery {
     pub fn new(point: GeoPoint, zoom: u8) -> Self {
-        Self { point, zoom, id: QueryId::new() }
+        let config = ReverseSearch::default();
+        Self {
+            point,
+            zoom,
+            radius: config.radius,
+            max_results: config.max_results as usize,
+            preferred_types: config.preferred_types.0,
+            id: QueryId::new(),
+        }
+    }
+
+    /// Get the search radius in meters.
+    fn radius_meters(&self) -> f64 {
+        let pixel_size = geometry::pixel_size(self.point.lat, self.zoom);
+        (self.radius * pixel_size).min(MAX_REVERSE_RADIUS)
+    }
+
+    /// Apply the entity type preference and result limit.
+    ///
+    /// The results are expected to be sorted by relevance.
+    fn filter_results(
+        &self,
+        entity_types: &HashMap<&str, EntityType>,
+        results: &mut Vec<QueryResult>,
+    ) {
+        let preferred: Vec<_> =
+            self.preferred_types.iter().filter_map(|tag| entity_types.get(tag.as_str())).collect();
+        let is_preferred = |result: &QueryResult| preferred.contains(&&result.entity_type);
+
+        // Only show preferred entities if any of them were found.
+        if results.iter().any(is_preferred) {
+            results.retain(is_preferred);
+        }
+
+        results.truncate(self.max_results);
+    }
+}
+
+/// Distance between sampled points of a search along a route in meters.
+const CORRIDOR_SAMPLE_DISTANCE: f64 = 1_000.;
+
+/// Search query for places to stop along a route.
+///
+/// Only the offline geocoder supports searching along a route.
+#[derive(Clone)]
+pub struct CorridorQuery {
+    id: QueryId,
+    /// Points along the route, at most [`CORRIDOR_SAMPLE_DISTANCE`] apart.
+    #[cfg_attr(not(feature = "offline-search"), allow(dead_code))]
+    points: Vec<GeoPoint>,
+    /// Average route speed in meters per second.
+    #[cfg_attr(not(feature = "offline-search"), allow(dead_code))]
+    speed: f64,
+}
+
+impl CorridorQuery {
+    /// Create a query for the part of a route after the first `progress` nodes.
+    pub fn new(route: &Route, progress: usize) -> Self {
+        let path = route.segments.iter().flat_map(|segment| &segment.points).skip(progress);
+        let points = geometry::sample_path(path.copied(), CORRIDOR_SAMPLE_DISTANCE);
+        let speed = route.length as f64 / route.time.max(1) as f64;
+        Self { points, speed, id: QueryId::new() }
     }
 }
 
@@ -241,6 +570,7 @@ pub enum QueryResultEvent {
     /// Photon search is done, no more results will be delivered.
     PhotonDone,
     /// Geocoder NLP search is done, no more results will be delivered.
+    #[cfg(feature = "offline-search")]
     NlpDone,
 }
 
@@ -248,6 +578,8 @@ pub enum QueryResultEvent {
 #[derive(Debug)]
 pub struct QueryResult {
     pub point: GeoPoint,
+    /// South-west and north-east corner of the entity's geographic extent.
+    pub extent: Option<[GeoPoint; 2]>,
     // Distance to the reference in meters.
     pub distance: Option<u32>,
 
@@ -255,16 +587,90 @@ pub struct QueryResult {
 
     pub address: String,
 
-    pub entity_type: &'static str,
+    pub entity_type: EntityType,
 
     pub rank: QueryResultRank,
+
+    /// Result of a typo-corrected query variation.
+    pub suggestion: bool,
 }
 
 /// Geocoder-specific search result rank.
 #[derive(Copy, Clone, Debug)]
 pub enum QueryResultRank {
     /// Geocoder NLP result rank, lower is better.
+    ///
+    /// This is the raw geocoder-nlp rank until results are submitted, which
+    /// replaces it with the result's position starting at zero.
     Nlp(f64),
     /// Photon result rank, lower is better.
     Photon(usize),
+    /// Favorite location, sorted by travel time.
+    Favorite,
+}
+
+impl Ord for QueryResultRank {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Favorite, Self::Favorite) => Ordering::Equal,
+            (Self::Favorite, _) => Ordering::Less,
+            (_, Self::Favorite) => Ordering::Greater,
+            (Self::Photon(a), Self::Photon(b)) => a.cmp(b),
+            (Self::Photon(_), Self::Nlp(_)) => Ordering::Less,
+            (Self::Nlp(a), Self::Nlp(b)) => a.total_cmp(b),
+            (Self::Nlp(_), Self::Photon(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for QueryResultRank {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueryResultRank {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for QueryResultRank {}
+
+/// Normalization of result ranks across geocoding providers.
+#[derive(Copy, Clone, Debug)]
+struct RankWeights {
+    photon: f64,
+    offline: f64,
+    distance_boost: f64,
+}
+
+impl RankWeights {
+    fn new(config: &Config) -> Self {
+        Self {
+            photon: config.search.photon_weight,
+            offline: config.search.offline_weight,
+            distance_boost: config.search.distance_boost,
+        }
+    }
+
+    /// Compare two results, with the better match first.
+    fn cmp(&self, a: &QueryResult, b: &QueryResult) -> Ordering {
+        let (score_a, score_b) = (self.score(a), self.score(b));
+        score_b.total_cmp(&score_a).then_with(|| a.rank.cmp(&b.rank))
+    }
+
+    /// Get a result's provider-independent relevance, higher is better.
+    fn score(&self, result: &QueryResult) -> f64 {
+        // Both ranks are result positions, starting at zero for the best match.
+        let (rank, weight) = match result.rank {
+            QueryResultRank::Favorite => return f64::INFINITY,
+            QueryResultRank::Photon(index) => (index as f64, self.photon),
+            QueryResultRank::Nlp(index) => (index, self.offline),
+        };
+        let relevance = weight / (1. + rank);
+
+        let distance_km = result.distance.map_or(0., |distance| distance as f64 / 1000.);
+        relevance / (1. + self.distance_boost * distance_km)
+    }
 }
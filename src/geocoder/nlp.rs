@@ -1,50 +1,108 @@
 //! Offline geocoding using geocoder-nlp.
 
 use std::collections::HashMap;
-use std::path::Path;
+use std::iter;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, mpsc};
 use std::thread::Builder as ThreadBuilder;
+use std::time::{Duration, Instant};
 
 use calloop::channel;
 use geocoder_nlp::{Geocoder as GeocoderNlp, SearchIter};
+use tokio::sync::watch;
 use tracing::{error, info, warn};
 
+use crate::Error;
+use crate::entity_type::{self, EntityType};
 use crate::geocoder::{
-    QueryEvent, QueryResult, QueryResultEvent, QueryResultRank, ReverseQuery, SearchQuery,
+    CorridorQuery, QueryEvent, QueryResult, QueryResultEvent, QueryResultRank, ReverseQuery,
+    SearchQuery,
 };
-use crate::geometry::{self, GeoPoint};
-use crate::region::{Region, Regions};
+use crate::geometry::GeoPoint;
+use crate::region::Regions;
 use crate::ui::view::search::QueryId;
-use crate::{Error, entity_type};
 
-/// Search radius in pixels for reverse geocoding.
-const SEARCH_RADIUS: f64 = 50.;
+/// Search radius around every sampled route point in meters.
+const CORRIDOR_RADIUS: f64 = 1_000.;
 
-/// Maximum search radius in meters.
+/// Entity types considered when searching along a route.
+const CORRIDOR_TAGS: [&str; 4] =
+    ["amenity_fuel", "amenity_charging_station", "highway_rest_area", "highway_services"];
+
+/// Maximum number of query variations searched when there are no results.
+const MAX_FUZZY_QUERIES: usize = 24;
+
+/// Maximum time spent searching query variations.
 ///
-/// This is required since Geocoder NLP will just search through EVERY available
-/// entry otherwise, which tends to be pathological beyond certain sizes.
-const MAX_SEARCH_RADIUS: f64 = 1_000.;
+/// Every variation is a full search of all datasets, so searching all of them
+/// could block the next query for a long time.
+const MAX_FUZZY_DURATION: Duration = Duration::from_secs(3);
+
+/// Minimum character count of words considered for typo correction.
+const MIN_FUZZY_WORD_LEN: usize = 4;
+
+/// Rows of a QWERTY keyboard, used to find likely substitution typos.
+const KEYBOARD_ROWS: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// Geocoder NLP thread event.
+pub enum Event {
+    /// Process a geocoding query.
+    Query(QueryEvent),
+    /// Find entity titles starting with a partial query.
+    Suggest(String),
+    /// Reload datasets to match the installed regions.
+    Reload,
+}
 
 /// Geocoder NLP orchestrator.
+///
+/// A separate dataset is kept loaded for every installed region, with the
+/// results of all datasets being merged before they're submitted.
+///
+/// Since libpostal is not thread-safe, all datasets are searched sequentially
+/// on the geocoder thread.
 pub struct Geocoder {
-    geocoder: Option<GeocoderNlp>,
+    geocoders: Vec<LoadedGeocoder>,
 
     regions: Arc<Regions>,
 
-    query_rx: mpsc::Receiver<QueryEvent>,
+    query_rx: mpsc::Receiver<Event>,
     result_tx: channel::Sender<(QueryId, QueryResultEvent)>,
+    suggestion_tx: channel::Sender<Vec<String>>,
+    loading_tx: channel::Sender<bool>,
+    available_tx: channel::Sender<bool>,
+    last_query: watch::Receiver<QueryId>,
+    available: bool,
 }
 
 impl Geocoder {
     /// Spawn Geocoder NLP in a new background thread.
+    ///
+    /// The `loading_tx` channel is notified whenever datasets start or finish
+    /// loading, while `available_tx` is notified whenever the datasets of all
+    /// installed regions start or stop failing to load.
     pub fn spawn(
         regions: Arc<Regions>,
-        query_rx: mpsc::Receiver<QueryEvent>,
+        query_rx: mpsc::Receiver<Event>,
         result_tx: channel::Sender<(QueryId, QueryResultEvent)>,
+        suggestion_tx: channel::Sender<Vec<String>>,
+        loading_tx: channel::Sender<bool>,
+        available_tx: channel::Sender<bool>,
+        last_query: watch::Receiver<QueryId>,
     ) -> Result<(), Error> {
         ThreadBuilder::new().name("geocoder-nlp".into()).spawn(move || {
-            let mut geocoder = Self { result_tx, query_rx, regions, geocoder: Default::default() };
+            let geocoders = Default::default();
+            let mut geocoder = Self {
+                suggestion_tx,
+                result_tx,
+                loading_tx,
+                available_tx,
+                query_rx,
+                last_query,
+                regions,
+                geocoders,
+                available: true,
+            };
             geocoder.listen();
         })?;
         Ok(())
@@ -57,15 +115,49 @@ impl Geocoder {
         let postal_global_path = self.regions.postal_global_path();
         let entity_types = entity_type::entity_types();
 
-        while let Ok(query) = self.query_rx.recv() {
+        // Load datasets ahead of time, to avoid delaying the first search.
+        self.load_geocoders(&postal_global_path);
+
+        while let Ok(event) = self.query_rx.recv() {
+            // Drain the queue, since only the newest query is still relevant.
+            let mut query = None;
+            let mut suggest = None;
+            let mut reload = false;
+            for event in iter::once(event).chain(self.query_rx.try_iter()) {
+                match event {
+                    Event::Query(next_query) => query = Some(next_query),
+                    Event::Suggest(text) => suggest = Some(text),
+                    Event::Reload => reload = true,
+                }
+            }
+
+            if reload {
+                self.load_geocoders(&postal_global_path);
+            }
+
+            let query = match (query, suggest) {
+                (Some(query), _) => query,
+                // Only look for suggestions while no search is pending.
+                (None, Some(text)) => {
+                    self.suggest(&postal_global_path, &text);
+                    continue;
+                },
+                (None, None) => continue,
+            };
+
             let id = query.id();
             match query {
+                // Skip queries which were superseded while waiting for dataset loading.
+                _ if id.is_superseded(&self.last_query) => (),
                 QueryEvent::Search(search_query) => {
                     self.search(&postal_global_path, entity_types, search_query);
                 },
                 QueryEvent::Reverse(reverse_query) => {
                     self.reverse(&postal_global_path, entity_types, reverse_query);
                 },
+                QueryEvent::Corridor(corridor_query) => {
+                    self.corridor(&postal_global_path, entity_types, corridor_query);
+                },
             }
 
             // Mark this query as done.
@@ -79,71 +171,248 @@ impl Geocoder {
     fn search(
         &mut self,
         postal_global_path: &Path,
-        entity_types: &HashMap<&str, &'static str>,
+        entity_types: &HashMap<&str, EntityType>,
         query: SearchQuery,
     ) {
-        self.regions.world().for_installed(&mut |region| {
-            Self::init_geocoder(&mut self.geocoder, &self.regions, region, postal_global_path);
-            let geocoder = match &mut self.geocoder {
-                Some(geocoder) => geocoder,
-                None => return,
-            };
+        // Parse the query using the postal country at the search reference.
+        let active_postal_path =
+            query.reference_point.and_then(|point| self.active_postal_path(point));
+        self.load_geocoders(postal_global_path);
+        self.set_postal_country(active_postal_path.as_deref());
+
+        let mut query_results = self.search_text(entity_types, &query, &query.text);
+
+        // Search for Latin-script names, since few entities have localized names.
+        if let Some(transliterated) = &query.transliterated {
+            for result in self.search_text(entity_types, &query, transliterated) {
+                if !query_results.iter().any(|r| r.point == result.point && r.title == result.title)
+                {
+                    query_results.push(result);
+                }
+            }
+        }
+
+        // Retry with typo-tolerant query variations if nothing was found.
+        if query_results.is_empty() {
+            query_results = self.fuzzy_search(entity_types, &query);
+        }
+
+        self.submit_results(query.id, query_results);
+    }
+
+    /// Search all datasets for a query text.
+    fn search_text(
+        &mut self,
+        entity_types: &HashMap<&str, EntityType>,
+        query: &SearchQuery,
+        text: &str,
+    ) -> Vec<QueryResult> {
+        let mut query_results = Vec::new();
+        for loaded in &mut self.geocoders {
+            // Abort between datasets once the search was replaced.
+            //
+            // Geocoder NLP searches cannot be interrupted, so this is the
+            // earliest point at which the next query can be processed.
+            if query.id.is_superseded(&self.last_query) {
+                break;
+            }
 
             // Search this region for a result.
-            let results = match geocoder.search(&query.text, query.reference_nlp()) {
+            let results = match loaded.geocoder.search(text, query.reference_nlp()) {
                 Ok(results) => results,
-                // Since only one region might be broken, we don't return `false` here.
+                // Since only one region might be broken, we don't abort here.
                 Err(err) => {
                     error!("Failed geocoder-nlp search: {err}");
-                    return;
+                    continue;
                 },
             };
 
-            // Process results and send them to the collector.
-            let query_results = Self::map_results(entity_types, query.reference_point, results);
-            let event = QueryResultEvent::Results(query_results);
-            let _ = self.result_tx.send((query.id, event));
-        });
+            let results = Self::map_results(entity_types, query.reference_point, results);
+            query_results.extend(results);
+        }
+        query_results
+    }
+
+    /// Find entity titles starting with a partial query.
+    fn suggest(&mut self, postal_global_path: &Path, text: &str) {
+        self.load_geocoders(postal_global_path);
+
+        let prefix = text.trim_start().to_lowercase();
+        let mut titles: Vec<String> = Vec::new();
+        for loaded in &mut self.geocoders {
+            let mut results = match loaded.geocoder.search(text, None) {
+                Ok(results) => results,
+                Err(err) => {
+                    error!("Failed geocoder-nlp suggestion search: {err}");
+                    continue;
+                },
+            };
+
+            while let Some(result) = results.next() {
+                let title = result.title();
+                if title.to_lowercase().starts_with(&prefix) && !titles.iter().any(|t| *t == title)
+                {
+                    titles.push(title.into_owned());
+                }
+            }
+        }
+
+        let _ = self.suggestion_tx.send(titles);
+    }
+
+    /// Search for query variations with a single typo corrected.
+    ///
+    /// Variations are searched from most to least likely, stopping at less
+    /// likely corrections once any results were found.
+    ///
+    /// Since these results do not match the query exactly, they are all marked
+    /// as suggestions.
+    fn fuzzy_search(
+        &mut self,
+        entity_types: &HashMap<&str, EntityType>,
+        query: &SearchQuery,
+    ) -> Vec<QueryResult> {
+        let deadline = Instant::now() + MAX_FUZZY_DURATION;
+
+        let mut query_results: Vec<QueryResult> = Vec::new();
+        let mut query_results_cost = 0;
+        for variant in fuzzy_variants(&query.text).iter().take(MAX_FUZZY_QUERIES) {
+            if query.id.is_superseded(&self.last_query) || Instant::now() >= deadline {
+                break;
+            }
+
+            // Prefer fewer results over results for unlikely typos.
+            if !query_results.is_empty() && query_results_cost < variant.cost {
+                break;
+            }
+
+            for mut result in self.search_text(entity_types, query, &variant.text) {
+                // Ignore results already found through a different variation.
+                if query_results.iter().any(|r| r.point == result.point && r.title == result.title)
+                {
+                    continue;
+                }
+
+                result.suggestion = true;
+                query_results.push(result);
+                query_results_cost = variant.cost;
+            }
+        }
+        query_results
     }
 
     /// Process a reverse geocoding query.
     fn reverse(
         &mut self,
         postal_global_path: &Path,
-        entity_types: &HashMap<&str, &'static str>,
+        entity_types: &HashMap<&str, EntityType>,
         query: ReverseQuery,
     ) {
-        self.regions.world().for_installed(&mut |region| {
-            Self::init_geocoder(&mut self.geocoder, &self.regions, region, postal_global_path);
-            let geocoder = match &mut self.geocoder {
-                Some(geocoder) => geocoder,
-                None => return,
-            };
+        let active_postal_path = self.active_postal_path(query.point);
+        self.load_geocoders(postal_global_path);
+        self.set_postal_country(active_postal_path.as_deref());
 
-            // Convert search radius in pixels to search radius in meters.
-            let pixel_size = geometry::pixel_size(query.point.lat, query.zoom);
-            let search_radius = (SEARCH_RADIUS * pixel_size).min(MAX_SEARCH_RADIUS);
+        let search_radius = query.radius_meters();
+
+        let mut query_results = Vec::new();
+        for loaded in &mut self.geocoders {
+            // Abort between datasets once the query was replaced.
+            if query.id.is_superseded(&self.last_query) {
+                return;
+            }
 
             // Search this region for a result.
-            let results = match geocoder.reverse(query.point.lat, query.point.lon, search_radius) {
+            let point = query.point;
+            let results = match loaded.geocoder.reverse(point.lat, point.lon, search_radius) {
                 Ok(results) => results,
-                // Since only one region might be broken, we don't return `false` here.
+                // Since only one region might be broken, we don't abort here.
                 Err(err) => {
                     error!("Failed geocoder-nlp reverse search: {err}");
-                    return;
+                    continue;
                 },
             };
 
-            // Process results and send them to the collector.
-            let query_results = Self::map_results(entity_types, Some(query.point), results);
-            let event = QueryResultEvent::Results(query_results);
-            let _ = self.result_tx.send((query.id, event));
-        });
+            query_results.extend(Self::map_results(entity_types, Some(point), results));
+        }
+
+        query_results.sort_unstable_by(|a, b| a.rank.cmp(&b.rank));
+        query.filter_results(entity_types, &mut query_results);
+
+        self.submit_results(query.id, query_results);
+    }
+
+    /// Process a search for places to stop along a route.
+    ///
+    /// Results are ranked by their estimated detour in seconds.
+    fn corridor(
+        &mut self,
+        postal_global_path: &Path,
+        entity_types: &HashMap<&str, EntityType>,
+        query: CorridorQuery,
+    ) {
+        self.load_geocoders(postal_global_path);
+
+        let corridor_types: HashMap<_, _> =
+            CORRIDOR_TAGS.iter().filter_map(|tag| Some((*tag, *entity_types.get(tag)?))).collect();
+
+        let mut query_results: Vec<QueryResult> = Vec::new();
+        for &point in &query.points {
+            // Abort long routes early once the search was replaced.
+            if query.id.is_superseded(&self.last_query) {
+                return;
+            }
+
+            for loaded in &mut self.geocoders {
+                let results = match loaded.geocoder.reverse(point.lat, point.lon, CORRIDOR_RADIUS) {
+                    Ok(results) => results,
+                    // Since only one region might be broken, we don't abort here.
+                    Err(err) => {
+                        error!("Failed geocoder-nlp corridor search: {err}");
+                        continue;
+                    },
+                };
+
+                for mut result in Self::map_results(&corridor_types, Some(point), results) {
+                    // Estimate the detour as the way to the result and back at route speed.
+                    let detour = 2. * result.distance.unwrap_or_default() as f64;
+                    result.rank = QueryResultRank::Nlp(detour / query.speed);
+
+                    // Keep the shortest detour for results close to multiple route points.
+                    let existing = query_results
+                        .iter_mut()
+                        .find(|r| r.point == result.point && r.title == result.title);
+                    match existing {
+                        Some(existing) if existing.rank > result.rank => *existing = result,
+                        Some(_) => (),
+                        None => query_results.push(result),
+                    }
+                }
+            }
+        }
+
+        self.submit_results(query.id, query_results);
+    }
+
+    /// Merge results from all datasets and send them to the collector.
+    ///
+    /// Since geocoder-nlp's ranks have no fixed scale, they are replaced by
+    /// the result's position, to allow comparing them to other providers.
+    fn submit_results(&self, id: QueryId, mut query_results: Vec<QueryResult>) {
+        if query_results.is_empty() {
+            return;
+        }
+
+        query_results.sort_unstable_by(|a, b| a.rank.cmp(&b.rank));
+        for (index, result) in query_results.iter_mut().enumerate() {
+            result.rank = QueryResultRank::Nlp(index as f64);
+        }
+
+        let _ = self.result_tx.send((id, QueryResultEvent::Results(query_results)));
     }
 
     /// Map Geocoder NLP result to our expected format.
     fn map_results(
-        entity_types: &HashMap<&str, &'static str>,
+        entity_types: &HashMap<&str, EntityType>,
         reference_point: Option<GeoPoint>,
         mut results: SearchIter,
     ) -> Vec<QueryResult> {
@@ -154,7 +423,7 @@ impl Geocoder {
             // Unknown entities generally refer to old data like
             // `emergency_fire_detection_system`, which have been removed from OSM. Since
             // these are likely irrelevant, we remove them from the result.
-            let entity_type = match entity_types.get(&*result.entity_type()).map(|et| &**et) {
+            let entity_type = match entity_types.get(&*result.entity_type()).copied() {
                 Some(entity_type) => entity_type,
                 None => continue,
             };
@@ -174,57 +443,300 @@ impl Geocoder {
                 point,
                 rank,
                 title: result.title().to_string(),
+                extent: None,
+                suggestion: false,
             });
         }
         query_results
     }
 
-    /// Dynamically initialize geocoder for a region.
-    fn init_geocoder(
-        geocoder: &mut Option<GeocoderNlp>,
-        regions: &Regions,
-        region: &Region,
-        postal_global_path: &Path,
-    ) {
-        // Get region-specific geocoding data paths.
-        let postal_country_path = match regions.postal_country_root(region) {
-            Some(postal_country_path) => postal_country_path,
-            None => {
-                warn!("Installed country has no postal data: {}", region.name);
-                return;
-            },
-        };
-        let geocoder_path = match regions.geocoder_path(region) {
-            Some(geocoder_path) => geocoder_path,
-            None => {
-                warn!("Installed country has no geocoder data: {}", region.name);
-                return;
-            },
-        };
+    /// Get the postal country data of the installed region at a point.
+    fn active_postal_path(&self, point: GeoPoint) -> Option<PathBuf> {
+        let mut postal_path = None;
+        self.regions.world().for_installed(&mut |region| {
+            if postal_path.is_none() && region.contains(point) {
+                postal_path = self.regions.postal_country_root(region);
+            }
+        });
+        postal_path
+    }
 
-        // Initialize or update the geocoder.
-        match geocoder {
-            Some(geocoder) => {
-                if let Err(err) = geocoder.set_geocoder_path(&geocoder_path) {
-                    error!("Failed to update geocoder path for {}: {err}", region.name);
-                    return;
-                }
-                geocoder.set_postal_country_path(&postal_country_path);
-            },
-            None => {
-                let geocoder_nlp = match GeocoderNlp::new(
-                    postal_global_path,
-                    &postal_country_path,
-                    &geocoder_path,
-                ) {
+    /// Update the loaded datasets to match the installed regions.
+    fn load_geocoders(&mut self, postal_global_path: &Path) {
+        // Get all regions which are currently installed.
+        let mut installed = Vec::new();
+        self.regions.world().for_installed(&mut |region| installed.push(region));
+        let installed_count = installed.len();
+
+        // Unload datasets for regions which have been removed.
+        self.geocoders.retain(|loaded| {
+            let keep = installed.iter().any(|region| region.id == loaded.region_id);
+            if !keep {
+                info!("Unloading geocoder dataset {:?}", loaded.geocoder_path);
+            }
+            keep
+        });
+
+        // Load datasets for newly installed regions.
+        installed
+            .retain(|region| !self.geocoders.iter().any(|loaded| loaded.region_id == region.id));
+        if installed.is_empty() {
+            self.update_available(installed_count);
+            return;
+        }
+
+        let _ = self.loading_tx.send(true);
+
+        for region in installed {
+            // Get region-specific geocoding data paths.
+            let postal_country_path = match self.regions.postal_country_root(region) {
+                Some(postal_country_path) => postal_country_path,
+                None => {
+                    warn!("Installed country has no postal data: {}", region.name);
+                    continue;
+                },
+            };
+            let geocoder_path = match self.regions.geocoder_path(region) {
+                Some(geocoder_path) => geocoder_path,
+                None => {
+                    warn!("Installed country has no geocoder data: {}", region.name);
+                    continue;
+                },
+            };
+
+            let geocoder =
+                match GeocoderNlp::new(postal_global_path, &postal_country_path, &geocoder_path) {
                     Ok(geocoder) => geocoder,
                     Err(err) => {
                         error!("Failed to initialize geocoder for {}: {err}", region.name);
-                        return;
+                        continue;
                     },
                 };
-                *geocoder = Some(geocoder_nlp);
+
+            info!("Loaded geocoder dataset {geocoder_path:?}");
+
+            self.geocoders.push(LoadedGeocoder {
+                region_postal_country_path: postal_country_path.clone(),
+                postal_country_path,
+                geocoder_path,
+                geocoder,
+                region_id: region.id,
+            });
+        }
+
+        let _ = self.loading_tx.send(false);
+
+        self.update_available(installed_count);
+    }
+
+    /// Update offline search availability after loading datasets.
+    ///
+    /// Offline search is unavailable when regions are installed, but none of
+    /// their datasets could be loaded.
+    fn update_available(&mut self, installed_count: usize) {
+        let available = installed_count == 0 || !self.geocoders.is_empty();
+        if available == self.available {
+            return;
+        }
+        self.available = available;
+
+        if !available {
+            error!("Offline search unavailable: no geocoder dataset could be loaded");
+        }
+
+        let _ = self.available_tx.send(available);
+    }
+
+    /// Update the postal country data of all loaded datasets.
+    ///
+    /// If `active_postal_path` is present, it will be used instead of the
+    /// region's own postal country data. This allows parsing queries using the
+    /// language of the user's current location, regardless of the searched
+    /// region.
+    fn set_postal_country(&mut self, active_postal_path: Option<&Path>) {
+        for loaded in &mut self.geocoders {
+            let postal_country_path =
+                active_postal_path.unwrap_or(loaded.region_postal_country_path.as_path());
+
+            // Only switch postal data on change, since it is expensive to load.
+            if loaded.postal_country_path != postal_country_path {
+                info!("Switching postal country data to {postal_country_path:?}");
+                loaded.geocoder.set_postal_country_path(postal_country_path);
+                loaded.postal_country_path = postal_country_path.to_path_buf();
+            }
+        }
+    }
+}
+
+/// Get all query variations within an edit distance of one.
+///
+/// Insertions are limited to repeating a character and substitutions to
+/// neighboring keys on the keyboard, since other characters would require
+/// guessing the alphabet.
+///
+/// Variations are sorted by how likely their typo is.
+fn fuzzy_variants(text: &str) -> Vec<FuzzyVariant> {
+    let words: Vec<&str> = text.split_whitespace().collect();
+
+    let mut variants = Vec::new();
+    for (i, word) in words.iter().enumerate() {
+        let chars: Vec<char> = word.chars().collect();
+        if chars.len() < MIN_FUZZY_WORD_LEN {
+            continue;
+        }
+
+        let mut push = |chars: Vec<char>, cost: u8, j: usize| {
+            // Typos in the first character are rare.
+            let cost = if j == 0 { cost + 2 } else { cost };
+
+            let text = replace_word(&words, i, chars.into_iter().collect());
+            variants.push(FuzzyVariant { text, cost });
+        };
+
+        for j in 0..chars.len() {
+            // Swap two adjacent characters.
+            if j + 1 < chars.len() && chars[j] != chars[j + 1] {
+                let mut transposition = chars.clone();
+                transposition.swap(j, j + 1);
+                push(transposition, 1, j);
+            }
+
+            // Replace a character with one of its neighboring keys.
+            for neighbor in keyboard_neighbors(chars[j]) {
+                let mut substitution = chars.clone();
+                substitution[j] = neighbor;
+                push(substitution, 2, j);
+            }
+
+            // Repeat a character.
+            let mut insertion = chars.clone();
+            insertion.insert(j, chars[j]);
+            push(insertion, 2, j);
+
+            // Remove one character, preferring accidentally repeated ones.
+            let mut deletion = chars.clone();
+            deletion.remove(j);
+            let repeated = chars.get(j + 1) == Some(&chars[j]);
+            push(deletion, if repeated { 2 } else { 3 }, j);
+        }
+    }
+
+    // Sort by likelihood, without separating variations of the same cost.
+    variants.sort_by_key(|variant| variant.cost);
+
+    // Remove duplicates, like deletions within repeated characters.
+    let mut unique: Vec<FuzzyVariant> = Vec::with_capacity(variants.len());
+    for variant in variants {
+        if !unique.iter().any(|unique| unique.text == variant.text) {
+            unique.push(variant);
+        }
+    }
+    unique
+}
+
+/// Get the keys next to a character on a QWERTY keyboard.
+///
+/// The case of the character is retained.
+fn keyboard_neighbors(c: char) -> Vec<char> {
+    let lowercase = c.to_ascii_lowercase();
+    let row = match KEYBOARD_ROWS.iter().position(|row| row.contains(lowercase)) {
+        Some(row) => row,
+        None => return Vec::new(),
+    };
+    let index = KEYBOARD_ROWS[row].find(lowercase).unwrap();
+
+    // Rows are staggered, so keys above are shifted right and keys below left.
+    let mut positions = vec![(row, index.wrapping_sub(1)), (row, index + 1)];
+    if row > 0 {
+        positions.extend([(row - 1, index), (row - 1, index + 1)]);
+    }
+    if row + 1 < KEYBOARD_ROWS.len() {
+        positions.extend([(row + 1, index.wrapping_sub(1)), (row + 1, index)]);
+    }
+
+    positions
+        .into_iter()
+        .filter_map(|(row, index)| KEYBOARD_ROWS[row].chars().nth(index))
+        .map(
+            |neighbor| {
+                if c.is_ascii_uppercase() { neighbor.to_ascii_uppercase() } else { neighbor }
             },
+        )
+        .collect()
+}
+
+/// Join words back together, with one of them replaced.
+fn replace_word(words: &[&str], index: usize, replacement: String) -> String {
+    let mut text = String::new();
+    for (i, word) in words.iter().enumerate() {
+        if i > 0 {
+            text.push(' ');
         }
+        text.push_str(if i == index { &replacement } else { word });
+    }
+    text
+}
+
+/// Query variation with a single typo corrected.
+#[derive(Debug)]
+struct FuzzyVariant {
+    text: String,
+    /// Unlikeliness of the corrected typo.
+    cost: u8,
+}
+
+/// Geocoder NLP instance with its loaded datasets.
+struct LoadedGeocoder {
+    geocoder: GeocoderNlp,
+    geocoder_path: PathBuf,
+    postal_country_path: PathBuf,
+    region_postal_country_path: PathBuf,
+    region_id: u32,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typo_variants() {
+        let variants = fuzzy_variants("Bahnhfo strase 1");
+        let texts: Vec<_> = variants.iter().map(|variant| variant.text.as_str()).collect();
+
+        // Transposition.
+        assert!(texts.contains(&"Bahnhof strase 1"));
+
+        // Substitution with a neighboring key.
+        assert!(texts.contains(&"Bahnhfo strade 1"));
+
+        // Repetition.
+        assert!(texts.contains(&"Bahnhfo strasse 1"));
+
+        // Deletion.
+        assert!(texts.contains(&"Bahnhfo strae 1"));
+
+        // Short words are left alone.
+        assert!(texts.iter().all(|text| text.ends_with(" 1")));
+
+        // Duplicate deletions within repeated characters are removed.
+        let variants = fuzzy_variants("Hallo");
+        assert_eq!(variants.iter().filter(|variant| variant.text == "Halo").count(), 1);
+    }
+
+    #[test]
+    fn typo_variant_order() {
+        let variants = fuzzy_variants("Bahnhfo strase");
+        assert!(variants.windows(2).all(|pair| pair[0].cost <= pair[1].cost));
+
+        // Likely typos in later words are not pushed back by earlier words.
+        let position = |text| variants.iter().position(|variant| variant.text == text).unwrap();
+        assert!(position("Bahnhfo srtase") < position("Bahnhf strase"));
+    }
+
+    #[test]
+    fn keyboard_typos() {
+        assert_eq!(keyboard_neighbors('s'), vec!['a', 'd', 'w', 'e', 'z', 'x']);
+        assert_eq!(keyboard_neighbors('Q'), vec!['W', 'A']);
+        assert_eq!(keyboard_neighbors('ß'), Vec::<char>::new());
     }
 }
@@ -2,20 +2,23 @@
 
 use std::collections::HashMap;
 use std::sync::{Arc, mpsc};
+use std::time::Duration;
 
 use calloop::channel;
 use reqwest::Client;
 use serde::Deserialize;
+use tokio::sync::watch;
 use tracing::{error, info};
 
+use crate::Error;
 use crate::config::Config;
+use crate::entity_type::{self, EntityType};
 use crate::geocoder::geojson::{Feature, GeoJson, Geometry};
 use crate::geocoder::{
     QueryEvent, QueryResult, QueryResultEvent, QueryResultRank, ReverseQuery, SearchQuery,
 };
 use crate::geometry::GeoPoint;
 use crate::ui::view::search::QueryId;
-use crate::{Error, entity_type};
 
 /// Maximum results returned by one Photon query.
 const MAX_RESULTS: u8 = 15;
@@ -24,7 +27,9 @@ const MAX_RESULTS: u8 = 15;
 pub struct Geocoder {
     query_rx: mpsc::Receiver<QueryEvent>,
     result_tx: channel::Sender<(QueryId, QueryResultEvent)>,
+    last_query: watch::Receiver<QueryId>,
     url: Arc<String>,
+    timeout: Duration,
     client: Client,
 }
 
@@ -35,10 +40,12 @@ impl Geocoder {
         config: &Config,
         query_rx: mpsc::Receiver<QueryEvent>,
         result_tx: channel::Sender<(QueryId, QueryResultEvent)>,
+        last_query: watch::Receiver<QueryId>,
     ) {
         let url = config.search.photon_url.clone();
-        tokio::spawn(async {
-            let mut geocoder = Self { result_tx, query_rx, client, url };
+        let timeout = *config.search.photon_timeout;
+        tokio::spawn(async move {
+            let mut geocoder = Self { result_tx, query_rx, last_query, client, timeout, url };
             geocoder.listen().await;
         });
     }
@@ -51,17 +58,12 @@ impl Geocoder {
 
         while let Ok(query) = self.query_rx.recv() {
             let id = query.id();
-            match query {
-                QueryEvent::Search(search_query) => {
-                    if let Err(err) = self.search(entity_types, search_query).await {
-                        error!("Photon geocoding failed: {err}");
-                    }
-                },
-                QueryEvent::Reverse(reverse_query) => {
-                    if let Err(err) = self.reverse(entity_types, reverse_query).await {
-                        error!("Photon reverse geocoding failed: {err}");
-                    }
-                },
+
+            // Abort the request once a newer query supersedes it.
+            let mut last_query = self.last_query.clone();
+            tokio::select! {
+                _ = id.superseded(&mut last_query) => info!("Cancelled outdated Photon query"),
+                _ = self.query(entity_types, query) => (),
             }
 
             // Mark this query as done, regardless of success.
@@ -71,15 +73,34 @@ impl Geocoder {
         info!("Shutting down Photon geocoder ({})", self.url);
     }
 
+    /// Process any type of query.
+    async fn query(&self, entity_types: &HashMap<&str, EntityType>, query: QueryEvent) {
+        match query {
+            QueryEvent::Search(search_query) => {
+                if let Err(err) = self.search(entity_types, search_query).await {
+                    error!("Photon geocoding failed: {err}");
+                }
+            },
+            QueryEvent::Reverse(reverse_query) => {
+                if let Err(err) = self.reverse(entity_types, reverse_query).await {
+                    error!("Photon reverse geocoding failed: {err}");
+                }
+            },
+            // Photon cannot search for entity types around a point.
+            QueryEvent::Corridor(_) => (),
+        }
+    }
+
     /// Process a geocoding search query.
     async fn search(
-        &mut self,
-        entity_types: &HashMap<&str, &'static str>,
+        &self,
+        entity_types: &HashMap<&str, EntityType>,
         query: SearchQuery,
     ) -> Result<(), Error> {
         // Get geocoding results from Photon.
         let url = format!("{}/api/?q={}&limit={}", self.url, query.text, MAX_RESULTS);
-        let response = self.client.get(&url).send().await?.error_for_status()?;
+        let request = self.client.get(&url).timeout(self.timeout);
+        let response = request.send().await?.error_for_status()?;
 
         let geo_json: GeoJson<PhotonProperties> = response.json().await?;
 
@@ -93,21 +114,27 @@ impl Geocoder {
 
     /// Process a reverse geocoding query.
     async fn reverse(
-        &mut self,
-        entity_types: &HashMap<&str, &'static str>,
+        &self,
+        entity_types: &HashMap<&str, EntityType>,
         query: ReverseQuery,
     ) -> Result<(), Error> {
         // Get geocoding results from Photon.
         let url = format!(
-            "{}/reverse?lat={}&lon={}&limit={}",
-            self.url, query.point.lat, query.point.lon, MAX_RESULTS,
+            "{}/reverse?lat={}&lon={}&radius={}&limit={}",
+            self.url,
+            query.point.lat,
+            query.point.lon,
+            query.radius_meters() / 1000.,
+            query.max_results,
         );
-        let response = self.client.get(&url).send().await?.error_for_status()?;
+        let request = self.client.get(&url).timeout(self.timeout);
+        let response = request.send().await?.error_for_status()?;
 
         let geo_json: GeoJson<PhotonProperties> = response.json().await?;
 
         // Transform and submit query results.
-        let query_results = Self::map_geo_json(entity_types, Some(query.point), geo_json);
+        let mut query_results = Self::map_geo_json(entity_types, Some(query.point), geo_json);
+        query.filter_results(entity_types, &mut query_results);
         let event = QueryResultEvent::Results(query_results);
         let _ = self.result_tx.send((query.id, event));
 
@@ -116,7 +143,7 @@ impl Geocoder {
 
     /// Map a Photon GeoJSON response to a list of query results.
     fn map_geo_json(
-        entity_types: &HashMap<&str, &'static str>,
+        entity_types: &HashMap<&str, EntityType>,
         reference_point: Option<GeoPoint>,
         geo_json: GeoJson<PhotonProperties>,
     ) -> Vec<QueryResult> {
@@ -142,7 +169,7 @@ impl Geocoder {
 
     /// Try to map a Photon GeoJSON feature to a query result.
     fn map_feature(
-        entity_types: &HashMap<&str, &'static str>,
+        entity_types: &HashMap<&str, EntityType>,
         reference_point: Option<GeoPoint>,
         feature: Feature<PhotonProperties>,
         index: usize,
@@ -157,7 +184,7 @@ impl Geocoder {
         // `emergency_fire_detection_system`, which have been removed from OSM. Since
         // these are likely irrelevant, we remove them from the result.
         let entity_type = format!("{}_{}", properties.osm_key, properties.osm_value);
-        let entity_type = entity_types.get(&*entity_type).map(|et| &**et)?;
+        let entity_type = *entity_types.get(&*entity_type)?;
 
         // Map geometry; luckily Photon only uses points, which makes our life easier.
         let point = match feature.geometry? {
@@ -168,12 +195,14 @@ impl Geocoder {
         };
 
         Some(QueryResult {
+            extent: properties.extent(),
             entity_type,
             address,
             point,
             title,
             distance: reference_point.map(|p| p.distance(point)),
             rank: QueryResultRank::Photon(index),
+            suggestion: false,
         })
     }
 }
@@ -193,9 +222,23 @@ struct PhotonProperties {
     country: Option<String>,
 
     name: Option<String>,
+
+    /// Bounding box as `[min_lon, max_lat, max_lon, min_lat]`.
+    #[serde(default)]
+    extent: Vec<f64>,
 }
 
 impl PhotonProperties {
+    /// Get the south-west and north-east corners of the entity's extent.
+    fn extent(&self) -> Option<[GeoPoint; 2]> {
+        match self.extent[..] {
+            [min_lon, max_lat, max_lon, min_lat] => {
+                Some([GeoPoint::new(min_lat, min_lon), GeoPoint::new(max_lat, max_lon)])
+            },
+            _ => None,
+        }
+    }
+
     /// Assemble address from its parts.
     fn address(&self) -> String {
         let mut address = String::new();
@@ -0,0 +1,204 @@
+//! Latin transliteration of Cyrillic and Greek text.
+
+/// Transliterate text to the Latin script.
+///
+/// Returns `None` if the text does not contain any transliterable characters.
+pub fn to_latin(text: &str) -> Option<String> {
+    let mut latin = String::with_capacity(text.len());
+    let mut changed = false;
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        let replacement = match latin_char(c) {
+            Some(replacement) => replacement,
+            None => {
+                latin.push(c);
+                continue;
+            },
+        };
+        changed = true;
+
+        // Keep multi-letter replacements of capitals uppercase within uppercase words.
+        let next_upper = chars.peek().is_some_and(|next| next.is_uppercase());
+        let last_upper = latin.chars().next_back().is_some_and(|last| last.is_uppercase());
+        if c.is_uppercase() && (next_upper || last_upper) {
+            latin.extend(replacement.chars().flat_map(char::to_uppercase));
+        } else {
+            latin.push_str(replacement);
+        }
+    }
+
+    changed.then_some(latin)
+}
+
+/// Get the Latin replacement for a single character.
+fn latin_char(c: char) -> Option<&'static str> {
+    let replacement = match c {
+        // Cyrillic.
+        'А' => "A",
+        'а' => "a",
+        'Б' => "B",
+        'б' => "b",
+        'В' => "V",
+        'в' => "v",
+        'Г' => "G",
+        'г' => "g",
+        'Ґ' => "G",
+        'ґ' => "g",
+        'Д' => "D",
+        'д' => "d",
+        'Ђ' => "Dj",
+        'ђ' => "dj",
+        'Е' => "E",
+        'е' => "e",
+        'Ё' => "Yo",
+        'ё' => "yo",
+        'Є' => "Ye",
+        'є' => "ye",
+        'Ж' => "Zh",
+        'ж' => "zh",
+        'З' => "Z",
+        'з' => "z",
+        'И' => "I",
+        'и' => "i",
+        'І' => "I",
+        'і' => "i",
+        'Ї' => "Yi",
+        'ї' => "yi",
+        'Й' => "Y",
+        'й' => "y",
+        'Ј' => "J",
+        'ј' => "j",
+        'К' => "K",
+        'к' => "k",
+        'Л' => "L",
+        'л' => "l",
+        'Љ' => "Lj",
+        'љ' => "lj",
+        'М' => "M",
+        'м' => "m",
+        'Н' => "N",
+        'н' => "n",
+        'Њ' => "Nj",
+        'њ' => "nj",
+        'О' => "O",
+        'о' => "o",
+        'П' => "P",
+        'п' => "p",
+        'Р' => "R",
+        'р' => "r",
+        'С' => "S",
+        'с' => "s",
+        'Т' => "T",
+        'т' => "t",
+        'Ћ' => "C",
+        'ћ' => "c",
+        'У' => "U",
+        'у' => "u",
+        'Ў' => "U",
+        'ў' => "u",
+        'Ф' => "F",
+        'ф' => "f",
+        'Х' => "Kh",
+        'х' => "kh",
+        'Ц' => "Ts",
+        'ц' => "ts",
+        'Ч' => "Ch",
+        'ч' => "ch",
+        'Џ' => "Dzh",
+        'џ' => "dzh",
+        'Ш' => "Sh",
+        'ш' => "sh",
+        'Щ' => "Shch",
+        'щ' => "shch",
+        'Ъ' | 'ъ' | 'Ь' | 'ь' => "",
+        'Ы' => "Y",
+        'ы' => "y",
+        'Э' => "E",
+        'э' => "e",
+        'Ю' => "Yu",
+        'ю' => "yu",
+        'Я' => "Ya",
+        'я' => "ya",
+        // Greek.
+        'Α' | 'Ά' => "A",
+        'α' | 'ά' => "a",
+        'Β' => "V",
+        'β' => "v",
+        'Γ' => "G",
+        'γ' => "g",
+        'Δ' => "D",
+        'δ' => "d",
+        'Ε' | 'Έ' => "E",
+        'ε' | 'έ' => "e",
+        'Ζ' => "Z",
+        'ζ' => "z",
+        'Η' | 'Ή' => "I",
+        'η' | 'ή' => "i",
+        'Θ' => "Th",
+        'θ' => "th",
+        'Ι' | 'Ί' | 'Ϊ' => "I",
+        'ι' | 'ί' | 'ϊ' | 'ΐ' => "i",
+        'Κ' => "K",
+        'κ' => "k",
+        'Λ' => "L",
+        'λ' => "l",
+        'Μ' => "M",
+        'μ' => "m",
+        'Ν' => "N",
+        'ν' => "n",
+        'Ξ' => "X",
+        'ξ' => "x",
+        'Ο' | 'Ό' => "O",
+        'ο' | 'ό' => "o",
+        'Π' => "P",
+        'π' => "p",
+        'Ρ' => "R",
+        'ρ' => "r",
+        'Σ' => "S",
+        'σ' | 'ς' => "s",
+        'Τ' => "T",
+        'τ' => "t",
+        'Υ' | 'Ύ' | 'Ϋ' => "Y",
+        'υ' | 'ύ' | 'ϋ' | 'ΰ' => "y",
+        'Φ' => "F",
+        'φ' => "f",
+        'Χ' => "Ch",
+        'χ' => "ch",
+        'Ψ' => "Ps",
+        'ψ' => "ps",
+        'Ω' | 'Ώ' => "O",
+        'ω' | 'ώ' => "o",
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cyrillic() {
+        assert_eq!(to_latin("Москва").as_deref(), Some("Moskva"));
+        assert_eq!(to_latin("Щелково").as_deref(), Some("Shchelkovo"));
+        assert_eq!(to_latin("ЖД вокзал").as_deref(), Some("ZHD vokzal"));
+    }
+
+    #[test]
+    fn greek() {
+        assert_eq!(to_latin("Αθήνα").as_deref(), Some("Athina"));
+        assert_eq!(to_latin("Θεσσαλονίκη").as_deref(), Some("Thessaloniki"));
+    }
+
+    #[test]
+    fn latin_unchanged() {
+        assert_eq!(to_latin("Berlin Hbf"), None);
+        assert_eq!(to_latin("Straße 12"), None);
+    }
+
+    #[test]
+    fn mixed_scripts() {
+        assert_eq!(to_latin("ул. Ленина 5").as_deref(), Some("ul. Lenina 5"));
+    }
+}
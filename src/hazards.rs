@@ -0,0 +1,209 @@
+//! User-supplied hazard points.
+//!
+//! Hazards like speed cameras or dangerous crossings are loaded from either a
+//! CSV file with `latitude,longitude[,name]` lines, or a GeoJSON document
+//! with point features.
+
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::Error;
+use crate::geometry::GeoPoint;
+
+/// Distance factor after which an alerted hazard can alert again.
+///
+/// This avoids repeated alerts due to GPS jitter around the alert distance.
+const REARM_FACTOR: u32 = 2;
+
+/// Hazard location.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Hazard {
+    pub point: GeoPoint,
+    pub name: String,
+}
+
+/// Load hazards from a CSV or GeoJSON file.
+pub async fn load(path: &str) -> Result<Vec<Hazard>, Error> {
+    let content = tokio::fs::read_to_string(path).await?;
+
+    let extension = Path::new(path).extension().and_then(|extension| extension.to_str());
+    match extension {
+        Some("geojson" | "json") => parse_geojson(&content),
+        _ => parse_csv(&content),
+    }
+}
+
+/// Parse hazards from `latitude,longitude[,name]` lines.
+///
+/// Empty lines, comments starting with `#`, and a header line are ignored.
+fn parse_csv(csv: &str) -> Result<Vec<Hazard>, Error> {
+    let mut hazards = Vec::new();
+
+    for (i, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut columns = line.splitn(3, ',').map(str::trim);
+        let lat = columns.next().and_then(|lat| lat.parse::<f64>().ok());
+        let lon = columns.next().and_then(|lon| lon.parse::<f64>().ok());
+        let name = columns.next().unwrap_or_default().trim_matches('"');
+
+        match (lat, lon) {
+            (Some(lat), Some(lon))
+                if (-90. ..=90.).contains(&lat) && (-180. ..=180.).contains(&lon) =>
+            {
+                hazards.push(Hazard { point: GeoPoint::new(lat, lon), name: name.into() });
+            },
+            // Skip column names in the first line.
+            _ if i == 0 => continue,
+            _ => return Err(Error::InvalidHazard(i + 1)),
+        }
+    }
+
+    Ok(hazards)
+}
+
+/// Parse hazards from the point features of a GeoJSON document.
+///
+/// Features with other geometries are ignored. The feature's `name` property
+/// is used as hazard name.
+fn parse_geojson(geojson: &str) -> Result<Vec<Hazard>, Error> {
+    let document: Value = serde_json::from_str(geojson)?;
+
+    let features = match document["type"].as_str() {
+        Some("FeatureCollection") => document["features"].as_array().cloned().unwrap_or_default(),
+        Some("Feature") => vec![document],
+        _ => Vec::new(),
+    };
+
+    let hazards = features
+        .iter()
+        .filter(|feature| feature["geometry"]["type"] == "Point")
+        .filter_map(|feature| {
+            // GeoJSON positions are in longitude, latitude order.
+            let coordinates = feature["geometry"]["coordinates"].as_array()?;
+            let lon = coordinates.first()?.as_f64()?;
+            let lat = coordinates.get(1)?.as_f64()?;
+            let name = feature["properties"]["name"].as_str().unwrap_or_default();
+
+            Some(Hazard { point: GeoPoint::new(lat, lon), name: name.into() })
+        })
+        .collect();
+
+    Ok(hazards)
+}
+
+/// Proximity alerts for hazards.
+#[derive(Default)]
+pub struct HazardAlerts {
+    hazards: Vec<Hazard>,
+    alerted: Vec<bool>,
+}
+
+impl HazardAlerts {
+    /// Replace all hazards.
+    pub fn set_hazards(&mut self, hazards: Vec<Hazard>) {
+        self.alerted = vec![false; hazards.len()];
+        self.hazards = hazards;
+    }
+
+    /// Get all hazards.
+    pub fn hazards(&self) -> &[Hazard] {
+        &self.hazards
+    }
+
+    /// Update the current location.
+    ///
+    /// Returns the closest hazard which just came within `distance` meters.
+    /// Every hazard only alerts once, until the location is far away from it
+    /// again.
+    pub fn update(&mut self, point: GeoPoint, distance: u32) -> Option<&Hazard> {
+        let mut closest: Option<(usize, u32)> = None;
+
+        for (i, hazard) in self.hazards.iter().enumerate() {
+            let hazard_distance = hazard.point.distance(point);
+
+            if hazard_distance > distance.saturating_mul(REARM_FACTOR) {
+                self.alerted[i] = false;
+            } else if hazard_distance <= distance
+                && !self.alerted[i]
+                && closest.is_none_or(|(_, closest)| hazard_distance < closest)
+            {
+                closest = Some((i, hazard_distance));
+            }
+        }
+
+        let (index, _) = closest?;
+        self.alerted[index] = true;
+        Some(&self.hazards[index])
+    }
+
+    /// Allow all hazards to alert again.
+    pub fn reset(&mut self) {
+        self.alerted.fill(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_hazards() {
+        let csv = "lat,lon,name\n\n# Comment\n52.5, 13.4, \"Speed camera\"\n48.1,11.5\n";
+        let hazards = parse_csv(csv).unwrap();
+        assert_eq!(hazards, vec![
+            Hazard { point: GeoPoint::new(52.5, 13.4), name: "Speed camera".into() },
+            Hazard { point: GeoPoint::new(48.1, 11.5), name: String::new() },
+        ]);
+
+        assert!(matches!(parse_csv("52.5,13.4\ninvalid"), Err(Error::InvalidHazard(2))));
+    }
+
+    #[test]
+    fn geojson_hazards() {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {
+                    "type": "Feature",
+                    "geometry": { "type": "Point", "coordinates": [13.4, 52.5] },
+                    "properties": { "name": "Crossing" }
+                },
+                {
+                    "type": "Feature",
+                    "geometry": { "type": "LineString", "coordinates": [[13.4, 52.5], [13.5, 52.6]] },
+                    "properties": {}
+                }
+            ]
+        }"#;
+        let hazards = parse_geojson(geojson).unwrap();
+        assert_eq!(hazards, vec![Hazard {
+            point: GeoPoint::new(52.5, 13.4),
+            name: "Crossing".into()
+        }]);
+    }
+
+    #[test]
+    fn proximity_alerts() {
+        let hazard = GeoPoint::new(52.5, 13.4);
+        let mut alerts = HazardAlerts::default();
+        alerts.set_hazards(vec![Hazard { point: hazard, name: String::new() }]);
+
+        // Alert once when getting close.
+        assert!(alerts.update(hazard.destination(0., 500.), 300).is_none());
+        assert!(alerts.update(hazard.destination(0., 250.), 300).is_some());
+        assert!(alerts.update(hazard.destination(0., 100.), 300).is_none());
+
+        // Jitter around the alert distance does not alert again.
+        assert!(alerts.update(hazard.destination(0., 350.), 300).is_none());
+        assert!(alerts.update(hazard.destination(0., 250.), 300).is_none());
+
+        // Alert again after moving away.
+        assert!(alerts.update(hazard.destination(0., 700.), 300).is_none());
+        assert!(alerts.update(hazard.destination(0., 250.), 300).is_some());
+    }
+}
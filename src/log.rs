@@ -0,0 +1,217 @@
+//! In-memory log history.
+
+use std::collections::VecDeque;
+use std::fmt::{Debug, Write};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// Maximum number of log lines kept in memory.
+const MAX_LINES: usize = 500;
+
+/// Minimum number of decimal places for a number to be considered a
+/// coordinate.
+const MIN_COORDINATE_DECIMALS: usize = 4;
+
+/// Debug output prefix of a tile's index.
+const TILE_INDEX_PREFIX: &str = "TileIndex { ";
+
+/// Number of slash-separated numbers in a tile URL path.
+const TILE_PATH_SEGMENTS: usize = 3;
+
+/// Recent log lines, with the oldest line first.
+static HISTORY: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Tracing layer storing recent log messages in memory.
+pub struct HistoryLayer {
+    start: Instant,
+}
+
+impl HistoryLayer {
+    pub fn new() -> Self {
+        Self { start: Instant::now() }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for HistoryLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+
+        // Format the log message.
+        let mut line = String::new();
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let _ = write!(line, "[{elapsed:.3}] {} {}: ", metadata.level(), metadata.target());
+        event.record(&mut MessageVisitor(&mut line));
+
+        // Add message to history, discarding the oldest line if it is full.
+        let mut history = HISTORY.lock().unwrap_or_else(PoisonError::into_inner);
+        if history.len() >= MAX_LINES {
+            history.pop_front();
+        }
+        history.push_back(line);
+    }
+}
+
+/// Field visitor writing an event's fields to a string.
+struct MessageVisitor<'a>(&'a mut String);
+
+impl Visit for MessageVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.0, "{value:?}");
+        } else {
+            let _ = write!(self.0, " {}={value:?}", field.name());
+        }
+    }
+}
+
+/// Get all recent log lines, with the oldest line first.
+pub fn history() -> Vec<String> {
+    let history = HISTORY.lock().unwrap_or_else(PoisonError::into_inner);
+    history.iter().cloned().collect()
+}
+
+/// Remove personal information from text.
+///
+/// This replaces the user's home directory, all numbers which look like
+/// geographic coordinates, and the location of map tiles.
+pub fn anonymize(text: &str) -> String {
+    let text = match dirs::home_dir().as_ref().and_then(|home| home.to_str()) {
+        Some(home) if !home.is_empty() => text.replace(home, "~"),
+        _ => text.to_owned(),
+    };
+    let text = redact_tile_indices(&text);
+    let text = redact_tile_paths(&text);
+    redact_coordinates(&text)
+}
+
+/// Replace the X and Y coordinates of all tile indices in a text.
+fn redact_tile_indices(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(TILE_INDEX_PREFIX) {
+        let fields_start = start + TILE_INDEX_PREFIX.len();
+        redacted.push_str(&rest[..fields_start]);
+        rest = &rest[fields_start..];
+
+        // Only keep the zoom level, since it does not reveal the tile's location.
+        for field in ["x: ", "y: "] {
+            let Some(value) = rest.strip_prefix(field) else { break };
+            let len = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+            redacted.push_str(field);
+            redacted.push_str("<redacted>");
+            rest = &value[len..];
+
+            if let Some(next_field) = rest.strip_prefix(", ") {
+                redacted.push_str(", ");
+                rest = next_field;
+            }
+        }
+    }
+    redacted.push_str(rest);
+
+    redacted
+}
+
+/// Replace all `z/x/y` tile paths in a text.
+fn redact_tile_paths(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let (prefix, path) = rest.split_at(start);
+        redacted.push_str(prefix);
+
+        // Find the end of the slash-separated numbers.
+        let mut end = 0;
+        let mut segments = 0;
+        loop {
+            let digits = &path[end..];
+            end += digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len());
+            segments += 1;
+
+            let next_digit = path[end..].strip_prefix('/').and_then(|next| next.chars().next());
+            if segments == TILE_PATH_SEGMENTS || !next_digit.is_some_and(|c| c.is_ascii_digit()) {
+                break;
+            }
+            end += 1;
+        }
+
+        if segments == TILE_PATH_SEGMENTS {
+            redacted.push_str("<redacted>");
+        } else {
+            redacted.push_str(&path[..end]);
+        }
+
+        rest = &path[end..];
+    }
+    redacted.push_str(rest);
+
+    redacted
+}
+
+/// Replace all high-precision decimal numbers in a text.
+fn redact_coordinates(text: &str) -> String {
+    let mut redacted = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let (prefix, number) = rest.split_at(start);
+        redacted.push_str(prefix);
+
+        // Find the end of the number, including its decimal places.
+        let integer_end = number.find(|c: char| !c.is_ascii_digit()).unwrap_or(number.len());
+        let mut end = integer_end;
+        let mut decimals = 0;
+        if let Some(fract) = number[integer_end..].strip_prefix('.') {
+            decimals = fract.find(|c: char| !c.is_ascii_digit()).unwrap_or(fract.len());
+            end += 1 + decimals;
+        }
+
+        if decimals >= MIN_COORDINATE_DECIMALS {
+            redacted.push_str("<redacted>");
+        } else {
+            redacted.push_str(&number[..end]);
+        }
+
+        rest = &number[end..];
+    }
+    redacted.push_str(rest);
+
+    redacted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_gps_coordinates() {
+        let text = "GPS location: GeoPoint { lat: 52.516275, lon: -13.37770 }";
+        let expected = "GPS location: GeoPoint { lat: <redacted>, lon: -<redacted> }";
+        assert_eq!(redact_coordinates(text), expected);
+    }
+
+    #[test]
+    fn redact_tile_locations() {
+        let text = "Tile download failed for https://tile.openstreetmap.org/18/140824/85966.png: \
+                    TileIndex { x: 140824, y: 85966, z: 18 }";
+        let expected = "Tile download failed for https://tile.openstreetmap.org/<redacted>.png: \
+                        TileIndex { x: <redacted>, y: <redacted>, z: 18 }";
+        assert_eq!(anonymize(text), expected);
+
+        // Paths with fewer numbers are not tile paths.
+        let text = "Retrying 3/5 downloads";
+        assert_eq!(anonymize(text), text);
+    }
+
+    #[test]
+    fn keep_low_precision_numbers() {
+        let text = "Charon 1.6.0 took 0.25s for 3 tiles at zoom 18.";
+        assert_eq!(redact_coordinates(text), text);
+    }
+}
@@ -4,6 +4,7 @@ use std::{env, process};
 use calloop::timer::{TimeoutAction, Timer};
 use calloop::{EventLoop, LoopHandle, RegistrationToken};
 use calloop_wayland_source::WaylandSource;
+use charon_map::geometry;
 use configory::{Manager as ConfigManager, Options as ConfigOptions};
 #[cfg(feature = "profiling")]
 use profiling::puffin;
@@ -22,23 +23,32 @@ use smithay_client_toolkit::reexports::client::{
 };
 use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers, RepeatInfo};
 use tracing::{error, info};
-use tracing_subscriber::{EnvFilter, FmtSubscriber};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, fmt};
 
 use crate::config::{Config, ConfigEventHandler};
 use crate::db::Db;
+use crate::log::HistoryLayer;
 use crate::ui::window::Window;
 use crate::wayland::{ProtocolStates, TextInput};
 
+mod audio;
 mod config;
 mod db;
 mod dbus;
 mod downloader;
 mod entity_type;
 mod geocoder;
-mod geometry;
+mod hazards;
+mod log;
 mod region;
 mod router;
+mod sharing;
+mod sun;
+mod tilejson;
 mod tiles;
+mod track;
 mod ui;
 mod wayland;
 
@@ -52,7 +62,11 @@ async fn main() {
     // Setup logging.
     let directives = env::var("RUST_LOG").unwrap_or("warn,charon=info,configory=info".into());
     let env_filter = EnvFilter::builder().parse_lossy(directives);
-    FmtSubscriber::builder().with_env_filter(env_filter).with_line_number(true).init();
+    tracing_subscriber::registry()
+        .with(env_filter)
+        .with(fmt::layer().with_line_number(true))
+        .with(HistoryLayer::new())
+        .init();
 
     // Start profiling server.
     #[cfg(feature = "profiling")]
@@ -86,6 +100,23 @@ async fn run() -> Result<(), Error> {
         event_loop.dispatch(None, &mut state)?;
     }
 
+    // Persist the map zoom level of the current output.
+    if let Some((output, zoom)) = state.window.output_zoom()
+        && let Err(err) = state.db.set_output_zoom(&output, zoom).await
+    {
+        error!("Failed to store output zoom level: {err}");
+    }
+
+    // Persist downloaded tiles which are still queued for writing.
+    if let Err(err) = state.window.views.map().tiles().flush_writes().await {
+        error!("Failed to store queued tiles: {err}");
+    }
+
+    // Persist tile access times collected since the last flush.
+    if let Err(err) = state.window.views.map().tiles().flush_access_times().await {
+        error!("Failed to store tile access times: {err}");
+    }
+
     // Ensure database is cleanly terminated.
     state.db.close().await;
 
@@ -128,18 +159,15 @@ impl State {
         let config_options = ConfigOptions::new("charon").notify(true);
         let config_handler = ConfigEventHandler::new(&event_loop);
         let config_manager = ConfigManager::with_options(&config_options, config_handler)?;
-        let config = config_manager
-            .get::<&str, Config>(&[])
-            .inspect_err(|err| error!("Config error: {err}"))
-            .ok()
-            .flatten()
-            .unwrap_or_default();
+        let (config, config_warnings) =
+            Config::from_parsed(config_manager.get::<&str, Config>(&[]));
 
         let db = Db::new()?;
 
         // Create the Wayland window.
-        let window =
+        let mut window =
             Window::new(&event_loop, &protocol_states, connection, queue, config, db.clone())?;
+        window.views.map().set_config_warnings(config_warnings);
 
         Ok(Self {
             protocol_states,
@@ -157,6 +185,18 @@ impl State {
             touch: Default::default(),
         })
     }
+
+    /// Copy a PNG image to the clipboard.
+    fn copy_image(&mut self, png: Vec<u8>) {
+        let serial = self.clipboard.next_serial();
+        let copy_paste_source = self
+            .protocol_states
+            .data_device_manager
+            .create_copy_paste_source(&self.window.queue, [PNG_MIME_TYPE]);
+        copy_paste_source.set_selection(&self.protocol_states.data_device, serial);
+        self.clipboard.source = Some(copy_paste_source);
+        self.clipboard.image = png;
+    }
 }
 
 /// Key status tracking for WlKeyboard.
@@ -273,11 +313,15 @@ impl CurrentRepeat {
     }
 }
 
+/// MIME type of images copied to the clipboard.
+const PNG_MIME_TYPE: &str = "image/png";
+
 /// Clipboard content cache.
 #[derive(Default)]
 struct ClipboardState {
     serial: u32,
     text: String,
+    image: Vec<u8>,
     source: Option<CopyPasteSource>,
 }
 
@@ -344,12 +388,30 @@ enum Error {
     ValhallaTilePrefixMissing,
     #[error("Missing user cache directory")]
     MissingCacheDir,
+    #[error("Missing user download directory")]
+    MissingExportDir,
     #[error("Unexpected root path")]
     UnexpectedRoot,
     #[error("Invalid offline tile map archive")]
     InvalidTileArchive,
     #[error("Unexpected non-utf8 codepoint in path")]
     NonUtf8Path,
+    #[error("Database is read-only while it is in use by another process")]
+    DatabaseReadOnly,
+    #[error("Tileserver downloads are suspended after repeated failures")]
+    TileserverUnavailable,
+    #[error("Tileserver rejected the request, check its API key")]
+    TileserverRejected,
+    #[error("Tile does not exist on the tileserver")]
+    TileNotFound,
+    #[error("TileJSON {0:?} describes vector tiles, only raster tiles are supported")]
+    TileJsonVector(String),
+    #[error("TileJSON {0:?} has no tile URLs")]
+    TileJsonMissingTiles(String),
+    #[error("Checksum mismatch for download {0:?}")]
+    ChecksumMismatch(String),
+    #[error("Invalid hazard in line {0}")]
+    InvalidHazard(usize),
 }
 
 impl<T> From<calloop::InsertError<T>> for Error {
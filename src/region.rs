@@ -1,13 +1,15 @@
 //! Geographic region management.
 
 use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::fs::File as StdFile;
 use std::io::Write;
 use std::marker::Unpin;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU8, AtomicU64, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
 use async_compression::tokio::bufread::GzipDecoder;
 use bzip2::write::BzDecoder;
@@ -17,18 +19,21 @@ use indexmap::IndexMap;
 use reqwest::Client;
 use reqwest::header::CONTENT_LENGTH;
 use serde::Deserialize;
+use sha2::{Digest, Sha256};
 use smallvec::SmallVec;
 use sqlx::QueryBuilder;
 use tempfile::NamedTempFile;
-use tokio::fs;
 use tokio::fs::File;
 use tokio::io::{self, AsyncRead, AsyncReadExt, BufReader};
-use tokio::task::JoinSet;
+use tokio::sync::mpsc;
+use tokio::task::{self, JoinSet};
+use tokio::{fs, time};
 use tokio_stream::StreamExt;
 use tokio_tar::{Archive, Entry};
 use tracing::{debug, error, warn};
 
 use crate::db::Db;
+use crate::geometry::GeoPoint;
 use crate::tiles::TileIndex;
 use crate::{Error, State};
 
@@ -53,6 +58,14 @@ const POSTAL_GLOBAL_FILES: &[&str] = &[
     "numex/numex.dat",
 ];
 
+/// Interval between download speed samples.
+const SPEED_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+/// Number of samples used for the rolling download speed average.
+const SPEED_WINDOW_SAMPLES: usize = 5;
+
+/// Maximum number of downloaded chunks waiting for decompression.
+const DECOMPRESSION_QUEUE_SIZE: usize = 32;
+
 /// SmallVec wrapper for storing up to 100 values on the stack.
 ///
 /// The most amount of subregions for any region is under 60, so with a stack
@@ -63,12 +76,18 @@ type OfflineTilesVec<T> = SmallVec<[T; 100]>;
 /// Region data management.
 pub struct Regions {
     data: RegionData,
+    custom_data: OnceLock<RegionData>,
+    index_loading: AtomicBool,
+    index_url: Arc<String>,
+    orphans_size: AtomicU64,
 
+    index_cache_path: PathBuf,
     geocoder_cache_dir: PathBuf,
     valhalla_cache_dir: PathBuf,
     postal_cache_dir: PathBuf,
 
-    router_reloader: Ping,
+    dataset_reloader: Ping,
+    index_reloader: Ping,
     ui_waker: Ping,
     client: Client,
     db: Db,
@@ -80,12 +99,14 @@ impl Regions {
         event_loop: LoopHandle<'static, State>,
         client: Client,
         db: Db,
+        index_url: Arc<String>,
     ) -> Result<Arc<Self>, Error> {
         // Deserialize region data generated at compile time.
         let data = RegionData::new()?;
 
         // Get cache storage locations.
         let cache_dir = dirs::cache_dir().ok_or(Error::MissingCacheDir)?.join("charon");
+        let index_cache_path = cache_dir.join("regions.json");
         let geocoder_cache_dir = cache_dir.join("geocoder");
         let valhalla_cache_dir = cache_dir.join("valhalla");
         let postal_cache_dir = cache_dir.join("postal");
@@ -97,31 +118,58 @@ impl Regions {
             state.window.unstall();
         })?;
 
-        // Register ping source to allow restarting Valhalla offline geocoder.
-        let (router_reloader, source) = ping::make_ping()?;
+        // Register ping source to allow reloading offline geocoder and router data.
+        let (dataset_reloader, source) = ping::make_ping()?;
         event_loop.insert_source(source, |_, _, state| {
-            state.window.views.search().router_mut().reload_offline_router();
+            let search_view = state.window.views.search();
+            search_view.router_mut().reload_offline_router();
+            search_view.geocoder_mut().reload_offline_datasets();
+        })?;
+
+        // Register ping source to reset the UI after loading a custom region index.
+        let (index_reloader, source) = ping::make_ping()?;
+        event_loop.insert_source(source, |_, _, state| {
+            state.window.views.download().reset_region();
+            state.window.unstall();
         })?;
 
         let regions = Arc::new(Self {
             geocoder_cache_dir,
             valhalla_cache_dir,
+            index_cache_path,
             postal_cache_dir,
-            router_reloader,
+            dataset_reloader,
+            index_reloader,
             ui_waker,
             client,
             data,
             db,
+            index_loading: AtomicBool::new(!index_url.is_empty()),
+            orphans_size: Default::default(),
+            custom_data: Default::default(),
+            index_url,
         });
 
         // Update region's download state from FS.
         let init_regions = regions.clone();
         tokio::spawn(async move {
+            // Replace compiled-in regions with the last user-provided index, without
+            // waiting for the network.
+            if !init_regions.index_url.is_empty() {
+                init_regions.load_cached_index().await;
+            }
+
             init_regions.refresh_download_state().await;
 
             // Start initial Valhalla offline router.
             if init_regions.world().has_valhalla_tiles() {
-                init_regions.router_reloader.ping();
+                init_regions.dataset_reloader.ping();
+            }
+
+            // Update the user-provided index in the background.
+            if !init_regions.index_url.is_empty() {
+                init_regions.update_index().await;
+                init_regions.index_loading.store(false, Ordering::Relaxed);
             }
         });
 
@@ -130,14 +178,111 @@ impl Regions {
 
     /// Get the root region.
     pub fn world(&self) -> &Region {
-        &self.data.world_region
+        &self.data().world_region
+    }
+
+    /// Check if region downloads and deletions are unavailable.
+    pub fn read_only(&self) -> bool {
+        self.db.read_only()
+    }
+
+    /// Get the active region data.
+    ///
+    /// This prefers the user-provided region index once it has been loaded.
+    fn data(&self) -> &RegionData {
+        self.custom_data.get().unwrap_or(&self.data)
+    }
+
+    /// Retry loading the custom region index in the background.
+    ///
+    /// This does nothing if the index was already loaded or no custom index is
+    /// configured.
+    pub fn reload_index(self: &Arc<Self>) {
+        if self.index_url.is_empty()
+            || self.custom_data.get().is_some()
+            || self.index_loading.swap(true, Ordering::Relaxed)
+        {
+            return;
+        }
+
+        let regions = self.clone();
+        tokio::spawn(async move {
+            regions.update_index().await;
+            regions.index_loading.store(false, Ordering::Relaxed);
+        });
+    }
+
+    /// Activate the last downloaded copy of the custom region index.
+    async fn load_cached_index(&self) {
+        let cached = match fs::read(&self.index_cache_path).await {
+            Ok(cached) => cached,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return,
+            Err(err) => {
+                error!("Failed to read cached region index: {err}");
+                return;
+            },
+        };
+
+        match serde_json::from_slice(&cached) {
+            Ok(data) => {
+                let _ = self.custom_data.set(data);
+                self.index_reloader.ping();
+            },
+            Err(err) => error!("Invalid cached region index: {err}"),
+        }
+    }
+
+    /// Download the custom region index.
+    ///
+    /// The index uses the same schema as the compiled-in region data and can
+    /// only be activated once, so if a cached copy is already in use, the
+    /// downloaded index is only applied after a restart.
+    async fn update_index(&self) {
+        let data = match self.download_index().await {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("Failed to download region index {:?}: {err}", self.index_url);
+                return;
+            },
+        };
+
+        if self.custom_data.set(data).is_ok() {
+            self.index_reloader.ping();
+            self.refresh_download_state().await;
+            self.dataset_reloader.ping();
+        }
+    }
+
+    /// Download the custom region index, caching it for offline use.
+    async fn download_index(&self) -> Result<RegionData, Error> {
+        let response = self.client.get(&*self.index_url).send().await?.error_for_status()?;
+        let bytes = response.bytes().await?;
+        let data = serde_json::from_slice(&bytes)?;
+
+        if let Some(parent) = self.index_cache_path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        fs::write(&self.index_cache_path, &bytes).await?;
+
+        Ok(data)
     }
 
     /// Download a region's data to the local cache.
     pub async fn download(&self, region: &Region) -> Result<(), Error> {
+        // Downloaded data is tracked in the database, so it must be writable.
+        if self.db.read_only() {
+            return Err(Error::DatabaseReadOnly);
+        }
+
         let mut downloads: JoinSet<Result<_, Error>> = JoinSet::new();
         let tracker = region.download_tracker(self.ui_waker.clone());
 
+        // Track download speed until all downloads are done.
+        //
+        // Dropping the JoinSet aborts the sampling task.
+        let mut speed_sampler = JoinSet::new();
+        speed_sampler.spawn(tracker.clone().sample_speed());
+
         // Download geocoder files.
         if let Some((geocoder_path, region_name)) = region.geocoder_uri_path() {
             for file in GEOCODER_FILES {
@@ -147,18 +292,20 @@ impl Regions {
                     continue;
                 }
 
-                let url = format!("{}/{geocoder_path}/{file}.bz2", self.data.geocoder_base);
+                let url = format!("{}/{geocoder_path}/{file}.bz2", self.data().geocoder_base);
+                let checksum = self.checksum(&url);
                 let client = self.client.clone();
                 let tracker = tracker.clone();
                 downloads.spawn(async move {
-                    Self::persist_bz2_download(client, tracker, &url, &path).await
+                    Self::persist_bz2_download(client, tracker, &url, checksum, &path).await
                 });
             }
         }
 
         // Download Valhalla files.
         for package in &region.valhalla_packages {
-            let url = format!("{}/{package}.tar.bz2", self.data.valhalla_base);
+            let url = format!("{}/{package}.tar.bz2", self.data().valhalla_base);
+            let checksum = self.checksum(&url);
 
             let cache_dir = self.valhalla_cache_dir.clone();
             let client = self.client.clone();
@@ -166,7 +313,11 @@ impl Regions {
             let package = package.clone();
             let db = self.db.clone();
             downloads.spawn(async move {
-                Self::extract_valhalla_tiles(db, client, tracker, &url, &cache_dir, &package).await
+                let checksum = checksum.as_deref();
+                Self::extract_valhalla_tiles(
+                    db, client, tracker, &url, checksum, &cache_dir, &package,
+                )
+                .await
             });
         }
 
@@ -183,12 +334,13 @@ impl Regions {
 
                 let url = format!(
                     "{}/{postal_path}/address_parser/{file}.bz2",
-                    self.data.postal_country_base
+                    self.data().postal_country_base
                 );
+                let checksum = self.checksum(&url);
                 let client = self.client.clone();
                 let tracker = tracker.clone();
                 downloads.spawn(async move {
-                    Self::persist_bz2_download(client, tracker, &url, &path).await
+                    Self::persist_bz2_download(client, tracker, &url, checksum, &path).await
                 });
             }
 
@@ -200,11 +352,12 @@ impl Regions {
                     continue;
                 }
 
-                let url = format!("{}/{file}.bz2", self.data.postal_global_base);
+                let url = format!("{}/{file}.bz2", self.data().postal_global_base);
+                let checksum = self.checksum(&url);
                 let client = self.client.clone();
                 let tracker = tracker.clone();
                 downloads.spawn(async move {
-                    Self::persist_bz2_download(client, tracker, &url, &path).await
+                    Self::persist_bz2_download(client, tracker, &url, checksum, &path).await
                 });
             }
         }
@@ -220,9 +373,6 @@ impl Regions {
             result??;
         }
 
-        // Load new Valhalla routing tiles.
-        self.router_reloader.ping();
-
         Ok(())
     }
 
@@ -231,6 +381,12 @@ impl Regions {
     /// This never removes the global postal data, since it's required to make
     /// search work with any region.
     pub async fn delete(&self, region: &Region) {
+        // Installed data is tracked in the database, so it must be writable.
+        if self.db.read_only() {
+            error!("Failed to delete region {:?}: {}", region.name, Error::DatabaseReadOnly);
+            return;
+        }
+
         // Delete geocoder data.
         if let Some((_, region_name)) = region.geocoder_uri_path() {
             let path = self.geocoder_cache_dir.join(region_name);
@@ -241,31 +397,9 @@ impl Regions {
 
         // Delete Valhalla packages, if they're not required by another region.
         for package in &region.valhalla_packages {
-            if self.world().requires_valhalla_package(package, &region.name) {
-                continue;
-            }
-
-            let package_paths = match self.valhalla_package_paths(package).await {
-                Ok(package_paths) => package_paths,
-                Err(err) => {
-                    error!("Failed to load Valhalla package paths for {package:?}: {err}");
-                    continue;
-                },
-            };
-
-            // Delete individual files, keeping the directories.
-            for path in package_paths {
-                if let Err(err) = fs::remove_file(&path).await {
-                    error!("Failed to delete {path:?}: {err}");
-                }
+            if !self.world().requires_valhalla_package(package, &region.name) {
+                self.delete_valhalla_package(package).await;
             }
-
-            // Delete package paths from DB.
-            let _ = sqlx::query("DELETE FROM valhalla_packages WHERE package = $1")
-                .bind(package)
-                .execute(self.db.pool().await)
-                .await
-                .inspect_err(|err| error!("Failed to remove Valhalla package from DB: {err}"));
         }
 
         // Delete offline map tiles for regions which aren't separately installed.
@@ -291,6 +425,136 @@ impl Regions {
         }
     }
 
+    /// Delete a Valhalla package's tiles and remove it from the database.
+    async fn delete_valhalla_package(&self, package: &str) {
+        let package_paths = match self.valhalla_package_paths(package).await {
+            Ok(package_paths) => package_paths,
+            Err(err) => {
+                error!("Failed to load Valhalla package paths for {package:?}: {err}");
+                return;
+            },
+        };
+
+        // Delete individual files, keeping the directories.
+        for path in package_paths {
+            if let Err(err) = fs::remove_file(&path).await {
+                error!("Failed to delete {path:?}: {err}");
+            }
+        }
+
+        // Delete package paths from DB.
+        let pool = match self.db.write_pool().await {
+            Ok(pool) => pool,
+            Err(err) => {
+                error!("Failed to remove Valhalla package from DB: {err}");
+                return;
+            },
+        };
+        let _ = sqlx::query("DELETE FROM valhalla_packages WHERE package = $1")
+            .bind(package)
+            .execute(&pool)
+            .await
+            .inspect_err(|err| error!("Failed to remove Valhalla package from DB: {err}"));
+    }
+
+    /// Get the storage size of data not used by any installed region.
+    pub fn orphans_size(&self) -> u64 {
+        self.orphans_size.load(Ordering::Relaxed)
+    }
+
+    /// Delete all data not used by any installed region.
+    pub async fn delete_orphans(&self) {
+        // Search for orphans again, in case a download was started since the last scan.
+        let orphans = match self.orphans().await {
+            Ok(orphans) => orphans,
+            Err(err) => {
+                error!("Failed to find orphaned region data: {err}");
+                return;
+            },
+        };
+
+        for path in orphans.dirs {
+            if let Err(err) = fs::remove_dir_all(&path).await {
+                error!("Failed to delete {path:?}: {err}");
+            }
+        }
+
+        for package in orphans.valhalla_packages {
+            self.delete_valhalla_package(&package).await;
+        }
+
+        self.refresh_orphans().await;
+    }
+
+    /// Update the storage size of data not used by any installed region.
+    async fn refresh_orphans(&self) {
+        match self.orphans().await {
+            Ok(orphans) => self.orphans_size.store(orphans.size, Ordering::Relaxed),
+            Err(err) => error!("Failed to find orphaned region data: {err}"),
+        }
+
+        // Ensure UI is updated on cleanup size changes.
+        self.ui_waker.ping();
+    }
+
+    /// Find data not used by any installed region.
+    ///
+    /// This includes leftovers from interrupted downloads, and data from
+    /// regions which no longer exist in the region index.
+    ///
+    /// No data is considered orphaned until the custom region index is loaded,
+    /// since its regions are unknown to the compiled-in region data.
+    async fn orphans(&self) -> Result<Orphans, Error> {
+        if !self.index_url.is_empty() && self.custom_data.get().is_none() {
+            return Ok(Orphans::default());
+        }
+
+        let mut datasets = Datasets::default();
+        self.world().used_datasets(&mut datasets);
+
+        let mut orphans = Orphans::default();
+
+        // Find unused geocoder and postal country directories.
+        let postal_countries_dir = self.postal_cache_dir.join("countries");
+        for (dir, used) in [
+            (&self.geocoder_cache_dir, &datasets.geocoder),
+            (&postal_countries_dir, &datasets.postal),
+        ] {
+            let mut entries = match fs::read_dir(dir).await {
+                Ok(entries) => entries,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name();
+                if name.to_str().is_none_or(|name| !used.contains(name)) {
+                    let path = entry.path();
+                    orphans.size += dir_size(&path).await;
+                    orphans.dirs.push(path);
+                }
+            }
+        }
+
+        // Find Valhalla packages in the database which are not used by any region.
+        let packages: Vec<String> =
+            sqlx::query_scalar("SELECT DISTINCT package FROM valhalla_packages")
+                .fetch_all(&self.db.pool().await)
+                .await?;
+        for package in packages {
+            if datasets.valhalla.contains(package.as_str()) {
+                continue;
+            }
+
+            for path in self.valhalla_package_paths(&package).await? {
+                orphans.size += fs::metadata(&path).await.map_or(0, |metadata| metadata.len());
+            }
+            orphans.valhalla_packages.push(package);
+        }
+
+        Ok(orphans)
+    }
+
     /// Recursively update download status based on current filesystem state.
     async fn refresh_download_state(&self) {
         // Check if global postal files are installed.
@@ -306,8 +570,10 @@ impl Regions {
             )
             .await;
 
-        // Ensure UI is updated on download state changes.
-        self.ui_waker.ping();
+        // Look for leftovers, now that all installed regions are known.
+        //
+        // This also wakes the UI to show the download state changes.
+        self.refresh_orphans().await;
     }
 
     /// Get the postal global file storage path.
@@ -332,16 +598,33 @@ impl Regions {
         &self.valhalla_cache_dir
     }
 
+    /// Reload offline geocoder and router data.
+    ///
+    /// This should be called whenever a region's data was installed or removed.
+    pub fn reload_datasets(&self) {
+        self.dataset_reloader.ping();
+    }
+
     /// Unstall UI and mark the download view as dirty.
     pub fn redraw_download_view(&self) {
         self.ui_waker.ping();
     }
 
+    /// Get the expected SHA-256 checksum of a download.
+    fn checksum(&self, url: &str) -> Option<String> {
+        let checksum = self.data().checksums.get(url).cloned();
+        if checksum.is_none() {
+            warn!("Missing checksum for {url:?}, skipping verification");
+        }
+        checksum
+    }
+
     /// Download a .bz2 file from `url` and decompress it to `path`.
     async fn persist_bz2_download(
         client: Client,
         tracker: DownloadTracker,
         url: &str,
+        checksum: Option<String>,
         path: &Path,
     ) -> Result<(), Error> {
         // Create tempfile to write the data to.
@@ -349,7 +632,7 @@ impl Regions {
         tokio::fs::create_dir_all(&parent).await?;
         let mut file = NamedTempFile::new_in(parent)?;
 
-        Self::download_bz2(client, &tracker, url, file.as_file_mut()).await?;
+        Self::download_bz2(client, &tracker, url, checksum.as_deref(), file.as_file_mut()).await?;
 
         // Atomically persist the tempfile to its target location.
         file.persist(path)?;
@@ -358,15 +641,20 @@ impl Regions {
     }
 
     /// Download a .bz2 file from `url` and decompress it into `file`.
+    ///
+    /// Decompression runs on the blocking thread pool while the download is
+    /// still in progress.
+    ///
+    /// If a checksum is provided, the compressed data is verified against it.
+    /// Since the decompressed data is written before verification, `file` must
+    /// be discarded on error.
     async fn download_bz2(
         client: Client,
         tracker: &DownloadTracker,
         url: &str,
+        checksum: Option<&str>,
         file: &mut StdFile,
     ) -> Result<(), Error> {
-        // Create a streaming decoder into the file.
-        let mut decoder = BzDecoder::new(file);
-
         // Send download request.
         let mut response = client.get(url).send().await?.error_for_status()?;
 
@@ -377,16 +665,37 @@ impl Regions {
             .and_then(|h| h.to_str().ok()?.parse().ok())
             .unwrap_or(0);
         tracker.add_download(content_length);
+        tracker.add_install(content_length);
+
+        // Create a streaming decoder into the file.
+        let (chunk_tx, mut chunk_rx) = mpsc::channel(DECOMPRESSION_QUEUE_SIZE);
+        let mut decoder = BzDecoder::new(file.try_clone()?);
+        let decoder_tracker = tracker.clone();
+        let decompression = task::spawn_blocking(move || -> Result<(), Error> {
+            while let Some(chunk) = chunk_rx.blocking_recv() {
+                decoder.write_all(&chunk)?;
+                decoder_tracker.add_install_progress(chunk.len() as u64);
+            }
+            decoder.finish()?;
+            Ok(())
+        });
 
         // Stream data through the decoder into the tempfile.
+        let mut hasher = Sha256::new();
         while let Some(chunk) = response.chunk().await? {
             tracker.add_progress(chunk.len() as u64);
-            decoder.write_all(&chunk)?;
+            hasher.update(&chunk);
+
+            // Stop download if decompression has failed.
+            if chunk_tx.send(chunk).await.is_err() {
+                break;
+            }
         }
-        decoder.finish()?;
-        drop(decoder);
+        drop(chunk_tx);
 
-        Ok(())
+        decompression.await??;
+
+        verify_checksum(url, checksum, hasher)
     }
 
     /// Download and import raster map tiles.
@@ -405,12 +714,14 @@ impl Regions {
 
         // Immediately add progress tracking for tile decompression/DB inserts.
         for (region, _) in &offline_tiles {
-            tracker.add_download(region.tiles_size);
+            tracker.add_install(region.tiles_size);
         }
 
         // Collect relevant tile data which can be sent across threads.
-        let offline_tiles: OfflineTilesVec<_> =
-            offline_tiles.into_iter().map(|(region, url)| (region.id, url)).collect();
+        let offline_tiles: OfflineTilesVec<_> = offline_tiles
+            .into_iter()
+            .map(|(region, url)| (region.id, self.checksum(&url), url))
+            .collect();
 
         let client = self.client.clone();
         let tracker = tracker.clone();
@@ -418,11 +729,12 @@ impl Regions {
 
         downloads.spawn(async move {
             // Initially, stage all archives as pending downloads.
-            let pending_downloads = offline_tiles.into_iter().map(|(region_id, url)| {
+            let pending_downloads = offline_tiles.into_iter().map(|(region_id, checksum, url)| {
                 let tracker = tracker.clone();
                 let client = client.clone();
                 async move {
-                    let tempfile = Self::download_map_archive(client, tracker, url).await?;
+                    let tempfile =
+                        Self::download_map_archive(client, tracker, url, checksum).await?;
                     Ok(Some((region_id, tempfile)))
                 }
             });
@@ -488,6 +800,7 @@ impl Regions {
         client: Client,
         tracker: DownloadTracker,
         url: Arc<String>,
+        checksum: Option<String>,
     ) -> Result<NamedTempFile, Error> {
         // NOTE: We stream to a file here which is technically pointless and will just
         // slow us down, but unfortunately Rust does not have a mature tar library that
@@ -509,11 +822,15 @@ impl Regions {
         let mut write_tempfile = File::create(tempfile.path()).await?;
 
         // Stream data to file.
+        let mut hasher = Sha256::new();
         while let Some(chunk) = response.chunk().await? {
             tracker.add_progress(chunk.len() as u64);
+            hasher.update(&chunk);
             io::copy(&mut &*chunk, &mut write_tempfile).await?;
         }
 
+        verify_checksum(&url, checksum.as_deref(), hasher)?;
+
         Ok(tempfile)
     }
 
@@ -550,7 +867,7 @@ impl Regions {
                 parse_tar_tile_file_name(entry.path()?).ok_or(Error::InvalidTileArchive)?;
             let mut tile_data = Vec::new();
             entry.read_to_end(&mut tile_data).await?;
-            tracker.add_progress(tile_data.len() as u64);
+            tracker.add_install_progress(tile_data.len() as u64);
             batch.push((tile_index, tile_data));
         }
 
@@ -568,29 +885,46 @@ impl Regions {
         client: Client,
         tracker: DownloadTracker,
         url: &str,
+        checksum: Option<&str>,
         valhalla_cache_dir: &Path,
         package: &str,
     ) -> Result<(), Error> {
         // Download and decompress the Valhalla archive.
+        //
+        // The archive is verified before extraction, so no tiles are written on
+        // checksum mismatch.
         let mut tempfile = NamedTempFile::new()?;
-        Self::download_bz2(client, &tracker, url, tempfile.as_file_mut()).await?;
+        Self::download_bz2(client, &tracker, url, checksum, tempfile.as_file_mut()).await?;
 
         // Reopen tempfile to create archive reader from the start.
         let mut archive_file = File::open(tempfile.path()).await?;
+        let archive_size = archive_file.metadata().await?.len();
         let mut archive = Archive::new(&mut archive_file);
 
+        // Track extraction separately, since it only starts after decompression.
+        tracker.add_install(archive_size);
+        let mut extracted = 0;
+
         let mut paths = Vec::new();
         let mut entries = archive.entries()?;
         while let Some(entry) = entries.next().await {
             let entry = entry?;
 
+            let entry_size = entry.header().size().unwrap_or(0).min(archive_size - extracted);
+            extracted += entry_size;
+
             // Copy the file from the archive to its target location.
             if let Some(path) = Self::extract_valhalla_tile(valhalla_cache_dir, entry).await? {
                 let path_str = path.to_str().ok_or(Error::NonUtf8Path)?;
                 paths.push(path_str.to_string());
             }
+
+            tracker.add_install_progress(entry_size);
         }
 
+        // Account for archive headers and padding.
+        tracker.add_install_progress(archive_size - extracted);
+
         // Store package <-> path relationships in DB.
         if !paths.is_empty() {
             let mut builder = QueryBuilder::new("INSERT INTO valhalla_packages (package, path) ");
@@ -599,7 +933,7 @@ impl Regions {
                 builder.push_bind(path);
             });
             builder.push(" ON CONFLICT DO NOTHING ");
-            builder.build().execute(db.pool().await).await?;
+            builder.build().execute(&db.write_pool().await?).await?;
         }
 
         Ok(())
@@ -640,12 +974,15 @@ impl Regions {
     async fn valhalla_package_paths(&self, package: &str) -> Result<Vec<String>, Error> {
         Ok(sqlx::query_scalar("SELECT path FROM valhalla_packages WHERE package = $1")
             .bind(package)
-            .fetch_all(self.db.pool().await)
+            .fetch_all(&self.db.pool().await)
             .await?)
     }
 }
 
-/// Region data generated at compile time.
+/// Region index data.
+///
+/// This is generated at compile time, but can be replaced at runtime with a
+/// user-provided index.
 #[derive(Deserialize)]
 struct RegionData {
     world_region: Region,
@@ -654,6 +991,10 @@ struct RegionData {
     postal_global_base: String,
     valhalla_base: String,
     geocoder_base: String,
+
+    /// SHA-256 checksums of downloadable archives, indexed by URL.
+    #[serde(default)]
+    checksums: HashMap<String, String>,
 }
 
 impl RegionData {
@@ -662,6 +1003,21 @@ impl RegionData {
     }
 }
 
+/// Compare a download's SHA-256 hash against its expected checksum.
+fn verify_checksum(url: &str, checksum: Option<&str>, hasher: Sha256) -> Result<(), Error> {
+    let checksum = match checksum {
+        Some(checksum) => checksum,
+        None => return Ok(()),
+    };
+
+    let hash: String = hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect();
+    if !hash.eq_ignore_ascii_case(checksum.trim()) {
+        return Err(Error::ChecksumMismatch(url.into()));
+    }
+
+    Ok(())
+}
+
 /// Data for a geographic region.
 #[derive(Deserialize, Debug)]
 pub struct Region {
@@ -675,6 +1031,7 @@ pub struct Region {
     geocoder_path: Option<String>,
     postal_path: Option<String>,
     tiles_url: Option<Arc<String>>,
+    bounds: Option<RegionBounds>,
     tiles_size: u64,
 
     #[serde(skip)]
@@ -683,6 +1040,12 @@ pub struct Region {
     download_pending: Arc<AtomicU64>,
     #[serde(skip)]
     download_done: Arc<AtomicU64>,
+    #[serde(skip)]
+    install_pending: Arc<AtomicU64>,
+    #[serde(skip)]
+    install_done: Arc<AtomicU64>,
+    #[serde(skip)]
+    download_speed: Arc<AtomicU64>,
 }
 
 impl Region {
@@ -697,6 +1060,9 @@ impl Region {
         if download_state == DownloadState::Downloading {
             self.download_pending.store(0, Ordering::Relaxed);
             self.download_done.store(0, Ordering::Relaxed);
+            self.install_pending.store(0, Ordering::Relaxed);
+            self.install_done.store(0, Ordering::Relaxed);
+            self.download_speed.store(0, Ordering::Relaxed);
         }
 
         self.download_state.store(download_state as u8, Ordering::Relaxed);
@@ -709,6 +1075,26 @@ impl Region {
         if pending == 0 { 0. } else { (done as f64 / pending as f64).min(1.) }
     }
 
+    /// Get current download speed in bytes per second.
+    pub fn download_speed(&self) -> Option<u64> {
+        Some(self.download_speed.load(Ordering::Relaxed)).filter(|speed| *speed > 0)
+    }
+
+    /// Get the estimated time until all pending data is downloaded.
+    pub fn download_eta(&self) -> Option<Duration> {
+        let pending = self.download_pending.load(Ordering::Relaxed);
+        let done = self.download_done.load(Ordering::Relaxed);
+        let speed = self.download_speed()?;
+        Some(Duration::from_secs(pending.saturating_sub(done).div_ceil(speed)))
+    }
+
+    /// Get current progress of decompression and extraction.
+    pub fn install_progress(&self) -> f64 {
+        let pending = self.install_pending.load(Ordering::Relaxed);
+        let done = self.install_done.load(Ordering::Relaxed);
+        if pending == 0 { 0. } else { (done as f64 / pending as f64).min(1.) }
+    }
+
     /// Get the current install size in bytes.
     pub fn current_install_size(&self) -> u64 {
         let mut size = 0;
@@ -737,7 +1123,7 @@ impl Region {
 
     /// Execute a function for all installed child regions.
     #[cfg_attr(feature = "profiling", profiling::function)]
-    pub fn for_installed(&self, f: &mut impl FnMut(&Self)) {
+    pub fn for_installed<'a>(&'a self, f: &mut impl FnMut(&'a Self)) {
         if self.is_installed() {
             f(self);
         }
@@ -747,6 +1133,81 @@ impl Region {
         }
     }
 
+    /// Get the geographic bounding box of this region.
+    pub fn bounds(&self) -> Option<RegionBounds> {
+        self.bounds
+    }
+
+    /// Check whether a point is within this region's bounds.
+    pub fn contains(&self, point: GeoPoint) -> bool {
+        self.bounds.is_some_and(|bounds| bounds.contains(point))
+    }
+
+    /// Get the index path of the bottommost region containing a point.
+    ///
+    /// Since regions are only stored as bounding boxes, neighboring regions
+    /// might overlap. In that case the region with the smallest bounds is
+    /// returned.
+    ///
+    /// Like the download view's region path, unused indices are set to
+    /// `usize::MAX`.
+    pub fn leaf_index_at(&self, point: GeoPoint) -> Option<[usize; 5]> {
+        let mut best = None;
+        self.smallest_leaf_at(point, &mut [usize::MAX; 5], 0, &mut best);
+        best.map(|(_, index)| index)
+    }
+
+    /// Get the uppercase ISO 3166-1 country code at a point.
+    pub fn country_code_at(&self, point: GeoPoint) -> Option<&str> {
+        let index = self.leaf_index_at(point)?;
+
+        // Use the most specific region with a postal country.
+        let mut region = self;
+        let mut country_code = None;
+        for i in index.iter().take_while(|i| **i != usize::MAX) {
+            region = &region.regions[*i];
+            if let Some((_, code)) = region.postal_uri_path() {
+                country_code = Some(code);
+            }
+        }
+
+        country_code
+    }
+
+    /// Recursively find the smallest leaf region containing a point.
+    fn smallest_leaf_at(
+        &self,
+        point: GeoPoint,
+        index: &mut [usize; 5],
+        depth: usize,
+        best: &mut Option<(f64, [usize; 5])>,
+    ) {
+        // Ignore regions beyond the maximum supported depth.
+        if depth >= index.len() {
+            return;
+        }
+
+        for (i, region) in self.regions.values().enumerate() {
+            // Skip regions which definitely don't contain the point.
+            if region.bounds.is_some_and(|bounds| !bounds.contains(point)) {
+                continue;
+            }
+
+            index[depth] = i;
+
+            if !region.regions.is_empty() {
+                region.smallest_leaf_at(point, index, depth + 1, best);
+            } else if let Some(bounds) = region.bounds {
+                let area = bounds.area();
+                if best.is_none_or(|(best_area, _)| area < best_area) {
+                    *best = Some((area, *index));
+                }
+            }
+
+            index[depth] = usize::MAX;
+        }
+    }
+
     /// Recursively update download status based on current filesystem state.
     async fn refresh_download_state(
         &self,
@@ -814,7 +1275,7 @@ impl Region {
             let result =
                 sqlx::query("SELECT region_id FROM offline_tile WHERE region_id = $1 LIMIT 1")
                     .bind(region_id)
-                    .fetch_optional(db.pool().await)
+                    .fetch_optional(&db.pool().await)
                     .await;
 
             match result {
@@ -838,7 +1299,7 @@ impl Region {
             let paths: Result<Vec<String>, _> =
                 sqlx::query_scalar("SELECT path FROM valhalla_packages WHERE package = $1")
                     .bind(package)
-                    .fetch_all(db.pool().await)
+                    .fetch_all(&db.pool().await)
                     .await;
 
             match paths {
@@ -867,6 +1328,9 @@ impl Region {
             ui_waker,
             download_pending: self.download_pending.clone(),
             download_done: self.download_done.clone(),
+            install_pending: self.install_pending.clone(),
+            install_done: self.install_done.clone(),
+            download_speed: self.download_speed.clone(),
         }
     }
 
@@ -904,6 +1368,24 @@ impl Region {
         Some((geocoder_path, region))
     }
 
+    /// Collect datasets of this region and its children which are installed or
+    /// currently being downloaded.
+    fn used_datasets<'a>(&'a self, datasets: &mut Datasets<'a>) {
+        if matches!(self.download_state(), DownloadState::Downloaded | DownloadState::Downloading) {
+            if let Some((_, region_name)) = self.geocoder_uri_path() {
+                datasets.geocoder.insert(region_name);
+            }
+            if let Some((_, country_code)) = self.postal_uri_path() {
+                datasets.postal.insert(country_code);
+            }
+            datasets.valhalla.extend(self.valhalla_packages.iter().map(String::as_str));
+        }
+
+        for region in self.regions.values() {
+            region.used_datasets(datasets);
+        }
+    }
+
     /// Check if a postal country dataset is required by this region or its
     /// children.
     ///
@@ -971,6 +1453,44 @@ impl Region {
     }
 }
 
+/// Names of datasets used by regions.
+#[derive(Default)]
+struct Datasets<'a> {
+    geocoder: HashSet<&'a str>,
+    postal: HashSet<&'a str>,
+    valhalla: HashSet<&'a str>,
+}
+
+/// Data not used by any installed region.
+#[derive(Default)]
+struct Orphans {
+    dirs: Vec<PathBuf>,
+    valhalla_packages: Vec<String>,
+    size: u64,
+}
+
+/// Geographic bounding box of a region.
+#[derive(Deserialize, PartialEq, Copy, Clone, Debug)]
+pub struct RegionBounds {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl RegionBounds {
+    /// Check whether a point is inside these bounds.
+    fn contains(&self, point: GeoPoint) -> bool {
+        (self.min_lat..=self.max_lat).contains(&point.lat)
+            && (self.min_lon..=self.max_lon).contains(&point.lon)
+    }
+
+    /// Get the area of these bounds in square degrees.
+    fn area(&self) -> f64 {
+        (self.max_lat - self.min_lat) * (self.max_lon - self.min_lon)
+    }
+}
+
 /// Download state of a region's data.
 #[derive(PartialEq, Eq, Copy, Clone, Debug)]
 pub enum DownloadState {
@@ -997,6 +1517,9 @@ impl From<u8> for DownloadState {
 struct DownloadTracker {
     download_pending: Arc<AtomicU64>,
     download_done: Arc<AtomicU64>,
+    install_pending: Arc<AtomicU64>,
+    install_done: Arc<AtomicU64>,
+    download_speed: Arc<AtomicU64>,
     ui_waker: Ping,
 }
 
@@ -1012,6 +1535,79 @@ impl DownloadTracker {
         self.download_done.fetch_add(size, Ordering::Relaxed);
         self.ui_waker.ping();
     }
+
+    /// Add new data which needs to be installed.
+    fn add_install(&self, size: u64) {
+        self.install_pending.fetch_add(size, Ordering::Relaxed);
+        self.ui_waker.ping();
+    }
+
+    /// Indicate a certain number of bytes have been installed.
+    fn add_install_progress(&self, size: u64) {
+        self.install_done.fetch_add(size, Ordering::Relaxed);
+        self.ui_waker.ping();
+    }
+
+    /// Periodically update the download speed.
+    ///
+    /// This never returns and must be aborted once the download is done.
+    async fn sample_speed(self) {
+        let mut window = SpeedWindow::default();
+        let mut interval = time::interval(SPEED_SAMPLE_INTERVAL);
+        loop {
+            let now = interval.tick().await;
+            let done = self.download_done.load(Ordering::Relaxed);
+            let speed = window.push(now.into_std(), done);
+            self.download_speed.store(speed, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Rolling window of download progress samples.
+#[derive(Default)]
+struct SpeedWindow {
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl SpeedWindow {
+    /// Add a new sample and get the average speed in bytes per second.
+    fn push(&mut self, time: Instant, done: u64) -> u64 {
+        if self.samples.len() >= SPEED_WINDOW_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((time, done));
+
+        let (start_time, start_done) = self.samples[0];
+        let elapsed = time.saturating_duration_since(start_time).as_secs_f64();
+        if elapsed == 0. {
+            return 0;
+        }
+
+        (done.saturating_sub(start_done) as f64 / elapsed) as u64
+    }
+}
+
+/// Get the total size of all files within a directory.
+async fn dir_size(path: &Path) -> u64 {
+    let mut size = 0;
+
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let mut entries = match fs::read_dir(&dir).await {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            match entry.metadata().await {
+                Ok(metadata) if metadata.is_dir() => pending.push(entry.path()),
+                Ok(metadata) => size += metadata.len(),
+                Err(_) => (),
+            }
+        }
+    }
+
+    size
 }
 
 /// Parse the filename of a tile in an offlin map archive.
@@ -1106,6 +1702,25 @@ mod tests {
         assert!(tiles_url.ends_with("/germany/nordrhein-westfalen/detmold-regbez/tiles.tar.gz"));
     }
 
+    #[test]
+    fn leaf_index_at() {
+        let world = RegionData::new().unwrap().world_region;
+
+        let index = world.leaf_index_at(GeoPoint::new(52.5163, 13.3777)).unwrap();
+        let mut region = &world;
+        for i in index.iter().take_while(|i| **i != usize::MAX) {
+            region = &region.regions[*i];
+        }
+        assert_eq!(region.name, "Berlin");
+    }
+
+    #[test]
+    fn country_code_at() {
+        let world = RegionData::new().unwrap().world_region;
+
+        assert_eq!(world.country_code_at(GeoPoint::new(52.5163, 13.3777)), Some("DE"));
+    }
+
     #[test]
     fn tar_tile_file_name() {
         let path = Path::new("./should/not/matter/14_8504_5473.png");
@@ -1135,4 +1750,37 @@ mod tests {
         let path = Path::new("");
         assert_eq!(parse_tar_tile_file_name(Cow::Borrowed(path)), None);
     }
+
+    #[test]
+    fn download_checksum() {
+        const ABC_SHA256: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+        let hasher = || Sha256::new_with_prefix(b"abc");
+
+        assert!(verify_checksum("abc", None, hasher()).is_ok());
+        assert!(verify_checksum("abc", Some(ABC_SHA256), hasher()).is_ok());
+        assert!(verify_checksum("abc", Some(&ABC_SHA256.to_uppercase()), hasher()).is_ok());
+        assert!(matches!(
+            verify_checksum("abc", Some(ABC_SHA256), Sha256::new()),
+            Err(Error::ChecksumMismatch(_))
+        ));
+    }
+
+    #[test]
+    fn download_speed_window() {
+        let start = Instant::now();
+        let mut window = SpeedWindow::default();
+
+        assert_eq!(window.push(start, 0), 0);
+        assert_eq!(window.push(start + Duration::from_secs(1), 1_000), 1_000);
+        assert_eq!(window.push(start + Duration::from_secs(2), 3_000), 1_500);
+
+        // Old samples are dropped from the window.
+        for i in 3..=SPEED_WINDOW_SAMPLES as u64 + 1 {
+            window.push(start + Duration::from_secs(i), 3_000 + (i - 2) * 500);
+        }
+        let last_secs = SPEED_WINDOW_SAMPLES as u64 + 2;
+        let last_done = 3_000 + (last_secs - 2) * 500;
+        assert_eq!(window.push(start + Duration::from_secs(last_secs), last_done), 500);
+    }
 }
@@ -1,18 +1,25 @@
 //! Route planning abstraction layer.
 
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+use std::future::Future;
 use std::sync::{Arc, mpsc};
+use std::time::{Duration, SystemTime};
 
 use calloop::channel::Event;
 use calloop::{LoopHandle, channel};
 use reqwest::Client;
-use serde::Serialize;
-use tracing::error;
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use tracing::{error, info};
 
 use crate::config::Config;
+use crate::db::{Db, RouteKey};
 use crate::geometry::GeoPoint;
 use crate::region::Regions;
 use crate::router::valhalla::offline::Router as OfflineRouter;
-use crate::router::valhalla::online::Router as OnlineRouter;
+use crate::router::valhalla::online::{BicycleCosting, Router as OnlineRouter};
 use crate::ui::skia::Svg;
 use crate::ui::view::View;
 use crate::ui::view::search::QueryId;
@@ -20,20 +27,38 @@ use crate::{Error, State};
 
 mod valhalla;
 
+/// Road distance relative to the straight-line distance between loop
+/// waypoints.
+const LOOP_DETOUR_FACTOR: f64 = 1.3;
+
+/// Number of waypoints between the start and end of a loop route.
+const LOOP_WAYPOINTS: usize = 3;
+
+/// Maximum age of a cached route before it is recalculated.
+const ROUTE_CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Maximum number of cached routes.
+const MAX_CACHED_ROUTES: usize = 25;
+
 /// Multi-provider router
 pub struct Router {
-    valhalla_online_query_tx: Option<mpsc::Sender<RoutingQuery>>,
-    valhalla_offline_query_tx: Option<mpsc::Sender<RoutingQuery>>,
+    valhalla_online: Option<Provider>,
+    valhalla_offline: Option<Provider>,
 
     result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
+    last_query_tx: watch::Sender<QueryId>,
     valhalla_url: Arc<String>,
+    valhalla_timeout: Duration,
+    bicycle_costing: BicycleCosting,
     regions: Arc<Regions>,
     client: Client,
+    db: Db,
+
+    route_cache: HashMap<RouteKey, CachedRoute>,
+    cache_key: Option<RouteKey>,
 
     last_query: QueryId,
     is_gps_route: bool,
-    valhalla_offline_routing: bool,
-    valhalla_online_routing: bool,
 }
 
 impl Router {
@@ -41,12 +66,26 @@ impl Router {
         event_loop: LoopHandle<'static, State>,
         config: &Config,
         client: Client,
+        db: Db,
         regions: Arc<Regions>,
     ) -> Result<Self, Error> {
         let (result_tx, result_rx) = channel::channel();
 
+        // Notify routers about new queries, to cancel outdated requests.
+        let last_query = QueryId::new();
+        let (last_query_tx, _) = watch::channel(last_query);
+
         // Handle new routing results.
         event_loop.insert_source(result_rx, |event, _, state| {
+            // Reorder search results by travel time.
+            if let Event::Msg((id, RoutingUpdate::Matrix(times))) = event {
+                let search_view = state.window.views.search();
+                search_view.geocoder_mut().sort_by_travel_time(id, &times);
+                search_view.set_dirty();
+                state.window.unstall();
+                return;
+            }
+
             let router = state.window.views.search().router_mut();
 
             let query_event = match event {
@@ -59,13 +98,16 @@ impl Router {
             match query_event {
                 // Finish routing when any result is found, since only one result is returned.
                 RoutingUpdate::Route(route) => {
-                    router.valhalla_offline_routing = false;
-                    router.valhalla_online_routing = false;
-                    router.last_query = QueryId::new();
+                    for provider in router.providers_mut() {
+                        provider.routing = false;
+                    }
+                    router.set_last_query(QueryId::new());
+
+                    router.cache_route(&route);
 
-                    let route = Arc::new(route);
                     let is_gps_route = router.is_gps_route;
                     state.window.views.map().set_route(route.clone(), is_gps_route);
+                    state.window.set_directions_route(route.clone());
                     state.window.views.route().set_route(route, is_gps_route);
                     if state.window.views.active() == View::Search {
                         state.window.set_view(View::Map);
@@ -75,10 +117,14 @@ impl Router {
 
                     return;
                 },
-                // Mark current Valhalla online routing as done.
-                RoutingUpdate::ValhallaApiDone => router.valhalla_online_routing = false,
-                // Mark current Valhalla offline routing as done.
-                RoutingUpdate::ValhallaOfflineDone => router.valhalla_offline_routing = false,
+                // Mark current routing of a provider as done.
+                RoutingUpdate::Done(name) => {
+                    if let Some(provider) = router.providers_mut().find(|p| p.name == name) {
+                        provider.routing = false;
+                    }
+                },
+                // Matrix results are handled before the query filter.
+                RoutingUpdate::Matrix(_) => return,
             }
 
             // Show error if no route was found.
@@ -91,23 +137,45 @@ impl Router {
             }
         })?;
 
+        // Load cached routes in the background.
+        let (cache_tx, cache_rx) = channel::channel();
+        event_loop.insert_source(cache_rx, |event, _, state| {
+            if let Event::Msg(routes) = event {
+                let router = state.window.views.search().router_mut();
+                for (key, route) in routes {
+                    router.route_cache.entry(key).or_insert(route);
+                }
+            }
+        })?;
+        let cache_db = db.clone();
+        tokio::spawn(async move {
+            match cache_db.cached_routes(ROUTE_CACHE_TTL).await {
+                Ok(routes) => {
+                    let _ = cache_tx.send(routes);
+                },
+                Err(err) => error!("Failed to load cached routes: {err}"),
+            }
+        });
+
         // Spawn Valhalla API routing engine.
-        let valhalla_online_query_tx = (!config.search.valhalla_url.is_empty()).then(|| {
-            let (query_tx, query_rx) = mpsc::channel::<RoutingQuery>();
-            OnlineRouter::spawn(client.clone(), config, query_rx, result_tx.clone());
-            query_tx
+        let valhalla_online = (!config.search.valhalla_url.is_empty()).then(|| {
+            let router = OnlineRouter::new(client.clone(), config);
+            Provider::spawn(router, result_tx.clone(), last_query_tx.subscribe())
         });
 
         Ok(Self {
-            valhalla_online_query_tx,
+            valhalla_online,
             result_tx,
             regions,
             client,
             valhalla_url: config.search.valhalla_url.clone(),
-            last_query: QueryId::new(),
-            valhalla_offline_query_tx: Default::default(),
-            valhalla_offline_routing: Default::default(),
-            valhalla_online_routing: Default::default(),
+            valhalla_timeout: *config.search.valhalla_timeout,
+            bicycle_costing: BicycleCosting::new(config),
+            last_query_tx,
+            last_query,
+            valhalla_offline: Default::default(),
+            route_cache: Default::default(),
+            cache_key: Default::default(),
             is_gps_route: Default::default(),
         })
     }
@@ -115,35 +183,57 @@ impl Router {
     /// Submit a routing query to all engines.
     pub fn route(&mut self, query: RoutingQuery, is_gps_route: bool) {
         self.is_gps_route = is_gps_route;
-        self.last_query = query.id;
+        self.set_last_query(query.id);
+
+        // Reuse recent routes for the same trip, without querying any engine.
+        self.cache_key = RouteKey::new(&query);
+        let cached = self.cache_key.and_then(|key| self.route_cache.get(&key));
+        if let Some(cached) = cached.filter(|cached| !cached.is_expired()) {
+            let _ = self.result_tx.send((query.id, RoutingUpdate::Route(cached.route.clone())));
+            self.cache_key = None;
+            return;
+        }
 
-        if let Some(query_tx) = &self.valhalla_online_query_tx {
-            self.valhalla_online_routing = true;
-            let _ = query_tx.send(query);
+        for provider in self.providers_mut() {
+            provider.routing = true;
+            let _ = provider.query_tx.send(QueryEvent::Route(query.clone()));
         }
-        if let Some(query_tx) = &self.valhalla_offline_query_tx {
-            self.valhalla_offline_routing = true;
-            let _ = query_tx.send(query);
+    }
+
+    /// Submit a travel time matrix query to all engines.
+    pub fn matrix(&mut self, query: MatrixQuery) {
+        for provider in self.providers() {
+            let _ = provider.query_tx.send(QueryEvent::Matrix(query.clone()));
         }
     }
 
     /// Check if routing is finished.
     pub fn routing(&self) -> bool {
-        self.valhalla_online_routing || self.valhalla_offline_routing
+        self.providers().any(|provider| provider.routing)
     }
 
     /// Handle config updates.
     pub fn update_config(&mut self, config: &Config) {
-        // Restart Valhalla API routing engine on URL change.
-        if config.search.valhalla_url != self.valhalla_url {
+        // Restart Valhalla API routing engine on URL, timeout or costing change.
+        let bicycle_costing = BicycleCosting::new(config);
+        if config.search.valhalla_url != self.valhalla_url
+            || *config.search.valhalla_timeout != self.valhalla_timeout
+            || bicycle_costing != self.bicycle_costing
+        {
             // Drop old router first, to improve log order.
-            self.valhalla_online_query_tx = None;
+            self.valhalla_online = None;
+
+            // Ignore cached routes using the old costing options.
+            if bicycle_costing != self.bicycle_costing {
+                self.route_cache.clear();
+            }
 
             self.valhalla_url = config.search.valhalla_url.clone();
-            self.valhalla_online_query_tx = (!config.search.valhalla_url.is_empty()).then(|| {
-                let (query_tx, query_rx) = mpsc::channel::<RoutingQuery>();
-                OnlineRouter::spawn(self.client.clone(), config, query_rx, self.result_tx.clone());
-                query_tx
+            self.valhalla_timeout = *config.search.valhalla_timeout;
+            self.bicycle_costing = bicycle_costing;
+            self.valhalla_online = (!config.search.valhalla_url.is_empty()).then(|| {
+                let router = OnlineRouter::new(self.client.clone(), config);
+                Provider::spawn(router, self.result_tx.clone(), self.last_query_tx.subscribe())
             });
         }
     }
@@ -151,36 +241,218 @@ impl Router {
     /// Reload offline router, to refresh the Valhalla tiles.
     pub fn reload_offline_router(&mut self) {
         // Drop old router first, to improve log order.
-        self.valhalla_offline_query_tx = None;
-
-        let (valhalla_offline_query_tx, query_rx) = mpsc::channel::<RoutingQuery>();
-        match OfflineRouter::spawn(self.regions.clone(), query_rx, self.result_tx.clone()) {
-            Ok(_) => self.valhalla_offline_query_tx = Some(valhalla_offline_query_tx),
+        self.valhalla_offline = None;
+
+        match OfflineRouter::new(self.regions.clone()) {
+            Ok(router) => {
+                let last_query_rx = self.last_query_tx.subscribe();
+                let provider = Provider::spawn(router, self.result_tx.clone(), last_query_rx);
+                self.valhalla_offline = Some(provider);
+            },
             Err(err) => error!("Failed to create Valhalla offline router: {err}"),
         }
     }
+
+    /// Add a new route result to the route cache.
+    fn cache_route(&mut self, route: &Arc<Route>) {
+        let key = match self.cache_key.take() {
+            Some(key) => key,
+            None => return,
+        };
+
+        // Evict expired routes and the oldest route once the cache is full.
+        self.route_cache.retain(|_, cached| !cached.is_expired());
+        if self.route_cache.len() >= MAX_CACHED_ROUTES {
+            let oldest = self.route_cache.iter().min_by_key(|(_, cached)| cached.ctime);
+            if let Some(oldest) = oldest.map(|(key, _)| *key) {
+                self.route_cache.remove(&oldest);
+            }
+        }
+
+        let ctime = SystemTime::now();
+        self.route_cache.insert(key, CachedRoute { route: route.clone(), ctime });
+
+        // Persist the route, so it is available after restarts.
+        if !self.db.read_only() {
+            let route = route.clone();
+            let db = self.db.clone();
+            tokio::spawn(async move {
+                if let Err(err) = db.insert_cached_route(key, &route).await {
+                    error!("Failed to cache route: {err}");
+                }
+                if let Err(err) = db.prune_route_cache(ROUTE_CACHE_TTL, MAX_CACHED_ROUTES).await {
+                    error!("Failed to prune route cache: {err}");
+                }
+            });
+        }
+    }
+
+    /// Get all active routing providers.
+    fn providers(&self) -> impl Iterator<Item = &Provider> {
+        self.valhalla_online.iter().chain(&self.valhalla_offline)
+    }
+
+    /// Get mutable access to all active routing providers.
+    fn providers_mut(&mut self) -> impl Iterator<Item = &mut Provider> {
+        self.valhalla_online.iter_mut().chain(&mut self.valhalla_offline)
+    }
+
+    /// Update the active query, cancelling requests for all previous queries.
+    fn set_last_query(&mut self, id: QueryId) {
+        self.last_query = id;
+        self.last_query_tx.send_replace(id);
+    }
+}
+
+/// Routing engine backend.
+pub trait RoutingProvider: Send + 'static {
+    /// Provider name, used for logging and display.
+    fn name(&self) -> &'static str;
+
+    /// Calculate a route through all query locations.
+    ///
+    /// Returns `None` if the locations cannot be connected.
+    fn route(
+        &mut self,
+        query: &RoutingQuery,
+    ) -> impl Future<Output = Result<Option<Route>, Error>> + Send;
+
+    /// Calculate the travel time in seconds to each matrix query target.
+    fn matrix(
+        &mut self,
+        query: &MatrixQuery,
+    ) -> impl Future<Output = Result<Vec<Option<u64>>, Error>> + Send;
+}
+
+/// Handle for a running routing provider.
+struct Provider {
+    query_tx: mpsc::Sender<QueryEvent>,
+    name: &'static str,
+    routing: bool,
+}
+
+impl Provider {
+    /// Spawn a routing provider in a tokio worker thread.
+    fn spawn<P: RoutingProvider>(
+        provider: P,
+        result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
+        last_query: watch::Receiver<QueryId>,
+    ) -> Self {
+        let name = provider.name();
+        let (query_tx, query_rx) = mpsc::channel();
+        tokio::spawn(Self::listen(provider, query_rx, result_tx, last_query));
+        Self { query_tx, name, routing: false }
+    }
+
+    /// Listen for new routing queries.
+    async fn listen<P: RoutingProvider>(
+        mut provider: P,
+        query_rx: mpsc::Receiver<QueryEvent>,
+        result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
+        mut last_query: watch::Receiver<QueryId>,
+    ) {
+        let name = provider.name();
+        info!("Starting {name} router");
+
+        while let Ok(event) = query_rx.recv() {
+            match event {
+                // Skip queries which were superseded while waiting in the queue.
+                QueryEvent::Route(query) if query.id.is_superseded(&last_query) => {
+                    let _ = result_tx.send((query.id, RoutingUpdate::Done(name)));
+                },
+                QueryEvent::Route(query) => {
+                    // Abort the request once a newer query supersedes it.
+                    tokio::select! {
+                        _ = query.id.superseded(&mut last_query) => {
+                            info!("Cancelled outdated {name} route");
+                        },
+                        result = provider.route(&query) => match result {
+                            Ok(Some(route)) => {
+                                let route = RoutingUpdate::Route(Arc::new(route));
+                                let _ = result_tx.send((query.id, route));
+                            },
+                            Ok(None) => (),
+                            Err(err) => error!("{name} routing failed: {err}"),
+                        },
+                    }
+
+                    // Mark this query as done, regardless of success.
+                    let _ = result_tx.send((query.id, RoutingUpdate::Done(name)));
+                },
+                QueryEvent::Matrix(query) => match provider.matrix(&query).await {
+                    Ok(times) => {
+                        let _ = result_tx.send((query.id, RoutingUpdate::Matrix(times)));
+                    },
+                    Err(err) => error!("{name} matrix failed: {err}"),
+                },
+            }
+        }
+
+        info!("Shutting down {name} router");
+    }
+}
+
+/// Router query types.
+pub enum QueryEvent {
+    Route(RoutingQuery),
+    Matrix(MatrixQuery),
 }
 
 /// Routing query.
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct RoutingQuery {
     pub id: QueryId,
     pub origin: GeoPoint,
+    pub via: Vec<GeoPoint>,
     pub target: GeoPoint,
     pub mode: Mode,
 }
 
 impl RoutingQuery {
     pub fn new(origin: GeoPoint, target: GeoPoint, mode: Mode) -> Self {
-        Self { mode, origin, target, id: QueryId::new() }
+        Self { mode, origin, target, id: QueryId::new(), via: Default::default() }
+    }
+
+    /// Create a roundtrip query with an approximate length in meters.
+    ///
+    /// The route passes through waypoints on a circle touching the origin,
+    /// with the circle's center in the direction of the `bearing`.
+    pub fn new_loop(origin: GeoPoint, length: f64, bearing: f64, mode: Mode) -> Self {
+        let via = loop_waypoints(origin, length, bearing);
+        Self { mode, origin, via, target: origin, id: QueryId::new() }
+    }
+
+    /// Get all locations the route passes through.
+    pub fn locations(&self) -> Vec<GeoPoint> {
+        let mut locations = Vec::with_capacity(self.via.len() + 2);
+        locations.push(self.origin);
+        locations.extend_from_slice(&self.via);
+        locations.push(self.target);
+        locations
+    }
+}
+
+/// Travel time matrix query from one origin to multiple targets.
+#[derive(Clone)]
+pub struct MatrixQuery {
+    pub id: QueryId,
+    pub origin: GeoPoint,
+    pub targets: Vec<GeoPoint>,
+    pub mode: Mode,
+}
+
+impl MatrixQuery {
+    pub fn new(id: QueryId, origin: GeoPoint, targets: Vec<GeoPoint>, mode: Mode) -> Self {
+        Self { id, origin, targets, mode }
     }
 }
 
 /// Routing travel modes.
-#[derive(Serialize, Default, Copy, Clone, Debug)]
+#[derive(Serialize, Deserialize, Default, Hash, PartialEq, Eq, Copy, Clone, Debug)]
 #[serde(rename_all = "lowercase")]
 pub enum Mode {
     // XXX: Integer values must match [`valhalla::proto::costing::Type`].
+    Bicycle = 1,
     Pedestrian = 5,
     #[default]
     Auto = 10,
@@ -190,24 +462,48 @@ impl Mode {
     /// Get corresponding SVG icon for this route.
     pub fn svg(&self) -> Svg {
         match self {
+            Self::Bicycle => Svg::Bicycle,
             Self::Pedestrian => Svg::Pedestrian,
             Self::Auto => Svg::Car,
         }
     }
+
+    /// Get the next mode in the mode button's cycle.
+    pub fn next(&self) -> Self {
+        match self {
+            Self::Pedestrian => Self::Bicycle,
+            Self::Bicycle => Self::Auto,
+            Self::Auto => Self::Pedestrian,
+        }
+    }
+}
+
+impl TryFrom<i64> for Mode {
+    type Error = ();
+
+    fn try_from(value: i64) -> Result<Self, Self::Error> {
+        match value {
+            1 => Ok(Self::Bicycle),
+            5 => Ok(Self::Pedestrian),
+            10 => Ok(Self::Auto),
+            _ => Err(()),
+        }
+    }
 }
 
 /// Routing query update event.
 pub enum RoutingUpdate {
     /// New query results available.
-    Route(Route),
-    /// Valhalla online routing is done, no more results will be delivered.
-    ValhallaApiDone,
-    /// Valhalla offline routing is done, no more results will be delivered.
-    ValhallaOfflineDone,
+    Route(Arc<Route>),
+    /// Routing of the named provider is done, no more results will be
+    /// delivered.
+    Done(&'static str),
+    /// Travel time in seconds to each matrix query target.
+    Matrix(Vec<Option<u64>>),
 }
 
 /// Routing result.
-#[derive(Default, Debug)]
+#[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Route {
     pub segments: Vec<Segment>,
     /// Complete trip time in seconds.
@@ -216,10 +512,26 @@ pub struct Route {
     pub length: u32,
     /// Transportation mode.
     pub mode: Mode,
+    /// Name of the provider which calculated the route.
+    pub provider: Cow<'static, str>,
+}
+
+/// Previously calculated route.
+pub struct CachedRoute {
+    pub route: Arc<Route>,
+    /// Time at which the route was calculated.
+    pub ctime: SystemTime,
+}
+
+impl CachedRoute {
+    /// Check if the route is too old to be reused.
+    fn is_expired(&self) -> bool {
+        self.ctime.elapsed().is_ok_and(|age| age >= ROUTE_CACHE_TTL)
+    }
 }
 
 /// Subsection of a route.
-#[derive(Debug)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct Segment {
     pub points: Vec<GeoPoint>,
     pub instruction: Arc<String>,
@@ -229,6 +541,19 @@ pub struct Segment {
     pub length: u32,
 }
 
+/// Get the intermediate waypoints for a loop route.
+fn loop_waypoints(origin: GeoPoint, length: f64, bearing: f64) -> Vec<GeoPoint> {
+    let radius = length / LOOP_DETOUR_FACTOR / (2. * PI);
+    let center = origin.destination(bearing, radius);
+
+    // Distribute waypoints evenly around the circle, starting at the origin.
+    let origin_bearing = bearing + 180.;
+    let step = 360. / (LOOP_WAYPOINTS + 1) as f64;
+    (1..=LOOP_WAYPOINTS)
+        .map(|i| center.destination(origin_bearing + step * i as f64, radius))
+        .collect()
+}
+
 /// Decode a polyline string.
 ///
 /// See <https://developers.google.com/maps/documentation/utilities/polylinealgorithm>.
@@ -272,6 +597,42 @@ fn parse_polyline_coordinate(mut chars: impl Iterator<Item = char>, previous: i3
     Some(value)
 }
 
+#[test]
+fn loop_length() {
+    let origin = GeoPoint::new(52.52, 13.405);
+    let query = RoutingQuery::new_loop(origin, 5_000., 45., Mode::Pedestrian);
+    let locations = query.locations();
+
+    assert_eq!(locations.len(), LOOP_WAYPOINTS + 2);
+    assert_eq!(locations.first(), locations.last());
+
+    // Straight-line length should leave room for road detours.
+    let straight_length: u32 = locations.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+    assert!((3_000..5_000).contains(&straight_length), "{straight_length}");
+}
+
+#[test]
+fn route_cache_key() {
+    let origin = GeoPoint::new(52.52, 13.405);
+    let target = GeoPoint::new(52.5163, 13.3777);
+    let query = RoutingQuery::new(origin, target, Mode::Auto);
+    let key = RouteKey::new(&query);
+    assert!(key.is_some());
+
+    // Small offsets map to the same route.
+    let nearby = GeoPoint::new(52.52002, 13.40498);
+    assert_eq!(RouteKey::new(&RoutingQuery::new(nearby, target, Mode::Auto)), key);
+
+    // Travel mode and larger offsets use separate routes.
+    assert_ne!(RouteKey::new(&RoutingQuery::new(origin, target, Mode::Pedestrian)), key);
+    let distant = GeoPoint::new(52.5205, 13.405);
+    assert_ne!(RouteKey::new(&RoutingQuery::new(distant, target, Mode::Auto)), key);
+
+    // Loop routes are never cached.
+    let loop_query = RoutingQuery::new_loop(origin, 5_000., 45., Mode::Auto);
+    assert_eq!(RouteKey::new(&loop_query), None);
+}
+
 #[test]
 fn decode_polyline5() {
     let x = decode_polyline("_p~iF~ps|U_ulLnnqC_mqNvxq`@", 1E5);
@@ -289,3 +650,13 @@ fn decode_polyline6() {
     let decoded = vec![GeoPoint::new(42.225139, -8.670911), GeoPoint::new(42.225224, -8.670718)];
     assert_eq!(x, decoded);
 }
+
+#[test]
+fn mode_cycle() {
+    let mut mode = Mode::Auto;
+    for _ in 0..3 {
+        assert_eq!(Mode::try_from(mode as i64), Ok(mode));
+        mode = mode.next();
+    }
+    assert_eq!(mode, Mode::Auto);
+}
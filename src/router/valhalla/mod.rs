@@ -2,13 +2,10 @@
 
 use std::sync::Arc;
 
-use calloop::channel::Sender;
 use serde::{Deserialize, Deserializer};
 use tracing::debug;
 
-use crate::Error;
-use crate::router::{self, GeoPoint, Route, RoutingQuery, RoutingUpdate, Segment};
-use crate::ui::view::search::QueryId;
+use crate::router::{self, GeoPoint, Mode, Route, Segment};
 
 pub mod offline;
 pub mod online;
@@ -23,16 +20,11 @@ struct RouteResponse {
 }
 
 impl RouteResponse {
-    fn submit(
-        self,
-        query: RoutingQuery,
-        result_tx: &Sender<(QueryId, RoutingUpdate)>,
-        router: &'static str,
-    ) -> Result<(), Error> {
-        // Skip sending results if routing failed.
+    /// Convert the response to a route, if routing was successful.
+    fn into_route(self, mode: Mode, router: &'static str) -> Option<Route> {
         if self.trip.status != 0 {
-            debug!("Valhalla {router} routing failed: {}", self.trip.status_message);
-            return Ok(());
+            debug!("{router} routing failed: {}", self.trip.status_message);
+            return None;
         }
 
         // Transform Valhalla response into Route.
@@ -40,9 +32,16 @@ impl RouteResponse {
             time: self.trip.summary.time.round() as u64,
             length: (self.trip.summary.length * 1_000.).round() as u32,
             segments: Vec::new(),
-            mode: query.mode,
+            provider: router.into(),
+            mode,
         };
-        for leg in self.trip.legs {
+        let leg_count = self.trip.legs.len();
+        for (i, mut leg) in self.trip.legs.into_iter().enumerate() {
+            // Skip arrival at intermediate waypoints.
+            if i + 1 < leg_count {
+                leg.maneuvers.pop();
+            }
+
             for maneuver in leg.maneuvers {
                 if let Some(segment) = maneuver.segment(&leg.shape) {
                     response_route.segments.push(segment);
@@ -50,10 +49,7 @@ impl RouteResponse {
             }
         }
 
-        // Submit result to the collector.
-        let _ = result_tx.send((query.id, RoutingUpdate::Route(response_route)));
-
-        Ok(())
+        Some(response_route)
     }
 }
 
@@ -114,6 +110,27 @@ struct Summary {
     time: f64,
 }
 
+/// Valhalla matrix API response body.
+#[derive(Deserialize)]
+struct MatrixResponse {
+    sources_to_targets: Vec<Vec<MatrixEntry>>,
+}
+
+impl MatrixResponse {
+    /// Get the travel time in seconds from the first source to each target.
+    fn times(self) -> Vec<Option<u64>> {
+        let entries = self.sources_to_targets.into_iter().next().unwrap_or_default();
+        entries.into_iter().map(|entry| entry.time.map(|time| time.round() as u64)).collect()
+    }
+}
+
+/// Source to target connection in a Valhalla matrix.
+#[derive(Deserialize)]
+struct MatrixEntry {
+    /// Estimated travel time in seconds, if the target is reachable.
+    time: Option<f64>,
+}
+
 /// Deserialize a Valhalla shape polyline.
 fn deserialize_shape<'de, D>(deserializer: D) -> Result<Vec<GeoPoint>, D::Error>
 where
@@ -122,3 +139,20 @@ where
     let text = String::deserialize(deserializer)?;
     Ok(router::decode_polyline(&text, POLYLINE_PRECISION))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_times() {
+        let json = r#"{
+            "sources_to_targets": [[
+                { "distance": 1.2, "time": 95.6, "from_index": 0, "to_index": 0 },
+                { "distance": null, "time": null, "from_index": 0, "to_index": 1 }
+            ]]
+        }"#;
+        let response: MatrixResponse = serde_json::from_str(json).unwrap();
+        assert_eq!(response.times(), vec![Some(96), None]);
+    }
+}
@@ -1,35 +1,25 @@
 //! Offline Valhalla router.
 
-use std::sync::{Arc, mpsc};
+use std::sync::Arc;
 
-use calloop::channel;
-use tracing::{error, info};
 use valhalla::proto::Options;
 use valhalla::{Actor, Config, Response};
 
 use crate::Error;
 use crate::region::Regions;
-use crate::router::valhalla::RouteResponse;
-use crate::router::{RoutingQuery, RoutingUpdate};
-use crate::ui::view::search::QueryId;
+use crate::router::valhalla::{MatrixResponse, RouteResponse};
+use crate::router::{MatrixQuery, Route, RoutingProvider, RoutingQuery};
 
 /// Valhalla configuration file.
 const VALHALLA_CONFIG: &str = include_str!("config.json");
 
-/// Valhalla API routing engine.
+/// Valhalla offline routing engine.
 pub struct Router {
-    query_rx: mpsc::Receiver<RoutingQuery>,
-    result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
     actor: Actor,
 }
 
 impl Router {
-    /// Spawn Valhalla API router in a tokio worker thread.
-    pub fn spawn(
-        regions: Arc<Regions>,
-        query_rx: mpsc::Receiver<RoutingQuery>,
-        result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
-    ) -> Result<(), Error> {
+    pub fn new(regions: Arc<Regions>) -> Result<Self, Error> {
         // Replace variables in Valhalla config.
         let tiles_path = regions.valhalla_tiles_path();
         let tiles_path = tiles_path.to_str().ok_or(Error::MissingCacheDir)?;
@@ -39,44 +29,44 @@ impl Router {
         let config = Config::from_json(&config)?;
         let actor = Actor::new(&config)?;
 
-        tokio::spawn(async {
-            let mut valhalla = Self { result_tx, query_rx, actor };
-            valhalla.listen().await;
-        });
-
-        Ok(())
+        Ok(Self { actor })
     }
+}
 
-    /// Listen for new routing queries.
-    async fn listen(&mut self) {
-        info!("Starting Valhalla Offline router");
+impl RoutingProvider for Router {
+    fn name(&self) -> &'static str {
+        "Valhalla Offline"
+    }
 
-        while let Ok(query) = self.query_rx.recv() {
-            if let Err(err) = self.route(query).await {
-                error!("Valhalla Offline routing failed: {err}");
-            }
+    async fn route(&mut self, query: &RoutingQuery) -> Result<Option<Route>, Error> {
+        let request = Options {
+            costing_type: query.mode as i32,
+            locations: query.locations().into_iter().map(Into::into).collect(),
+            ..Default::default()
+        };
 
-            // Mark this query as done, regardless of success.
-            let _ = self.result_tx.send((query.id, RoutingUpdate::ValhallaOfflineDone));
-        }
+        let route: RouteResponse = match self.actor.route(&request)? {
+            Response::Json(json) => serde_json::from_str(&json)?,
+            _ => return Err(Error::ValhallaInvalidResponseType),
+        };
 
-        info!("Shutting down Valhalla Offline router");
+        Ok(route.into_route(query.mode, self.name()))
     }
 
-    /// Process a routing query.
-    async fn route(&mut self, query: RoutingQuery) -> Result<(), Error> {
+    async fn matrix(&mut self, query: &MatrixQuery) -> Result<Vec<Option<u64>>, Error> {
         let request = Options {
             costing_type: query.mode as i32,
-            locations: vec![query.origin.into(), query.target.into()],
+            sources: vec![query.origin.into()],
+            targets: query.targets.iter().map(|&target| target.into()).collect(),
             ..Default::default()
         };
 
-        let route: RouteResponse = match self.actor.route(&request)? {
+        let matrix: MatrixResponse = match self.actor.matrix(&request)? {
             Response::Json(json) => serde_json::from_str(&json)?,
             _ => return Err(Error::ValhallaInvalidResponseType),
         };
 
-        route.submit(query, &self.result_tx, "Offline")
+        Ok(matrix.times())
     }
 }
 
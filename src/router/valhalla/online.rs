@@ -1,72 +1,79 @@
 //! Online Valhalla router.
 
-use std::sync::{Arc, mpsc};
+use std::sync::Arc;
+use std::time::Duration;
 
-use calloop::channel;
 use reqwest::Client;
 use serde::Serialize;
-use tracing::{error, info};
 
 use crate::Error;
-use crate::config::Config;
+use crate::config::{BicycleType, Config};
 use crate::geometry::GeoPoint;
-use crate::router::valhalla::RouteResponse;
-use crate::router::{Mode, RoutingQuery, RoutingUpdate};
-use crate::ui::view::search::QueryId;
+use crate::router::valhalla::{MatrixResponse, RouteResponse};
+use crate::router::{MatrixQuery, Mode, Route, RoutingProvider, RoutingQuery};
 
 /// Valhalla API routing engine.
 pub struct Router {
-    query_rx: mpsc::Receiver<RoutingQuery>,
-    result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
     url: Arc<String>,
+    timeout: Duration,
     client: Client,
+    bicycle: BicycleCosting,
 }
 
 impl Router {
-    /// Spawn Valhalla API router in a tokio worker thread.
-    pub fn spawn(
-        client: Client,
-        config: &Config,
-        query_rx: mpsc::Receiver<RoutingQuery>,
-        result_tx: channel::Sender<(QueryId, RoutingUpdate)>,
-    ) {
+    pub fn new(client: Client, config: &Config) -> Self {
         let url = config.search.valhalla_url.clone();
-        tokio::spawn(async {
-            let mut valhalla = Self { result_tx, query_rx, client, url };
-            valhalla.listen().await;
-        });
+        let timeout = *config.search.valhalla_timeout;
+        let bicycle = BicycleCosting::new(config);
+        Self { client, timeout, url, bicycle }
     }
 
-    /// Listen for new routing queries.
-    async fn listen(&mut self) {
-        info!("Starting Valhalla API router ({})", self.url);
-
-        while let Ok(query) = self.query_rx.recv() {
-            if let Err(err) = self.route(query).await {
-                error!("Valhalla API routing failed: {err}");
-            }
-
-            // Mark this query as done, regardless of success.
-            let _ = self.result_tx.send((query.id, RoutingUpdate::ValhallaApiDone));
-        }
+    /// Get the costing options of all travel modes.
+    fn costing_options(&self) -> CostingOptions {
+        CostingOptions { bicycle: self.bicycle }
+    }
+}
 
-        info!("Shutting down Valhalla API router ({})", self.url);
+impl RoutingProvider for Router {
+    fn name(&self) -> &'static str {
+        "Valhalla API"
     }
 
-    /// Process a routing query.
-    async fn route(&mut self, query: RoutingQuery) -> Result<(), Error> {
+    async fn route(&mut self, query: &RoutingQuery) -> Result<Option<Route>, Error> {
         // Convert query to Valhalla routing request format.
-        let locations = vec![query.origin, query.target];
-        let request = RouteRequest { locations, costing: query.mode };
+        let locations = query.locations();
+        let costing_options = self.costing_options();
+        let request = RouteRequest { locations, costing: query.mode, costing_options };
         let data = serde_json::to_string(&request)?;
 
         // Get routing results from Valhalla.
         let url = format!("{}/route?json={}", self.url, data);
-        let response = self.client.get(&url).send().await?.error_for_status()?;
+        let request = self.client.get(&url).timeout(self.timeout);
+        let response = request.send().await?.error_for_status()?;
 
         let route: RouteResponse = response.json().await?;
 
-        route.submit(query, &self.result_tx, "Online")
+        Ok(route.into_route(query.mode, self.name()))
+    }
+
+    async fn matrix(&mut self, query: &MatrixQuery) -> Result<Vec<Option<u64>>, Error> {
+        // Convert query to Valhalla matrix request format.
+        let request = MatrixRequest {
+            sources: vec![query.origin],
+            targets: query.targets.clone(),
+            costing: query.mode,
+            costing_options: self.costing_options(),
+        };
+        let data = serde_json::to_string(&request)?;
+
+        // Get travel times from Valhalla.
+        let url = format!("{}/sources_to_targets?json={}", self.url, data);
+        let request = self.client.get(&url).timeout(self.timeout);
+        let response = request.send().await?.error_for_status()?;
+
+        let matrix: MatrixResponse = response.json().await?;
+
+        Ok(matrix.times())
     }
 }
 
@@ -75,4 +82,36 @@ impl Router {
 struct RouteRequest {
     locations: Vec<GeoPoint>,
     costing: Mode,
+    costing_options: CostingOptions,
+}
+
+/// Valhalla matrix API request body.
+#[derive(Serialize)]
+struct MatrixRequest {
+    sources: Vec<GeoPoint>,
+    targets: Vec<GeoPoint>,
+    costing: Mode,
+    costing_options: CostingOptions,
+}
+
+/// Valhalla costing options, by travel mode.
+#[derive(Serialize)]
+struct CostingOptions {
+    bicycle: BicycleCosting,
+}
+
+/// Valhalla bicycle costing options.
+#[derive(Serialize, PartialEq, Copy, Clone, Debug)]
+pub struct BicycleCosting {
+    bicycle_type: BicycleType,
+    use_hills: f64,
+}
+
+impl BicycleCosting {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            bicycle_type: config.search.bicycle_type,
+            use_hills: config.search.bicycle_use_hills,
+        }
+    }
 }
@@ -0,0 +1,104 @@
+//! Live position sharing.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use reqwest::Client;
+use serde::Serialize;
+use tracing::error;
+
+use crate::config::{Config, Sharing};
+use crate::geometry::GeoPoint;
+
+/// Maximum time to wait for the sharing endpoint to accept an update.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// OwnTracks position publisher.
+pub struct PositionSharing {
+    last_update: Option<Instant>,
+    config: Sharing,
+    client: Client,
+}
+
+impl PositionSharing {
+    pub fn new(client: Client, config: &Config) -> Self {
+        Self { client, config: config.sharing.clone(), last_update: None }
+    }
+
+    /// Publish a new position, if the update interval has elapsed.
+    pub fn update(&mut self, point: GeoPoint, heading: Option<f64>) {
+        // Sharing is strictly opt-in.
+        if self.config.url.is_empty() {
+            return;
+        }
+
+        if self.last_update.is_some_and(|update| update.elapsed() < *self.config.interval) {
+            return;
+        }
+        self.last_update = Some(Instant::now());
+
+        let location = Location::new(point, heading, &self.config.tracker_id);
+        let request = self.client.post(&*self.config.url).timeout(REQUEST_TIMEOUT).json(&location);
+
+        tokio::spawn(async move {
+            let result = request.send().await.and_then(|response| response.error_for_status());
+            if let Err(err) = result {
+                error!("Failed to share position: {err}");
+            }
+        });
+    }
+
+    /// Handle config updates.
+    pub fn update_config(&mut self, config: &Config) {
+        // Publish the next position immediately after the endpoint changed.
+        if self.config.url != config.sharing.url {
+            self.last_update = None;
+        }
+        self.config = config.sharing.clone();
+    }
+}
+
+/// OwnTracks location message.
+#[derive(Serialize, Debug)]
+struct Location<'a> {
+    #[serde(rename = "_type")]
+    kind: &'static str,
+    lat: f64,
+    lon: f64,
+    tst: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    cog: Option<u16>,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    tid: &'a str,
+}
+
+impl<'a> Location<'a> {
+    fn new(point: GeoPoint, heading: Option<f64>, tracker_id: &'a str) -> Self {
+        let tst = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+        let cog = heading.map(|heading| heading.rem_euclid(360.).round() as u16 % 360);
+
+        Self { tst, cog, kind: "location", lat: point.lat, lon: point.lon, tid: tracker_id }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn owntracks_location() {
+        let point = GeoPoint::new(52.5, 13.4);
+
+        let location = Location::new(point, Some(-90.), "ch");
+        let json = serde_json::to_value(&location).unwrap();
+        assert_eq!(json["_type"], "location");
+        assert_eq!(json["lat"], 52.5);
+        assert_eq!(json["lon"], 13.4);
+        assert_eq!(json["cog"], 270);
+        assert_eq!(json["tid"], "ch");
+
+        let location = Location::new(point, None, "");
+        let json = serde_json::to_value(&location).unwrap();
+        assert!(json.get("cog").is_none());
+        assert!(json.get("tid").is_none());
+    }
+}
@@ -0,0 +1,153 @@
+//! Sunrise and sunset calculation.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::geometry::GeoPoint;
+
+/// Julian date of the unix epoch.
+const UNIX_EPOCH_JULIAN: f64 = 2_440_587.5;
+
+/// Julian date of the J2000 epoch.
+const J2000: f64 = 2_451_545.;
+
+/// Seconds per day.
+const DAY_SECS: f64 = 86_400.;
+
+/// Sun altitude in degrees at sunrise and sunset.
+///
+/// This accounts for atmospheric refraction and the sun's diameter.
+const HORIZON_ALTITUDE: f64 = -0.833;
+
+/// Tilt of the earth's axis in degrees.
+const AXIAL_TILT: f64 = 23.4397;
+
+/// Sun position over the course of a day.
+#[derive(PartialEq, Debug)]
+pub enum SunTimes {
+    /// Sun rises and sets on this day.
+    Day { sunrise: SystemTime, sunset: SystemTime },
+    /// Sun stays above the horizon all day.
+    PolarDay,
+    /// Sun stays below the horizon all day.
+    PolarNight,
+}
+
+impl SunTimes {
+    /// Calculate sunrise and sunset around a point in time.
+    ///
+    /// This uses the solar noon closest to `time` at the location's longitude.
+    pub fn new(point: GeoPoint, time: SystemTime) -> Self {
+        let unix = match time.duration_since(UNIX_EPOCH) {
+            Ok(unix) => unix.as_secs_f64(),
+            Err(err) => -err.duration().as_secs_f64(),
+        };
+        let julian = unix / DAY_SECS + UNIX_EPOCH_JULIAN;
+
+        // Get mean solar time of the closest solar noon.
+        let day = (julian - J2000 + point.lon / 360.).round();
+        let mean_solar_time = day - point.lon / 360.;
+
+        // Calculate sun's position on its orbit.
+        let anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.);
+        let anomaly_rad = anomaly.to_radians();
+        let center = 1.9148 * anomaly_rad.sin()
+            + 0.02 * (2. * anomaly_rad).sin()
+            + 0.0003 * (3. * anomaly_rad).sin();
+        let longitude = (anomaly + center + 180. + 102.9372).rem_euclid(360.).to_radians();
+
+        // Calculate solar noon.
+        let transit =
+            J2000 + mean_solar_time + 0.0053 * anomaly_rad.sin() - 0.0069 * (2. * longitude).sin();
+
+        // Calculate the hour angle between solar noon and sunrise/sunset.
+        let declination_sin = longitude.sin() * AXIAL_TILT.to_radians().sin();
+        let declination_cos = declination_sin.asin().cos();
+        let lat = point.lat.to_radians();
+        let hour_angle_cos = (HORIZON_ALTITUDE.to_radians().sin() - lat.sin() * declination_sin)
+            / (lat.cos() * declination_cos);
+
+        if hour_angle_cos > 1. {
+            return Self::PolarNight;
+        } else if hour_angle_cos < -1. {
+            return Self::PolarDay;
+        }
+
+        let hour_angle = hour_angle_cos.acos().to_degrees() / 360.;
+        let sunrise = julian_to_system_time(transit - hour_angle);
+        let sunset = julian_to_system_time(transit + hour_angle);
+
+        Self::Day { sunrise, sunset }
+    }
+}
+
+/// Check if the sun is up at a location.
+///
+/// The `margin` extends the day before sunrise and after sunset.
+pub fn is_daytime(point: GeoPoint, time: SystemTime, margin: Duration) -> bool {
+    match SunTimes::new(point, time) {
+        SunTimes::Day { sunrise, sunset } => {
+            let start = sunrise.checked_sub(margin).unwrap_or(sunrise);
+            let end = sunset.checked_add(margin).unwrap_or(sunset);
+            (start..=end).contains(&time)
+        },
+        SunTimes::PolarDay => true,
+        SunTimes::PolarNight => false,
+    }
+}
+
+/// Convert a julian date to system time.
+fn julian_to_system_time(julian: f64) -> SystemTime {
+    let unix = (julian - UNIX_EPOCH_JULIAN) * DAY_SECS;
+    if unix >= 0. {
+        UNIX_EPOCH + Duration::from_secs_f64(unix)
+    } else {
+        UNIX_EPOCH - Duration::from_secs_f64(-unix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Assert that a time is within 5 minutes of a unix timestamp.
+    fn assert_near(time: SystemTime, expected: u64) {
+        let unix = time.duration_since(UNIX_EPOCH).unwrap().as_secs();
+        assert!(unix.abs_diff(expected) < 300, "{unix} is not close to {expected}");
+    }
+
+    #[test]
+    fn berlin_summer_solstice() {
+        let berlin = GeoPoint::new(52.52, 13.405);
+
+        // 2024-06-21T12:00:00Z.
+        let noon = UNIX_EPOCH + Duration::from_secs(1_718_971_200);
+        let (sunrise, sunset) = match SunTimes::new(berlin, noon) {
+            SunTimes::Day { sunrise, sunset } => (sunrise, sunset),
+            times => panic!("expected sunrise and sunset, got {times:?}"),
+        };
+
+        // 2024-06-21T02:43:00Z and 2024-06-21T19:33:00Z.
+        assert_near(sunrise, 1_718_937_780);
+        assert_near(sunset, 1_718_998_380);
+
+        assert!(is_daytime(berlin, noon, Duration::ZERO));
+
+        // 2024-06-21T22:00:00Z.
+        let night = UNIX_EPOCH + Duration::from_secs(1_719_007_200);
+        assert!(!is_daytime(berlin, night, Duration::ZERO));
+        assert!(is_daytime(berlin, night, Duration::from_secs(3 * 3600)));
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        let tromso = GeoPoint::new(69.65, 18.96);
+
+        // 2024-06-21T12:00:00Z.
+        let summer = UNIX_EPOCH + Duration::from_secs(1_718_971_200);
+        assert_eq!(SunTimes::new(tromso, summer), SunTimes::PolarDay);
+
+        // 2024-12-21T12:00:00Z.
+        let winter = UNIX_EPOCH + Duration::from_secs(1_734_782_400);
+        assert_eq!(SunTimes::new(tromso, winter), SunTimes::PolarNight);
+    }
+}
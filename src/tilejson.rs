@@ -0,0 +1,195 @@
+//! TileJSON tile source metadata.
+//!
+//! See _<https://github.com/mapbox/tilejson-spec>_ for the specification.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::Error;
+use crate::config::Tiles as TilesConfig;
+use crate::tiles::MAX_ZOOM;
+
+/// File extensions of vector tile formats.
+const VECTOR_EXTENSIONS: &[&str] = &[".pbf", ".mvt"];
+
+/// Tile source described by a TileJSON document.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TileJson {
+    tiles: Vec<String>,
+    #[serde(default)]
+    minzoom: Option<u8>,
+    #[serde(default)]
+    maxzoom: Option<u8>,
+    #[serde(default)]
+    attribution: Option<String>,
+    #[serde(default)]
+    scheme: Option<String>,
+    #[serde(default, skip_serializing)]
+    vector_layers: Option<serde_json::Value>,
+}
+
+impl TileJson {
+    /// Get the last downloaded copy of a TileJSON document.
+    pub fn cached(url: &str) -> Option<Self> {
+        let data = fs::read(Self::cache_path().ok()?).ok()?;
+        let cached: CachedTileJson = serde_json::from_slice(&data).ok()?;
+        (cached.url == url).then_some(cached.tilejson)
+    }
+
+    /// Download a TileJSON document, caching it for offline use.
+    pub async fn download(client: &Client, url: &str) -> Result<Self, Error> {
+        let response = client.get(url).send().await?.error_for_status()?;
+        let tilejson: Self = response.json().await?;
+
+        // Reject documents without any usable raster tiles.
+        if tilejson.vector_layers.is_some()
+            || tilejson
+                .tiles
+                .iter()
+                .any(|tiles| VECTOR_EXTENSIONS.iter().any(|e| tiles.contains(e)))
+        {
+            return Err(Error::TileJsonVector(url.into()));
+        }
+        if tilejson.tiles.is_empty() {
+            return Err(Error::TileJsonMissingTiles(url.into()));
+        }
+
+        let cached = CachedTileJson { url: url.into(), tilejson };
+        let path = Self::cache_path()?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, serde_json::to_vec(&cached)?).await?;
+
+        Ok(cached.tilejson)
+    }
+
+    /// Override the tile configuration with this tile source.
+    pub fn apply(&self, config: &mut TilesConfig) {
+        if let Some(server) = self.tiles.first() {
+            // Flip the Y coordinate for TMS tile sources.
+            let server = match self.scheme.as_deref() {
+                Some("tms") => server.replace("{y}", "{-y}"),
+                _ => server.clone(),
+            };
+            config.server = Arc::new(server);
+        }
+
+        if let Some(min_zoom) = self.minzoom {
+            config.min_zoom = min_zoom.min(MAX_ZOOM);
+        }
+        if let Some(max_zoom) = self.maxzoom {
+            config.max_zoom = max_zoom.min(MAX_ZOOM);
+        }
+        if config.min_zoom > config.max_zoom {
+            warn!("Ignoring invalid TileJSON zoom range {:?}..={:?}", self.minzoom, self.maxzoom);
+            (config.min_zoom, config.max_zoom) = (0, MAX_ZOOM);
+        }
+
+        if let Some(attribution) = &self.attribution {
+            let (text, url) = parse_attribution(attribution);
+            config.attribution = Arc::new(text);
+            if let Some(url) = url {
+                config.attribution_url = Arc::new(url);
+            }
+        }
+    }
+
+    /// Storage location of the last downloaded TileJSON document.
+    fn cache_path() -> Result<PathBuf, Error> {
+        Ok(dirs::cache_dir().ok_or(Error::MissingCacheDir)?.join("charon/tilejson.json"))
+    }
+}
+
+/// TileJSON document with its source URL.
+#[derive(Serialize, Deserialize)]
+struct CachedTileJson {
+    url: String,
+    tilejson: TileJson,
+}
+
+/// Convert an HTML attribution to plain text.
+///
+/// Returns the attribution text and the target of its first link.
+fn parse_attribution(html: &str) -> (String, Option<String>) {
+    let mut text = String::new();
+    let mut url = None;
+
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+
+        let Some(end) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = &rest[start + 1..start + end];
+        rest = &rest[start + end + 1..];
+
+        // Use the first link's target as attribution URL.
+        if url.is_none()
+            && let Some(href_start) = tag.find("href=\"")
+        {
+            let href = &tag[href_start + 6..];
+            url = href.find('"').map(|href_end| href[..href_end].to_string());
+        }
+    }
+    text.push_str(rest);
+
+    // Decode the entities commonly used in attributions.
+    let text = text
+        .replace("&copy;", "©")
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&");
+
+    (text.split_whitespace().collect::<Vec<_>>().join(" "), url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_attribution() {
+        let html = "<a href=\"https://openmaptiles.org/\" target=\"_blank\">&copy; OpenMapTiles</a> \
+                    <a href=\"https://www.openstreetmap.org/copyright\">&copy;  OSM &amp; co</a>";
+        let (text, url) = parse_attribution(html);
+        assert_eq!(text, "© OpenMapTiles © OSM & co");
+        assert_eq!(url.as_deref(), Some("https://openmaptiles.org/"));
+
+        let (text, url) = parse_attribution("© Plain");
+        assert_eq!(text, "© Plain");
+        assert_eq!(url, None);
+    }
+
+    #[test]
+    fn apply_tilejson() {
+        let tilejson: TileJson = serde_json::from_str(
+            r#"{
+                "tilejson": "3.0.0",
+                "tiles": ["https://a.example.org/{z}/{x}/{y}.png"],
+                "minzoom": 2,
+                "maxzoom": 30,
+                "attribution": "<a href=\"https://example.org\">Example</a>"
+            }"#,
+        )
+        .unwrap();
+
+        let mut config = TilesConfig::default();
+        tilejson.apply(&mut config);
+
+        assert_eq!(*config.server, "https://a.example.org/{z}/{x}/{y}.png");
+        assert_eq!(config.min_zoom, 2);
+        assert_eq!(config.max_zoom, MAX_ZOOM);
+        assert_eq!(*config.attribution, "Example");
+        assert_eq!(*config.attribution_url, "https://example.org");
+    }
+}
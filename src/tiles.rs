@@ -1,31 +1,31 @@
 //! Map tile handling.
 
-use std::collections::{HashMap, LinkedList};
-use std::iter;
-use std::sync::Arc;
-use std::sync::atomic::{AtomicU16, Ordering};
-use std::time::Duration;
+use std::collections::{HashMap, LinkedList, VecDeque};
+use std::mem;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use calloop::channel::Sender;
-use reqwest::Client;
+use charon_map::source::tile_url;
+use charon_map::tiles::corridor_tiles;
+pub use charon_map::tiles::{MAX_ZOOM, TILE_SIZE, TileIndex, TileIter};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Client, StatusCode};
+use skia_safe::image::CachingHint;
 use skia_safe::{Data, Image};
 use sqlx::sqlite::SqliteRow;
 use sqlx::{FromRow, Row};
 use tokio::runtime::Handle as RuntimeHandle;
+use tokio::sync::mpsc;
 use tokio::task::{self, JoinHandle};
 use tokio::time;
-use tracing::error;
+use tracing::{error, warn};
 
 use crate::Error;
-use crate::config::Config;
+use crate::config::{Config, HighDpi};
 use crate::db::Db;
-use crate::geometry::{Point, Size};
-
-/// Width and height of a single tile.
-pub const TILE_SIZE: i32 = 256;
-
-/// Maximum tile zoom level.
-pub const MAX_ZOOM: u8 = 19;
+use crate::geometry::GeoPoint;
 
 /// Name of the tileserver placeholder for offline storage.
 pub const OFFLINE_TILESERVER: &str = "__offline";
@@ -38,14 +38,76 @@ pub const OFFLINE_TILESERVER: &str = "__offline";
 ///
 /// A higher cleanup interval means less frequent database queries to remove old
 /// entries.
-const FS_CACHE_CLEANUP_INTERVAL: u16 = 1_000;
+const FS_CACHE_CLEANUP_INTERVAL: usize = 1_000;
 
 /// Maximum db tile age in seconds before an online refresh is attempted.
 const MAX_FS_CACHE_TIME: u64 = 60 * 60 * 24 * 7;
 
+/// How frequently tile access times are written to the database.
+///
+/// Access times are only used for evicting old tiles from the database, so
+/// they are collected in memory to avoid a database write for every tile load.
+const ACCESS_TIME_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Maximum number of downloaded tiles waiting to be written to the database.
+const WRITE_QUEUE_SIZE: usize = 256;
+
+/// Maximum number of tiles written to the database at once.
+const MAX_WRITE_BATCH: usize = 64;
+
+/// Maximum time tile writes are deferred while tiles are read from the
+/// database.
+const MAX_WRITE_DELAY: Duration = Duration::from_millis(500);
+
+/// Interval for checking if pending tile reads have completed.
+const WRITE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Maximum memory used by decoded tile bitmaps in bytes.
+///
+/// Decoded tiles take ~256kB each, or ~1MB for high DPI tiles, which is
+/// significantly more than their encoded data, so only the most recently drawn
+/// tiles are kept decoded.
+const MAX_DECODED_BYTES: usize = 32 * 1024 * 1024;
+
 /// Time before a failed download will be re-attempted.
 const FAILED_DOWNLOAD_DELAY: Duration = Duration::from_secs(3);
 
+/// Consecutive tileserver failures before downloads are suspended.
+const MAX_TILESERVER_FAILURES: u32 = 5;
+
+/// Time downloads are suspended for after repeated tileserver failures.
+const TILESERVER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Maximum time downloads are suspended for by a rate limiting tileserver.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Pause between tile downloads ahead of a trip.
+///
+/// This avoids flooding the tileserver, since a long route can cover thousands
+/// of tiles and public tileservers don't permit bulk downloads.
+const PREFETCH_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Minimum time between redraws for tile prefetch progress updates.
+const PREFETCH_PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Tile cache lookups since startup.
+static CACHE_LOOKUPS: CacheLookups = CacheLookups {
+    memory_hits: AtomicU64::new(0),
+    memory_misses: AtomicU64::new(0),
+    fs_hits: AtomicU64::new(0),
+    fs_misses: AtomicU64::new(0),
+};
+
+/// Get the number of tile cache hits and misses since startup.
+pub fn cache_lookups() -> CacheLookupCounts {
+    CacheLookupCounts {
+        memory_hits: CACHE_LOOKUPS.memory_hits.load(Ordering::Relaxed),
+        memory_misses: CACHE_LOOKUPS.memory_misses.load(Ordering::Relaxed),
+        fs_hits: CACHE_LOOKUPS.fs_hits.load(Ordering::Relaxed),
+        fs_misses: CACHE_LOOKUPS.fs_misses.load(Ordering::Relaxed),
+    }
+}
+
 /// Map tile cache.
 ///
 /// This manages the local cache for all rendered tiles and can either
@@ -53,6 +115,12 @@ const FAILED_DOWNLOAD_DELAY: Duration = Duration::from_secs(3);
 pub struct Tiles {
     download_state: DownloadState,
     lru_cache: LruCache,
+    prefetch: Option<Prefetch>,
+    prefetch_radius: u32,
+    prefetch_zoom: u8,
+    server: Arc<String>,
+    high_dpi: HighDpi,
+    scale: f64,
 }
 
 impl Tiles {
@@ -63,7 +131,9 @@ impl Tiles {
         config: &Config,
     ) -> Result<Self, Error> {
         // Initialize filesystem cache and remove outdated maps.
-        let fs_cache = FsCache::new(config, db);
+        let server = resolve_resolution(&config.tiles.server, config.tiles.high_dpi, 1.);
+        let fallback_server = resolve_resolution(&config.tiles.server, HighDpi::Never, 1.);
+        let fs_cache = FsCache::new(config, db, server.clone(), fallback_server);
         let cleanup_cache = fs_cache.clone();
         tokio::spawn(async move {
             // Delay initial cache cleanup to avoid locking up the database with an
@@ -73,10 +143,38 @@ impl Tiles {
             cleanup_cache.clean_cache().await
         });
 
-        let download_state =
-            DownloadState { fs_cache, tile_tx, client, server: config.tiles.server.clone() };
+        // Periodically persist tile access times.
+        let flush_cache = fs_cache.clone();
+        tokio::spawn(async move {
+            let mut interval = time::interval(ACCESS_TIME_FLUSH_INTERVAL);
+            loop {
+                interval.tick().await;
+
+                if let Err(err) = flush_cache.flush_access_times().await {
+                    error!("Failed to store tile access times: {err}");
+                }
+            }
+        });
 
-        Ok(Self { download_state, lru_cache: LruCache::new(config.tiles.max_mem_tiles) })
+        let download_state = DownloadState {
+            fs_cache,
+            tile_tx,
+            client,
+            server,
+            breaker: Default::default(),
+            rejected: Default::default(),
+        };
+
+        Ok(Self {
+            download_state,
+            lru_cache: LruCache::new(config.tiles.max_mem_tiles),
+            prefetch_radius: config.tiles.prefetch_radius,
+            prefetch_zoom: config.tiles.prefetch_zoom.min(config.tiles.max_zoom),
+            server: config.tiles.server.clone(),
+            high_dpi: config.tiles.high_dpi,
+            prefetch: Default::default(),
+            scale: 1.,
+        })
     }
 
     /// Get a raster map tile.
@@ -97,183 +195,168 @@ impl Tiles {
     pub fn preload(&mut self, index: TileIndex) {
         // Ignore tile if it is already cached.
         if self.lru_cache.has_tile(&index) {
+            CACHE_LOOKUPS.memory_hits.fetch_add(1, Ordering::Relaxed);
             return;
         }
+        CACHE_LOOKUPS.memory_misses.fetch_add(1, Ordering::Relaxed);
 
         let download_state = self.download_state.clone();
         self.lru_cache.insert(Tile::new(download_state, index));
     }
 
-    /// Handle config updates.
-    pub fn update_config(&mut self, config: &Config) -> bool {
-        let mut dirty = false;
-
-        if self.download_state.server != config.tiles.server {
-            self.download_state.fs_cache.set_tileserver(config.tiles.server.clone());
-            self.download_state.server = config.tiles.server.clone();
-            self.lru_cache.clear();
-            dirty = true;
-        }
-        if self.lru_cache.capacity != config.tiles.max_mem_tiles {
-            self.lru_cache.capacity = config.tiles.max_mem_tiles;
-        }
-        if self.download_state.fs_cache.capacity != config.tiles.max_fs_tiles {
-            self.download_state.fs_cache.capacity = config.tiles.max_fs_tiles;
-        }
-
-        dirty
+    /// Get the number of tiles cached in memory.
+    pub fn cached_count(&self) -> usize {
+        self.lru_cache.tiles.len()
     }
-}
-
-/// Iterator over positioned tiles.
-pub struct TileIter {
-    tile_index: TileIndex,
-    origin: Point,
 
-    max_tiles_x: u32,
-    max_tiles_y: u32,
-    tile_count: u32,
-    tile_size: i32,
-
-    screen_size: Size,
-    scale: f64,
-
-    index: u32,
-}
-
-impl TileIter {
-    pub fn new(screen_size: Size, mut tile_index: TileIndex, offset: Point, scale: f64) -> Self {
-        // Get position of the tile's top-left on the screen.
-        let x_offset = (offset.x as f64 * scale).round() as i32;
-        let y_offset = (offset.y as f64 * scale).round() as i32;
-        let x_origin = screen_size.width as i32 / 2 - x_offset;
-        let y_origin = screen_size.height as i32 / 2 - y_offset;
-
-        // Get top-left tile's indices and offset.
-        let tile_size = (TILE_SIZE as f64 * scale).round() as i32;
-        let x_delta = ((x_origin + tile_size - 1) / tile_size).min(tile_index.x as i32);
-        tile_index.x -= x_delta as u32;
-        let y_delta = ((y_origin + tile_size - 1) / tile_size).min(tile_index.y as i32);
-        tile_index.y -= y_delta as u32;
-        let origin = Point::new(x_origin - x_delta * tile_size, y_origin - y_delta * tile_size);
-
-        // Calculate maximum tile indices.
-
-        let tile_count = 1 << tile_index.z as u32;
+    /// Get the number of cached tiles which are still loading.
+    pub fn loading_count(&self) -> usize {
+        let tiles = self.lru_cache.tiles.values();
+        tiles.filter(|tile| matches!(tile.image, PendingImage::Loading(_))).count()
+    }
 
-        let available_x = screen_size.width as i32 - origin.x;
-        let tiles_x = ((available_x + tile_size - 1) / tile_size) as u32;
-        let max_tiles_x = tiles_x.min(tile_count - tile_index.x);
+    /// Check if downloads are suspended due to tileserver failures.
+    pub fn tileserver_offline(&self) -> bool {
+        self.download_state.breaker.is_open()
+    }
 
-        let available_y = screen_size.height as i32 - origin.y;
-        let tiles_y = ((available_y + tile_size - 1) / tile_size) as u32;
-        let max_tiles_y = tiles_y.min(tile_count - tile_index.y);
+    /// Check if the tileserver rejected requests due to missing authorization.
+    pub fn tileserver_rejected(&self) -> bool {
+        self.download_state.rejected.load(Ordering::Relaxed)
+    }
 
-        Self {
-            max_tiles_x,
-            max_tiles_y,
-            screen_size,
-            tile_index,
-            tile_count,
-            tile_size,
-            origin,
-            scale,
-            index: Default::default(),
+    /// Retry downloads after the tileserver rejected requests.
+    pub fn retry_rejected(&mut self) {
+        if self.download_state.rejected.swap(false, Ordering::Relaxed) {
+            self.lru_cache.clear();
         }
     }
 
-    /// Get iterator over tile indices surrounding the viewport.
-    pub fn border_tiles(&self) -> impl Iterator<Item = TileIndex> {
-        let min_x = self.tile_index.x.saturating_sub(1);
-        let max_x = self.tile_index.x + self.max_tiles_x;
-
-        let min_y = self.tile_index.y.saturating_sub(1);
-        let max_y = self.tile_index.y + self.max_tiles_y;
+    /// Download all tiles around a path to the filesystem cache.
+    ///
+    /// This replaces any previous prefetch which is still in progress.
+    pub fn prefetch(&mut self, path: impl IntoIterator<Item = GeoPoint>) {
+        let radius = self.prefetch_radius as f64;
+        let tiles = corridor_tiles(path, radius, self.prefetch_zoom);
+        if tiles.len() > self.download_state.fs_cache.capacity() as usize {
+            warn!("Prefetching {} tiles, which exceeds `tiles.max_fs_tiles`", tiles.len());
+        }
 
-        let x_range = min_x..(max_x + 1).min(self.tile_count);
-        let y_range = min_y..(max_y + 1).min(self.tile_count);
+        let total = tiles.len();
+        let done = Arc::new(AtomicUsize::new(0));
+        let task_done = done.clone();
+        let state = self.download_state.clone();
+        let task = tokio::spawn(async move {
+            let mut last_progress = Instant::now();
+            for index in tiles {
+                match state.fs_cache.contains(index).await {
+                    Ok(true) => (),
+                    Ok(false) => {
+                        match Tile::fetch(&state, index).await {
+                            Ok(_) => (),
+                            // Abort if no more tiles can be downloaded.
+                            Err(
+                                err @ (Error::DatabaseReadOnly
+                                | Error::TileserverUnavailable
+                                | Error::TileserverRejected),
+                            ) => {
+                                error!("Stopping tile prefetch: {err}");
+                                break;
+                            },
+                            Err(err) => error!("Failed to prefetch tile {index:?}: {err}"),
+                        }
+
+                        time::sleep(PREFETCH_INTERVAL).await;
+                    },
+                    Err(err) => error!("Failed to check cache for tile {index:?}: {err}"),
+                }
+
+                // Notify renderer about the progress, at a limited rate.
+                task_done.fetch_add(1, Ordering::Relaxed);
+                if last_progress.elapsed() >= PREFETCH_PROGRESS_INTERVAL {
+                    last_progress = Instant::now();
+                    let _ = state.tile_tx.send(index);
+                }
+            }
 
-        // Use empty ranges to skip rows/columns outside the tileset.
-        let top_range = if self.tile_index.x > 0 { x_range.clone() } else { 0..0 };
-        let bottom_range = if max_y < self.tile_count { x_range } else { 0..0 };
-        let left_range = if self.tile_index.y > 0 { y_range.clone() } else { 0..0 };
-        let right_range = if max_x < self.tile_count { y_range } else { 0..0 };
+            // Ensure progress is cleared after aborting.
+            task_done.store(total, Ordering::Relaxed);
+            let _ = state.tile_tx.send(TileIndex::default());
+        });
 
-        (top_range.zip(iter::repeat(min_y)))
-            .chain(bottom_range.zip(iter::repeat(max_y)))
-            .chain(iter::repeat(min_x).zip(left_range))
-            .chain(iter::repeat(max_x).zip(right_range))
-            .map(|(x, y)| TileIndex::new(x, y, self.tile_index.z))
+        self.prefetch = Some(Prefetch { task, done, total });
     }
 
-    /// Get physical position of a map point on the screen.
-    ///
-    /// The supplied `tile_index` must have a `z` coordinate matching the
-    /// iterator's `z` coordinate.
-    pub fn screen_point(&self, tile_index: TileIndex, offset: Point) -> Option<Point> {
-        let point = self.tile_point(tile_index, offset);
-
-        // Check whether point is visible.
-        if point.x < 0
-            || point.y < 0
-            || point.x >= self.screen_size.width as i32
-            || point.y >= self.screen_size.height as i32
-        {
-            None
-        } else {
-            Some(point)
+    /// Get the processed and total number of tiles of the active prefetch.
+    pub fn prefetch_progress(&mut self) -> Option<(usize, usize)> {
+        let prefetch = self.prefetch.as_ref()?;
+        let done = prefetch.done.load(Ordering::Relaxed);
+        if done >= prefetch.total {
+            self.prefetch = None;
+            return None;
         }
+        Some((done, prefetch.total))
     }
 
-    /// Get physical position of a map point in screen coordinates.
-    ///
-    /// The supplied `tile_index` must have a `z` coordinate matching the
-    /// iterator's `z` coordinate.
-    pub fn tile_point(&self, tile_index: TileIndex, offset: Point) -> Point {
-        debug_assert_eq!(tile_index.z, self.tile_index.z);
-
-        let x_delta = tile_index.x as i32 - self.tile_index.x as i32;
-        let y_delta = tile_index.y as i32 - self.tile_index.y as i32;
-
-        // Apply fractional scale to tile offset.
-        let mut point = self.origin + offset * self.scale;
-
-        point.x += x_delta * self.tile_size;
-        point.y += y_delta * self.tile_size;
-
-        point
+    /// Check if tiles are being downloaded ahead of a trip.
+    pub fn prefetching(&self) -> bool {
+        self.prefetch.is_some()
     }
 
-    /// Target width and height of the tile.
-    pub fn tile_size(&self) -> i32 {
-        self.tile_size
+    /// Write all queued tiles to the database.
+    pub async fn flush_writes(&self) -> Result<(), Error> {
+        self.download_state.fs_cache.flush_writes().await
     }
-}
 
-impl Iterator for TileIter {
-    type Item = (TileIndex, Point);
+    /// Write pending tile access times to the database.
+    pub async fn flush_access_times(&self) -> Result<(), Error> {
+        self.download_state.fs_cache.flush_access_times().await
+    }
 
-    /// Get the next tile and its screen position.
-    fn next(&mut self) -> Option<Self::Item> {
-        let x_delta = self.index % self.max_tiles_x;
-        let y_delta = self.index / self.max_tiles_x;
+    /// Handle config updates.
+    pub fn update_config(&mut self, config: &Config) -> bool {
+        self.server = config.tiles.server.clone();
+        self.high_dpi = config.tiles.high_dpi;
+        let dirty = self.update_server();
 
-        // Stop if there's no more tiles available.
-        if y_delta >= self.max_tiles_y {
-            return None;
+        if self.lru_cache.capacity != config.tiles.max_mem_tiles {
+            self.lru_cache.capacity = config.tiles.max_mem_tiles;
         }
+        if self.download_state.fs_cache.capacity() != config.tiles.max_fs_tiles {
+            self.download_state.fs_cache.set_capacity(config.tiles.max_fs_tiles);
+        }
+        self.prefetch_radius = config.tiles.prefetch_radius;
+        self.prefetch_zoom = config.tiles.prefetch_zoom.min(config.tiles.max_zoom);
 
-        self.index += 1;
+        dirty
+    }
+
+    /// Update the output's DPI factor, to request tiles in a matching
+    /// resolution.
+    ///
+    /// Returns `true` if the tileserver URL has changed.
+    pub fn set_scale_factor(&mut self, scale: f64) -> bool {
+        self.scale = scale;
+        self.update_server()
+    }
 
-        let tile_x = self.tile_index.x + x_delta;
-        let tile_y = self.tile_index.y + y_delta;
-        let index = TileIndex::new(tile_x, tile_y, self.tile_index.z);
+    /// Switch to the tileserver URL for the current config and resolution.
+    ///
+    /// Returns `true` if the tileserver URL has changed.
+    fn update_server(&mut self) -> bool {
+        let server = resolve_resolution(&self.server, self.high_dpi, self.scale);
+        if self.download_state.server == server {
+            return false;
+        }
 
-        let x = self.origin.x + x_delta as i32 * self.tile_size;
-        let y = self.origin.y + y_delta as i32 * self.tile_size;
-        let point = Point::new(x, y);
+        let fallback_server = resolve_resolution(&self.server, HighDpi::Never, 1.);
+        self.download_state.fs_cache.set_tileserver(server.clone(), fallback_server);
+        self.download_state.server = server;
+        self.download_state.breaker = Default::default();
+        self.download_state.rejected = Default::default();
+        self.lru_cache.clear();
 
-        Some((index, point))
+        true
     }
 }
 
@@ -281,6 +364,8 @@ impl Iterator for TileIter {
 pub struct Tile {
     index: TileIndex,
     image: PendingImage,
+    decoded: Option<Image>,
+    downloading: Arc<AtomicBool>,
 
     download_state: DownloadState,
 }
@@ -299,11 +384,15 @@ impl Tile {
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn new(download_state: DownloadState, index: TileIndex) -> Self {
         // Spawn background task to load image from cache or network.
+        let downloading = Arc::new(AtomicBool::new(false));
+        let task_downloading = downloading.clone();
         let task_download_state = download_state.clone();
         let load_task = tokio::spawn(async move {
             // Try to load the tile from the filesystem DB.
             match task_download_state.fs_cache.get(index).await {
                 Ok(Some(db_tile)) => {
+                    CACHE_LOOKUPS.fs_hits.fetch_add(1, Ordering::Relaxed);
+
                     // If image is outdated, download it in the background.
                     // We still return the outdated image to improve performance.
                     if db_tile.age_secs > MAX_FS_CACHE_TIME {
@@ -316,22 +405,67 @@ impl Tile {
 
                     return Ok(db_tile.image);
                 },
-                Ok(None) => (),
+                Ok(None) => {
+                    CACHE_LOOKUPS.fs_misses.fetch_add(1, Ordering::Relaxed);
+                },
                 Err(err) => error!("Failed to load tile {index:?} from cache: {err}"),
             }
 
             // If it's not in the filesystem cache, start a new download.
+            task_downloading.store(true, Ordering::Relaxed);
             Self::download(task_download_state, index).await
         });
         let image = PendingImage::Loading(Some(load_task));
 
-        Self { download_state, index, image }
+        Self { download_state, downloading, index, image, decoded: None }
+    }
+
+    /// Check if the tile's image is being downloaded from the tileserver.
+    ///
+    /// This is `false` while the tile is loaded from the filesystem cache.
+    pub fn downloading(&self) -> bool {
+        matches!(self.image, PendingImage::Loading(_)) && self.downloading.load(Ordering::Relaxed)
     }
 
     /// Get the tile's image.
+    ///
+    /// This prefers the decoded bitmap, to avoid decoding the tile again when
+    /// it is drawn at a different scale.
     #[cfg_attr(feature = "profiling", profiling::function)]
     pub fn image(&mut self) -> Option<&Image> {
-        // Process asynchronous loads once finished.
+        self.poll();
+
+        match (&self.decoded, &self.image) {
+            (Some(decoded), _) => Some(decoded),
+            (None, PendingImage::Done(image)) => Some(image),
+            _ => None,
+        }
+    }
+
+    /// Decode the tile's image into a bitmap, once it has finished loading.
+    ///
+    /// Returns `true` if a new bitmap was decoded.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn decode(&mut self) -> bool {
+        self.poll();
+
+        if self.decoded.is_some() {
+            return false;
+        }
+
+        let PendingImage::Done(image) = &self.image else { return false };
+        self.decoded = image.make_raster_image(None, CachingHint::Disallow);
+
+        self.decoded.is_some()
+    }
+
+    /// Get the memory used by the tile's decoded bitmap in bytes.
+    fn decoded_size(&self) -> usize {
+        self.decoded.as_ref().map_or(0, |image| image.image_info().compute_min_byte_size())
+    }
+
+    /// Process asynchronous loads once finished.
+    fn poll(&mut self) {
         if let PendingImage::Loading(task) = &mut self.image
             && task.as_ref().is_some_and(|task| task.is_finished())
         {
@@ -340,9 +474,17 @@ impl Tile {
 
             match image {
                 Ok(Ok(image)) => self.image = PendingImage::Done(image),
+                // Stop loading tiles which cannot be stored in the database, or which the
+                // tileserver refuses or is unable to provide.
+                Ok(Err(
+                    Error::DatabaseReadOnly | Error::TileserverRejected | Error::TileNotFound,
+                )) => self.image = PendingImage::Unavailable,
                 // Handle errors for download failures, DB errors are never propagated.
                 Ok(Err(err)) => {
-                    error!("Image download failed: {err}");
+                    // Avoid log spam while downloads are suspended.
+                    if !matches!(err, Error::TileserverUnavailable) {
+                        error!("Image download failed: {err}");
+                    }
 
                     // Retry download with a delay on failure.
                     let download_state = self.download_state.clone();
@@ -356,49 +498,116 @@ impl Tile {
                 Err(err) => error!("Failed to join image future: {err}"),
             }
         }
-
-        match &self.image {
-            PendingImage::Done(image) => Some(image),
-            _ => None,
-        }
     }
 
     /// Load a new tile from the tileserver.
     async fn download(state: DownloadState, index: TileIndex) -> Result<Image, Error> {
-        // Get image from tileserver.
-        let url = state
-            .server
-            .replace("{x}", &index.x.to_string())
-            .replace("{y}", &index.y.to_string())
-            .replace("{z}", &index.z.to_string());
-        let response = state.client.get(&url).send().await?.error_for_status()?;
-        let data = response.bytes().await?;
-
-        // Add tile to filesystem cache.
-        state.fs_cache.insert(index, &data).await?;
+        let (data, url) = Self::fetch(&state, index).await?;
 
         // Try to decode bytes as image.
-        let image =
-            Image::from_encoded(Data::new_copy(&data)).ok_or_else(|| Error::InvalidImage(url))?;
+        let image = Image::from_encoded(data).ok_or_else(|| Error::InvalidImage(url))?;
 
         // Notify renderer about new map download completion.
         let _ = state.tile_tx.send(index);
 
         Ok(image)
     }
+
+    /// Download a tile and add it to the filesystem cache.
+    ///
+    /// Returns the tile's data and its URL.
+    async fn fetch(state: &DownloadState, index: TileIndex) -> Result<(Data, String), Error> {
+        // Only serve cached tiles while the database is read-only.
+        if state.fs_cache.db.read_only() {
+            return Err(Error::DatabaseReadOnly);
+        }
+
+        // Avoid flooding the tileserver once it has rejected our credentials.
+        if state.rejected.load(Ordering::Relaxed) {
+            return Err(Error::TileserverRejected);
+        }
+
+        // Skip the request while the tileserver is considered offline.
+        if !state.breaker.allow(Instant::now()) {
+            return Err(Error::TileserverUnavailable);
+        }
+
+        // Get image from tileserver.
+        let url = tile_url(&state.server, index);
+        let result = state.client.get(&url).send().await;
+        let retry_delay = result.as_ref().ok().and_then(|response| retry_after(response.headers()));
+        let response = match result.and_then(|response| response.error_for_status()) {
+            Ok(response) => response,
+            // Stop downloading if the tileserver requires a valid API key.
+            Err(err)
+                if matches!(
+                    err.status(),
+                    Some(StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN)
+                ) =>
+            {
+                warn!("Tileserver rejected request: {err}");
+
+                // Notify renderer to show the rejection.
+                if !state.rejected.swap(true, Ordering::Relaxed) {
+                    let _ = state.tile_tx.send(index);
+                }
+
+                return Err(Error::TileserverRejected);
+            },
+            // Suspend downloads for the time requested by a rate limiting tileserver.
+            Err(err) if err.status() == Some(StatusCode::TOO_MANY_REQUESTS) => {
+                let delay = retry_delay.unwrap_or(TILESERVER_COOLDOWN).min(MAX_RETRY_AFTER);
+                warn!("Tileserver rate limit exceeded, suspending downloads for {delay:?}");
+
+                // Notify renderer to update the offline indicator.
+                if state.breaker.suspend(Instant::now() + delay) {
+                    let _ = state.tile_tx.send(index);
+                }
+
+                return Err(err.into());
+            },
+            // Missing tiles will never become available, so avoid retrying them.
+            //
+            // This says nothing about the server's availability, so it is neither
+            // counted as success nor failure.
+            Err(err) if err.status() == Some(StatusCode::NOT_FOUND) => {
+                return Err(Error::TileNotFound);
+            },
+            Err(err) => {
+                // Only count failures of the server itself, since other client errors
+                // are specific to the request.
+                let server_failure = err.status().is_none_or(|status| status.is_server_error());
+
+                // Notify renderer to update the offline indicator.
+                if server_failure && state.breaker.record_failure(Instant::now()) {
+                    let _ = state.tile_tx.send(index);
+                }
+
+                return Err(err.into());
+            },
+        };
+        if state.breaker.record_success() {
+            let _ = state.tile_tx.send(index);
+        }
+        let data = Data::new_copy(&response.bytes().await?);
+
+        // Add tile to filesystem cache.
+        state.fs_cache.insert(index, data.clone()).await;
+
+        Ok((data, url))
+    }
 }
 
-/// Index uniquely identifying a map tile.
-#[derive(Default, Hash, PartialEq, Eq, Copy, Clone, Debug)]
-pub struct TileIndex {
-    pub x: u32,
-    pub y: u32,
-    pub z: u8,
+/// Tile downloads ahead of a trip.
+struct Prefetch {
+    task: JoinHandle<()>,
+    done: Arc<AtomicUsize>,
+    total: usize,
 }
 
-impl TileIndex {
-    pub fn new(x: u32, y: u32, z: u8) -> Self {
-        Self { x, y, z }
+impl Drop for Prefetch {
+    fn drop(&mut self) {
+        self.task.abort();
     }
 }
 
@@ -406,6 +615,88 @@ impl TileIndex {
 enum PendingImage {
     Loading(Option<JoinHandle<Result<Image, Error>>>),
     Done(Image),
+    Unavailable,
+}
+
+/// Circuit breaker suspending downloads from a failing tileserver.
+///
+/// After too many consecutive failures, downloads are suspended for a cooldown
+/// period. Once the cooldown has passed, a single probe request is allowed
+/// through to check whether the server is available again.
+#[derive(Default)]
+struct CircuitBreaker {
+    state: Mutex<CircuitBreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Check if a new request should be attempted.
+    fn allow(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.open_until {
+            None => true,
+            Some(open_until) if now < open_until => false,
+            // Allow one probe request for every cooldown period.
+            Some(_) => {
+                state.open_until = Some(now + TILESERVER_COOLDOWN);
+                true
+            },
+        }
+    }
+
+    /// Record a successful request.
+    ///
+    /// Returns `true` if this resumed suspended downloads.
+    fn record_success(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.failures = 0;
+        state.open_until.take().is_some()
+    }
+
+    /// Record a failed request.
+    ///
+    /// Returns `true` if this suspended downloads.
+    fn record_failure(&self, now: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        state.failures += 1;
+
+        if state.failures < MAX_TILESERVER_FAILURES {
+            return false;
+        }
+
+        let was_open = state.open_until.is_some();
+        state.open_until = Some(now + TILESERVER_COOLDOWN);
+        !was_open
+    }
+
+    /// Suspend downloads until a specific time.
+    ///
+    /// Returns `true` if this suspended downloads.
+    fn suspend(&self, until: Instant) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let was_open = state.open_until.is_some();
+        state.open_until = Some(state.open_until.map_or(until, |open_until| open_until.max(until)));
+        !was_open
+    }
+
+    /// Check if downloads are currently suspended.
+    fn is_open(&self) -> bool {
+        self.state.lock().unwrap().open_until.is_some()
+    }
+}
+
+/// Get the delay requested by a tileserver's `Retry-After` header.
+///
+/// Only delays in seconds are supported, since tileservers rarely send dates.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?;
+    value.trim().parse().ok().map(Duration::from_secs)
+}
+
+/// Mutable circuit breaker state.
+#[derive(Default)]
+struct CircuitBreakerState {
+    open_until: Option<Instant>,
+    failures: u32,
 }
 
 /// An LRU cache for tiles.
@@ -413,12 +704,20 @@ enum PendingImage {
 struct LruCache {
     tiles: HashMap<TileIndex, Tile>,
     lru: LinkedList<TileIndex>,
+    decoded: VecDeque<TileIndex>,
+    decoded_bytes: usize,
     capacity: usize,
 }
 
 impl LruCache {
     fn new(capacity: usize) -> Self {
-        Self { capacity, tiles: Default::default(), lru: Default::default() }
+        Self {
+            capacity,
+            tiles: Default::default(),
+            lru: Default::default(),
+            decoded: Default::default(),
+            decoded_bytes: Default::default(),
+        }
     }
 
     /// Add a new tile to the cache.
@@ -431,7 +730,12 @@ impl LruCache {
             // Remove oldest entry if cache is full.
             while self.tiles.len() >= self.capacity {
                 let lru = self.lru.pop_back().unwrap();
-                self.tiles.remove(&lru);
+                if let Some(tile) = self.tiles.remove(&lru)
+                    && tile.decoded.is_some()
+                {
+                    self.decoded.retain(|decoded| *decoded != lru);
+                    self.decoded_bytes -= tile.decoded_size();
+                }
             }
 
             // Add tile to the cache.
@@ -448,7 +752,31 @@ impl LruCache {
     }
 
     /// Load a tile from the cache.
+    ///
+    /// This decodes the tile's image once it has finished loading, releasing
+    /// the least recently used bitmaps beyond `MAX_DECODED_BYTES`.
     fn get(&mut self, index: &TileIndex) -> Option<&mut Tile> {
+        let tile = self.tiles.get_mut(index)?;
+        if tile.decode() {
+            self.decoded_bytes += tile.decoded_size();
+            self.decoded.push_back(*index);
+
+            // Always keep the new bitmap, even if it exceeds the limit by itself.
+            while self.decoded_bytes > MAX_DECODED_BYTES && self.decoded.len() > 1 {
+                let oldest = self.decoded.pop_front().unwrap();
+                if let Some(tile) = self.tiles.get_mut(&oldest) {
+                    self.decoded_bytes -= tile.decoded_size();
+                    tile.decoded = None;
+                }
+            }
+        } else if tile.decoded.is_some()
+            && let Some(position) = self.decoded.iter().position(|decoded| decoded == index)
+        {
+            // Mark the bitmap as most recently used.
+            self.decoded.remove(position);
+            self.decoded.push_back(*index);
+        }
+
         self.tiles.get_mut(index)
     }
 
@@ -456,74 +784,249 @@ impl LruCache {
     fn clear(&mut self) {
         self.tiles.clear();
         self.lru.clear();
+        self.decoded.clear();
+        self.decoded_bytes = 0;
     }
 }
 
 /// A filesystem cach for tiles.
+#[derive(Clone)]
 pub struct FsCache {
     db: Db,
-    last_cleanup: Arc<AtomicU16>,
+    access_times: Arc<Mutex<AccessTimes>>,
+    pending_writes: Arc<Mutex<PendingWrites>>,
+    write_tx: mpsc::Sender<TileKey>,
     tileserver: Arc<String>,
-    capacity: u32,
+    fallback_tileserver: Arc<String>,
+    capacity: Arc<AtomicU32>,
 }
 
 impl FsCache {
     #[cfg_attr(feature = "profiling", profiling::function)]
-    fn new(config: &Config, db: Db) -> Self {
-        Self {
+    fn new(
+        config: &Config,
+        db: Db,
+        tileserver: Arc<String>,
+        fallback_tileserver: Arc<String>,
+    ) -> Self {
+        let (write_tx, write_rx) = mpsc::channel(WRITE_QUEUE_SIZE);
+        let fs_cache = Self {
+            fallback_tileserver,
+            tileserver,
+            write_tx,
             db,
-            last_cleanup: Arc::new(AtomicU16::new(0)),
-            tileserver: config.tiles.server.clone(),
-            capacity: config.tiles.max_fs_tiles,
-        }
+            capacity: Arc::new(AtomicU32::new(config.tiles.max_fs_tiles)),
+            pending_writes: Default::default(),
+            access_times: Default::default(),
+        };
+
+        // Write downloaded tiles on a dedicated task, to avoid delaying tile loads.
+        tokio::spawn(fs_cache.clone().run_writer(write_rx));
+
+        fs_cache
     }
 
     /// Add a new tile to the cache.
-    async fn insert(&self, index: TileIndex, data: &[u8]) -> Result<(), Error> {
-        self.db.insert_tiles(&self.tileserver, &[(index, data)]).await?;
+    ///
+    /// The tile is available for reads immediately, but only written to the
+    /// database in batches once there are no more pending reads.
+    async fn insert(&self, index: TileIndex, data: Data) {
+        let key = (self.tileserver.clone(), index);
+        self.pending_writes.lock().unwrap().insert(key.clone(), data);
+
+        // Wait for queue capacity, to limit the memory used by pending writes.
+        if self.write_tx.send(key).await.is_err() {
+            error!("Tile cache writer has stopped");
+        }
+    }
+
+    /// Write queued tiles to the database.
+    async fn run_writer(self, mut write_rx: mpsc::Receiver<TileKey>) {
+        let mut inserts = 0;
+        let mut batch = Vec::with_capacity(MAX_WRITE_BATCH);
+        while write_rx.recv_many(&mut batch, MAX_WRITE_BATCH).await > 0 {
+            // Prioritize tile reads, to avoid delaying the rendering of cached tiles.
+            let start = Instant::now();
+            while self.db.active_queries() > 0 && start.elapsed() < MAX_WRITE_DELAY {
+                time::sleep(WRITE_POLL_INTERVAL).await;
+            }
+
+            match self.write_tiles(batch.drain(..)).await {
+                Ok(count) => inserts += count,
+                Err(err) => error!("Failed to write tiles to cache: {err}"),
+            }
 
-        // Cleanup cache every `FS_CACHE_CLEANUP_INTERVAL` inserts.
-        if self.last_cleanup.fetch_add(1, Ordering::Relaxed) >= FS_CACHE_CLEANUP_INTERVAL {
-            self.last_cleanup.store(0, Ordering::Relaxed);
+            // Cleanup cache every `FS_CACHE_CLEANUP_INTERVAL` inserts.
+            if inserts >= FS_CACHE_CLEANUP_INTERVAL {
+                inserts = 0;
 
-            // Report cache errors immediately, to avoid failing the download.
-            if let Err(err) = self.clean_cache().await {
-                error!("Failed tile DB cleanup: {err}");
+                if let Err(err) = self.clean_cache().await {
+                    error!("Failed tile DB cleanup: {err}");
+                }
             }
         }
+    }
 
+    /// Write all queued tiles to the database.
+    async fn flush_writes(&self) -> Result<(), Error> {
+        let keys: Vec<_> = self.pending_writes.lock().unwrap().keys().cloned().collect();
+        self.write_tiles(keys).await?;
         Ok(())
     }
 
+    /// Write pending tiles in a single query for each tileserver.
+    ///
+    /// Returns the number of written tiles.
+    async fn write_tiles(&self, keys: impl IntoIterator<Item = TileKey>) -> Result<usize, Error> {
+        // Collect data of tiles which have not been written yet.
+        let mut tileservers: HashMap<Arc<String>, Vec<(TileIndex, Data)>> = HashMap::new();
+        {
+            let pending_writes = self.pending_writes.lock().unwrap();
+            for key in keys {
+                if let Some(data) = pending_writes.get(&key) {
+                    tileservers.entry(key.0).or_default().push((key.1, data.clone()));
+                }
+            }
+        }
+
+        let mut result = Ok(0);
+        for (tileserver, tiles) in &tileservers {
+            let tiles: Vec<_> =
+                tiles.iter().map(|(index, data)| (*index, data.as_bytes())).collect();
+            match self.db.insert_tiles(tileserver, &tiles).await {
+                Ok(()) => result = result.map(|count| count + tiles.len()),
+                Err(err) => result = Err(err),
+            }
+        }
+
+        // Stop serving tiles from memory, even if the write failed.
+        let mut pending_writes = self.pending_writes.lock().unwrap();
+        for (tileserver, tiles) in tileservers {
+            for (index, _) in tiles {
+                pending_writes.remove(&(tileserver.clone(), index));
+            }
+        }
+
+        result
+    }
+
+    /// Check if a tile is in the cache, without loading it.
+    async fn contains(&self, index: TileIndex) -> Result<bool, Error> {
+        // Consider tiles queued for writing as cached.
+        let key = (self.tileserver.clone(), index);
+        if self.pending_writes.lock().unwrap().contains_key(&key) {
+            return Ok(true);
+        }
+
+        #[rustfmt::skip]
+        let exists = sqlx::query_scalar(
+            "SELECT EXISTS(SELECT 1 FROM tile
+                WHERE tileserver IN ($1, $2, $3)
+                AND x = $4 AND y = $5 AND z = $6)"
+        )
+        .bind(&*self.tileserver)
+        .bind(&*self.fallback_tileserver)
+        .bind(OFFLINE_TILESERVER)
+        .bind(index.x)
+        .bind(index.y)
+        .bind(index.z)
+        .fetch_one(&self.db.pool().await)
+        .await?;
+
+        Ok(exists)
+    }
+
     /// Read a tile from the cache.
     async fn get(&self, index: TileIndex) -> Result<Option<DbTile>, Error> {
-        // Get both online tileserver's and offline tile.
+        // Serve tiles which are still queued for writing from memory.
+        let key = (self.tileserver.clone(), index);
+        let pending = self.pending_writes.lock().unwrap().get(&key).cloned();
+        if let Some(image) = pending.and_then(Image::from_encoded) {
+            let tileserver = self.tileserver.to_string();
+            return Ok(Some(DbTile { tileserver, age_secs: 0, image }));
+        }
+
+        // Get the online tileserver's, its low-DPI variant's and the offline tile.
         #[rustfmt::skip]
         let data: Vec<DbTile> = sqlx::query_as(
-            "UPDATE tile SET atime = unixepoch() \
-                WHERE tileserver IN ($1, $2) \
-                   AND x = $3 AND y = $4 and z = $5 \
-             RETURNING unixepoch() - ctime as age_secs, data, tileserver",
+            "SELECT unixepoch() - ctime as age_secs, data, tileserver FROM tile \
+                WHERE tileserver IN ($1, $2, $3) \
+                   AND x = $4 AND y = $5 and z = $6",
         )
         .bind(&*self.tileserver)
+        .bind(&*self.fallback_tileserver)
         .bind(OFFLINE_TILESERVER)
         .bind(index.x)
         .bind(index.y)
         .bind(index.z)
-        .fetch_all(self.db.pool().await)
+        .fetch_all(&self.db.pool().await)
         .await?;
 
-        // Filter offline tile if online tileserver has tile.
-        let mut iter = data.into_iter();
-        let first = iter.next();
-        let tile = iter.next().filter(|tile| tile.tileserver != OFFLINE_TILESERVER).or(first);
+        // Prefer the current tileserver's tile over low-DPI and offline tiles.
+        //
+        // This keeps tiles downloaded at a different resolution available,
+        // since the `{r}` variable changes the tileserver URL.
+        let tile = data.into_iter().min_by_key(|tile| {
+            if tile.tileserver == *self.tileserver {
+                0
+            } else if tile.tileserver == *self.fallback_tileserver {
+                1
+            } else {
+                2
+            }
+        });
+
+        // Defer the access time update until the next flush.
+        if let Some(tile) = &tile {
+            let tileserver = if tile.tileserver == *self.fallback_tileserver {
+                self.fallback_tileserver.clone()
+            } else {
+                self.tileserver.clone()
+            };
+
+            let atime = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+            let mut access_times = self.access_times.lock().unwrap();
+            access_times.insert((tileserver, index), atime as i64);
+        }
 
         Ok(tile)
     }
 
+    /// Write all pending tile access times in a single transaction.
+    async fn flush_access_times(&self) -> Result<(), Error> {
+        let access_times = mem::take(&mut *self.access_times.lock().unwrap());
+        if access_times.is_empty() || self.db.read_only() {
+            return Ok(());
+        }
+
+        let mut transaction = self.db.write_pool().await?.begin().await?;
+        for ((tileserver, index), atime) in access_times {
+            #[rustfmt::skip]
+            sqlx::query(
+                "UPDATE tile SET atime = MAX(atime, $1) \
+                    WHERE tileserver IN ($2, $3) \
+                       AND x = $4 AND y = $5 and z = $6",
+            )
+            .bind(atime)
+            .bind(&*tileserver)
+            .bind(OFFLINE_TILESERVER)
+            .bind(index.x)
+            .bind(index.y)
+            .bind(index.z)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await?;
+
+        Ok(())
+    }
+
     /// Perform filesystem cache cleanup.
     async fn clean_cache(&self) -> Result<(), Error> {
-        let pool = self.db.pool().await;
+        // Ensure recently used tiles are not considered for deletion.
+        self.flush_access_times().await?;
+
+        let pool = &self.db.write_pool().await?;
 
         // Delete least recently used tiles beyond the tile capacity.
         #[rustfmt::skip]
@@ -535,7 +1038,7 @@ impl FsCache {
              )",
         )
         .bind(OFFLINE_TILESERVER)
-        .bind(self.capacity)
+        .bind(self.capacity())
         .execute(pool)
         .await?;
 
@@ -543,24 +1046,51 @@ impl FsCache {
     }
 
     /// Update the tileserver URL.
-    fn set_tileserver(&mut self, tileserver: Arc<String>) {
+    ///
+    /// Tiles of the `fallback_tileserver` are used when the tileserver has no
+    /// cached tile.
+    fn set_tileserver(&mut self, tileserver: Arc<String>, fallback_tileserver: Arc<String>) {
+        self.fallback_tileserver = fallback_tileserver;
         self.tileserver = tileserver;
     }
-}
 
-impl Clone for FsCache {
-    fn clone(&self) -> Self {
-        let last_cleanup = self.last_cleanup.load(Ordering::Relaxed);
+    /// Get the maximum number of online tiles.
+    fn capacity(&self) -> u32 {
+        self.capacity.load(Ordering::Relaxed)
+    }
 
-        Self {
-            last_cleanup: Arc::new(AtomicU16::new(last_cleanup)),
-            tileserver: self.tileserver.clone(),
-            capacity: self.capacity,
-            db: self.db.clone(),
-        }
+    /// Update the maximum number of online tiles.
+    fn set_capacity(&self, capacity: u32) {
+        self.capacity.store(capacity, Ordering::Relaxed);
     }
 }
 
+/// Tileserver and index identifying a cached tile.
+type TileKey = (Arc<String>, TileIndex);
+
+/// Downloaded tiles waiting to be written to the database.
+type PendingWrites = HashMap<TileKey, Data>;
+
+/// Atomic tile cache lookup counters.
+struct CacheLookups {
+    memory_hits: AtomicU64,
+    memory_misses: AtomicU64,
+    fs_hits: AtomicU64,
+    fs_misses: AtomicU64,
+}
+
+/// Tile cache hits and misses.
+#[derive(Copy, Clone, Default, Debug)]
+pub struct CacheLookupCounts {
+    pub memory_hits: u64,
+    pub memory_misses: u64,
+    pub fs_hits: u64,
+    pub fs_misses: u64,
+}
+
+/// Unix time of the last access for each tileserver's tile.
+type AccessTimes = HashMap<TileKey, i64>;
+
 /// Tile data retrieved from the database.
 struct DbTile {
     tileserver: String,
@@ -581,12 +1111,31 @@ impl FromRow<'_, SqliteRow> for DbTile {
     }
 }
 
+/// Resolve the high-DPI `{r}` variable of a tileserver URL template.
+///
+/// High-DPI tiles are requested with the common `@2x` suffix. Since they cover
+/// the same area as regular tiles, only their image resolution differs.
+fn resolve_resolution(template: &Arc<String>, high_dpi: HighDpi, scale: f64) -> Arc<String> {
+    if !template.contains("{r}") {
+        return template.clone();
+    }
+
+    let suffix = match high_dpi {
+        HighDpi::Auto if scale > 1. => "@2x",
+        HighDpi::Always => "@2x",
+        HighDpi::Auto | HighDpi::Never => "",
+    };
+    Arc::new(template.replace("{r}", suffix))
+}
+
 /// State used in the download future.
 ///
 /// Since this state is shared between all download futures, it **must** be
 /// cheap to clone.
 #[derive(Clone)]
 struct DownloadState {
+    breaker: Arc<CircuitBreaker>,
+    rejected: Arc<AtomicBool>,
     tile_tx: Sender<TileIndex>,
     server: Arc<String>,
     fs_cache: FsCache,
@@ -598,83 +1147,78 @@ mod tests {
     use super::*;
 
     #[test]
-    fn single_tile_iter() {
-        let size = Size::new(TILE_SIZE as u32, TILE_SIZE as u32);
-        let index = TileIndex::new(8504, 5473, 14);
-        let offset = Point::new(128, 128);
-
-        let mut iter = TileIter::new(size, index, offset, 1.);
-
-        let (iter_index, point) = iter.next().unwrap();
-        assert_eq!(iter_index, index);
-        assert_eq!(point, Point::new(0, 0));
-
-        assert_eq!(iter.next(), None);
+    fn high_dpi_templates() {
+        let template = Arc::new(String::from("/{z}/{x}/{y}{r}.png"));
+        assert_eq!(*resolve_resolution(&template, HighDpi::Auto, 1.), "/{z}/{x}/{y}.png");
+        assert_eq!(*resolve_resolution(&template, HighDpi::Auto, 1.5), "/{z}/{x}/{y}@2x.png");
+        assert_eq!(*resolve_resolution(&template, HighDpi::Always, 1.), "/{z}/{x}/{y}@2x.png");
+        assert_eq!(*resolve_resolution(&template, HighDpi::Never, 2.), "/{z}/{x}/{y}.png");
+
+        let template = Arc::new(String::from("/{z}/{x}/{y}.png"));
+        let resolved = resolve_resolution(&template, HighDpi::Always, 2.);
+        assert!(Arc::ptr_eq(&resolved, &template));
     }
 
     #[test]
-    fn partial_tiles_iter() {
-        let size = Size::new(300, 300);
-        let index = TileIndex::new(8504, 5473, 14);
-        let offset = Point::new(128, 128);
-
-        let mut iter = TileIter::new(size, index, offset, 1.);
+    fn circuit_breaker() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
 
-        for i in 0..9 {
-            let tile_x = (8503 + i % 3) as u32;
-            let tile_y = (5472 + i / 3) as u32;
-
-            let x = -234 + i % 3 * TILE_SIZE;
-            let y = -234 + i / 3 * TILE_SIZE;
-
-            let (iter_index, point) = iter.next().unwrap();
-            assert_eq!(iter_index, TileIndex::new(tile_x, tile_y, index.z));
-            assert_eq!(point, Point::new(x, y));
+        // Failures below the limit keep downloads going.
+        for _ in 1..MAX_TILESERVER_FAILURES {
+            assert!(!breaker.record_failure(now));
         }
+        assert!(breaker.allow(now));
+
+        // Reaching the limit suspends downloads.
+        assert!(breaker.record_failure(now));
+        assert!(breaker.is_open());
+        assert!(!breaker.allow(now));
+
+        // A single probe is allowed after the cooldown.
+        let later = now + TILESERVER_COOLDOWN;
+        assert!(breaker.allow(later));
+        assert!(!breaker.allow(later));
+
+        // Failed probes keep downloads suspended.
+        assert!(!breaker.record_failure(later));
+        assert!(breaker.is_open());
+
+        // Successful probes resume downloads.
+        let later = later + TILESERVER_COOLDOWN;
+        assert!(breaker.allow(later));
+        assert!(breaker.record_success());
+        assert!(!breaker.is_open());
+        assert!(breaker.allow(later));
     }
 
     #[test]
-    fn map_border_iter() {
-        let size = Size::new(300, 300);
-        let index = TileIndex::new(0, 0, 0);
-        let offset = Point::new(128, 128);
-
-        let mut iter = TileIter::new(size, index, offset, 1.);
-
-        let (iter_index, point) = iter.next().unwrap();
-        assert_eq!(iter_index, index);
-        assert_eq!(point, Point::new(22, 22));
-
-        assert_eq!(iter.next(), None);
+    fn circuit_breaker_suspend() {
+        let breaker = CircuitBreaker::default();
+        let now = Instant::now();
+
+        // Suspending holds downloads until the requested time.
+        let until = now + Duration::from_secs(120);
+        assert!(breaker.suspend(until));
+        assert!(!breaker.allow(now + TILESERVER_COOLDOWN));
+        assert!(breaker.allow(until));
+
+        // Shorter suspensions do not cut an active one short.
+        let until = now + Duration::from_secs(300);
+        assert!(!breaker.suspend(until));
+        assert!(!breaker.suspend(now));
+        assert!(!breaker.allow(until - TILESERVER_COOLDOWN));
     }
 
     #[test]
-    fn surrounding_tiles() {
-        let size = Size::new(TILE_SIZE as u32, TILE_SIZE as u32);
-        let index = TileIndex::new(1, 1, 14);
-        let offset = Point::new(128, 128);
-
-        let iter = TileIter::new(size, index, offset, 1.);
-        let mut border_tiles = iter.border_tiles();
-
-        // Top row.
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 0, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(1, 0, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 0, 14));
-
-        // Bottom row.
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 2, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(1, 2, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 2, 14));
-
-        // Left column.
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 0, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 1, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(0, 2, 14));
-
-        // Right column.
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 0, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 1, 14));
-        assert_eq!(border_tiles.next().unwrap(), TileIndex::new(2, 2, 14));
+    fn retry_after_header() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers), None);
+
+        headers.insert(RETRY_AFTER, "120".parse().unwrap());
+        assert_eq!(retry_after(&headers), Some(Duration::from_secs(120)));
+
+        headers.insert(RETRY_AFTER, "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap());
+        assert_eq!(retry_after(&headers), None);
     }
 }
@@ -0,0 +1,426 @@
+//! GPS track recording.
+
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+
+use calloop::channel::Sender;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{error, info};
+
+use crate::Error;
+use crate::db::{Db, Track, TrackPoint};
+use crate::geometry::GeoPoint;
+
+/// Minimum distance between two recorded track points in meters.
+///
+/// This avoids filling the track with GPS jitter while standing still.
+const MIN_POINT_DISTANCE: u32 = 5;
+
+/// GPS track recorder.
+///
+/// All database operations are processed in order by a background task,
+/// which reports the results to the UI through a [`TrackEvent`] channel.
+pub struct TrackRecorder {
+    tx: UnboundedSender<Command>,
+    recording: Recording,
+}
+
+impl TrackRecorder {
+    pub fn new(db: Db, event_tx: Sender<TrackEvent>) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(process_commands(db, rx, event_tx));
+
+        Self { tx, recording: Recording::Stopped }
+    }
+
+    /// Check whether a track is currently being recorded.
+    ///
+    /// This is already `true` while the recording is being started.
+    pub fn recording(&self) -> bool {
+        self.recording != Recording::Stopped
+    }
+
+    /// Get the ID of the track currently being recorded.
+    pub fn active_track(&self) -> Option<i64> {
+        match self.recording {
+            Recording::Active(id) => Some(id),
+            Recording::Starting | Recording::Stopped => None,
+        }
+    }
+
+    /// Start recording a new track.
+    ///
+    /// The recording is confirmed through [`TrackEvent::Started`].
+    pub fn start(&mut self) {
+        self.recording = Recording::Starting;
+        let _ = self.tx.send(Command::Start);
+    }
+
+    /// Handle the result of starting a recording.
+    ///
+    /// Returns `false` if the recording could not be started.
+    pub fn started(&mut self, id: Option<i64>) -> bool {
+        // Ignore recordings which were stopped while starting.
+        if self.recording == Recording::Starting {
+            self.recording = id.map_or(Recording::Stopped, Recording::Active);
+        }
+        self.recording()
+    }
+
+    /// Stop the active recording.
+    pub fn stop(&mut self) {
+        self.recording = Recording::Stopped;
+        let _ = self.tx.send(Command::Stop);
+    }
+
+    /// Add a GPS location to the active recording.
+    pub fn update(&mut self, point: GeoPoint) {
+        if !self.recording() {
+            return;
+        }
+
+        let time = UNIX_EPOCH.elapsed().map_or(0, |elapsed| elapsed.as_secs() as i64);
+        let _ = self.tx.send(Command::Point(TrackPoint { point, time }));
+    }
+
+    /// Reload the list of recorded tracks.
+    pub fn reload(&self) {
+        let _ = self.tx.send(Command::Reload);
+    }
+
+    /// Delete a recorded track.
+    ///
+    /// The active recording cannot be deleted.
+    pub fn delete(&self, track: Track) {
+        let _ = self.tx.send(Command::Delete(track));
+    }
+
+    /// Save a track as GPX file in the user's download directory.
+    pub fn export(&self, track: Track) {
+        let _ = self.tx.send(Command::Export(track));
+    }
+
+    /// Load a track's points for playback.
+    ///
+    /// The points are delivered through [`TrackEvent::Playback`].
+    pub fn play(&self, track: Track) {
+        let _ = self.tx.send(Command::Play(track));
+    }
+}
+
+/// Track recorder update.
+pub enum TrackEvent {
+    /// All recorded tracks, most recent first.
+    Tracks(Vec<Track>),
+    /// ID of the started recording, or `None` if it could not be started.
+    Started(Option<i64>),
+    /// All points of a track requested for playback.
+    Playback(Track, Vec<TrackPoint>),
+    /// Result of a user action.
+    Status(String),
+}
+
+/// Recording state of the UI.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum Recording {
+    Stopped,
+    Starting,
+    Active(i64),
+}
+
+/// Background task operation.
+enum Command {
+    Start,
+    Stop,
+    Point(TrackPoint),
+    Reload,
+    Delete(Track),
+    Export(Track),
+    Play(Track),
+}
+
+/// Track currently being recorded.
+struct ActiveTrack {
+    id: i64,
+    last_point: Option<GeoPoint>,
+}
+
+/// Process recorder commands until the recorder is dropped.
+async fn process_commands(
+    db: Db,
+    mut rx: UnboundedReceiver<Command>,
+    event_tx: Sender<TrackEvent>,
+) {
+    let mut active_track: Option<ActiveTrack> = None;
+
+    while let Some(command) = rx.recv().await {
+        match command {
+            Command::Start => match db.insert_track().await {
+                Ok(id) => {
+                    active_track = Some(ActiveTrack { id, last_point: None });
+                    let _ = event_tx.send(TrackEvent::Started(Some(id)));
+                },
+                Err(err) => {
+                    error!("Failed to start track recording: {err}");
+                    let _ = event_tx.send(TrackEvent::Started(None));
+                    let _ = event_tx.send(TrackEvent::Status(format!("Recording failed: {err}")));
+                    continue;
+                },
+            },
+            Command::Stop => active_track = None,
+            Command::Point(track_point) => {
+                let track = match &mut active_track {
+                    Some(track) => track,
+                    None => continue,
+                };
+
+                // Skip points too close to the last recorded location.
+                let distance = track.last_point.map_or(0, |last| last.distance(track_point.point));
+                if track.last_point.is_some() && distance < MIN_POINT_DISTANCE {
+                    continue;
+                }
+
+                match db.insert_track_point(track.id, track_point, distance).await {
+                    Ok(()) => track.last_point = Some(track_point.point),
+                    Err(err) => error!("Failed to record track point: {err}"),
+                }
+
+                // Only update the track list when starting or stopping a recording.
+                continue;
+            },
+            Command::Reload => (),
+            Command::Delete(track) => {
+                if active_track.as_ref().is_some_and(|active| active.id == track.id) {
+                    continue;
+                }
+
+                if let Err(err) = db.delete_track(track.id).await {
+                    error!("Failed to delete track: {err}");
+                }
+            },
+            Command::Export(track) => {
+                let status = match export(&db, track).await {
+                    Ok(path) => {
+                        info!("Saved GPS track to {path:?}");
+                        format!("Track saved to {}", path.display())
+                    },
+                    Err(err) => {
+                        error!("Could not save GPS track: {err}");
+                        format!("Track export failed: {err}")
+                    },
+                };
+                let _ = event_tx.send(TrackEvent::Status(status));
+                continue;
+            },
+            Command::Play(track) => {
+                match db.track_points(track.id).await {
+                    Ok(points) => {
+                        let _ = event_tx.send(TrackEvent::Playback(track, points));
+                    },
+                    Err(err) => {
+                        error!("Failed to load track points: {err}");
+                        let status = format!("Loading track failed: {err}");
+                        let _ = event_tx.send(TrackEvent::Status(status));
+                    },
+                }
+                continue;
+            },
+        }
+
+        match db.tracks().await {
+            Ok(tracks) => {
+                let _ = event_tx.send(TrackEvent::Tracks(tracks));
+            },
+            Err(err) => error!("Failed to load recorded tracks: {err}"),
+        }
+    }
+}
+
+/// Write a track to the user's download directory.
+async fn export(db: &Db, track: Track) -> Result<PathBuf, Error> {
+    let dir = dirs::download_dir().or_else(dirs::home_dir).ok_or(Error::MissingExportDir)?;
+    let path = dir.join(format!("charon-track-{}.gpx", track.ctime));
+
+    let points = db.track_points(track.id).await?;
+    tokio::fs::write(&path, gpx(track, &points)).await?;
+
+    Ok(path)
+}
+
+/// Convert a track to a GPX 1.1 document.
+fn gpx(track: Track, points: &[TrackPoint]) -> String {
+    let mut gpx = String::new();
+
+    gpx.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    gpx.push_str("<gpx version=\"1.1\" creator=\"Charon\" ");
+    gpx.push_str("xmlns=\"http://www.topografix.com/GPX/1/1\">\n");
+    gpx.push_str("  <trk>\n    <name>Charon track ");
+    format_date(&mut gpx, track.ctime);
+    gpx.push_str("</name>\n    <trkseg>\n");
+
+    for point in points {
+        let (lat, lon) = (point.point.lat, point.point.lon);
+        let _ = write!(gpx, "      <trkpt lat=\"{lat:.7}\" lon=\"{lon:.7}\"><time>");
+        format_timestamp(&mut gpx, point.time);
+        gpx.push_str("</time></trkpt>\n");
+    }
+
+    gpx.push_str("    </trkseg>\n  </trk>\n</gpx>\n");
+
+    gpx
+}
+
+/// Format a unix time as UTC date and time in minutes.
+pub fn format_date(w: &mut impl Write, time: i64) {
+    let (year, month, day, hour, minute, _) = utc_datetime(time);
+    let _ = write!(w, "{year}-{month:0>2}-{day:0>2} {hour:0>2}:{minute:0>2}");
+}
+
+/// Format a duration in seconds as hours, minutes and seconds.
+pub fn format_duration(w: &mut impl Write, secs: i64) {
+    let secs = secs.max(0);
+    let (hours, minutes, seconds) = (secs / 3_600, secs % 3_600 / 60, secs % 60);
+    let _ = write!(w, "{hours}:{minutes:0>2}:{seconds:0>2}");
+}
+
+/// Get the recorded location `time` seconds after the start of a track.
+///
+/// Locations between two recorded points are interpolated linearly. Times
+/// beyond the end of the track return its last point.
+pub fn position_at(points: &[TrackPoint], time: f64) -> Option<GeoPoint> {
+    let start = points.first()?.time;
+    let time = start as f64 + time.max(0.);
+
+    // Find the first point recorded after the requested time.
+    let index = points.partition_point(|point| (point.time as f64) <= time);
+    let (prev, next) = match (index.checked_sub(1), points.get(index)) {
+        (Some(prev), Some(next)) => (points[prev], *next),
+        (Some(prev), None) => return Some(points[prev].point),
+        (None, _) => return Some(points[0].point),
+    };
+
+    let fraction = (time - prev.time as f64) / (next.time - prev.time) as f64;
+    let lat = prev.point.lat + (next.point.lat - prev.point.lat) * fraction;
+    let lon = prev.point.lon + (next.point.lon - prev.point.lon) * fraction;
+    Some(GeoPoint::new(lat, lon))
+}
+
+/// Format a unix time as ISO 8601 UTC timestamp.
+fn format_timestamp(w: &mut impl Write, time: i64) {
+    let (year, month, day, hour, minute, second) = utc_datetime(time);
+    let _ = write!(w, "{year}-{month:0>2}-{day:0>2}T{hour:0>2}:{minute:0>2}:{second:0>2}Z");
+}
+
+/// Convert a unix time to its UTC calendar date and time of day.
+///
+/// See _<https://howardhinnant.github.io/date_algorithms.html#civil_from_days>_.
+fn utc_datetime(time: i64) -> (i64, i64, i64, i64, i64, i64) {
+    let days = time.div_euclid(86_400);
+    let seconds = time.rem_euclid(86_400);
+
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    (year, month, day, seconds / 3_600, seconds % 3_600 / 60, seconds % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        let mut timestamp = String::new();
+        format_timestamp(&mut timestamp, 0);
+        assert_eq!(timestamp, "1970-01-01T00:00:00Z");
+
+        timestamp.clear();
+        format_timestamp(&mut timestamp, 951_782_400);
+        assert_eq!(timestamp, "2000-02-29T00:00:00Z");
+
+        timestamp.clear();
+        format_timestamp(&mut timestamp, 1_700_000_000);
+        assert_eq!(timestamp, "2023-11-14T22:13:20Z");
+
+        let mut date = String::new();
+        format_date(&mut date, 1_700_000_000);
+        assert_eq!(date, "2023-11-14 22:13");
+    }
+
+    #[test]
+    fn durations() {
+        let mut duration = String::new();
+        format_duration(&mut duration, 59);
+        assert_eq!(duration, "0:00:59");
+
+        duration.clear();
+        format_duration(&mut duration, 3_600 + 62);
+        assert_eq!(duration, "1:01:02");
+    }
+
+    #[test]
+    fn playback_position() {
+        let points = [
+            TrackPoint { point: GeoPoint::new(52., 13.), time: 100 },
+            TrackPoint { point: GeoPoint::new(52., 13.), time: 110 },
+            TrackPoint { point: GeoPoint::new(53., 14.), time: 130 },
+        ];
+
+        assert_eq!(position_at(&[], 0.), None);
+        assert_eq!(position_at(&points, -5.), Some(GeoPoint::new(52., 13.)));
+        assert_eq!(position_at(&points, 5.), Some(GeoPoint::new(52., 13.)));
+        assert_eq!(position_at(&points, 20.), Some(GeoPoint::new(52.5, 13.5)));
+        assert_eq!(position_at(&points, 60.), Some(GeoPoint::new(53., 14.)));
+    }
+
+    #[test]
+    fn gpx_document() {
+        let track = Track { id: 1, distance: 0, points: 1, duration: 0, ctime: 1_700_000_000 };
+        let points = [TrackPoint { point: GeoPoint::new(52.5, 13.25), time: 1_700_000_060 }];
+
+        let expected = "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<gpx version=\"1.1\" creator=\"Charon\" \
+                        xmlns=\"http://www.topografix.com/GPX/1/1\">
+  <trk>
+    <name>Charon track 2023-11-14 22:13</name>
+    <trkseg>
+      <trkpt lat=\"52.5000000\" lon=\"13.2500000\"><time>2023-11-14T22:14:20Z</time></trkpt>
+    </trkseg>
+  </trk>
+</gpx>
+";
+        assert_eq!(gpx(track, &points), expected);
+    }
+
+    #[test]
+    fn recording_state() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut recorder = TrackRecorder { tx, recording: Recording::Stopped };
+
+        recorder.start();
+        assert!(recorder.recording());
+        assert_eq!(recorder.active_track(), None);
+        assert!(recorder.started(Some(3)));
+        assert_eq!(recorder.active_track(), Some(3));
+
+        // Failure to start the recording resets it.
+        recorder.stop();
+        recorder.start();
+        assert!(!recorder.started(None));
+        assert!(!recorder.recording());
+
+        // Recordings stopped while starting stay stopped.
+        recorder.start();
+        recorder.stop();
+        assert!(!recorder.started(Some(4)));
+        assert_eq!(recorder.active_track(), None);
+    }
+}
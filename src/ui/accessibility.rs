@@ -0,0 +1,174 @@
+//! Screen reader support.
+//!
+//! Interactive elements register themselves while they are rendered, and are
+//! exposed to assistive technology through AT-SPI. Actions requested by a
+//! screen reader are dispatched as taps on the element's center.
+
+use accesskit::{Action, ActionRequest, Node, NodeId, Role, Tree, TreeUpdate};
+use accesskit_unix::Adapter;
+use calloop::LoopHandle;
+use calloop::channel::{self, Event, Sender};
+use skia_safe::Rect;
+use tracing::error;
+
+use crate::geometry::Point;
+use crate::{Error, State};
+
+/// Node ID of the window's root node.
+const ROOT_ID: NodeId = NodeId(0);
+
+/// Accessible name of the window's root node.
+const WINDOW_LABEL: &str = "Charon";
+
+/// AT-SPI bridge for the window's content.
+pub struct Accessibility {
+    adapter: Adapter,
+    elements: Vec<AccessibleElement>,
+    pending: bool,
+}
+
+impl Accessibility {
+    pub fn new(event_loop: &LoopHandle<'static, State>) -> Result<Self, Error> {
+        // Forward screen reader requests to the event loop.
+        let (tx, rx) = channel::channel();
+        event_loop.insert_source(rx, |event, _, state| match event {
+            Event::Msg(AccessibilityEvent::Activated) => state.window.accessibility_activated(),
+            Event::Msg(AccessibilityEvent::Action(request)) => {
+                state.window.accessibility_action(request)
+            },
+            Event::Closed => (),
+        })?;
+
+        let activation_handler = EventHandler { tx: tx.clone() };
+        let action_handler = EventHandler { tx };
+        let adapter = Adapter::new(activation_handler, action_handler, DeactivationHandler);
+
+        Ok(Self { adapter, elements: Default::default(), pending: Default::default() })
+    }
+
+    /// Request a complete tree update with the next frame.
+    pub fn activate(&mut self) {
+        self.pending = true;
+    }
+
+    /// Update the elements exposed to screen readers.
+    pub fn update(&mut self, elements: Vec<AccessibleElement>) {
+        if !self.pending && elements == self.elements {
+            return;
+        }
+        self.pending = false;
+        self.elements = elements;
+
+        self.adapter.update_if_active(|| tree_update(&self.elements));
+    }
+
+    /// Get the physical location targeted by a screen reader action.
+    pub fn action_point(&self, request: &ActionRequest) -> Option<Point<f64>> {
+        if !matches!(request.action, Action::Click | Action::Focus) {
+            return None;
+        }
+
+        let index = (request.target.0 as usize).checked_sub(1)?;
+        let rect = self.elements.get(index)?.rect;
+        Some(Point::new(rect.center_x() as f64, rect.center_y() as f64))
+    }
+}
+
+/// Interactive element registered during rendering.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AccessibleElement {
+    pub role: AccessibleRole,
+    pub label: String,
+    pub value: Option<String>,
+    /// Physical bounds within the window.
+    pub rect: Rect,
+}
+
+/// Kind of interactive element.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum AccessibleRole {
+    Button,
+    TextInput,
+    ListItem,
+}
+
+impl From<AccessibleRole> for Role {
+    fn from(role: AccessibleRole) -> Self {
+        match role {
+            AccessibleRole::Button => Self::Button,
+            AccessibleRole::TextInput => Self::TextInput,
+            AccessibleRole::ListItem => Self::ListItem,
+        }
+    }
+}
+
+/// Build the complete accessibility tree.
+fn tree_update(elements: &[AccessibleElement]) -> TreeUpdate {
+    let mut nodes = Vec::with_capacity(elements.len() + 1);
+
+    let mut root = Node::new(Role::Window);
+    root.set_label(WINDOW_LABEL);
+    root.set_children((1..=elements.len() as u64).map(NodeId).collect::<Vec<_>>());
+    nodes.push((ROOT_ID, root));
+
+    for (i, element) in elements.iter().enumerate() {
+        let mut node = Node::new(element.role.into());
+        node.set_label(element.label.as_str());
+        if let Some(value) = &element.value {
+            node.set_value(value.as_str());
+        }
+
+        let rect = element.rect;
+        let (left, top, right, bottom) = (rect.left, rect.top, rect.right, rect.bottom);
+        node.set_bounds(accesskit::Rect::new(left as f64, top as f64, right as f64, bottom as f64));
+
+        match element.role {
+            AccessibleRole::TextInput => node.add_action(Action::Focus),
+            AccessibleRole::Button | AccessibleRole::ListItem => node.add_action(Action::Click),
+        }
+
+        nodes.push((NodeId(i as u64 + 1), node));
+    }
+
+    TreeUpdate { nodes, tree: Some(Tree::new(ROOT_ID)), focus: ROOT_ID }
+}
+
+/// Screen reader request.
+enum AccessibilityEvent {
+    Activated,
+    Action(ActionRequest),
+}
+
+/// Handler forwarding AT-SPI requests to the event loop.
+struct EventHandler {
+    tx: Sender<AccessibilityEvent>,
+}
+
+impl EventHandler {
+    fn send(&self, event: AccessibilityEvent) {
+        if let Err(err) = self.tx.send(event) {
+            error!("Failed to send on accessibility channel: {err}");
+        }
+    }
+}
+
+impl accesskit::ActivationHandler for EventHandler {
+    fn request_initial_tree(&mut self) -> Option<TreeUpdate> {
+        // Elements are only known after the next frame was rendered.
+        self.send(AccessibilityEvent::Activated);
+        None
+    }
+}
+
+impl accesskit::ActionHandler for EventHandler {
+    fn do_action(&mut self, request: ActionRequest) {
+        self.send(AccessibilityEvent::Action(request));
+    }
+}
+
+/// No-op handler for screen readers disconnecting.
+struct DeactivationHandler;
+
+impl accesskit::DeactivationHandler for DeactivationHandler {
+    fn deactivate_accessibility(&mut self) {}
+}
@@ -0,0 +1,93 @@
+//! Frame-driven animations.
+//!
+//! Animations are advanced once per frame based on the time that has passed,
+//! so motion speed does not depend on the display's refresh rate.
+
+use std::time::{Duration, Instant};
+
+/// Progress curve of a tween.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Easing {
+    /// Fast start, slowing down towards the end.
+    EaseOut,
+    /// Slow start and end, fastest in the middle.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Map linear progress between 0 and 1 to the eased value.
+    pub fn apply(self, t: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Self::EaseOut => 1. - (1. - t).powi(3),
+            Self::EaseInOut if t < 0.5 => 4. * t.powi(3),
+            Self::EaseInOut => 1. - (-2. * t + 2.).powi(3) / 2.,
+        }
+    }
+}
+
+/// Animation over a fixed duration.
+pub struct Tween {
+    start: Option<Instant>,
+    duration: Duration,
+    easing: Easing,
+    done: bool,
+}
+
+impl Tween {
+    pub fn new(duration: Duration, easing: Easing) -> Self {
+        Self { duration, easing, start: None, done: false }
+    }
+
+    /// Advance the tween and get its eased progress.
+    ///
+    /// The tween's clock starts with the first frame, so delays before it is
+    /// first rendered do not skip parts of the animation.
+    pub fn progress(&mut self, now: Instant) -> f64 {
+        let start = *self.start.get_or_insert(now);
+
+        let elapsed = now.saturating_duration_since(start).as_secs_f64();
+        let t = if self.duration.is_zero() { 1. } else { elapsed / self.duration.as_secs_f64() };
+        self.done = t >= 1.;
+
+        self.easing.apply(t)
+    }
+
+    /// Check if the tween has reached its end.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn easing_bounds() {
+        for easing in [Easing::EaseOut, Easing::EaseInOut] {
+            assert_eq!(easing.apply(0.), 0.);
+            assert_eq!(easing.apply(1.), 1.);
+            assert_eq!(easing.apply(2.), 1.);
+        }
+
+        assert_eq!(Easing::EaseInOut.apply(0.5), 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn tween_progress() {
+        let start = Instant::now();
+        let mut tween = Tween::new(Duration::from_millis(100), Easing::EaseInOut);
+
+        // Clock starts with the first frame.
+        assert_eq!(tween.progress(start + Duration::from_millis(500)), 0.);
+
+        let progress = tween.progress(start + Duration::from_millis(550));
+        assert!((progress - 0.5).abs() < 1e-9);
+        assert!(!tween.is_done());
+
+        assert_eq!(tween.progress(start + Duration::from_millis(700)), 1.);
+        assert!(tween.is_done());
+    }
+}
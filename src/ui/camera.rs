@@ -0,0 +1,335 @@
+//! Navigation camera.
+
+use std::time::{Duration, Instant};
+
+use skia_safe::Matrix;
+
+use crate::config::{CameraMode, Navigation};
+use crate::geometry::{GeoPoint, Point, Size};
+
+/// Time after a manual zoom change before auto-zoom is resumed.
+const MANUAL_OVERRIDE_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Minimum zoom difference before the zoom level is adjusted.
+///
+/// This avoids constantly switching between two zoom levels when the speed is
+/// right at the boundary.
+const ZOOM_HYSTERESIS: f64 = 0.75;
+
+/// Minimum time between two GPS fixes used for speed estimation.
+const MIN_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Weight of the latest sample in the averaged speed.
+const SMOOTHING_FACTOR: f64 = 0.3;
+
+/// Minimum distance in meters traveled before the travel direction is updated.
+const MIN_BEARING_DISTANCE: u32 = 5;
+
+/// Tilt of the perspective camera in degrees.
+const PERSPECTIVE_TILT: f32 = 40.;
+
+/// Camera distance of the perspective camera, relative to the screen height.
+const PERSPECTIVE_DISTANCE: f32 = 2.;
+
+/// Vertical screen position of the map center in perspective mode, relative to
+/// the screen height.
+const PERSPECTIVE_CENTER: f32 = 0.65;
+
+/// Camera controller following the GPS location during navigation.
+#[derive(Default)]
+pub struct NavigationCamera {
+    last_fix: Option<(GeoPoint, Instant)>,
+    manual_override: Option<Instant>,
+    bearing: Option<f32>,
+    speed: Option<f64>,
+}
+
+impl NavigationCamera {
+    /// Update the speed estimate with a new GPS location.
+    pub fn update_position(&mut self, point: GeoPoint, now: Instant) {
+        let (last_point, last_time) = match self.last_fix {
+            Some(last_fix) => last_fix,
+            None => {
+                self.last_fix = Some((point, now));
+                return;
+            },
+        };
+
+        // Ignore fixes too close together for a reliable estimate.
+        let elapsed = now.saturating_duration_since(last_time);
+        if elapsed < MIN_SAMPLE_INTERVAL {
+            return;
+        }
+        self.last_fix = Some((point, now));
+
+        // Update travel direction, ignoring GPS jitter while standing still.
+        let distance = point.distance(last_point);
+        if distance >= MIN_BEARING_DISTANCE {
+            self.bearing = Some(last_point.bearing(point) as f32);
+        }
+
+        // Convert m/s to km/h.
+        let sample = distance as f64 / elapsed.as_secs_f64() * 3.6;
+        self.speed = Some(match self.speed {
+            Some(speed) => speed + (sample - speed) * SMOOTHING_FACTOR,
+            None => sample,
+        });
+    }
+
+    /// Suspend auto-zoom after the user changed the zoom level.
+    pub fn override_zoom(&mut self, now: Instant) {
+        self.manual_override = Some(now);
+    }
+
+    /// Reset all tracking state.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Get the estimated travel speed in km/h.
+    pub fn speed(&self) -> Option<f64> {
+        self.speed
+    }
+
+    /// Get the direction of travel in degrees clockwise from north.
+    pub fn bearing(&self) -> Option<f32> {
+        self.bearing
+    }
+
+    /// Get the desired zoom level, if it should be changed.
+    ///
+    /// The `maneuver_distance` is the distance in meters to the next
+    /// navigation instruction.
+    pub fn zoom(
+        &self,
+        config: &Navigation,
+        current: u8,
+        maneuver_distance: Option<u32>,
+        now: Instant,
+    ) -> Option<u8> {
+        if !config.auto_zoom {
+            return None;
+        }
+
+        // Leave zoom alone while the user is in control.
+        if let Some(manual_override) = self.manual_override
+            && now.saturating_duration_since(manual_override) < MANUAL_OVERRIDE_TIMEOUT
+        {
+            return None;
+        }
+
+        // Always show street level details near maneuvers.
+        let target = match maneuver_distance {
+            Some(distance) if distance <= config.maneuver_distance => config.street_zoom as f64,
+            _ => curve_zoom(config, self.speed?),
+        };
+
+        let zoom = target.round() as u8;
+        ((target - current as f64).abs() >= ZOOM_HYSTERESIS && zoom != current).then_some(zoom)
+    }
+}
+
+/// Transform between map and screen coordinates.
+///
+/// Map coordinates are the physical screen coordinates the map would have
+/// without any rotation or tilt, while tiles are rendered into a separate
+/// coordinate space big enough to cover the entire transformed screen.
+pub struct MapTransform {
+    matrix: Matrix,
+    inverse: Matrix,
+    tiles_size: Size,
+    tiles_offset: Point<f32>,
+}
+
+impl MapTransform {
+    /// Create the transform for a camera mode.
+    ///
+    /// The `rotation` is the direction in degrees clockwise from north, which
+    /// should be pointing upwards.
+    pub fn new(mode: CameraMode, rotation: f32, size: Size) -> Self {
+        let width = size.width as f32;
+        let height = size.height as f32;
+
+        // Transform map coordinates relative to the map center onto the screen.
+        let mut matrix = match mode {
+            CameraMode::NorthUp => return Self::identity(size),
+            CameraMode::HeadingUp => Matrix::translate((width / 2., height / 2.)),
+            CameraMode::Perspective => {
+                let (sin, cos) = PERSPECTIVE_TILT.to_radians().sin_cos();
+                let distance = PERSPECTIVE_DISTANCE * height;
+                let tilt = Matrix::new_all(1., 0., 0., 0., cos, 0., 0., -sin / distance, 1.);
+
+                let mut matrix = Matrix::translate((width / 2., height * PERSPECTIVE_CENTER));
+                matrix.pre_concat(&tilt);
+                matrix
+            },
+        };
+        matrix.pre_rotate(-rotation, None);
+
+        let inverse = match matrix.invert() {
+            Some(inverse) => inverse,
+            None => return Self::identity(size),
+        };
+
+        // Find the map area visible on the screen.
+        let mut max_x: f32 = 0.;
+        let mut max_y: f32 = 0.;
+        for corner in [(0., 0.), (width, 0.), (0., height), (width, height)] {
+            let point = inverse.map_point(corner);
+            max_x = max_x.max(point.x.abs());
+            max_y = max_y.max(point.y.abs());
+        }
+
+        // Cover the entire visible area, with the map center in the middle.
+        let tiles_width = (2. * max_x.ceil()).max(width) as u32;
+        let tiles_height = (2. * max_y.ceil()).max(height) as u32;
+        let tiles_size = Size::new(tiles_width + tiles_width % 2, tiles_height + tiles_height % 2);
+        matrix.pre_translate((-(tiles_size.width / 2) as f32, -(tiles_size.height / 2) as f32));
+
+        let tiles_offset = Point::new(
+            (tiles_size.width / 2 - size.width / 2) as f32,
+            (tiles_size.height / 2 - size.height / 2) as f32,
+        );
+
+        let inverse = matrix.invert().unwrap_or_default();
+
+        Self { matrix, inverse, tiles_size, tiles_offset }
+    }
+
+    /// Create a transform without any rotation or tilt.
+    pub fn identity(size: Size) -> Self {
+        Self {
+            tiles_size: size,
+            matrix: Default::default(),
+            inverse: Default::default(),
+            tiles_offset: Default::default(),
+        }
+    }
+
+    /// Canvas matrix for rendering tile coordinates onto the screen.
+    pub fn matrix(&self) -> &Matrix {
+        &self.matrix
+    }
+
+    /// Size of the area which must be covered by tiles.
+    pub fn tiles_size(&self) -> Size {
+        self.tiles_size
+    }
+
+    /// Convert map coordinates to tile coordinates.
+    pub fn map_to_tiles(&self, point: Point<f32>) -> Point<f32> {
+        point + self.tiles_offset
+    }
+
+    /// Convert tile coordinates to screen coordinates.
+    pub fn tiles_to_screen(&self, point: Point<f32>) -> Point<f32> {
+        let point = self.matrix.map_point(point);
+        Point::new(point.x, point.y)
+    }
+
+    /// Convert screen coordinates to map coordinates.
+    pub fn screen_to_map(&self, point: Point<f64>) -> Point<f64> {
+        let tiles_point = self.inverse.map_point(point);
+        let x = tiles_point.x - self.tiles_offset.x;
+        let y = tiles_point.y - self.tiles_offset.y;
+        Point::new(x as f64, y as f64)
+    }
+}
+
+/// Get the fractional zoom level for a speed.
+///
+/// The zoom is linearly interpolated between the street and overview zoom
+/// levels.
+fn curve_zoom(config: &Navigation, speed: f64) -> f64 {
+    let speed_range = config.overview_speed - config.street_speed;
+    let progress = if speed_range > 0. {
+        ((speed - config.street_speed) / speed_range).clamp(0., 1.)
+    } else if speed >= config.overview_speed {
+        1.
+    } else {
+        0.
+    };
+
+    let street_zoom = config.street_zoom as f64;
+    street_zoom + (config.overview_zoom as f64 - street_zoom) * progress
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zoom_speed_curve() {
+        let config = Navigation::default();
+
+        assert_eq!(curve_zoom(&config, 0.), config.street_zoom as f64);
+        assert_eq!(curve_zoom(&config, config.street_speed), config.street_zoom as f64);
+        assert_eq!(curve_zoom(&config, config.overview_speed), config.overview_zoom as f64);
+        assert_eq!(curve_zoom(&config, 500.), config.overview_zoom as f64);
+
+        let half_speed = (config.street_speed + config.overview_speed) / 2.;
+        let half_zoom = (config.street_zoom as f64 + config.overview_zoom as f64) / 2.;
+        assert_eq!(curve_zoom(&config, half_speed), half_zoom);
+    }
+
+    #[test]
+    fn transform_roundtrip() {
+        let size = Size::new(720, 1440);
+        let center = Point::new(360., 720.);
+
+        // North-up leaves map coordinates untouched.
+        let transform = MapTransform::new(CameraMode::NorthUp, 90., size);
+        assert_eq!(transform.tiles_size(), size);
+        assert_eq!(transform.screen_to_map(center), center);
+
+        // Heading-up keeps the map center in place, while rotating around it.
+        let transform = MapTransform::new(CameraMode::HeadingUp, 90., size);
+        let tiles_size = transform.tiles_size();
+        let tiles_center = Point::new(tiles_size.width as f32 / 2., tiles_size.height as f32 / 2.);
+        let screen_center = transform.tiles_to_screen(tiles_center);
+        assert!((screen_center.x - 360.).abs() < 0.01);
+        assert!((screen_center.y - 720.).abs() < 0.01);
+
+        let map_point = transform.screen_to_map(Point::new(360., 0.));
+        assert!((map_point.x - 1080.).abs() < 0.01);
+        assert!((map_point.y - 720.).abs() < 0.01);
+
+        // Perspective covers more map area at the top than at the bottom.
+        let transform = MapTransform::new(CameraMode::Perspective, 0., size);
+        let top = transform.screen_to_map(Point::new(360., 0.));
+        let bottom = transform.screen_to_map(Point::new(360., 1440.));
+        assert!(720. - top.y > bottom.y - 720.);
+    }
+
+    #[test]
+    fn zoom_hysteresis_and_override() {
+        let config = Navigation {
+            street_zoom: 18,
+            overview_zoom: 14,
+            street_speed: 10.,
+            overview_speed: 90.,
+            ..Default::default()
+        };
+        let mut camera = NavigationCamera::default();
+        let now = Instant::now();
+
+        // Unknown speed.
+        assert_eq!(camera.zoom(&config, 16, None, now), None);
+
+        // Maneuvers force street zoom.
+        let maneuver_distance = Some(config.maneuver_distance);
+        assert_eq!(camera.zoom(&config, 16, maneuver_distance, now), Some(18));
+
+        // Speed right between two zoom levels keeps either of them.
+        camera.speed = Some(80.);
+        assert_eq!(camera.zoom(&config, 14, None, now), None);
+        assert_eq!(camera.zoom(&config, 15, None, now), None);
+        assert_eq!(camera.zoom(&config, 16, None, now), Some(15));
+
+        // Manual zoom suspends auto-zoom.
+        camera.override_zoom(now);
+        assert_eq!(camera.zoom(&config, 16, None, now), None);
+        let later = now + MANUAL_OVERRIDE_TIMEOUT;
+        assert_eq!(camera.zoom(&config, 16, None, later), Some(15));
+    }
+}
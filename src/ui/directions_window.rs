@@ -0,0 +1,168 @@
+//! Separate window for textual directions.
+
+use std::sync::Arc;
+
+use calloop::LoopHandle;
+use glutin::display::Display;
+use smithay_client_toolkit::compositor::CompositorState;
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::{Connection, QueueHandle};
+use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::xdg::window::{Window as XdgWindow, WindowDecorations};
+
+use crate::config::Config;
+use crate::geometry::{Point, Size};
+use crate::router::Route;
+use crate::ui::surface::{Surface, SurfaceKind, SurfaceState};
+use crate::ui::view::UiView;
+use crate::ui::view::directions::DirectionsView;
+use crate::wayland::ProtocolStates;
+use crate::{Error, State};
+
+/// Secondary Wayland window showing the route's directions.
+///
+/// This allows following the maneuver list next to the map, on compositors
+/// which can show multiple windows at once.
+pub struct DirectionsWindow {
+    // NOTE: The surface state must be dropped before the XDG window destroys
+    // its surface.
+    state: SurfaceState,
+    xdg_window: XdgWindow,
+
+    view: DirectionsView,
+
+    touch_slots: Vec<i32>,
+}
+
+impl DirectionsWindow {
+    pub fn new(
+        event_loop: &LoopHandle<'static, State>,
+        protocol_states: &ProtocolStates,
+        connection: Connection,
+        queue: QueueHandle<State>,
+        egl_display: Display,
+        config: &Config,
+    ) -> Result<Self, Error> {
+        // Default to a reasonable default size.
+        let size = Size { width: 360, height: 720 };
+
+        let state =
+            SurfaceState::new(protocol_states, connection, queue, egl_display, config, size);
+
+        // Create the XDG shell window.
+        let xdg_window = protocol_states.xdg_shell.create_window(
+            state.wl_surface().clone(),
+            WindowDecorations::RequestClient,
+            state.queue(),
+        );
+        xdg_window.set_title("Charon Directions");
+        xdg_window.set_app_id("Charon");
+        xdg_window.commit();
+
+        let mut view = DirectionsView::new(event_loop.clone(), config, size)?;
+        view.set_detached(true);
+
+        Ok(Self { xdg_window, state, view, touch_slots: Default::default() })
+    }
+}
+
+impl Surface for DirectionsWindow {
+    fn kind(&self) -> SurfaceKind {
+        SurfaceKind::Directions
+    }
+
+    fn state(&self) -> &SurfaceState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut SurfaceState {
+        &mut self.state
+    }
+
+    fn wl_surface(&self) -> &WlSurface {
+        self.xdg_window.wl_surface()
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw(&mut self) {
+        let view = &mut self.view;
+        self.state.draw(view.dirty(), |config, render_state| view.draw(config, render_state));
+    }
+
+    fn configure(&mut self, compositor: &CompositorState, size: Option<Size>) {
+        let size = size.unwrap_or(self.state.size());
+        if self.state.configure(compositor, size) {
+            self.view.set_size(size);
+            self.unstall();
+        }
+    }
+
+    fn set_scale_factor(&mut self, scale: f64) {
+        if self.state.set_scale_factor(scale) {
+            self.view.set_scale_factor(scale);
+            self.unstall();
+        }
+    }
+
+    fn update_config(&mut self, config: &Config) {
+        self.state.update_config(config);
+        self.view.update_config(config);
+
+        self.unstall();
+    }
+
+    fn set_route(&mut self, route: Arc<Route>) {
+        self.view.set_route(route);
+
+        if self.view.dirty() {
+            self.unstall();
+        }
+    }
+
+    fn set_progress(&mut self, progress: usize) {
+        self.view.set_progress(progress);
+
+        if self.view.dirty() {
+            self.unstall();
+        }
+    }
+
+    fn has_touch(&self, slot: i32) -> bool {
+        self.touch_slots.contains(&slot)
+    }
+
+    fn touch_down(&mut self, slot: i32, time: u32, point: Point<f64>) {
+        self.touch_slots.push(slot);
+        self.view.touch_down(slot, time, point);
+
+        if self.view.dirty() {
+            self.unstall();
+        }
+    }
+
+    fn touch_motion(&mut self, slot: i32, point: Point<f64>) {
+        self.view.touch_motion(slot, point);
+
+        if self.view.dirty() {
+            self.unstall();
+        }
+    }
+
+    fn touch_up(&mut self, slot: i32) {
+        self.touch_slots.retain(|&touch_slot| touch_slot != slot);
+        self.view.touch_up(slot);
+
+        if self.view.dirty() {
+            self.unstall();
+        }
+    }
+
+    fn press_key(&mut self, raw: u32, keysym: Keysym, modifiers: Modifiers) {
+        self.view.press_key(raw, keysym, modifiers);
+
+        if self.view.dirty() {
+            self.unstall();
+        }
+    }
+}
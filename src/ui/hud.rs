@@ -0,0 +1,118 @@
+//! Runtime metrics overlay.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use skia_safe::{Color4f, Paint, Rect};
+
+use crate::config::Config;
+use crate::geometry::{Point, Size};
+use crate::ui::skia::{RenderState, TextOptions};
+
+/// HUD font size relative to the default.
+const FONT_SIZE: f32 = 0.6;
+
+/// Padding inside the HUD background at scale 1.
+const INSIDE_PADDING: f32 = 5.;
+
+/// Distance between the HUD and the screen edge at scale 1.
+const OUTSIDE_PADDING: f32 = 16.;
+
+/// Weight of the latest sample in the averaged frame timings.
+const SMOOTHING_FACTOR: f64 = 0.1;
+
+/// Metrics collected by the HUD's owner.
+pub struct HudMetrics {
+    pub cached_tiles: usize,
+    pub loading_tiles: usize,
+    pub active_queries: usize,
+    pub gps_age: Option<Duration>,
+}
+
+/// Runtime metrics overlay.
+///
+/// Frame timings are averaged across redraws, so they only update while the
+/// view is being redrawn.
+#[derive(Default)]
+pub struct DebugHud {
+    frame_start: Option<Instant>,
+    frame_interval: f64,
+    frame_time: f64,
+
+    bg_paint: Paint,
+    text: String,
+}
+
+impl DebugHud {
+    /// Mark the start of a new frame.
+    pub fn start_frame(&mut self) {
+        let now = Instant::now();
+        if let Some(last_start) = self.frame_start.replace(now) {
+            let interval = now.duration_since(last_start).as_secs_f64();
+            self.frame_interval = smooth(self.frame_interval, interval);
+        }
+    }
+
+    /// Render the HUD in the bottom-left corner.
+    ///
+    /// Since this is expected to be the last draw call of the frame, it also
+    /// completes the frame time measurement.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn draw<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        size: Size,
+        scale: f64,
+        metrics: HudMetrics,
+    ) {
+        if let Some(frame_start) = self.frame_start {
+            self.frame_time = smooth(self.frame_time, frame_start.elapsed().as_secs_f64());
+        }
+
+        // Format all metrics.
+
+        let fps = if self.frame_interval > 0. { 1. / self.frame_interval } else { 0. };
+        let frame_ms = self.frame_time * 1000.;
+
+        self.text.clear();
+        let _ = writeln!(self.text, "{fps:.0} FPS ({frame_ms:.1} ms)");
+        let (cached, loading) = (metrics.cached_tiles, metrics.loading_tiles);
+        let _ = writeln!(self.text, "Tiles: {cached} ({loading} loading)");
+        let _ = writeln!(self.text, "DB queue: {}", metrics.active_queries);
+        let _ = match metrics.gps_age {
+            Some(age) => write!(self.text, "GPS fix: {:.1}s ago", age.as_secs_f64()),
+            None => write!(self.text, "GPS fix: none"),
+        };
+
+        // Layout metrics text.
+
+        let size = size * scale;
+        let inside_padding = INSIDE_PADDING * scale as f32;
+        let outside_padding = OUTSIDE_PADDING * scale as f32;
+
+        let text_options = Some(TextOptions::new().ellipsize(false));
+        let mut builder = render_state.paragraph(config.colors.foreground, FONT_SIZE, text_options);
+        builder.add_text(&self.text);
+
+        let mut paragraph = builder.build();
+        paragraph.layout(size.width as f32 - 2. * (outside_padding + inside_padding));
+
+        // Draw background and text.
+
+        let width = paragraph.max_intrinsic_width() + 2. * inside_padding;
+        let height = paragraph.height() + 2. * inside_padding;
+        let x = outside_padding;
+        let y = size.height as f32 - outside_padding - height;
+
+        self.bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        render_state.draw_rect(Rect::new(x, y, x + width, y + height), &self.bg_paint);
+
+        paragraph.paint(render_state, Point::new(x + inside_padding, y + inside_padding));
+    }
+}
+
+/// Add a new sample to an exponential moving average.
+fn smooth(average: f64, sample: f64) -> f64 {
+    if average == 0. { sample } else { average + (sample - average) * SMOOTHING_FACTOR }
+}
@@ -0,0 +1,138 @@
+//! Maneuver alert policy.
+
+use crate::router::Mode as RouteMode;
+
+/// Minimum average road speed in km/h to use highway alert distances.
+const HIGHWAY_SPEED: f64 = 80.;
+
+/// Minimum average road speed in km/h to use arterial road alert distances.
+const ARTERIAL_SPEED: f64 = 50.;
+
+/// Early alert distances in meters for highway maneuvers like exits.
+const HIGHWAY_DISTANCES: &[u32] = &[1_000, 300];
+
+/// Early alert distances in meters for arterial road maneuvers.
+const ARTERIAL_DISTANCES: &[u32] = &[300, 100];
+
+/// Early alert distances in meters for city turns.
+const CITY_DISTANCES: &[u32] = &[100];
+
+/// Time in seconds before the maneuver at which the final alert is triggered.
+const IMMEDIATE_ALERT_TIME: f64 = 3.;
+
+/// Minimum distance in meters for the final alert before the maneuver.
+const MIN_IMMEDIATE_DISTANCE: u32 = 15;
+
+/// Alert state for the upcoming maneuver.
+#[derive(Default)]
+pub struct ManeuverAlerts {
+    segment: Option<usize>,
+    last_alert: Option<u32>,
+}
+
+impl ManeuverAlerts {
+    /// Check whether the user should be alerted about the next maneuver.
+    ///
+    /// The `road_speed` is the average speed of the current route segment,
+    /// while `speed` is the user's current speed, both in km/h.
+    pub fn update(
+        &mut self,
+        segment: usize,
+        distance: u32,
+        mode: RouteMode,
+        road_speed: f64,
+        speed: Option<f64>,
+    ) -> bool {
+        // Reset alerts when a new segment is started.
+        if self.segment != Some(segment) {
+            self.segment = Some(segment);
+            self.last_alert = None;
+        }
+
+        // Get the closest alert distance which was reached, but not alerted yet.
+        let due = alert_distances(mode, road_speed, speed)
+            .into_iter()
+            .filter(|alert| self.last_alert.is_none_or(|last| *alert < last))
+            .filter(|alert| distance <= *alert)
+            .min();
+
+        match due {
+            Some(alert) => {
+                self.last_alert = Some(alert);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Clear alert state for a new route.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Get all alert distances in meters before a maneuver, furthest first.
+fn alert_distances(mode: RouteMode, road_speed: f64, speed: Option<f64>) -> Vec<u32> {
+    let early = match mode {
+        RouteMode::Auto if road_speed >= HIGHWAY_SPEED => HIGHWAY_DISTANCES,
+        RouteMode::Auto if road_speed >= ARTERIAL_SPEED => ARTERIAL_DISTANCES,
+        RouteMode::Auto | RouteMode::Bicycle | RouteMode::Pedestrian => CITY_DISTANCES,
+    };
+
+    // Alert right before the maneuver, based on the current speed.
+    let speed = speed.unwrap_or(road_speed);
+    let immediate = (speed / 3.6 * IMMEDIATE_ALERT_TIME).round() as u32;
+    let immediate = immediate.max(MIN_IMMEDIATE_DISTANCE);
+
+    let mut distances = early.to_vec();
+    if early.last().is_none_or(|last| immediate < *last) {
+        distances.push(immediate);
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn highway_alerts() {
+        let mut alerts = ManeuverAlerts::default();
+        let speed = Some(120.);
+
+        assert!(!alerts.update(0, 2_000, RouteMode::Auto, 110., speed));
+        assert!(alerts.update(0, 990, RouteMode::Auto, 110., speed));
+        assert!(!alerts.update(0, 500, RouteMode::Auto, 110., speed));
+        assert!(alerts.update(0, 290, RouteMode::Auto, 110., speed));
+        assert!(!alerts.update(0, 150, RouteMode::Auto, 110., speed));
+        assert!(alerts.update(0, 90, RouteMode::Auto, 110., speed));
+        assert!(!alerts.update(0, 10, RouteMode::Auto, 110., speed));
+    }
+
+    #[test]
+    fn city_alerts() {
+        let mut alerts = ManeuverAlerts::default();
+        let speed = Some(30.);
+
+        assert!(!alerts.update(0, 300, RouteMode::Auto, 30., speed));
+        assert!(alerts.update(0, 100, RouteMode::Auto, 30., speed));
+        assert!(!alerts.update(0, 50, RouteMode::Auto, 30., speed));
+        assert!(alerts.update(0, 25, RouteMode::Auto, 30., speed));
+        assert!(!alerts.update(0, 5, RouteMode::Auto, 30., speed));
+
+        // Alerts start over for the next maneuver.
+        assert!(alerts.update(1, 80, RouteMode::Auto, 30., speed));
+    }
+
+    #[test]
+    fn skipped_alerts() {
+        let mut alerts = ManeuverAlerts::default();
+
+        // Only a single alert is triggered when multiple distances are passed at once.
+        assert!(alerts.update(0, 200, RouteMode::Auto, 100., Some(100.)));
+        assert!(!alerts.update(0, 150, RouteMode::Auto, 100., Some(100.)));
+
+        // Walking speed uses the minimum distance for the final alert.
+        assert_eq!(alert_distances(RouteMode::Pedestrian, 5., Some(5.)), [100, 15]);
+    }
+}
@@ -0,0 +1,206 @@
+//! Always-on-top navigation overlay.
+
+use std::sync::Arc;
+
+use glutin::display::Display;
+use smithay_client_toolkit::compositor::{CompositorState, Region};
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::{Connection, QueueHandle};
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::{
+    Anchor, KeyboardInteractivity, Layer, LayerShell, LayerSurface,
+};
+
+use crate::State;
+use crate::config::Config;
+use crate::geometry::{Point, Size};
+use crate::router::Route;
+use crate::ui::surface::{Surface, SurfaceKind, SurfaceState};
+use crate::ui::view;
+use crate::ui::view::directions;
+use crate::wayland::ProtocolStates;
+
+/// Overlay height at scale 1.
+const HEIGHT: u32 = 72;
+
+/// Space between the overlay and the screen edges at scale 1.
+const MARGIN: i32 = 8;
+
+/// Padding around the overlay's text at scale 1.
+const PADDING: f64 = 8.;
+
+/// Maneuver distance font size relative to the default.
+const DISTANCE_FONT_SIZE: f32 = 1.25;
+
+/// Instruction font size relative to the default.
+const INSTRUCTION_FONT_SIZE: f32 = 0.75;
+
+/// Small overlay showing the next maneuver above other applications.
+///
+/// The overlay is purely informational and lets all input pass through to
+/// the surfaces below it.
+pub struct MiniWindow {
+    // NOTE: The surface state must be dropped before the layer surface destroys
+    // its surface.
+    state: SurfaceState,
+    layer: LayerSurface,
+
+    route: Arc<Route>,
+    progress: usize,
+
+    dirty: bool,
+}
+
+impl MiniWindow {
+    pub fn new(
+        protocol_states: &ProtocolStates,
+        layer_shell: &LayerShell,
+        connection: Connection,
+        queue: QueueHandle<State>,
+        egl_display: Display,
+        config: &Config,
+    ) -> Self {
+        let size = Size::new(360 - 2 * MARGIN as u32, HEIGHT);
+        let state =
+            SurfaceState::new(protocol_states, connection, queue, egl_display, config, size);
+
+        // Pass all input through to the surfaces below.
+        if let Ok(region) = Region::new(&protocol_states.compositor) {
+            state.wl_surface().set_input_region(Some(region.wl_region()));
+        }
+
+        // Create a layer shell surface spanning the top of the screen.
+        let layer = layer_shell.create_layer_surface(
+            state.queue(),
+            state.wl_surface().clone(),
+            Layer::Overlay,
+            Some("charon-navigation"),
+            None,
+        );
+        layer.set_anchor(Anchor::TOP | Anchor::LEFT | Anchor::RIGHT);
+        layer.set_keyboard_interactivity(KeyboardInteractivity::None);
+        layer.set_margin(MARGIN, MARGIN, 0, MARGIN);
+        layer.set_size(0, HEIGHT);
+        layer.commit();
+
+        Self { layer, state, dirty: true, progress: Default::default(), route: Default::default() }
+    }
+}
+
+impl Surface for MiniWindow {
+    fn kind(&self) -> SurfaceKind {
+        SurfaceKind::MiniWindow
+    }
+
+    fn state(&self) -> &SurfaceState {
+        &self.state
+    }
+
+    fn state_mut(&mut self) -> &mut SurfaceState {
+        &mut self.state
+    }
+
+    fn wl_surface(&self) -> &WlSurface {
+        self.layer.wl_surface()
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw(&mut self) {
+        // Render the next maneuver.
+        let scale = self.state.scale();
+        let size = self.state.size() * scale;
+        let maneuver =
+            directions::upcoming_maneuvers(&self.route, self.progress).into_iter().next();
+        let drawn = self.state.draw(self.dirty, |config, mut render_state| {
+            let padding = (PADDING * scale).round() as f32;
+            let text_width = size.width as f32 - 2. * padding;
+
+            render_state.clear(config.colors.highlight);
+
+            let maneuver = match maneuver {
+                Some(maneuver) => maneuver,
+                None => return,
+            };
+
+            // Layout maneuver distance.
+
+            let mut distance = String::with_capacity("X.XX km".len());
+            view::format_distance(&mut distance, maneuver.distance, config.navigation.units);
+
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, DISTANCE_FONT_SIZE, None);
+            builder.add_text(&distance);
+
+            let mut distance_paragraph = builder.build();
+            distance_paragraph.layout(text_width);
+            let distance_height = distance_paragraph.height();
+
+            // Layout instruction text.
+
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, INSTRUCTION_FONT_SIZE, None);
+            builder.add_text(&*maneuver.instruction);
+
+            let mut instruction_paragraph = builder.build();
+            instruction_paragraph.layout(text_width);
+            let instruction_height = instruction_paragraph.height();
+
+            // Draw both labels vertically centered.
+
+            let y = (size.height as f32 - distance_height - instruction_height) / 2.;
+            let mut text_point = Point::new(padding, y);
+            distance_paragraph.paint(&render_state, text_point);
+            text_point.y += distance_height;
+            instruction_paragraph.paint(&render_state, text_point);
+        });
+        self.dirty &= !drawn;
+    }
+
+    fn configure(&mut self, compositor: &CompositorState, size: Option<Size>) {
+        // Fall back to our requested size if the compositor leaves it to us.
+        let current_size = self.state.size();
+        let size = size.unwrap_or(current_size);
+        let size = Size::new(
+            if size.width == 0 { current_size.width } else { size.width },
+            if size.height == 0 { HEIGHT } else { size.height },
+        );
+
+        if self.state.configure(compositor, size) {
+            self.dirty = true;
+            self.unstall();
+        }
+    }
+
+    fn set_scale_factor(&mut self, scale: f64) {
+        if self.state.set_scale_factor(scale) {
+            self.dirty = true;
+            self.unstall();
+        }
+    }
+
+    fn update_config(&mut self, config: &Config) {
+        self.state.update_config(config);
+        self.dirty = true;
+
+        self.unstall();
+    }
+
+    fn set_route(&mut self, route: Arc<Route>) {
+        self.route = route;
+        self.progress = 0;
+        self.dirty = true;
+
+        self.unstall();
+    }
+
+    fn set_progress(&mut self, progress: usize) {
+        if self.progress == progress {
+            return;
+        }
+
+        self.progress = progress;
+        self.dirty = true;
+
+        self.unstall();
+    }
+}
@@ -1,88 +1,44 @@
-use std::time::Instant;
+use std::mem;
 
+pub use charon_map::input::Velocity;
 use skia_safe::{Color4f, Paint, Rect};
+use tracing::error;
 
 use crate::config::Input;
+use crate::dbus;
 use crate::geometry::{Point, Size, rect_contains};
+use crate::ui::accessibility::AccessibleRole;
+pub use crate::ui::animation::{Easing, Tween};
+pub use crate::ui::scroll_list::{RowProvider, ScrollList};
 use crate::ui::skia::{RenderState, Svg};
+pub use crate::ui::suggestions::Suggestions;
 pub use crate::ui::text_field::TextField;
 
+pub mod accessibility;
+mod animation;
+pub mod camera;
+pub mod directions_window;
+pub mod hud;
+pub mod maneuver;
+pub mod mini_window;
 pub mod renderer;
+mod scroll_list;
 pub mod skia;
+mod suggestions;
+pub mod surface;
 mod text_field;
+pub mod trip_summary;
 pub mod view;
 pub mod window;
 
 /// Percentage of the button size reserved as padding.
 const BUTTON_PADDING: f64 = 0.1;
 
-/// Velocity state.
-#[derive(Default)]
-pub struct Velocity {
-    last_tick: Option<Instant>,
-    velocity: Point<f64>,
-}
-
-impl Velocity {
-    /// Check if there is any velocity active.
-    pub fn is_moving(&self) -> bool {
-        self.velocity != Point::default()
-    }
-
-    /// Set the velocity.
-    pub fn set(&mut self, velocity: Point<f64>) {
-        self.velocity = velocity;
-        self.last_tick = None;
-    }
-
-    /// Reset all velocity.
-    pub fn stop(&mut self) {
-        self.velocity = Point::default();
-    }
+/// Background brightness of pressed buttons relative to their regular color.
+const BUTTON_PRESSED_BRIGHTNESS: f32 = 0.6;
 
-    /// Apply and update the current velocity.
-    pub fn apply(&mut self, input: &Input) -> Option<Point<f64>> {
-        // No-op without velocity.
-        if self.velocity == Point::default() {
-            return None;
-        }
-
-        // Initialize velocity on the first tick.
-        //
-        // This avoids applying velocity while the user is still interacting.
-        let last_tick = match self.last_tick.take() {
-            Some(last_tick) => last_tick,
-            None => {
-                self.last_tick = Some(Instant::now());
-                return None;
-            },
-        };
-
-        // Calculate velocity steps since last tick.
-        let now = Instant::now();
-        let interval =
-            (now - last_tick).as_micros() as f64 / (input.velocity_interval as f64 * 1_000.);
-
-        // Update velocity and calculate the expected delta.
-        let apply = |velocity: &mut f64| {
-            let delta = *velocity * (1. - input.velocity_friction.powf(interval + 1.))
-                / (1. - input.velocity_friction);
-            *velocity *= input.velocity_friction.powf(interval);
-            delta
-        };
-        let x = apply(&mut self.velocity.x);
-        let y = apply(&mut self.velocity.y);
-
-        // Request next tick if velocity is significant.
-        if self.velocity.x.abs() > 1. || self.velocity.y.abs() > 1. {
-            self.last_tick = Some(now);
-        } else {
-            self.velocity = Point::default();
-        }
-
-        Some(Point::new(x, y))
-    }
-}
+/// Opacity of buttons which cannot be pressed.
+const BUTTON_DISABLED_ALPHA: f32 = 0.4;
 
 /// An SVG button.
 struct Button {
@@ -90,29 +46,51 @@ struct Button {
     point: Point,
     size: Size,
     svg: Svg,
+
+    touched: bool,
+    pressed: bool,
+    disabled: bool,
 }
 
 impl Button {
     fn new(point: Point, size: Size, svg: Svg) -> Self {
         let paint = Paint::default();
-        Self { paint, point, size, svg }
+        Self { paint, point, size, svg, touched: false, pressed: false, disabled: false }
     }
 
     /// Render the button.
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn draw(&mut self, render_state: &mut RenderState, background: impl Into<Color4f>) {
-        self.paint.set_color4f(background.into(), None);
+        // Darken background while the button is held down.
+        let mut background = background.into();
+        if self.pressed {
+            background.r *= BUTTON_PRESSED_BRIGHTNESS;
+            background.g *= BUTTON_PRESSED_BRIGHTNESS;
+            background.b *= BUTTON_PRESSED_BRIGHTNESS;
+        }
+        self.paint.set_color4f(background, None);
 
         let right = self.point.x as f32 + self.size.width as f32;
         let bottom = self.point.y as f32 + self.size.height as f32;
         let rect = Rect::new(self.point.x as f32, self.point.y as f32, right, bottom);
+
+        // Fade out buttons which cannot be pressed.
+        if self.disabled {
+            render_state.save_layer_alpha_f(rect, BUTTON_DISABLED_ALPHA);
+        }
+
         render_state.draw_rect(rect, &self.paint);
+        render_state.add_accessible(AccessibleRole::Button, self.svg.label(), None, rect);
 
         let padding = self.size * BUTTON_PADDING;
         let svg_size = self.size - Size::new(padding.width * 2, padding.height * 2);
         let x = self.point.x + padding.width as i32;
         let y = self.point.y + padding.height as i32;
         render_state.draw_svg(self.svg, Point::new(x, y), svg_size);
+
+        if self.disabled {
+            render_state.restore();
+        }
     }
 
     /// Update the button's position.
@@ -130,9 +108,122 @@ impl Button {
         self.svg = svg;
     }
 
+    /// Prevent the button from being pressed.
+    pub fn set_disabled(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
     /// Check if a point lies within this button.
     pub fn contains(&self, point: Point<f64>) -> bool {
         let point = Point::new(point.x.round() as i32, point.y.round() as i32);
         rect_contains(self.point, self.size.into(), point)
     }
+
+    /// Start a touch sequence on this button.
+    pub fn press(&mut self, input: &Input) {
+        if self.disabled {
+            return;
+        }
+
+        self.touched = true;
+        self.pressed = true;
+
+        if input.haptic_feedback {
+            tokio::spawn(async {
+                if let Err(err) = dbus::feedbackd::button_feedback().await {
+                    error!("Failed to trigger haptic feedback: {err}");
+                }
+            });
+        }
+    }
+
+    /// Update the pressed state after the touch point has moved.
+    ///
+    /// Returns `true` if the pressed state has changed.
+    pub fn touch_motion(&mut self, point: Point<f64>) -> bool {
+        let pressed = self.touched && self.contains(point);
+        mem::replace(&mut self.pressed, pressed) != pressed
+    }
+
+    /// End the active touch sequence.
+    ///
+    /// Returns `true` if the button was pressed before its release.
+    pub fn release(&mut self) -> bool {
+        self.touched = false;
+        mem::take(&mut self.pressed)
+    }
+}
+
+/// Highlight the button targeted by a new touch sequence.
+///
+/// Returns `true` if a button was pressed.
+fn press_button(button: Option<&mut Button>, input: &Input) -> bool {
+    match button {
+        Some(button) => {
+            button.press(input);
+            true
+        },
+        None => false,
+    }
+}
+
+/// Update the targeted button's pressed state after touch motion.
+///
+/// Returns `true` if the pressed state has changed.
+fn move_button_touch(button: Option<&mut Button>, point: Point<f64>) -> bool {
+    button.is_some_and(|button| button.touch_motion(point))
+}
+
+/// Clear the targeted button's press highlight.
+///
+/// Returns `true` if the button was pressed before its release.
+fn release_button(button: Option<&mut Button>) -> bool {
+    button.is_some_and(|button| button.release())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_press_state() {
+        let mut button = Button::new(Point::new(10, 10), Size::new(20, 20), Svg::Search);
+        assert!(!button.touch_motion(Point::new(15., 15.)));
+
+        button.press(&Input::default());
+        assert!(button.pressed);
+
+        // Leaving and re-entering the button toggles its pressed state.
+        assert!(button.touch_motion(Point::new(50., 15.)));
+        assert!(!button.pressed);
+        assert!(!button.touch_motion(Point::new(60., 15.)));
+        assert!(button.touch_motion(Point::new(25., 25.)));
+
+        assert!(button.release());
+        assert!(!button.release());
+        assert!(!button.touch_motion(Point::new(15., 15.)));
+    }
+
+    #[test]
+    fn disabled_button_press() {
+        let mut button = Button::new(Point::new(10, 10), Size::new(20, 20), Svg::Search);
+        button.set_disabled(true);
+
+        button.press(&Input::default());
+        assert!(!button.pressed);
+        assert!(!button.touch_motion(Point::new(15., 15.)));
+        assert!(!button.release());
+    }
+
+    #[test]
+    fn action_button_touch() {
+        assert!(!press_button(None, &Input::default()));
+        assert!(!move_button_touch(None, Point::new(15., 15.)));
+        assert!(!release_button(None));
+
+        let mut button = Button::new(Point::new(10, 10), Size::new(20, 20), Svg::Search);
+        assert!(press_button(Some(&mut button), &Input::default()));
+        assert!(move_button_touch(Some(&mut button), Point::new(50., 15.)));
+        assert!(!release_button(Some(&mut button)));
+    }
 }
@@ -1,8 +1,9 @@
 //! OpenGL renderer.
 
-use std::ffi::CString;
+use std::ffi::{CStr, CString};
 use std::num::NonZeroU32;
 use std::ptr::NonNull;
+use std::sync::OnceLock;
 
 use glutin::config::{Api, Config, ConfigTemplateBuilder};
 use glutin::context::{ContextApi, ContextAttributesBuilder, PossiblyCurrentContext, Version};
@@ -17,6 +18,14 @@ use crate::geometry::Size;
 use crate::gl;
 use crate::ui::skia::GlConfig as SkiaGlConfig;
 
+/// OpenGL driver description, available after the first draw.
+static GL_INFO: OnceLock<String> = OnceLock::new();
+
+/// Get the OpenGL renderer and version of the active driver.
+pub fn gl_info() -> Option<&'static str> {
+    GL_INFO.get().map(String::as_str)
+}
+
 /// OpenGL renderer.
 #[derive(Debug)]
 pub struct Renderer {
@@ -55,6 +64,13 @@ impl Renderer {
         sized.swap_buffers();
     }
 
+    /// Make the renderer's OpenGL context current, if it was initialized.
+    pub fn make_current(&self) {
+        if let Some(sized) = &self.sized {
+            sized.make_current();
+        }
+    }
+
     /// Get render state requiring a size.
     fn sized(&mut self, size: Size) -> &SizedRenderer {
         // Initialize or resize sized state.
@@ -90,6 +106,13 @@ impl SizedRenderer {
         // Create EGL surface and context and make it current.
         let (egl_surface, egl_context, egl_config) = Self::create_surface(display, surface, size);
 
+        // Store driver details for diagnostics.
+        GL_INFO.get_or_init(|| {
+            let renderer = Self::gl_string(gl::RENDERER);
+            let version = Self::gl_string(gl::VERSION);
+            format!("{renderer} ({version})")
+        });
+
         Self { egl_surface, egl_context, egl_config, size }
     }
 
@@ -122,6 +145,15 @@ impl SizedRenderer {
         self.egl_context.make_current(&self.egl_surface).unwrap();
     }
 
+    /// Get an OpenGL string from the current context.
+    fn gl_string(name: gl::types::GLenum) -> String {
+        let string = unsafe { gl::GetString(name) };
+        if string.is_null() {
+            return String::from("unknown");
+        }
+        unsafe { CStr::from_ptr(string.cast()) }.to_string_lossy().into_owned()
+    }
+
     /// Perform OpenGL buffer swap.
     fn swap_buffers(&self) {
         self.egl_surface.swap_buffers(&self.egl_context).unwrap();
@@ -0,0 +1,441 @@
+//! Scrollable list of uniformly sized rows.
+
+use std::time::{Duration, Instant};
+
+use calloop::timer::{TimeoutAction, Timer};
+use calloop::{LoopHandle, RegistrationToken};
+use skia_safe::{Color4f, Paint, Rect};
+use tracing::error;
+
+use crate::State;
+use crate::config::{Config, Input};
+use crate::geometry::{Point, Size};
+use crate::ui::Velocity;
+use crate::ui::accessibility::AccessibleRole;
+use crate::ui::skia::RenderState;
+
+/// Time after scrolling stops before the scrollbar starts fading out.
+const SCROLLBAR_TIMEOUT: Duration = Duration::from_millis(750);
+
+/// Duration of the scrollbar fade-out animation.
+const SCROLLBAR_FADE: Duration = Duration::from_millis(250);
+
+/// Scrollbar width at scale 1.
+const SCROLLBAR_WIDTH: f64 = 4.;
+
+/// Minimum scrollbar length at scale 1.
+const SCROLLBAR_MIN_LENGTH: f64 = 32.;
+
+/// Maximum scrollbar opacity.
+const SCROLLBAR_ALPHA: f32 = 0.5;
+
+/// Content of a [`ScrollList`].
+pub trait RowProvider {
+    /// Get the total number of rows.
+    fn row_count(&self) -> usize;
+
+    /// Render a single row.
+    ///
+    /// Rows are indexed from the bottom, so index `0` is the bottommost row.
+    fn draw_row(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'_>,
+        index: usize,
+        point: Point,
+        size: Size,
+    );
+
+    /// Get a row's description for screen readers.
+    fn row_label(&self, _index: usize) -> Option<String> {
+        None
+    }
+}
+
+/// Vertically scrollable list, with rows stacked from the bottom up.
+pub struct ScrollList {
+    event_loop: LoopHandle<'static, State>,
+
+    velocity: Velocity,
+    offset: f64,
+
+    last_scroll: Option<Instant>,
+    fade_timer: Option<RegistrationToken>,
+
+    /// Physical location of the bottommost row.
+    point: Point,
+    /// Physical size of a single row.
+    row_size: Size,
+
+    /// Space between rows at scale 1.
+    row_padding: f64,
+    /// Space above the topmost row at scale 1.
+    top_padding: f64,
+    scale: f64,
+}
+
+impl ScrollList {
+    pub fn new(event_loop: LoopHandle<'static, State>, row_padding: f64, top_padding: f64) -> Self {
+        Self {
+            event_loop,
+            row_padding,
+            top_padding,
+            scale: 1.,
+            last_scroll: Default::default(),
+            fade_timer: Default::default(),
+            velocity: Default::default(),
+            row_size: Default::default(),
+            offset: Default::default(),
+            point: Default::default(),
+        }
+    }
+
+    /// Render all visible rows.
+    ///
+    /// Rows are clipped at the bottom of the list, to avoid overlapping other
+    /// elements while scrolling.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn draw<'a, P: RowProvider>(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        provider: &P,
+    ) {
+        let row_height = self.row_size.height as i32;
+        let padding = self.physical_row_padding() as i32;
+
+        let mut row_point = self.point;
+        row_point.y += self.offset.round() as i32;
+
+        // Set clipping mask to cut off rows overlapping elements below the list.
+        let right = self.point.x as f32 + self.row_size.width as f32;
+        let bottom = self.point.y as f32 + row_height as f32;
+        let clip_rect = Rect::new(self.point.x as f32, 0., right, bottom);
+        render_state.save();
+        render_state.clip_rect(clip_rect, None, Some(false));
+
+        for index in 0..provider.row_count() {
+            if row_point.y > self.point.y + row_height {
+                row_point.y -= row_height + padding;
+                continue;
+            } else if row_point.y + row_height < 0 {
+                break;
+            }
+
+            provider.draw_row(config, render_state, index, row_point, self.row_size);
+
+            // Expose the row's visible area to screen readers.
+            let row_bottom = row_point.y as f32 + row_height as f32;
+            let mut row_rect = Rect::new(row_point.x as f32, row_point.y as f32, right, row_bottom);
+            if let Some(label) = provider.row_label(index)
+                && row_rect.intersect(clip_rect)
+            {
+                render_state.add_accessible(AccessibleRole::ListItem, label, None, row_rect);
+            }
+
+            row_point.y -= row_height + padding;
+        }
+
+        // Reset clipping mask.
+        render_state.restore();
+
+        // Draw scrollbar on top of the rows.
+        let alpha = self.scrollbar_alpha();
+        if alpha > 0.
+            && let Some(rect) = self.scrollbar_rect(provider.row_count())
+        {
+            let mut color = Color4f::from(config.colors.foreground);
+            color.a = alpha;
+
+            let mut paint = Paint::default();
+            paint.set_color4f(color, None);
+            render_state.draw_rect(rect, &paint);
+        }
+    }
+
+    /// Apply scroll velocity and ensure the offset is within the content.
+    pub fn update(&mut self, input: &Input, row_count: usize) {
+        let was_moving = self.velocity.is_moving();
+
+        if let Some(delta) = self.velocity.apply(input.inertia()) {
+            self.offset += delta.y;
+            self.last_scroll = Some(Instant::now());
+        }
+        self.clamp_offset(row_count);
+
+        // Hide scrollbar once scroll velocity has stopped.
+        if was_moving && !self.velocity.is_moving() {
+            self.stage_fade();
+        }
+    }
+
+    /// Scroll the list by a touch drag distance.
+    ///
+    /// Returns `true` if the scroll offset was changed.
+    pub fn drag(&mut self, delta: f64, row_count: usize) -> bool {
+        // Update pending scroll velocity.
+        self.velocity.set(Point::new(0., delta));
+
+        let old_offset = self.offset;
+        self.offset += delta;
+        self.clamp_offset(row_count);
+
+        // Show scrollbar until scrolling stops.
+        self.last_scroll = Some(Instant::now());
+        self.stage_fade();
+
+        self.offset != old_offset
+    }
+
+    /// Scroll a row to the bottom of the list.
+    pub fn scroll_to(&mut self, index: usize, row_count: usize) {
+        let row_height = self.row_size.height as f64 + self.physical_row_padding() as f64;
+        self.offset = index as f64 * row_height;
+        self.clamp_offset(row_count);
+    }
+
+    /// Scroll back to the bottommost row.
+    pub fn reset(&mut self) {
+        self.velocity.stop();
+        self.last_scroll = None;
+        self.offset = 0.;
+    }
+
+    /// Cancel scroll velocity.
+    pub fn stop(&mut self) {
+        self.velocity.stop();
+    }
+
+    /// Check if the list is being animated.
+    ///
+    /// This is the case while scroll velocity is active or the scrollbar is
+    /// fading out.
+    pub fn is_animating(&self) -> bool {
+        let fading = self.last_scroll.is_some_and(|last_scroll| {
+            let elapsed = last_scroll.elapsed();
+            elapsed >= SCROLLBAR_TIMEOUT && elapsed < SCROLLBAR_TIMEOUT + SCROLLBAR_FADE
+        });
+        self.velocity.is_moving() || fading
+    }
+
+    /// Get the row at the specified location.
+    ///
+    /// Returns the row's index and the location relative to the row's origin.
+    pub fn row_at(&self, point: Point<f64>, row_count: usize) -> Option<(usize, Point<f64>)> {
+        let row_height = self.row_size.height as f64;
+        let rows_end = self.point.y as f64 + row_height;
+
+        // Short-circuit if point is outside the list.
+        if point.x < self.point.x as f64
+            || point.x >= self.point.x as f64 + self.row_size.width as f64
+            || point.y >= rows_end
+        {
+            return None;
+        }
+
+        // Ignore taps within vertical padding.
+        let padded_height = row_height + self.physical_row_padding() as f64;
+        let bottom_relative = rows_end - (point.y - self.offset) - 1.;
+        let row_relative = bottom_relative % padded_height;
+        if row_relative >= row_height {
+            return None;
+        }
+
+        // Find index at the specified offset.
+        let index = (bottom_relative / padded_height).floor() as usize;
+        if index >= row_count {
+            return None;
+        }
+
+        let relative_point =
+            Point::new(point.x - self.point.x as f64, row_height - 1. - row_relative);
+        Some((index, relative_point))
+    }
+
+    /// Update the physical location of the bottommost row.
+    pub fn set_point(&mut self, point: Point) {
+        self.point = point;
+    }
+
+    /// Update the physical size of a row.
+    pub fn set_row_size(&mut self, size: Size) {
+        self.row_size = size;
+    }
+
+    /// Update the render scale.
+    pub fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+    }
+
+    /// Clamp scroll offset to the list's content.
+    fn clamp_offset(&mut self, row_count: usize) {
+        let old_offset = self.offset;
+        self.offset = self.offset.clamp(0., self.max_offset(row_count) as f64);
+
+        // Cancel velocity after reaching the scroll limit.
+        if old_offset != self.offset {
+            self.velocity.stop();
+        }
+    }
+
+    /// Get maximum scroll offset.
+    fn max_offset(&self, row_count: usize) -> usize {
+        let top_padding = (self.top_padding * self.scale).round() as usize;
+        let padding = self.physical_row_padding() as usize;
+        let row_height = self.row_size.height as usize;
+
+        // Calculate height of all rows plus top padding.
+        let rows_height =
+            (row_count * (row_height + padding)).saturating_sub(padding) + top_padding;
+
+        // Calculate content outside the viewport, rows can start above it.
+        let visible_height = (self.point.y + row_height as i32).max(0) as usize;
+        rows_height.saturating_sub(visible_height)
+    }
+
+    /// Physical space between rows.
+    fn physical_row_padding(&self) -> u32 {
+        (self.row_padding * self.scale).round() as u32
+    }
+
+    /// Get the current scrollbar opacity.
+    fn scrollbar_alpha(&self) -> f32 {
+        let elapsed = match self.last_scroll {
+            Some(last_scroll) => last_scroll.elapsed(),
+            None => return 0.,
+        };
+
+        match elapsed.checked_sub(SCROLLBAR_TIMEOUT) {
+            Some(fade) => {
+                let progress = fade.as_secs_f32() / SCROLLBAR_FADE.as_secs_f32();
+                SCROLLBAR_ALPHA * (1. - progress).max(0.)
+            },
+            None => SCROLLBAR_ALPHA,
+        }
+    }
+
+    /// Get the physical scrollbar geometry.
+    ///
+    /// Returns `None` if all rows fit within the list.
+    fn scrollbar_rect(&self, row_count: usize) -> Option<Rect> {
+        let max_offset = self.max_offset(row_count) as f32;
+        if max_offset == 0. {
+            return None;
+        }
+
+        // Calculate scrollbar length relative to the visible content.
+        let visible_height = (self.point.y + self.row_size.height as i32).max(0) as f32;
+        let content_height = visible_height + max_offset;
+        let min_length = (SCROLLBAR_MIN_LENGTH * self.scale) as f32;
+        let length = (visible_height * visible_height / content_height).max(min_length);
+
+        // Place scrollbar at the bottom while showing the bottommost row.
+        let bottom = visible_height - (visible_height - length) * self.offset as f32 / max_offset;
+
+        let right = self.point.x as f32 + self.row_size.width as f32;
+        let left = right - (SCROLLBAR_WIDTH * self.scale) as f32;
+        Some(Rect::new(left, bottom - length, right, bottom))
+    }
+
+    /// Stage redraw for fading out the scrollbar.
+    fn stage_fade(&mut self) {
+        // Clear any previous timeouts.
+        if let Some(token) = self.fade_timer.take() {
+            self.event_loop.remove(token);
+        }
+
+        let timer = Timer::from_duration(SCROLLBAR_TIMEOUT);
+        let token = self.event_loop.insert_source(timer, |_, _, state| {
+            state.window.unstall();
+            TimeoutAction::Drop
+        });
+
+        self.fade_timer =
+            token.inspect_err(|err| error!("Failed to stage scrollbar timer: {err}")).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calloop::EventLoop;
+
+    use super::*;
+
+    #[test]
+    fn row_hit_testing() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let mut list = ScrollList::new(event_loop.handle(), 2., 16.);
+        list.set_point(Point::new(16, 400));
+        list.set_row_size(Size::new(100, 50));
+
+        // Bottommost row.
+        assert_eq!(list.row_at(Point::new(20., 449.), 3), Some((0, Point::new(4., 49.))));
+        assert_eq!(list.row_at(Point::new(20., 400.), 3), Some((0, Point::new(4., 0.))));
+
+        // Padding between rows.
+        assert_eq!(list.row_at(Point::new(20., 399.), 3), None);
+
+        // Second row.
+        assert_eq!(list.row_at(Point::new(20., 397.), 3), Some((1, Point::new(4., 49.))));
+
+        // Outside of the list.
+        assert_eq!(list.row_at(Point::new(10., 420.), 3), None);
+        assert_eq!(list.row_at(Point::new(20., 450.), 3), None);
+        assert_eq!(list.row_at(Point::new(20., 290.), 3), None);
+    }
+
+    #[test]
+    fn scroll_clamping() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let mut list = ScrollList::new(event_loop.handle(), 2., 16.);
+        list.set_point(Point::new(16, 100));
+        list.set_row_size(Size::new(100, 50));
+
+        // All rows fit on screen.
+        assert!(!list.drag(10., 2));
+
+        // 10 rows are 534px high with top padding, 150px of which are visible.
+        assert!(list.drag(1000., 10));
+        assert_eq!(list.offset, 384.);
+        assert!(!list.velocity.is_moving());
+
+        // Scrolled rows are hit tested at their new location.
+        assert_eq!(list.row_at(Point::new(20., 149.), 10).map(|(i, _)| i), Some(7));
+
+        list.scroll_to(2, 10);
+        assert_eq!(list.offset, 104.);
+    }
+
+    #[test]
+    fn tall_row_scrolling() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let mut list = ScrollList::new(event_loop.handle(), 0., 16.);
+        list.set_point(Point::new(16, -400));
+        list.set_row_size(Size::new(100, 500));
+
+        // The row is 516px high with top padding, 100px of which are visible.
+        assert!(list.drag(1000., 1));
+        assert_eq!(list.offset, 416.);
+    }
+
+    #[test]
+    fn scrollbar_geometry() {
+        let event_loop = EventLoop::try_new().unwrap();
+        let mut list = ScrollList::new(event_loop.handle(), 2., 16.);
+        list.set_point(Point::new(16, 100));
+        list.set_row_size(Size::new(100, 50));
+
+        // No scrollbar without scrollable content.
+        assert_eq!(list.scrollbar_rect(2), None);
+
+        // Scrollbar at the bottom while showing the first row.
+        let rect = list.scrollbar_rect(10).unwrap();
+        assert_eq!((rect.left, rect.right), (112., 116.));
+        assert_eq!(rect.bottom, 150.);
+        assert!((rect.height() - 150. * 150. / 534.).abs() < 0.01);
+
+        // Scrollbar at the top after scrolling to the end.
+        list.drag(1000., 10);
+        assert!(list.scrollbar_rect(10).unwrap().top.abs() < 0.01);
+    }
+}
@@ -1,6 +1,7 @@
 //! Skia graphics rendering.
 
 use std::collections::HashMap;
+use std::mem;
 use std::ops::Deref;
 use std::sync::Arc;
 
@@ -25,6 +26,7 @@ use crate::config::Config;
 use crate::geometry::{Point, Size};
 use crate::gl;
 use crate::gl::types::GLint;
+use crate::ui::accessibility::{AccessibleElement, AccessibleRole};
 
 /// Alpha value for preedit and placeholder text.
 const HINT_TEXT_ALPHA: f32 = 0.6;
@@ -45,6 +47,8 @@ pub struct Canvas {
     svg_cache: HashMap<SvgCacheKey, Image>,
     svg_paint: Paint,
 
+    accessible_elements: Vec<AccessibleElement>,
+
     scale: f32,
 }
 
@@ -92,6 +96,7 @@ impl Canvas {
             font_size,
             svg_paint: Paint::default(),
             scale: 1.,
+            accessible_elements: Default::default(),
             svg_cache: Default::default(),
             surface: Default::default(),
         }
@@ -106,33 +111,74 @@ impl Canvas {
         F: FnOnce(RenderState),
     {
         // Create Skia surface on-demand.
-        let surface = self.surface.get_or_insert_with(|| Surface::new(gl_config, size));
+        let mut surface = self.surface.take().unwrap_or_else(|| Surface::new(gl_config, size));
 
         // Resize surface if necessary.
         surface.resize(gl_config, size);
 
         // Perform custom rendering operations.
+        self.render(surface.surface.canvas(), f);
+
+        // Flush GPU commands.
+        surface.context.flush_and_submit();
+
+        self.surface = Some(surface);
+    }
+
+    /// Draw to a CPU raster surface, without requiring an OpenGL context.
+    pub fn draw_raster<F>(&mut self, size: Size, f: F) -> Image
+    where
+        F: FnOnce(RenderState),
+    {
+        let size = (size.width as i32, size.height as i32);
+        let mut surface = skia_safe::surfaces::raster_n32_premul(size).unwrap();
+
+        self.render(surface.canvas(), f);
+
+        surface.image_snapshot()
+    }
+
+    /// Release the OpenGL resources of the Skia surface.
+    ///
+    /// The OpenGL context used for drawing must be current.
+    pub fn release_gl_resources(&mut self) {
+        self.surface = None;
+    }
+
+    /// Take the interactive elements registered during the last frame.
+    pub fn take_accessible_elements(&mut self) -> Vec<AccessibleElement> {
+        mem::take(&mut self.accessible_elements)
+    }
+
+    /// Run rendering operations on a Skia canvas.
+    fn render<F>(&mut self, canvas: &SkiaCanvas, f: F)
+    where
+        F: FnOnce(RenderState),
+    {
+        self.accessible_elements.clear();
+
         f(RenderState {
+            accessible_elements: &mut self.accessible_elements,
             placeholder_style: &mut self.placeholder_style,
             selection_style: &mut self.selection_style,
             font_collection: &self.font_collection,
             preedit_style: &mut self.preedit_style,
-            canvas: surface.surface.canvas(),
             text_paint: &mut self.text_paint,
             text_style: &mut self.text_style,
             svg_cache: &mut self.svg_cache,
             svg_paint: &self.svg_paint,
             font_size: self.font_size,
             scale: self.scale,
+            canvas,
         });
-
-        // Flush GPU commands.
-        surface.context.flush_and_submit();
     }
 
     /// Handle DPI factor updates.
     pub fn set_scale_factor(&mut self, scale: f64) {
         self.scale = scale as f32;
+
+        // Drop SVGs rasterized for the old scale.
+        self.svg_cache.clear();
     }
 
     /// Handle config updates.
@@ -159,6 +205,8 @@ pub struct RenderState<'a> {
     text_paint: &'a mut Paint,
     font_size: f32,
 
+    accessible_elements: &'a mut Vec<AccessibleElement>,
+
     canvas: &'a SkiaCanvas,
 
     scale: f32,
@@ -265,6 +313,21 @@ impl<'a> RenderState<'a> {
         self.placeholder_style
     }
 
+    /// Expose an interactive element to screen readers.
+    ///
+    /// The `rect` is the element's physical bounds, which is also used as
+    /// target for actions requested by the screen reader.
+    pub fn add_accessible(
+        &mut self,
+        role: AccessibleRole,
+        label: impl Into<String>,
+        value: Option<String>,
+        rect: Rect,
+    ) {
+        let label = label.into();
+        self.accessible_elements.push(AccessibleElement { role, label, value, rect });
+    }
+
     /// Render an SVG with automatic caching.
     #[cfg_attr(feature = "profiling", profiling::function)]
     pub fn draw_svg(&mut self, svg: Svg, point: Point, size: Size) {
@@ -399,31 +462,146 @@ pub struct GlConfig {
 #[derive(Hash, PartialEq, Eq, Copy, Clone, Debug)]
 pub enum Svg {
     CancelRoute,
+    Perspective,
     Pedestrian,
+    StarFilled,
+    Directions,
+    MiniWindow,
+    Transport,
+    HeadingUp,
     ArrowLeft,
     Download,
+    Bicycle,
+    Lodging,
+    Preview,
+    Sliders,
+    NorthUp,
+    ZoomOut,
+    Offline,
+    Heatmap,
     Config,
     Search,
+    ZoomIn,
+    Health,
+    Nature,
+    Detach,
+    Record,
+    Online,
     Route,
+    Place,
+    Retry,
+    Check,
+    Close,
+    Track,
+    Info,
+    Star,
+    Food,
+    Shop,
+    Loop,
+    Lock,
+    Stop,
     Bin,
     Car,
     Gps,
+    Poi,
 }
 
 impl Svg {
+    /// Get the SVG's description for screen readers.
+    pub const fn label(&self) -> &'static str {
+        match self {
+            Self::CancelRoute => "Cancel route",
+            Self::Perspective => "Perspective view",
+            Self::Pedestrian => "Walking",
+            Self::StarFilled => "Favorite",
+            Self::Directions => "Text directions",
+            Self::MiniWindow => "Navigation overlay",
+            Self::Transport => "Public transport",
+            Self::HeadingUp => "Heading up",
+            Self::ArrowLeft => "Back",
+            Self::Download => "Download",
+            Self::Bicycle => "Cycling",
+            Self::Lodging => "Lodging",
+            Self::Preview => "Preview route",
+            Self::Sliders => "Settings",
+            Self::NorthUp => "North up",
+            Self::ZoomOut => "Zoom out",
+            Self::Offline => "Offline only",
+            Self::Heatmap => "Visited areas",
+            Self::Config => "Configuration",
+            Self::Search => "Search",
+            Self::ZoomIn => "Zoom in",
+            Self::Health => "Health",
+            Self::Nature => "Nature",
+            Self::Detach => "Open in new window",
+            Self::Record => "Start recording",
+            Self::Online => "Online services",
+            Self::Route => "Route",
+            Self::Place => "Place",
+            Self::Retry => "Retry",
+            Self::Check => "Done",
+            Self::Close => "Close",
+            Self::Track => "Recorded tracks",
+            Self::Info => "Information",
+            Self::Star => "Favorites",
+            Self::Food => "Food",
+            Self::Shop => "Shops",
+            Self::Loop => "Round trip",
+            Self::Lock => "Lock",
+            Self::Stop => "Stop recording",
+            Self::Bin => "Delete",
+            Self::Car => "Car",
+            Self::Gps => "Current location",
+            Self::Poi => "Points of interest",
+        }
+    }
+
     /// Get SVG's text content.
     const fn content(&self) -> &'static [u8] {
         match self {
             Self::CancelRoute => include_bytes!("../../svgs/cancel_route.svg"),
+            Self::Perspective => include_bytes!("../../svgs/perspective.svg"),
             Self::Pedestrian => include_bytes!("../../svgs/pedestrian.svg"),
+            Self::StarFilled => include_bytes!("../../svgs/star_filled.svg"),
+            Self::Directions => include_bytes!("../../svgs/directions.svg"),
+            Self::MiniWindow => include_bytes!("../../svgs/mini_window.svg"),
+            Self::Transport => include_bytes!("../../svgs/transport.svg"),
+            Self::HeadingUp => include_bytes!("../../svgs/heading_up.svg"),
             Self::ArrowLeft => include_bytes!("../../svgs/arrow_left.svg"),
             Self::Download => include_bytes!("../../svgs/download.svg"),
+            Self::Bicycle => include_bytes!("../../svgs/bicycle.svg"),
+            Self::Lodging => include_bytes!("../../svgs/lodging.svg"),
+            Self::Preview => include_bytes!("../../svgs/preview.svg"),
+            Self::Sliders => include_bytes!("../../svgs/sliders.svg"),
+            Self::NorthUp => include_bytes!("../../svgs/north_up.svg"),
+            Self::ZoomOut => include_bytes!("../../svgs/zoom_out.svg"),
+            Self::Offline => include_bytes!("../../svgs/offline.svg"),
+            Self::Heatmap => include_bytes!("../../svgs/heatmap.svg"),
             Self::Config => include_bytes!("../../svgs/config.svg"),
             Self::Search => include_bytes!("../../svgs/search.svg"),
+            Self::ZoomIn => include_bytes!("../../svgs/zoom_in.svg"),
+            Self::Health => include_bytes!("../../svgs/health.svg"),
+            Self::Nature => include_bytes!("../../svgs/nature.svg"),
+            Self::Detach => include_bytes!("../../svgs/detach.svg"),
+            Self::Record => include_bytes!("../../svgs/record.svg"),
+            Self::Online => include_bytes!("../../svgs/online.svg"),
             Self::Route => include_bytes!("../../svgs/route.svg"),
+            Self::Place => include_bytes!("../../svgs/place.svg"),
+            Self::Retry => include_bytes!("../../svgs/retry.svg"),
+            Self::Check => include_bytes!("../../svgs/check.svg"),
+            Self::Close => include_bytes!("../../svgs/close.svg"),
+            Self::Track => include_bytes!("../../svgs/track.svg"),
+            Self::Info => include_bytes!("../../svgs/info.svg"),
+            Self::Star => include_bytes!("../../svgs/star.svg"),
+            Self::Food => include_bytes!("../../svgs/food.svg"),
+            Self::Shop => include_bytes!("../../svgs/shop.svg"),
+            Self::Loop => include_bytes!("../../svgs/loop.svg"),
+            Self::Lock => include_bytes!("../../svgs/lock.svg"),
+            Self::Stop => include_bytes!("../../svgs/stop.svg"),
             Self::Bin => include_bytes!("../../svgs/bin.svg"),
             Self::Car => include_bytes!("../../svgs/car.svg"),
             Self::Gps => include_bytes!("../../svgs/gps.svg"),
+            Self::Poi => include_bytes!("../../svgs/poi.svg"),
         }
     }
 }
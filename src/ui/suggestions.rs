@@ -0,0 +1,250 @@
+//! Text input suggestions overlay.
+
+use skia_safe::{Color4f, Paint, Rect};
+
+use crate::config::Config;
+use crate::geometry::{Point, Size, rect_contains};
+use crate::ui::skia::RenderState;
+
+/// Maximum number of suggestions shown at once.
+const MAX_SUGGESTIONS: usize = 5;
+
+/// Suggestion entry height at scale 1.
+const ENTRY_HEIGHT: f64 = 48.;
+
+/// Vertical space between suggestion entries at scale 1.
+const ENTRY_PADDING: f64 = 2.;
+
+/// Horizontal padding around the suggestion text at scale 1.
+const TEXT_PADDING: f64 = 16.;
+
+/// List of suggestions stacked on top of a text field.
+///
+/// The first suggestion is closest to the text field, with all following
+/// suggestions placed above it.
+pub struct Suggestions {
+    entries: Vec<String>,
+    selected: Option<usize>,
+
+    bg_paint: Paint,
+    hl_paint: Paint,
+
+    /// Bottom-left corner of the suggestion list.
+    point: Point,
+    width: u32,
+    scale: f64,
+
+    dirty: bool,
+}
+
+impl Suggestions {
+    pub fn new(point: Point, width: u32) -> Self {
+        Self {
+            point,
+            width,
+            bg_paint: Paint::default(),
+            hl_paint: Paint::default(),
+            scale: 1.,
+            selected: Default::default(),
+            entries: Default::default(),
+            dirty: Default::default(),
+        }
+    }
+
+    /// Render the suggestions.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn draw<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) {
+        self.dirty = false;
+
+        self.bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        self.hl_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+
+        let entry_size = self.entry_size();
+        let text_padding = (TEXT_PADDING * self.scale).round() as f32;
+        let text_width = entry_size.width as f32 - 2. * text_padding;
+
+        for (i, entry) in self.entries.iter().enumerate() {
+            let point = self.entry_point(i);
+
+            // Draw entry background.
+            let right = point.x as f32 + entry_size.width as f32;
+            let bottom = point.y as f32 + entry_size.height as f32;
+            let rect = Rect::new(point.x as f32, point.y as f32, right, bottom);
+            let paint = if self.selected == Some(i) { &self.hl_paint } else { &self.bg_paint };
+            render_state.draw_rect(rect, paint);
+
+            // Draw entry text.
+            let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
+            builder.add_text(entry);
+
+            let mut paragraph = builder.build();
+            paragraph.layout(text_width);
+
+            let x = point.x as f32 + text_padding;
+            let y = point.y as f32 + (entry_size.height as f32 - paragraph.height()) / 2.;
+            paragraph.paint(render_state, Point::new(x, y));
+        }
+    }
+
+    /// Update suggestions for the current input text.
+    ///
+    /// Candidates are matched case-insensitively by their prefix, keeping
+    /// their original order and skipping duplicates.
+    pub fn update<'a>(&mut self, candidates: impl IntoIterator<Item = &'a str>, text: &str) {
+        let prefix = text.trim_start().to_lowercase();
+        let mut entries: Vec<String> = Vec::new();
+        if !prefix.is_empty() {
+            for candidate in candidates {
+                let lowercase = candidate.to_lowercase();
+                if lowercase.len() > prefix.len()
+                    && lowercase.starts_with(&prefix)
+                    && !entries.iter().any(|entry| entry == candidate)
+                {
+                    entries.push(candidate.into());
+                }
+
+                if entries.len() >= MAX_SUGGESTIONS {
+                    break;
+                }
+            }
+        }
+
+        if entries != self.entries {
+            self.entries = entries;
+            self.selected = None;
+            self.dirty = true;
+        }
+    }
+
+    /// Remove all suggestions.
+    pub fn clear(&mut self) {
+        self.dirty |= !self.entries.is_empty();
+        self.entries.clear();
+        self.selected = None;
+    }
+
+    /// Check if there are no suggestions.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Move the selection one suggestion away from the text field.
+    pub fn select_next(&mut self) {
+        let last = match self.entries.len().checked_sub(1) {
+            Some(last) => last,
+            None => return,
+        };
+
+        self.selected = Some(self.selected.map_or(0, |selected| (selected + 1).min(last)));
+        self.dirty = true;
+    }
+
+    /// Move the selection one suggestion towards the text field.
+    ///
+    /// Moving the selection past the first suggestion clears it.
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.and_then(|selected| selected.checked_sub(1));
+        self.dirty = true;
+    }
+
+    /// Get the selected suggestion.
+    pub fn selected(&self) -> Option<&str> {
+        self.entries.get(self.selected?).map(String::as_str)
+    }
+
+    /// Get the index of the suggestion at the specified location.
+    pub fn index_at(&self, point: Point<f64>) -> Option<usize> {
+        let point = Point::new(point.x.round() as i32, point.y.round() as i32);
+        let entry_size = self.entry_size();
+        (0..self.entries.len())
+            .find(|i| rect_contains(self.entry_point(*i), entry_size.into(), point))
+    }
+
+    /// Get the suggestion at an index.
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Check if the suggestions require a redraw.
+    pub fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Update the bottom-left corner of the suggestion list.
+    pub fn set_point(&mut self, point: Point) {
+        self.point = point;
+        self.dirty = true;
+    }
+
+    /// Update the width of the suggestion list.
+    pub fn set_width(&mut self, width: u32) {
+        self.width = width;
+        self.dirty = true;
+    }
+
+    /// Update the render scale.
+    pub fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.dirty = true;
+    }
+
+    /// Physical location of a suggestion entry.
+    fn entry_point(&self, index: usize) -> Point {
+        let entry_height = self.entry_size().height as i32;
+        let padding = (ENTRY_PADDING * self.scale).round() as i32;
+        let y = self.point.y - (index as i32 + 1) * entry_height - index as i32 * padding;
+        Point::new(self.point.x, y)
+    }
+
+    /// Physical size of a suggestion entry.
+    fn entry_size(&self) -> Size {
+        Size::new(self.width, (ENTRY_HEIGHT * self.scale).round() as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefix_matching() {
+        let history = ["Berlin Hbf", "bergstraße 5", "Bern", "Hamburg", "Ber"];
+        let mut suggestions = Suggestions::new(Point::default(), 100);
+
+        suggestions.update(history, "ber");
+        assert_eq!(suggestions.entries, ["Berlin Hbf", "bergstraße 5", "Bern"]);
+
+        suggestions.update(history, "  BERL");
+        assert_eq!(suggestions.entries, ["Berlin Hbf"]);
+
+        // Exact matches aren't worth suggesting.
+        suggestions.update(history, "hamburg");
+        assert!(suggestions.is_empty());
+
+        suggestions.update(history, "");
+        assert!(suggestions.is_empty());
+
+        // Offline matches are appended without repeating history entries.
+        suggestions.update(history.into_iter().chain(["Bern", "Bernau"]), "bern");
+        assert_eq!(suggestions.entries, ["Bernau"]);
+        suggestions.update(history.into_iter().chain(["Berlin Hbf", "Berlin"]), "berl");
+        assert_eq!(suggestions.entries, ["Berlin Hbf", "Berlin"]);
+    }
+
+    #[test]
+    fn selection() {
+        let mut suggestions = Suggestions::new(Point::default(), 100);
+        suggestions.update(["ab", "abc"], "a");
+        assert_eq!(suggestions.selected(), None);
+
+        suggestions.select_next();
+        assert_eq!(suggestions.selected(), Some("ab"));
+        suggestions.select_next();
+        suggestions.select_next();
+        assert_eq!(suggestions.selected(), Some("abc"));
+
+        suggestions.select_previous();
+        suggestions.select_previous();
+        assert_eq!(suggestions.selected(), None);
+    }
+}
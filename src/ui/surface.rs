@@ -0,0 +1,259 @@
+//! Secondary Wayland surfaces.
+
+use std::mem;
+use std::sync::Arc;
+
+use glutin::display::Display;
+use smithay_client_toolkit::compositor::{CompositorState, Region};
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
+use smithay_client_toolkit::reexports::client::{Connection, QueueHandle};
+use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
+use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
+
+use crate::State;
+use crate::config::Config;
+use crate::geometry::{Point, Size};
+use crate::router::Route;
+use crate::ui::renderer::Renderer;
+use crate::ui::skia::{Canvas, RenderState};
+use crate::wayland::ProtocolStates;
+
+/// Secondary surface types.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum SurfaceKind {
+    /// Separate window for textual directions.
+    Directions,
+    /// Always-on-top navigation overlay.
+    MiniWindow,
+}
+
+/// Wayland surface shown in addition to the main window.
+///
+/// All secondary surfaces show the active route, so they are updated together
+/// with the route and its progress.
+pub trait Surface {
+    /// Get the surface's type.
+    fn kind(&self) -> SurfaceKind;
+
+    /// Get the surface's rendering state.
+    fn state(&self) -> &SurfaceState;
+
+    /// Get the surface's mutable rendering state.
+    fn state_mut(&mut self) -> &mut SurfaceState;
+
+    /// Redraw the surface.
+    fn draw(&mut self);
+
+    /// Handle the compositor's surface configuration.
+    ///
+    /// Without a size suggested by the compositor, the current size is kept.
+    fn configure(&mut self, compositor: &CompositorState, size: Option<Size>);
+
+    /// Update the surface's DPI factor.
+    fn set_scale_factor(&mut self, scale: f64);
+
+    /// Handle config updates.
+    fn update_config(&mut self, config: &Config);
+
+    /// Update the displayed route.
+    fn set_route(&mut self, route: Arc<Route>);
+
+    /// Set the number of nodes already traveled in the route.
+    fn set_progress(&mut self, progress: usize);
+
+    /// Check whether a touch sequence was started on this surface.
+    fn has_touch(&self, _slot: i32) -> bool {
+        false
+    }
+
+    /// Handle touch press.
+    fn touch_down(&mut self, _slot: i32, _time: u32, _point: Point<f64>) {}
+
+    /// Handle touch motion.
+    fn touch_motion(&mut self, _slot: i32, _point: Point<f64>) {}
+
+    /// Handle touch release.
+    fn touch_up(&mut self, _slot: i32) {}
+
+    /// Handle keyboard key press.
+    fn press_key(&mut self, _raw: u32, _keysym: Keysym, _modifiers: Modifiers) {}
+
+    /// Get the surface's Wayland surface.
+    fn wl_surface(&self) -> &WlSurface {
+        &self.state().wl_surface
+    }
+
+    /// Unstall the renderer.
+    ///
+    /// This will render a new frame if there currently is no frame request
+    /// pending.
+    fn unstall(&mut self) {
+        if !mem::take(&mut self.state_mut().stalled) {
+            return;
+        }
+
+        self.draw();
+        let _ = self.state().connection.flush();
+    }
+}
+
+/// Rendering state shared by all secondary surfaces.
+pub struct SurfaceState {
+    // NOTE: The renderer must be dropped before the shell surface destroys the
+    // surface its EGL surface was created for.
+    renderer: Renderer,
+    canvas: Canvas,
+
+    queue: QueueHandle<State>,
+    connection: Connection,
+    wl_surface: WlSurface,
+    viewport: WpViewport,
+
+    config: Config,
+
+    size: Size,
+    scale: f64,
+
+    initial_configure_done: bool,
+    stalled: bool,
+}
+
+impl SurfaceState {
+    /// Create a new Wayland surface with its renderer.
+    pub fn new(
+        protocol_states: &ProtocolStates,
+        connection: Connection,
+        queue: QueueHandle<State>,
+        egl_display: Display,
+        config: &Config,
+        size: Size,
+    ) -> Self {
+        // Create surface's Wayland global handles.
+        let wl_surface = protocol_states.compositor.create_surface(&queue);
+        if let Some(fractional_scale) = &protocol_states.fractional_scale {
+            fractional_scale.fractional_scaling(&queue, &wl_surface);
+        }
+        let viewport = protocol_states.viewporter.viewport(&queue, &wl_surface);
+
+        let renderer = Renderer::new(egl_display, wl_surface.clone());
+
+        Self {
+            connection,
+            wl_surface,
+            renderer,
+            viewport,
+            queue,
+            size,
+            canvas: Canvas::new(config),
+            config: config.clone(),
+            stalled: true,
+            scale: 1.,
+            initial_configure_done: Default::default(),
+        }
+    }
+
+    /// Get the surface's Wayland surface.
+    pub fn wl_surface(&self) -> &WlSurface {
+        &self.wl_surface
+    }
+
+    /// Get the Wayland event queue of the surface.
+    pub fn queue(&self) -> &QueueHandle<State> {
+        &self.queue
+    }
+
+    /// Get the surface's logical size.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Get the surface's DPI factor.
+    pub fn scale(&self) -> f64 {
+        self.scale
+    }
+
+    /// Render a new frame.
+    ///
+    /// The renderer is stalled instead if the content is not `dirty`, returning
+    /// `false`.
+    pub fn draw<F>(&mut self, dirty: bool, f: F) -> bool
+    where
+        F: FnOnce(&Config, RenderState),
+    {
+        // Stall rendering if nothing changed since last redraw.
+        if !dirty || !self.initial_configure_done {
+            self.stalled = true;
+            return false;
+        }
+
+        // Update viewporter logical render size.
+        self.viewport.set_destination(self.size.width as i32, self.size.height as i32);
+
+        // Mark entire surface as damaged.
+        self.wl_surface.damage(0, 0, self.size.width as i32, self.size.height as i32);
+
+        // Render the surface content.
+        let size = self.size * self.scale;
+        let config = &self.config;
+        let canvas = &mut self.canvas;
+        self.renderer.draw(size, |renderer| {
+            canvas.draw(renderer.skia_config(), size, |render_state| f(config, render_state));
+        });
+
+        // Request a new frame.
+        self.wl_surface.frame(&self.queue, self.wl_surface.clone());
+
+        // Apply surface changes.
+        self.wl_surface.commit();
+
+        true
+    }
+
+    /// Update the surface's logical size.
+    ///
+    /// Returns `false` if the size was already applied.
+    pub fn configure(&mut self, compositor: &CompositorState, size: Size) -> bool {
+        if self.initial_configure_done && self.size == size {
+            return false;
+        }
+
+        self.initial_configure_done = true;
+        self.size = size;
+
+        // Update the surface's opaque region.
+        if let Ok(region) = Region::new(compositor) {
+            region.add(0, 0, size.width as i32, size.height as i32);
+            self.wl_surface.set_opaque_region(Some(region.wl_region()));
+        }
+
+        true
+    }
+
+    /// Update the surface's DPI factor.
+    ///
+    /// Returns `false` if the scale was already applied.
+    pub fn set_scale_factor(&mut self, scale: f64) -> bool {
+        if self.scale == scale {
+            return false;
+        }
+
+        self.canvas.set_scale_factor(scale);
+        self.scale = scale;
+
+        true
+    }
+
+    /// Handle config updates.
+    pub fn update_config(&mut self, config: &Config) {
+        self.canvas.update_config(config);
+        self.config = config.clone();
+    }
+}
+
+impl Drop for SurfaceState {
+    fn drop(&mut self) {
+        // Free Skia's GPU resources while their OpenGL context is still alive.
+        self.renderer.make_current();
+        self.canvas.release_gl_resources();
+    }
+}
@@ -17,6 +17,7 @@ use tracing::{error, warn};
 use crate::State;
 use crate::config::{Config, Input as InputConfig};
 use crate::geometry::{Point, Size};
+use crate::ui::accessibility::AccessibleRole;
 use crate::ui::rect_contains;
 use crate::ui::skia::{RenderState, TextOptions};
 
@@ -32,6 +33,9 @@ const TEXT_PADDING: f32 = 15.;
 /// Selection caret size at scale 1.
 const CARET_SIZE: f64 = 5.;
 
+/// Maximum number of undo steps.
+const MAX_UNDO_STEPS: usize = 100;
+
 /// Single line text input field.
 pub struct TextField {
     event_loop: LoopHandle<'static, State>,
@@ -49,6 +53,7 @@ pub struct TextField {
 
     selection: Option<Range<usize>>,
     cursor_index: usize,
+    history: UndoHistory,
 
     touch_state: TouchState,
     scroll_offset: f64,
@@ -92,6 +97,7 @@ impl TextField {
             placeholder: Default::default(),
             touch_state: Default::default(),
             selection: Default::default(),
+            history: Default::default(),
             text: Default::default(),
         }
     }
@@ -110,6 +116,9 @@ impl TextField {
         self.paint.set_color4f(background.into(), None);
         render_state.draw_rect(bg_rect, &self.paint);
 
+        let value = Some(self.text.clone());
+        render_state.add_accessible(AccessibleRole::TextInput, self.placeholder, value, bg_rect);
+
         // Get selection range, defaulting to an empty selection.
         let selection = match self.selection.as_ref() {
             Some(selection) => selection.start..selection.end,
@@ -313,6 +322,8 @@ impl TextField {
             return;
         }
 
+        self.checkpoint(EditKind::Replace);
+
         self.cursor_index = text.len();
         self.focus_cursor = true;
         self.text = text;
@@ -328,6 +339,11 @@ impl TextField {
         &self.text
     }
 
+    /// Get the IME's uncommitted text.
+    pub fn preedit_text(&self) -> &str {
+        &self.preedit_text
+    }
+
     /// Set placeholder text, if no text is present.
     pub fn set_placeholder(&mut self, placeholder: &'static str) {
         self.placeholder = placeholder;
@@ -344,57 +360,71 @@ impl TextField {
         self.focus_cursor = true;
 
         match (keysym, modifiers.shift, modifiers.ctrl) {
-            (Keysym::Left, false, false) => {
-                self.cursor_index = match self.selection.take() {
-                    Some(selection) => selection.start,
-                    None => self.cursor_index.saturating_sub(1),
+            (Keysym::Left | Keysym::Right | Keysym::Home | Keysym::End, shift, ctrl) => {
+                // Keep the opposite end of the selection in place while extending it.
+                let anchor = shift.then(|| self.selection_anchor());
+                self.history.end_group();
+
+                let index = match (keysym, &self.selection) {
+                    // Collapse selection in the direction of movement.
+                    (Keysym::Left, Some(selection)) if !shift && !ctrl => selection.start,
+                    (Keysym::Right, Some(selection)) if !shift && !ctrl => selection.end,
+                    (Keysym::Left, _) if ctrl => prev_word_boundary(&self.text, self.cursor_index),
+                    (Keysym::Right, _) if ctrl => next_word_boundary(&self.text, self.cursor_index),
+                    (Keysym::Left, _) => prev_char_boundary(&self.text, self.cursor_index),
+                    (Keysym::Right, _) => next_char_boundary(&self.text, self.cursor_index),
+                    (Keysym::Home, _) => 0,
+                    _ => self.text.len(),
                 };
+                self.cursor_index = index;
 
-                self.text_input_dirty = true;
-                self.dirty = true;
-            },
-            (Keysym::Right, false, false) => {
-                self.cursor_index = match self.selection.take() {
-                    Some(selection) => selection.end,
-                    None => cmp::min(self.cursor_index + 1, self.text.len()),
-                };
+                match anchor {
+                    Some(anchor) => self.select(cmp::min(anchor, index)..cmp::max(anchor, index)),
+                    None => self.selection = None,
+                }
 
                 self.text_input_dirty = true;
                 self.dirty = true;
             },
-            (Keysym::BackSpace, false, false) => {
-                if self.text.is_empty() {
+            (Keysym::BackSpace, false, ctrl) => {
+                if self.cursor_index == 0 && self.selection.is_none() {
                     return;
                 }
+                self.checkpoint(EditKind::Delete);
 
                 match self.selection.take() {
                     Some(selection) => self.delete_selected(selection),
                     None if self.cursor_index == 0 => return,
                     None => {
-                        if self.text.is_empty() || self.cursor_index == 0 {
-                            return;
-                        }
-
-                        // Jump to the previous character.
-                        self.cursor_index = self.cursor_index.saturating_sub(1);
-                        while self.cursor_index > 0
-                            && !self.text.is_char_boundary(self.cursor_index)
-                        {
-                            self.cursor_index -= 1;
-                        }
-
-                        // Pop the character after the cursor.
-                        self.text.remove(self.cursor_index);
+                        // Find start of the previous character or word.
+                        let start = if ctrl {
+                            prev_word_boundary(&self.text, self.cursor_index)
+                        } else {
+                            prev_char_boundary(&self.text, self.cursor_index)
+                        };
+
+                        self.text.drain(start..self.cursor_index);
+                        self.cursor_index = start;
                     },
                 }
 
                 self.text_input_dirty = true;
                 self.dirty = true;
             },
-            (Keysym::Delete, false, false) => {
+            (Keysym::Delete, false, ctrl) => {
+                if self.cursor_index >= self.text.len() && self.selection.is_none() {
+                    return;
+                }
+                self.checkpoint(EditKind::Delete);
+
                 match self.selection.take() {
                     Some(selection) => self.delete_selected(selection),
                     None if self.cursor_index >= self.text.len() => return,
+                    // Pop the next word after the cursor.
+                    None if ctrl => {
+                        let end = next_word_boundary(&self.text, self.cursor_index);
+                        self.text.drain(self.cursor_index..end);
+                    },
                     // Pop character after the cursor.
                     None => _ = self.text.remove(self.cursor_index),
                 }
@@ -402,6 +432,12 @@ impl TextField {
                 self.text_input_dirty = true;
                 self.dirty = true;
             },
+            (Keysym::a, false, true) => {
+                self.cursor_index = self.text.len();
+                self.select(..);
+            },
+            (Keysym::z, false, true) => self.undo(),
+            (Keysym::Z, true, true) => self.redo(),
             (Keysym::Return, false, false) => {
                 // Submit current search.
                 self.event_loop.insert_idle(move |state| {
@@ -460,6 +496,8 @@ impl TextField {
                     None => return,
                 };
 
+                self.checkpoint(EditKind::Insert);
+
                 // Delete selection before writing new text.
                 if let Some(selection) = self.selection.take() {
                     self.delete_selected(selection);
@@ -553,6 +591,7 @@ impl TextField {
         match self.touch_state.action {
             TouchAction::Tap => {
                 self.cursor_index = self.offset_at(point).unwrap_or(0);
+                self.history.end_group();
                 self.focus_cursor = true;
 
                 self.clear_selection();
@@ -593,6 +632,12 @@ impl TextField {
 
     /// Paste text into the input element.
     pub fn paste(&mut self, text: &str) {
+        self.checkpoint(EditKind::Paste);
+        self.insert_text(text);
+    }
+
+    /// Insert text at the current cursor position.
+    fn insert_text(&mut self, text: &str) {
         // Delete selection before writing new text.
         if let Some(selection) = self.selection.take() {
             self.delete_selected(selection);
@@ -615,6 +660,8 @@ impl TextField {
 
     /// Delete text around the current cursor position.
     pub fn delete_surrounding_text(&mut self, before_length: u32, after_length: u32) {
+        self.checkpoint(EditKind::Ime);
+
         // Calculate removal boundaries.
         let end = (self.cursor_index + after_length as usize).min(self.text.len());
         let start = self.cursor_index.saturating_sub(before_length as usize);
@@ -633,7 +680,8 @@ impl TextField {
 
     /// Insert text at the current cursor position.
     pub fn commit_string(&mut self, text: &str) {
-        self.paste(text);
+        self.checkpoint(EditKind::Insert);
+        self.insert_text(text);
     }
 
     /// Set preedit text at the current cursor position.
@@ -647,6 +695,7 @@ impl TextField {
         if !text.is_empty()
             && let Some(selection) = self.selection.take()
         {
+            self.checkpoint(EditKind::Delete);
             self.delete_selected(selection);
         }
 
@@ -721,6 +770,51 @@ impl TextField {
         }
     }
 
+    /// Store the current text for undoing the next modification.
+    fn checkpoint(&mut self, kind: EditKind) {
+        self.history.push(&self.text, self.cursor_index, kind);
+    }
+
+    /// Revert the last text modification.
+    fn undo(&mut self) {
+        if let Some(snapshot) = self.history.undo(&self.text, self.cursor_index) {
+            self.restore(snapshot);
+        }
+    }
+
+    /// Reapply the last reverted text modification.
+    fn redo(&mut self) {
+        if let Some(snapshot) = self.history.redo(&self.text, self.cursor_index) {
+            self.restore(snapshot);
+        }
+    }
+
+    /// Replace text and cursor with an undo snapshot.
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.text = snapshot.text;
+        self.cursor_index = snapshot.cursor_index;
+        self.selection = None;
+
+        self.text_input_dirty = true;
+        self.dirty = true;
+    }
+
+    /// Get the end of the selection which stays fixed during keyboard
+    /// selection.
+    ///
+    /// This moves the cursor to the other end of the selection, if it isn't
+    /// at either end already.
+    fn selection_anchor(&mut self) -> usize {
+        match &self.selection {
+            Some(selection) if self.cursor_index == selection.start => selection.end,
+            Some(selection) => {
+                self.cursor_index = selection.end;
+                selection.start
+            },
+            None => self.cursor_index,
+        }
+    }
+
     /// Clear text selection.
     fn clear_selection(&mut self) {
         if self.selection.is_none() {
@@ -970,6 +1064,82 @@ impl TouchState {
     }
 }
 
+/// Undo/redo stack for text modifications.
+#[derive(Default)]
+struct UndoHistory {
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: Option<EditKind>,
+}
+
+impl UndoHistory {
+    /// Record the text state before a modification.
+    ///
+    /// Consecutive insertions are grouped into a single undo step, as is IME
+    /// text replacement followed by its inserted text.
+    fn push(&mut self, text: &str, cursor_index: usize, kind: EditKind) {
+        let last_edit = self.last_edit.replace(kind);
+        if kind == EditKind::Insert && matches!(last_edit, Some(EditKind::Insert | EditKind::Ime)) {
+            return;
+        }
+
+        if self.undo.len() >= MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+        self.undo.push(Snapshot::new(text, cursor_index));
+        self.redo.clear();
+    }
+
+    /// Get the state before the last modification.
+    fn undo(&mut self, text: &str, cursor_index: usize) -> Option<Snapshot> {
+        let snapshot = self.undo.pop()?;
+        self.redo.push(Snapshot::new(text, cursor_index));
+        self.last_edit = None;
+        Some(snapshot)
+    }
+
+    /// Get the state before the last undo.
+    fn redo(&mut self, text: &str, cursor_index: usize) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push(Snapshot::new(text, cursor_index));
+        self.last_edit = None;
+        Some(snapshot)
+    }
+
+    /// Stop grouping the next modification with previous ones.
+    fn end_group(&mut self) {
+        self.last_edit = None;
+    }
+}
+
+/// Text field state for undo/redo.
+#[derive(PartialEq, Debug)]
+struct Snapshot {
+    text: String,
+    cursor_index: usize,
+}
+
+impl Snapshot {
+    fn new(text: &str, cursor_index: usize) -> Self {
+        Self { text: text.into(), cursor_index }
+    }
+}
+
+/// Type of a text modification.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum EditKind {
+    /// Typed or IME-committed text.
+    Insert,
+    /// Deleted characters or selection.
+    Delete,
+    /// Pasted text.
+    Paste,
+    /// IME deletion of text surrounding the cursor.
+    Ime,
+    /// Replacement of the entire text.
+    Replace,
+}
+
 /// Intention of a touch sequence.
 #[derive(Default, PartialEq, Eq, Copy, Clone, Debug)]
 enum TouchAction {
@@ -1005,3 +1175,80 @@ impl GlyphMetrics {
         }
     }
 }
+
+/// Get the start of the character before `index`.
+fn prev_char_boundary(text: &str, index: usize) -> usize {
+    text[..index].char_indices().next_back().map_or(0, |(i, _)| i)
+}
+
+/// Get the end of the character after `index`.
+fn next_char_boundary(text: &str, index: usize) -> usize {
+    text[index..].chars().next().map_or(index, |c| index + c.len_utf8())
+}
+
+/// Get the start of the word before `index`.
+///
+/// Any non-alphanumeric characters directly before `index` are skipped.
+fn prev_word_boundary(text: &str, index: usize) -> usize {
+    let mut chars = text[..index].char_indices().rev().skip_while(|(_, c)| !c.is_alphanumeric());
+    chars.find(|(_, c)| !c.is_alphanumeric()).map_or(0, |(i, c)| i + c.len_utf8())
+}
+
+/// Get the end of the word after `index`.
+///
+/// Any non-alphanumeric characters directly after `index` are skipped.
+fn next_word_boundary(text: &str, index: usize) -> usize {
+    let mut chars = text[index..].char_indices().skip_while(|(_, c)| !c.is_alphanumeric());
+    chars.find(|(_, c)| !c.is_alphanumeric()).map_or(text.len(), |(i, _)| index + i)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn char_boundaries() {
+        let text = "aßc";
+        assert_eq!(next_char_boundary(text, 0), 1);
+        assert_eq!(next_char_boundary(text, 1), 3);
+        assert_eq!(next_char_boundary(text, 4), 4);
+        assert_eq!(prev_char_boundary(text, 4), 3);
+        assert_eq!(prev_char_boundary(text, 3), 1);
+        assert_eq!(prev_char_boundary(text, 0), 0);
+    }
+
+    #[test]
+    fn undo_grouping() {
+        let mut history = UndoHistory::default();
+
+        // Consecutive insertions are undone together.
+        history.push("", 0, EditKind::Insert);
+        history.push("a", 1, EditKind::Insert);
+        history.push("ab", 2, EditKind::Delete);
+        history.push("a", 1, EditKind::Ime);
+        history.push("", 0, EditKind::Insert);
+
+        assert_eq!(history.undo("xyz", 3), Some(Snapshot::new("a", 1)));
+        assert_eq!(history.undo("a", 1), Some(Snapshot::new("ab", 2)));
+        assert_eq!(history.undo("ab", 2), Some(Snapshot::new("", 0)));
+        assert_eq!(history.undo("", 0), None);
+
+        assert_eq!(history.redo("", 0), Some(Snapshot::new("ab", 2)));
+
+        // New modifications clear the redo stack.
+        history.push("ab", 2, EditKind::Paste);
+        assert_eq!(history.redo("abc", 3), None);
+    }
+
+    #[test]
+    fn word_boundaries() {
+        let text = "Straße 12, Köln";
+        assert_eq!(next_word_boundary(text, 0), 7);
+        assert_eq!(next_word_boundary(text, 7), 10);
+        assert_eq!(next_word_boundary(text, 10), text.len());
+        assert_eq!(prev_word_boundary(text, text.len()), 12);
+        assert_eq!(prev_word_boundary(text, 12), 8);
+        assert_eq!(prev_word_boundary(text, 8), 0);
+        assert_eq!(prev_word_boundary(text, 0), 0);
+    }
+}
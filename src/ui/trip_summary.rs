@@ -0,0 +1,335 @@
+//! Trip summary image shown after arriving at a destination.
+
+use std::path::PathBuf;
+use std::time::{Instant, UNIX_EPOCH};
+
+use skia_safe::{
+    Color4f, EncodedImageFormat, FilterMode, MipmapMode, Paint, PaintCap, PaintJoin, PathBuilder,
+    Rect, SamplingOptions,
+};
+
+use crate::Error;
+use crate::config::Config;
+use crate::geometry::{GeoPoint, Point, Size};
+use crate::tiles::{TILE_SIZE, TileIndex, Tiles};
+use crate::ui::skia::{Canvas, RenderState};
+use crate::ui::view;
+
+/// Minimum distance between two recorded trip points in meters.
+const MIN_POINT_DISTANCE: u32 = 10;
+
+/// Zoom level used to project the traveled path.
+const PROJECTION_ZOOM: u8 = 20;
+
+/// Logical size of the summary image.
+const CARD_SIZE: Size = Size { width: 480, height: 360 };
+
+/// Scale factor of the summary image.
+const CARD_SCALE: f64 = 2.;
+
+/// Padding around the summary's content at scale 1.
+const PADDING: f32 = 16.;
+
+/// Height of the traveled path's map at scale 1.
+const MAP_HEIGHT: f32 = 240.;
+
+/// Width of the traveled path at scale 1.
+const PATH_WIDTH: f32 = 4.;
+
+/// Radius of the start and end markers at scale 1.
+const MARKER_RADIUS: f32 = 6.;
+
+/// Distance font size relative to the default.
+const DISTANCE_FONT_SIZE: f32 = 1.25;
+
+/// Traveled path of a GPS navigation.
+pub struct Trip {
+    points: Vec<GeoPoint>,
+    start: Instant,
+    distance: u32,
+}
+
+impl Default for Trip {
+    fn default() -> Self {
+        Self { start: Instant::now(), points: Default::default(), distance: Default::default() }
+    }
+}
+
+impl Trip {
+    /// Add a GPS location to the trip.
+    pub fn update(&mut self, point: GeoPoint) {
+        // Skip points too close to the last location, to ignore GPS jitter.
+        if let Some(last) = self.points.last() {
+            let distance = last.distance(point);
+            if distance < MIN_POINT_DISTANCE {
+                return;
+            }
+            self.distance += distance;
+        }
+
+        self.points.push(point);
+    }
+
+    /// Render the trip summary as PNG image.
+    ///
+    /// Map tiles which are already loaded are drawn behind the traveled path,
+    /// tiles missing from the cache are left blank.
+    pub fn render(&self, config: &Config, tiles: &mut Tiles) -> Option<Vec<u8>> {
+        let mut canvas = Canvas::new(config);
+        canvas.set_scale_factor(CARD_SCALE);
+
+        let scale = CARD_SCALE as f32;
+        let size = CARD_SIZE * CARD_SCALE;
+        let image = canvas.draw_raster(size, |mut render_state| {
+            let padding = PADDING * scale;
+            let content_width = size.width as f32 - 2. * padding;
+
+            render_state.clear(config.colors.background);
+
+            // Draw the traveled path's background.
+
+            let map_rect = Rect::new(padding, padding, padding + content_width, MAP_HEIGHT * scale);
+            let mut paint = Paint::default();
+            paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+            paint.set_anti_alias(true);
+            render_state.draw_rect(map_rect, &paint);
+
+            // Draw the map tiles around the traveled path.
+
+            let inner_rect = map_rect.with_inset((padding, padding));
+            let fit = self.fit(inner_rect);
+            fit.draw_tiles(config, &mut render_state, tiles, map_rect);
+
+            // Draw the traveled path, scaled to fit the map area.
+
+            let points = fit.points();
+
+            paint.set_color4f(Color4f::from(config.colors.highlight), None);
+            paint.set_stroke_width(PATH_WIDTH * scale);
+            paint.set_stroke_join(PaintJoin::Round);
+            paint.set_stroke_cap(PaintCap::Round);
+            paint.set_stroke(true);
+
+            let mut path = PathBuilder::new();
+            for (i, point) in points.iter().enumerate() {
+                if i == 0 {
+                    path.move_to(*point);
+                } else {
+                    path.line_to(*point);
+                }
+            }
+            render_state.draw_path(&path.detach(), &paint);
+
+            // Mark the trip's origin and destination.
+
+            paint.set_stroke(false);
+            if let Some(origin) = points.first() {
+                paint.set_color4f(Color4f::from(config.colors.foreground), None);
+                render_state.draw_circle(*origin, MARKER_RADIUS * scale, &paint);
+            }
+            if let Some(destination) = points.last() {
+                paint.set_color4f(Color4f::from(config.colors.highlight), None);
+                render_state.draw_circle(*destination, MARKER_RADIUS * scale, &paint);
+            }
+
+            // Draw traveled distance.
+
+            let mut distance = String::with_capacity("X.XX km".len());
+            view::format_distance(&mut distance, self.distance, config.navigation.units);
+
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, DISTANCE_FONT_SIZE, None);
+            builder.add_text(&distance);
+
+            let mut distance_paragraph = builder.build();
+            distance_paragraph.layout(content_width);
+
+            let mut text_point = Point::new(padding, map_rect.bottom + padding);
+            distance_paragraph.paint(&render_state, text_point);
+            text_point.y += distance_paragraph.height();
+
+            // Draw trip duration and average speed.
+
+            let duration = self.start.elapsed().as_secs();
+            let hours = duration / 3600;
+            let minutes = (duration % 3600 + 30) / 60;
+            let mut details = format!("{hours:0>2}:{minutes:0>2} · ");
+            let speed = self.distance as f64 / duration.max(1) as f64 * 3.6;
+            view::format_speed(&mut details, speed, config.navigation.units);
+
+            let mut builder = render_state.paragraph(config.colors.alt_foreground, 1., None);
+            builder.add_text(&details);
+
+            let mut details_paragraph = builder.build();
+            details_paragraph.layout(content_width);
+            details_paragraph.paint(&render_state, text_point);
+        });
+
+        let data = image.encode(None, EncodedImageFormat::PNG, None)?;
+        Some(data.as_bytes().to_vec())
+    }
+
+    /// Scale and center the trip's points to fit a rectangle.
+    fn fit(&self, rect: Rect) -> PathFit {
+        let pixels: Vec<_> = self
+            .points
+            .iter()
+            .map(|point| {
+                let (tile, offset) = point.tile(PROJECTION_ZOOM);
+                let x = tile.x as f64 * TILE_SIZE as f64 + offset.x as f64;
+                let y = tile.y as f64 * TILE_SIZE as f64 + offset.y as f64;
+                Point::new(x, y)
+            })
+            .collect();
+
+        // Get the path's bounding box.
+        let mut min = Point::new(f64::MAX, f64::MAX);
+        let mut max = Point::new(f64::MIN, f64::MIN);
+        for pixel in &pixels {
+            (min.x, min.y) = (min.x.min(pixel.x), min.y.min(pixel.y));
+            (max.x, max.y) = (max.x.max(pixel.x), max.y.max(pixel.y));
+        }
+
+        // Scale uniformly, without dividing by zero for trips without movement.
+        let width = (max.x - min.x).max(1.);
+        let height = (max.y - min.y).max(1.);
+        let scale = (rect.width() as f64 / width).min(rect.height() as f64 / height);
+
+        let origin = Point::new(
+            rect.left as f64 + (rect.width() as f64 - width * scale) / 2.,
+            rect.top as f64 + (rect.height() as f64 - height * scale) / 2.,
+        );
+
+        PathFit { pixels, min, origin, scale }
+    }
+}
+
+/// Traveled path projected onto the summary image.
+struct PathFit {
+    /// Path points in pixels at the [`PROJECTION_ZOOM`].
+    pixels: Vec<Point<f64>>,
+    /// Top-left corner of the path's bounding box at the [`PROJECTION_ZOOM`].
+    min: Point<f64>,
+    /// Top-left corner of the path's bounding box in the image.
+    origin: Point<f64>,
+    /// Image pixels per pixel at the [`PROJECTION_ZOOM`].
+    scale: f64,
+}
+
+impl PathFit {
+    /// Get the path's points in image coordinates.
+    fn points(&self) -> Vec<Point<f32>> {
+        self.pixels.iter().map(|pixel| self.project(*pixel)).collect()
+    }
+
+    /// Convert a pixel at the [`PROJECTION_ZOOM`] to image coordinates.
+    fn project(&self, pixel: Point<f64>) -> Point<f32> {
+        let x = self.origin.x + (pixel.x - self.min.x) * self.scale;
+        let y = self.origin.y + (pixel.y - self.min.y) * self.scale;
+        Point::new(x as f32, y as f32)
+    }
+
+    /// Get the tile zoom level closest to the image's resolution.
+    fn tile_zoom(&self, min_zoom: u8, max_zoom: u8) -> u8 {
+        // Tiles are rendered at the image's scale factor, like on the map.
+        let zoom = PROJECTION_ZOOM as f64 + (self.scale / CARD_SCALE).log2();
+        (zoom.round().max(0.) as u8).clamp(min_zoom, max_zoom.min(PROJECTION_ZOOM))
+    }
+
+    /// Draw all cached tiles intersecting with a rectangle.
+    fn draw_tiles<'a>(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        tiles: &mut Tiles,
+        rect: Rect,
+    ) {
+        let zoom = self.tile_zoom(config.tiles.min_zoom, config.tiles.max_zoom);
+        let tile_pixels = TILE_SIZE as f64 * 2f64.powi((PROJECTION_ZOOM - zoom) as i32);
+
+        // Get the range of tiles within the rectangle.
+        let max_tile = (1u32 << zoom) - 1;
+        let tile_at = |image_x: f32, image_y: f32| {
+            let x = self.min.x + (image_x as f64 - self.origin.x) / self.scale;
+            let y = self.min.y + (image_y as f64 - self.origin.y) / self.scale;
+            let tile_x = (x / tile_pixels).floor().clamp(0., max_tile as f64) as u32;
+            let tile_y = (y / tile_pixels).floor().clamp(0., max_tile as f64) as u32;
+            (tile_x, tile_y)
+        };
+        let (min_x, min_y) = tile_at(rect.left, rect.top);
+        let (max_x, max_y) = tile_at(rect.right, rect.bottom);
+
+        render_state.save();
+        render_state.clip_rect(rect, None, Some(true));
+
+        let sampling = SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear);
+        let paint = Paint::default();
+        for x in min_x..=max_x {
+            for y in min_y..=max_y {
+                let image = match tiles.try_get(TileIndex::new(x, y, zoom)) {
+                    Some(tile) => tile.image(),
+                    None => continue,
+                };
+                let image = match image {
+                    Some(image) => image,
+                    None => continue,
+                };
+
+                let origin = Point::new(x as f64 * tile_pixels, y as f64 * tile_pixels);
+                let start = self.project(origin);
+                let end = self.project(origin + Point::new(tile_pixels, tile_pixels));
+                let dst_rect = Rect::new(start.x, start.y, end.x, end.y);
+                render_state
+                    .draw_image_rect_with_sampling_options(image, None, dst_rect, sampling, &paint);
+            }
+        }
+
+        render_state.restore();
+    }
+}
+
+/// Write a trip summary image to the user's download directory.
+pub async fn save(png: Vec<u8>) -> Result<PathBuf, Error> {
+    let dir = dirs::download_dir().or_else(dirs::home_dir).ok_or(Error::MissingExportDir)?;
+    let time = UNIX_EPOCH.elapsed().map_or(0, |elapsed| elapsed.as_secs());
+    let path = dir.join(format!("charon-trip-{time}.png"));
+
+    tokio::fs::write(&path, png).await?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trip_path() {
+        let mut trip = Trip::default();
+        trip.update(GeoPoint::new(0., 0.));
+        trip.update(GeoPoint::new(0., 0.00001));
+        trip.update(GeoPoint::new(0., 0.01));
+        assert_eq!(trip.points.len(), 2);
+        assert_eq!(trip.distance, 1_112);
+
+        // Path is scaled to the rectangle's width and centered vertically.
+        let points = trip.fit(Rect::new(0., 0., 100., 50.)).points();
+        assert!(points[0].x.abs() < 0.01);
+        assert!((points[1].x - 100.).abs() < 0.01);
+        assert!(points.iter().all(|point| (point.y - 25.).abs() < 0.1));
+    }
+
+    #[test]
+    fn trip_tile_zoom() {
+        let mut trip = Trip::default();
+        trip.update(GeoPoint::new(0., 0.));
+        trip.update(GeoPoint::new(0., 0.01));
+
+        // A 1.1 km path fits 1000 pixels at zoom 16 and scale 2.
+        let fit = trip.fit(Rect::new(0., 0., 1000., 1000.));
+        assert_eq!(fit.tile_zoom(0, 19), 16);
+
+        // Zoom is clamped to the tile server's limits.
+        assert_eq!(fit.tile_zoom(0, 14), 14);
+    }
+}
@@ -0,0 +1,687 @@
+//! Diagnostics UI view.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use std::{fs, mem};
+
+use calloop::LoopHandle;
+use calloop::channel::{self, Event, Sender};
+use skia_safe::textlayout::Paragraph;
+use tracing::{error, info};
+
+use crate::config::{Config, Input};
+use crate::db::{Db, TileCacheStats, TileSourceStats};
+use crate::geometry::{Point, Size};
+use crate::tiles::{self, OFFLINE_TILESERVER};
+use crate::ui::skia::{RenderState, TextOptions};
+use crate::ui::view::download::format_size;
+use crate::ui::view::{UiView, View};
+use crate::ui::{
+    Button, RowProvider, ScrollList, Svg, move_button_touch, press_button, release_button, renderer,
+};
+use crate::{Error, State, log};
+
+/// Button width and height at scale 1.
+const BUTTON_SIZE: u32 = 48;
+
+/// Padding around the screen edge at scale 1.
+const OUTSIDE_PADDING: u32 = 16;
+
+/// Report font size relative to the default.
+const REPORT_FONT_SIZE: f32 = 0.6;
+
+/// Status label font size relative to the default.
+const STATUS_FONT_SIZE: f32 = 0.75;
+
+/// Maximum number of tileservers with a button to clear their cache.
+const MAX_CLEAR_ROWS: usize = 3;
+
+/// Notice shown while the database is locked by another process.
+const READ_ONLY_MESSAGE: &str = "Database in use by another process: tile caches can't be cleared";
+
+/// Diagnostics UI view.
+pub struct DiagnosticsView {
+    report_paragraph: Option<Paragraph>,
+    config_text: String,
+    report: String,
+    status: String,
+
+    cache_stats: TileCacheStats,
+    cache_tx: Sender<CacheEvent>,
+    clear_sources: Vec<String>,
+    clear_buttons: Vec<Button>,
+
+    export_button: Button,
+    back_button: Button,
+
+    touch_state: TouchState,
+    input_config: Input,
+    report_list: ScrollList,
+
+    event_loop: LoopHandle<'static, State>,
+    db: Db,
+
+    size: Size,
+    scale: f64,
+
+    dirty: bool,
+}
+
+impl DiagnosticsView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn new(
+        event_loop: LoopHandle<'static, State>,
+        db: Db,
+        config: &Config,
+        size: Size,
+    ) -> Result<Self, Error> {
+        // Handle tile cache statistics and cleanup results.
+        let (cache_tx, cache_rx) = channel::channel();
+        event_loop.insert_source(cache_rx, |event, _, state| {
+            if let Event::Msg(event) = event {
+                let diagnostics_view = state.window.views.diagnostics();
+                match event {
+                    CacheEvent::Stats(stats) => diagnostics_view.set_cache_stats(stats),
+                    CacheEvent::Status(status) => {
+                        diagnostics_view.status = status;
+                        diagnostics_view.dirty = true;
+                    },
+                }
+                state.window.unstall();
+            }
+        })?;
+
+        // Initialize UI elements.
+        let point = Self::back_button_point(size, 1.);
+        let button_size = Self::button_size(1.);
+        let back_button = Button::new(point, button_size, Svg::ArrowLeft);
+
+        let point = Self::export_button_point(size, 1.);
+        let export_button = Button::new(point, button_size, Svg::Download);
+
+        let report_list = ScrollList::new(event_loop.clone(), 0., OUTSIDE_PADDING as f64);
+
+        Ok(Self {
+            export_button,
+            back_button,
+            report_list,
+            event_loop,
+            cache_tx,
+            size,
+            db,
+            config_text: format!("{:#?}", config.redacted()),
+            input_config: config.input,
+            dirty: true,
+            scale: 1.,
+            report_paragraph: Default::default(),
+            touch_state: Default::default(),
+            report: Default::default(),
+            status: Default::default(),
+            cache_stats: Default::default(),
+            clear_sources: Default::default(),
+            clear_buttons: Default::default(),
+        })
+    }
+
+    /// Load the tile cache statistics in the background.
+    fn load_cache_stats(&self) {
+        let cache_tx = self.cache_tx.clone();
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            match db.tile_cache_stats().await {
+                Ok(stats) => {
+                    let _ = cache_tx.send(CacheEvent::Stats(stats));
+                },
+                Err(err) => error!("Failed to load tile cache statistics: {err}"),
+            }
+        });
+    }
+
+    /// Update the tile cache statistics.
+    fn set_cache_stats(&mut self, stats: TileCacheStats) {
+        // Offer cleanup for the largest online tileservers.
+        let online_sources = stats.sources.iter().filter(|s| s.tileserver != OFFLINE_TILESERVER);
+        self.clear_sources =
+            online_sources.take(MAX_CLEAR_ROWS).map(|s| s.tileserver.clone()).collect();
+
+        let (size, scale) = (self.size, self.scale);
+        let button_size = Self::button_size(scale);
+        self.clear_buttons = (0..self.clear_sources.len())
+            .map(|i| {
+                let point = Self::clear_button_point(size, scale, i);
+                let mut button = Button::new(point, button_size, Svg::Bin);
+                button.set_disabled(self.db.read_only());
+                button
+            })
+            .collect();
+
+        self.cache_stats = stats;
+        self.update_report();
+    }
+
+    /// Delete all cached tiles of a tileserver.
+    fn clear_cache(&mut self, index: usize) {
+        let tileserver = match self.clear_sources.get(index) {
+            Some(tileserver) => tileserver.clone(),
+            None => return,
+        };
+
+        if self.db.read_only() {
+            return;
+        }
+
+        self.status = format!("Clearing {}", source_label(&tileserver));
+        self.dirty = true;
+
+        let cache_tx = self.cache_tx.clone();
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            let status = match db.delete_tiles(&tileserver).await {
+                Ok(()) => {
+                    info!("Cleared tile cache of {tileserver:?}");
+                    format!("Cleared {}", source_label(&tileserver))
+                },
+                Err(err) => {
+                    error!("Could not clear tile cache: {err}");
+                    format!("Cache cleanup failed: {err}")
+                },
+            };
+            let _ = cache_tx.send(CacheEvent::Status(status));
+
+            match db.tile_cache_stats().await {
+                Ok(stats) => {
+                    let _ = cache_tx.send(CacheEvent::Stats(stats));
+                },
+                Err(err) => error!("Failed to load tile cache statistics: {err}"),
+            }
+        });
+    }
+
+    /// Collect all diagnostics information.
+    fn update_report(&mut self) {
+        self.report.clear();
+
+        let _ = writeln!(self.report, "Charon {}", env!("CARGO_PKG_VERSION"));
+        let _ = writeln!(self.report, "OpenGL: {}", renderer::gl_info().unwrap_or("unknown"));
+        let _ = writeln!(self.report, "Size: {:?} (scale {})", self.size, self.scale);
+
+        self.write_cache_stats();
+
+        let _ = writeln!(self.report, "\n{}\n", self.config_text);
+
+        for line in log::history() {
+            let _ = writeln!(self.report, "{line}");
+        }
+
+        // Strip trailing newline to avoid empty space below the last log line.
+        self.report.truncate(self.report.trim_end().len());
+
+        self.report_paragraph = None;
+        self.dirty = true;
+    }
+
+    /// Add the tile cache statistics to the report.
+    fn write_cache_stats(&mut self) {
+        let report = &mut self.report;
+        let _ = writeln!(report, "\nTile cache:");
+
+        let lookups = tiles::cache_lookups();
+        let _ = write!(report, "  Memory: ");
+        write_lookups(report, lookups.memory_hits, lookups.memory_misses);
+        let _ = write!(report, "  Disk: ");
+        write_lookups(report, lookups.fs_hits, lookups.fs_misses);
+
+        let now = UNIX_EPOCH.elapsed().map_or(0, |elapsed| elapsed.as_secs() as i64);
+        for source in &self.cache_stats.sources {
+            write_source(report, source, now);
+        }
+
+        if !self.cache_stats.zoom_counts.is_empty() {
+            let _ = write!(report, "  Tiles per zoom:");
+            for (zoom, count) in &self.cache_stats.zoom_counts {
+                let _ = write!(report, " {zoom}: {count}");
+            }
+            let _ = writeln!(report);
+        }
+    }
+
+    /// Write the anonymized report to the user's download directory.
+    fn export(&self) -> Result<PathBuf, Error> {
+        let dir = dirs::download_dir().or_else(dirs::home_dir).ok_or(Error::MissingExportDir)?;
+
+        let timestamp = UNIX_EPOCH.elapsed()?.as_secs();
+        let path = dir.join(format!("charon-report-{timestamp}.txt"));
+
+        fs::write(&path, log::anonymize(&self.report))?;
+
+        Ok(path)
+    }
+
+    /// Physical size of the UI SVG buttons.
+    fn button_size(scale: f64) -> Size {
+        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+    }
+
+    /// Physical location of the back button.
+    fn back_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+        let y = (physical_size.height - button_size.height) as i32 - padding;
+
+        Point::new(x, y)
+    }
+
+    /// Physical location of the report export button.
+    fn export_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::back_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Physical location of a tileserver's cache cleanup button.
+    ///
+    /// Cleanup buttons are stacked above the navigation buttons, starting with
+    /// the largest tileserver at the bottom.
+    fn clear_button_point(size: Size, scale: f64, index: usize) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_height = Self::button_size(scale).height as i32;
+        let mut point = Self::back_button_point(size, scale);
+
+        point.y -= (index as i32 + 1) * (button_height + padding);
+
+        point
+    }
+
+    /// Physical location of a tileserver's cache label.
+    fn clear_label_point(&self, index: usize) -> Point {
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
+        let button_point = Self::clear_button_point(self.size, self.scale, index);
+
+        Point::new(outside_padding, button_point.y)
+    }
+
+    /// Physical size of a tileserver's cache label.
+    fn clear_label_size(&self) -> Size {
+        let padding = (OUTSIDE_PADDING as f64 * self.scale).round() as u32;
+        let button_point = Self::back_button_point(self.size, self.scale);
+        let button_size = Self::button_size(self.scale);
+
+        Size::new((button_point.x as u32).saturating_sub(2 * padding), button_size.height)
+    }
+
+    /// Physical Y coordinate of the top of the button area.
+    fn buttons_top(&self) -> i32 {
+        match self.clear_buttons.len() {
+            0 => Self::back_button_point(self.size, self.scale).y,
+            len => Self::clear_button_point(self.size, self.scale, len - 1).y,
+        }
+    }
+
+    /// Physical location of the export status text.
+    fn status_label_point(&self) -> Point {
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
+        let button_point = Self::export_button_point(self.size, self.scale);
+
+        Point::new(outside_padding, button_point.y)
+    }
+
+    /// Physical size of the export status text.
+    fn status_label_size(&self) -> Size {
+        let padding = (OUTSIDE_PADDING as f64 * self.scale).round() as u32;
+        let button_point = Self::export_button_point(self.size, self.scale);
+        let button_size = Self::button_size(self.scale);
+
+        Size::new((button_point.x as u32).saturating_sub(2 * padding), button_size.height)
+    }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Export => Some(&mut self.export_button),
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Clear(index) => self.clear_buttons.get_mut(index),
+            TouchAction::Tap | TouchAction::Drag => None,
+        }
+    }
+}
+
+impl RowProvider for DiagnosticsView {
+    fn row_count(&self) -> usize {
+        // Show the whole report as a single row, since its lines can wrap.
+        self.report_paragraph.is_some() as usize
+    }
+
+    fn draw_row(
+        &self,
+        _config: &Config,
+        render_state: &mut RenderState<'_>,
+        _index: usize,
+        point: Point,
+        _size: Size,
+    ) {
+        if let Some(paragraph) = &self.report_paragraph {
+            paragraph.paint(render_state, point);
+        }
+    }
+}
+
+impl UiView for DiagnosticsView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
+        let size = self.size * self.scale;
+
+        render_state.clear(config.colors.background);
+
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as f32;
+        let report_bottom = self.buttons_top() as f32 - outside_padding;
+        let report_width = size.width as f32 - 2. * outside_padding;
+
+        // Layout the report once, since it can contain hundreds of lines.
+        if self.report_paragraph.is_none() {
+            let text_options = Some(TextOptions::new().ellipsize(false));
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, REPORT_FONT_SIZE, text_options);
+            builder.add_text(&self.report);
+
+            let mut paragraph = builder.build();
+            paragraph.layout(report_width);
+            self.report_paragraph = Some(paragraph);
+        }
+
+        // Anchor the report to the bottom, to show the latest logs first.
+        let report_height = self.report_paragraph.as_ref().map_or(0., |p| p.height()).ceil();
+        let report_point = Point::new(outside_padding, report_bottom - report_height);
+        self.report_list.set_point(report_point.into());
+        self.report_list.set_row_size(Size::new(report_width, report_height).into());
+
+        // Apply scroll velocity and ensure offset is correct in case size changed.
+        self.report_list.update(&self.input_config, self.row_count());
+
+        // Render report, clipped above the bottom buttons.
+        self.report_list.draw(config, &mut render_state, self);
+
+        // Draw export status vertically centered next to the buttons.
+        let status = if self.status.is_empty() && self.db.read_only() {
+            READ_ONLY_MESSAGE
+        } else {
+            self.status.as_str()
+        };
+        if !status.is_empty() {
+            let label_point: Point<f32> = self.status_label_point().into();
+            let label_size: Size<f32> = self.status_label_size().into();
+
+            let text_options = Some(TextOptions::new().ellipsize(false));
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, STATUS_FONT_SIZE, text_options);
+            builder.add_text(status);
+
+            let mut paragraph = builder.build();
+            paragraph.layout(label_size.width);
+
+            let y = label_point.y + (label_size.height - paragraph.height()) / 2.;
+            paragraph.paint(&render_state, Point::new(label_point.x, y));
+        }
+
+        // Draw tileserver labels next to their cleanup buttons.
+        let label_size: Size<f32> = self.clear_label_size().into();
+        for (i, tileserver) in self.clear_sources.iter().enumerate() {
+            let label_point: Point<f32> = self.clear_label_point(i).into();
+
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, STATUS_FONT_SIZE, None);
+            builder.add_text(source_label(tileserver));
+
+            let mut paragraph = builder.build();
+            paragraph.layout(label_size.width);
+
+            let y = label_point.y + (label_size.height - paragraph.height()) / 2.;
+            paragraph.paint(&render_state, Point::new(label_point.x, y));
+        }
+
+        // Render navigation buttons.
+        for button in &mut self.clear_buttons {
+            button.draw(&mut render_state, config.colors.alt_background);
+        }
+        self.export_button.draw(&mut render_state, config.colors.alt_background);
+        self.back_button.draw(&mut render_state, config.colors.alt_background);
+
+        // Clear dirtiness flag.
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty || self.report_list.is_animating()
+    }
+
+    fn enter(&mut self) {
+        self.report_list.reset();
+        self.status.clear();
+        self.update_report();
+        self.load_cache_stats();
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.report_paragraph = None;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.export_button.set_point(Self::export_button_point(size, self.scale));
+        self.back_button.set_point(Self::back_button_point(size, self.scale));
+        for (i, button) in self.clear_buttons.iter_mut().enumerate() {
+            button.set_point(Self::clear_button_point(size, self.scale, i));
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.report_paragraph = None;
+        self.report_list.set_scale_factor(scale);
+        self.dirty = true;
+
+        // Update UI elements.
+        self.export_button.set_point(Self::export_button_point(self.size, scale));
+        self.export_button.set_size(Self::button_size(scale));
+        self.back_button.set_point(Self::back_button_point(self.size, scale));
+        self.back_button.set_size(Self::button_size(scale));
+        for (i, button) in self.clear_buttons.iter_mut().enumerate() {
+            button.set_point(Self::clear_button_point(self.size, scale, i));
+            button.set_size(Self::button_size(scale));
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_down(&mut self, slot: i32, _time: u32, point: Point<f64>) {
+        // Cancel velocity if a new touch sequence starts.
+        self.report_list.stop();
+
+        // Only allow a single active touch slot.
+        if !self.touch_state.slots.is_empty() {
+            return;
+        }
+
+        // Determine goal of this touch sequence.
+        let point = point * self.scale;
+        self.touch_state.action = if self.export_button.contains(point) {
+            TouchAction::Export
+        } else if self.back_button.contains(point) {
+            TouchAction::Back
+        } else if let Some(index) = self.clear_buttons.iter().position(|b| b.contains(point)) {
+            TouchAction::Clear(index)
+        } else {
+            TouchAction::Tap
+        };
+
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
+        // Convert position to physical space.
+        let slot = self.touch_state.slots.entry(slot).or_default();
+        slot.point = point;
+        slot.start = point;
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_motion(&mut self, slot: i32, point: Point<f64>) {
+        // Ignore unknown touch slots.
+        let slot = match self.touch_state.slots.get_mut(&slot) {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        // Update touch point.
+        let point = point * self.scale;
+        let old_point = mem::replace(&mut slot.point, point);
+
+        // Handle action transitions.
+        if let TouchAction::Tap | TouchAction::Drag = self.touch_state.action {
+            // Ignore dragging until tap distance limit is exceeded.
+            let max_tap_distance = self.input_config.max_tap_distance;
+            let delta = slot.point - slot.start;
+            if delta.x.powi(2) + delta.y.powi(2) <= max_tap_distance {
+                return;
+            }
+            self.touch_state.action = TouchAction::Drag;
+
+            // Apply scroll motion.
+            let delta = slot.point.y - old_point.y;
+            self.dirty |= self.report_list.drag(delta, self.row_count());
+        } else {
+            self.dirty |= move_button_touch(self.action_button(), point);
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_up(&mut self, slot: i32) {
+        // Reset touch slot, ignoring unknown slots.
+        let removed = match self.touch_state.slots.remove(&slot) {
+            Some(removed) => removed,
+            None => return,
+        };
+
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
+        // Dispatch tap actions on release.
+        match self.touch_state.action {
+            // Handle report export button.
+            TouchAction::Export if self.export_button.contains(removed.point) => {
+                self.status = match self.export() {
+                    Ok(path) => {
+                        info!("Saved diagnostics report to {path:?}");
+                        format!("Report saved to {}", path.display())
+                    },
+                    Err(err) => {
+                        error!("Could not save diagnostics report: {err}");
+                        format!("Report export failed: {err}")
+                    },
+                };
+                self.dirty = true;
+            },
+            // Handle "back" button navigation.
+            TouchAction::Back if self.back_button.contains(removed.point) => {
+                self.event_loop.insert_idle(|state| state.window.set_view(View::Search));
+            },
+            // Handle tile cache cleanup buttons.
+            TouchAction::Clear(index)
+                if self.clear_buttons.get(index).is_some_and(|b| b.contains(removed.point)) =>
+            {
+                self.clear_cache(index);
+            },
+            _ => (),
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn update_config(&mut self, config: &Config) {
+        self.config_text = format!("{:#?}", config.redacted());
+        self.input_config = config.input;
+
+        // Force report relayout, to apply color changes.
+        self.report_paragraph = None;
+        self.dirty = true;
+    }
+}
+
+/// Touch event tracking.
+#[derive(Default)]
+struct TouchState {
+    slots: HashMap<i32, TouchSlot>,
+    action: TouchAction,
+}
+
+/// Touch slot state.
+#[derive(Copy, Clone, Default, Debug)]
+struct TouchSlot {
+    start: Point<f64>,
+    point: Point<f64>,
+}
+
+/// Intention of a touch sequence.
+#[derive(PartialEq, Eq, Default)]
+enum TouchAction {
+    #[default]
+    Tap,
+    Drag,
+    Export,
+    Back,
+    Clear(usize),
+}
+
+/// Background tile cache operation result.
+enum CacheEvent {
+    Stats(TileCacheStats),
+    Status(String),
+}
+
+/// Get a tileserver's display name.
+///
+/// The URL's query is omitted, since it commonly contains the API key.
+fn source_label(tileserver: &str) -> &str {
+    match tileserver {
+        OFFLINE_TILESERVER => "Offline regions",
+        _ => tileserver.split('?').next().unwrap_or(tileserver),
+    }
+}
+
+/// Write cache hits and misses with their hit ratio.
+fn write_lookups(w: &mut impl Write, hits: u64, misses: u64) {
+    let total = hits + misses;
+    let ratio = if total == 0 { 0. } else { hits as f64 / total as f64 * 100. };
+    let _ = writeln!(w, "{hits} hits, {misses} misses ({ratio:.1}%)");
+}
+
+/// Write a tileserver's storage statistics.
+fn write_source(w: &mut impl Write, source: &TileSourceStats, now: i64) {
+    let _ = write!(w, "  {}: {} tiles, ", source_label(&source.tileserver), source.tiles);
+    format_size(w, source.size);
+
+    let _ = write!(w, ", accessed ");
+    format_age(w, now - source.oldest_atime);
+    let _ = write!(w, " to ");
+    format_age(w, now - source.newest_atime);
+    let _ = writeln!(w, " ago");
+}
+
+/// Format a duration in seconds with its largest unit.
+fn format_age(w: &mut impl Write, secs: i64) {
+    let secs = secs.max(0);
+    let _ = match secs {
+        ..60 => write!(w, "{secs}s"),
+        60..3_600 => write!(w, "{}m", secs / 60),
+        3_600..86_400 => write!(w, "{}h", secs / 3_600),
+        _ => write!(w, "{}d", secs / 86_400),
+    };
+}
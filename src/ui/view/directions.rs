@@ -0,0 +1,492 @@
+//! Textual navigation UI view.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use calloop::LoopHandle;
+use skia_safe::{Color4f, Paint, Rect};
+use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
+
+use crate::config::{Config, Input};
+use crate::geometry::{Point, Size};
+use crate::router::Route;
+use crate::ui::accessibility::AccessibleRole;
+use crate::ui::skia::{RenderState, TextOptions};
+use crate::ui::surface::SurfaceKind;
+use crate::ui::view::{self, UiView, View};
+use crate::ui::{Button, Svg, move_button_touch, press_button, release_button};
+use crate::{Error, State};
+
+/// Button width and height at scale 1.
+const BUTTON_SIZE: u32 = 48;
+
+/// Padding around the screen edge at scale 1.
+const OUTSIDE_PADDING: u32 = 16;
+
+/// Padding around the content of a maneuver at scale 1.
+const MANEUVER_INSIDE_PADDING: f64 = 16.;
+
+/// Vertical padding between distance and instruction at scale 1.
+const MANEUVER_TEXT_PADDING: f64 = 8.;
+
+/// Vertical space between maneuvers at scale 1.
+const MANEUVER_Y_PADDING: f64 = 2.;
+
+/// Instruction font size relative to the default.
+const INSTRUCTION_FONT_SIZE: f32 = 1.5;
+
+/// Distance font size relative to the default.
+const DISTANCE_FONT_SIZE: f32 = 2.;
+
+/// Textual navigation UI view.
+///
+/// This lists all upcoming maneuvers of the active route in a large font,
+/// advancing automatically with the GPS progress.
+pub struct DirectionsView {
+    route: Arc<Route>,
+    progress: usize,
+
+    detach_button: Button,
+    back_button: Button,
+    alt_bg_paint: Paint,
+    hl_paint: Paint,
+
+    touch_state: TouchState,
+    input_config: Input,
+
+    event_loop: LoopHandle<'static, State>,
+
+    size: Size,
+    scale: f64,
+
+    detached: bool,
+    dirty: bool,
+}
+
+impl DirectionsView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn new(
+        event_loop: LoopHandle<'static, State>,
+        config: &Config,
+        size: Size,
+    ) -> Result<Self, Error> {
+        // Initialize UI elements.
+        let point = Self::back_button_point(size, 1.);
+        let button_size = Self::button_size(1.);
+        let back_button = Button::new(point, button_size, Svg::ArrowLeft);
+
+        let point = Self::detach_button_point(size, 1.);
+        let detach_button = Button::new(point, button_size, Svg::Detach);
+
+        let mut alt_bg_paint = Paint::default();
+        alt_bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+
+        let mut hl_paint = Paint::default();
+        hl_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+
+        Ok(Self {
+            alt_bg_paint,
+            detach_button,
+            back_button,
+            event_loop,
+            hl_paint,
+            size,
+            input_config: config.input,
+            dirty: true,
+            scale: 1.,
+            touch_state: Default::default(),
+            progress: Default::default(),
+            detached: Default::default(),
+            route: Default::default(),
+        })
+    }
+
+    /// Update the active route.
+    pub fn set_route(&mut self, route: Arc<Route>) {
+        self.route = route;
+        self.progress = 0;
+        self.dirty = true;
+    }
+
+    /// Set the number of nodes already traveled in the route.
+    pub fn set_progress(&mut self, progress: usize) {
+        self.dirty |= self.progress != progress;
+        self.progress = progress;
+    }
+
+    /// Get the active route.
+    pub fn route(&self) -> Arc<Route> {
+        self.route.clone()
+    }
+
+    /// Get the number of nodes already traveled in the route.
+    pub fn progress(&self) -> usize {
+        self.progress
+    }
+
+    /// Mark this view as the content of a separate window.
+    ///
+    /// Detached views close their window when going back, instead of
+    /// switching to the map view.
+    pub fn set_detached(&mut self, detached: bool) {
+        self.dirty |= self.detached != detached;
+        self.detached = detached;
+    }
+
+    /// Close the separate window, or return to the map.
+    fn back(&mut self) {
+        if self.detached {
+            self.event_loop.insert_idle(|state| {
+                state.window.close_surface(SurfaceKind::Directions);
+            });
+        } else {
+            self.event_loop.insert_idle(|state| state.window.set_view(View::Map));
+        }
+    }
+
+    /// Physical size of the UI SVG buttons.
+    fn button_size(scale: f64) -> Size {
+        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+    }
+
+    /// Physical location of the back button.
+    fn back_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+        let y = (physical_size.height - button_size.height) as i32 - padding;
+
+        Point::new(x, y)
+    }
+
+    /// Physical location of the detach button.
+    fn detach_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let mut point = Self::back_button_point(size, scale);
+        point.x -= button_size.width as i32 + padding;
+        point
+    }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Detach => Some(&mut self.detach_button),
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Tap => None,
+        }
+    }
+}
+
+impl UiView for DirectionsView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
+        let size = self.size * self.scale;
+
+        // Ensure paints are up to date.
+        self.alt_bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        self.hl_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+
+        render_state.clear(config.colors.background);
+
+        let back_button_point = Self::back_button_point(self.size, self.scale);
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as f32;
+        let inside_padding = (MANEUVER_INSIDE_PADDING * self.scale).round() as f32;
+        let text_padding = (MANEUVER_TEXT_PADDING * self.scale).round() as f32;
+        let maneuver_padding = (MANEUVER_Y_PADDING * self.scale).round() as f32;
+        let maneuvers_bottom = back_button_point.y as f32 - outside_padding;
+        let maneuver_width = size.width as f32 - 2. * outside_padding;
+        let text_width = maneuver_width - 2. * inside_padding;
+
+        // Set clipping mask to cut off maneuvers overlapping the bottom button.
+        let clip_rect = Rect::new(0., 0., size.width as f32, maneuvers_bottom);
+        render_state.save();
+        render_state.clip_rect(clip_rect, None, Some(false));
+
+        // Render upcoming maneuvers from the top, until the screen is filled.
+        let mut y = outside_padding;
+        let mut distance = String::with_capacity("X.XX km".len());
+        for (i, maneuver) in upcoming_maneuvers(&self.route, self.progress).iter().enumerate() {
+            if y >= maneuvers_bottom {
+                break;
+            }
+
+            // Layout maneuver distance.
+
+            distance.clear();
+            view::format_distance(&mut distance, maneuver.distance, config.navigation.units);
+
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, DISTANCE_FONT_SIZE, None);
+            builder.add_text(&distance);
+
+            let mut distance_paragraph = builder.build();
+            distance_paragraph.layout(text_width);
+            let distance_height = distance_paragraph.height();
+
+            // Layout instruction text.
+
+            let text_options = Some(TextOptions::new().ellipsize(false));
+            let mut builder = render_state.paragraph(
+                config.colors.foreground,
+                INSTRUCTION_FONT_SIZE,
+                text_options,
+            );
+            builder.add_text(&*maneuver.instruction);
+
+            let mut instruction_paragraph = builder.build();
+            instruction_paragraph.layout(text_width);
+            let instruction_height = instruction_paragraph.height();
+
+            // Highlight the next maneuver.
+            let height = distance_height + instruction_height + text_padding + 2. * inside_padding;
+            let rect = Rect::new(outside_padding, y, outside_padding + maneuver_width, y + height);
+            let paint = if i == 0 { &self.hl_paint } else { &self.alt_bg_paint };
+            render_state.draw_rect(rect, paint);
+
+            let mut text_point = Point::new(outside_padding + inside_padding, y + inside_padding);
+            distance_paragraph.paint(&render_state, text_point);
+            text_point.y += distance_height + text_padding;
+            instruction_paragraph.paint(&render_state, text_point);
+
+            // Expose maneuvers to screen readers in the order they are encountered.
+            let label = maneuver.instruction.to_string();
+            render_state.add_accessible(
+                AccessibleRole::ListItem,
+                label,
+                Some(distance.clone()),
+                rect,
+            );
+
+            y += height + maneuver_padding;
+        }
+
+        // Reset maneuver clipping mask.
+        render_state.restore();
+
+        // Render navigation buttons.
+        if !self.detached {
+            self.detach_button.draw(&mut render_state, config.colors.alt_background);
+        }
+        self.back_button.draw(&mut render_state, config.colors.alt_background);
+
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.detach_button.set_point(Self::detach_button_point(size, self.scale));
+        self.back_button.set_point(Self::back_button_point(size, self.scale));
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.detach_button.set_point(Self::detach_button_point(self.size, scale));
+        self.detach_button.set_size(Self::button_size(scale));
+        self.back_button.set_point(Self::back_button_point(self.size, scale));
+        self.back_button.set_size(Self::button_size(scale));
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_down(&mut self, slot: i32, _time: u32, point: Point<f64>) {
+        // Only allow a single active touch slot.
+        if !self.touch_state.slots.is_empty() {
+            return;
+        }
+
+        // Determine goal of this touch sequence.
+        let point = point * self.scale;
+        self.touch_state.action = if self.back_button.contains(point) {
+            TouchAction::Back
+        } else if !self.detached && self.detach_button.contains(point) {
+            TouchAction::Detach
+        } else {
+            TouchAction::Tap
+        };
+
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
+        // Convert position to physical space.
+        self.touch_state.slots.insert(slot, point);
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_motion(&mut self, slot: i32, point: Point<f64>) {
+        // Ignore unknown touch slots.
+        let slot_point = match self.touch_state.slots.get_mut(&slot) {
+            Some(slot_point) => slot_point,
+            None => return,
+        };
+
+        // Update touch point.
+        let point = point * self.scale;
+        *slot_point = point;
+
+        self.dirty |= move_button_touch(self.action_button(), point);
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_up(&mut self, slot: i32) {
+        // Reset touch slot, ignoring unknown slots.
+        let point = match self.touch_state.slots.remove(&slot) {
+            Some(point) => point,
+            None => return,
+        };
+
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
+        match self.touch_state.action {
+            // Close the separate window, or return to the map.
+            TouchAction::Back if self.back_button.contains(point) => self.back(),
+            // Move the directions to a separate window.
+            TouchAction::Detach if self.detach_button.contains(point) => {
+                let route = self.route.clone();
+                let progress = self.progress;
+                self.event_loop.insert_idle(move |state| {
+                    let event_loop = state.event_loop.clone();
+                    let protocol_states = &state.protocol_states;
+                    state.window.open_directions_window(
+                        &event_loop,
+                        protocol_states,
+                        route,
+                        progress,
+                    );
+                    state.window.set_view(View::Map);
+                });
+            },
+            _ => (),
+        }
+    }
+
+    fn press_key(&mut self, _raw: u32, keysym: Keysym, _modifiers: Modifiers) {
+        // Close the separate window, since it has no global shortcuts.
+        if self.detached && keysym == Keysym::Escape {
+            self.back();
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn update_config(&mut self, config: &Config) {
+        self.input_config = config.input;
+        self.dirty = true;
+    }
+}
+
+/// Upcoming route maneuver.
+#[derive(PartialEq, Debug)]
+pub struct Maneuver {
+    pub instruction: Arc<String>,
+    /// Distance to the maneuver in meters.
+    pub distance: u32,
+}
+
+/// Get all maneuvers ahead of the current route progress.
+///
+/// Like the map's instruction overlay, this approximates the traveled distance
+/// within a segment by assuming every node is evenly spaced.
+pub fn upcoming_maneuvers(route: &Route, progress: usize) -> Vec<Maneuver> {
+    let mut maneuvers = Vec::new();
+    let mut distance = None;
+    let mut segment_start = 0;
+
+    for (i, segment) in route.segments.iter().enumerate() {
+        let node_count = segment.points.len();
+
+        match distance {
+            // Add maneuvers after the current segment.
+            Some(segment_distance) => {
+                let instruction = segment.instruction.clone();
+                maneuvers.push(Maneuver { instruction, distance: segment_distance });
+                distance = Some(segment_distance + segment.length);
+            },
+            // Calculate the remaining length of the current segment.
+            None if segment_start + node_count > progress => {
+                let completed_nodes = progress - segment_start;
+                let remaining = 1. - completed_nodes as f64 / node_count as f64;
+                let remaining = (segment.length as f64 * remaining).round() as u32;
+                distance = Some(remaining);
+
+                // Show the current instruction if there is no next segment.
+                if i + 1 == route.segments.len() {
+                    let instruction = segment.instruction.clone();
+                    maneuvers.push(Maneuver { instruction, distance: remaining });
+                }
+            },
+            None => (),
+        }
+
+        segment_start += node_count;
+    }
+
+    maneuvers
+}
+
+/// Touch event tracking.
+#[derive(Default)]
+struct TouchState {
+    slots: HashMap<i32, Point<f64>>,
+    action: TouchAction,
+}
+
+/// Intention of a touch sequence.
+#[derive(PartialEq, Eq, Default)]
+enum TouchAction {
+    #[default]
+    Tap,
+    Detach,
+    Back,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::GeoPoint;
+    use crate::router::Segment;
+
+    #[test]
+    fn maneuver_distances() {
+        let segment = |instruction: &str, length| Segment {
+            points: vec![GeoPoint::new(0., 0.); 4],
+            instruction: Arc::new(instruction.into()),
+            time: 60,
+            length,
+        };
+        let route = Route {
+            segments: vec![segment("Start", 400), segment("Turn", 100), segment("Arrive", 0)],
+            ..Default::default()
+        };
+        let maneuver = |instruction: &str, distance| Maneuver {
+            instruction: Arc::new(instruction.into()),
+            distance,
+        };
+
+        assert_eq!(upcoming_maneuvers(&route, 0), vec![
+            maneuver("Turn", 400),
+            maneuver("Arrive", 500)
+        ]);
+        assert_eq!(upcoming_maneuvers(&route, 2), vec![
+            maneuver("Turn", 200),
+            maneuver("Arrive", 300)
+        ]);
+        assert_eq!(upcoming_maneuvers(&route, 5), vec![maneuver("Arrive", 75)]);
+        assert_eq!(upcoming_maneuvers(&route, 9), vec![maneuver("Arrive", 0)]);
+        assert_eq!(upcoming_maneuvers(&route, 12), vec![]);
+    }
+}
@@ -3,6 +3,7 @@
 use std::collections::HashMap;
 use std::fmt::Write;
 use std::sync::Arc;
+use std::time::Duration;
 use std::{fs, mem};
 
 use calloop::LoopHandle;
@@ -11,11 +12,13 @@ use tracing::error;
 
 use crate::config::{Config, Input};
 use crate::db::Db;
-use crate::geometry::{Point, Size, rect_contains};
+use crate::geometry::{GeoPoint, Point, Size, rect_contains};
 use crate::region::{DownloadState, Region, Regions};
 use crate::ui::skia::RenderState;
 use crate::ui::view::{UiView, View};
-use crate::ui::{Button, Svg, Velocity};
+use crate::ui::{
+    Button, RowProvider, ScrollList, Svg, move_button_touch, press_button, release_button,
+};
 use crate::{Error, State};
 
 /// Back button width and height at scale 1.
@@ -39,6 +42,9 @@ const REGION_BUTTON_SIZE: u32 = 32;
 /// Progress bar height at scale 1.
 const PROGRESS_HEIGHT: f32 = 8.;
 
+/// Vertical space between download and installation progress bars at scale 1.
+const PROGRESS_PADDING: f32 = 4.;
+
 /// Secondary font size for region size/count relative to primary font.
 const ALT_FONT_SIZE: f32 = 0.5;
 
@@ -46,16 +52,21 @@ const ALT_FONT_SIZE: f32 = 0.5;
 pub struct DownloadView {
     regions: Arc<Regions>,
     current_region: [usize; 5],
+    gps: Option<GeoPoint>,
     tiles_size: u64,
 
     back_button: Button,
+    gps_button: Button,
+    cleanup_button: Button,
+    coverage_button: Button,
     alt_bg_paint: Paint,
     bg_paint: Paint,
     hl_paint: Paint,
 
     touch_state: TouchState,
     input_config: Input,
-    scroll_offset: f64,
+    region_list: ScrollList,
+    cleanup_pending: bool,
 
     event_loop: LoopHandle<'static, State>,
 
@@ -74,9 +85,16 @@ impl DownloadView {
         size: Size,
     ) -> Result<Self, Error> {
         // Initialize UI elements.
+        let button_size = Self::back_button_size(1.);
         let point = Self::back_button_point(size, 1.);
-        let size = Self::back_button_size(1.);
-        let back_button = Button::new(point, size, Svg::ArrowLeft);
+        let back_button = Button::new(point, button_size, Svg::ArrowLeft);
+
+        let point = Self::gps_button_point(size, 1.);
+        let gps_button = Button::new(point, button_size, Svg::Gps);
+
+        let point = Self::region_action_button_point(size, 1., false);
+        let cleanup_button = Button::new(point, button_size, Svg::Bin);
+        let coverage_button = Button::new(point, button_size, Svg::Place);
 
         let mut alt_bg_paint = Paint::default();
         alt_bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
@@ -85,22 +103,33 @@ impl DownloadView {
         let mut hl_paint = Paint::default();
         hl_paint.set_color4f(Color4f::from(config.colors.highlight), None);
 
-        Ok(Self {
+        let region_list =
+            ScrollList::new(event_loop.clone(), REGION_Y_PADDING, OUTSIDE_PADDING as f64);
+
+        let mut download_view = Self {
             alt_bg_paint,
             back_button,
+            gps_button,
+            cleanup_button,
+            coverage_button,
             event_loop,
             bg_paint,
             hl_paint,
             regions,
+            region_list,
             size,
             current_region: [usize::MAX; 5],
             input_config: config.input,
             dirty: true,
             scale: 1.,
-            scroll_offset: Default::default(),
             touch_state: Default::default(),
+            cleanup_pending: Default::default(),
             tiles_size: Default::default(),
-        })
+            gps: Default::default(),
+        };
+        download_view.update_region_list();
+
+        Ok(download_view)
     }
 
     /// Mark the view as dirty.
@@ -108,6 +137,98 @@ impl DownloadView {
         self.dirty = true;
     }
 
+    /// Return to the root region.
+    ///
+    /// This must be called whenever the region index is replaced, since the
+    /// current region's index might no longer exist.
+    pub fn reset_region(&mut self) {
+        self.current_region = [usize::MAX; 5];
+        self.region_list.reset();
+        self.dirty = true;
+    }
+
+    /// Update the current GPS location.
+    pub fn set_gps(&mut self, point: Option<GeoPoint>) {
+        // Only redraw when the GPS button's visibility changes.
+        if self.gps.is_some() != point.is_some() {
+            let button_point =
+                Self::region_action_button_point(self.size, self.scale, point.is_some());
+            self.cleanup_button.set_point(button_point);
+            self.coverage_button.set_point(button_point);
+            self.dirty = true;
+        }
+        self.gps = point;
+    }
+
+    /// Download a region's data in the background.
+    fn download_region(&mut self, index: [usize; 5]) {
+        // Immediately mark region as downloading.
+        let region = Self::index_region(self.regions.world(), &index);
+        region.set_download_state(DownloadState::Downloading);
+        self.dirty = true;
+
+        let regions = self.regions.clone();
+        tokio::spawn(async move {
+            // Re-index the region, since we can't move the reference.
+            let region = Self::index_region(regions.world(), &index);
+
+            match regions.download(region).await {
+                Ok(_) => {
+                    region.set_download_state(DownloadState::Downloaded);
+
+                    // Load the new region's geocoder and routing data.
+                    regions.reload_datasets();
+                },
+                Err(err) => {
+                    error!("Region data download failed: {err}");
+
+                    // Delete all data to avoid tempfiles stealing storage space.
+                    regions.delete(region).await;
+
+                    region.set_download_state(DownloadState::Available);
+                },
+            }
+
+            // Wake UI to display the download state update.
+            regions.redraw_download_view();
+        });
+    }
+
+    /// Show the region at the current GPS location and download its data.
+    fn download_gps_region(&mut self) {
+        let gps = match self.gps {
+            Some(gps) => gps,
+            None => return,
+        };
+
+        let index = match self.regions.world().leaf_index_at(gps) {
+            Some(index) => index,
+            None => {
+                error!("No region found at GPS location {gps:?}");
+                return;
+            },
+        };
+
+        // Navigate to the region's parent, so its download progress is visible.
+        let mut parent_index = index;
+        let region_index = match parent_index.iter_mut().rfind(|i| **i != usize::MAX) {
+            Some(region_index) => mem::replace(region_index, usize::MAX),
+            None => return,
+        };
+        self.current_region = parent_index;
+        self.dirty = true;
+
+        // Scroll the region into view.
+        let region_count = self.region().regions.len();
+        self.region_list.scroll_to(region_count - region_index - 1, region_count);
+
+        // Start the download, unless the data is already installed.
+        let region = Self::index_region(self.regions.world(), &index);
+        if region.download_state() == DownloadState::Available {
+            self.download_region(index);
+        }
+    }
+
     /// Draw a region entry.
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn draw_region<'a>(
@@ -142,24 +263,29 @@ impl DownloadView {
 
                 region_button_point.x as f32 - padding * 2.
             },
-            // Draw download progress bar.
+            // Draw download and installation progress bars.
             (None, true) => {
                 let region_button_point: Point<f32> = self.region_button_point().into();
                 let button_point = region_button_point + point.into();
                 let button_size: Size<f32> = self.region_button_size().into();
                 let progress_height = PROGRESS_HEIGHT * self.scale as f32;
-                let progress = region.download_progress() as f32;
+                let progress_padding = PROGRESS_PADDING * self.scale as f32;
 
-                // Draw progress bar background.
                 let right = button_point.x + button_size.width;
-                let top = button_point.y + (button_size.height - progress_height) / 2.;
-                let bottom = top + progress_height;
-                let mut rect = Rect::new(button_point.x, top, right, bottom);
-                render_state.draw_rect(rect, &self.bg_paint);
-
-                // Draw progress bar foreground.
-                rect.right -= button_size.width * (1. - progress);
-                render_state.draw_rect(rect, &self.hl_paint);
+                let total_height = 2. * progress_height + progress_padding;
+                let mut top = button_point.y + (button_size.height - total_height) / 2.;
+                for progress in [region.download_progress(), region.install_progress()] {
+                    // Draw progress bar background.
+                    let bottom = top + progress_height;
+                    let mut rect = Rect::new(button_point.x, top, right, bottom);
+                    render_state.draw_rect(rect, &self.bg_paint);
+
+                    // Draw progress bar foreground.
+                    rect.right -= button_size.width * (1. - progress as f32);
+                    render_state.draw_rect(rect, &self.hl_paint);
+
+                    top = bottom + progress_padding;
+                }
 
                 region_button_point.x - padding * 2.
             },
@@ -181,10 +307,23 @@ impl DownloadView {
 
         let mut size_text = String::with_capacity("X.XX GB · 99 Regions".len());
         format_size(&mut size_text, region.storage_size);
-        match region.regions.len() {
-            0 => (),
-            1 => size_text.push_str(" · 1 Region"),
-            count => _ = write!(&mut size_text, " · {count} Regions"),
+        if downloading {
+            // Show transfer speed and remaining time for active downloads.
+            if let Some(speed) = region.download_speed() {
+                size_text.push_str(" · ");
+                format_size(&mut size_text, speed);
+                size_text.push_str("/s");
+            }
+            if let Some(eta) = region.download_eta() {
+                size_text.push_str(" · ");
+                format_eta(&mut size_text, eta);
+            }
+        } else {
+            match region.regions.len() {
+                0 => (),
+                1 => size_text.push_str(" · 1 Region"),
+                count => _ = write!(&mut size_text, " · {count} Regions"),
+            }
         }
 
         let mut builder = render_state.paragraph(config.colors.alt_foreground, ALT_FONT_SIZE, None);
@@ -222,6 +361,57 @@ impl DownloadView {
         Size::new(BACK_BUTTON_SIZE, BACK_BUTTON_SIZE) * scale
     }
 
+    /// Physical location of the GPS region download button.
+    fn gps_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let back_button_point = Self::back_button_point(size, scale);
+        let button_size = Self::back_button_size(scale);
+
+        let x = back_button_point.x - button_size.width as i32 - padding;
+
+        Point::new(x, back_button_point.y)
+    }
+
+    /// Physical location of the orphaned data cleanup and region coverage
+    /// buttons.
+    ///
+    /// Only one of these buttons is visible at a time.
+    fn region_action_button_point(size: Size, scale: f64, gps_visible: bool) -> Point {
+        let mut point = Self::gps_button_point(size, scale);
+
+        // Move next to the GPS button, if it is visible.
+        if gps_visible {
+            let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+            let button_size = Self::back_button_size(scale);
+            point.x -= button_size.width as i32 + padding;
+        }
+
+        point
+    }
+
+    /// Check whether the orphaned data cleanup button is visible.
+    fn cleanup_visible(&self) -> bool {
+        self.current_region[0] == usize::MAX && self.regions.orphans_size() > 0
+    }
+
+    /// Check whether the region coverage button is visible.
+    fn coverage_visible(&self) -> bool {
+        self.current_region[0] != usize::MAX && self.region().bounds().is_some()
+    }
+
+    /// Show a region's outline on the map.
+    fn show_coverage(&self, region: &Region) {
+        let bounds = match region.bounds() {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        self.event_loop.insert_idle(move |state| {
+            state.window.set_view(View::Map);
+            state.window.views.map().show_coverage(bounds);
+        });
+    }
+
     /// Physical location of the current install size label.
     fn installed_label_point(&self) -> Point {
         let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
@@ -237,7 +427,17 @@ impl DownloadView {
         let button_size = Self::back_button_size(self.scale);
         let size = self.size * self.scale;
 
-        let width = size.width - 2 * padding - button_size.width;
+        let mut width = size.width - 2 * padding - button_size.width;
+
+        // Make space for the GPS button.
+        if self.gps.is_some() {
+            width -= padding + button_size.width;
+        }
+
+        // Make space for the cleanup or coverage button.
+        if self.cleanup_visible() || self.coverage_visible() {
+            width -= padding + button_size.width;
+        }
 
         Size::new(width, button_size.height)
     }
@@ -279,71 +479,24 @@ impl DownloadView {
     }
 
     /// Get region at the specified location.
-    fn region_at(&self, mut point: Point<f64>) -> Option<(usize, &Region, bool)> {
-        let region_point = self.region_point();
-        let region_size = self.region_size();
-        let region_end = region_point.y as f64 + region_size.height as f64;
-
-        // Short-circuit if point is outside the region list.
-        if point.x < region_point.x as f64
-            || point.x >= region_point.x as f64 + region_size.width as f64
-            || point.y >= region_end
-        {
-            return None;
-        }
-
-        // Apply current scroll offset.
-        point.y -= self.scroll_offset;
-
-        // Ignore taps within vertical padding.
-        let region_height = region_size.height as f64 + REGION_Y_PADDING * self.scale;
-        let bottom_relative = region_end - point.y - 1.;
-        if bottom_relative % region_height >= region_size.height as f64 {
-            return None;
-        }
-
-        // Find index at the specified offset.
-        let rindex = (bottom_relative / region_height).floor() as usize;
-        let index = self.region().regions.len().checked_sub(rindex + 1)?;
+    fn region_at(&self, point: Point<f64>) -> Option<(usize, &Region, bool)> {
+        let regions = &self.region().regions;
+        let (rindex, relative_point) = self.region_list.row_at(point, regions.len())?;
+        let index = regions.len() - rindex - 1;
 
         // Check whether the tap is within the region's icon.
-        let relative_x = point.x - region_point.x as f64;
-        let relative_y = region_height - 1. - (bottom_relative % region_height);
-        let relative_point = Point::new(relative_x, relative_y);
         let region_button_point: Point<f64> = self.region_button_point().into();
         let region_button_size: Size<f64> = self.region_button_size().into();
         let button_pressed = rect_contains(region_button_point, region_button_size, relative_point);
 
-        Some((index, &self.region().regions[index], button_pressed))
-    }
-
-    /// Clamp viewport offset.
-    fn clamp_scroll_offset(&mut self) {
-        let old_offset = self.scroll_offset;
-        let max_offset = self.max_scroll_offset() as f64;
-        self.scroll_offset = self.scroll_offset.clamp(0., max_offset);
-
-        // Cancel velocity after reaching the scroll limit.
-        if old_offset != self.scroll_offset {
-            self.touch_state.velocity.stop();
-            self.dirty = true;
-        }
+        Some((index, &regions[index], button_pressed))
     }
 
-    /// Get maximum viewport offset.
-    fn max_scroll_offset(&self) -> usize {
-        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as usize;
-        let region_padding = (REGION_Y_PADDING * self.scale).round() as usize;
-        let region_height = self.region_size().height as usize;
-
-        // Calculate height of all regions plus top padding.
-        let region_count = self.region().regions.len();
-        let regions_height = (region_count * (region_height + region_padding))
-            .saturating_sub(region_padding)
-            + outside_padding;
-
-        // Calculate content outside the viewport.
-        regions_height.saturating_sub(self.region_point().y as usize + region_height)
+    /// Update the region list geometry.
+    fn update_region_list(&mut self) {
+        self.region_list.set_point(self.region_point());
+        self.region_list.set_row_size(self.region_size());
+        self.region_list.set_scale_factor(self.scale);
     }
 
     /// Get the currently selected region.
@@ -353,34 +506,63 @@ impl DownloadView {
 
     /// Get a sub-region using a list of region indices.
     ///
-    /// # Panics
-    ///
-    /// Panics if the index does not exist.
+    /// If the index does not exist, the deepest existing region is returned
+    /// instead. This can happen while the region index is being replaced.
     fn index_region<'a>(mut region: &'a Region, index: &'a [usize]) -> &'a Region {
         for i in index.iter().take_while(|i| **i != usize::MAX) {
-            region = &region.regions[*i];
+            match region.regions.get_index(*i) {
+                Some((_, subregion)) => region = subregion,
+                None => break,
+            }
         }
         region
     }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Gps => Some(&mut self.gps_button),
+            TouchAction::Cleanup => Some(&mut self.cleanup_button),
+            TouchAction::Coverage => Some(&mut self.coverage_button),
+            TouchAction::Tap | TouchAction::Drag => None,
+        }
+    }
+}
+
+impl RowProvider for DownloadView {
+    fn row_count(&self) -> usize {
+        self.region().regions.len()
+    }
+
+    fn draw_row(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'_>,
+        index: usize,
+        point: Point,
+        size: Size,
+    ) {
+        // Show the first region at the top of the list.
+        let regions = &self.region().regions;
+        let region = &regions[regions.len() - index - 1];
+        self.draw_region(config, render_state, point, size, region);
+    }
+
+    fn row_label(&self, index: usize) -> Option<String> {
+        let regions = &self.region().regions;
+        Some(regions[regions.len() - index - 1].name.clone())
+    }
 }
 
 impl UiView for DownloadView {
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
-        let size = self.size * self.scale;
-
-        // Apply scroll velocity.
-        if let Some(delta) = self.touch_state.velocity.apply(&self.input_config) {
-            self.scroll_offset += delta.y;
-        }
-
-        // Ensure offset is correct in case size changed.
-        self.clamp_scroll_offset();
+        // Apply scroll velocity and ensure offset is correct in case size changed.
+        let region_count = self.region().regions.len();
+        self.region_list.update(&self.input_config, region_count);
 
         // Clear dirtiness flag.
-        //
-        // This is inentionally placed after functions like `clamp_scroll_offset`, since
-        // these modify dirtiness but do not require another redraw.
         self.dirty = false;
 
         // Ensure paints are up to date.
@@ -390,37 +572,9 @@ impl UiView for DownloadView {
 
         render_state.clear(config.colors.background);
 
-        // Calculate region list geometry.
-
-        let padding = (REGION_Y_PADDING * self.scale).round() as i32;
-        let region_start = self.region_point();
-        let region_size = self.region_size();
-
-        let mut region_point = region_start;
-        region_point.y += self.scroll_offset.round() as i32;
-
-        // Set clipping mask to cut off regions overlapping the bottom button.
-        let bottom = region_start.y as f32 + region_size.height as f32;
-        let clip_rect = Rect::new(0., 0., size.width as f32, bottom);
-        render_state.save();
-        render_state.clip_rect(clip_rect, None, Some(false));
-
         // Render region entries.
+        self.region_list.draw(config, &mut render_state, self);
         let region = self.region();
-        for (_, region) in region.regions.iter().rev() {
-            if region_point.y > region_start.y + (region_size.height as i32) {
-                region_point.y -= region_size.height as i32 + padding;
-                continue;
-            } else if region_point.y + (region_size.height as i32) < 0 {
-                break;
-            }
-
-            self.draw_region(config, &mut render_state, region_point, region_size, region);
-            region_point.y -= region_size.height as i32 + padding;
-        }
-
-        // Reset region clipping mask.
-        render_state.restore();
 
         let mut label_point: Point<f32> = self.installed_label_point().into();
         let label_size: Size<f32> = self.installed_label_size().into();
@@ -431,6 +585,16 @@ impl UiView for DownloadView {
             let mut tiles_size_text = String::with_capacity("Tiles: X.XXGB".len());
             tiles_size_text.push_str("Tiles: ");
             format_size(&mut tiles_size_text, self.tiles_size);
+
+            // Show storage used by leftover region data.
+            let orphans_size = self.regions.orphans_size();
+            if orphans_size > 0 && self.cleanup_pending {
+                tiles_size_text.push_str(" · Tap again to delete ");
+                format_size(&mut tiles_size_text, orphans_size);
+            } else if orphans_size > 0 {
+                tiles_size_text.push_str(" · Unused: ");
+                format_size(&mut tiles_size_text, orphans_size);
+            }
             builder.add_text(&tiles_size_text);
 
             let mut paragraph = builder.build();
@@ -464,12 +628,25 @@ impl UiView for DownloadView {
             paragraph.paint(&render_state, label_point);
         }
 
-        // Render navigation button.
+        // Render navigation buttons.
         self.back_button.draw(&mut render_state, config.colors.alt_background);
+        if self.gps.is_some() {
+            self.gps_button.draw(&mut render_state, config.colors.alt_background);
+        }
+        if self.cleanup_visible() {
+            let color = if self.cleanup_pending {
+                config.colors.highlight
+            } else {
+                config.colors.alt_background
+            };
+            self.cleanup_button.draw(&mut render_state, color);
+        } else if self.coverage_visible() {
+            self.coverage_button.draw(&mut render_state, config.colors.alt_background);
+        }
     }
 
     fn dirty(&self) -> bool {
-        self.dirty || self.touch_state.velocity.is_moving()
+        self.dirty || self.region_list.is_animating()
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
@@ -479,6 +656,13 @@ impl UiView for DownloadView {
 
         // Update UI elements.
         self.back_button.set_point(Self::back_button_point(size, self.scale));
+        self.gps_button.set_point(Self::gps_button_point(size, self.scale));
+        let gps_visible = self.gps.is_some();
+        let point = Self::region_action_button_point(size, self.scale, gps_visible);
+        self.cleanup_button.set_point(point);
+        self.coverage_button.set_point(point);
+
+        self.update_region_list();
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
@@ -489,12 +673,22 @@ impl UiView for DownloadView {
         // Update UI elements.
         self.back_button.set_point(Self::back_button_point(self.size, scale));
         self.back_button.set_size(Self::back_button_size(scale));
+        self.gps_button.set_point(Self::gps_button_point(self.size, scale));
+        self.gps_button.set_size(Self::back_button_size(scale));
+        let gps_visible = self.gps.is_some();
+        let point = Self::region_action_button_point(self.size, scale, gps_visible);
+        self.cleanup_button.set_point(point);
+        self.cleanup_button.set_size(Self::back_button_size(scale));
+        self.coverage_button.set_point(point);
+        self.coverage_button.set_size(Self::back_button_size(scale));
+
+        self.update_region_list();
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn touch_down(&mut self, slot: i32, _time: u32, point: Point<f64>) {
         // Cancel velocity if a new touch sequence starts.
-        self.touch_state.velocity.stop();
+        self.region_list.stop();
 
         // Only allow a single active touch slot.
         if !self.touch_state.slots.is_empty() {
@@ -503,8 +697,27 @@ impl UiView for DownloadView {
 
         // Determine goal of this touch sequence.
         let point = point * self.scale;
-        self.touch_state.action =
-            if self.back_button.contains(point) { TouchAction::Back } else { TouchAction::Tap };
+        self.touch_state.action = if self.back_button.contains(point) {
+            TouchAction::Back
+        } else if self.gps.is_some() && self.gps_button.contains(point) {
+            TouchAction::Gps
+        } else if self.cleanup_visible() && self.cleanup_button.contains(point) {
+            TouchAction::Cleanup
+        } else if self.coverage_visible() && self.coverage_button.contains(point) {
+            TouchAction::Coverage
+        } else {
+            TouchAction::Tap
+        };
+
+        // Cancel pending cleanup confirmation when anything else is touched.
+        if self.touch_state.action != TouchAction::Cleanup && self.cleanup_pending {
+            self.cleanup_pending = false;
+            self.dirty = true;
+        }
+
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
 
         // Convert position to physical space.
         let slot = self.touch_state.slots.entry(slot).or_default();
@@ -534,15 +747,12 @@ impl UiView for DownloadView {
             }
             self.touch_state.action = TouchAction::Drag;
 
-            // Update pending scroll velocity.
-            let delta = slot.point.y - old_point.y;
-            self.touch_state.velocity.set(Point::new(0., delta));
-
             // Apply scroll motion.
-            let old_offset = self.scroll_offset;
-            self.scroll_offset += delta;
-            self.clamp_scroll_offset();
-            self.dirty |= self.scroll_offset != old_offset;
+            let delta = slot.point.y - old_point.y;
+            let region_count = self.region().regions.len();
+            self.dirty |= self.region_list.drag(delta, region_count);
+        } else {
+            self.dirty |= move_button_touch(self.action_button(), point);
         }
     }
 
@@ -554,6 +764,9 @@ impl UiView for DownloadView {
             None => return,
         };
 
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
         // Dispatch tap actions on release.
         match self.touch_state.action {
             // Handle touch tap on region entries.
@@ -568,34 +781,18 @@ impl UiView for DownloadView {
                 match (index, region, download_state) {
                     // Ignore button interactions during download
                     (.., DownloadState::Downloading) => (),
+                    // Keep installed data while the database is read-only.
+                    (.., DownloadState::Downloaded) if self.regions.read_only() => (),
                     // Download region's data.
-                    (_, region, DownloadState::Available) => {
-                        // Immediately mark region as downloading.
-                        region.set_download_state(DownloadState::Downloading);
-                        self.dirty = true;
-
-                        let current_region = self.current_region;
-                        let regions = self.regions.clone();
-                        tokio::spawn(async move {
-                            // Re-index the region, since we can't move the reference.
-                            let mut region = Self::index_region(regions.world(), &current_region);
-                            region = &region.regions[index];
-
-                            match regions.download(region).await {
-                                Ok(_) => region.set_download_state(DownloadState::Downloaded),
-                                Err(err) => {
-                                    error!("Region data download failed: {err}");
-
-                                    // Delete all data to avoid tempfiles stealing storage space.
-                                    regions.delete(region).await;
-
-                                    region.set_download_state(DownloadState::Available);
-                                },
-                            }
-
-                            // Wake UI to display the download state update.
-                            regions.redraw_download_view();
-                        });
+                    (index, _, DownloadState::Available) => {
+                        let mut region_index = self.current_region;
+                        match region_index.iter_mut().find(|i| **i == usize::MAX) {
+                            Some(child_index) => {
+                                *child_index = index;
+                                self.download_region(region_index);
+                            },
+                            None => error!("Insufficient region depth; please file a bug report"),
+                        }
                     },
                     // Delete region's local data.
                     (_, region, DownloadState::Downloaded) => {
@@ -611,17 +808,20 @@ impl UiView for DownloadView {
                             let mut region = Self::index_region(regions.world(), &current_region);
                             region = &region.regions[index];
 
-                            regions.delete(region).await
+                            regions.delete(region).await;
+
+                            // Unload the region's geocoder and routing data.
+                            regions.reload_datasets();
                         });
                     },
-                    // Ignore touch on region when region doesn't have child regions.
-                    (_, region, _) if region.regions.is_empty() => (),
+                    // Show coverage of regions without child regions.
+                    (_, region, _) if region.regions.is_empty() => self.show_coverage(region),
                     // Handle navigation into the next region.
                     (index, ..) => {
                         match self.current_region.iter_mut().find(|i| **i == usize::MAX) {
                             Some(region_index) => {
                                 *region_index = index;
-                                self.scroll_offset = 0.;
+                                self.region_list.reset();
                                 self.dirty = true;
                             },
                             None => error!("Insufficient region depth; please file a bug report"),
@@ -629,6 +829,24 @@ impl UiView for DownloadView {
                     },
                 }
             },
+            // Handle GPS region download.
+            TouchAction::Gps if self.gps_button.contains(removed.point) => {
+                self.download_gps_region();
+            },
+            // Delete leftover region data in the background, after a second tap.
+            TouchAction::Cleanup if self.cleanup_button.contains(removed.point) => {
+                self.cleanup_pending = !self.cleanup_pending;
+                self.dirty = true;
+
+                if !self.cleanup_pending {
+                    let regions = self.regions.clone();
+                    tokio::spawn(async move { regions.delete_orphans().await });
+                }
+            },
+            // Show the current region's outline on the map.
+            TouchAction::Coverage if self.coverage_button.contains(removed.point) => {
+                self.show_coverage(self.region());
+            },
             // Handle "back" button navigation.
             TouchAction::Back if self.back_button.contains(removed.point) => {
                 match self.current_region.iter_mut().rfind(|i| **i != usize::MAX) {
@@ -654,6 +872,11 @@ impl UiView for DownloadView {
     }
 
     fn enter(&mut self) {
+        self.cleanup_pending = false;
+
+        // Retry fetching the region index if it failed to load at startup.
+        self.regions.reload_index();
+
         // Update current tiles storage size.
         //
         // While the database includes data beyond just the tile storage itself, that
@@ -670,8 +893,6 @@ impl UiView for DownloadView {
 struct TouchState {
     slots: HashMap<i32, TouchSlot>,
     action: TouchAction,
-
-    velocity: Velocity,
 }
 
 /// Touch slot state.
@@ -688,10 +909,13 @@ enum TouchAction {
     Tap,
     Drag,
     Back,
+    Gps,
+    Cleanup,
+    Coverage,
 }
 
 /// Format a byte size into a 3 digit human-readable size.
-fn format_size(w: &mut impl Write, size: u64) {
+pub fn format_size(w: &mut impl Write, size: u64) {
     // Define bounds to ensure maximum value is 999 after rounding.
     let (unit, divisor) = match size {
         ..1_000 => ("B", 1.),
@@ -705,3 +929,14 @@ fn format_size(w: &mut impl Write, size: u64) {
 
     let _ = write!(w, "{size:.precision$} {unit}");
 }
+
+/// Format the remaining download time.
+fn format_eta(w: &mut impl Write, eta: Duration) {
+    let secs = eta.as_secs();
+    let (hours, minutes, seconds) = (secs / 3600, secs % 3600 / 60, secs % 60);
+
+    let _ = match hours {
+        0 => write!(w, "{minutes}:{seconds:0>2} left"),
+        _ => write!(w, "{hours}:{minutes:0>2}:{seconds:0>2} left"),
+    };
+}
@@ -0,0 +1,228 @@
+//! Headless view harness for tests.
+//!
+//! Views are rendered to a CPU raster surface and receive synthetic touch and
+//! keyboard input, which allows testing them without a Wayland compositor.
+//!
+//! Idle callbacks scheduled by the views are never dispatched, since that
+//! requires the complete application state.
+
+use calloop::{EventLoop, LoopHandle};
+use skia_safe::Image;
+use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
+
+use crate::config::Config;
+use crate::geometry::{Point, Size};
+use crate::ui::skia::Canvas;
+use crate::ui::view::UiView;
+use crate::{Error, State};
+
+/// Logical width of the harness' render target.
+const WIDTH: u32 = 360;
+
+/// Logical height of the harness' render target.
+const HEIGHT: u32 = 720;
+
+/// Time between synthetic touch sequences in milliseconds.
+///
+/// This is large enough to never trigger multi-tap detection.
+const TOUCH_INTERVAL: u32 = 10_000;
+
+/// View with a headless render target.
+pub struct Harness<V> {
+    pub view: V,
+    pub config: Config,
+
+    canvas: Canvas,
+    size: Size,
+    scale: f64,
+    time: u32,
+
+    // Event loop must outlive the view's loop handle.
+    _event_loop: EventLoop<'static, State>,
+}
+
+impl<V: UiView> Harness<V> {
+    /// Create a view using the default configuration.
+    pub fn new<F>(new_view: F) -> Self
+    where
+        F: FnOnce(LoopHandle<'static, State>, &Config, Size) -> Result<V, Error>,
+    {
+        let event_loop = EventLoop::try_new().unwrap();
+        let config = Config::default();
+        let size = Size::new(WIDTH, HEIGHT);
+
+        let view = new_view(event_loop.handle(), &config, size).unwrap();
+        let canvas = Canvas::new(&config);
+
+        Self { view, config, canvas, size, _event_loop: event_loop, scale: 1., time: 0 }
+    }
+
+    /// Update the render target's scale factor.
+    pub fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.canvas.set_scale_factor(scale);
+        self.view.set_scale_factor(scale);
+    }
+
+    /// Render the view.
+    pub fn draw(&mut self) -> Image {
+        let config = &self.config;
+        let view = &mut self.view;
+        self.canvas.draw_raster(self.size * self.scale, |render_state| {
+            view.draw(config, render_state);
+        })
+    }
+
+    /// Tap a logical point.
+    pub fn tap(&mut self, point: Point<f64>) {
+        self.touch_down(point);
+        self.view.touch_up(0);
+    }
+
+    /// Drag from one logical point to another.
+    pub fn drag(&mut self, start: Point<f64>, end: Point<f64>, steps: u32) {
+        self.touch_down(start);
+        for step in 1..=steps {
+            let progress = step as f64 / steps as f64;
+            let x = start.x + (end.x - start.x) * progress;
+            let y = start.y + (end.y - start.y) * progress;
+            self.view.touch_motion(0, Point::new(x, y));
+        }
+        self.view.touch_up(0);
+    }
+
+    /// Start a new touch sequence at a logical point.
+    pub fn touch_down(&mut self, point: Point<f64>) {
+        self.time += TOUCH_INTERVAL;
+        self.view.touch_down(0, self.time, point);
+    }
+
+    /// Press a key without modifiers.
+    pub fn press_key(&mut self, keysym: Keysym) {
+        self.view.press_key(0, keysym, Modifiers::default());
+    }
+
+    /// Enter text through the input method.
+    pub fn commit_string(&mut self, text: &str) {
+        self.view.commit_string(text.into());
+    }
+}
+
+/// Get the raw pixel data of a rendered image.
+pub fn pixels(image: &Image) -> Vec<u8> {
+    let pixmap = image.peek_pixels().unwrap();
+    pixmap.bytes().unwrap().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::db::Db;
+    use crate::geometry::GeoPoint;
+    use crate::region::Regions;
+    use crate::router::{Route, Segment};
+    use crate::ui::view::onboarding::OnboardingView;
+    use crate::ui::view::route::RouteView;
+    use crate::ui::view::search::SearchView;
+    use crate::ui::view::settings::SettingsView;
+
+    #[test]
+    fn route_view_scrolling() {
+        let mut harness = Harness::new(RouteView::new);
+
+        let segment = |i| Segment {
+            points: vec![GeoPoint::new(0., i as f64 * 0.01), GeoPoint::new(0., i as f64 * 0.01)],
+            instruction: Arc::new(format!("Instruction {i}")),
+            length: 1_000,
+            time: 60,
+        };
+        let route = Route { segments: (0..30).map(segment).collect(), ..Default::default() };
+        harness.view.set_route(Arc::new(route), false);
+
+        let initial = pixels(&harness.draw());
+        assert!(!harness.view.dirty());
+
+        // Content moves with the touch point.
+        harness.drag(Point::new(180., 200.), Point::new(180., 400.), 10);
+        assert!(harness.view.dirty());
+        assert_ne!(pixels(&harness.draw()), initial);
+    }
+
+    #[test]
+    fn onboarding_gps_update() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+
+        let db = runtime.block_on(Db::new_in_memory()).unwrap();
+        let mut harness = Harness::new(|event_loop, config, size| {
+            OnboardingView::new(event_loop, db, config, size)
+        });
+
+        let waiting = pixels(&harness.draw());
+        assert!(!harness.view.dirty());
+
+        harness.view.set_gps(Some(GeoPoint::new(52.52, 13.405)));
+        assert!(harness.view.dirty());
+        let located = pixels(&harness.draw());
+        assert_ne!(located, waiting);
+
+        // Completing onboarding leaves no button highlighted behind.
+        harness.tap(Point::new(WIDTH as f64 - 40., HEIGHT as f64 - 40.));
+        assert_eq!(pixels(&harness.draw()), located);
+    }
+
+    #[test]
+    fn search_text_input() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let _guard = runtime.enter();
+
+        let db = runtime.block_on(Db::new_in_memory()).unwrap();
+        let mut harness = Harness::new(|event_loop, config, size| {
+            let client = crate::http_client()?;
+            let index_url = config.search.region_index_url.clone();
+            let regions = Regions::new(event_loop.clone(), client.clone(), db.clone(), index_url)?;
+            SearchView::new(event_loop, client, db, config, regions, size)
+        });
+        harness.view.enter();
+        harness.view.text_input_enter();
+        let empty = pixels(&harness.draw());
+
+        // Text is added through the input method.
+        harness.commit_string("Berlin");
+        assert!(harness.view.dirty());
+        assert_ne!(pixels(&harness.draw()), empty);
+
+        // Text is removed through the keyboard.
+        for _ in 0.."Berlin".len() {
+            harness.press_key(Keysym::BackSpace);
+        }
+        assert_eq!(pixels(&harness.draw()), empty);
+    }
+
+    #[test]
+    fn button_press_highlight() {
+        let mut harness = Harness::new(SettingsView::new);
+        let idle = pixels(&harness.draw());
+
+        // Back button is highlighted while it is touched.
+        harness.touch_down(Point::new(WIDTH as f64 - 40., HEIGHT as f64 - 40.));
+        assert!(harness.view.dirty());
+        assert_ne!(pixels(&harness.draw()), idle);
+
+        // Highlight is removed on release.
+        harness.view.touch_up(0);
+        assert_eq!(pixels(&harness.draw()), idle);
+    }
+
+    #[test]
+    fn scaled_rendering() {
+        let mut harness = Harness::new(SettingsView::new);
+        harness.set_scale_factor(2.);
+
+        let image = harness.draw();
+        assert_eq!(image.width() as u32, WIDTH * 2);
+        assert_eq!(image.height() as u32, HEIGHT * 2);
+    }
+}
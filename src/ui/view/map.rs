@@ -1,32 +1,49 @@
 //! Map rendering UI view.
 
 use std::collections::{HashMap, HashSet};
+use std::f32::consts::PI;
 use std::mem;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use calloop::channel::{self, Event};
 use calloop::timer::{TimeoutAction, Timer};
 use calloop::{LoopHandle, RegistrationToken};
+use charon_map::view::MapPosition;
+use charon_map::{input, marker};
 use reqwest::Client;
 use skia_safe::textlayout::TextAlign;
 use skia_safe::{
     ClipOp, Color4f, FilterMode, MipmapMode, Paint, PaintCap, PaintJoin, Path, PathBuilder, Rect,
     SamplingOptions,
 };
+use smallvec::SmallVec;
 use tracing::error;
 
-use crate::config::{Config, Input};
-use crate::db::Db;
-use crate::geometry::{self, GeoPoint, Point, Size, rect_intersects_line};
+use crate::config::{CameraMode, Config, Hazards, Input, Navigation};
+use crate::db::{self, Db, Favorite, HeatmapCell, Track, TrackPoint};
+use crate::dbus::DbusEvent;
+use crate::geocoder::CorridorQuery;
+use crate::geometry::{self, GeoPoint, Point, Size, rect_contains, rect_intersects_line};
+use crate::hazards::{self, Hazard, HazardAlerts};
+use crate::region::RegionBounds;
 use crate::router::{Mode as RouteMode, Route};
+use crate::sharing::PositionSharing;
 use crate::tiles::{MAX_ZOOM, TILE_SIZE, TileIndex, TileIter, Tiles};
+use crate::ui::camera::{MapTransform, NavigationCamera};
+use crate::ui::hud::{DebugHud, HudMetrics};
+use crate::ui::maneuver::ManeuverAlerts;
 use crate::ui::skia::{RenderState, TextOptions};
+use crate::ui::surface::SurfaceKind;
+use crate::ui::trip_summary::Trip;
 use crate::ui::view::map::route::MapRoute;
 use crate::ui::view::search::RouteOrigin;
 use crate::ui::view::{self, UiView, View};
-use crate::ui::{Button, Svg, Velocity};
-use crate::{Error, State, dbus};
+use crate::ui::{
+    Button, Easing, Svg, Tween, Velocity, move_button_touch, press_button, release_button,
+};
+use crate::{Error, State, audio, dbus, track};
 
 /// Button width and height at scale 1.
 const BUTTON_SIZE: u32 = 48;
@@ -37,18 +54,62 @@ const BUTTON_PADDING: u32 = 16;
 /// Border size around the buttons at scale 1.
 const BUTTON_BORDER: f64 = 2.;
 
-/// Border size around the locked GPS button at scale 1.
+/// Border size around the locked GPS and map lock buttons at scale 1.
 const LOCKED_GPS_BORDER: f64 = 4.;
 
+/// Extra tile size overlapping neighboring tiles.
+///
+/// Tile edges are snapped to the pixel grid, but without this overlap hairline
+/// seams still show up between tiles while the map is rotated or tilted.
+const TILE_OVERLAP: f32 = 1.;
+
+/// Padding inside the read-only database banner at scale 1.
+const BANNER_PADDING: f32 = 8.;
+
+/// Notice shown while the database is locked by another process.
+const READ_ONLY_MESSAGE: &str =
+    "Database in use by another process: showing cached tiles only, changes are not saved";
+
+/// Notice shown while the database could not be opened.
+const IN_MEMORY_MESSAGE: &str = "Failed to open database: changes are not saved, tap to retry";
+
+/// Notice shown while map tiles along a route are downloaded.
+const PREFETCH_MESSAGE: &str = "Downloading map along the route";
+
+/// Prefix for notices about invalid configuration values.
+const CONFIG_WARNING_PREFIX: &str = "Config error";
+
+/// Notice shown after the trip summary image was saved.
+const TRIP_SUMMARY_MESSAGE: &str = "Trip summary copied to the clipboard and saved to";
+
+/// Notice shown while tileserver downloads are suspended.
+const TILESERVER_OFFLINE_MESSAGE: &str = "Tileserver offline: showing cached tiles only";
+
+/// Notice shown while the tileserver refuses to provide tiles.
+const TILESERVER_REJECTED_MESSAGE: &str =
+    "Tileserver rejected requests: check the API key in `tiles.server`, tap to retry";
+
 /// Attribution label font size relative to the default.
 const ATTRIBUTION_FONT_SIZE: f32 = 0.5;
 
+/// Duration of one cycle of the shimmer over downloading tiles.
+const SHIMMER_PERIOD: Duration = Duration::from_millis(1500);
+
+/// Maximum opacity of the shimmer over downloading tiles.
+const SHIMMER_ALPHA: f32 = 0.15;
+
+/// Interval between redraws of the shimmer over downloading tiles.
+const SHIMMER_INTERVAL: Duration = Duration::from_millis(100);
+
 /// POI/GPS indicator width/height at scale 1.
 const INDICATOR_SIZE: f32 = 10.;
 
 /// POI/GPS indicator border size at scale 1.
 const INDICATOR_BORDER: f32 = 4.;
 
+/// Maximum touch distance from a marker's center for moving it at scale 1.
+const MARKER_TOUCH_RADIUS: f64 = 24.;
+
 /// Padding around the instruction message box at scale 1.
 const INSTRUCTION_OUTSIDE_PADDING: f32 = 16.;
 
@@ -64,6 +125,18 @@ const INSTRUCTION_FONT_SIZE: f32 = 1.2;
 /// Instruction distance/time font size relative to the default.
 const INSTRUCTION_ALT_FONT_SIZE: f32 = 0.75;
 
+/// Maximum POI callout width at scale 1.
+const CALLOUT_MAX_WIDTH: f32 = 300.;
+
+/// Padding inside and around the POI callout at scale 1.
+const CALLOUT_PADDING: f32 = 8.;
+
+/// POI callout action button width and height at scale 1.
+const CALLOUT_BUTTON_SIZE: u32 = 40;
+
+/// POI callout address font size relative to the default.
+const CALLOUT_ADDRESS_FONT_SIZE: f32 = 0.75;
+
 /// Time after losing GPS signal before GPS indicator is removed.
 const GPS_TIMEOUT: Duration = Duration::from_secs(10);
 
@@ -73,6 +146,22 @@ const ROUTE_WIDTH: f32 = 10.;
 /// Square of the minimum physical distance between a route's path segments.
 const ROUTE_RESOLUTION: f32 = 15.;
 
+/// Zoom levels with precomputed simplified route geometry.
+///
+/// Above the highest level, the route is always drawn in full detail.
+const ROUTE_SIMPLIFIED_ZOOMS: [u8; 6] = [4, 6, 8, 10, 12, 14];
+
+/// Maximum deviation of a simplified route from the full route in pixels at
+/// the simplification's zoom level.
+const ROUTE_SIMPLIFY_TOLERANCE: f64 = 1.;
+
+/// Route distance ahead of the current position in meters, which is always
+/// drawn in full detail.
+const ROUTE_DETAIL_DISTANCE: u32 = 2_000;
+
+/// Width of the region coverage outline at scale 1.
+const COVERAGE_WIDTH: f32 = 4.;
+
 /// Percentage of route width used to center the map.
 const ROUTE_ZOOM_PADDING: f64 = 1.1;
 
@@ -88,6 +177,58 @@ const MIN_REROUTE_INTERVAL: Duration = Duration::from_secs(5);
 /// Default zoom level for displaying GPS location.
 const GPS_ZOOM: u8 = 18;
 
+/// Duration of animated map movements.
+const FLY_TO_DURATION: Duration = Duration::from_millis(400);
+
+/// Duration of the zoom animation to the nearest integer zoom level.
+const ZOOM_SNAP_DURATION: Duration = Duration::from_millis(150);
+
+/// Duration of the zoom animation for zoom keys and the scroll wheel.
+const ZOOM_STEP_DURATION: Duration = Duration::from_millis(200);
+
+/// Maximum distance in screen sizes for animated map movements.
+///
+/// Larger jumps would download all tiles along the way.
+const FLY_TO_MAX_SCREENS: f64 = 3.;
+
+/// Duration of the highlight ring around newly selected POI markers.
+const MARKER_PULSE_DURATION: Duration = Duration::from_secs(1);
+
+/// Final radius of the POI highlight ring at scale 1.
+const MARKER_PULSE_RADIUS: f32 = 40.;
+
+/// Line width of the POI highlight ring at scale 1.
+const MARKER_PULSE_WIDTH: f32 = 3.;
+
+/// Travel time simulated per second of route preview.
+const PREVIEW_SPEEDUP: f64 = 20.;
+
+/// Interval between position updates during route preview.
+const PREVIEW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Recorded time played back per second of track playback.
+const PLAYBACK_SPEEDUP: f64 = 60.;
+
+/// Interval between position updates during track playback.
+const PLAYBACK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Line width of played back tracks at scale 1.
+const TRACK_WIDTH: f32 = 4.;
+
+/// Height of the track playback slider's bar at scale 1.
+const SLIDER_BAR_HEIGHT: f32 = 4.;
+
+/// Track playback slider label font size relative to the default.
+const SLIDER_FONT_SIZE: f32 = 0.75;
+
+/// Number of zoom levels between the map and the visited area heatmap cells.
+///
+/// Each tile is divided into `2^HEATMAP_CELL_DEPTH` cells along each axis.
+const HEATMAP_CELL_DEPTH: u8 = 3;
+
+/// Opacity of heatmap cells with the fewest and most visits.
+const HEATMAP_ALPHA: (f32, f32) = (0.15, 0.6);
+
 /// Distance it takes to go from 1x to 2x zoom at scale 1.
 const DOUBLE_TAP_ZOOM_DISTANCE: f64 = 100.;
 
@@ -95,27 +236,77 @@ const DOUBLE_TAP_ZOOM_DISTANCE: f64 = 100.;
 pub struct MapView {
     rendered_parent_tiles: HashSet<TileIndex>,
     pending_tiles: Vec<TileIndex>,
+    shimmer_timer: Option<RegistrationToken>,
+    shimmer_start: Instant,
+    shimmering: bool,
     tiles: Tiles,
+    db: Db,
 
     gps: Option<RenderGeoPoint>,
+    last_gps_fix: Option<Instant>,
     poi: Option<RenderGeoPoint>,
+    poi_details: Option<PoiDetails>,
+    favorites: Vec<Favorite>,
+    hazard_alerts: HazardAlerts,
     route: Option<MapRoute>,
+    preview: Option<RoutePreview>,
+    playback: Option<TrackPlayback>,
+    heatmap: Option<Heatmap>,
+    trip: Option<Trip>,
+    sharing: PositionSharing,
     last_reroute: Instant,
     heading: Option<f32>,
     rerouting: bool,
+    gps_resync: bool,
 
-    cursor_tile: TileIndex,
-    cursor_offset: Point,
-    cursor_zoom: f64,
+    position: MapPosition,
+    fly_to: Option<FlyTo>,
+    marker_pulse: Option<Tween>,
     gps_locked: bool,
+    interaction_locked: bool,
 
+    callout_favorite_button: Button,
+    callout_route_button: Button,
+    callout_visible: bool,
     search_button: Button,
+    close_button: Button,
+    zoom_in_button: Button,
+    zoom_out_button: Button,
+    camera_button: Button,
+    lock_button: Button,
+    along_route_button: Button,
+    mini_window_button: Button,
+    mini_window_supported: bool,
     gps_button: Button,
     route_paint: Paint,
+    track_paint: Paint,
     tile_paint: Paint,
+    coverage_paint: Paint,
+    coverage: Option<RegionBounds>,
+    hud_enabled: bool,
+    hud: DebugHud,
+    attribution_url: Arc<String>,
+    attribution_size: Size<f32>,
+    db_banner: (f32, f32),
+    tileserver_banner: (f32, f32),
+    config_warnings: Vec<String>,
+    config_banner: (f32, f32),
+    trip_summary_path: Option<PathBuf>,
+    trip_summary_banner: (f32, f32),
+    favorites_tx: channel::Sender<Vec<Favorite>>,
+    heatmap_tx: channel::Sender<(u8, Vec<HeatmapCell>)>,
+    hazards_tx: channel::Sender<Vec<Hazard>>,
 
     touch_state: TouchState,
     input_config: Input,
+    navigation_config: Navigation,
+    hazards_config: Hazards,
+    min_zoom: u8,
+    max_zoom: u8,
+    camera: NavigationCamera,
+    camera_mode: CameraMode,
+    transform: MapTransform,
+    maneuver_alerts: ManeuverAlerts,
 
     event_loop: LoopHandle<'static, State>,
 
@@ -139,29 +330,130 @@ impl MapView {
         event_loop.insert_source(tile_rx, |event, _, state| {
             let map_view = state.window.views.map();
             if let Event::Msg(tile_index) = event
-                && map_view.pending_tiles.contains(&tile_index)
+                && (map_view.pending_tiles.contains(&tile_index) || map_view.tiles.prefetching())
             {
                 map_view.dirty = true;
                 state.window.unstall();
             }
         })?;
-        let tiles = Tiles::new(client, db, tile_tx, config)?;
+        let sharing = PositionSharing::new(client.clone(), config);
+        let tiles = Tiles::new(client, db.clone(), tile_tx, config)?;
+
+        // Load favorite locations in the background.
+        let (favorites_tx, favorites_rx) = channel::channel();
+        event_loop.insert_source(favorites_rx, |event, _, state| {
+            if let Event::Msg(favorites) = event {
+                let map_view = state.window.views.map();
+                map_view.favorites = favorites;
+                map_view.dirty = true;
+                state.window.unstall();
+            }
+        })?;
+        let favorites_db = db.clone();
+        let load_favorites_tx = favorites_tx.clone();
+        tokio::spawn(async move {
+            match favorites_db.favorites().await {
+                Ok(favorites) => {
+                    let _ = load_favorites_tx.send(favorites);
+                },
+                Err(err) => error!("Failed to load favorites: {err}"),
+            }
+        });
+
+        // Handle visited area heatmap updates.
+        let (heatmap_tx, heatmap_rx) = channel::channel();
+        event_loop.insert_source(heatmap_rx, |event, _, state| {
+            if let Event::Msg((zoom, cells)) = event {
+                let map_view = state.window.views.map();
+                if let Some(heatmap) = &mut map_view.heatmap {
+                    heatmap.set_cells(zoom, cells);
+                    map_view.dirty = true;
+                    state.window.unstall();
+                }
+            }
+        })?;
+
+        // Restore the last selected camera mode in the background.
+        let (camera_mode_tx, camera_mode_rx) = channel::channel();
+        event_loop.insert_source(camera_mode_rx, |event, _, state| {
+            if let Event::Msg(camera_mode) = event {
+                let map_view = state.window.views.map();
+                map_view.camera_mode = camera_mode;
+                map_view.camera_button.set_svg(camera_mode_svg(camera_mode));
+                map_view.dirty = true;
+                state.window.unstall();
+            }
+        })?;
+        let camera_mode_db = db.clone();
+        tokio::spawn(async move {
+            match camera_mode_db.camera_mode().await {
+                Ok(Some(camera_mode)) => {
+                    let _ = camera_mode_tx.send(camera_mode);
+                },
+                Ok(None) => (),
+                Err(err) => error!("Failed to load camera mode: {err}"),
+            }
+        });
+
+        // Load user-supplied hazard points in the background.
+        let (hazards_tx, hazards_rx) = channel::channel();
+        event_loop.insert_source(hazards_rx, |event, _, state| {
+            if let Event::Msg(hazards) = event {
+                let map_view = state.window.views.map();
+                map_view.hazard_alerts.set_hazards(hazards);
+                map_view.dirty = true;
+                state.window.unstall();
+            }
+        })?;
+        load_hazards(config.hazards.path.clone(), hazards_tx.clone());
 
         // Listen for new GPS location updates.
         Self::spawn_gps(&event_loop)?;
 
-        // Set (0, 0) start location at a zoom level without empty space.
-        let (cursor_tile, cursor_offset) = GeoPoint::new(0., 0.).tile(3);
+        // Set the configured start location.
+        let startup = config.startup;
+        let start = GeoPoint::new(startup.latitude, startup.longitude);
+        let (min_zoom, max_zoom) = zoom_range(config);
+        let position = MapPosition::new(start, startup.zoom.clamp(min_zoom, max_zoom));
 
         // Initialize UI elements.
         let point = Self::search_button_point(size, 1.);
         let size = Self::button_size(1.);
         let search_button = Button::new(point, size, Svg::Search);
+        let close_button = Button::new(point, size, Svg::Close);
 
         let point = Self::gps_button_point(size, 1.);
         let size = Self::button_size(1.);
         let gps_button = Button::new(point, size, Svg::Gps);
 
+        let camera_mode = config.navigation.camera_mode;
+        let point = Self::camera_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let camera_button = Button::new(point, size, camera_mode_svg(camera_mode));
+
+        let point = Self::lock_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let lock_button = Button::new(point, size, Svg::Lock);
+
+        let point = Self::along_route_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let along_route_button = Button::new(point, size, Svg::Poi);
+
+        let point = Self::mini_window_button_point(size, 1.);
+        let mini_window_button = Button::new(point, size, Svg::MiniWindow);
+
+        let point = Self::zoom_out_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let zoom_out_button = Button::new(point, size, Svg::ZoomOut);
+
+        let point = Self::zoom_in_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let zoom_in_button = Button::new(point, size, Svg::ZoomIn);
+
+        let size = Self::callout_button_size(1.);
+        let callout_route_button = Button::new(Point::default(), size, Svg::Route);
+        let callout_favorite_button = Button::new(Point::default(), size, Svg::Star);
+
         let mut tile_paint = Paint::default();
         tile_paint.set_color4f(Color4f::from(config.colors.background), None);
 
@@ -175,28 +467,88 @@ impl MapView {
         route_paint.set_anti_alias(false);
         route_paint.set_stroke(true);
 
+        let mut track_paint = route_paint.clone();
+        track_paint.set_color4f(Color4f::from(config.colors.alt_foreground), None);
+        track_paint.set_stroke_width(TRACK_WIDTH);
+
+        let mut coverage_paint = Paint::default();
+        coverage_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+        coverage_paint.set_stroke_width(COVERAGE_WIDTH);
+        coverage_paint.set_anti_alias(true);
+        coverage_paint.set_stroke(true);
+
         Ok(Self {
-            cursor_offset,
+            callout_favorite_button,
+            camera_button,
+            camera_mode,
+            callout_route_button,
+            lock_button,
+            along_route_button,
+            mini_window_button,
+            sharing,
             search_button,
-            cursor_tile,
+            close_button,
+            zoom_in_button,
+            zoom_out_button,
+            position,
             route_paint,
+            track_paint,
             event_loop,
             gps_button,
             tile_paint,
+            coverage_paint,
             tiles,
             size,
+            db,
             last_reroute: Instant::now(),
+            preview: Default::default(),
+            playback: Default::default(),
+            heatmap: Default::default(),
+            hud_enabled: config.debug.hud,
+            attribution_url: config.tiles.attribution_url.clone(),
+            attribution_size: Default::default(),
+            db_banner: Default::default(),
+            tileserver_banner: Default::default(),
+            coverage: Default::default(),
+            config_warnings: Default::default(),
+            config_banner: Default::default(),
+            trip_summary_path: Default::default(),
+            trip_summary_banner: Default::default(),
+            favorites_tx,
+            heatmap_tx,
+            hazards_tx,
             input_config: config.input,
+            navigation_config: config.navigation,
+            hazards_config: config.hazards.clone(),
+            min_zoom,
+            max_zoom,
             dirty: true,
             scale: 1.,
             rendered_parent_tiles: Default::default(),
             pending_tiles: Default::default(),
-            cursor_zoom: Default::default(),
+            shimmer_timer: Default::default(),
+            shimmer_start: Instant::now(),
+            shimmering: Default::default(),
+            fly_to: Default::default(),
+            marker_pulse: Default::default(),
             touch_state: Default::default(),
+            callout_visible: Default::default(),
+            camera: Default::default(),
+            maneuver_alerts: Default::default(),
+            transform: MapTransform::identity(Size::default()),
+            last_gps_fix: Default::default(),
+            poi_details: Default::default(),
+            favorites: Default::default(),
+            hazard_alerts: Default::default(),
+            trip: Default::default(),
             gps_locked: Default::default(),
+            interaction_locked: Default::default(),
+            mini_window_supported: Default::default(),
             rerouting: Default::default(),
+            gps_resync: Default::default(),
             heading: Default::default(),
             route: Default::default(),
+            hud: Default::default(),
             gps: Default::default(),
             poi: Default::default(),
         })
@@ -204,19 +556,30 @@ impl MapView {
 
     /// Render all visible tiles.
     #[cfg_attr(feature = "profiling", profiling::function)]
-    fn draw_tiles<'a>(&mut self, render_state: &mut RenderState<'a>, iter: &mut TileIter) {
-        let size: Size<f32> = (self.size * self.scale).into();
+    fn draw_tiles<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        iter: &mut TileIter,
+    ) {
+        let size: Size<f32> = iter.screen_size().into();
         let tile_size = iter.tile_size() as f32;
 
         // Reset which oversized tiles have been rendered this run.
         self.rendered_parent_tiles.clear();
 
+        // Indicate tiles which are still downloading, unless downloads are suspended.
+        let show_downloads = !self.tiles.tileserver_offline();
+        let mut downloading_tiles = Vec::new();
+
         for (index, point) in iter {
             let mut point: Point<f32> = point.into();
             let mut tile_size = tile_size;
 
             // Get image for this tile.
-            let (image, fallback) = match self.tiles.get(index).image() {
+            let tile = self.tiles.get(index);
+            let downloading = show_downloads && tile.downloading();
+            let (image, fallback) = match tile.image() {
                 Some(image) => (image, false),
                 None => {
                     #[cfg(feature = "profiling")]
@@ -225,6 +588,12 @@ impl MapView {
                     // If the image hasn't loaded yet, add it to the pending tiles.
                     self.pending_tiles.push(index);
 
+                    if downloading {
+                        let rect =
+                            Rect::new(point.x, point.y, point.x + tile_size, point.y + tile_size);
+                        downloading_tiles.push(rect);
+                    }
+
                     // Search for a bigger tile which is already loaded.
                     let mut alt_index = index;
                     let mut alt_image = None;
@@ -255,11 +624,13 @@ impl MapView {
                             render_state.save();
 
                             // Exclude everything above this tile.
-                            let below_rect = Rect::new(0., point.y, size.width, size.height);
+                            let top = point.y.round();
+                            let below_rect = Rect::new(0., top, size.width, size.height);
                             render_state.clip_rect(below_rect, None, Some(false));
 
                             // Exclude everything to the left of this tile.
-                            let left_rect = Rect::new(0., point.y, point.x, point.y + tile_size);
+                            let bottom = (point.y + tile_size).round();
+                            let left_rect = Rect::new(0., top, point.x.round(), bottom);
                             render_state.clip_rect(left_rect, ClipOp::Difference, Some(false));
 
                             // Transform tile scale and position.
@@ -283,8 +654,13 @@ impl MapView {
             #[cfg(feature = "profiling")]
             profiling::scope!("draw_tile_image");
 
-            // Draw the scaled tile to the canvas.
-            let dst_rect = Rect::new(point.x, point.y, point.x + tile_size, point.y + tile_size);
+            // Draw the scaled tile to the canvas, snapping its edges to the pixel grid
+            // and covering its neighbors' edges.
+            let left = point.x.round();
+            let top = point.y.round();
+            let right = (point.x + tile_size).round() + TILE_OVERLAP;
+            let bottom = (point.y + tile_size).round() + TILE_OVERLAP;
+            let dst_rect = Rect::new(left, top, right, bottom);
             let sampling = SamplingOptions::new(FilterMode::Linear, MipmapMode::Linear);
             render_state.draw_image_rect_with_sampling_options(
                 image,
@@ -299,13 +675,67 @@ impl MapView {
                 render_state.restore();
             }
         }
+
+        self.draw_shimmer(config, render_state, &downloading_tiles);
+    }
+
+    /// Render a pulsing overlay over tiles which are still downloading.
+    fn draw_shimmer<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        tiles: &[Rect],
+    ) {
+        self.shimmering = !tiles.is_empty();
+        if !self.shimmering {
+            return;
+        }
+
+        // Animate the shimmer at a low framerate, instead of redrawing continuously.
+        if self.shimmer_timer.is_none() {
+            let timer = Timer::from_duration(SHIMMER_INTERVAL);
+            let token = self.event_loop.insert_source(timer, |_, _, state| {
+                let active = state.window.views.active() == View::Map;
+                let map = state.window.views.map();
+                if !active || !map.shimmering {
+                    map.shimmer_timer = None;
+                    return TimeoutAction::Drop;
+                }
+
+                map.dirty = true;
+                state.window.unstall();
+                TimeoutAction::ToDuration(SHIMMER_INTERVAL)
+            });
+            match token {
+                Ok(token) => self.shimmer_timer = Some(token),
+                Err(err) => error!("Failed to stage shimmer timer: {err}"),
+            }
+        }
+
+        // Get the current brightness of the pulse.
+        let period = SHIMMER_PERIOD.as_secs_f32();
+        let cycle = self.shimmer_start.elapsed().as_secs_f32() % period / period;
+        let brightness = (1. - (cycle * 2. * PI).cos()) / 2.;
+
+        let mut color = Color4f::from(config.colors.foreground);
+        color.a *= SHIMMER_ALPHA * brightness;
+        self.tile_paint.set_color4f(color, None);
+
+        for rect in tiles {
+            render_state.draw_rect(*rect, &self.tile_paint);
+        }
+
+        self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
     }
 
-    /// Render the attribution message
+    /// Render the attribution message.
+    ///
+    /// Returns the height of the attribution label.
     #[cfg_attr(feature = "profiling", profiling::function)]
-    fn draw_attribution<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) {
+    fn draw_attribution<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) -> f32 {
         if config.tiles.attribution.is_empty() {
-            return;
+            self.attribution_size = Size::default();
+            return 0.;
         }
 
         let fg = config.colors.foreground;
@@ -315,6 +745,115 @@ impl MapView {
         let mut paragraph = builder.build();
         paragraph.layout(self.size.width as f32 * self.scale as f32);
         paragraph.paint(render_state, Point::new(0., 0.));
+
+        // Store label dimensions for tap-through.
+        self.attribution_size = Size::new(paragraph.longest_line(), paragraph.height());
+
+        paragraph.height()
+    }
+
+    /// Check whether a physical point is on the attribution label.
+    fn attribution_contains(&self, point: Point<f64>) -> bool {
+        !self.attribution_url.is_empty()
+            && point.x >= 0.
+            && point.y >= 0.
+            && point.x < self.attribution_size.width as f64
+            && point.y < self.attribution_size.height as f64
+    }
+
+    /// Check whether a physical point is on the database failure banner.
+    fn db_banner_contains(&self, point: Point<f64>) -> bool {
+        let (top, bottom) = self.db_banner;
+        point.y >= top as f64 && point.y < bottom as f64
+    }
+
+    /// Check whether a physical point is on the tileserver rejection banner.
+    fn tileserver_banner_contains(&self, point: Point<f64>) -> bool {
+        let (top, bottom) = self.tileserver_banner;
+        point.y >= top as f64 && point.y < bottom as f64
+    }
+
+    /// Update the problems found in the configuration file.
+    pub fn set_config_warnings(&mut self, warnings: Vec<String>) {
+        self.dirty |= self.config_warnings != warnings;
+        self.config_warnings = warnings;
+    }
+
+    /// Show the location of the last saved trip summary image.
+    pub fn set_trip_summary_path(&mut self, path: PathBuf) {
+        self.trip_summary_path = Some(path);
+        self.dirty = true;
+    }
+
+    /// Check whether a physical point is on the trip summary banner.
+    fn trip_summary_banner_contains(&self, point: Point<f64>) -> bool {
+        let (top, bottom) = self.trip_summary_banner;
+        point.y >= top as f64 && point.y < bottom as f64
+    }
+
+    /// Check whether a physical point is on the configuration warning banners.
+    fn config_banner_contains(&self, point: Point<f64>) -> bool {
+        let (top, bottom) = self.config_banner;
+        point.y >= top as f64 && point.y < bottom as f64
+    }
+
+    /// Retry opening the database after it failed to open.
+    fn retry_db(&self) {
+        let favorites_tx = self.favorites_tx.clone();
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            if let Err(err) = db.retry().await {
+                error!("Failed to reopen database: {err}");
+                return;
+            }
+
+            // Reload data which was missing from the temporary database.
+            match db.favorites().await {
+                Ok(favorites) => {
+                    let _ = favorites_tx.send(favorites);
+                },
+                Err(err) => error!("Failed to load favorites: {err}"),
+            }
+        });
+    }
+
+    /// Open the tile source's attribution website.
+    fn open_attribution(&self) {
+        let url = self.attribution_url.clone();
+        tokio::spawn(async move {
+            if let Err(err) = dbus::open_uri::open_uri(&url).await {
+                error!("Failed to open attribution URL {url:?}: {err}");
+            }
+        });
+    }
+
+    /// Render a full-width status notice.
+    ///
+    /// Returns the height of the banner.
+    fn draw_banner<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        y: f32,
+        text: &str,
+    ) -> f32 {
+        let width = self.size.width as f32 * self.scale as f32;
+        let padding = BANNER_PADDING * self.scale as f32;
+
+        let options = TextOptions::new().ellipsize(false).align(TextAlign::Center);
+        let mut builder = render_state.paragraph(config.colors.foreground, 1., options);
+        builder.add_text(text);
+
+        let mut paragraph = builder.build();
+        paragraph.layout(width - 2. * padding);
+
+        let height = paragraph.height() + 2. * padding;
+        self.tile_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        render_state.draw_rect(Rect::new(0., y, width, y + height), &self.tile_paint);
+
+        paragraph.paint(render_state, Point::new(padding, y + padding));
+
+        height
     }
 
     /// Render active POI and GPS symbols.
@@ -328,10 +867,69 @@ impl MapView {
         let fill_size = INDICATOR_SIZE * self.scale as f32;
         let border_size = fill_size + INDICATOR_BORDER * self.scale as f32;
 
-        // Draw POI rectangle.
-        let poi_tile = self.poi.as_mut().map(|poi| poi.tile(self.cursor_tile.z));
-        let poi_point = poi_tile.and_then(|(tile, offset)| iter.screen_point(tile, offset));
-        if let Some(point) = poi_point {
+        // Draw hazard warning triangles below all other markers.
+        for hazard in self.hazard_alerts.hazards() {
+            let (tile, offset) = hazard.point.tile(self.position.tile.z);
+            let point: Point<f32> = match iter.screen_point(tile, offset) {
+                Some(point) => point.into(),
+                None => continue,
+            };
+
+            let height = fill_size * 0.866;
+            let points = [
+                (point + Point::new(0., -height / 2.)).into(),
+                (point + Point::new(-fill_size / 2., height / 2.)).into(),
+                (point + Point::new(fill_size / 2., height / 2.)).into(),
+            ];
+            let path = Path::polygon(&points, true, None, true);
+
+            // Draw border.
+            self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
+            self.tile_paint.set_stroke_width(border_size - fill_size);
+            self.tile_paint.set_stroke(true);
+            render_state.draw_path(&path, &self.tile_paint);
+
+            // Draw fill.
+            self.tile_paint.set_color4f(Color4f::from(config.colors.foreground), None);
+            self.tile_paint.set_stroke(false);
+            render_state.draw_path(&path, &self.tile_paint);
+        }
+
+        // Draw POI/route target rectangle, following the touch point while dragged.
+        let marker_point = match self.touch_state.action {
+            TouchAction::MarkerDrag(_) => {
+                let point = self.transform.map_to_tiles(self.touch_state.marker_point.into());
+                Some(Point::new(point.x.round() as i32, point.y.round() as i32))
+            },
+            _ => {
+                let marker_tile = self.marker_tile().map(|(_, tile)| tile);
+                marker_tile.and_then(|(tile, offset)| iter.screen_point(tile, offset))
+            },
+        };
+
+        // Advance the marker highlight, even while the marker is offscreen.
+        let pulse = self.marker_pulse.as_mut().map(|pulse| pulse.progress(Instant::now()));
+        if self.marker_pulse.as_ref().is_some_and(Tween::is_done) {
+            self.marker_pulse = None;
+        }
+
+        if let Some(point) = marker_point {
+            // Draw highlight ring, expanding from the marker while fading out.
+            if let Some(pulse) = pulse {
+                let min_radius = border_size / 2.;
+                let max_radius = MARKER_PULSE_RADIUS * self.scale as f32;
+                let radius = min_radius + (max_radius - min_radius) * pulse as f32;
+
+                let mut color = Color4f::from(config.colors.highlight);
+                color.a *= 1. - pulse as f32;
+                self.tile_paint.set_color4f(color, None);
+                self.tile_paint.set_stroke_width(MARKER_PULSE_WIDTH * self.scale as f32);
+                self.tile_paint.set_stroke(true);
+                let center: Point<f32> = point.into();
+                render_state.draw_circle(center, radius, &self.tile_paint);
+                self.tile_paint.set_stroke(false);
+            }
+
             // Draw border.
             self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
             let rect = Rect::new(
@@ -354,7 +952,7 @@ impl MapView {
         }
 
         // Draw GPS circle/arrow.
-        let gps_tile = self.gps.as_mut().map(|gps| gps.tile(self.cursor_tile.z));
+        let gps_tile = self.gps.as_mut().map(|gps| gps.tile(self.position.tile.z));
         let gps_point = gps_tile.and_then(|(tile, offset)| iter.screen_point(tile, offset));
         if let Some(point) = gps_point {
             let point: Point<f32> = point.into();
@@ -393,188 +991,526 @@ impl MapView {
         }
     }
 
-    /// Render active route.
+    /// Render the POI details callout above its marker.
     #[cfg_attr(feature = "profiling", profiling::function)]
-    fn draw_route<'a>(
+    fn draw_poi_callout<'a>(
         &mut self,
         config: &Config,
         render_state: &mut RenderState<'a>,
         iter: &TileIter,
     ) {
-        let route = match &mut self.route {
-            Some(route) => route,
+        self.callout_visible = false;
+
+        // Hide the callout while its marker is moved.
+        if let TouchAction::MarkerDrag(_) = self.touch_state.action {
+            return;
+        }
+
+        let (poi, details) = match (&mut self.poi, &self.poi_details) {
+            (Some(poi), Some(details)) => (poi, details),
             _ => return,
         };
+        let (tile, offset) = poi.tile(self.position.tile.z);
+        let marker_point = self.transform.tiles_to_screen(iter.tile_point(tile, offset).into());
+        let is_favorite = self.favorites.iter().any(|favorite| favorite.point == poi.point);
 
-        let size = (self.size * self.scale).into();
-
+        // Hide the callout when its marker is offscreen.
+        let size: Size<f32> = (self.size * self.scale).into();
+        if marker_point.x < 0.
+            || marker_point.y < 0.
+            || marker_point.x >= size.width
+            || marker_point.y >= size.height
         {
-            #[cfg(feature = "profiling")]
-            profiling::scope!("draw_route_segments");
-
-            let mut path = PathBuilder::new();
-            let route_len = route.len();
-            let mut last_node = None;
-            let mut skipped = true;
-
-            // Add path segments for all visible route sections.
-            for (i, node) in route.points_mut().iter_mut().enumerate() {
-                // Get screen position for the node.
-                let (tile, offset) = node.tile(self.cursor_tile.z);
-                let end_point: Point<f32> = iter.tile_point(tile, offset).into();
-
-                // For the first node, just initialize `last_node`.
-                let start_point = match last_node {
-                    Some(start_point) => start_point,
-                    None => {
-                        last_node = Some(end_point);
-                        continue;
-                    },
-                };
+            return;
+        }
 
-                // Omit point if it is too close to the last one, unless it's the final point.
-                // This also skips the `last_node` update, to ensure the path is consistent.
-                let delta = start_point - end_point;
-                if i + 1 < route_len && delta.x.hypot(delta.y) < ROUTE_RESOLUTION {
-                    continue;
-                }
+        let padding = (CALLOUT_PADDING * self.scale as f32).round();
+        let border = (BUTTON_BORDER * self.scale).round() as f32;
+        let indicator_size = (INDICATOR_SIZE + INDICATOR_BORDER) * self.scale as f32;
+        let button_size = Self::callout_button_size(self.scale);
+        let button_width = button_size.width as f32;
 
-                // Draw visible route segments, or break the path.
-                if rect_intersects_line(Point::default(), size, start_point, end_point) {
-                    if mem::take(&mut skipped) {
-                        path.move_to(start_point);
-                    }
-                    path.line_to(end_point);
-                } else {
-                    skipped = true;
-                }
+        // Layout title and address text.
 
-                last_node = Some(end_point);
-            }
+        let width = (CALLOUT_MAX_WIDTH * self.scale as f32).min(size.width - 2. * padding);
+        let text_width = width - 4. * padding - 2. * button_width;
+
+        let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
+        builder.add_text(&details.title);
+
+        let mut title_paragraph = builder.build();
+        title_paragraph.layout(text_width);
+        let title_height = title_paragraph.height();
+
+        let fg = config.colors.alt_foreground;
+        let mut builder = render_state.paragraph(fg, CALLOUT_ADDRESS_FONT_SIZE, None);
+        builder.add_text(&details.address);
 
-            // Ensure route color is up to date.
-            self.route_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+        let mut address_paragraph = builder.build();
+        address_paragraph.layout(text_width);
+        let address_height = address_paragraph.height();
 
-            // Draw the entire path.
-            render_state.draw_path(&path.detach(), &self.route_paint);
+        // Place callout above the marker, or below it if there is no space.
+
+        let height = (title_height + address_height).max(button_size.height as f32) + 2. * padding;
+        let x = (marker_point.x - width / 2.).clamp(padding, size.width - padding - width);
+        let mut y = marker_point.y - indicator_size / 2. - padding - height;
+        if y < 0. {
+            y = marker_point.y + indicator_size / 2. + padding;
         }
 
-        // Draw instructions for GPS routes.
-        if route.has_gps_origin() {
-            #[cfg(feature = "profiling")]
-            profiling::scope!("draw_route_instruction");
+        // Draw callout border and background.
 
-            let outside_padding = (INSTRUCTION_OUTSIDE_PADDING * self.scale as f32).round();
-            let inside_padding = (INSTRUCTION_INSIDE_PADDING * self.scale as f32).round();
-            let border = (INSTRUCTION_BORDER * self.scale).round() as f32;
-            let box_width = size.width - 2. * outside_padding;
-            let text_width = box_width - 2. * inside_padding - 2. * border;
-            let fg = config.colors.foreground;
+        let mut rect = Rect::new(x - border, y - border, x + width + border, y + height + border);
+        self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
+        render_state.draw_rect(rect, &self.tile_paint);
 
-            let instruction = route.instruction();
+        rect = Rect::new(x, y, x + width, y + height);
+        self.tile_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        render_state.draw_rect(rect, &self.tile_paint);
 
-            // Layout all text, to determine the box height.
+        // Draw text vertically centered.
 
-            // Layout instruction text.
+        let text_y = y + (height - title_height - address_height) / 2.;
+        title_paragraph.paint(render_state, Point::new(x + padding, text_y));
+        address_paragraph.paint(render_state, Point::new(x + padding, text_y + title_height));
 
-            let text_options = Some(TextOptions::new().ellipsize(false));
-            let mut builder = render_state.paragraph(fg, INSTRUCTION_FONT_SIZE, text_options);
-            builder.add_text(&*instruction.text);
+        // Draw action buttons.
 
-            let mut instruction_paragraph = builder.build();
-            instruction_paragraph.layout(text_width);
-            let instruction_height = instruction_paragraph.height();
+        let button_y = (y + (height - button_size.height as f32) / 2.).round() as i32;
+        let favorite_x = (x + width - padding - button_width).round() as i32;
+        let route_x = favorite_x - (padding + button_width).round() as i32;
 
-            // Layout travel time text.
+        let favorite_svg = if is_favorite { Svg::StarFilled } else { Svg::Star };
+        self.callout_favorite_button.set_svg(favorite_svg);
+        self.callout_favorite_button.set_disabled(self.db.read_only());
+        self.callout_favorite_button.set_point(Point::new(favorite_x, button_y));
+        self.callout_favorite_button.draw(render_state, config.colors.background);
 
-            let hours = instruction.time / 3600;
-            let minutes = (instruction.time % 3600 + 30) / 60;
-            let time_text = format!("{hours:0>2}:{minutes:0>2}");
+        self.callout_route_button.set_point(Point::new(route_x, button_y));
+        self.callout_route_button.draw(render_state, config.colors.background);
 
-            let mut builder = render_state.paragraph(fg, INSTRUCTION_ALT_FONT_SIZE, None);
-            builder.add_text(&time_text);
+        self.callout_visible = true;
+    }
 
-            let mut time_paragraph = builder.build();
-            time_paragraph.layout(text_width);
-            let time_height = time_paragraph.height();
+    /// Render active route.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw_route<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        iter: &TileIter,
+    ) {
+        let route = match &mut self.route {
+            Some(route) => route,
+            _ => return,
+        };
 
-            // Layout travel distance text.
+        let size = iter.screen_size().into();
+        let zoom = self.position.tile.z;
 
-            let mut distance = String::with_capacity("X.XX km".len());
-            view::format_distance(&mut distance, instruction.length);
+        let mut path = PathBuilder::new();
+        let mut last_node = None;
+        let mut skipped = true;
 
-            let text_options = Some(TextOptions::new().align(TextAlign::Right));
-            let mut builder = render_state.paragraph(fg, INSTRUCTION_ALT_FONT_SIZE, text_options);
-            builder.add_text(&distance);
+        // Add path segments for all visible route sections.
+        route.render_points(zoom, |node, is_last| {
+            // Get screen position for the node.
+            let (tile, offset) = node.tile(zoom);
+            let end_point: Point<f32> = iter.tile_point(tile, offset).into();
 
-            let mut distance_paragraph = builder.build();
-            distance_paragraph.layout(text_width);
+            // For the first node, just initialize `last_node`.
+            let start_point = match last_node {
+                Some(start_point) => start_point,
+                None => {
+                    last_node = Some(end_point);
+                    return;
+                },
+            };
 
-            // Calculate instruction message box height.
-            let box_height = instruction_height + time_height + 3. * inside_padding + 2. * border;
+            // Omit point if it is too close to the last one, unless it's the final point.
+            // This also skips the `last_node` update, to ensure the path is consistent.
+            let delta = start_point - end_point;
+            if !is_last && delta.x.hypot(delta.y) < ROUTE_RESOLUTION {
+                return;
+            }
 
-            // Draw border around instruction message box.
-            let mut rect = Rect::new(
-                outside_padding,
-                outside_padding,
-                outside_padding + box_width,
-                outside_padding + box_height,
-            );
-            self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
-            render_state.draw_rect(rect, &self.tile_paint);
+            // Draw visible route segments, or break the path.
+            if rect_intersects_line(Point::default(), size, start_point, end_point) {
+                if mem::take(&mut skipped) {
+                    path.move_to(start_point);
+                }
+                path.line_to(end_point);
+            } else {
+                skipped = true;
+            }
 
-            // Draw instruction message box background.
-            rect.left += border;
-            rect.top += border;
-            rect.right -= border;
-            rect.bottom -= border;
-            self.tile_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
-            render_state.draw_rect(rect, &self.tile_paint);
+            last_node = Some(end_point);
+        });
 
-            // Draw all paragraphs.
+        // Ensure route color is up to date.
+        self.route_paint.set_color4f(Color4f::from(config.colors.highlight), None);
 
-            let mut text_origin = Point::new(rect.left + inside_padding, rect.top + inside_padding);
-            instruction_paragraph.paint(render_state, text_origin);
-            text_origin.y += inside_padding + instruction_height;
-            time_paragraph.paint(render_state, text_origin);
-            distance_paragraph.paint(render_state, text_origin);
-        }
+        // Draw the entire path.
+        render_state.draw_path(&path.detach(), &self.route_paint);
     }
 
-    /// Render buttons.
+    /// Render the number of recorded track points as translucent overlay.
     #[cfg_attr(feature = "profiling", profiling::function)]
-    fn draw_buttons<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) {
-        let search_point: Point<f32> = Self::search_button_point(self.size, self.scale).into();
-        let button_size: Size<f32> = Self::button_size(self.scale).into();
-        let button_border = (BUTTON_BORDER * self.scale).round() as f32;
-        let bg = config.colors.background;
+    fn draw_heatmap<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        iter: &TileIter,
+    ) {
+        // Request cells matching the current zoom level.
+        self.load_heatmap();
 
-        // Get visible buttons with their respective borders.
-        let button_points: &mut [_] = match self.gps {
-            Some(_) if self.gps_locked => {
-                let gps_point: Point<f32> = Self::gps_button_point(self.size, self.scale).into();
+        let heatmap = match &self.heatmap {
+            Some(heatmap) => heatmap,
+            None => return,
+        };
 
-                let gps_border = (LOCKED_GPS_BORDER * self.scale).round() as f32;
-                let search = (&mut self.search_button, search_point, button_border, bg);
-                let gps = (&mut self.gps_button, gps_point, gps_border, config.colors.highlight);
-                &mut [search, gps]
-            },
-            Some(_) => {
-                let gps_point: Point<f32> = Self::gps_button_point(self.size, self.scale).into();
+        let zoom = self.position.tile.z;
+        let screen_size: Size<f32> = iter.screen_size().into();
+        let max_weight = (heatmap.max_count as f32).ln_1p();
+        let (min_alpha, max_alpha) = HEATMAP_ALPHA;
+
+        let mut color = Color4f::from(config.colors.highlight);
+        for cell in &heatmap.cells {
+            let (tile, offset, size) = heatmap_cell_tile(cell, heatmap.zoom, zoom);
+            let point: Point<f32> = iter.tile_point(tile, offset).into();
+            let size = size * iter.tile_size() as f32;
+
+            // Skip cells outside the visible area.
+            if point.x + size < 0.
+                || point.y + size < 0.
+                || point.x >= screen_size.width
+                || point.y >= screen_size.height
+            {
+                continue;
+            }
 
-                &mut [
-                    (&mut self.search_button, search_point, button_border, bg),
-                    (&mut self.gps_button, gps_point, button_border, bg),
-                ]
-            },
-            None => &mut [(&mut self.search_button, search_point, button_border, bg)],
-        };
+            // Scale opacity logarithmically, to keep rarely visited areas visible.
+            let weight = (cell.count as f32).ln_1p() / max_weight;
+            color.a = min_alpha + (max_alpha - min_alpha) * weight;
+            self.tile_paint.set_color4f(color, None);
 
-        // Draw all buttons.
-        for (button, point, border_size, border_color) in button_points {
-            let search_left = point.x - *border_size;
-            let search_top = point.y - *border_size;
-            let search_right = point.x + button_size.width + *border_size;
+            let rect = Rect::new(point.x, point.y, point.x + size, point.y + size);
+            render_state.draw_rect(rect, &self.tile_paint);
+        }
+    }
+
+    /// Render the played back track and its current position.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw_playback_track<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        iter: &TileIter,
+    ) {
+        let playback = match &mut self.playback {
+            Some(playback) => playback,
+            None => return,
+        };
+
+        let size = iter.screen_size().into();
+        let zoom = self.position.tile.z;
+
+        let mut path = PathBuilder::new();
+        let mut last_node = None;
+        let mut skipped = true;
+
+        // Add path segments for all visible track sections.
+        let last_index = playback.render_points.len().saturating_sub(1);
+        for (i, node) in playback.render_points.iter_mut().enumerate() {
+            let (tile, offset) = node.tile(zoom);
+            let end_point: Point<f32> = iter.tile_point(tile, offset).into();
+
+            let start_point = match last_node {
+                Some(start_point) => start_point,
+                None => {
+                    last_node = Some(end_point);
+                    continue;
+                },
+            };
+
+            // Omit point if it is too close to the last one, unless it's the final point.
+            let delta = start_point - end_point;
+            if i != last_index && delta.x.hypot(delta.y) < ROUTE_RESOLUTION {
+                continue;
+            }
+
+            if rect_intersects_line(Point::default(), size, start_point, end_point) {
+                if mem::take(&mut skipped) {
+                    path.move_to(start_point);
+                }
+                path.line_to(end_point);
+            } else {
+                skipped = true;
+            }
+
+            last_node = Some(end_point);
+        }
+
+        self.track_paint.set_color4f(Color4f::from(config.colors.alt_foreground), None);
+        render_state.draw_path(&path.detach(), &self.track_paint);
+
+        // Draw the position at the current playback time.
+        let position = match track::position_at(&playback.points, playback.time) {
+            Some(position) => position,
+            None => return,
+        };
+        let (tile, offset) = position.tile(zoom);
+        if let Some(point) = iter.screen_point(tile, offset) {
+            let point: Point<f32> = point.into();
+            let fill_size = INDICATOR_SIZE * self.scale as f32;
+            let border_size = fill_size + INDICATOR_BORDER * self.scale as f32;
+
+            // Draw border.
+            self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
+            render_state.draw_circle(point, border_size / 2., &self.tile_paint);
+
+            // Draw fill.
+            self.tile_paint.set_color4f(Color4f::from(config.colors.alt_foreground), None);
+            render_state.draw_circle(point, fill_size / 2., &self.tile_paint);
+        }
+    }
+
+    /// Render the track playback time slider.
+    fn draw_playback_slider<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) {
+        let playback = match &self.playback {
+            Some(playback) => playback,
+            None => return,
+        };
+
+        let point: Point<f32> = Self::playback_slider_point(self.size, self.scale).into();
+        let size: Size<f32> = Self::playback_slider_size(self.size, self.scale).into();
+        let border = (BUTTON_BORDER * self.scale).round() as f32;
+        let padding = BANNER_PADDING * self.scale as f32;
+
+        // Draw background with the same border as the buttons.
+        self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
+        let border_rect = Rect::new(
+            point.x - border,
+            point.y - border,
+            point.x + size.width + border,
+            point.y + size.height + border,
+        );
+        render_state.draw_rect(border_rect, &self.tile_paint);
+        self.tile_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        let rect = Rect::new(point.x, point.y, point.x + size.width, point.y + size.height);
+        render_state.draw_rect(rect, &self.tile_paint);
+
+        // Draw the elapsed and total playback time.
+        let mut label = String::with_capacity("0:00:00 / 0:00:00".len());
+        track::format_duration(&mut label, playback.time as i64);
+        label.push_str(" / ");
+        track::format_duration(&mut label, playback.duration as i64);
+
+        let options = TextOptions::new().align(TextAlign::Center);
+        let fg = config.colors.foreground;
+        let mut builder = render_state.paragraph(fg, SLIDER_FONT_SIZE, options);
+        builder.add_text(&label);
+        let mut paragraph = builder.build();
+        paragraph.layout(size.width - 2. * padding);
+        paragraph.paint(render_state, Point::new(point.x + padding, point.y + padding / 2.));
+
+        // Draw the bar, filled up to the current playback time.
+        let (bar_start, bar_end) = Self::playback_bar_range(self.size, self.scale);
+        let (bar_start, bar_end) = (bar_start as f32, bar_end as f32);
+        let bar_height = SLIDER_BAR_HEIGHT * self.scale as f32;
+        let bar_y = point.y + size.height - padding - bar_height / 2.;
+        let progress = playback.progress() as f32;
+        let progress_x = bar_start + (bar_end - bar_start) * progress;
+
+        self.tile_paint.set_color4f(Color4f::from(config.colors.foreground), None);
+        let rect = Rect::new(progress_x, bar_y - bar_height / 2., bar_end, bar_y + bar_height / 2.);
+        render_state.draw_rect(rect, &self.tile_paint);
+
+        self.tile_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+        let rect =
+            Rect::new(bar_start, bar_y - bar_height / 2., progress_x, bar_y + bar_height / 2.);
+        render_state.draw_rect(rect, &self.tile_paint);
+
+        let knob_radius = INDICATOR_SIZE * self.scale as f32 / 2.;
+        render_state.draw_circle(Point::new(progress_x, bar_y), knob_radius, &self.tile_paint);
+    }
+
+    /// Render the outline of the highlighted region.
+    fn draw_coverage<'a>(
+        &mut self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        iter: &TileIter,
+    ) {
+        let bounds = match self.coverage {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        // Get screen position of the north-west and south-east corners.
+        let zoom = self.position.tile.z;
+        let (tile, offset) = GeoPoint::new(bounds.max_lat, bounds.min_lon).tile(zoom);
+        let top_left: Point<f32> = iter.tile_point(tile, offset).into();
+        let (tile, offset) = GeoPoint::new(bounds.min_lat, bounds.max_lon).tile(zoom);
+        let bottom_right: Point<f32> = iter.tile_point(tile, offset).into();
+
+        let rect = Rect::new(top_left.x, top_left.y, bottom_right.x, bottom_right.y);
+
+        self.coverage_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+        self.coverage_paint.set_stroke_width(COVERAGE_WIDTH * self.scale as f32);
+        render_state.draw_rect(rect, &self.coverage_paint);
+    }
+
+    /// Render navigation instructions of the active route.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw_route_instruction<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) {
+        let route = match &self.route {
+            Some(route) if route.has_gps_origin() => route,
+            _ => return,
+        };
+
+        let size: Size<f32> = (self.size * self.scale).into();
+        let outside_padding = (INSTRUCTION_OUTSIDE_PADDING * self.scale as f32).round();
+        let inside_padding = (INSTRUCTION_INSIDE_PADDING * self.scale as f32).round();
+        let border = (INSTRUCTION_BORDER * self.scale).round() as f32;
+        let box_width = size.width - 2. * outside_padding;
+        let text_width = box_width - 2. * inside_padding - 2. * border;
+        let fg = config.colors.foreground;
+
+        let instruction = route.instruction();
+
+        // Layout all text, to determine the box height.
+
+        // Layout instruction text.
+
+        let text_options = Some(TextOptions::new().ellipsize(false));
+        let mut builder = render_state.paragraph(fg, INSTRUCTION_FONT_SIZE, text_options);
+        builder.add_text(&*instruction.text);
+
+        let mut instruction_paragraph = builder.build();
+        instruction_paragraph.layout(text_width);
+        let instruction_height = instruction_paragraph.height();
+
+        // Layout travel time text.
+
+        let hours = instruction.time / 3600;
+        let minutes = (instruction.time % 3600 + 30) / 60;
+        let time_text = format!("{hours:0>2}:{minutes:0>2}");
+
+        let mut builder = render_state.paragraph(fg, INSTRUCTION_ALT_FONT_SIZE, None);
+        builder.add_text(&time_text);
+
+        let mut time_paragraph = builder.build();
+        time_paragraph.layout(text_width);
+        let time_height = time_paragraph.height();
+
+        // Layout travel distance text.
+
+        let mut distance = String::with_capacity("X.XX km".len());
+        view::format_distance(&mut distance, instruction.length, config.navigation.units);
+
+        let text_options = Some(TextOptions::new().align(TextAlign::Right));
+        let mut builder = render_state.paragraph(fg, INSTRUCTION_ALT_FONT_SIZE, text_options);
+        builder.add_text(&distance);
+
+        let mut distance_paragraph = builder.build();
+        distance_paragraph.layout(text_width);
+
+        // Calculate instruction message box height.
+        let box_height = instruction_height + time_height + 3. * inside_padding + 2. * border;
+
+        // Draw border around instruction message box.
+        let mut rect = Rect::new(
+            outside_padding,
+            outside_padding,
+            outside_padding + box_width,
+            outside_padding + box_height,
+        );
+        self.tile_paint.set_color4f(Color4f::from(config.colors.background), None);
+        render_state.draw_rect(rect, &self.tile_paint);
+
+        // Draw instruction message box background.
+        rect.left += border;
+        rect.top += border;
+        rect.right -= border;
+        rect.bottom -= border;
+        self.tile_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        render_state.draw_rect(rect, &self.tile_paint);
+
+        // Draw all paragraphs.
+
+        let mut text_origin = Point::new(rect.left + inside_padding, rect.top + inside_padding);
+        instruction_paragraph.paint(render_state, text_origin);
+        text_origin.y += inside_padding + instruction_height;
+        time_paragraph.paint(render_state, text_origin);
+        distance_paragraph.paint(render_state, text_origin);
+    }
+
+    /// Render buttons.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw_buttons<'a>(&mut self, config: &Config, render_state: &mut RenderState<'a>) {
+        let search_point: Point<f32> = Self::search_button_point(self.size, self.scale).into();
+        let button_size: Size<f32> = Self::button_size(self.scale).into();
+        let button_border = (BUTTON_BORDER * self.scale).round() as f32;
+        let bg = config.colors.background;
+
+        let is_navigating = self.is_navigating();
+
+        // Get visible buttons with their respective borders.
+        let mut button_points: SmallVec<[_; 8]> = SmallVec::new();
+        let search_button =
+            if self.overlay_visible() { &mut self.close_button } else { &mut self.search_button };
+        button_points.push((search_button, search_point, button_border, bg));
+        if self.input_config.zoom_buttons {
+            let zoom_out_point = Self::zoom_out_button_point(self.size, self.scale).into();
+            button_points.push((&mut self.zoom_out_button, zoom_out_point, button_border, bg));
+
+            let zoom_in_point = Self::zoom_in_button_point(self.size, self.scale).into();
+            button_points.push((&mut self.zoom_in_button, zoom_in_point, button_border, bg));
+        }
+        match self.gps {
+            Some(_) if self.gps_locked => {
+                let gps_point: Point<f32> = Self::gps_button_point(self.size, self.scale).into();
+
+                let gps_border = (LOCKED_GPS_BORDER * self.scale).round() as f32;
+                let gps = (&mut self.gps_button, gps_point, gps_border, config.colors.highlight);
+                button_points.push(gps);
+            },
+            Some(_) => {
+                let gps_point: Point<f32> = Self::gps_button_point(self.size, self.scale).into();
+                button_points.push((&mut self.gps_button, gps_point, button_border, bg));
+            },
+            None => (),
+        }
+        if is_navigating {
+            let camera_point = Self::camera_button_point(self.size, self.scale).into();
+            button_points.push((&mut self.camera_button, camera_point, button_border, bg));
+        }
+        if self.interaction_locked {
+            let lock_point = Self::lock_button_point(self.size, self.scale).into();
+            let lock_border = (LOCKED_GPS_BORDER * self.scale).round() as f32;
+            let lock = (&mut self.lock_button, lock_point, lock_border, config.colors.highlight);
+            button_points.push(lock);
+        } else if is_navigating {
+            let lock_point = Self::lock_button_point(self.size, self.scale).into();
+            button_points.push((&mut self.lock_button, lock_point, button_border, bg));
+
+            let along_point = Self::along_route_button_point(self.size, self.scale).into();
+            button_points.push((&mut self.along_route_button, along_point, button_border, bg));
+
+            if self.mini_window_supported {
+                let mini_point = Self::mini_window_button_point(self.size, self.scale).into();
+                button_points.push((&mut self.mini_window_button, mini_point, button_border, bg));
+            }
+        }
+
+        // Draw all buttons.
+        for (button, point, border_size, border_color) in button_points {
+            let search_left = point.x - *border_size;
+            let search_top = point.y - *border_size;
+            let search_right = point.x + button_size.width + *border_size;
             let search_bottom = point.y + button_size.height + *border_size;
             let border_rect = Rect::new(search_left, search_top, search_right, search_bottom);
 
@@ -587,31 +1523,255 @@ impl MapView {
 
     /// Get the current center point of the map.
     pub fn center_point(&self) -> GeoPoint {
-        GeoPoint::from_tile(self.cursor_tile, self.cursor_offset)
+        self.position.center()
+    }
+
+    /// Get the map tile cache.
+    pub fn tiles(&self) -> &Tiles {
+        &self.tiles
+    }
+
+    /// Get the mutable map tile cache.
+    pub fn tiles_mut(&mut self) -> &mut Tiles {
+        &mut self.tiles
     }
 
     /// Get the current tile zoom level.
     pub fn zoom(&self) -> u8 {
-        self.cursor_tile.z
+        self.position.tile.z
+    }
+
+    /// Center the map on the GPS location.
+    ///
+    /// If the GPS location is already centered, this will zoom in first and
+    /// then toggle the GPS lock.
+    pub fn center_gps(&mut self) {
+        let point = match self.gps {
+            Some(RenderGeoPoint { point, .. }) => point,
+            None => return,
+        };
+        let (tile, offset) = point.tile(self.position.tile.z);
+
+        if self.position.offset != offset || self.position.tile != tile {
+            self.animate_to(point, self.render_zoom(), FLY_TO_DURATION);
+        } else if self.position.tile.z != GPS_ZOOM {
+            self.animate_to(point, GPS_ZOOM as f64, FLY_TO_DURATION);
+        } else {
+            self.gps_locked = !self.gps_locked;
+        }
+        self.dirty = true;
+    }
+
+    /// Change the zoom level by one step around the map center.
+    pub fn step_zoom(&mut self, zoom_in: bool) {
+        // Pause automatic navigation zoom while the user is zooming.
+        self.camera.override_zoom(Instant::now());
+
+        // Continue from the target of running animations, so quick steps add up.
+        let (center, zoom) = match &self.fly_to {
+            Some(fly_to) => (fly_to.end, fly_to.end_zoom),
+            None => (self.center_point(), self.render_zoom()),
+        };
+
+        let step = self.input_config.zoom_step;
+        let zoom = if zoom_in { zoom + step } else { zoom - step };
+        self.animate_to(center, zoom, ZOOM_STEP_DURATION);
     }
 
     /// Go to a specific coordinate.
     pub fn goto(&mut self, point: GeoPoint, zoom: Option<u8>) {
-        let tile_zoom = zoom.unwrap_or(self.cursor_tile.z);
-        let (cursor_tile, cursor_offset) = point.tile(tile_zoom);
-        if self.cursor_tile != cursor_tile || self.cursor_offset != cursor_offset {
+        // Complete running animations, to retain their target zoom level.
+        if let Some(fly_to) = self.fly_to.take() {
+            self.jump_to(fly_to.end, fly_to.end_zoom);
+        }
+
+        let tile_zoom = zoom.unwrap_or(self.position.tile.z).clamp(self.min_zoom, self.max_zoom);
+        let (tile, offset) = point.tile(tile_zoom);
+        if self.position.tile != tile || self.position.offset != offset {
             // Reset sub-tile zoom offset, if zoom level is changed.
             if zoom.is_some() {
-                self.cursor_zoom = 0.;
+                self.position.zoom = 0.;
             }
 
-            self.cursor_tile = cursor_tile;
-            self.cursor_offset = cursor_offset;
+            self.position.tile = tile;
+            self.position.offset = offset;
             self.gps_locked = false;
             self.dirty = true;
         }
     }
 
+    /// Animate the map to a specific coordinate.
+    pub fn fly_to(&mut self, point: GeoPoint, zoom: Option<u8>) {
+        let zoom = zoom.map_or(self.render_zoom(), |zoom| zoom as f64);
+        self.animate_to(point, zoom, FLY_TO_DURATION);
+        self.gps_locked = false;
+    }
+
+    /// Start animating the map center and zoom level.
+    ///
+    /// Distant targets are shown immediately instead.
+    fn animate_to(&mut self, point: GeoPoint, zoom: f64, duration: Duration) {
+        let zoom = zoom.clamp(self.min_zoom as f64, self.max_zoom as f64);
+        let start = self.center_point();
+        let start_zoom = self.render_zoom();
+
+        // Get the distance between both points at the lower zoom level.
+        let tile_zoom = start_zoom.min(zoom).floor() as u8;
+        let (start_tile, start_offset) = start.tile(tile_zoom);
+        let (end_tile, end_offset) = point.tile(tile_zoom);
+        let distance_x = (end_tile.x as f64 - start_tile.x as f64) * TILE_SIZE as f64
+            + (end_offset.x - start_offset.x) as f64;
+        let distance_y = (end_tile.y as f64 - start_tile.y as f64) * TILE_SIZE as f64
+            + (end_offset.y - start_offset.y) as f64;
+
+        let size = self.size * self.scale;
+        if distance_x.abs() > size.width as f64 * FLY_TO_MAX_SCREENS
+            || distance_y.abs() > size.height as f64 * FLY_TO_MAX_SCREENS
+        {
+            self.fly_to = None;
+            self.jump_to(point, zoom);
+            return;
+        }
+
+        let tween = Tween::new(duration, Easing::EaseInOut);
+        self.fly_to = Some(FlyTo { tween, start, start_zoom, end: point, end_zoom: zoom });
+        self.dirty = true;
+    }
+
+    /// Advance the active map movement animation.
+    fn apply_fly_to(&mut self) {
+        let fly_to = match &mut self.fly_to {
+            Some(fly_to) => fly_to,
+            None => return,
+        };
+
+        let progress = fly_to.tween.progress(Instant::now());
+        let lat = fly_to.start.lat + (fly_to.end.lat - fly_to.start.lat) * progress;
+        let lon = fly_to.start.lon + (fly_to.end.lon - fly_to.start.lon) * progress;
+        let zoom = fly_to.start_zoom + (fly_to.end_zoom - fly_to.start_zoom) * progress;
+
+        if fly_to.tween.is_done() {
+            self.fly_to = None;
+        }
+
+        self.jump_to(GeoPoint::new(lat, lon), zoom);
+    }
+
+    /// Center the map on a coordinate at a fractional zoom level.
+    fn jump_to(&mut self, point: GeoPoint, zoom: f64) {
+        let tile_zoom = zoom.round().clamp(self.min_zoom as f64, self.max_zoom as f64);
+        let zoom_offset = (zoom - tile_zoom).clamp(-0.5, 0.5);
+        self.position = MapPosition::new(point, tile_zoom as u8);
+        self.position.zoom = zoom_offset;
+        self.dirty = true;
+    }
+
+    /// Get the current zoom level, including the sub-tile zoom.
+    fn render_zoom(&self) -> f64 {
+        self.position.zoom_level()
+    }
+
+    /// Get the marker which can be moved by the user.
+    ///
+    /// This is the POI if one is set, otherwise the active route's target.
+    fn marker_tile(&mut self) -> Option<(Marker, (TileIndex, Point))> {
+        let z = self.position.tile.z;
+
+        if let Some(poi) = &mut self.poi {
+            return Some((Marker::Poi, poi.tile(z)));
+        }
+
+        let target = self.route.as_mut()?.points_mut().last_mut()?;
+        Some((Marker::RouteTarget, target.tile(z)))
+    }
+
+    /// Get the marker at a physical location in map coordinates.
+    fn marker_at(&mut self, point: Point<f64>) -> Option<Marker> {
+        let size = self.size * self.scale;
+        let iter = self.position.tiles(size);
+
+        let (marker, marker_tile) = self.marker_tile()?;
+        let radius = MARKER_TOUCH_RADIUS * self.scale;
+        marker::touches(&iter, marker_tile, point, radius).then_some(marker)
+    }
+
+    /// Convert a physical location in map coordinates to a geographic point.
+    ///
+    /// Map coordinates match screen coordinates unless the map is rotated or
+    /// tilted by the navigation camera.
+    fn screen_geo_point(&self, mut point: Point<f64>) -> GeoPoint {
+        // Convert point from screen origin to center origin.
+        let size = self.size * self.scale;
+        point.x -= size.width as f64 / 2.;
+        point.y -= size.height as f64 / 2.;
+
+        let (tile_index, offset) = self.position.center_point_tile(point);
+        GeoPoint::from_tile(tile_index, offset)
+    }
+
+    /// Move a marker to a new location.
+    fn move_marker(&mut self, marker: Marker, point: GeoPoint) {
+        match marker {
+            Marker::Poi => {
+                self.poi = Some(point.into());
+                self.dirty = true;
+            },
+            // Recalculate the route to the new target.
+            Marker::RouteTarget => {
+                let route = match &mut self.route {
+                    Some(route) => route,
+                    None => return,
+                };
+
+                let origin = match route.start() {
+                    Some(_) if route.has_gps_origin() => RouteOrigin::Gps,
+                    Some(start) => start.into(),
+                    None => return,
+                };
+                let mode = route.mode();
+
+                self.event_loop.insert_idle(move |state| {
+                    state.window.views.search().route(origin, point, mode);
+                });
+            },
+        }
+    }
+
+    /// Toggle the favorite status of the current POI.
+    fn toggle_favorite(&mut self) {
+        let (point, details) = match (&self.poi, &self.poi_details) {
+            (Some(poi), Some(details)) if !self.db.read_only() => (poi.point, details),
+            _ => return,
+        };
+
+        // Update local favorites and persist the change in the background.
+        let db = self.db.clone();
+        match self.favorites.iter().position(|favorite| favorite.point == point) {
+            Some(index) => {
+                self.favorites.remove(index);
+                tokio::spawn(async move {
+                    if let Err(err) = db.delete_favorite(point).await {
+                        error!("Failed to delete favorite: {err}");
+                    }
+                });
+            },
+            None => {
+                let title = details.title.clone();
+                let address = details.address.clone();
+                let favorite = Favorite { point, title, address };
+
+                self.favorites.push(favorite.clone());
+                tokio::spawn(async move {
+                    if let Err(err) = db.insert_favorite(&favorite).await {
+                        error!("Failed to add favorite: {err}");
+                    }
+                });
+            },
+        }
+
+        self.dirty = true;
+    }
+
     /// Highlight a specific point on the map.
     pub fn set_poi(&mut self, point: Option<GeoPoint>) {
         let point = point.map(RenderGeoPoint::from);
@@ -619,33 +1779,71 @@ impl MapView {
             return;
         }
 
+        self.poi_details = None;
+
         // Clear route when a new POI is set.
         if point.is_some() {
             self.cancel_route();
         }
 
+        // Highlight the marker, so it is easy to find on a busy map.
+        self.marker_pulse =
+            point.is_some().then(|| Tween::new(MARKER_PULSE_DURATION, Easing::EaseOut));
+
         self.dirty = true;
         self.poi = point;
     }
 
+    /// Set title and address shown in the current POI's callout.
+    pub fn set_poi_details(&mut self, title: String, address: String) {
+        self.poi_details = Some(PoiDetails { title, address });
+        self.dirty = true;
+    }
+
     /// Update the GPS indicator location.
-    #[cfg_attr(feature = "profiling", profiling::function)]
     pub fn set_gps(&mut self, point: Option<GeoPoint>, heading: Option<f64>) {
+        // Share the real position with followers while navigating.
+        if let Some(point) = point
+            && self.is_navigating()
+        {
+            self.sharing.update(point, heading);
+        }
+
+        // Defer GPS updates until the route preview is done.
+        if let Some(preview) = &mut self.preview {
+            preview.gps = point.map(|point| (point, heading));
+            return;
+        }
+
+        self.update_gps(point, heading);
+    }
+
+    /// Update the GPS indicator location and navigation progress.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn update_gps(&mut self, point: Option<GeoPoint>, heading: Option<f64>) {
+        self.last_gps_fix = point.map(|_| Instant::now());
+
         let point = match point.map(RenderGeoPoint::from) {
             // Ignore GPS positions matching the current state.
-            point if point.as_ref() == self.gps.as_ref() => return,
+            point if !self.gps_resync && point.as_ref() == self.gps.as_ref() => return,
             Some(point) => point,
             None => {
                 self.dirty |= self.gps.is_some();
                 self.gps_locked = false;
+                self.camera.reset();
                 self.gps = None;
                 return;
             },
         };
 
+        self.gps_resync = false;
+
+        self.camera.update_position(point.point, Instant::now());
+
         // Jump to new GPS position if the view is locked to the GPS.
         if self.gps_locked {
-            self.goto(point.point, None);
+            let zoom = self.navigation_zoom();
+            self.goto(point.point, zoom);
             self.gps_locked = true;
         }
 
@@ -657,23 +1855,65 @@ impl MapView {
                 && point.point.distance(last) <= MAX_GPS_ROUTE_DISTANCE
             {
                 // Delete route once it has been completed.
-                self.cancel_route();
+                if self.preview.is_none() {
+                    let trip = self.trip.take();
+                    self.cancel_route();
+
+                    if let Some(mut trip) = trip
+                        && self.navigation_config.trip_summary
+                    {
+                        trip.update(point.point);
+                        self.event_loop.insert_idle(move |state| {
+                            if let Some(png) = state.window.save_trip_summary(trip) {
+                                state.copy_image(png);
+                            }
+                        });
+                    }
+                }
             } else {
                 let (index, distance) = nearest_route_segment(route.points(), point.point);
 
+                // Record the traveled path for the trip summary.
+                if self.preview.is_none()
+                    && let Some(trip) = &mut self.trip
+                {
+                    trip.update(point.point);
+                }
+
                 // Update the route to remove segments already traveled.
                 if distance <= MAX_GPS_ROUTE_DISTANCE && index > 0 {
                     route.truncate_start(index);
 
-                    // Update progress in the route view.
+                    // Update progress in the route and directions views.
                     let progress = route.progress();
                     self.event_loop.insert_idle(move |state| {
                         state.window.views.route().set_progress(progress);
+                        state.window.set_directions_progress(progress);
                     });
                 }
 
+                // Alert user when approaching the next maneuver.
+                let segment = route.segment_index();
+                let instruction = route.instruction();
+                let road_speed = match instruction.time {
+                    0 => 0.,
+                    time => instruction.length as f64 / time as f64 * 3.6,
+                };
+                if self.preview.is_none()
+                    && self.maneuver_alerts.update(
+                        segment,
+                        instruction.length,
+                        route.mode(),
+                        road_speed,
+                        self.camera.speed(),
+                    )
+                {
+                    Self::alert_maneuver(&self.navigation_config);
+                }
+
                 // Reroute if GPS is way off course.
                 if !self.rerouting
+                    && self.preview.is_none()
                     && distance >= MIN_GPS_REROUTE_DISTANCE
                     && let Some(target) = route.end()
                     && self.last_reroute.elapsed() >= MIN_REROUTE_INTERVAL
@@ -687,14 +1927,90 @@ impl MapView {
             }
         }
 
+        // Alert user when approaching a hazard during navigation.
+        if self.is_navigating()
+            && self.preview.is_none()
+            && self.hazard_alerts.update(point.point, self.hazards_config.alert_distance).is_some()
+        {
+            Self::alert_maneuver(&self.navigation_config);
+        }
+
         self.heading = heading.map(|h| h as f32);
         self.gps = Some(point);
         self.dirty = true;
     }
 
+    /// Check whether the map is following a GPS route.
+    pub fn is_navigating(&self) -> bool {
+        self.route.as_ref().is_some_and(|route| route.has_gps_origin())
+    }
+
+    /// Update the map transform for the active camera mode.
+    fn update_transform(&mut self) {
+        let mode = if self.is_navigating() { self.camera_mode } else { CameraMode::NorthUp };
+        let rotation = match mode {
+            CameraMode::NorthUp => 0.,
+            CameraMode::HeadingUp | CameraMode::Perspective => {
+                self.camera.bearing().or(self.heading).unwrap_or_default()
+            },
+        };
+        self.transform = MapTransform::new(mode, rotation, self.size * self.scale);
+    }
+
+    /// Switch to the next navigation camera mode.
+    fn cycle_camera_mode(&mut self) {
+        self.camera_mode = match self.camera_mode {
+            CameraMode::NorthUp => CameraMode::HeadingUp,
+            CameraMode::HeadingUp => CameraMode::Perspective,
+            CameraMode::Perspective => CameraMode::NorthUp,
+        };
+        self.camera_button.set_svg(camera_mode_svg(self.camera_mode));
+        self.dirty = true;
+
+        self.store_camera_mode();
+    }
+
+    /// Persist the current camera mode across restarts.
+    fn store_camera_mode(&self) {
+        let camera_mode = self.camera_mode;
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            if let Err(err) = db.set_camera_mode(camera_mode).await {
+                error!("Failed to store camera mode: {err}");
+            }
+        });
+    }
+
+    /// Notify the user about an upcoming maneuver.
+    fn alert_maneuver(config: &Navigation) {
+        if config.chime {
+            audio::play_chime();
+        }
+
+        if config.haptic {
+            tokio::spawn(async {
+                if let Err(err) = dbus::feedbackd::maneuver_feedback().await {
+                    error!("Failed to trigger haptic feedback: {err}");
+                }
+            });
+        }
+    }
+
+    /// Get the navigation camera's zoom level, if it should be changed.
+    fn navigation_zoom(&self) -> Option<u8> {
+        // Only control zoom while following a GPS route.
+        let route = self.route.as_ref().filter(|route| route.has_gps_origin())?;
+        let maneuver_distance = route.instruction().length;
+
+        let zoom = self.position.tile.z;
+        self.camera.zoom(&self.navigation_config, zoom, Some(maneuver_distance), Instant::now())
+    }
+
     /// Update the active route.
     #[cfg_attr(feature = "profiling", profiling::function)]
     pub fn set_route(&mut self, route: Arc<Route>, is_gps_route: bool) {
+        self.stop_preview();
+
         // Update the current route.
         let map_route = self.route.get_or_insert_default();
         let was_gps_route = map_route.has_gps_origin();
@@ -712,8 +2028,17 @@ impl MapView {
         }
 
         self.reset_reroute_timeout();
+        self.maneuver_alerts.reset();
+
+        // Record the traveled path of GPS routes, keeping it across reroutes.
+        if is_gps_route {
+            self.trip.get_or_insert_default();
+        } else {
+            self.trip = None;
+        }
 
         // Clear POIs, since they're either part of the route or a distraction.
+        self.poi_details = None;
         self.poi = None;
 
         // Use search button for route overview while a route is active.
@@ -728,34 +2053,323 @@ impl MapView {
         self.rerouting = false;
     }
 
+    /// Discard navigation state which went stale while the system was asleep.
+    ///
+    /// The next GPS update is always processed, to update route progress even
+    /// if the location did not change.
+    pub fn resync_navigation(&mut self) {
+        self.camera.reset();
+        self.rerouting = false;
+        self.gps_resync = true;
+    }
+
+    /// Simulate navigation along a route at accelerated speed.
+    pub fn start_preview(&mut self, route: Arc<Route>, is_gps_route: bool) {
+        self.stop_preview();
+
+        // Stage timer for advancing the simulated position.
+        let timer = Timer::from_duration(PREVIEW_INTERVAL);
+        let token = self.event_loop.insert_source(timer, |_, _, state| {
+            let map = state.window.views.map();
+            let action = if map.advance_preview() {
+                TimeoutAction::ToDuration(PREVIEW_INTERVAL)
+            } else {
+                TimeoutAction::Drop
+            };
+            state.window.unstall();
+            action
+        });
+        let timer = match token {
+            Ok(timer) => timer,
+            Err(err) => {
+                error!("Failed to stage route preview timer: {err}");
+                return;
+            },
+        };
+
+        let gps = self.gps.as_ref().map(|gps| (gps.point, self.heading.map(f64::from)));
+        let start = Instant::now();
+        self.preview = Some(RoutePreview { route: route.clone(), is_gps_route, start, timer, gps });
+
+        // Follow the route like a GPS route starting at its origin.
+        let mut map_route = MapRoute::default();
+        map_route.set_route(route, true);
+        self.route = Some(map_route);
+        self.maneuver_alerts.reset();
+        self.gps_locked = true;
+        self.camera.reset();
+        self.gps = None;
+
+        self.advance_preview();
+    }
+
+    /// Stop the active route preview.
+    pub fn stop_preview(&mut self) {
+        if let Some(preview) = self.preview.take() {
+            self.event_loop.remove(preview.timer);
+            self.finish_preview(preview);
+        }
+    }
+
+    /// Move the route preview to its current simulated position.
+    ///
+    /// Returns `false` once the end of the route has been reached.
+    fn advance_preview(&mut self) -> bool {
+        let preview = match &self.preview {
+            Some(preview) => preview,
+            None => return false,
+        };
+
+        let time = preview.start.elapsed().as_secs_f64() * PREVIEW_SPEEDUP;
+        match route_position(&preview.route, time) {
+            Some(point) => {
+                self.update_gps(Some(point), None);
+                true
+            },
+            None => {
+                if let Some(preview) = self.preview.take() {
+                    self.finish_preview(preview);
+                }
+                false
+            },
+        }
+    }
+
+    /// Restore the state from before the route preview.
+    fn finish_preview(&mut self, preview: RoutePreview) {
+        // Reset simulated navigation state.
+        self.gps_locked = false;
+        self.camera.reset();
+        self.route = None;
+        self.gps = None;
+
+        // Reset simulated progress in the route and directions views.
+        self.event_loop.insert_idle(|state| {
+            state.window.views.route().set_progress(0);
+            state.window.set_directions_progress(0);
+        });
+
+        // Apply the last real GPS location, to lock GPS routes to it again.
+        if let Some((point, heading)) = preview.gps {
+            self.update_gps(Some(point), heading);
+        }
+
+        self.set_route(preview.route, preview.is_gps_route);
+    }
+
+    /// Play back a recorded track on the map.
+    pub fn play_track(&mut self, track: Track, points: Vec<TrackPoint>) {
+        self.stop_playback();
+
+        // Show the entire track.
+        let (mut min, mut max) = match points.first() {
+            Some(point) => (point.point, point.point),
+            None => return,
+        };
+        for TrackPoint { point, .. } in &points {
+            min = GeoPoint::new(min.lat.min(point.lat), min.lon.min(point.lon));
+            max = GeoPoint::new(max.lat.max(point.lat), max.lon.max(point.lon));
+        }
+        self.fit_bounds(min, max);
+
+        let render_points = points.iter().map(|point| point.point.into()).collect();
+        self.playback = Some(TrackPlayback {
+            render_points,
+            points,
+            duration: track.duration as f64,
+            last_update: Instant::now(),
+            timer: None,
+            time: 0.,
+        });
+        self.resume_playback();
+        self.dirty = true;
+    }
+
+    /// Close the active track playback.
+    pub fn stop_playback(&mut self) {
+        self.pause_playback();
+        self.dirty |= self.playback.take().is_some();
+    }
+
+    /// Show the areas visited by recorded tracks.
+    pub fn show_heatmap(&mut self) {
+        if self.heatmap.is_none() {
+            self.heatmap = Some(Heatmap::default());
+            self.dirty = true;
+        }
+    }
+
+    /// Request heatmap cells for the current zoom level.
+    fn load_heatmap(&mut self) {
+        let zoom = (self.position.tile.z + HEATMAP_CELL_DEPTH).min(db::HEATMAP_ZOOM);
+        match &mut self.heatmap {
+            Some(heatmap) if heatmap.pending != Some(zoom) && heatmap.zoom != zoom => {
+                heatmap.pending = Some(zoom);
+            },
+            _ => return,
+        }
+
+        let heatmap_tx = self.heatmap_tx.clone();
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            match db.track_heatmap(zoom).await {
+                Ok(cells) => {
+                    let _ = heatmap_tx.send((zoom, cells));
+                },
+                Err(err) => error!("Failed to load track heatmap: {err}"),
+            }
+        });
+    }
+
+    /// Check whether track playback or the heatmap are shown.
+    fn overlay_visible(&self) -> bool {
+        self.playback.is_some() || self.heatmap.is_some()
+    }
+
+    /// Close track playback and the heatmap.
+    fn close_overlays(&mut self) {
+        self.stop_playback();
+        self.dirty |= self.heatmap.take().is_some();
+    }
+
+    /// Start advancing the track playback time.
+    fn resume_playback(&mut self) {
+        let playback = match &mut self.playback {
+            Some(playback) if playback.timer.is_none() && playback.progress() < 1. => playback,
+            _ => return,
+        };
+
+        // Stage timer for advancing the playback time.
+        let timer = Timer::from_duration(PLAYBACK_INTERVAL);
+        let token = self.event_loop.insert_source(timer, |_, _, state| {
+            let map = state.window.views.map();
+            let action = if map.advance_playback() {
+                TimeoutAction::ToDuration(PLAYBACK_INTERVAL)
+            } else {
+                TimeoutAction::Drop
+            };
+            state.window.unstall();
+            action
+        });
+        match token {
+            Ok(timer) => {
+                playback.timer = Some(timer);
+                playback.last_update = Instant::now();
+            },
+            Err(err) => error!("Failed to stage track playback timer: {err}"),
+        }
+    }
+
+    /// Stop advancing the track playback time.
+    fn pause_playback(&mut self) {
+        if let Some(timer) = self.playback.as_mut().and_then(|playback| playback.timer.take()) {
+            self.event_loop.remove(timer);
+        }
+    }
+
+    /// Advance the track playback by the time since the last update.
+    ///
+    /// Returns `false` once the end of the track has been reached.
+    fn advance_playback(&mut self) -> bool {
+        let playback = match &mut self.playback {
+            Some(playback) => playback,
+            None => return false,
+        };
+
+        let now = Instant::now();
+        let elapsed = now - mem::replace(&mut playback.last_update, now);
+        playback.time =
+            (playback.time + elapsed.as_secs_f64() * PLAYBACK_SPEEDUP).min(playback.duration);
+        self.dirty = true;
+
+        // Timer is removed by the caller once the end is reached.
+        if playback.progress() >= 1. {
+            playback.timer = None;
+            return false;
+        }
+
+        true
+    }
+
+    /// Move the track playback to the time at a horizontal slider position.
+    fn seek_playback(&mut self, x: f64) {
+        let (bar_start, bar_end) = Self::playback_bar_range(self.size, self.scale);
+        if let Some(playback) = &mut self.playback {
+            let progress = ((x - bar_start) / (bar_end - bar_start)).clamp(0., 1.);
+            playback.time = playback.duration * progress;
+            self.dirty = true;
+        }
+    }
+
+    /// Check whether a physical point is on the track playback slider.
+    fn playback_slider_contains(&self, point: Point<f64>) -> bool {
+        let slider_point: Point<f64> = Self::playback_slider_point(self.size, self.scale).into();
+        let slider_size: Size<f64> = Self::playback_slider_size(self.size, self.scale).into();
+        self.playback.is_some() && rect_contains(slider_point, slider_size, point)
+    }
+
     /// Clear the active route.
     pub fn cancel_route(&mut self) {
+        self.stop_preview();
+
         self.search_button.set_svg(Svg::Search);
         self.dirty |= self.route.is_some();
         self.route = None;
+        self.trip = None;
+        self.hazard_alerts.reset();
+
+        // Close all route surfaces once there is nothing to navigate.
+        self.event_loop.insert_idle(|state| {
+            state.window.close_surface(SurfaceKind::MiniWindow);
+            state.window.close_surface(SurfaceKind::Directions);
+        });
+    }
+
+    /// Set whether the compositor supports the navigation overlay.
+    pub fn set_mini_window_supported(&mut self, supported: bool) {
+        self.dirty |= self.mini_window_supported != supported;
+        self.mini_window_supported = supported;
+    }
+
+    /// Check whether the map lock button is visible.
+    fn show_lock_button(&self) -> bool {
+        self.interaction_locked || self.is_navigating()
+    }
+
+    /// Lock or unlock touch interactions with the map.
+    fn set_interaction_locked(&mut self, locked: bool) {
+        self.dirty |= self.interaction_locked != locked;
+        self.interaction_locked = locked;
+    }
+
+    /// Map lock button long-press callback.
+    fn trigger_unlock(&mut self) {
+        self.touch_state.long_press_token = None;
+        self.set_interaction_locked(false);
+
+        if self.lock_button.release() {
+            self.dirty = true;
+        }
+
+        // Ignore the remainder of the touch sequence.
+        self.touch_state.action = TouchAction::None;
     }
 
     /// Touch long-press callback.
-    pub fn trigger_long_press(&mut self, mut point: Point<f64>) {
+    pub fn trigger_long_press(&mut self, point: Point<f64>) {
         // Manually reset touch state, since touch release might be sent to search view.
         self.touch_state.slots.clear();
         self.touch_state.last_time = 0;
 
-        // Convert point from screen origin to center origin.
-        let size = self.size * self.scale;
-        point.x -= size.width as f64 / 2.;
-        point.y -= size.height as f64 / 2.;
-
-        // Convert screen point to geographic point.
-        let (tile_index, offset) = self.center_point_tile(point);
-        let geo_point = GeoPoint::from_tile(tile_index, offset);
+        let geo_point = self.screen_geo_point(point);
+        let zoom = self.position.tile.z;
 
         // Clear POI marker.
         self.set_poi(None);
 
         // Submit query and open search view.
         self.event_loop.insert_idle(move |state| {
-            state.window.views.search().reverse(geo_point, tile_index.z);
+            state.window.views.search().reverse(geo_point, zoom);
             state.window.set_view(View::Search);
         });
     }
@@ -766,57 +2380,19 @@ impl MapView {
             return;
         }
 
-        let (tile, offset) = self.center_point_tile(delta * -1.);
-        self.cursor_tile = tile;
-        self.cursor_offset = offset;
+        self.position.move_by(delta);
 
         self.gps_locked = false;
         self.dirty = true;
     }
 
-    /// Convert a point relative to the screen's center to a tile + offset.
-    fn center_point_tile(&self, point: Point<f64>) -> (TileIndex, Point) {
-        let mut tile = self.cursor_tile;
-        let mut offset = self.cursor_offset;
-
-        // Apply sub-tile scale, since the cursor is in tile coordinates.
-        let scale = self.zoom_scale();
-        let x = (point.x / scale).round() as i32;
-        let y = (point.y / scale).round() as i32;
-
-        let max_tile = (1 << tile.z) - 1;
-        let offset_x = offset.x + x;
-        let offset_y = offset.y + y;
-
-        // Calculate tile index.
-        let tile_x = tile.x as i32 + offset_x.div_euclid(TILE_SIZE);
-        let tile_y = tile.y as i32 + offset_y.div_euclid(TILE_SIZE);
-        tile.x = tile_x.clamp(0, max_tile) as u32;
-        tile.y = tile_y.clamp(0, max_tile) as u32;
-
-        // Calculate tile offset.
-        let clamp_offset = |tile: i32, offset: i32| {
-            if tile > max_tile {
-                255
-            } else if tile < 0 {
-                0
-            } else {
-                offset.rem_euclid(TILE_SIZE)
-            }
-        };
-        offset.x = clamp_offset(tile_x, offset_x);
-        offset.y = clamp_offset(tile_y, offset_y);
-
-        (tile, offset)
-    }
-
     /// Zoom the map by a percentage.
     ///
     /// A value of `2.5` will increase the resolution of the current map by
     /// `2.5`, which will increase the tileset zoom level by 1.
     fn zoom_by(&mut self, zoom: f64) {
-        let map_delta = (1. / zoom).log2() - self.cursor_zoom;
-        let map_delta_trunc = map_delta.trunc() as i32;
+        // Pause automatic navigation zoom while the user is zooming.
+        self.camera.override_zoom(Instant::now());
 
         let size = self.size * self.scale;
         let center = Point::new(size.width as f64, size.height as f64) / 2.;
@@ -828,115 +2404,183 @@ impl MapView {
             self.touch_state.zoom_focus
         };
 
-        // Calculate offset required to keep zoom focus stationary.
-        //
-        // Rounding and precision here means the focus point isn't kept precisely in the
-        // same position, which is fine for our usecase.
-        //
-        // The rounding is required to avoid insignificant negative changes being
-        // floored to integer cursor_offset changes and causing the map to move around
-        // when moving the zoom points without changing their distance.
-        let focus_delta = zoom_focus - center;
-        let mut focus_offset = focus_delta / self.zoom_scale() * (zoom - 1.);
-        focus_offset.x = focus_offset.x.round();
-        focus_offset.y = focus_offset.y.round();
-
-        // Convert position within tile back to fractional tile indices.
-        let tile_x = self.cursor_tile.x as f64
-            + (self.cursor_offset.x as f64 + focus_offset.x) / TILE_SIZE as f64;
-        let tile_y = self.cursor_tile.y as f64
-            + (self.cursor_offset.y as f64 + focus_offset.y) / TILE_SIZE as f64;
-        let tile_z = self.cursor_tile.z as i32;
-
-        // Calculate new fractional tile indices.
-        let tile_delta = map_delta_trunc.clamp(-(MAX_ZOOM as i32 - tile_z), tile_z);
-        let new_tile_x = tile_x * 2f64.powi(-tile_delta);
-        let new_tile_y = tile_y * 2f64.powi(-tile_delta);
-
-        // Convert fractional, to integer tile indices and offset.
-        let x_offset = (new_tile_x.fract() * TILE_SIZE as f64).floor() as i32;
-        let y_offset = (new_tile_y.fract() * TILE_SIZE as f64).floor() as i32;
-        self.cursor_tile.x = new_tile_x.trunc() as u32;
-        self.cursor_tile.y = new_tile_y.trunc() as u32;
-        self.cursor_tile.z = (tile_z - tile_delta) as u8;
-        self.cursor_offset = Point::new(x_offset, y_offset);
-
-        // Clamp scale fraction to 199/49% when clamped.
-        self.cursor_zoom = if map_delta_trunc != tile_delta {
-            0.999f64.copysign(-map_delta_trunc as f64)
-        } else {
-            -map_delta.fract()
-        };
+        self.position.zoom_by(zoom, zoom_focus - center, self.min_zoom, self.max_zoom);
 
         self.dirty = true;
     }
 
-    /// Snap zoom to nearest integer tile scale.
+    /// Animate zoom to nearest integer tile scale.
     fn snap_zoom(&mut self) {
-        if self.cursor_zoom == 0. {
+        if self.position.zoom == 0. || self.fly_to.is_some() {
             return;
         }
 
-        if (self.cursor_zoom < -0.5 && self.cursor_tile.z > 0)
-            || self.cursor_zoom >= 0.5 && self.cursor_tile.z < MAX_ZOOM
-        {
-            let zoom_signum = self.cursor_zoom.signum() as i32;
+        let mut zoom = self.position.tile.z;
+        if self.position.zoom < -0.5 && zoom > self.min_zoom {
+            zoom -= 1;
+        } else if self.position.zoom >= 0.5 && zoom < self.max_zoom {
+            zoom += 1;
+        }
+
+        self.animate_to(self.center_point(), zoom as f64, ZOOM_SNAP_DURATION);
+    }
 
-            let tile_x = self.cursor_tile.x as f64 + self.cursor_offset.x as f64 / TILE_SIZE as f64;
-            let tile_x = tile_x * 2f64.powi(zoom_signum);
-            let tile_y = self.cursor_tile.y as f64 + self.cursor_offset.y as f64 / TILE_SIZE as f64;
-            let tile_y = tile_y * 2f64.powi(zoom_signum);
+    /// Physical size of the POI callout buttons.
+    fn callout_button_size(scale: f64) -> Size {
+        Size::new(CALLOUT_BUTTON_SIZE, CALLOUT_BUTTON_SIZE) * scale
+    }
 
-            self.cursor_tile.x = tile_x.floor() as u32;
-            self.cursor_tile.y = tile_y.floor() as u32;
-            self.cursor_tile.z = (self.cursor_tile.z as i32 + zoom_signum) as u8;
+    /// Physical size of the UI buttons.
+    fn button_size(scale: f64) -> Size {
+        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+    }
 
-            self.cursor_offset.x = (tile_x.fract() * TILE_SIZE as f64).floor() as i32;
-            self.cursor_offset.y = (tile_y.fract() * TILE_SIZE as f64).floor() as i32;
-        }
-        self.cursor_zoom = 0.;
+    /// Physical location of the search button.
+    fn search_button_point(size: Size, scale: f64) -> Point {
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+        let y = (physical_size.height - button_size.height) as i32 - padding;
+
+        Point::new(x, y)
+    }
+
+    /// Physical location of the track playback slider.
+    ///
+    /// The slider is placed above the bottom button row, to avoid overlapping
+    /// the navigation buttons.
+    fn playback_slider_point(size: Size, scale: f64) -> Point {
+        let zoom_out_button_point = Self::zoom_out_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        Point::new(padding, zoom_out_button_point.y)
+    }
+
+    /// Physical size of the track playback slider.
+    fn playback_slider_size(size: Size, scale: f64) -> Size {
+        let zoom_out_button_point = Self::zoom_out_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let width = (zoom_out_button_point.x - 2 * padding).max(0) as u32;
+        Size::new(width, button_size.height)
+    }
+
+    /// Physical horizontal start and end of the track playback slider's bar.
+    fn playback_bar_range(size: Size, scale: f64) -> (f64, f64) {
+        let point = Self::playback_slider_point(size, scale);
+        let slider_size = Self::playback_slider_size(size, scale);
+        let padding = BANNER_PADDING as f64 * scale;
+
+        let start = point.x as f64 + padding;
+        let end = (point.x + slider_size.width as i32) as f64 - padding;
+        (start, end.max(start + 1.))
+    }
+
+    /// Physical location of the zoom out button.
+    fn zoom_out_button_point(size: Size, scale: f64) -> Point {
+        let search_button_point = Self::search_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let mut point = search_button_point;
+        point.y -= button_size.height as i32 + padding;
+
+        point
+    }
+
+    /// Physical location of the zoom in button.
+    fn zoom_in_button_point(size: Size, scale: f64) -> Point {
+        let zoom_out_button_point = Self::zoom_out_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let mut point = zoom_out_button_point;
+        point.y -= button_size.height as i32 + padding;
+
+        point
+    }
+
+    /// Physical location of the GPS centering button.
+    fn gps_button_point(size: Size, scale: f64) -> Point {
+        let search_button_point = Self::search_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let mut point = search_button_point;
+        point.x -= button_size.width as i32 + padding;
+
+        point
+    }
+
+    /// Physical location of the camera mode button.
+    fn camera_button_point(size: Size, scale: f64) -> Point {
+        let gps_button_point = Self::gps_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let mut point = gps_button_point;
+        point.x -= button_size.width as i32 + padding;
 
-        self.dirty = true;
+        point
     }
 
-    /// Get the current sub-tile zoom level.
-    ///
-    /// A value of 1.5 means tiles should be rendered at 150% of their size.
-    /// This value will always be between 0.5 and 2.0, never reaching either
-    /// bound.
-    fn zoom_scale(&self) -> f64 {
-        (2f64.powf(self.cursor_zoom) * 100.).round() / 100.
-    }
+    /// Physical location of the map lock button.
+    fn lock_button_point(size: Size, scale: f64) -> Point {
+        let camera_button_point = Self::camera_button_point(size, scale);
+        let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
 
-    /// Physical size of the UI buttons.
-    fn button_size(scale: f64) -> Size {
-        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+        let mut point = camera_button_point;
+        point.x -= button_size.width as i32 + padding;
+
+        point
     }
 
-    /// Physical location of the search button.
-    fn search_button_point(size: Size, scale: f64) -> Point {
+    /// Physical location of the search along route button.
+    fn along_route_button_point(size: Size, scale: f64) -> Point {
+        let lock_button_point = Self::lock_button_point(size, scale);
         let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
         let button_size = Self::button_size(scale);
-        let physical_size = size * scale;
 
-        let x = (physical_size.width - button_size.width) as i32 - padding;
-        let y = (physical_size.height - button_size.height) as i32 - padding;
+        let mut point = lock_button_point;
+        point.x -= button_size.width as i32 + padding;
 
-        Point::new(x, y)
+        point
     }
 
-    /// Physical location of the GPS centering button.
-    fn gps_button_point(size: Size, scale: f64) -> Point {
-        let search_button_point = Self::search_button_point(size, scale);
+    /// Physical location of the navigation overlay button.
+    fn mini_window_button_point(size: Size, scale: f64) -> Point {
+        let along_route_button_point = Self::along_route_button_point(size, scale);
         let padding = (BUTTON_PADDING as f64 * scale).round() as i32;
         let button_size = Self::button_size(scale);
 
-        let mut point = search_button_point;
-        point.x -= button_size.width as i32 + padding;
+        let mut point = along_route_button_point;
+        point.y -= button_size.height as i32 + padding;
 
         point
     }
 
+    /// Download map tiles around the part of a route after the first `progress`
+    /// nodes.
+    pub fn prefetch_route(&mut self, route: &Route, progress: usize) {
+        let path = route.segments.iter().flat_map(|segment| &segment.points).skip(progress);
+        self.tiles.prefetch(path.copied());
+        self.dirty = true;
+    }
+
+    /// Open search results for places to stop along the active route.
+    fn search_along_route(&self) {
+        self.event_loop.insert_idle(|state| {
+            let route_view = state.window.views.route();
+            let query = CorridorQuery::new(route_view.route(), route_view.progress());
+
+            state.window.views.search().search_along_route(query);
+            state.window.set_view(View::Search);
+        });
+    }
+
     /// Set tile index and offset to give an overview over the current route.
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn center_route(&mut self) {
@@ -950,12 +2594,29 @@ impl MapView {
             None => return,
         };
 
-        // Calculate center point of the route.
+        self.fit_bounds(start, end);
+    }
+
+    /// Highlight a region's outline and move the map to show all of it.
+    ///
+    /// The outline is hidden again with the next tap on the map.
+    pub fn show_coverage(&mut self, bounds: RegionBounds) {
+        let start = GeoPoint::new(bounds.min_lat, bounds.min_lon);
+        let end = GeoPoint::new(bounds.max_lat, bounds.max_lon);
+        self.fit_bounds(start, end);
+
+        self.coverage = Some(bounds);
+        self.dirty = true;
+    }
+
+    /// Set tile index and offset to show the area between two points.
+    pub fn fit_bounds(&mut self, start: GeoPoint, end: GeoPoint) {
+        // Calculate center point of the area.
         let center_lat = (start.lat + end.lat) / 2.;
         let center_lon = (start.lon + end.lon) / 2.;
         let center = GeoPoint::new(center_lat, center_lon);
 
-        // Calculate maximum dimensions (in meters) of the route.
+        // Calculate maximum dimensions (in meters) of the area.
         //
         // We use the minimum latitude for width calculation since circumference gets
         // bigger when closer to the equator (lat 0), which gives us the maximum
@@ -964,7 +2625,7 @@ impl MapView {
         let width = GeoPoint::new(min_lat, start.lon).distance(GeoPoint::new(min_lat, end.lon));
         let height = GeoPoint::new(start.lat, 0.).distance(GeoPoint::new(end.lat, 0.));
 
-        // Add tolerance to ensure route doesn't 'bump' into screen borders.
+        // Add tolerance to ensure the area doesn't 'bump' into screen borders.
         let width = width as f64 * ROUTE_ZOOM_PADDING;
         let height = height as f64 * ROUTE_ZOOM_PADDING;
 
@@ -978,7 +2639,7 @@ impl MapView {
         let height_zoom = geometry::zoom_for_distance(max_lat, height, size.height);
         let zoom = width_zoom.min(height_zoom);
 
-        self.goto(center, Some(zoom));
+        self.fly_to(center, Some(zoom));
     }
 
     /// Create the GPS location background task.
@@ -995,7 +2656,19 @@ impl MapView {
         // Forward new GPS locations.
         event_loop.insert_source(gps_rx, |event, _, state| {
             let (location, heading) = match event {
-                Event::Msg(msg) => msg,
+                Event::Msg(DbusEvent::Location(location, heading)) => (location, heading),
+                // Stop GPS removal, since no updates are received while asleep.
+                Event::Msg(DbusEvent::Suspend) => {
+                    if let Some(token) = state.gps_timeout.take() {
+                        state.event_loop.remove(token);
+                    }
+                    return;
+                },
+                // Resynchronize navigation with the next GPS update.
+                Event::Msg(DbusEvent::Resume) => {
+                    state.window.views.map().resync_navigation();
+                    return;
+                },
                 Event::Closed => return,
             };
 
@@ -1009,6 +2682,11 @@ impl MapView {
 
                     state.window.views.map().set_gps(Some(location), heading);
                     state.window.views.search().set_gps(Some(location));
+                    state.window.views.download().set_gps(Some(location));
+                    state.window.views.onboarding().set_gps(Some(location));
+                    state.window.views.tracks().set_gps(Some(location));
+                    state.window.update_theme(location);
+                    state.window.update_units(location);
                     state.window.unstall();
                 },
                 // Delay GPS removal by `GPS_TIMEOUT`.
@@ -1017,6 +2695,9 @@ impl MapView {
                     let token = state.event_loop.insert_source(timer, move |_, _, state| {
                         state.window.views.map().set_gps(None, None);
                         state.window.views.search().set_gps(None);
+                        state.window.views.download().set_gps(None);
+                        state.window.views.onboarding().set_gps(None);
+                        state.window.views.tracks().set_gps(None);
                         state.window.unstall();
 
                         TimeoutAction::Drop
@@ -1030,18 +2711,53 @@ impl MapView {
 
         Ok(())
     }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::CalloutFavorite => Some(&mut self.callout_favorite_button),
+            TouchAction::CalloutRoute => Some(&mut self.callout_route_button),
+            TouchAction::CameraMode => Some(&mut self.camera_button),
+            TouchAction::Lock => Some(&mut self.lock_button),
+            TouchAction::AlongRoute => Some(&mut self.along_route_button),
+            TouchAction::MiniWindow => Some(&mut self.mini_window_button),
+            TouchAction::Search => Some(&mut self.search_button),
+            TouchAction::CloseOverlay => Some(&mut self.close_button),
+            TouchAction::ZoomIn => Some(&mut self.zoom_in_button),
+            TouchAction::ZoomOut => Some(&mut self.zoom_out_button),
+            TouchAction::Gps => Some(&mut self.gps_button),
+            TouchAction::DoubleTap
+            | TouchAction::Marker(_)
+            | TouchAction::MarkerDrag(_)
+            | TouchAction::Drag
+            | TouchAction::Zoom
+            | TouchAction::Attribution
+            | TouchAction::RetryDb
+            | TouchAction::RetryTileserver
+            | TouchAction::DismissConfigWarnings
+            | TouchAction::DismissTripSummary
+            | TouchAction::PlaybackSeek
+            | TouchAction::Tap
+            | TouchAction::None => None,
+        }
+    }
 }
 
 impl UiView for MapView {
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
-        let size = self.size * self.scale;
+        if self.hud_enabled {
+            self.hud.start_frame();
+        }
 
-        // Apply pending velocities.
-        if let Some(velocity_delta) = self.touch_state.move_velocity.apply(&self.input_config) {
+        // Apply pending animations.
+        self.apply_fly_to();
+        if let Some(velocity_delta) =
+            self.touch_state.move_velocity.apply(self.input_config.inertia())
+        {
             self.move_by(velocity_delta);
         }
-        if let Some(velocity) = self.touch_state.zoom_velocity.apply(&self.input_config) {
+        if let Some(velocity) = self.touch_state.zoom_velocity.apply(self.input_config.inertia()) {
             // Get zoom velocity as increasing distance, then swap numerator and denominator
             // if we're zooming out. This is done to avoid shrinking distances
             // eventually running past the zero point and causing issues.
@@ -1072,24 +2788,104 @@ impl UiView for MapView {
 
         render_state.clear(config.colors.background);
 
+        // Apply camera rotation and tilt to all map elements.
+        self.update_transform();
+        render_state.save();
+        render_state.concat(self.transform.matrix());
+
         // Create iterator over visible tiles.
-        let mut iter = TileIter::new(size, self.cursor_tile, self.cursor_offset, self.zoom_scale());
+        let tiles_size = self.transform.tiles_size();
+        let mut iter = self.position.tiles(tiles_size);
 
         // Render all visible tiles.
-        self.draw_tiles(&mut render_state, &mut iter);
+        self.draw_tiles(config, &mut render_state, &mut iter);
 
-        // Render attribution message.
-        self.draw_attribution(config, &mut render_state);
+        // Render visited area heatmap.
+        self.draw_heatmap(config, &mut render_state, &iter);
 
         // Render active route.
         self.draw_route(config, &mut render_state, &iter);
 
+        // Render played back track.
+        self.draw_playback_track(config, &mut render_state, &iter);
+
+        // Render highlighted region outline.
+        self.draw_coverage(config, &mut render_state, &iter);
+
         // Render active POI and GPS symbols.
         self.draw_map_points(config, &mut render_state, &iter);
 
+        render_state.restore();
+
+        // Render attribution message.
+        let attribution_height = self.draw_attribution(config, &mut render_state);
+
+        // Render status notices below the attribution.
+        let mut banner_y = attribution_height;
+        if self.db.read_only() {
+            banner_y += self.draw_banner(config, &mut render_state, banner_y, READ_ONLY_MESSAGE);
+        }
+        if self.db.in_memory() {
+            let height = self.draw_banner(config, &mut render_state, banner_y, IN_MEMORY_MESSAGE);
+            self.db_banner = (banner_y, banner_y + height);
+            banner_y += height;
+        } else {
+            self.db_banner = Default::default();
+        }
+        if self.tiles.tileserver_offline() {
+            banner_y +=
+                self.draw_banner(config, &mut render_state, banner_y, TILESERVER_OFFLINE_MESSAGE);
+        }
+        if self.tiles.tileserver_rejected() {
+            let message = TILESERVER_REJECTED_MESSAGE;
+            let height = self.draw_banner(config, &mut render_state, banner_y, message);
+            self.tileserver_banner = (banner_y, banner_y + height);
+            banner_y += height;
+        } else {
+            self.tileserver_banner = Default::default();
+        }
+        if let Some((done, total)) = self.tiles.prefetch_progress() {
+            let text = format!("{PREFETCH_MESSAGE}: {done}/{total}");
+            banner_y += self.draw_banner(config, &mut render_state, banner_y, &text);
+        }
+        if let Some(path) = &self.trip_summary_path {
+            let text = format!("{TRIP_SUMMARY_MESSAGE} {}", path.display());
+            let height = self.draw_banner(config, &mut render_state, banner_y, &text);
+            self.trip_summary_banner = (banner_y, banner_y + height);
+            banner_y += height;
+        } else {
+            self.trip_summary_banner = Default::default();
+        }
+        let config_banner_y = banner_y;
+        for i in 0..self.config_warnings.len() {
+            let text = format!("{CONFIG_WARNING_PREFIX}: {}", self.config_warnings[i]);
+            banner_y += self.draw_banner(config, &mut render_state, banner_y, &text);
+        }
+        self.config_banner = (config_banner_y, banner_y);
+
+        // Render navigation instructions.
+        self.draw_route_instruction(config, &mut render_state);
+
+        // Render POI details.
+        self.draw_poi_callout(config, &mut render_state, &iter);
+
         // Render buttons.
         self.draw_buttons(config, &mut render_state);
 
+        // Render track playback controls.
+        self.draw_playback_slider(config, &mut render_state);
+
+        // Render runtime metrics.
+        if self.hud_enabled {
+            let metrics = HudMetrics {
+                cached_tiles: self.tiles.cached_count(),
+                loading_tiles: self.tiles.loading_count(),
+                active_queries: self.db.active_queries(),
+                gps_age: self.last_gps_fix.map(|fix| fix.elapsed()),
+            };
+            self.hud.draw(config, &mut render_state, self.size, self.scale, metrics);
+        }
+
         // If no downloads are pending, pre-download tiles just outside the viewport.
         #[cfg(feature = "profiling")]
         profiling::scope!("fetch_background_tiles");
@@ -1102,6 +2898,8 @@ impl UiView for MapView {
 
     fn dirty(&self) -> bool {
         self.dirty
+            || self.fly_to.is_some()
+            || self.marker_pulse.is_some()
             || self.touch_state.move_velocity.is_moving()
             || self.touch_state.zoom_velocity.is_moving()
     }
@@ -1113,7 +2911,14 @@ impl UiView for MapView {
 
         // Update UI elements.
         self.search_button.set_point(Self::search_button_point(size, self.scale));
+        self.close_button.set_point(Self::search_button_point(size, self.scale));
+        self.zoom_in_button.set_point(Self::zoom_in_button_point(size, self.scale));
+        self.zoom_out_button.set_point(Self::zoom_out_button_point(size, self.scale));
         self.gps_button.set_point(Self::gps_button_point(size, self.scale));
+        self.camera_button.set_point(Self::camera_button_point(size, self.scale));
+        self.lock_button.set_point(Self::lock_button_point(size, self.scale));
+        self.along_route_button.set_point(Self::along_route_button_point(size, self.scale));
+        self.mini_window_button.set_point(Self::mini_window_button_point(size, self.scale));
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
@@ -1124,28 +2929,119 @@ impl UiView for MapView {
         // Update UI elements.
         self.search_button.set_point(Self::search_button_point(self.size, scale));
         self.search_button.set_size(Self::button_size(scale));
+        self.close_button.set_point(Self::search_button_point(self.size, scale));
+        self.close_button.set_size(Self::button_size(scale));
+        self.zoom_in_button.set_point(Self::zoom_in_button_point(self.size, scale));
+        self.zoom_in_button.set_size(Self::button_size(scale));
+        self.zoom_out_button.set_point(Self::zoom_out_button_point(self.size, scale));
+        self.zoom_out_button.set_size(Self::button_size(scale));
         self.gps_button.set_point(Self::gps_button_point(self.size, scale));
         self.gps_button.set_size(Self::button_size(scale));
+        self.camera_button.set_point(Self::camera_button_point(self.size, scale));
+        self.camera_button.set_size(Self::button_size(scale));
+        self.lock_button.set_point(Self::lock_button_point(self.size, scale));
+        self.lock_button.set_size(Self::button_size(scale));
+        self.along_route_button.set_point(Self::along_route_button_point(self.size, scale));
+        self.along_route_button.set_size(Self::button_size(scale));
+        self.mini_window_button.set_point(Self::mini_window_button_point(self.size, scale));
+        self.mini_window_button.set_size(Self::button_size(scale));
+        self.callout_route_button.set_size(Self::callout_button_size(scale));
+        self.callout_favorite_button.set_size(Self::callout_button_size(scale));
         self.route_paint.set_stroke_width(ROUTE_WIDTH * scale as f32);
+        self.track_paint.set_stroke_width(TRACK_WIDTH * scale as f32);
+
+        // Request tiles matching the new resolution.
+        self.dirty |= self.tiles.set_scale_factor(scale);
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn touch_down(&mut self, slot: i32, time: u32, point: Point<f64>) {
         let point = point * self.scale;
 
-        // Cancel velocity/long-press if a new touch sequence starts.
+        // Ignore all touch input while locked, except for the unlock button.
+        let lock_touched = self.touch_state.slots.is_empty()
+            && self.show_lock_button()
+            && self.lock_button.contains(point);
+        if self.interaction_locked && !lock_touched {
+            return;
+        }
+
+        // Stop route preview on any interaction with the map.
+        if !self.interaction_locked {
+            self.stop_preview();
+        }
+
+        // Cancel animations/long-press if a new touch sequence starts.
         self.touch_state.clear_long_press(&self.event_loop);
         self.touch_state.move_velocity.stop();
         self.touch_state.zoom_velocity.stop();
+        self.fly_to = None;
 
         // Only allow at most 2 touch slots at a time.
         match self.touch_state.slots.len() {
+            0 if self.overlay_visible() && self.close_button.contains(point) => {
+                self.touch_state.action = TouchAction::CloseOverlay;
+            },
+            0 if self.playback_slider_contains(point) => {
+                self.touch_state.action = TouchAction::PlaybackSeek;
+
+                // Hold playback position while seeking.
+                self.pause_playback();
+                self.seek_playback(point.x);
+            },
             0 if self.search_button.contains(point) => {
                 self.touch_state.action = TouchAction::Search;
             },
             0 if self.gps_button.contains(point) => {
                 self.touch_state.action = TouchAction::Gps;
             },
+            0 if self.input_config.zoom_buttons && self.zoom_in_button.contains(point) => {
+                self.touch_state.action = TouchAction::ZoomIn;
+            },
+            0 if self.input_config.zoom_buttons && self.zoom_out_button.contains(point) => {
+                self.touch_state.action = TouchAction::ZoomOut;
+            },
+            0 if self.is_navigating() && self.camera_button.contains(point) => {
+                self.touch_state.action = TouchAction::CameraMode;
+            },
+            0 if self.is_navigating() && self.along_route_button.contains(point) => {
+                self.touch_state.action = TouchAction::AlongRoute;
+            },
+            0 if self.is_navigating()
+                && self.mini_window_supported
+                && self.mini_window_button.contains(point) =>
+            {
+                self.touch_state.action = TouchAction::MiniWindow;
+            },
+            0 if self.config_banner_contains(point) => {
+                self.touch_state.action = TouchAction::DismissConfigWarnings;
+            },
+            0 if self.trip_summary_banner_contains(point) => {
+                self.touch_state.action = TouchAction::DismissTripSummary;
+            },
+            0 if self.tileserver_banner_contains(point) => {
+                self.touch_state.action = TouchAction::RetryTileserver;
+            },
+            0 if self.db_banner_contains(point) => {
+                self.touch_state.action = TouchAction::RetryDb;
+            },
+            0 if self.attribution_contains(point) => {
+                self.touch_state.action = TouchAction::Attribution;
+            },
+            0 if lock_touched => {
+                self.touch_state.action = TouchAction::Lock;
+
+                // Require a long-press to unlock.
+                if self.interaction_locked {
+                    self.touch_state.stage_unlock(&self.event_loop, &self.input_config);
+                }
+            },
+            0 if self.callout_visible && self.callout_route_button.contains(point) => {
+                self.touch_state.action = TouchAction::CalloutRoute;
+            },
+            0 if self.callout_visible && self.callout_favorite_button.contains(point) => {
+                self.touch_state.action = TouchAction::CalloutFavorite;
+            },
             0 => {
                 // Calculate delta to last tap.
                 let elapsed =
@@ -1153,11 +3049,16 @@ impl UiView for MapView {
                 let delta = self.touch_state.last_point - point;
                 let distance = delta.x.powi(2) + delta.y.powi(2);
 
-                let action = if elapsed >= time && distance <= self.input_config.max_tap_distance {
+                let map_point = self.transform.screen_to_map(point);
+                let action = if let Some(marker) = self.marker_at(map_point) {
+                    self.touch_state.marker_point = map_point;
+                    TouchAction::Marker(marker)
+                } else if elapsed >= time && distance <= self.input_config.max_tap_distance {
                     TouchAction::DoubleTap
                 } else {
                     // Stage long-press only for initial tap action.
-                    self.touch_state.stage_long_press(&self.event_loop, &self.input_config, point);
+                    let input_config = &self.input_config;
+                    self.touch_state.stage_long_press(&self.event_loop, input_config, map_point);
 
                     TouchAction::Tap
                 };
@@ -1167,10 +3068,18 @@ impl UiView for MapView {
                 self.touch_state.last_time = time;
                 self.touch_state.last_point = point;
             },
-            1 => self.touch_state.action = TouchAction::Zoom,
+            1 => {
+                // Abort button presses once a second touch starts zooming.
+                self.dirty |= release_button(self.action_button());
+                self.touch_state.action = TouchAction::Zoom;
+            },
             _ => return,
         }
 
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
         // Update active touch slot.
         let slot = self.touch_state.slots.entry(slot).or_default();
         slot.point = point;
@@ -1194,13 +3103,13 @@ impl UiView for MapView {
             TouchAction::Tap | TouchAction::Drag => {
                 // Ignore dragging until tap distance limit is exceeded.
                 let max_tap_distance = self.input_config.max_tap_distance;
-                let delta = slot.point - slot.start;
-                if delta.x.powi(2) + delta.y.powi(2) <= max_tap_distance {
+                if !input::is_drag(slot.start, slot.point, max_tap_distance) {
                     return;
                 }
                 self.touch_state.action = TouchAction::Drag;
 
-                let delta = slot.point - old_point;
+                let new_point = self.transform.screen_to_map(slot.point);
+                let delta = new_point - self.transform.screen_to_map(old_point);
                 self.touch_state.move_velocity.set(delta);
                 self.move_by(delta);
 
@@ -1220,7 +3129,7 @@ impl UiView for MapView {
                 };
 
                 // Set zoom focus point to initial touch location.
-                self.touch_state.zoom_focus = slot.start;
+                self.touch_state.zoom_focus = self.transform.screen_to_map(slot.start);
 
                 self.zoom_by(scale);
             },
@@ -1234,7 +3143,8 @@ impl UiView for MapView {
                 };
 
                 // Ensure zoom's focus point is set.
-                self.touch_state.zoom_focus = (slot.start + slot2.start) * 0.5;
+                let zoom_focus = (slot.start + slot2.start) * 0.5;
+                self.touch_state.zoom_focus = self.transform.screen_to_map(zoom_focus);
 
                 // Calculate relative distance change.
 
@@ -1255,7 +3165,47 @@ impl UiView for MapView {
                 self.touch_state.velocity_zooming_in = distance > last_distance;
                 self.touch_state.zoom_velocity_distance = distance;
             },
-            TouchAction::Gps | TouchAction::Search | TouchAction::None => (),
+            TouchAction::Marker(marker) | TouchAction::MarkerDrag(marker) => {
+                // Ignore dragging until tap distance limit is exceeded.
+                let max_tap_distance = self.input_config.max_tap_distance;
+                if self.touch_state.action == TouchAction::Marker(marker)
+                    && !input::is_drag(slot.start, slot.point, max_tap_distance)
+                {
+                    return;
+                }
+                self.touch_state.action = TouchAction::MarkerDrag(marker);
+
+                self.touch_state.marker_point = self.transform.screen_to_map(point);
+                self.dirty = true;
+            },
+            TouchAction::CalloutFavorite
+            | TouchAction::CalloutRoute
+            | TouchAction::CameraMode
+            | TouchAction::AlongRoute
+            | TouchAction::Gps
+            | TouchAction::ZoomIn
+            | TouchAction::ZoomOut
+            | TouchAction::CloseOverlay
+            | TouchAction::Search => {
+                self.dirty |= move_button_touch(self.action_button(), point);
+            },
+            TouchAction::PlaybackSeek => self.seek_playback(point.x),
+            TouchAction::Lock => {
+                if self.lock_button.touch_motion(point) {
+                    self.dirty = true;
+                }
+
+                // Abort unlocking when leaving the button.
+                if !self.lock_button.contains(point) {
+                    self.touch_state.clear_long_press(&self.event_loop);
+                }
+            },
+            TouchAction::Attribution
+            | TouchAction::RetryDb
+            | TouchAction::RetryTileserver
+            | TouchAction::DismissConfigWarnings
+            | TouchAction::DismissTripSummary
+            | TouchAction::None => (),
         }
     }
 
@@ -1267,38 +3217,104 @@ impl UiView for MapView {
             None => return,
         };
 
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
         // Cancel pending long-press timers.
         self.touch_state.clear_long_press(&self.event_loop);
 
         match self.touch_state.action {
-            // On tap, snap zoom to nearest integer scale.
-            TouchAction::Tap => self.snap_zoom(),
+            // On tap, snap zoom to nearest integer scale and hide region outlines.
+            TouchAction::Tap => {
+                self.snap_zoom();
+
+                if self.coverage.take().is_some() {
+                    self.dirty = true;
+                }
+            },
             // Handle route/search button press.
             TouchAction::Search if self.search_button.contains(removed.point) => {
                 let view = if self.route.is_some() { View::Route } else { View::Search };
                 self.event_loop.insert_idle(move |state| state.window.set_view(view));
             },
-            // Handle GPS centering button press.
-            TouchAction::Gps if self.gps_button.contains(removed.point) => {
-                if let Some(RenderGeoPoint { point, .. }) = self.gps {
-                    let (tile, offset) = point.tile(self.cursor_tile.z);
-
-                    // Zoom in, if the GPS location is already centered.
-                    // Toggle GPS lock if the map is already zoomed in.
-                    if self.cursor_offset != offset || self.cursor_tile != tile {
-                        self.cursor_offset = offset;
-                        self.cursor_tile = tile;
-                        self.dirty = true;
-                    } else if self.cursor_tile.z != GPS_ZOOM {
-                        let (tile, offset) = point.tile(GPS_ZOOM);
-                        self.cursor_offset = offset;
-                        self.cursor_tile = tile;
-                        self.dirty = true;
-                    } else {
-                        self.gps_locked = !self.gps_locked;
-                        self.dirty = true;
-                    }
+            // Handle track playback and heatmap close button press.
+            TouchAction::CloseOverlay if self.close_button.contains(removed.point) => {
+                self.close_overlays();
+            },
+            // Continue track playback from the selected time.
+            TouchAction::PlaybackSeek => self.resume_playback(),
+            // Handle POI callout routing button press.
+            TouchAction::CalloutRoute if self.callout_route_button.contains(removed.point) => {
+                if let Some(RenderGeoPoint { point, .. }) = self.poi {
+                    self.event_loop.insert_idle(move |state| {
+                        state.window.views.search().route_to(point);
+                    });
+                }
+            },
+            // Handle POI callout favorite button press.
+            TouchAction::CalloutFavorite
+                if self.callout_favorite_button.contains(removed.point) =>
+            {
+                self.toggle_favorite();
+            },
+            // Place dragged marker at its new location, unless it was dropped
+            // where it started.
+            TouchAction::MarkerDrag(marker) => {
+                let max_tap_distance = self.input_config.max_tap_distance;
+                if input::is_drag(removed.start, removed.point, max_tap_distance) {
+                    let map_point = self.transform.screen_to_map(removed.point);
+                    self.move_marker(marker, self.screen_geo_point(map_point));
                 }
+                self.dirty = true;
+            },
+            // Handle camera mode button press.
+            TouchAction::CameraMode if self.camera_button.contains(removed.point) => {
+                self.cycle_camera_mode();
+            },
+            // Handle search along route button press.
+            TouchAction::AlongRoute if self.along_route_button.contains(removed.point) => {
+                self.search_along_route();
+            },
+            // Handle navigation overlay button press.
+            TouchAction::MiniWindow if self.mini_window_button.contains(removed.point) => {
+                self.event_loop.insert_idle(|state| {
+                    state.window.toggle_mini_window(&state.protocol_states);
+                });
+            },
+            // Handle zoom button presses.
+            TouchAction::ZoomIn if self.zoom_in_button.contains(removed.point) => {
+                self.step_zoom(true);
+            },
+            TouchAction::ZoomOut if self.zoom_out_button.contains(removed.point) => {
+                self.step_zoom(false);
+            },
+            // Handle GPS centering button press.
+            TouchAction::Gps if self.gps_button.contains(removed.point) => self.center_gps(),
+            // Hide configuration warnings on banner tap.
+            TouchAction::DismissConfigWarnings if self.config_banner_contains(removed.point) => {
+                self.set_config_warnings(Vec::new());
+            },
+            // Hide trip summary location on banner tap.
+            TouchAction::DismissTripSummary if self.trip_summary_banner_contains(removed.point) => {
+                self.trip_summary_path = None;
+                self.dirty = true;
+            },
+            // Retry opening the database on banner tap.
+            TouchAction::RetryDb if self.db_banner_contains(removed.point) => self.retry_db(),
+            // Retry downloading tiles on banner tap.
+            TouchAction::RetryTileserver if self.tileserver_banner_contains(removed.point) => {
+                self.tiles.retry_rejected();
+                self.dirty = true;
+            },
+            // Open attribution website on tap.
+            TouchAction::Attribution if self.attribution_contains(removed.point) => {
+                self.open_attribution();
+            },
+            // Handle map lock button press.
+            TouchAction::Lock
+                if !self.interaction_locked && self.lock_button.contains(removed.point) =>
+            {
+                self.set_interaction_locked(true);
             },
             _ => (),
         }
@@ -1317,6 +3333,35 @@ impl UiView for MapView {
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn update_config(&mut self, config: &Config) {
         self.dirty |= self.tiles.update_config(config);
+        self.dirty |= self.hud_enabled != config.debug.hud;
+        self.hud_enabled = config.debug.hud;
+        self.attribution_url = config.tiles.attribution_url.clone();
+
+        // Reset camera mode to the new default.
+        if self.navigation_config.camera_mode != config.navigation.camera_mode {
+            self.camera_mode = config.navigation.camera_mode;
+            self.camera_button.set_svg(camera_mode_svg(self.camera_mode));
+            self.dirty = true;
+
+            self.store_camera_mode();
+        }
+        self.navigation_config = config.navigation;
+
+        // Move into the tile source's zoom range.
+        let (min_zoom, max_zoom) = zoom_range(config);
+        if (self.min_zoom, self.max_zoom) != (min_zoom, max_zoom) {
+            (self.min_zoom, self.max_zoom) = (min_zoom, max_zoom);
+            self.fly_to = None;
+            self.jump_to(self.center_point(), self.render_zoom());
+        }
+
+        self.sharing.update_config(config);
+
+        // Reload hazards when their source changed.
+        if self.hazards_config.path != config.hazards.path {
+            load_hazards(config.hazards.path.clone(), self.hazards_tx.clone());
+        }
+        self.hazards_config = config.hazards.clone();
 
         if self.input_config != config.input {
             self.input_config = config.input;
@@ -1325,6 +3370,33 @@ impl UiView for MapView {
     }
 }
 
+/// Load hazard points in the background.
+///
+/// An empty path removes all hazards.
+fn load_hazards(path: Arc<String>, hazards_tx: channel::Sender<Vec<Hazard>>) {
+    if path.is_empty() {
+        let _ = hazards_tx.send(Vec::new());
+        return;
+    }
+
+    tokio::spawn(async move {
+        match hazards::load(&path).await {
+            Ok(hazards) => {
+                let _ = hazards_tx.send(hazards);
+            },
+            Err(err) => error!("Failed to load hazards from {path:?}: {err}"),
+        }
+    });
+}
+
+/// Get the zoom levels supported by the tile source.
+///
+/// Invalid ranges are clamped, since the config only warns about them.
+fn zoom_range(config: &Config) -> (u8, u8) {
+    let max_zoom = config.tiles.max_zoom.min(MAX_ZOOM);
+    (config.tiles.min_zoom.min(max_zoom), max_zoom)
+}
+
 /// Touch event tracking.
 #[derive(Default)]
 struct TouchState {
@@ -1341,6 +3413,8 @@ struct TouchState {
     zoom_velocity_distance: f64,
     velocity_zooming_in: bool,
     zoom_focus: Point<f64>,
+
+    marker_point: Point<f64>,
 }
 
 impl TouchState {
@@ -1366,6 +3440,23 @@ impl TouchState {
             token.inspect_err(|err| error!("Failed to stage long-press timer: {err}")).ok();
     }
 
+    /// Stage timer for unlocking map interactions.
+    fn stage_unlock(&mut self, event_loop: &LoopHandle<'static, State>, input_config: &Input) {
+        // Clear any previous timeouts.
+        self.clear_long_press(event_loop);
+
+        // Stage new callback.
+        let timer = Timer::from_duration(*input_config.long_press);
+        let token = event_loop.insert_source(timer, move |_, _, state| {
+            state.window.views.map().trigger_unlock();
+            state.window.unstall();
+            TimeoutAction::Drop
+        });
+
+        self.long_press_token =
+            token.inspect_err(|err| error!("Failed to stage unlock timer: {err}")).ok();
+    }
+
     /// Cancel active long-press timer.
     fn clear_long_press(&mut self, event_loop: &LoopHandle<'static, State>) {
         if let Some(token) = self.long_press_token.take() {
@@ -1387,14 +3478,53 @@ enum TouchAction {
     #[default]
     None,
 
+    CalloutFavorite,
+    CalloutRoute,
+    Attribution,
+    CameraMode,
+    AlongRoute,
+    MiniWindow,
+    RetryDb,
+    RetryTileserver,
+    DismissConfigWarnings,
+    DismissTripSummary,
+    CloseOverlay,
+    PlaybackSeek,
     DoubleTap,
     Search,
+    Lock,
     Drag,
+    Marker(Marker),
+    MarkerDrag(Marker),
     Zoom,
+    ZoomIn,
+    ZoomOut,
     Gps,
     Tap,
 }
 
+/// Map markers which can be moved by the user.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum Marker {
+    Poi,
+    RouteTarget,
+}
+
+/// Get the button icon for a camera mode.
+fn camera_mode_svg(mode: CameraMode) -> Svg {
+    match mode {
+        CameraMode::NorthUp => Svg::NorthUp,
+        CameraMode::HeadingUp => Svg::HeadingUp,
+        CameraMode::Perspective => Svg::Perspective,
+    }
+}
+
+/// POI details shown above its marker.
+struct PoiDetails {
+    title: String,
+    address: String,
+}
+
 /// Geographic point with a tile location cache.
 ///
 /// XXX: This is intentionally not `Copy`, to avoid accidentally updating the
@@ -1496,6 +3626,118 @@ fn nearest_point(start: GeoPoint, end: GeoPoint, point: GeoPoint) -> GeoPoint {
     GeoPoint::new(projection_point_lat, projection_point_lon)
 }
 
+/// Animated map movement.
+struct FlyTo {
+    tween: Tween,
+    start: GeoPoint,
+    start_zoom: f64,
+    end: GeoPoint,
+    end_zoom: f64,
+}
+
+/// Simulated navigation along a route.
+struct RoutePreview {
+    route: Arc<Route>,
+    is_gps_route: bool,
+    start: Instant,
+    timer: RegistrationToken,
+    /// Last real GPS location and heading received during the preview.
+    gps: Option<(GeoPoint, Option<f64>)>,
+}
+
+/// Map playback of a recorded track.
+struct TrackPlayback {
+    points: Vec<TrackPoint>,
+    render_points: Vec<RenderGeoPoint>,
+    /// Time between the first and last point in seconds.
+    duration: f64,
+    /// Playback position in seconds since the first point.
+    time: f64,
+    last_update: Instant,
+    timer: Option<RegistrationToken>,
+}
+
+impl TrackPlayback {
+    /// Get the playback position as fraction of the track's duration.
+    fn progress(&self) -> f64 {
+        if self.duration > 0. { (self.time / self.duration).min(1.) } else { 1. }
+    }
+}
+
+/// Visited area heatmap.
+#[derive(Default)]
+struct Heatmap {
+    cells: Vec<HeatmapCell>,
+    /// Highest point count of all cells.
+    max_count: u32,
+    /// Zoom level of the loaded cells.
+    zoom: u8,
+    /// Zoom level of the cells currently being loaded.
+    pending: Option<u8>,
+}
+
+impl Heatmap {
+    /// Replace the loaded cells.
+    fn set_cells(&mut self, zoom: u8, cells: Vec<HeatmapCell>) {
+        if self.pending == Some(zoom) {
+            self.pending = None;
+        }
+
+        self.max_count = cells.iter().map(|cell| cell.count).max().unwrap_or_default();
+        self.cells = cells;
+        self.zoom = zoom;
+    }
+}
+
+/// Get the tile, tile offset and relative size of a heatmap cell.
+///
+/// The returned size is relative to the tile size at `zoom`.
+fn heatmap_cell_tile(cell: &HeatmapCell, cell_zoom: u8, zoom: u8) -> (TileIndex, Point, f32) {
+    if cell_zoom >= zoom {
+        let depth = cell_zoom - zoom;
+        let mask = (1 << depth) - 1;
+
+        let tile = TileIndex::new(cell.x >> depth, cell.y >> depth, zoom);
+        let offset_x = ((cell.x & mask) as i32 * TILE_SIZE) >> depth;
+        let offset_y = ((cell.y & mask) as i32 * TILE_SIZE) >> depth;
+
+        (tile, Point::new(offset_x, offset_y), 1. / (1 << depth) as f32)
+    } else {
+        let depth = zoom - cell_zoom;
+        let tile = TileIndex::new(cell.x << depth, cell.y << depth, zoom);
+        (tile, Point::default(), (1 << depth) as f32)
+    }
+}
+
+/// Get the position after traveling along a route for `time` seconds.
+///
+/// Returns `None` once the end of the route has been reached.
+fn route_position(route: &Route, mut time: f64) -> Option<GeoPoint> {
+    for segment in &route.segments {
+        if time >= segment.time as f64 {
+            time -= segment.time as f64;
+            continue;
+        }
+
+        // Interpolate within the segment, assuming constant speed.
+        let mut remaining = segment.length as f64 * time / segment.time as f64;
+        for pair in segment.points.windows(2) {
+            let length = pair[0].distance(pair[1]) as f64;
+            if remaining < length {
+                let fraction = remaining / length;
+                let lat = pair[0].lat + (pair[1].lat - pair[0].lat) * fraction;
+                let lon = pair[0].lon + (pair[1].lon - pair[0].lon) * fraction;
+                return Some(GeoPoint::new(lat, lon));
+            }
+            remaining -= length;
+        }
+
+        return segment.points.last().copied();
+    }
+
+    None
+}
+
 /// Navigation instruction details.
 #[derive(Debug)]
 pub struct Instruction {
@@ -1520,6 +3762,10 @@ mod route {
     #[derive(Default)]
     pub struct MapRoute {
         points: Vec<RenderGeoPoint>,
+        /// Distance from the route's origin to every point in meters.
+        distances: Vec<u32>,
+        /// Indices of the points retained for each simplified zoom level.
+        simplified: Vec<Vec<usize>>,
         instructions: Vec<(usize, Instruction)>,
         has_gps_origin: bool,
         mode: RouteMode,
@@ -1544,6 +3790,63 @@ mod route {
                 // Add all points for this segment.
                 self.points.extend(segment.points.iter().map(|point| RenderGeoPoint::from(*point)));
             }
+
+            // Accumulate distances along the route.
+            let mut distance = 0;
+            let mut last_point = None;
+            self.distances = self
+                .points
+                .iter()
+                .map(|point| {
+                    distance += last_point.map_or(0, |last: GeoPoint| last.distance(point.point));
+                    last_point = Some(point.point);
+                    distance
+                })
+                .collect();
+
+            // Simplify every level based on the next more detailed one.
+            let mut indices: Vec<usize> = (0..self.points.len()).collect();
+            self.simplified = vec![Vec::new(); ROUTE_SIMPLIFIED_ZOOMS.len()];
+            for (level, zoom) in ROUTE_SIMPLIFIED_ZOOMS.iter().enumerate().rev() {
+                let pixels: Vec<_> =
+                    indices.iter().map(|&i| world_pixel(self.points[i].point, *zoom)).collect();
+                let retained = simplify(&pixels, ROUTE_SIMPLIFY_TOLERANCE);
+                indices = retained.into_iter().map(|i| indices[i]).collect();
+                self.simplified[level] = indices.clone();
+            }
+        }
+
+        /// Call `f` for every remaining point which should be drawn at a zoom
+        /// level.
+        ///
+        /// Points close to the current position are always passed in full
+        /// detail, while the rest of the route uses the simplified geometry
+        /// closest to the zoom level.
+        pub fn render_points(&mut self, zoom: u8, mut f: impl FnMut(&mut RenderGeoPoint, bool)) {
+            let last_index = self.points.len().saturating_sub(1);
+            let level = ROUTE_SIMPLIFIED_ZOOMS.iter().position(|level_zoom| *level_zoom >= zoom);
+
+            // Find the end of the full detail section.
+            let detail_end = match level {
+                Some(_) => {
+                    let start = self.distances.get(self.offset).copied().unwrap_or_default();
+                    let end = start.saturating_add(ROUTE_DETAIL_DISTANCE);
+                    self.distances.partition_point(|distance| *distance < end).max(self.offset)
+                },
+                None => self.points.len(),
+            };
+
+            for i in self.offset..detail_end {
+                f(&mut self.points[i], i == last_index);
+            }
+
+            if let Some(level) = level {
+                let indices = &self.simplified[level];
+                let start = indices.partition_point(|i| *i < detail_end);
+                for &i in &indices[start..] {
+                    f(&mut self.points[i], i == last_index);
+                }
+            }
         }
 
         /// Advance this route by `offset` points.
@@ -1593,6 +3896,11 @@ mod route {
             Instruction { text, length, time }
         }
 
+        /// Get the index of the current route segment.
+        pub fn segment_index(&self) -> usize {
+            self.instructions.iter().rposition(|(i, _)| *i <= self.offset).unwrap_or_default()
+        }
+
         /// Get the current progress in the route.
         ///
         /// Progress is defined as the number of traveled nodes.
@@ -1624,17 +3932,96 @@ mod route {
         pub fn mode(&mut self) -> RouteMode {
             self.mode
         }
+    }
+
+    /// Get a point's global pixel position at a zoom level.
+    fn world_pixel(point: GeoPoint, zoom: u8) -> (f64, f64) {
+        let (tile, offset) = point.tile(zoom);
+        let x = tile.x as f64 * TILE_SIZE as f64 + offset.x as f64;
+        let y = tile.y as f64 * TILE_SIZE as f64 + offset.y as f64;
+        (x, y)
+    }
+
+    /// Simplify a polyline using the Ramer-Douglas-Peucker algorithm.
+    ///
+    /// Returns the indices of all retained points in ascending order, which
+    /// always includes the first and last point.
+    pub(super) fn simplify(points: &[(f64, f64)], tolerance: f64) -> Vec<usize> {
+        if points.len() <= 2 {
+            return (0..points.len()).collect();
+        }
+
+        let mut retained = vec![false; points.len()];
+        retained[0] = true;
+        retained[points.len() - 1] = true;
+
+        // Recursively split sections at their most distant point.
+        let mut sections = vec![(0, points.len() - 1)];
+        while let Some((start, end)) = sections.pop() {
+            let mut max_distance = 0.;
+            let mut max_index = start;
+            for i in start + 1..end {
+                let distance = segment_distance(points[i], points[start], points[end]);
+                if distance > max_distance {
+                    max_distance = distance;
+                    max_index = i;
+                }
+            }
 
-        /// Get points remaining in this route.
-        pub fn len(&self) -> usize {
-            self.points[self.offset..].len()
+            if max_distance > tolerance {
+                retained[max_index] = true;
+                sections.push((start, max_index));
+                sections.push((max_index, end));
+            }
         }
+
+        retained.iter().enumerate().filter_map(|(i, retained)| retained.then_some(i)).collect()
+    }
+
+    /// Get the distance between a point and a line segment.
+    fn segment_distance(point: (f64, f64), start: (f64, f64), end: (f64, f64)) -> f64 {
+        let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+        let length_squared = dx * dx + dy * dy;
+
+        // Project the point onto the segment, clamping to its ends.
+        let t = if length_squared == 0. {
+            0.
+        } else {
+            (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length_squared).clamp(0., 1.)
+        };
+
+        let (x, y) = (start.0 + t * dx, start.1 + t * dy);
+        (point.0 - x).hypot(point.1 - y)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::router::Segment;
+
+    #[test]
+    fn preview_route_position() {
+        let segment = |points: Vec<GeoPoint>, time| {
+            let length = points.windows(2).map(|pair| pair[0].distance(pair[1])).sum();
+            Segment { points, time, length, instruction: Default::default() }
+        };
+        let route = Route {
+            segments: vec![
+                segment(vec![GeoPoint::new(0., 0.), GeoPoint::new(0., 0.01)], 100),
+                segment(vec![GeoPoint::new(0., 0.01), GeoPoint::new(0.01, 0.01)], 50),
+            ],
+            ..Default::default()
+        };
+
+        let position = route_position(&route, 50.).unwrap();
+        assert_eq!(position.distance(GeoPoint::new(0., 0.005)), 0);
+
+        let position = route_position(&route, 125.).unwrap();
+        assert_eq!(position.distance(GeoPoint::new(0.005, 0.01)), 0);
+
+        assert!(route_position(&route, 150.).is_none());
+    }
 
     #[test]
     fn nearest_segment_broken_route() {
@@ -1841,6 +4228,17 @@ mod tests {
         assert_eq!(index, 1);
     }
 
+    #[test]
+    fn route_simplification() {
+        // Nearly straight points are dropped, while corners are retained.
+        let points = [(0., 0.), (5., 0.5), (10., 0.), (10., 10.), (10., 20.)];
+        assert_eq!(route::simplify(&points, 1.), vec![0, 2, 4]);
+        assert_eq!(route::simplify(&points, 0.1), vec![0, 1, 2, 4]);
+
+        assert_eq!(route::simplify(&points[..2], 1.), vec![0, 1]);
+        assert_eq!(route::simplify(&[], 1.), Vec::<usize>::new());
+    }
+
     #[test]
     fn nearest_segment_real_route() {
         let route = vec![
@@ -1858,4 +4256,24 @@ mod tests {
         assert_eq!(distance, 5);
         assert_eq!(index, 4);
     }
+
+    #[test]
+    fn heatmap_cell_tiles() {
+        let cell = HeatmapCell { x: 43, y: 21, count: 1 };
+
+        let (tile, offset, size) = heatmap_cell_tile(&cell, 15, 12);
+        assert_eq!(tile, TileIndex::new(5, 2, 12));
+        assert_eq!(offset, Point::new(96, 160));
+        assert_eq!(size, 0.125);
+
+        let (tile, offset, size) = heatmap_cell_tile(&cell, 15, 15);
+        assert_eq!(tile, TileIndex::new(43, 21, 15));
+        assert_eq!(offset, Point::new(0, 0));
+        assert_eq!(size, 1.);
+
+        let (tile, offset, size) = heatmap_cell_tile(&cell, 15, 17);
+        assert_eq!(tile, TileIndex::new(172, 84, 17));
+        assert_eq!(offset, Point::new(0, 0));
+        assert_eq!(size, 4.);
+    }
 }
@@ -2,25 +2,47 @@
 
 use std::fmt::Write;
 use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
 
 use calloop::LoopHandle;
 use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
 
-use crate::config::Config;
+use crate::config::{Config, StartupView, Units};
 use crate::db::Db;
 use crate::geometry::{Point, Size};
 use crate::region::Regions;
 use crate::ui::skia::RenderState;
+use crate::ui::view::diagnostics::DiagnosticsView;
+use crate::ui::view::directions::DirectionsView;
 use crate::ui::view::download::DownloadView;
 use crate::ui::view::map::MapView;
+use crate::ui::view::onboarding::OnboardingView;
 use crate::ui::view::route::RouteView;
 use crate::ui::view::search::SearchView;
+use crate::ui::view::settings::SettingsView;
+use crate::ui::view::tracks::TracksView;
 use crate::{Error, State};
 
+/// Countries using miles for road distances.
+const IMPERIAL_COUNTRIES: [&str; 4] = ["GB", "LR", "MM", "US"];
+
+/// Meters per mile.
+const METERS_PER_MILE: f64 = 1_609.344;
+
+/// Feet per meter.
+const FEET_PER_METER: f64 = 3.28084;
+
+pub mod diagnostics;
+pub mod directions;
 pub mod download;
+#[cfg(test)]
+pub mod harness;
 pub mod map;
+pub mod onboarding;
 pub mod route;
 pub mod search;
+pub mod settings;
+pub mod tracks;
 
 pub trait UiView {
     /// Redraw the view.
@@ -56,6 +78,11 @@ pub trait UiView {
     /// Handle keyboard key press.
     fn press_key(&mut self, _raw: u32, _keysym: Keysym, _modifiers: Modifiers) {}
 
+    /// Check if a key press is handled by the view instead of global shortcuts.
+    fn captures_key(&self, _keysym: Keysym, _modifiers: Modifiers) -> bool {
+        false
+    }
+
     /// Paste text into the view.
     fn paste(&mut self, _text: &str) {}
 
@@ -107,17 +134,29 @@ pub enum View {
     #[default]
     Map,
     Route,
+    Directions,
     Search,
     Download,
+    Diagnostics,
+    Settings,
+    Onboarding,
+    Tracks,
 }
 
 /// UI view tracking.
 pub struct Views {
+    diagnostics: DiagnosticsView,
+    directions: DirectionsView,
+    onboarding: OnboardingView,
+    settings: SettingsView,
     download: DownloadView,
+    tracks: TracksView,
     search: SearchView,
     route: RouteView,
     map: MapView,
     active_view: View,
+
+    regions: Arc<Regions>,
 }
 
 impl Views {
@@ -130,20 +169,65 @@ impl Views {
         let client = crate::http_client()?;
 
         // Create geographic region manager.
-        let regions = Regions::new(event_loop.clone(), client.clone(), db.clone())?;
+        let index_url = config.search.region_index_url.clone();
+        let regions = Regions::new(event_loop.clone(), client.clone(), db.clone(), index_url)?;
 
         let download = DownloadView::new(event_loop.clone(), config, regions.clone(), size)?;
-        let search =
-            SearchView::new(event_loop.clone(), client.clone(), config, regions.clone(), size)?;
+        let search = SearchView::new(
+            event_loop.clone(),
+            client.clone(),
+            db.clone(),
+            config,
+            regions.clone(),
+            size,
+        )?;
+        let diagnostics = DiagnosticsView::new(event_loop.clone(), db.clone(), config, size)?;
+        let settings = SettingsView::new(event_loop.clone(), config, size)?;
+        let tracks = TracksView::new(event_loop.clone(), db.clone(), config, size)?;
+        let onboarding = OnboardingView::new(event_loop.clone(), db.clone(), config, size)?;
+        let directions = DirectionsView::new(event_loop.clone(), config, size)?;
         let route = RouteView::new(event_loop.clone(), config, size)?;
         let map = MapView::new(event_loop.clone(), client, db, config, size)?;
 
-        Ok(Self { download, search, route, map, active_view: Default::default() })
+        let mut views = Self {
+            diagnostics,
+            directions,
+            onboarding,
+            settings,
+            download,
+            tracks,
+            search,
+            route,
+            map,
+            regions,
+            active_view: Default::default(),
+        };
+
+        // Open the configured startup view.
+        if config.startup.view == StartupView::Search {
+            let (point, zoom) = (views.map.center_point(), views.map.zoom());
+            views.search.set_map_center(point, zoom);
+
+            views.set_view(View::Search);
+            views.enter();
+        }
+
+        Ok(views)
     }
 
     /// Get a mutable iterator over all views.
-    pub fn views_mut(&mut self) -> [&mut dyn UiView; 4] {
-        [&mut self.map, &mut self.route, &mut self.search, &mut self.download]
+    pub fn views_mut(&mut self) -> [&mut dyn UiView; 9] {
+        [
+            &mut self.map,
+            &mut self.route,
+            &mut self.directions,
+            &mut self.search,
+            &mut self.download,
+            &mut self.diagnostics,
+            &mut self.settings,
+            &mut self.onboarding,
+            &mut self.tracks,
+        ]
     }
 
     /// Update the active view.
@@ -151,11 +235,31 @@ impl Views {
         self.active_view = view;
     }
 
+    /// Get mutable access to the diagnostics view.
+    pub fn diagnostics(&mut self) -> &mut DiagnosticsView {
+        &mut self.diagnostics
+    }
+
+    /// Get mutable access to the directions view.
+    pub fn directions(&mut self) -> &mut DirectionsView {
+        &mut self.directions
+    }
+
     /// Get mutable access to the download view.
     pub fn download(&mut self) -> &mut DownloadView {
         &mut self.download
     }
 
+    /// Get mutable access to the onboarding view.
+    pub fn onboarding(&mut self) -> &mut OnboardingView {
+        &mut self.onboarding
+    }
+
+    /// Get mutable access to the tracks view.
+    pub fn tracks(&mut self) -> &mut TracksView {
+        &mut self.tracks
+    }
+
     /// Get mutable access to the search view.
     pub fn search(&mut self) -> &mut SearchView {
         &mut self.search
@@ -175,6 +279,11 @@ impl Views {
     pub fn active(&self) -> View {
         self.active_view
     }
+
+    /// Get the geographic region manager.
+    pub fn regions(&self) -> &Regions {
+        &self.regions
+    }
 }
 
 impl Deref for Views {
@@ -182,7 +291,12 @@ impl Deref for Views {
 
     fn deref(&self) -> &Self::Target {
         match self.active_view {
+            View::Diagnostics => &self.diagnostics,
+            View::Directions => &self.directions,
+            View::Onboarding => &self.onboarding,
+            View::Settings => &self.settings,
             View::Download => &self.download,
+            View::Tracks => &self.tracks,
             View::Search => &self.search,
             View::Route => &self.route,
             View::Map => &self.map,
@@ -193,7 +307,12 @@ impl Deref for Views {
 impl DerefMut for Views {
     fn deref_mut(&mut self) -> &mut Self::Target {
         match self.active_view {
+            View::Diagnostics => &mut self.diagnostics,
+            View::Directions => &mut self.directions,
+            View::Onboarding => &mut self.onboarding,
+            View::Settings => &mut self.settings,
             View::Download => &mut self.download,
+            View::Tracks => &mut self.tracks,
             View::Search => &mut self.search,
             View::Route => &mut self.route,
             View::Map => &mut self.map,
@@ -201,15 +320,56 @@ impl DerefMut for Views {
     }
 }
 
+/// Check whether a country uses miles for road distances.
+pub fn is_imperial_country(country_code: &str) -> bool {
+    IMPERIAL_COUNTRIES.contains(&country_code)
+}
+
 /// Format a distance targeting 3 visible digits.
-pub fn format_distance(w: &mut impl Write, distance: u32) {
-    let (unit, divisor) = match distance {
-        ..1_000 => ("m", 1.),
-        _ => ("km", 1000.),
+///
+/// Distances are formatted in metric units, unless `units` is imperial.
+pub fn format_distance(w: &mut impl Write, distance: u32, units: Units) {
+    let imperial = units == Units::Imperial;
+    let meters = distance as f64;
+    let (unit, distance) = match distance {
+        _ if imperial && meters < METERS_PER_MILE / 10. => {
+            ("ft", (meters * FEET_PER_METER).round())
+        },
+        _ if imperial => ("mi", meters / METERS_PER_MILE),
+        ..1_000 => ("m", meters),
+        _ => ("km", meters / 1000.),
     };
 
-    let distance = distance as f64 / divisor;
     let precision = 2usize.saturating_sub(distance.log10() as usize);
 
     let _ = write!(w, "{distance:.precision$} {unit}");
 }
+
+/// Format a speed given in km/h.
+pub fn format_speed(w: &mut impl Write, speed: f64, units: Units) {
+    if units == Units::Imperial {
+        let speed = speed * 1_000. / METERS_PER_MILE;
+        let _ = write!(w, "{speed:.0} mph");
+    } else {
+        let _ = write!(w, "{speed:.0} km/h");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distance_units() {
+        let format = |distance, units| {
+            let mut text = String::new();
+            format_distance(&mut text, distance, units);
+            text
+        };
+
+        assert_eq!(format(500, Units::Metric), "500 m");
+        assert_eq!(format(1_500, Units::Metric), "1.50 km");
+        assert_eq!(format(100, Units::Imperial), "328 ft");
+        assert_eq!(format(3_219, Units::Imperial), "2.00 mi");
+    }
+}
@@ -0,0 +1,417 @@
+//! First-run onboarding UI view.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Arc;
+
+use calloop::LoopHandle;
+use calloop::channel::{self, Event};
+use tracing::error;
+
+use crate::config::{Config, Input};
+use crate::db::Db;
+use crate::geometry::{GeoPoint, Point, Size};
+use crate::ui::skia::{RenderState, TextOptions};
+use crate::ui::view::{UiView, View};
+use crate::ui::{Button, Svg, move_button_touch, press_button, release_button};
+use crate::{Error, State};
+
+/// Button width and height at scale 1.
+const BUTTON_SIZE: u32 = 48;
+
+/// Padding around the screen edge at scale 1.
+const OUTSIDE_PADDING: u32 = 16;
+
+/// Title font size relative to the default.
+const TITLE_FONT_SIZE: f32 = 1.5;
+
+/// Vertical space between the title and the description at scale 1.
+const TITLE_PADDING: f32 = 16.;
+
+/// First-run onboarding UI view.
+pub struct OnboardingView {
+    text: String,
+    photon_url: Arc<String>,
+    valhalla_url: Arc<String>,
+    gps: Option<GeoPoint>,
+    online: bool,
+
+    online_button: Button,
+    download_button: Button,
+    done_button: Button,
+
+    touch_state: TouchState,
+    input_config: Input,
+
+    event_loop: LoopHandle<'static, State>,
+    db: Db,
+
+    size: Size,
+    scale: f64,
+
+    dirty: bool,
+}
+
+impl OnboardingView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn new(
+        event_loop: LoopHandle<'static, State>,
+        db: Db,
+        config: &Config,
+        size: Size,
+    ) -> Result<Self, Error> {
+        // Initialize UI elements.
+        let point = Self::done_button_point(size, 1.);
+        let button_size = Self::button_size(1.);
+        let done_button = Button::new(point, button_size, Svg::Check);
+
+        let point = Self::download_button_point(size, 1.);
+        let download_button = Button::new(point, button_size, Svg::Download);
+
+        let point = Self::online_button_point(size, 1.);
+        let online_button = Button::new(point, button_size, Svg::Online);
+
+        // Show onboarding until the user has completed it once.
+        let (onboarding_tx, onboarding_rx) = channel::channel();
+        event_loop.insert_source(onboarding_rx, |event, _, state| {
+            let Event::Msg(online) = event else { return };
+            match online {
+                // Apply the preference chosen during onboarding.
+                Some(online) => {
+                    state.window.views.onboarding().set_online(online);
+                    state.window.set_online_services(online);
+                },
+                None if state.window.views.active() == View::Map => {
+                    state.window.set_view(View::Onboarding);
+                },
+                None => (),
+            }
+        })?;
+        let onboarding_db = db.clone();
+        tokio::spawn(async move {
+            match onboarding_db.onboarding().await {
+                // Skip onboarding if its completion cannot be persisted.
+                Ok(None) if onboarding_db.read_only() || onboarding_db.in_memory() => {
+                    let _ = onboarding_tx.send(Some(true));
+                },
+                Ok(online) => {
+                    let _ = onboarding_tx.send(online);
+                },
+                Err(err) => error!("Failed to load onboarding state: {err}"),
+            }
+        });
+
+        let mut view = Self {
+            online_button,
+            download_button,
+            done_button,
+            event_loop,
+            size,
+            db,
+            photon_url: config.search.photon_url.clone(),
+            valhalla_url: config.search.valhalla_url.clone(),
+            input_config: config.input,
+            dirty: true,
+            scale: 1.,
+            touch_state: Default::default(),
+            text: Default::default(),
+            gps: Default::default(),
+            online: true,
+        };
+        view.update_text();
+
+        Ok(view)
+    }
+
+    /// Update the current GPS location.
+    pub fn set_gps(&mut self, point: Option<GeoPoint>) {
+        if self.gps != point {
+            self.gps = point;
+            self.update_text();
+        }
+    }
+
+    /// Update the preference for online services.
+    fn set_online(&mut self, online: bool) {
+        if self.online != online {
+            self.online = online;
+            self.online_button.set_svg(if online { Svg::Online } else { Svg::Offline });
+            self.update_text();
+        }
+    }
+
+    /// Toggle between online and offline-only services.
+    fn toggle_online(&mut self) {
+        let online = !self.online;
+        self.set_online(online);
+
+        self.event_loop.insert_idle(move |state| state.window.set_online_services(online));
+    }
+
+    /// Update the description of the current setup.
+    fn update_text(&mut self) {
+        let service = |url: &str| if url.is_empty() { "disabled" } else { url }.to_string();
+
+        self.text.clear();
+
+        let _ = writeln!(
+            self.text,
+            "Download your region to use maps, search and routing without a network connection."
+        );
+
+        let mode = if self.online { "online and offline" } else { "offline only" };
+        let _ = writeln!(self.text, "\nServices: {mode}, tap the globe to switch");
+
+        let _ = writeln!(self.text, "Online search: {}", service(&self.photon_url));
+        let _ = writeln!(self.text, "Online routing: {}", service(&self.valhalla_url));
+        let _ = writeln!(
+            self.text,
+            "These servers can be changed with `search.photon_url` and `search.valhalla_url` in \
+             charon.toml, or disabled with an empty URL."
+        );
+
+        let _ = match self.gps {
+            Some(gps) => write!(self.text, "\nGPS: {:.5}, {:.5}", gps.lat, gps.lon),
+            None => write!(self.text, "\nGPS: Waiting for location…"),
+        };
+
+        self.dirty = true;
+    }
+
+    /// Persist onboarding completion and switch to another view.
+    fn finish(&mut self, view: View) {
+        let db = self.db.clone();
+        let online = self.online;
+        tokio::spawn(async move {
+            if let Err(err) = db.set_onboarding_done(online).await {
+                error!("Failed to store onboarding state: {err}");
+            }
+        });
+
+        self.event_loop.insert_idle(move |state| {
+            state.window.set_view(view);
+            if view == View::Map {
+                state.window.views.map().center_gps();
+            }
+        });
+    }
+
+    /// Physical size of the UI SVG buttons.
+    fn button_size(scale: f64) -> Size {
+        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+    }
+
+    /// Physical location of the done button.
+    fn done_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+        let y = (physical_size.height - button_size.height) as i32 - padding;
+
+        Point::new(x, y)
+    }
+
+    /// Physical location of the region download button.
+    fn download_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::done_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Physical location of the online services button.
+    fn online_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::download_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Online => Some(&mut self.online_button),
+            TouchAction::Download => Some(&mut self.download_button),
+            TouchAction::Done => Some(&mut self.done_button),
+            TouchAction::Tap => None,
+        }
+    }
+}
+
+impl UiView for OnboardingView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
+        let size = self.size * self.scale;
+
+        render_state.clear(config.colors.background);
+
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as f32;
+        let text_width = size.width as f32 - 2. * outside_padding;
+        let text_options = Some(TextOptions::new().ellipsize(false));
+
+        // Draw the welcome title.
+        let mut builder =
+            render_state.paragraph(config.colors.foreground, TITLE_FONT_SIZE, text_options);
+        builder.add_text("Welcome to Charon");
+
+        let mut paragraph = builder.build();
+        paragraph.layout(text_width);
+        paragraph.paint(&render_state, Point::new(outside_padding, outside_padding));
+
+        // Draw the setup description below it.
+        let y = outside_padding + paragraph.height() + TITLE_PADDING * self.scale as f32;
+        let mut builder = render_state.paragraph(config.colors.foreground, 1., text_options);
+        builder.add_text(&self.text);
+
+        let mut paragraph = builder.build();
+        paragraph.layout(text_width);
+        paragraph.paint(&render_state, Point::new(outside_padding, y));
+
+        // Render navigation buttons.
+        self.online_button.draw(&mut render_state, config.colors.alt_background);
+        self.download_button.draw(&mut render_state, config.colors.alt_background);
+        self.done_button.draw(&mut render_state, config.colors.alt_background);
+
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.online_button.set_point(Self::online_button_point(size, self.scale));
+        self.download_button.set_point(Self::download_button_point(size, self.scale));
+        self.done_button.set_point(Self::done_button_point(size, self.scale));
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.online_button.set_point(Self::online_button_point(self.size, scale));
+        self.online_button.set_size(Self::button_size(scale));
+        self.download_button.set_point(Self::download_button_point(self.size, scale));
+        self.download_button.set_size(Self::button_size(scale));
+        self.done_button.set_point(Self::done_button_point(self.size, scale));
+        self.done_button.set_size(Self::button_size(scale));
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_down(&mut self, slot: i32, _time: u32, point: Point<f64>) {
+        // Only allow a single active touch slot.
+        if !self.touch_state.slots.is_empty() {
+            return;
+        }
+
+        // Determine goal of this touch sequence.
+        let point = point * self.scale;
+        self.touch_state.action = if self.online_button.contains(point) {
+            TouchAction::Online
+        } else if self.download_button.contains(point) {
+            TouchAction::Download
+        } else if self.done_button.contains(point) {
+            TouchAction::Done
+        } else {
+            TouchAction::Tap
+        };
+
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
+        // Convert position to physical space.
+        let slot = self.touch_state.slots.entry(slot).or_default();
+        slot.point = point;
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_motion(&mut self, slot: i32, point: Point<f64>) {
+        // Ignore unknown touch slots.
+        let slot = match self.touch_state.slots.get_mut(&slot) {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        // Update touch point.
+        let point = point * self.scale;
+        slot.point = point;
+
+        self.dirty |= move_button_touch(self.action_button(), point);
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_up(&mut self, slot: i32) {
+        // Reset touch slot, ignoring unknown slots.
+        let removed = match self.touch_state.slots.remove(&slot) {
+            Some(removed) => removed,
+            None => return,
+        };
+
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
+        // Dispatch tap actions on release.
+        match self.touch_state.action {
+            // Switch between online and offline-only services.
+            TouchAction::Online if self.online_button.contains(removed.point) => {
+                self.toggle_online();
+            },
+            // Open the region download view.
+            TouchAction::Download if self.download_button.contains(removed.point) => {
+                self.finish(View::Download);
+            },
+            // Close onboarding and show the map.
+            TouchAction::Done if self.done_button.contains(removed.point) => {
+                self.finish(View::Map);
+            },
+            _ => (),
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn update_config(&mut self, config: &Config) {
+        self.input_config = config.input;
+        self.photon_url = config.search.photon_url.clone();
+        self.valhalla_url = config.search.valhalla_url.clone();
+        self.update_text();
+    }
+}
+
+/// Touch event tracking.
+#[derive(Default)]
+struct TouchState {
+    slots: HashMap<i32, TouchSlot>,
+    action: TouchAction,
+}
+
+/// Touch slot state.
+#[derive(Copy, Clone, Default, Debug)]
+struct TouchSlot {
+    point: Point<f64>,
+}
+
+/// Intention of a touch sequence.
+#[derive(PartialEq, Eq, Default)]
+enum TouchAction {
+    #[default]
+    Tap,
+    Online,
+    Download,
+    Done,
+}
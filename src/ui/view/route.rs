@@ -8,13 +8,13 @@ use calloop::LoopHandle;
 use skia_safe::textlayout::{Paragraph, TextAlign};
 use skia_safe::{Color4f, Paint, Rect};
 
-use crate::config::{Color, Config, Input};
+use crate::config::{Color, Config, Input, Units};
 use crate::geometry::{Point, Size};
-use crate::router::{Mode as RouteMode, Route, Segment};
+use crate::router::{Route, Segment};
 use crate::ui::skia::{RenderState, TextOptions};
 use crate::ui::view::search::RouteOrigin;
 use crate::ui::view::{self, UiView, View};
-use crate::ui::{Button, Svg, Velocity};
+use crate::ui::{Button, Svg, Velocity, move_button_touch, press_button, release_button};
 use crate::{Error, State};
 
 /// Button width and height at scale 1.
@@ -43,6 +43,9 @@ pub struct RouteView {
     scroll_to_progress: bool,
     progress: usize,
 
+    preview_button: Button,
+    prefetch_button: Button,
+    directions_button: Button,
     cancel_button: Button,
     back_button: Button,
     mode_button: Button,
@@ -52,6 +55,7 @@ pub struct RouteView {
     touch_state: TouchState,
     input_config: Input,
     scroll_offset: f64,
+    units: Units,
 
     event_loop: LoopHandle<'static, State>,
 
@@ -81,6 +85,18 @@ impl RouteView {
         let size = Self::button_size(1.);
         let cancel_button = Button::new(point, size, Svg::CancelRoute);
 
+        let point = Self::preview_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let preview_button = Button::new(point, size, Svg::Preview);
+
+        let point = Self::prefetch_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let prefetch_button = Button::new(point, size, Svg::Download);
+
+        let point = Self::directions_button_point(size, 1.);
+        let size = Self::button_size(1.);
+        let directions_button = Button::new(point, size, Svg::Directions);
+
         let mut alt_bg_paint = Paint::default();
         alt_bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
 
@@ -88,6 +104,9 @@ impl RouteView {
         hl_paint.set_color4f(Color4f::from(config.colors.highlight), None);
 
         Ok(Self {
+            directions_button,
+            preview_button,
+            prefetch_button,
             cancel_button,
             alt_bg_paint,
             back_button,
@@ -96,6 +115,7 @@ impl RouteView {
             hl_paint,
             size,
             input_config: config.input,
+            units: config.navigation.units,
             dirty: true,
             scale: 1.,
             scroll_to_progress: Default::default(),
@@ -121,6 +141,16 @@ impl RouteView {
         self.dirty = true;
     }
 
+    /// Get the active route.
+    pub fn route(&self) -> &Route {
+        &self.route
+    }
+
+    /// Get the number of nodes already traveled in the route.
+    pub fn progress(&self) -> usize {
+        self.progress
+    }
+
     /// Set the number of nodes already traveled in the route.
     pub fn set_progress(&mut self, progress: usize) {
         self.dirty |= self.progress != progress;
@@ -166,10 +196,43 @@ impl RouteView {
         point
     }
 
+    /// Physical location of the preview button.
+    fn preview_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::cancel_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Physical location of the map download button.
+    fn prefetch_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+
+        Point::new(x, padding)
+    }
+
+    /// Physical location of the text directions button.
+    fn directions_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::prefetch_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
     /// Physical location of the route summary text.
     fn summary_label_point(&self) -> Point {
         let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
-        let button_point = Self::cancel_button_point(self.size, self.scale);
+        let button_point = Self::preview_button_point(self.size, self.scale);
 
         Point::new(outside_padding, button_point.y)
     }
@@ -177,7 +240,7 @@ impl RouteView {
     /// Physical size of the route summary text.
     fn summary_label_size(&self) -> Size {
         let padding = (OUTSIDE_PADDING as f64 * self.scale).round() as u32;
-        let button_point = Self::cancel_button_point(self.size, self.scale);
+        let button_point = Self::preview_button_point(self.size, self.scale);
         let button_size = Self::button_size(self.scale);
 
         Size::new(button_point.x as u32 - 2 * padding, button_size.height)
@@ -212,6 +275,19 @@ impl RouteView {
         let back_button_point = Self::back_button_point(self.size, self.scale);
         total_height.saturating_sub(back_button_point.y as usize)
     }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Preview => Some(&mut self.preview_button),
+            TouchAction::Prefetch => Some(&mut self.prefetch_button),
+            TouchAction::Directions => Some(&mut self.directions_button),
+            TouchAction::Cancel => Some(&mut self.cancel_button),
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Mode => Some(&mut self.mode_button),
+            TouchAction::Tap | TouchAction::Drag => None,
+        }
+    }
 }
 
 impl UiView for RouteView {
@@ -220,7 +296,7 @@ impl UiView for RouteView {
         let size = self.size * self.scale;
 
         // Apply scroll velocity.
-        if let Some(delta) = self.touch_state.velocity.apply(&self.input_config) {
+        if let Some(delta) = self.touch_state.velocity.apply(self.input_config.inertia()) {
             self.scroll_offset += delta.y;
         }
 
@@ -325,7 +401,7 @@ impl UiView for RouteView {
         let minutes = (self.route.time % 3600 + 30) / 60;
 
         let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
-        builder.add_text(format!("{hours:0>2}:{minutes:0>2}"));
+        builder.add_text(format!("{hours:0>2}:{minutes:0>2} · {}", self.route.provider));
 
         let mut time_paragraph = builder.build();
         time_paragraph.layout(label_size.width);
@@ -333,7 +409,7 @@ impl UiView for RouteView {
         // Layout overall route distance paragraph.
 
         let mut distance = String::with_capacity("X.XX km".len());
-        view::format_distance(&mut distance, self.route.length);
+        view::format_distance(&mut distance, self.route.length, config.navigation.units);
 
         let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
         builder.add_text(&distance);
@@ -353,6 +429,9 @@ impl UiView for RouteView {
         time_paragraph.paint(&render_state, label_point);
 
         // Render navigation button.
+        self.preview_button.draw(&mut render_state, config.colors.alt_background);
+        self.prefetch_button.draw(&mut render_state, config.colors.alt_background);
+        self.directions_button.draw(&mut render_state, config.colors.alt_background);
         self.cancel_button.draw(&mut render_state, config.colors.alt_background);
         self.mode_button.draw(&mut render_state, config.colors.alt_background);
         self.back_button.draw(&mut render_state, config.colors.alt_background);
@@ -374,6 +453,9 @@ impl UiView for RouteView {
         self.dirty = true;
 
         // Update UI elements.
+        self.preview_button.set_point(Self::preview_button_point(size, self.scale));
+        self.prefetch_button.set_point(Self::prefetch_button_point(size, self.scale));
+        self.directions_button.set_point(Self::directions_button_point(size, self.scale));
         self.cancel_button.set_point(Self::cancel_button_point(size, self.scale));
         self.mode_button.set_point(Self::mode_button_point(size, self.scale));
         self.back_button.set_point(Self::back_button_point(size, self.scale));
@@ -385,6 +467,12 @@ impl UiView for RouteView {
         self.dirty = true;
 
         // Update UI elements.
+        self.preview_button.set_point(Self::preview_button_point(self.size, scale));
+        self.preview_button.set_size(Self::button_size(scale));
+        self.prefetch_button.set_point(Self::prefetch_button_point(self.size, scale));
+        self.prefetch_button.set_size(Self::button_size(scale));
+        self.directions_button.set_point(Self::directions_button_point(self.size, scale));
+        self.directions_button.set_size(Self::button_size(scale));
         self.cancel_button.set_point(Self::cancel_button_point(self.size, scale));
         self.cancel_button.set_size(Self::button_size(scale));
         self.back_button.set_point(Self::back_button_point(self.size, scale));
@@ -405,7 +493,13 @@ impl UiView for RouteView {
 
         // Determine goal of this touch sequence.
         let point = point * self.scale;
-        self.touch_state.action = if self.cancel_button.contains(point) {
+        self.touch_state.action = if self.preview_button.contains(point) {
+            TouchAction::Preview
+        } else if self.prefetch_button.contains(point) {
+            TouchAction::Prefetch
+        } else if self.directions_button.contains(point) {
+            TouchAction::Directions
+        } else if self.cancel_button.contains(point) {
             TouchAction::Cancel
         } else if self.back_button.contains(point) {
             TouchAction::Back
@@ -415,6 +509,10 @@ impl UiView for RouteView {
             TouchAction::Tap
         };
 
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
         // Convert position to physical space.
         let slot = self.touch_state.slots.entry(slot).or_default();
         slot.point = point;
@@ -455,6 +553,8 @@ impl UiView for RouteView {
 
             // Cancel automatic progress tracking on manual scroll.
             self.scroll_to_progress = false;
+        } else {
+            self.dirty |= move_button_touch(self.action_button(), point);
         }
     }
 
@@ -466,6 +566,9 @@ impl UiView for RouteView {
             None => return,
         };
 
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
         // Dispatch tap actions on release.
         match self.touch_state.action {
             // Handle route cancel button.
@@ -475,6 +578,35 @@ impl UiView for RouteView {
                     state.window.set_view(View::Map);
                 });
             },
+            // Handle route preview button.
+            TouchAction::Preview if self.preview_button.contains(removed.point) => {
+                let route = self.route.clone();
+                let is_gps_route = self.is_gps_route;
+                self.event_loop.insert_idle(move |state| {
+                    state.window.views.map().start_preview(route, is_gps_route);
+                    state.window.set_view(View::Map);
+                });
+            },
+            // Download map tiles for the remaining route.
+            TouchAction::Prefetch if self.prefetch_button.contains(removed.point) => {
+                let route = self.route.clone();
+                let progress = self.progress;
+                self.event_loop.insert_idle(move |state| {
+                    state.window.views.map().prefetch_route(&route, progress);
+                    state.window.set_view(View::Map);
+                });
+            },
+            // Open the textual navigation view.
+            TouchAction::Directions if self.directions_button.contains(removed.point) => {
+                let route = self.route.clone();
+                let progress = self.progress;
+                self.event_loop.insert_idle(move |state| {
+                    let directions_view = state.window.views.directions();
+                    directions_view.set_route(route);
+                    directions_view.set_progress(progress);
+                    state.window.set_view(View::Directions);
+                });
+            },
             // Handle "back" button navigation.
             TouchAction::Back if self.back_button.contains(removed.point) => {
                 self.event_loop.insert_idle(|state| state.window.set_view(View::Map));
@@ -491,10 +623,7 @@ impl UiView for RouteView {
                     None => return,
                 };
 
-                let mode = match self.route.mode {
-                    RouteMode::Pedestrian => RouteMode::Auto,
-                    RouteMode::Auto => RouteMode::Pedestrian,
-                };
+                let mode = self.route.mode.next();
 
                 self.event_loop.insert_idle(move |state| {
                     state.window.views.search().route(origin, target, mode)
@@ -510,6 +639,13 @@ impl UiView for RouteView {
             self.input_config = config.input;
             self.dirty = true;
         }
+
+        // Force segment relayout, to apply unit changes.
+        if self.units != config.navigation.units {
+            self.units = config.navigation.units;
+            self.segments.clear();
+            self.dirty = true;
+        }
     }
 
     fn enter(&mut self) {
@@ -531,6 +667,7 @@ struct RenderSegment {
     time_height: f32,
 
     foreground: Color,
+    units: Units,
     node_count: usize,
 
     inside_padding: f32,
@@ -591,6 +728,7 @@ impl RenderSegment {
             foreground,
             text_width,
             height,
+            units: config.navigation.units,
             width,
             node_count: segment.points.len(),
             length: segment.length,
@@ -614,7 +752,7 @@ impl RenderSegment {
         // determine a segment's height, since it matches the distance height.
 
         let mut distance = String::with_capacity("X.XX km".len());
-        view::format_distance(&mut distance, self.length);
+        view::format_distance(&mut distance, self.length, self.units);
 
         let text_options = Some(TextOptions::new().align(TextAlign::Right));
         let mut builder = render_state.paragraph(self.foreground, ALT_FONT_SIZE, text_options);
@@ -661,7 +799,7 @@ impl RenderSegment {
     fn distance_paragraph<'a>(&mut self, render_state: &mut RenderState<'a>) -> &Paragraph {
         self.distance_paragraph.get_or_insert_with(|| {
             let mut distance = String::with_capacity("X.XX km".len());
-            view::format_distance(&mut distance, self.length);
+            view::format_distance(&mut distance, self.length, self.units);
 
             let text_options = Some(TextOptions::new().align(TextAlign::Right));
             let mut builder = render_state.paragraph(self.foreground, ALT_FONT_SIZE, text_options);
@@ -697,6 +835,9 @@ enum TouchAction {
     #[default]
     Tap,
     Drag,
+    Preview,
+    Prefetch,
+    Directions,
     Cancel,
     Back,
     Mode,
@@ -2,25 +2,34 @@
 
 use std::borrow::Cow;
 use std::collections::HashMap;
-use std::fmt::Write;
 use std::mem;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicU64, Ordering};
 
 use calloop::LoopHandle;
+use calloop::channel::{self, Event};
 use reqwest::Client;
 use skia_safe::textlayout::TextAlign;
 use skia_safe::{Color4f, Paint, Rect};
 use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
+use tokio::sync::watch;
+use tracing::error;
 
 use crate::config::{Config, Input};
-use crate::geocoder::{Geocoder, QueryResult, ReverseQuery, SearchQuery};
+use crate::db::{Db, DestinationKey, Favorite};
+use crate::entity_type::{Category, EntityType};
+use crate::geocoder::{
+    CorridorQuery, Geocoder, QueryResult, QueryResultRank, ReverseQuery, SearchQuery,
+};
 use crate::geometry::{GeoPoint, Point, Size};
 use crate::region::Regions;
-use crate::router::{Mode as RouteMode, Router, RoutingQuery};
+use crate::router::{MatrixQuery, Mode as RouteMode, Router, RoutingQuery};
 use crate::ui::skia::{RenderState, TextOptions};
 use crate::ui::view::{self, UiView, View};
-use crate::ui::{Button, Svg, TextField, Velocity};
+use crate::ui::{
+    Button, RowProvider, ScrollList, Suggestions, Svg, TextField, move_button_touch, press_button,
+    release_button,
+};
 use crate::{Error, State};
 
 /// Padding around the screen edge at scale 1.
@@ -41,15 +50,46 @@ const RESULTS_HEIGHT: u32 = 100;
 /// Size of the routing button inside geocoding search results at scale 1.
 const ROUTING_BUTTON_SIZE: u32 = 32;
 
+/// Size of the entity category icon inside geocoding search results at scale 1.
+const CATEGORY_ICON_SIZE: u32 = 24;
+
 /// Padding between text inside the result entries at scale 1.
 const TEXT_PADDING: f64 = 3.;
 
+/// Entity type prefix for typo-corrected search results.
+const SUGGESTION_PREFIX: &str = "Did you mean? · ";
+
+/// Loop route length in meters, when none was entered.
+const DEFAULT_LOOP_LENGTH: f64 = 5_000.;
+
+/// Bearing change in degrees between consecutive loop routes.
+const LOOP_BEARING_STEP: f64 = 137.5;
+
+/// Entity type of favorite locations.
+const FAVORITE_ENTITY_TYPE: EntityType =
+    EntityType { name: "Favorite", category: Category::Favorite };
+
 /// Search state text font size relative to the default.
 const SEARCH_STATE_FONT_SIZE: f32 = 1.2;
 
 /// Search result address text font size relative to the default.
 const ADDRESS_FONT_SIZE: f32 = 0.6;
 
+/// Number of placeholder entries shown while a search is in progress.
+const SKELETON_COUNT: usize = 3;
+
+/// Minimum character count of partial queries searched for offline suggestions.
+const MIN_OFFLINE_SUGGESTION_LEN: usize = 3;
+
+/// Placeholder entry title bar height at scale 1.
+const SKELETON_TITLE_HEIGHT: f64 = 18.;
+
+/// Placeholder entry address bar height at scale 1.
+const SKELETON_ADDRESS_HEIGHT: f64 = 12.;
+
+/// Placeholder entry bar opacity.
+const SKELETON_ALPHA: f32 = 0.25;
+
 /// Search UI view.
 pub struct SearchView {
     event_loop: LoopHandle<'static, State>,
@@ -63,21 +103,37 @@ pub struct SearchView {
     pending_reverse: bool,
     route_origin: Option<RouteOrigin>,
     route_mode: RouteMode,
+    route_mode_selected: bool,
+    loop_bearing: f64,
+    destination_modes: HashMap<DestinationKey, RouteMode>,
     gps: Option<GeoPoint>,
+    favorites_tx: channel::Sender<Vec<Favorite>>,
+    history: Vec<String>,
+    offline_suggestions: Vec<String>,
+    suggested_text: String,
+    db: Db,
 
     cancel_route_button: Button,
     route_mode_button: Button,
+    loop_button: Button,
+    diagnostics_button: Button,
+    favorites_button: Button,
+    settings_button: Button,
+    tracks_button: Button,
     search_field: TextField,
+    suggestions: Suggestions,
     config_button: Button,
     search_button: Button,
     back_button: Button,
     gps_button: Button,
+    retry_button: Button,
     bg_paint: Paint,
+    skeleton_paint: Paint,
     error: &'static str,
 
     touch_state: TouchState,
     input_config: Input,
-    scroll_offset: f64,
+    result_list: ScrollList,
 
     keyboard_focused: bool,
     search_focused: bool,
@@ -94,12 +150,58 @@ impl SearchView {
     pub fn new(
         event_loop: LoopHandle<'static, State>,
         client: Client,
+        db: Db,
         config: &Config,
         regions: Arc<Regions>,
         size: Size,
     ) -> Result<Self, Error> {
         let geocoder = Geocoder::new(event_loop.clone(), config, client.clone(), regions.clone())?;
-        let router = Router::new(event_loop.clone(), config, client, regions)?;
+        let router = Router::new(event_loop.clone(), config, client, db.clone(), regions)?;
+
+        // Load preferred travel modes in the background.
+        let (modes_tx, modes_rx) = channel::channel();
+        event_loop.insert_source(modes_rx, |event, _, state| {
+            if let Event::Msg(modes) = event {
+                state.window.views.search().destination_modes = modes;
+            }
+        })?;
+        let modes_db = db.clone();
+        tokio::spawn(async move {
+            match modes_db.destination_modes().await {
+                Ok(modes) => {
+                    let _ = modes_tx.send(modes);
+                },
+                Err(err) => error!("Failed to load destination travel modes: {err}"),
+            }
+        });
+
+        // Load search history for suggestions in the background.
+        let (history_tx, history_rx) = channel::channel();
+        event_loop.insert_source(history_rx, |event, _, state| {
+            if let Event::Msg(history) = event {
+                let search = state.window.views.search();
+                search.history = history;
+                search.update_suggestions();
+            }
+        })?;
+        let history_db = db.clone();
+        tokio::spawn(async move {
+            match history_db.search_history().await {
+                Ok(history) => {
+                    let _ = history_tx.send(history);
+                },
+                Err(err) => error!("Failed to load search history: {err}"),
+            }
+        });
+
+        // Handle favorites loaded for the favorites list.
+        let (favorites_tx, favorites_rx) = channel::channel();
+        event_loop.insert_source(favorites_rx, |event, _, state| {
+            if let Event::Msg(favorites) = event {
+                state.window.views.search().show_favorites(favorites);
+                state.window.unstall();
+            }
+        })?;
 
         // Initialize UI elements.
 
@@ -119,6 +221,18 @@ impl SearchView {
         let point = Self::gps_button_point(size, 1.);
         let gps_button = Button::new(point, button_size, Svg::Gps);
 
+        let point = Self::diagnostics_button_point(size, 1.);
+        let diagnostics_button = Button::new(point, button_size, Svg::Info);
+
+        let point = Self::favorites_button_point(size, 1.);
+        let favorites_button = Button::new(point, button_size, Svg::Star);
+
+        let point = Self::settings_button_point(size, 1.);
+        let settings_button = Button::new(point, button_size, Svg::Sliders);
+
+        let point = Self::tracks_button_point(size, 1.);
+        let tracks_button = Button::new(point, button_size, Svg::Track);
+
         let point = Self::cancel_route_button_point(size, 1.);
         let cancel_route_button = Button::new(point, button_size, Svg::CancelRoute);
 
@@ -126,17 +240,37 @@ impl SearchView {
         let point = Self::route_mode_button_point(size, 1.);
         let route_mode_button = Button::new(point, button_size, route_mode.svg());
 
+        let point = Self::loop_button_point(size, 1.);
+        let loop_button = Button::new(point, button_size, Svg::Loop);
+
+        let point = Self::retry_button_point(size, 1.);
+        let retry_button = Button::new(point, button_size, Svg::Retry);
+
         let search_size = Self::search_field_size(size, 1.);
         let point = Self::search_field_point(size, 1.);
         let mut search_field = TextField::new(event_loop.clone(), point, search_size, 1.);
         search_field.set_placeholder("Search…");
 
-        Ok(Self {
+        let point = Self::suggestions_point(size, 1.);
+        let suggestions = Suggestions::new(point, Self::suggestions_width(size, 1.));
+
+        let result_list =
+            ScrollList::new(event_loop.clone(), RESULTS_Y_PADDING, OUTSIDE_PADDING as f64);
+
+        let mut search_view = Self {
             cancel_route_button,
             route_mode_button,
+            loop_button,
+            diagnostics_button,
+            favorites_button,
+            settings_button,
+            tracks_button,
             config_button,
             search_button,
             search_field,
+            suggestions,
+            result_list,
+            retry_button,
             back_button,
             event_loop,
             gps_button,
@@ -144,23 +278,34 @@ impl SearchView {
             bg_paint,
             geocoder,
             router,
+            favorites_tx,
             size,
+            db,
             input_config: config.input,
             search_focused: true,
             dirty: true,
             scale: 1.,
+            skeleton_paint: Default::default(),
             keyboard_focused: Default::default(),
             map_center_point: Default::default(),
             map_center_zoom: Default::default(),
             pending_reverse: Default::default(),
-            scroll_offset: Default::default(),
             ime_focused: Default::default(),
             touch_state: Default::default(),
             last_query: Default::default(),
             route_origin: Default::default(),
+            route_mode_selected: Default::default(),
+            loop_bearing: Default::default(),
+            destination_modes: Default::default(),
+            history: Default::default(),
+            offline_suggestions: Default::default(),
+            suggested_text: Default::default(),
             error: Default::default(),
             gps: Default::default(),
-        })
+        };
+        search_view.update_result_list();
+
+        Ok(search_view)
     }
 
     /// Get mutable access to the geocoder.
@@ -190,6 +335,12 @@ impl SearchView {
         self.gps = point;
     }
 
+    /// Update the offline entity titles matching the partial search query.
+    pub fn set_offline_suggestions(&mut self, suggestions: Vec<String>) {
+        self.offline_suggestions = suggestions;
+        self.update_suggestions();
+    }
+
     /// Set an error message indicating that an operation has failed.
     pub fn set_error(&mut self, error: &'static str) {
         self.dirty |= self.error != error;
@@ -199,12 +350,15 @@ impl SearchView {
     /// Submit current search field text for geocoding.
     pub fn submit_search(&mut self) {
         self.last_query = self.search_field.text().to_owned();
+        self.suggestions.clear();
         self.dirty = true;
 
         if self.last_query.trim().is_empty() {
             // Reset search without query.
             self.geocoder.reset();
         } else {
+            self.remember_query();
+
             // Submit background query.
             let reference_point = match self.route_origin {
                 Some(RouteOrigin::Gps) | None => self.gps.unwrap_or(self.map_center_point),
@@ -232,6 +386,19 @@ impl SearchView {
         self.search_field.set_text("");
     }
 
+    /// Search for places to stop along a route.
+    pub fn search_along_route(&mut self, query: CorridorQuery) {
+        self.last_query = String::from("Along route");
+        self.pending_reverse = true;
+        self.suggestions.clear();
+        self.dirty = true;
+
+        // Submit background query.
+        self.geocoder.search_corridor(query);
+
+        self.search_field.set_text("");
+    }
+
     /// Start a new route calculation.
     pub fn route(&mut self, origin: RouteOrigin, target: GeoPoint, mode: RouteMode) {
         // Determine route origin and whether the route should be updated from GPS.
@@ -254,11 +421,210 @@ impl SearchView {
 
         self.geocoder.reset();
 
+        self.remember_route_mode(target, mode);
+
         // Submit background query.
         let query = RoutingQuery::new(origin, target, mode);
         self.router.route(query, is_gps_route);
     }
 
+    /// Start a new loop route calculation from the route origin.
+    ///
+    /// The loop length in kilometers is taken from the search field, falling
+    /// back to a default length.
+    fn route_loop(&mut self) {
+        let origin = match (self.route_origin, self.gps) {
+            (Some(RouteOrigin::GeoPoint(origin)), _) => origin,
+            (Some(RouteOrigin::Gps), Some(gps)) => gps,
+            _ => return,
+        };
+        let length = parse_loop_length(self.search_field.text()).unwrap_or(DEFAULT_LOOP_LENGTH);
+
+        // Vary the loop direction on each new attempt.
+        let bearing = self.loop_bearing;
+        self.loop_bearing = (self.loop_bearing + LOOP_BEARING_STEP) % 360.;
+
+        self.search_field.set_text("");
+        self.route_origin = None;
+        self.route_mode_selected = false;
+        self.dirty = true;
+
+        self.geocoder.reset();
+
+        // Submit background query.
+        //
+        // Loops are never updated from GPS, since rerouting would skip the
+        // remaining waypoints.
+        let query = RoutingQuery::new_loop(origin, length, bearing, self.route_mode);
+        self.router.route(query, false);
+    }
+
+    /// Start a new route calculation with the preferred mode for the target.
+    ///
+    /// The last travel mode used for the target is preferred, unless a mode
+    /// was explicitly selected for this route.
+    fn route_preferred(&mut self, origin: RouteOrigin, target: GeoPoint) {
+        let mode = match self.destination_modes.get(&target.into()) {
+            Some(&mode) if !self.route_mode_selected => mode,
+            _ => self.route_mode,
+        };
+        self.route(origin, target, mode);
+    }
+
+    /// Store the travel mode used for a destination.
+    fn remember_route_mode(&mut self, target: GeoPoint, mode: RouteMode) {
+        self.route_mode_selected = false;
+        if self.route_mode != mode {
+            self.route_mode = mode;
+            self.route_mode_button.set_svg(mode.svg());
+        }
+
+        // Persist the mode in the background, if it changed.
+        let key = DestinationKey::from(target);
+        if self.destination_modes.insert(key, mode) != Some(mode) {
+            let db = self.db.clone();
+            tokio::spawn(async move {
+                if let Err(err) = db.set_destination_mode(key, mode).await {
+                    error!("Failed to store destination travel mode: {err}");
+                }
+            });
+        }
+    }
+
+    /// Add the last query to the search history.
+    fn remember_query(&mut self) {
+        let query = self.last_query.trim();
+        self.history.retain(|entry| entry != query);
+        self.history.insert(0, query.to_owned());
+
+        // Persist the query in the background.
+        if !self.db.read_only() {
+            let query = query.to_owned();
+            let db = self.db.clone();
+            tokio::spawn(async move {
+                if let Err(err) = db.insert_search_history(&query).await {
+                    error!("Failed to store search history: {err}");
+                }
+            });
+        }
+    }
+
+    /// Update search suggestions for the current search field text.
+    ///
+    /// This includes the IME's preedit text, to allow selecting suggestions
+    /// before the composed text was committed.
+    fn update_suggestions(&mut self) {
+        let text = self.search_field.text();
+        let preedit = self.search_field.preedit_text();
+
+        // Hide suggestions for the query whose results are shown.
+        if preedit.is_empty() && text == self.last_query {
+            self.suggestions.clear();
+            return;
+        }
+
+        let text: String =
+            if preedit.is_empty() { text.into() } else { format!("{text}{preedit}") };
+
+        // Request offline matches, which are shown after the search history.
+        let long_enough = text.trim().chars().count() >= MIN_OFFLINE_SUGGESTION_LEN;
+        if long_enough && text != self.suggested_text {
+            self.geocoder.suggest(&text);
+            self.suggested_text = text.clone();
+        }
+
+        let history = self.history.iter().map(String::as_str);
+        let candidates = history.chain(self.offline_suggestions.iter().map(String::as_str));
+        self.suggestions.update(candidates, &text);
+    }
+
+    /// Replace the search field text with a suggestion.
+    fn commit_suggestion(&mut self, suggestion: String) {
+        // Drop uncommitted IME text, since it is part of the suggestion.
+        self.search_field.set_preedit_string(String::new(), 0, 0);
+        self.search_field.set_text(suggestion);
+        self.update_suggestions();
+    }
+
+    /// Repeat the last failed search.
+    fn retry_search(&mut self) {
+        if self.geocoder.retry() {
+            self.error = "";
+            self.dirty = true;
+        }
+    }
+
+    /// Start routing to a point from the current GPS location.
+    ///
+    /// Without GPS, the point is used as origin and route target selection is
+    /// opened instead.
+    pub fn route_to(&mut self, point: GeoPoint) {
+        if self.gps.is_some() {
+            self.route_preferred(RouteOrigin::Gps, point);
+        } else {
+            self.set_route_origin(point.into());
+            self.event_loop.insert_idle(|state| state.window.set_view(View::Search));
+        }
+    }
+
+    /// Load favorite locations into the search results.
+    fn load_favorites(&mut self) {
+        let favorites_tx = self.favorites_tx.clone();
+        let db = self.db.clone();
+        tokio::spawn(async move {
+            match db.favorites().await {
+                Ok(favorites) => {
+                    let _ = favorites_tx.send(favorites);
+                },
+                Err(err) => error!("Failed to load favorites: {err}"),
+            }
+        });
+    }
+
+    /// Show favorite locations as search results.
+    ///
+    /// Favorites are sorted by distance first, then updated with the travel
+    /// time once it is known.
+    fn show_favorites(&mut self, favorites: Vec<Favorite>) {
+        let origin = match self.route_origin {
+            Some(RouteOrigin::Gps) | None => self.gps,
+            Some(RouteOrigin::GeoPoint(point)) => Some(point),
+        };
+        let reference = origin.unwrap_or(self.map_center_point);
+
+        let mut results: Vec<_> = favorites
+            .into_iter()
+            .map(|favorite| QueryResult {
+                distance: Some(reference.distance(favorite.point)),
+                rank: QueryResultRank::Favorite,
+                entity_type: FAVORITE_ENTITY_TYPE,
+                address: favorite.address,
+                point: favorite.point,
+                title: favorite.title,
+                extent: None,
+                suggestion: false,
+            })
+            .collect();
+        results.sort_by_key(|result| result.distance);
+
+        if results.is_empty() {
+            self.set_error("No Favorites");
+        }
+
+        // Request travel times from the route origin.
+        let targets: Vec<_> = results.iter().map(|result| result.point).collect();
+        let id = self.geocoder.show_favorites(results);
+        if let Some(origin) = origin
+            && !targets.is_empty()
+        {
+            self.router.matrix(MatrixQuery::new(id, origin, targets, self.route_mode));
+        }
+
+        self.search_field.set_text("");
+        self.result_list.reset();
+        self.dirty = true;
+    }
+
     /// Set origin for routing and start route target selection.
     fn set_route_origin(&mut self, origin: RouteOrigin) {
         self.route_origin = Some(origin);
@@ -281,9 +647,12 @@ impl SearchView {
         let mut routing_button_point = self.routing_button_point();
         let routing_button_size = self.routing_button_size();
 
-        let text_width = routing_button_point.x as f32 - padding * 2.;
+        let icon_size = Size::new(CATEGORY_ICON_SIZE, CATEGORY_ICON_SIZE) * self.scale;
+        let text_offset = padding * 2. + icon_size.width as f32;
+
+        let text_width = routing_button_point.x as f32 - padding - text_offset;
         let mut text_point = point;
-        text_point.x += padding as i32;
+        text_point.x += text_offset as i32;
 
         // Draw background.
         let bg_width = point.x as f32 + size.width as f32;
@@ -291,6 +660,12 @@ impl SearchView {
         let bg_rect = Rect::new(point.x as f32, point.y as f32, bg_width, bg_height);
         render_state.draw_rect(bg_rect, &self.bg_paint);
 
+        // Draw entity category icon.
+        let icon_x = point.x + padding as i32;
+        let icon_y = point.y + (size.height - icon_size.height) as i32 / 2;
+        let icon_svg = category_svg(result.entity_type.category);
+        render_state.draw_svg(icon_svg, Point::new(icon_x, icon_y), icon_size);
+
         // Layout title and distance text.
 
         let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
@@ -304,15 +679,22 @@ impl SearchView {
         let options = TextOptions::new().ellipsize(true);
         let mut builder =
             render_state.paragraph(config.colors.foreground, ADDRESS_FONT_SIZE, options);
-        let entity_text = match result.distance {
-            Some(distance) => {
-                let mut text =
-                    String::with_capacity(result.entity_type.len() + " · XXXXX km".len());
-                let _ = write!(&mut text, "{} · ", result.entity_type);
-                view::format_distance(&mut text, distance);
+        let entity_text = match (result.distance, result.suggestion) {
+            (None, false) => Cow::Borrowed(result.entity_type.name),
+            (distance, suggestion) => {
+                let name = result.entity_type.name;
+                let capacity = SUGGESTION_PREFIX.len() + name.len() + " · XXXXX km".len();
+                let mut text = String::with_capacity(capacity);
+                if suggestion {
+                    text.push_str(SUGGESTION_PREFIX);
+                }
+                text.push_str(name);
+                if let Some(distance) = distance {
+                    text.push_str(" · ");
+                    view::format_distance(&mut text, distance, config.navigation.units);
+                }
                 Cow::Owned(text)
             },
-            None => Cow::Borrowed(result.entity_type),
         };
         builder.add_text(entity_text);
 
@@ -352,6 +734,50 @@ impl SearchView {
         render_state.draw_svg(Svg::Route, routing_button_point, routing_button_size);
     }
 
+    /// Draw a placeholder entry for a pending search result.
+    fn draw_skeleton_result<'a>(
+        &self,
+        render_state: &mut RenderState<'a>,
+        point: Point,
+        size: Size,
+    ) {
+        let padding = (RESULTS_INSIDE_PADDING * self.scale).round() as f32;
+        let text_padding = (TEXT_PADDING * self.scale).round() as f32;
+        let title_height = (SKELETON_TITLE_HEIGHT * self.scale).round() as f32;
+        let address_height = (SKELETON_ADDRESS_HEIGHT * self.scale).round() as f32;
+
+        let icon_size = Size::new(CATEGORY_ICON_SIZE, CATEGORY_ICON_SIZE) * self.scale;
+        let text_x = point.x as f32 + padding * 2. + icon_size.width as f32;
+        let text_width =
+            self.routing_button_point().x as f32 - padding * 3. - icon_size.width as f32;
+
+        // Draw background.
+        let bg_width = point.x as f32 + size.width as f32;
+        let bg_height = point.y as f32 + size.height as f32;
+        let bg_rect = Rect::new(point.x as f32, point.y as f32, bg_width, bg_height);
+        render_state.draw_rect(bg_rect, &self.bg_paint);
+
+        // Draw entity category icon placeholder.
+        let icon_x = point.x as f32 + padding;
+        let icon_y = point.y as f32 + (size.height - icon_size.height) as f32 / 2.;
+        let icon_right = icon_x + icon_size.width as f32;
+        let icon_bottom = icon_y + icon_size.height as f32;
+        let icon_rect = Rect::new(icon_x, icon_y, icon_right, icon_bottom);
+        render_state.draw_rect(icon_rect, &self.skeleton_paint);
+
+        // Draw title and address placeholders.
+        let text_height = title_height + text_padding + address_height;
+        let title_y = point.y as f32 + (size.height as f32 - text_height) / 2.;
+        let title_rect =
+            Rect::new(text_x, title_y, text_x + text_width * 0.6, title_y + title_height);
+        render_state.draw_rect(title_rect, &self.skeleton_paint);
+
+        let address_y = title_y + title_height + text_padding;
+        let address_rect =
+            Rect::new(text_x, address_y, text_x + text_width * 0.4, address_y + address_height);
+        render_state.draw_rect(address_rect, &self.skeleton_paint);
+    }
+
     /// Physical location of the search text field.
     fn search_field_point(size: Size, scale: f64) -> Point {
         let search_button_point = Self::search_button_point(size, scale);
@@ -374,6 +800,22 @@ impl SearchView {
         Size::new(width, button_size.height)
     }
 
+    /// Physical location of the bottom-left corner of the suggestions list.
+    fn suggestions_point(size: Size, scale: f64) -> Point {
+        let search_button_point = Self::search_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+
+        Point::new(search_button_point.x, search_button_point.y - padding)
+    }
+
+    /// Physical width of the suggestions list.
+    fn suggestions_width(size: Size, scale: f64) -> u32 {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as u32;
+        let physical_size = size * scale;
+
+        physical_size.width - 2 * padding
+    }
+
     /// Physical location of the search button.
     fn search_button_point(size: Size, scale: f64) -> Point {
         let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
@@ -420,6 +862,50 @@ impl SearchView {
         Point::new(x, config_button_point.y)
     }
 
+    /// Physical location of the diagnostics button.
+    fn diagnostics_button_point(size: Size, scale: f64) -> Point {
+        let gps_button_point = Self::gps_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let x = gps_button_point.x - button_size.width as i32 - padding;
+
+        Point::new(x, gps_button_point.y)
+    }
+
+    /// Physical location of the favorites button.
+    fn favorites_button_point(size: Size, scale: f64) -> Point {
+        let diagnostics_button_point = Self::diagnostics_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let x = diagnostics_button_point.x - button_size.width as i32 - padding;
+
+        Point::new(x, diagnostics_button_point.y)
+    }
+
+    /// Physical location of the input settings button.
+    fn settings_button_point(size: Size, scale: f64) -> Point {
+        let favorites_button_point = Self::favorites_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let x = favorites_button_point.x - button_size.width as i32 - padding;
+
+        Point::new(x, favorites_button_point.y)
+    }
+
+    /// Physical location of the recorded tracks button.
+    fn tracks_button_point(size: Size, scale: f64) -> Point {
+        let settings_button_point = Self::settings_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let y = settings_button_point.y - button_size.height as i32 - padding;
+
+        Point::new(settings_button_point.x, y)
+    }
+
     /// Physical location of the route cancellation button.
     fn cancel_route_button_point(size: Size, scale: f64) -> Point {
         let config_button_point = Self::config_button_point(size, scale);
@@ -443,6 +929,32 @@ impl SearchView {
         Point::new(x, y)
     }
 
+    /// Physical location of the loop route button.
+    fn loop_button_point(size: Size, scale: f64) -> Point {
+        let route_mode_button_point = Self::route_mode_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+
+        let x = route_mode_button_point.x - button_size.width as i32 - padding;
+
+        Point::new(x, route_mode_button_point.y)
+    }
+
+    /// Physical location of the search retry button.
+    fn retry_button_point(size: Size, scale: f64) -> Point {
+        let search_button_point = Self::search_button_point(size, scale);
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        // Place button below the vertically centered search status text.
+        let results_end = search_button_point.y - padding;
+        let x = (physical_size.width - button_size.width) as i32 / 2;
+        let y = results_end / 2 + 2 * padding;
+
+        Point::new(x, y)
+    }
+
     /// Physical size of the back/search buttons.
     fn button_size(scale: f64) -> Size {
         Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
@@ -493,11 +1005,21 @@ impl SearchView {
         if self.router.routing() { &[] } else { self.geocoder.results() }
     }
 
-    /// Check whether the config/gps buttons should be rendered.
+    /// Check whether the config/gps/diagnostics buttons should be rendered.
     fn show_extra_buttons(&self) -> bool {
         self.results().is_empty() && !self.geocoder.searching() && !self.router.routing()
     }
 
+    /// Check whether placeholder entries should be rendered.
+    fn show_skeletons(&self) -> bool {
+        self.geocoder.searching() && self.results().is_empty() && !self.router.routing()
+    }
+
+    /// Check whether the search retry button should be rendered.
+    fn show_retry_button(&self) -> bool {
+        !self.error.is_empty() && self.geocoder.can_retry() && self.show_extra_buttons()
+    }
+
     /// Check whether the route cancellation/travel mode buttons should be
     /// rendered.
     fn show_route_buttons(&self) -> bool {
@@ -505,32 +1027,9 @@ impl SearchView {
     }
 
     /// Get result at the specified location.
-    fn result_at(&self, mut point: Point<f64>) -> Option<(&QueryResult, bool)> {
-        let result_point = self.result_point();
-        let result_size = self.result_size();
-        let results_end = result_point.y as f64 + result_size.height as f64;
-
-        // Short-circuit if point is outside the results list.
-        if point.x < result_point.x as f64
-            || point.x >= result_point.x as f64 + result_size.width as f64
-            || point.y >= results_end
-        {
-            return None;
-        }
-
-        // Apply current scroll offset.
-        point.y -= self.scroll_offset;
-
-        // Ignore taps within vertical padding.
-        let results_height = result_size.height as f64 + RESULTS_Y_PADDING * self.scale;
-        let bottom_relative = results_end - point.y - 1.;
-        if bottom_relative % results_height >= result_size.height as f64 {
-            return None;
-        }
-
-        // Find index at the specified offset.
-        let index = (bottom_relative / results_height).floor() as usize;
-        let result = self.results().get(index)?;
+    fn result_at(&self, point: Point<f64>) -> Option<(&QueryResult, bool)> {
+        let results = self.results();
+        let (index, relative_point) = self.result_list.row_at(point, results.len())?;
 
         // Check whether the tap is within the result's button.
         //
@@ -539,38 +1038,63 @@ impl SearchView {
         // hit consistently otherwise.
         let padding = (RESULTS_INSIDE_PADDING * self.scale).round();
         let routing_button_point: Point<f64> = self.routing_button_point().into();
-        let button_pressed = point.x - result_point.x as f64 >= routing_button_point.x - padding;
+        let button_pressed = relative_point.x >= routing_button_point.x - padding;
 
-        Some((result, button_pressed))
+        Some((&results[index], button_pressed))
     }
 
-    /// Clamp viewport offset.
-    fn clamp_scroll_offset(&mut self) {
-        let old_offset = self.scroll_offset;
-        let max_offset = self.max_scroll_offset() as f64;
-        self.scroll_offset = self.scroll_offset.clamp(0., max_offset);
+    /// Update the results list geometry.
+    fn update_result_list(&mut self) {
+        self.result_list.set_point(self.result_point());
+        self.result_list.set_row_size(self.result_size());
+        self.result_list.set_scale_factor(self.scale);
+    }
 
-        // Cancel velocity after reaching the scroll limit.
-        if old_offset != self.scroll_offset {
-            self.touch_state.velocity.stop();
-            self.dirty = true;
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Diagnostics => Some(&mut self.diagnostics_button),
+            TouchAction::CancelRoute => Some(&mut self.cancel_route_button),
+            TouchAction::Favorites => Some(&mut self.favorites_button),
+            TouchAction::Settings => Some(&mut self.settings_button),
+            TouchAction::Tracks => Some(&mut self.tracks_button),
+            TouchAction::RouteMode => Some(&mut self.route_mode_button),
+            TouchAction::RouteLoop => Some(&mut self.loop_button),
+            TouchAction::RouteGps => Some(&mut self.gps_button),
+            TouchAction::Search => Some(&mut self.search_button),
+            TouchAction::Config => Some(&mut self.config_button),
+            TouchAction::Retry => Some(&mut self.retry_button),
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Suggestion(_)
+            | TouchAction::SearchField
+            | TouchAction::Drag
+            | TouchAction::Tap => None,
         }
     }
+}
 
-    /// Get maximum viewport offset.
-    fn max_scroll_offset(&self) -> usize {
-        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as usize;
-        let results_padding = (RESULTS_Y_PADDING * self.scale).round() as usize;
-        let result_height = self.result_size().height as usize;
+impl RowProvider for SearchView {
+    fn row_count(&self) -> usize {
+        if self.show_skeletons() { SKELETON_COUNT } else { self.results().len() }
+    }
 
-        // Calculate height of all results plus top padding.
-        let results_count = self.results().len();
-        let results_height = (results_count * (result_height + results_padding))
-            .saturating_sub(results_padding)
-            + outside_padding;
+    fn draw_row(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'_>,
+        index: usize,
+        point: Point,
+        size: Size,
+    ) {
+        match self.results().get(index) {
+            Some(result) => self.draw_geocoding_result(config, render_state, point, size, result),
+            None => self.draw_skeleton_result(render_state, point, size),
+        }
+    }
 
-        // Calculate tab content outside the viewport.
-        results_height.saturating_sub(self.result_point().y as usize + result_height)
+    fn row_label(&self, index: usize) -> Option<String> {
+        let result = self.results().get(index)?;
+        Some(format!("{}, {}", result.title, result.address))
     }
 }
 
@@ -579,73 +1103,38 @@ impl UiView for SearchView {
     fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
         let size = self.size * self.scale;
 
-        // Apply scroll velocity.
-        if let Some(delta) = self.touch_state.velocity.apply(&self.input_config) {
-            self.scroll_offset += delta.y;
-        }
-
-        // Ensure offset is correct in case size changed.
-        self.clamp_scroll_offset();
+        // Apply scroll velocity and ensure offset is correct in case size changed.
+        let result_count = self.results().len();
+        self.result_list.update(&self.input_config, result_count);
 
         // Clear dirtiness flag.
-        //
-        // This is inentionally placed after functions like `clamp_scroll_offset`, since
-        // these modify dirtiness but do not require another redraw.
         self.dirty = false;
 
-        // Ensure background paint is up to date.
+        // Ensure background paints are up to date.
         self.bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        let mut skeleton_color = Color4f::from(config.colors.alt_foreground);
+        skeleton_color.a *= SKELETON_ALPHA;
+        self.skeleton_paint.set_color4f(skeleton_color, None);
 
         render_state.clear(config.colors.background);
 
-        // Calculate results list geometry.
-
-        let padding = (RESULTS_Y_PADDING * self.scale).round() as i32;
-        let result_size = self.result_size();
-
-        let results_start = self.result_point();
-        let mut result_point = results_start;
-        result_point.y += self.scroll_offset.round() as i32;
-
-        // Set clipping mask to cut off results overlapping the bottom buttons.
-        let bottom = results_start.y as f32 + result_size.height as f32;
-        let clip_rect = Rect::new(0., 0., size.width as f32, bottom);
-        render_state.save();
-        render_state.clip_rect(clip_rect, None, Some(false));
-
-        // Draw query results.
-        let results = self.results();
-        for result in results {
-            if result_point.y > results_start.y + (result_size.height as i32) {
-                result_point.y -= result_size.height as i32 + padding;
-                continue;
-            } else if result_point.y + (result_size.height as i32) < 0 {
-                break;
-            }
-
-            self.draw_geocoding_result(
-                config,
-                &mut render_state,
-                result_point,
-                result_size,
-                result,
-            );
-            result_point.y -= result_size.height as i32 + padding;
-        }
-
-        // Reset region clipping mask.
-        render_state.restore();
+        // Draw query results, or placeholders while searching.
+        self.result_list.draw(config, &mut render_state, self);
 
         // Draw current search status indicator.
-        if results.is_empty() {
-            let msg = match (self.route_origin, self.geocoder.searching(), self.router.routing()) {
-                (_, _, true) => Cow::Borrowed("Calculating Route …"),
-                (_, true, _) => Cow::Owned(format!("Searching for \"{}\" …", self.last_query)),
-                (Some(_), ..) => Cow::Borrowed("Enter Destination"),
-                (None, false, false) if self.error.is_empty() => {
-                    Cow::Borrowed("Search for an Address or POI")
+        if self.results().is_empty() && !self.show_skeletons() {
+            let msg = match (self.route_origin, self.router.routing()) {
+                (_, true) => "Calculating Route …",
+                _ if self.show_retry_button() => self.error,
+                (Some(_), _) => "Enter Destination or Loop Length (km)",
+                (None, false) if self.error.is_empty() && self.geocoder.offline_loading() => {
+                    "Offline Search Warming Up …"
+                },
+                (None, false) if self.error.is_empty() && !self.geocoder.offline_available() => {
+                    "Search for an Address or POI (Offline Search Unavailable)"
                 },
-                (None, false, false) => Cow::Borrowed(self.error),
+                (None, false) if self.error.is_empty() => "Search for an Address or POI",
+                (None, false) => self.error,
             };
 
             let options = TextOptions::new().ellipsize(false).align(TextAlign::Center);
@@ -660,7 +1149,8 @@ impl UiView for SearchView {
             let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as f32;
             paragraph.layout(size.width as f32 - 2. * outside_padding);
 
-            let result_end = results_start.y as f32 + result_size.height as f32;
+            let results_start = self.result_point();
+            let result_end = results_start.y as f32 + self.result_size().height as f32;
             let y = (result_end - paragraph.height()) / 2.;
             paragraph.paint(&render_state, Point::new(outside_padding, y));
         }
@@ -669,22 +1159,39 @@ impl UiView for SearchView {
 
         self.search_field.draw(config, &mut render_state, config.colors.alt_background);
 
+        if self.show_retry_button() {
+            self.retry_button.draw(&mut render_state, config.colors.alt_background);
+        }
+
         if self.show_extra_buttons() {
             if self.show_route_buttons() {
                 self.cancel_route_button.draw(&mut render_state, config.colors.alt_background);
                 self.route_mode_button.draw(&mut render_state, config.colors.alt_background);
+                self.loop_button.draw(&mut render_state, config.colors.alt_background);
             }
             if self.gps.is_some() {
                 self.gps_button.draw(&mut render_state, config.colors.alt_background);
             }
+            self.diagnostics_button.draw(&mut render_state, config.colors.alt_background);
+            self.favorites_button.draw(&mut render_state, config.colors.alt_background);
+            self.settings_button.draw(&mut render_state, config.colors.alt_background);
+            self.tracks_button.draw(&mut render_state, config.colors.alt_background);
             self.config_button.draw(&mut render_state, config.colors.alt_background);
         }
         self.search_button.draw(&mut render_state, config.colors.alt_background);
         self.back_button.draw(&mut render_state, config.colors.alt_background);
+
+        // Draw suggestions on top of everything else.
+        if self.search_focused {
+            self.suggestions.draw(config, &mut render_state);
+        }
     }
 
     fn dirty(&self) -> bool {
-        self.dirty || self.touch_state.velocity.is_moving() || self.search_field.dirty()
+        self.dirty
+            || self.result_list.is_animating()
+            || self.search_field.dirty()
+            || self.suggestions.dirty()
     }
 
     fn enter(&mut self) {
@@ -711,13 +1218,24 @@ impl UiView for SearchView {
 
         self.cancel_route_button.set_point(Self::cancel_route_button_point(size, self.scale));
         self.route_mode_button.set_point(Self::route_mode_button_point(size, self.scale));
+        self.loop_button.set_point(Self::loop_button_point(size, self.scale));
         self.config_button.set_point(Self::config_button_point(size, self.scale));
         self.search_button.set_point(Self::search_button_point(size, self.scale));
         self.back_button.set_point(Self::back_button_point(size, self.scale));
         self.gps_button.set_point(Self::gps_button_point(size, self.scale));
+        self.diagnostics_button.set_point(Self::diagnostics_button_point(size, self.scale));
+        self.favorites_button.set_point(Self::favorites_button_point(size, self.scale));
+        self.settings_button.set_point(Self::settings_button_point(size, self.scale));
+        self.tracks_button.set_point(Self::tracks_button_point(size, self.scale));
+        self.retry_button.set_point(Self::retry_button_point(size, self.scale));
 
         self.search_field.set_point(Self::search_field_point(size, self.scale));
         self.search_field.set_size(Self::search_field_size(size, self.scale));
+
+        self.suggestions.set_point(Self::suggestions_point(size, self.scale));
+        self.suggestions.set_width(Self::suggestions_width(size, self.scale));
+
+        self.update_result_list();
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
@@ -735,6 +1253,9 @@ impl UiView for SearchView {
         self.route_mode_button.set_point(Self::route_mode_button_point(self.size, scale));
         self.route_mode_button.set_size(button_size);
 
+        self.loop_button.set_point(Self::loop_button_point(self.size, scale));
+        self.loop_button.set_size(button_size);
+
         self.config_button.set_point(Self::config_button_point(self.size, scale));
         self.config_button.set_size(button_size);
 
@@ -747,15 +1268,36 @@ impl UiView for SearchView {
         self.gps_button.set_point(Self::gps_button_point(self.size, scale));
         self.gps_button.set_size(button_size);
 
+        self.diagnostics_button.set_point(Self::diagnostics_button_point(self.size, scale));
+        self.diagnostics_button.set_size(button_size);
+
+        self.favorites_button.set_point(Self::favorites_button_point(self.size, scale));
+        self.favorites_button.set_size(button_size);
+
+        self.settings_button.set_point(Self::settings_button_point(self.size, scale));
+        self.settings_button.set_size(button_size);
+
+        self.tracks_button.set_point(Self::tracks_button_point(self.size, scale));
+        self.tracks_button.set_size(button_size);
+
+        self.retry_button.set_point(Self::retry_button_point(self.size, scale));
+        self.retry_button.set_size(button_size);
+
         self.search_field.set_point(Self::search_field_point(self.size, scale));
         self.search_field.set_scale_factor(scale);
         self.search_field.set_size(button_size);
+
+        self.suggestions.set_point(Self::suggestions_point(self.size, scale));
+        self.suggestions.set_width(Self::suggestions_width(self.size, scale));
+        self.suggestions.set_scale_factor(scale);
+
+        self.update_result_list();
     }
 
     #[cfg_attr(feature = "profiling", profiling::function)]
     fn touch_down(&mut self, slot: i32, time: u32, point: Point<f64>) {
         // Cancel velocity if a new touch sequence starts.
-        self.touch_state.velocity.stop();
+        self.result_list.stop();
 
         // Only allow a single active touch slot.
         if !self.touch_state.slots.is_empty() {
@@ -764,11 +1306,23 @@ impl UiView for SearchView {
 
         let point = point * self.scale;
 
+        // Handle suggestion taps without changing search field focus.
+        let suggestion = self.search_focused.then(|| self.suggestions.index_at(point)).flatten();
+        if let Some(index) = suggestion {
+            self.touch_state.action = TouchAction::Suggestion(index);
+
+            let slot = self.touch_state.slots.entry(slot).or_default();
+            slot.point = point;
+            slot.start = point;
+            return;
+        }
+
         // Handle focus changes for search field input.
         self.search_focused = self.search_field.contains(point);
         if self.search_focused {
             self.search_field.set_keyboard_focus(self.keyboard_focused);
             self.search_field.set_ime_focus(self.ime_focused);
+            self.update_suggestions();
         } else {
             self.search_field.set_keyboard_focus(false);
             self.search_field.set_ime_focus(false);
@@ -783,10 +1337,22 @@ impl UiView for SearchView {
             TouchAction::CancelRoute
         } else if self.show_route_buttons() && self.route_mode_button.contains(point) {
             TouchAction::RouteMode
+        } else if self.show_route_buttons() && self.loop_button.contains(point) {
+            TouchAction::RouteLoop
         } else if show_extra_buttons && self.gps.is_some() && self.gps_button.contains(point) {
             TouchAction::RouteGps
         } else if show_extra_buttons && self.config_button.contains(point) {
             TouchAction::Config
+        } else if show_extra_buttons && self.diagnostics_button.contains(point) {
+            TouchAction::Diagnostics
+        } else if show_extra_buttons && self.favorites_button.contains(point) {
+            TouchAction::Favorites
+        } else if show_extra_buttons && self.settings_button.contains(point) {
+            TouchAction::Settings
+        } else if show_extra_buttons && self.tracks_button.contains(point) {
+            TouchAction::Tracks
+        } else if self.show_retry_button() && self.retry_button.contains(point) {
+            TouchAction::Retry
         } else if self.search_button.contains(point) {
             TouchAction::Search
         } else if self.back_button.contains(point) {
@@ -795,6 +1361,10 @@ impl UiView for SearchView {
             TouchAction::Tap
         };
 
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
         // Convert position to physical space.
         let slot = self.touch_state.slots.entry(slot).or_default();
         slot.point = point;
@@ -824,18 +1394,15 @@ impl UiView for SearchView {
                 }
                 self.touch_state.action = TouchAction::Drag;
 
-                // Update pending scroll velocity.
-                let delta = slot.point.y - old_point.y;
-                self.touch_state.velocity.set(Point::new(0., delta));
-
                 // Apply scroll motion.
-                let old_offset = self.scroll_offset;
-                self.scroll_offset += delta;
-                self.clamp_scroll_offset();
-                self.dirty |= self.scroll_offset != old_offset;
+                let delta = slot.point.y - old_point.y;
+                let result_count = self.results().len();
+                self.dirty |= self.result_list.drag(delta, result_count);
             },
             TouchAction::SearchField => self.search_field.touch_motion(&self.input_config, point),
-            _ => (),
+            _ => {
+                self.dirty |= move_button_touch(self.action_button(), point);
+            },
         }
     }
 
@@ -847,20 +1414,31 @@ impl UiView for SearchView {
             None => return,
         };
 
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
         // Dispatch tap actions on release.
         match self.touch_state.action {
             TouchAction::Tap => match self.result_at(removed.point) {
-                Some((&QueryResult { point, ref address, .. }, false)) => {
+                Some((&QueryResult { point, extent, ref title, ref address, .. }, false)) => {
                     let zoom = zoom_from_address(address);
+                    let (title, address) = (title.clone(), address.clone());
                     self.event_loop.insert_idle(move |state| {
+                        // Show the entire entity, guessing its size if the extent is unknown.
                         let map_view = state.window.views.map();
-                        map_view.goto(point, Some(zoom));
+                        match extent {
+                            Some([south_west, north_east]) => {
+                                map_view.fit_bounds(south_west, north_east)
+                            },
+                            None => map_view.fly_to(point, Some(zoom)),
+                        }
                         map_view.set_poi(Some(point));
+                        map_view.set_poi_details(title, address);
                         state.window.set_view(View::Map);
                     });
                 },
                 Some((&QueryResult { point, .. }, true)) => match self.route_origin {
-                    Some(origin) => self.route(origin, point, self.route_mode),
+                    Some(origin) => self.route_preferred(origin, point),
                     None => self.set_route_origin(point.into()),
                 },
                 None => (),
@@ -870,28 +1448,57 @@ impl UiView for SearchView {
             {
                 self.event_loop.insert_idle(|state| state.window.set_view(View::Download));
             },
+            TouchAction::Diagnostics
+                if self.show_extra_buttons() && self.diagnostics_button.contains(removed.point) =>
+            {
+                self.event_loop.insert_idle(|state| state.window.set_view(View::Diagnostics));
+            },
+            TouchAction::Favorites
+                if self.show_extra_buttons() && self.favorites_button.contains(removed.point) =>
+            {
+                self.load_favorites();
+            },
+            TouchAction::Settings
+                if self.show_extra_buttons() && self.settings_button.contains(removed.point) =>
+            {
+                self.event_loop.insert_idle(|state| state.window.set_view(View::Settings));
+            },
+            TouchAction::Tracks
+                if self.show_extra_buttons() && self.tracks_button.contains(removed.point) =>
+            {
+                self.event_loop.insert_idle(|state| state.window.set_view(View::Tracks));
+            },
+            TouchAction::Retry
+                if self.show_retry_button() && self.retry_button.contains(removed.point) =>
+            {
+                self.retry_search();
+            },
             TouchAction::CancelRoute
                 if self.show_route_buttons()
                     && self.cancel_route_button.contains(removed.point) =>
             {
                 self.route_origin = None;
+                self.route_mode_selected = false;
                 self.dirty = true;
             },
             TouchAction::RouteMode
                 if self.show_route_buttons() && self.route_mode_button.contains(removed.point) =>
             {
-                self.route_mode = match self.route_mode {
-                    RouteMode::Pedestrian => RouteMode::Auto,
-                    RouteMode::Auto => RouteMode::Pedestrian,
-                };
+                self.route_mode = self.route_mode.next();
                 self.route_mode_button.set_svg(self.route_mode.svg());
+                self.route_mode_selected = true;
                 self.dirty = true;
             },
+            TouchAction::RouteLoop
+                if self.show_route_buttons() && self.loop_button.contains(removed.point) =>
+            {
+                self.route_loop();
+            },
             TouchAction::RouteGps
                 if self.show_extra_buttons() && self.gps_button.contains(removed.point) =>
             {
                 match (self.gps, self.route_origin) {
-                    (Some(gps), Some(origin)) => self.route(origin, gps, self.route_mode),
+                    (Some(gps), Some(origin)) => self.route_preferred(origin, gps),
                     (Some(_), None) => self.set_route_origin(RouteOrigin::Gps),
                     (None, _) => (),
                 }
@@ -902,6 +1509,13 @@ impl UiView for SearchView {
             TouchAction::Back if self.back_button.contains(removed.point) => {
                 self.event_loop.insert_idle(|state| state.window.set_view(View::Map));
             },
+            TouchAction::Suggestion(index)
+                if self.suggestions.index_at(removed.point) == Some(index) =>
+            {
+                if let Some(suggestion) = self.suggestions.get(index) {
+                    self.commit_suggestion(suggestion.to_owned());
+                }
+            },
             TouchAction::SearchField => self.search_field.touch_up(),
             _ => (),
         }
@@ -923,11 +1537,43 @@ impl UiView for SearchView {
     }
 
     fn press_key(&mut self, _raw: u32, keysym: Keysym, modifiers: Modifiers) {
+        // Handle suggestion selection.
+        if !self.suggestions.is_empty() {
+            match keysym {
+                Keysym::Up => {
+                    self.suggestions.select_next();
+                    return;
+                },
+                Keysym::Down => {
+                    self.suggestions.select_previous();
+                    return;
+                },
+                Keysym::Return | Keysym::KP_Enter => {
+                    if let Some(suggestion) = self.suggestions.selected() {
+                        self.commit_suggestion(suggestion.to_owned());
+                        return;
+                    }
+                },
+                Keysym::Escape => {
+                    self.suggestions.clear();
+                    return;
+                },
+                _ => (),
+            }
+        }
+
         self.search_field.press_key(keysym, modifiers);
+        self.update_suggestions();
+    }
+
+    fn captures_key(&self, keysym: Keysym, _modifiers: Modifiers) -> bool {
+        // Close suggestions before leaving the search view.
+        keysym == Keysym::Escape && !self.suggestions.is_empty()
     }
 
     fn paste(&mut self, text: &str) {
         self.search_field.paste(text);
+        self.update_suggestions();
     }
 
     fn text_input_enter(&mut self) {
@@ -947,14 +1593,17 @@ impl UiView for SearchView {
 
     fn delete_surrounding_text(&mut self, before_length: u32, after_length: u32) {
         self.search_field.delete_surrounding_text(before_length, after_length);
+        self.update_suggestions();
     }
 
     fn commit_string(&mut self, text: String) {
         self.search_field.commit_string(&text);
+        self.update_suggestions();
     }
 
     fn set_preedit_string(&mut self, text: String, cursor_begin: i32, cursor_end: i32) {
         self.search_field.set_preedit_string(text, cursor_begin, cursor_end);
+        self.update_suggestions();
     }
 
     fn take_text_input_dirty(&mut self) -> bool {
@@ -997,6 +1646,17 @@ impl QueryId {
         static NEXT_QUERY_ID: AtomicU64 = AtomicU64::new(0);
         Self(NEXT_QUERY_ID.fetch_add(1, Ordering::Relaxed))
     }
+
+    /// Check if a newer query has replaced this one.
+    pub fn is_superseded(self, last_query: &watch::Receiver<QueryId>) -> bool {
+        *last_query.borrow() != self
+    }
+
+    /// Wait until a newer query replaces this one.
+    pub async fn superseded(self, last_query: &mut watch::Receiver<QueryId>) {
+        // Closed channels can never deliver a matching query ID again.
+        let _ = last_query.wait_for(|last_query| *last_query != self).await;
+    }
 }
 
 /// Touch event tracking.
@@ -1004,8 +1664,6 @@ impl QueryId {
 struct TouchState {
     slots: HashMap<i32, TouchSlot>,
     action: TouchAction,
-
-    velocity: Velocity,
 }
 
 /// Touch slot state.
@@ -1018,12 +1676,19 @@ struct TouchSlot {
 /// Intention of a touch sequence.
 #[derive(PartialEq, Eq, Default)]
 enum TouchAction {
+    Suggestion(usize),
+    Diagnostics,
     SearchField,
     CancelRoute,
+    Favorites,
     RouteMode,
+    RouteLoop,
+    Settings,
     RouteGps,
+    Tracks,
     Search,
     Config,
+    Retry,
     Back,
     Drag,
     #[default]
@@ -1043,6 +1708,21 @@ impl From<GeoPoint> for RouteOrigin {
     }
 }
 
+/// Get the icon for an entity category.
+fn category_svg(category: Category) -> Svg {
+    match category {
+        Category::Transport => Svg::Transport,
+        Category::Lodging => Svg::Lodging,
+        Category::Health => Svg::Health,
+        Category::Nature => Svg::Nature,
+        Category::Place => Svg::Place,
+        Category::Food => Svg::Food,
+        Category::Shop => Svg::Shop,
+        Category::Favorite => Svg::StarFilled,
+        Category::Other => Svg::Poi,
+    }
+}
+
 /// Get zoom level necessary to make an address fully or mostly visible.
 fn zoom_from_address(address: &str) -> u8 {
     match address.matches(',').count() {
@@ -1052,3 +1732,36 @@ fn zoom_from_address(address: &str) -> u8 {
         _ => 18,
     }
 }
+
+/// Parse a loop route length in kilometers, returning it in meters.
+fn parse_loop_length(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let text = text.strip_suffix("km").unwrap_or(text).trim_end();
+    let length = text.replace(',', ".").parse::<f64>().ok()?;
+    (length.is_finite() && length > 0.).then_some(length * 1_000.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_supersession() {
+        let id = QueryId::new();
+        let (last_query_tx, last_query_rx) = watch::channel(id);
+        assert!(!id.is_superseded(&last_query_rx));
+
+        last_query_tx.send_replace(QueryId::new());
+        assert!(id.is_superseded(&last_query_rx));
+    }
+
+    #[test]
+    fn loop_length() {
+        assert_eq!(parse_loop_length("5"), Some(5_000.));
+        assert_eq!(parse_loop_length(" 7.5 km "), Some(7_500.));
+        assert_eq!(parse_loop_length("2,5km"), Some(2_500.));
+        assert_eq!(parse_loop_length("0"), None);
+        assert_eq!(parse_loop_length("Berlin"), None);
+        assert_eq!(parse_loop_length(""), None);
+    }
+}
@@ -0,0 +1,547 @@
+//! Settings UI view.
+
+use std::collections::HashMap;
+use std::ops::RangeInclusive;
+use std::time::Duration;
+
+use calloop::LoopHandle;
+use skia_safe::{Color4f, Paint, PaintCap};
+
+use crate::config::{Config, Input, MillisDuration};
+use crate::geometry::{Point, Size};
+use crate::ui::skia::RenderState;
+use crate::ui::view::{UiView, View};
+use crate::ui::{Button, Svg, move_button_touch, press_button, release_button};
+use crate::{Error, State};
+
+/// Button width and height at scale 1.
+const BUTTON_SIZE: u32 = 48;
+
+/// Padding around the screen edge at scale 1.
+const OUTSIDE_PADDING: u32 = 16;
+
+/// Height of a slider's label at scale 1.
+const SLIDER_LABEL_HEIGHT: u32 = 24;
+
+/// Height of a slider's touch area at scale 1.
+const SLIDER_HEIGHT: u32 = 48;
+
+/// Vertical space between sliders at scale 1.
+const SLIDER_PADDING: u32 = 24;
+
+/// Width of a slider's track at scale 1.
+const SLIDER_TRACK_WIDTH: f32 = 4.;
+
+/// Radius of a slider's knob at scale 1.
+const SLIDER_KNOB_RADIUS: f32 = 12.;
+
+/// Settings UI view.
+pub struct SettingsView {
+    input_override: InputOverride,
+    input: Input,
+
+    reset_button: Button,
+    back_button: Button,
+    track_paint: Paint,
+
+    touch_state: TouchState,
+    input_config: Input,
+
+    event_loop: LoopHandle<'static, State>,
+
+    size: Size,
+    scale: f64,
+
+    dirty: bool,
+}
+
+impl SettingsView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn new(
+        event_loop: LoopHandle<'static, State>,
+        config: &Config,
+        size: Size,
+    ) -> Result<Self, Error> {
+        // Initialize UI elements.
+        let point = Self::back_button_point(size, 1.);
+        let button_size = Self::button_size(1.);
+        let back_button = Button::new(point, button_size, Svg::ArrowLeft);
+
+        let point = Self::reset_button_point(size, 1.);
+        let reset_button = Button::new(point, button_size, Svg::Retry);
+
+        let mut track_paint = Paint::default();
+        track_paint.set_stroke_width(SLIDER_TRACK_WIDTH);
+        track_paint.set_stroke_cap(PaintCap::Round);
+        track_paint.set_anti_alias(true);
+
+        Ok(Self {
+            reset_button,
+            back_button,
+            track_paint,
+            event_loop,
+            size,
+            input_config: config.input,
+            input: config.input,
+            input_override: Default::default(),
+            dirty: true,
+            scale: 1.,
+            touch_state: Default::default(),
+        })
+    }
+
+    /// Physical size of the UI SVG buttons.
+    fn button_size(scale: f64) -> Size {
+        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+    }
+
+    /// Physical location of the back button.
+    fn back_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+        let y = (physical_size.height - button_size.height) as i32 - padding;
+
+        Point::new(x, y)
+    }
+
+    /// Physical location of the reset button.
+    fn reset_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::back_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Physical location of a slider's touch area.
+    fn slider_point(&self, index: usize) -> Point {
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
+        let label_height = (SLIDER_LABEL_HEIGHT as f64 * self.scale).round() as i32;
+        let slider_height = (SLIDER_HEIGHT as f64 * self.scale).round() as i32;
+        let slider_padding = (SLIDER_PADDING as f64 * self.scale).round() as i32;
+
+        let row_height = label_height + slider_height + slider_padding;
+        let y = outside_padding + index as i32 * row_height + label_height;
+
+        Point::new(outside_padding, y)
+    }
+
+    /// Physical size of a slider's touch area.
+    fn slider_size(&self) -> Size {
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as u32;
+        let height = (SLIDER_HEIGHT as f64 * self.scale).round() as u32;
+        let size = self.size * self.scale;
+
+        Size::new(size.width.saturating_sub(2 * outside_padding), height)
+    }
+
+    /// Horizontal physical range of a slider's knob center.
+    fn slider_track(&self) -> RangeInclusive<f64> {
+        let knob_radius = SLIDER_KNOB_RADIUS as f64 * self.scale;
+        let point = self.slider_point(0);
+        let size = self.slider_size();
+
+        let start = point.x as f64 + knob_radius;
+        let end = (point.x as f64 + size.width as f64 - knob_radius).max(start);
+
+        start..=end
+    }
+
+    /// Get the slider at the specified physical location.
+    fn slider_at(&self, point: Point<f64>) -> Option<usize> {
+        let size: Size<f64> = self.slider_size().into();
+        (0..Setting::ALL.len()).find(|index| {
+            let slider_point: Point<f64> = self.slider_point(*index).into();
+            point.x >= slider_point.x
+                && point.y >= slider_point.y
+                && point.x < slider_point.x + size.width
+                && point.y < slider_point.y + size.height
+        })
+    }
+
+    /// Update a slider's value from the touch position.
+    fn drag_slider(&mut self, index: usize, x: f64) {
+        let track = self.slider_track();
+        let track_width = track.end() - track.start();
+        let fraction = if track_width > 0. { (x - track.start()) / track_width } else { 0. };
+
+        let setting = Setting::ALL[index];
+        let value = setting.value_at(fraction);
+        if value != setting.get(&self.input) {
+            setting.set(&mut self.input, value);
+            self.input_override.set(setting, &self.input);
+            self.dirty = true;
+        }
+    }
+
+    /// Apply the current settings to all views.
+    fn submit(&mut self) {
+        let input_override = self.input_override;
+        self.event_loop.insert_idle(move |state| state.window.set_input_override(input_override));
+    }
+
+    /// Reset all settings to their default values.
+    fn reset(&mut self) {
+        let defaults = Input::default();
+        for setting in Setting::ALL {
+            self.input_override.set(setting, &defaults);
+        }
+        self.submit();
+    }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Reset => Some(&mut self.reset_button),
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Slider(_) | TouchAction::Tap => None,
+        }
+    }
+}
+
+impl UiView for SettingsView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
+        render_state.clear(config.colors.background);
+
+        let label_height = (SLIDER_LABEL_HEIGHT as f64 * self.scale).round() as f32;
+        let knob_radius = SLIDER_KNOB_RADIUS * self.scale as f32;
+        let slider_size: Size<f32> = self.slider_size().into();
+        let track = self.slider_track();
+
+        self.track_paint.set_stroke_width(SLIDER_TRACK_WIDTH * self.scale as f32);
+
+        for (index, setting) in Setting::ALL.iter().enumerate() {
+            let slider_point: Point<f32> = self.slider_point(index).into();
+            let value = setting.get(&self.input);
+
+            // Draw setting name and current value above the slider.
+            let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
+            builder.add_text(format!("{}: {}", setting.label(), setting.format(value)));
+
+            let mut paragraph = builder.build();
+            paragraph.layout(slider_size.width);
+
+            let y = slider_point.y - label_height + (label_height - paragraph.height()) / 2.;
+            paragraph.paint(&render_state, Point::new(slider_point.x, y));
+
+            // Draw the slider track, highlighting the part up to the current value.
+            let y = slider_point.y + slider_size.height / 2.;
+            let start = Point::new(*track.start() as f32, y);
+            let end = Point::new(*track.end() as f32, y);
+            let knob = start + Point::new((end.x - start.x) * setting.fraction(value) as f32, 0.);
+
+            self.track_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+            render_state.draw_line(knob, end, &self.track_paint);
+            self.track_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+            render_state.draw_line(start, knob, &self.track_paint);
+
+            // Draw the slider knob.
+            self.track_paint.set_color4f(Color4f::from(config.colors.foreground), None);
+            render_state.draw_circle(knob, knob_radius, &self.track_paint);
+        }
+
+        // Render navigation buttons.
+        self.reset_button.draw(&mut render_state, config.colors.alt_background);
+        self.back_button.draw(&mut render_state, config.colors.alt_background);
+
+        self.dirty = false;
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.reset_button.set_point(Self::reset_button_point(size, self.scale));
+        self.back_button.set_point(Self::back_button_point(size, self.scale));
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.reset_button.set_point(Self::reset_button_point(self.size, scale));
+        self.reset_button.set_size(Self::button_size(scale));
+        self.back_button.set_point(Self::back_button_point(self.size, scale));
+        self.back_button.set_size(Self::button_size(scale));
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_down(&mut self, slot: i32, _time: u32, point: Point<f64>) {
+        // Only allow a single active touch slot.
+        if !self.touch_state.slots.is_empty() {
+            return;
+        }
+
+        // Determine goal of this touch sequence.
+        let point = point * self.scale;
+        self.touch_state.action = if self.reset_button.contains(point) {
+            TouchAction::Reset
+        } else if self.back_button.contains(point) {
+            TouchAction::Back
+        } else if let Some(index) = self.slider_at(point) {
+            self.drag_slider(index, point.x);
+            TouchAction::Slider(index)
+        } else {
+            TouchAction::Tap
+        };
+
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
+        // Convert position to physical space.
+        let slot = self.touch_state.slots.entry(slot).or_default();
+        slot.point = point;
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_motion(&mut self, slot: i32, point: Point<f64>) {
+        // Ignore unknown touch slots.
+        let slot = match self.touch_state.slots.get_mut(&slot) {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        // Update touch point.
+        let point = point * self.scale;
+        slot.point = point;
+
+        if let TouchAction::Slider(index) = self.touch_state.action {
+            self.drag_slider(index, point.x);
+        } else {
+            self.dirty |= move_button_touch(self.action_button(), point);
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_up(&mut self, slot: i32) {
+        // Reset touch slot, ignoring unknown slots.
+        let removed = match self.touch_state.slots.remove(&slot) {
+            Some(removed) => removed,
+            None => return,
+        };
+
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
+        // Dispatch tap actions on release.
+        match self.touch_state.action {
+            // Apply the new slider value.
+            TouchAction::Slider(_) => self.submit(),
+            // Restore the default values.
+            TouchAction::Reset if self.reset_button.contains(removed.point) => self.reset(),
+            // Handle "back" button navigation.
+            TouchAction::Back if self.back_button.contains(removed.point) => {
+                self.event_loop.insert_idle(|state| state.window.set_view(View::Search));
+            },
+            _ => (),
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn update_config(&mut self, config: &Config) {
+        self.input_config = config.input;
+        self.input = config.input;
+        self.dirty = true;
+    }
+}
+
+/// Input settings changed at runtime.
+///
+/// Only settings changed by the user are overridden, all others keep following
+/// the configuration file.
+#[derive(Copy, Clone, Default, PartialEq, Debug)]
+pub struct InputOverride {
+    long_press: Option<MillisDuration>,
+    max_tap_distance: Option<f64>,
+    velocity_friction: Option<f64>,
+}
+
+impl InputOverride {
+    /// Override a setting with its current value in `input`.
+    fn set(&mut self, setting: Setting, input: &Input) {
+        match setting {
+            Setting::LongPress => self.long_press = Some(input.long_press),
+            Setting::TapDistance => self.max_tap_distance = Some(input.max_tap_distance),
+            Setting::ScrollMomentum => self.velocity_friction = Some(input.velocity_friction),
+        }
+    }
+
+    /// Apply all overridden settings to an input configuration.
+    pub fn apply(&self, input: &mut Input) {
+        if let Some(long_press) = self.long_press {
+            input.long_press = long_press;
+        }
+        if let Some(max_tap_distance) = self.max_tap_distance {
+            input.max_tap_distance = max_tap_distance;
+        }
+        if let Some(velocity_friction) = self.velocity_friction {
+            input.velocity_friction = velocity_friction;
+        }
+    }
+}
+
+/// Input setting adjustable at runtime.
+#[derive(Copy, Clone, Debug)]
+pub enum Setting {
+    LongPress,
+    TapDistance,
+    ScrollMomentum,
+}
+
+impl Setting {
+    /// All adjustable settings, in display order.
+    pub const ALL: [Self; 3] = [Self::LongPress, Self::TapDistance, Self::ScrollMomentum];
+
+    /// Get the setting's display name.
+    fn label(&self) -> &'static str {
+        match self {
+            Self::LongPress => "Long-press duration",
+            Self::TapDistance => "Tap distance",
+            Self::ScrollMomentum => "Scroll momentum",
+        }
+    }
+
+    /// Get the range of the setting's display value.
+    fn range(&self) -> RangeInclusive<f64> {
+        match self {
+            Self::LongPress => 250.0..=2_000.,
+            Self::TapDistance => 5.0..=60.,
+            Self::ScrollMomentum => 50.0..=98.,
+        }
+    }
+
+    /// Get the smallest increment of the setting's display value.
+    fn step(&self) -> f64 {
+        match self {
+            Self::LongPress => 50.,
+            Self::TapDistance | Self::ScrollMomentum => 1.,
+        }
+    }
+
+    /// Format a display value with its unit.
+    fn format(&self, value: f64) -> String {
+        match self {
+            Self::LongPress => format!("{value:.0} ms"),
+            Self::TapDistance => format!("{value:.0} px"),
+            Self::ScrollMomentum => format!("{value:.0}%"),
+        }
+    }
+
+    /// Get the setting's display value.
+    pub fn get(&self, input: &Input) -> f64 {
+        match self {
+            Self::LongPress => input.long_press.as_millis() as f64,
+            Self::TapDistance => input.max_tap_distance.sqrt().round(),
+            Self::ScrollMomentum => (input.velocity_friction * 100.).round(),
+        }
+    }
+
+    /// Update the setting from its display value.
+    pub fn set(&self, input: &mut Input, value: f64) {
+        match self {
+            Self::LongPress => input.long_press = Duration::from_millis(value as u64).into(),
+            Self::TapDistance => input.max_tap_distance = value.powi(2),
+            Self::ScrollMomentum => input.velocity_friction = value / 100.,
+        }
+    }
+
+    /// Get the display value at a relative position on the slider.
+    fn value_at(&self, fraction: f64) -> f64 {
+        let range = self.range();
+        let value = range.start() + (range.end() - range.start()) * fraction.clamp(0., 1.);
+        let step = self.step();
+        (value / step).round() * step
+    }
+
+    /// Get the relative position of a display value on the slider.
+    fn fraction(&self, value: f64) -> f64 {
+        let range = self.range();
+        ((value - range.start()) / (range.end() - range.start())).clamp(0., 1.)
+    }
+}
+
+/// Touch event tracking.
+#[derive(Default)]
+struct TouchState {
+    slots: HashMap<i32, TouchSlot>,
+    action: TouchAction,
+}
+
+/// Touch slot state.
+#[derive(Copy, Clone, Default, Debug)]
+struct TouchSlot {
+    point: Point<f64>,
+}
+
+/// Intention of a touch sequence.
+#[derive(PartialEq, Eq, Default)]
+enum TouchAction {
+    #[default]
+    Tap,
+    Slider(usize),
+    Reset,
+    Back,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn setting_values() {
+        let mut input = Input::default();
+
+        // Slider positions snap to the setting's step size.
+        assert_eq!(Setting::LongPress.value_at(0.), 250.);
+        assert_eq!(Setting::LongPress.value_at(0.51), 1_150.);
+        assert_eq!(Setting::LongPress.value_at(2.), 2_000.);
+
+        // Display values are converted to config units.
+        Setting::TapDistance.set(&mut input, 20.);
+        assert_eq!(input.max_tap_distance, 400.);
+        assert_eq!(Setting::TapDistance.get(&input), 20.);
+
+        Setting::ScrollMomentum.set(&mut input, 90.);
+        assert_eq!(Setting::ScrollMomentum.get(&input), 90.);
+        assert_eq!(Setting::ScrollMomentum.fraction(90.), 40. / 48.);
+    }
+
+    #[test]
+    fn override_changed_settings_only() {
+        let mut config = Input { max_tap_distance: 500., ..Input::default() };
+
+        let mut changed = config;
+        Setting::LongPress.set(&mut changed, 1_000.);
+
+        let mut input_override = InputOverride::default();
+        input_override.set(Setting::LongPress, &changed);
+        input_override.apply(&mut config);
+
+        // Untouched settings keep their exact configured value.
+        assert_eq!(config.long_press, Duration::from_millis(1_000).into());
+        assert_eq!(config.max_tap_distance, 500.);
+
+        // Resetting restores the unrounded defaults.
+        let defaults = Input::default();
+        for setting in Setting::ALL {
+            input_override.set(setting, &defaults);
+        }
+        input_override.apply(&mut config);
+        assert_eq!(config.max_tap_distance, 800.);
+    }
+}
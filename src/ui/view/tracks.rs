@@ -0,0 +1,699 @@
+//! GPS track recording UI view.
+
+use std::collections::HashMap;
+use std::mem;
+
+use calloop::LoopHandle;
+use calloop::channel::{self, Event};
+use skia_safe::{Color4f, Paint, Rect};
+
+use crate::config::{Config, Input};
+use crate::db::{Db, Track};
+use crate::geometry::{GeoPoint, Point, Size, rect_contains};
+use crate::track::{self, TrackEvent, TrackRecorder};
+use crate::ui::skia::{RenderState, TextOptions};
+use crate::ui::view::{self, UiView, View};
+use crate::ui::{
+    Button, RowProvider, ScrollList, Svg, move_button_touch, press_button, release_button,
+};
+use crate::{Error, State};
+
+/// Button width and height at scale 1.
+const BUTTON_SIZE: u32 = 48;
+
+/// Padding around the screen edge at scale 1.
+const OUTSIDE_PADDING: u32 = 16;
+
+/// Padding around the content of the track entries at scale 1.
+const TRACK_INSIDE_PADDING: f64 = 16.;
+
+/// Vertical space between track entries at scale 1.
+const TRACK_Y_PADDING: f64 = 2.;
+
+/// Track entry height at scale 1.
+const TRACK_HEIGHT: u32 = 50;
+
+/// Export/Delete button width and height at scale 1.
+const TRACK_BUTTON_SIZE: u32 = 32;
+
+/// Secondary font size for track distance/points relative to primary font.
+const ALT_FONT_SIZE: f32 = 0.5;
+
+/// Status text font size relative to the default.
+const STATUS_FONT_SIZE: f32 = 0.75;
+
+/// Notice shown while the database is locked by another process.
+const READ_ONLY_MESSAGE: &str =
+    "Database in use by another process: tracks can't be recorded or deleted";
+
+/// GPS track recording UI view.
+pub struct TracksView {
+    recorder: TrackRecorder,
+    tracks: Vec<Track>,
+    status: String,
+    delete_pending: Option<i64>,
+    db: Db,
+
+    heatmap_button: Button,
+    record_button: Button,
+    back_button: Button,
+    alt_bg_paint: Paint,
+    highlight_paint: Paint,
+
+    touch_state: TouchState,
+    input_config: Input,
+    track_list: ScrollList,
+
+    event_loop: LoopHandle<'static, State>,
+
+    size: Size,
+    scale: f64,
+
+    dirty: bool,
+}
+
+impl TracksView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    pub fn new(
+        event_loop: LoopHandle<'static, State>,
+        db: Db,
+        config: &Config,
+        size: Size,
+    ) -> Result<Self, Error> {
+        // Handle track list updates and export results.
+        let (track_tx, track_rx) = channel::channel();
+        event_loop.insert_source(track_rx, |event, _, state| {
+            if let Event::Msg(event) = event {
+                let tracks_view = state.window.views.tracks();
+                match event {
+                    TrackEvent::Tracks(tracks) => tracks_view.tracks = tracks,
+                    TrackEvent::Started(id) => {
+                        if !tracks_view.recorder.started(id) {
+                            tracks_view.record_button.set_svg(Svg::Record);
+                        }
+                    },
+                    TrackEvent::Playback(_, points) if points.is_empty() => {
+                        tracks_view.status = String::from("Track has no recorded points");
+                    },
+                    TrackEvent::Playback(track, points) => {
+                        tracks_view.status.clear();
+                        state.window.views.map().play_track(track, points);
+                        state.window.set_view(View::Map);
+                        return;
+                    },
+                    TrackEvent::Status(status) => tracks_view.status = status,
+                }
+                tracks_view.dirty = true;
+                state.window.unstall();
+            }
+        })?;
+
+        // Initialize UI elements.
+        let point = Self::back_button_point(size, 1.);
+        let button_size = Self::button_size(1.);
+        let back_button = Button::new(point, button_size, Svg::ArrowLeft);
+
+        let point = Self::record_button_point(size, 1.);
+        let record_button = Button::new(point, button_size, Svg::Record);
+
+        let point = Self::heatmap_button_point(size, 1.);
+        let heatmap_button = Button::new(point, button_size, Svg::Heatmap);
+
+        let mut alt_bg_paint = Paint::default();
+        alt_bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        let mut highlight_paint = Paint::default();
+        highlight_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+
+        let track_list =
+            ScrollList::new(event_loop.clone(), TRACK_Y_PADDING, OUTSIDE_PADDING as f64);
+
+        let mut tracks_view = Self {
+            heatmap_button,
+            record_button,
+            highlight_paint,
+            alt_bg_paint,
+            back_button,
+            track_list,
+            event_loop,
+            size,
+            recorder: TrackRecorder::new(db.clone(), track_tx),
+            input_config: config.input,
+            dirty: true,
+            scale: 1.,
+            touch_state: Default::default(),
+            tracks: Default::default(),
+            status: Default::default(),
+            delete_pending: Default::default(),
+            db,
+        };
+        tracks_view.update_track_list();
+
+        Ok(tracks_view)
+    }
+
+    /// Update the current GPS location.
+    pub fn set_gps(&mut self, point: Option<GeoPoint>) {
+        if let Some(point) = point {
+            self.recorder.update(point);
+        }
+    }
+
+    /// Start or stop the track recording.
+    fn toggle_recording(&mut self) {
+        if self.recorder.recording() {
+            self.recorder.stop();
+            self.record_button.set_svg(Svg::Record);
+        } else if !self.db.read_only() {
+            self.recorder.start();
+            self.record_button.set_svg(Svg::Stop);
+        }
+
+        self.status.clear();
+        self.track_list.reset();
+        self.dirty = true;
+    }
+
+    /// Draw a track entry.
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw_track<'a>(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'a>,
+        point: Point,
+        size: Size,
+        track: &Track,
+        recording: bool,
+    ) {
+        let padding = (TRACK_INSIDE_PADDING * self.scale).round() as f32;
+
+        // Draw background.
+        let bg_width = point.x as f32 + size.width as f32;
+        let bg_height = point.y as f32 + size.height as f32;
+        let bg_rect = Rect::new(point.x as f32, point.y as f32, bg_width, bg_height);
+        render_state.draw_rect(bg_rect, &self.alt_bg_paint);
+
+        // Draw track's buttons, the active recording can neither be exported nor
+        // deleted.
+        let text_width = if recording {
+            size.width as f32 - padding * 2.
+        } else {
+            let button_size = self.track_button_size();
+            let delete_point = self.delete_button_point();
+
+            // Highlight the delete button while waiting for confirmation.
+            if self.delete_pending == Some(track.id) {
+                let start: Point<f32> = (point + delete_point).into();
+                let end = start + Point::new(button_size.width as f32, button_size.height as f32);
+                let rect = Rect::new(start.x, start.y, end.x, end.y);
+                render_state.draw_rect(rect, &self.highlight_paint);
+            }
+
+            // Hide the delete button while the track can't be removed.
+            if !self.db.read_only() {
+                render_state.draw_svg(Svg::Bin, point + delete_point, button_size);
+            }
+            let export_point = self.export_button_point();
+            render_state.draw_svg(Svg::Download, point + export_point, button_size);
+
+            export_point.x as f32 - padding * 2.
+        };
+
+        let mut text_point = point;
+        text_point.x += padding as i32;
+
+        // Layout track start time.
+
+        let mut title = String::with_capacity("YYYY-MM-DD HH:MM UTC".len());
+        track::format_date(&mut title, track.ctime);
+        title.push_str(" UTC");
+
+        let mut builder = render_state.paragraph(config.colors.foreground, 1., None);
+        builder.add_text(&title);
+
+        let mut title_paragraph = builder.build();
+        title_paragraph.layout(text_width);
+
+        // Layout track distance and point count.
+
+        let mut details = String::with_capacity("X.XX km · 0:00:00 · 99999 Points".len());
+        view::format_distance(&mut details, track.distance, config.navigation.units);
+        details.push_str(" · ");
+        track::format_duration(&mut details, track.duration);
+        details.push_str(" · ");
+        match track.points {
+            1 => details.push_str("1 Point"),
+            count => details.push_str(&format!("{count} Points")),
+        }
+        if recording {
+            details.push_str(" · Recording");
+        }
+
+        let mut builder = render_state.paragraph(config.colors.alt_foreground, ALT_FONT_SIZE, None);
+        builder.add_text(&details);
+
+        let mut details_paragraph = builder.build();
+        details_paragraph.layout(text_width);
+
+        // Draw both labels.
+
+        let title_height = title_paragraph.height().round() as i32;
+        let details_height = details_paragraph.height().round() as i32;
+
+        text_point.y += (size.height as i32 - title_height - details_height) / 2;
+        title_paragraph.paint(render_state, text_point);
+
+        text_point.y += title_height;
+        details_paragraph.paint(render_state, text_point);
+    }
+
+    /// Physical size of the UI SVG buttons.
+    fn button_size(scale: f64) -> Size {
+        Size::new(BUTTON_SIZE, BUTTON_SIZE) * scale
+    }
+
+    /// Physical location of the back button.
+    fn back_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_size = Self::button_size(scale);
+        let physical_size = size * scale;
+
+        let x = (physical_size.width - button_size.width) as i32 - padding;
+        let y = (physical_size.height - button_size.height) as i32 - padding;
+
+        Point::new(x, y)
+    }
+
+    /// Physical location of the recording start/stop button.
+    fn record_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::back_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Physical location of the visited area heatmap button.
+    fn heatmap_button_point(size: Size, scale: f64) -> Point {
+        let padding = (OUTSIDE_PADDING as f64 * scale).round() as i32;
+        let button_width = Self::button_size(scale).width as i32;
+        let mut point = Self::record_button_point(size, scale);
+
+        point.x -= button_width + padding;
+
+        point
+    }
+
+    /// Physical location of the status text.
+    fn status_label_point(&self) -> Point {
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
+        let button_point = Self::heatmap_button_point(self.size, self.scale);
+
+        Point::new(outside_padding, button_point.y)
+    }
+
+    /// Physical size of the status text.
+    fn status_label_size(&self) -> Size {
+        let padding = (OUTSIDE_PADDING as f64 * self.scale).round() as u32;
+        let button_point = Self::heatmap_button_point(self.size, self.scale);
+        let button_size = Self::button_size(self.scale);
+
+        Size::new((button_point.x as u32).saturating_sub(2 * padding), button_size.height)
+    }
+
+    /// Physical point of the bottommost track entry.
+    fn track_point(&self) -> Point {
+        let back_button_point = Self::back_button_point(self.size, self.scale);
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as i32;
+        let track_size = self.track_size();
+
+        let y = back_button_point.y - outside_padding - track_size.height as i32;
+        Point::new(outside_padding, y)
+    }
+
+    /// Physical size of a track entry.
+    fn track_size(&self) -> Size {
+        let outside_padding = (OUTSIDE_PADDING as f64 * self.scale).round() as u32;
+        let size = self.size * self.scale;
+
+        let width = size.width - outside_padding * 2;
+        let height = (TRACK_HEIGHT as f64 * self.scale).round() as u32;
+
+        Size::new(width, height)
+    }
+
+    /// Physical point of the delete button relative to the track origin.
+    fn delete_button_point(&self) -> Point {
+        let button_size = self.track_button_size();
+        let track_size = self.track_size();
+        let padding = (track_size.height - button_size.height) as i32 / 2;
+
+        let x = track_size.width as i32 - button_size.width as i32 - padding;
+        Point::new(x, padding)
+    }
+
+    /// Physical point of the export button relative to the track origin.
+    fn export_button_point(&self) -> Point {
+        let mut point = self.delete_button_point();
+        point.x -= self.track_button_size().width as i32 + point.y;
+        point
+    }
+
+    /// Physical size of the track's export/delete buttons.
+    fn track_button_size(&self) -> Size {
+        Size::new(TRACK_BUTTON_SIZE, TRACK_BUTTON_SIZE) * self.scale
+    }
+
+    /// Check whether a track is the active recording.
+    fn is_recording(&self, index: usize) -> bool {
+        self.recorder.active_track() == Some(self.tracks[index].id)
+    }
+
+    /// Get track at the specified location.
+    fn track_at(&self, point: Point<f64>) -> Option<(usize, TrackButton)> {
+        let (rindex, relative_point) = self.track_list.row_at(point, self.tracks.len())?;
+        let index = self.tracks.len() - rindex - 1;
+
+        // Check whether the tap is within one of the track's buttons.
+        let button_size: Size<f64> = self.track_button_size().into();
+        let button = if self.is_recording(index) {
+            TrackButton::None
+        } else if rect_contains(self.delete_button_point().into(), button_size, relative_point) {
+            TrackButton::Delete
+        } else if rect_contains(self.export_button_point().into(), button_size, relative_point) {
+            TrackButton::Export
+        } else {
+            TrackButton::None
+        };
+
+        Some((index, button))
+    }
+
+    /// Update the track list geometry.
+    fn update_track_list(&mut self) {
+        self.track_list.set_point(self.track_point());
+        self.track_list.set_row_size(self.track_size());
+        self.track_list.set_scale_factor(self.scale);
+    }
+
+    /// Get the button targeted by the active touch sequence.
+    fn action_button(&mut self) -> Option<&mut Button> {
+        match self.touch_state.action {
+            TouchAction::Heatmap => Some(&mut self.heatmap_button),
+            TouchAction::Record => Some(&mut self.record_button),
+            TouchAction::Back => Some(&mut self.back_button),
+            TouchAction::Tap | TouchAction::Drag => None,
+        }
+    }
+}
+
+impl RowProvider for TracksView {
+    fn row_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    fn draw_row(
+        &self,
+        config: &Config,
+        render_state: &mut RenderState<'_>,
+        index: usize,
+        point: Point,
+        size: Size,
+    ) {
+        // Show the most recent track at the top of the list.
+        let index = self.tracks.len() - index - 1;
+        let recording = self.is_recording(index);
+        self.draw_track(config, render_state, point, size, &self.tracks[index], recording);
+    }
+
+    fn row_label(&self, index: usize) -> Option<String> {
+        let track = &self.tracks[self.tracks.len() - index - 1];
+        let mut label = String::new();
+        track::format_date(&mut label, track.ctime);
+        Some(label)
+    }
+}
+
+impl UiView for TracksView {
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn draw<'a>(&mut self, config: &Config, mut render_state: RenderState<'a>) {
+        // Apply scroll velocity and ensure offset is correct in case size changed.
+        self.track_list.update(&self.input_config, self.tracks.len());
+
+        // Clear dirtiness flag.
+        self.dirty = false;
+
+        // Ensure paints are up to date.
+        self.alt_bg_paint.set_color4f(Color4f::from(config.colors.alt_background), None);
+        self.highlight_paint.set_color4f(Color4f::from(config.colors.highlight), None);
+
+        render_state.clear(config.colors.background);
+
+        // Render track entries.
+        self.track_list.draw(config, &mut render_state, self);
+
+        // Draw status vertically centered next to the buttons.
+        let status = match (self.status.is_empty(), self.tracks.is_empty()) {
+            (false, _) => self.status.as_str(),
+            (true, _) if self.db.read_only() => READ_ONLY_MESSAGE,
+            (true, true) => "No recorded tracks",
+            (true, false) => "",
+        };
+        if !status.is_empty() {
+            let label_point: Point<f32> = self.status_label_point().into();
+            let label_size: Size<f32> = self.status_label_size().into();
+
+            let text_options = Some(TextOptions::new().ellipsize(false));
+            let mut builder =
+                render_state.paragraph(config.colors.foreground, STATUS_FONT_SIZE, text_options);
+            builder.add_text(status);
+
+            let mut paragraph = builder.build();
+            paragraph.layout(label_size.width);
+
+            let y = label_point.y + (label_size.height - paragraph.height()) / 2.;
+            paragraph.paint(&render_state, Point::new(label_point.x, y));
+        }
+
+        // Render navigation buttons.
+        self.record_button.set_disabled(self.db.read_only() && !self.recorder.recording());
+        self.heatmap_button.draw(&mut render_state, config.colors.alt_background);
+        self.record_button.draw(&mut render_state, config.colors.alt_background);
+        self.back_button.draw(&mut render_state, config.colors.alt_background);
+    }
+
+    fn dirty(&self) -> bool {
+        self.dirty || self.track_list.is_animating()
+    }
+
+    fn enter(&mut self) {
+        self.delete_pending = None;
+        self.status.clear();
+        self.recorder.reload();
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_size(&mut self, size: Size) {
+        self.size = size;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.back_button.set_point(Self::back_button_point(size, self.scale));
+        self.record_button.set_point(Self::record_button_point(size, self.scale));
+        self.heatmap_button.set_point(Self::heatmap_button_point(size, self.scale));
+
+        self.update_track_list();
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn set_scale_factor(&mut self, scale: f64) {
+        self.scale = scale;
+        self.dirty = true;
+
+        // Update UI elements.
+        self.back_button.set_point(Self::back_button_point(self.size, scale));
+        self.back_button.set_size(Self::button_size(scale));
+        self.record_button.set_point(Self::record_button_point(self.size, scale));
+        self.record_button.set_size(Self::button_size(scale));
+        self.heatmap_button.set_point(Self::heatmap_button_point(self.size, scale));
+        self.heatmap_button.set_size(Self::button_size(scale));
+
+        self.update_track_list();
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_down(&mut self, slot: i32, _time: u32, point: Point<f64>) {
+        // Cancel velocity if a new touch sequence starts.
+        self.track_list.stop();
+
+        // Only allow a single active touch slot.
+        if !self.touch_state.slots.is_empty() {
+            return;
+        }
+
+        // Determine goal of this touch sequence.
+        let point = point * self.scale;
+        self.touch_state.action = if self.back_button.contains(point) {
+            TouchAction::Back
+        } else if self.record_button.contains(point) {
+            TouchAction::Record
+        } else if self.heatmap_button.contains(point) {
+            TouchAction::Heatmap
+        } else {
+            TouchAction::Tap
+        };
+
+        // Cancel pending deletion when anything else is touched.
+        if self.delete_pending.is_some() {
+            let delete_touched = self.touch_state.action == TouchAction::Tap
+                && matches!(self.track_at(point), Some((_, TrackButton::Delete)));
+            if !delete_touched {
+                self.delete_pending = None;
+                self.status.clear();
+                self.dirty = true;
+            }
+        }
+
+        // Highlight the touched button.
+        let input_config = self.input_config;
+        self.dirty |= press_button(self.action_button(), &input_config);
+
+        // Convert position to physical space.
+        let slot = self.touch_state.slots.entry(slot).or_default();
+        slot.point = point;
+        slot.start = point;
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_motion(&mut self, slot: i32, point: Point<f64>) {
+        // Ignore unknown touch slots.
+        let slot = match self.touch_state.slots.get_mut(&slot) {
+            Some(slot) => slot,
+            None => return,
+        };
+
+        // Update touch point.
+        let point = point * self.scale;
+        let old_point = mem::replace(&mut slot.point, point);
+
+        // Handle action transitions.
+        if let TouchAction::Tap | TouchAction::Drag = self.touch_state.action {
+            // Ignore dragging until tap distance limit is exceeded.
+            let max_tap_distance = self.input_config.max_tap_distance;
+            let delta = slot.point - slot.start;
+            if delta.x.powi(2) + delta.y.powi(2) <= max_tap_distance {
+                return;
+            }
+            self.touch_state.action = TouchAction::Drag;
+
+            // Apply scroll motion.
+            let delta = slot.point.y - old_point.y;
+            self.dirty |= self.track_list.drag(delta, self.tracks.len());
+        } else {
+            self.dirty |= move_button_touch(self.action_button(), point);
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn touch_up(&mut self, slot: i32) {
+        // Reset touch slot, ignoring unknown slots.
+        let removed = match self.touch_state.slots.remove(&slot) {
+            Some(removed) => removed,
+            None => return,
+        };
+
+        // Clear button press highlight.
+        self.dirty |= release_button(self.action_button());
+
+        // Dispatch tap actions on release.
+        match self.touch_state.action {
+            // Handle touch tap on track entries.
+            TouchAction::Tap => match self.track_at(removed.point) {
+                Some((index, TrackButton::Export)) => {
+                    self.status = String::from("Exporting track…");
+                    self.recorder.export(self.tracks[index]);
+                    self.dirty = true;
+                },
+                // Delete tracks after a second tap on their delete button.
+                Some((_, TrackButton::Delete)) if self.db.read_only() => (),
+                Some((index, TrackButton::Delete)) => {
+                    if self.delete_pending == Some(self.tracks[index].id) {
+                        let track = self.tracks.remove(index);
+                        self.recorder.delete(track);
+                        self.delete_pending = None;
+                        self.status.clear();
+                    } else {
+                        self.delete_pending = Some(self.tracks[index].id);
+                        self.status = String::from("Tap again to delete the track");
+                    }
+                    self.dirty = true;
+                },
+                // Play back the track on the map.
+                Some((index, TrackButton::None)) => {
+                    self.status = String::from("Loading track…");
+                    self.recorder.play(self.tracks[index]);
+                    self.dirty = true;
+                },
+                None => (),
+            },
+            // Handle recording start/stop button.
+            TouchAction::Record if self.record_button.contains(removed.point) => {
+                self.toggle_recording();
+            },
+            // Show visited areas on the map.
+            TouchAction::Heatmap if self.heatmap_button.contains(removed.point) => {
+                self.event_loop.insert_idle(|state| {
+                    state.window.views.map().show_heatmap();
+                    state.window.set_view(View::Map);
+                });
+            },
+            // Handle "back" button navigation.
+            TouchAction::Back if self.back_button.contains(removed.point) => {
+                self.event_loop.insert_idle(|state| state.window.set_view(View::Search));
+            },
+            _ => (),
+        }
+    }
+
+    #[cfg_attr(feature = "profiling", profiling::function)]
+    fn update_config(&mut self, config: &Config) {
+        if self.input_config != config.input {
+            self.input_config = config.input;
+            self.dirty = true;
+        }
+    }
+}
+
+/// Touch event tracking.
+#[derive(Default)]
+struct TouchState {
+    slots: HashMap<i32, TouchSlot>,
+    action: TouchAction,
+}
+
+/// Touch slot state.
+#[derive(Copy, Clone, Default, Debug)]
+struct TouchSlot {
+    start: Point<f64>,
+    point: Point<f64>,
+}
+
+/// Intention of a touch sequence.
+#[derive(PartialEq, Eq, Default)]
+enum TouchAction {
+    #[default]
+    Tap,
+    Drag,
+    Heatmap,
+    Record,
+    Back,
+}
+
+/// Button within a track entry.
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+enum TrackButton {
+    Export,
+    Delete,
+    None,
+}
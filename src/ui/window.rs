@@ -1,28 +1,62 @@
 //! Wayland window management.
 
+use std::collections::HashMap;
 use std::mem;
+use std::path::PathBuf;
 use std::ptr::NonNull;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
+use _idle::zwp_idle_inhibitor_v1::ZwpIdleInhibitorV1;
 use _text_input::zwp_text_input_v3::{ChangeCause, ContentHint, ContentPurpose, ZwpTextInputV3};
+use accesskit::ActionRequest;
 use calloop::LoopHandle;
+use calloop::channel::{self, Event, Sender};
 use glutin::display::{Display, DisplayApiPreference};
 use raw_window_handle::{RawDisplayHandle, WaylandDisplayHandle};
+use reqwest::Client;
 use smithay_client_toolkit::compositor::{CompositorState, Region};
+use smithay_client_toolkit::output::OutputState;
+use smithay_client_toolkit::reexports::client::protocol::wl_output::WlOutput;
+use smithay_client_toolkit::reexports::client::protocol::wl_surface::WlSurface;
 use smithay_client_toolkit::reexports::client::{Connection, QueueHandle};
+use smithay_client_toolkit::reexports::protocols::wp::idle_inhibit::zv1::client as _idle;
 use smithay_client_toolkit::reexports::protocols::wp::text_input::zv3::client as _text_input;
 use smithay_client_toolkit::reexports::protocols::wp::viewporter::client::wp_viewport::WpViewport;
 use smithay_client_toolkit::seat::keyboard::{Keysym, Modifiers};
 use smithay_client_toolkit::shell::WaylandSurface;
 use smithay_client_toolkit::shell::xdg::window::{Window as XdgWindow, WindowDecorations};
+use tokio::sync::oneshot;
+use tracing::{error, info, warn};
 
-use crate::config::Config;
+use crate::config::{Config, KeyAction, Theme, ThemeMode, ThemeVariant, Units};
 use crate::db::Db;
-use crate::geometry::{Point, Size};
+use crate::geometry::{GeoPoint, Point, Size};
+use crate::router::Route;
+use crate::tilejson::TileJson;
+use crate::ui::accessibility::Accessibility;
+use crate::ui::directions_window::DirectionsWindow;
+use crate::ui::mini_window::MiniWindow;
 use crate::ui::renderer::Renderer;
 use crate::ui::skia::Canvas;
-use crate::ui::view::{View, Views};
+use crate::ui::surface::{Surface, SurfaceKind};
+use crate::ui::trip_summary::{self, Trip};
+use crate::ui::view::settings::InputOverride;
+use crate::ui::view::{self, View, Views};
 use crate::wayland::ProtocolStates;
-use crate::{Error, State};
+use crate::wayland::idle_inhibit::IdleInhibitManager;
+use crate::{Error, State, dbus, sun};
+
+/// Reason for inhibiting the screensaver, reported to the DBus fallback.
+const IDLE_INHIBIT_REASON: &str = "Navigating";
+
+/// Scroll wheel distance of a single zoom step.
+///
+/// This matches one detent of a regular scroll wheel.
+const SCROLL_ZOOM_STEP: i32 = 120;
+
+/// Touch slot used for taps requested by screen readers.
+const ACCESSIBILITY_TOUCH_SLOT: i32 = i32::MAX;
 
 /// Wayland window.
 pub struct Window {
@@ -33,16 +67,41 @@ pub struct Window {
     xdg_window: XdgWindow,
     viewport: WpViewport,
 
+    idle_inhibit_manager: Option<IdleInhibitManager>,
+    idle_inhibitor: Option<IdleInhibitor>,
+
     ime_cause: Option<ChangeCause>,
     text_input: Option<TextInput>,
 
     renderer: Renderer,
     canvas: Canvas,
-
+    accessibility: Accessibility,
+
+    egl_display: Display,
+    surfaces: Vec<Box<dyn Surface>>,
+    keyboard_surface: Option<WlSurface>,
+
+    base_config: Config,
+    input_override: InputOverride,
+    online_services: bool,
+    tilejson: Option<TileJson>,
+    tilejson_tx: Sender<(Arc<String>, TileJson)>,
+    trip_summary_tx: Sender<PathBuf>,
+    client: Client,
     config: Config,
+    theme: ThemeVariant,
+    country_units: Units,
+    location: Option<GeoPoint>,
+
+    outputs: Vec<WlOutput>,
+    output_name: Option<String>,
+    output_zooms: HashMap<String, u8>,
+    db: Db,
 
     size: Size,
     scale: f64,
+    last_touch_time: (u32, Instant),
+    scroll_remainder: i32,
 
     initial_configure_done: bool,
     text_input_dirty: bool,
@@ -83,32 +142,107 @@ impl Window {
         xdg_window.commit();
 
         // Create OpenGL renderer.
-        let renderer = Renderer::new(egl_display, surface);
+        let renderer = Renderer::new(egl_display.clone(), surface);
 
         // Default to a reasonable default size.
         let size = Size { width: 360, height: 720 };
 
-        let views = Views::new(event_loop, &config, db, size)?;
+        // Apply the theme variant, until the GPS location is known.
+        let theme = theme_variant(&config.theme, None, ThemeVariant::default());
+        let base_config = config;
+        let tilejson = TileJson::cached(&base_config.tiles.tilejson);
+        let country_units = Units::Metric;
+        let config = resolve_config(
+            &base_config,
+            theme,
+            country_units,
+            tilejson.as_ref(),
+            &Default::default(),
+            true,
+        );
+
+        let mut views = Views::new(event_loop, &config, db.clone(), size)?;
+        views.map().set_mini_window_supported(protocol_states.layer_shell.is_some());
         let canvas = Canvas::new(&config);
+        let accessibility = Accessibility::new(event_loop)?;
+
+        // Load preferred zoom levels of all outputs in the background.
+        let (zooms_tx, zooms_rx) = channel::channel();
+        event_loop.insert_source(zooms_rx, |event, _, state| {
+            if let Event::Msg(zooms) = event {
+                state.window.set_output_zooms(zooms);
+            }
+        })?;
+        let zooms_db = db.clone();
+        tokio::spawn(async move {
+            match zooms_db.output_zooms().await {
+                Ok(zooms) => {
+                    let _ = zooms_tx.send(zooms);
+                },
+                Err(err) => error!("Failed to load output zoom levels: {err}"),
+            }
+        });
 
-        Ok(Self {
+        // Update the tile source once its TileJSON document was downloaded.
+        let (tilejson_tx, tilejson_rx) = channel::channel();
+        event_loop.insert_source(tilejson_rx, |event, _, state| {
+            if let Event::Msg((url, tilejson)) = event {
+                state.window.set_tilejson(url, tilejson);
+            }
+        })?;
+
+        // Show the trip summary's location once it was saved.
+        let (trip_summary_tx, trip_summary_rx) = channel::channel();
+        event_loop.insert_source(trip_summary_rx, |event, _, state| {
+            if let Event::Msg(path) = event {
+                state.window.views.map().set_trip_summary_path(path);
+                state.window.unstall();
+            }
+        })?;
+
+        let mut window = Self {
+            client: crate::http_client()?,
+            base_config,
+            trip_summary_tx,
+            tilejson_tx,
+            tilejson,
             connection,
             xdg_window,
             renderer,
             viewport,
             canvas,
+            accessibility,
+            egl_display,
             config,
             queue,
             views,
+            theme,
             size,
+            db,
+            online_services: true,
             stalled: true,
             dirty: true,
             scale: 1.,
             initial_configure_done: Default::default(),
+            last_touch_time: (0, Instant::now()),
+            scroll_remainder: Default::default(),
             text_input_dirty: Default::default(),
+            input_override: Default::default(),
             text_input: Default::default(),
             ime_cause: Default::default(),
-        })
+            country_units,
+            location: Default::default(),
+            idle_inhibit_manager: protocol_states.idle_inhibit.clone(),
+            output_zooms: Default::default(),
+            idle_inhibitor: Default::default(),
+            keyboard_surface: Default::default(),
+            surfaces: Default::default(),
+            output_name: Default::default(),
+            outputs: Default::default(),
+        };
+        window.download_tilejson();
+
+        Ok(window)
     }
 
     /// Redraw the window.
@@ -126,6 +260,7 @@ impl Window {
         self.dirty = false;
 
         self.update_text_input();
+        self.update_idle_inhibit();
 
         // Update viewporter logical render size.
         //
@@ -144,6 +279,7 @@ impl Window {
                 self.views.draw(&self.config, render_state);
             });
         });
+        self.accessibility.update(self.canvas.take_accessible_elements());
 
         // Request a new frame.
         wl_surface.frame(&self.queue, wl_surface.clone());
@@ -165,6 +301,139 @@ impl Window {
         let _ = self.connection.flush();
     }
 
+    /// Get the secondary surface owning a Wayland surface.
+    pub fn surface(&mut self, wl_surface: &WlSurface) -> Option<&mut dyn Surface> {
+        let surface =
+            self.surfaces.iter_mut().find(|surface| surface.wl_surface() == wl_surface)?;
+        Some(surface.as_mut())
+    }
+
+    /// Get the secondary surface of a specific type.
+    fn surface_kind(&mut self, kind: SurfaceKind) -> Option<&mut dyn Surface> {
+        let surface = self.surfaces.iter_mut().find(|surface| surface.kind() == kind)?;
+        Some(surface.as_mut())
+    }
+
+    /// Close a secondary surface.
+    pub fn close_surface(&mut self, kind: SurfaceKind) {
+        let count = self.surfaces.len();
+        self.surfaces.retain(|surface| surface.kind() != kind);
+
+        if self.surfaces.len() != count {
+            let _ = self.connection.flush();
+        }
+    }
+
+    /// Show the route's directions in a separate window.
+    pub fn open_directions_window(
+        &mut self,
+        event_loop: &LoopHandle<'static, State>,
+        protocol_states: &ProtocolStates,
+        route: Arc<Route>,
+        progress: usize,
+    ) {
+        if self.surface_kind(SurfaceKind::Directions).is_none() {
+            match DirectionsWindow::new(
+                event_loop,
+                protocol_states,
+                self.connection.clone(),
+                self.queue.clone(),
+                self.egl_display.clone(),
+                &self.config,
+            ) {
+                Ok(window) => self.surfaces.push(Box::new(window)),
+                Err(err) => {
+                    error!("Failed to open directions window: {err}");
+                    return;
+                },
+            }
+        }
+
+        if let Some(directions_window) = self.surface_kind(SurfaceKind::Directions) {
+            directions_window.set_route(route);
+            directions_window.set_progress(progress);
+        }
+    }
+
+    /// Show or hide the always-on-top navigation overlay.
+    pub fn toggle_mini_window(&mut self, protocol_states: &ProtocolStates) {
+        if self.surface_kind(SurfaceKind::MiniWindow).is_some() {
+            self.close_surface(SurfaceKind::MiniWindow);
+            return;
+        }
+
+        let layer_shell = match &protocol_states.layer_shell {
+            Some(layer_shell) => layer_shell,
+            None => {
+                warn!("Navigation overlay requires the wlr_layer_shell protocol");
+                return;
+            },
+        };
+
+        let mut mini_window = MiniWindow::new(
+            protocol_states,
+            layer_shell,
+            self.connection.clone(),
+            self.queue.clone(),
+            self.egl_display.clone(),
+            &self.config,
+        );
+
+        let directions = self.views.directions();
+        mini_window.set_route(directions.route());
+        mini_window.set_progress(directions.progress());
+
+        self.surfaces.push(Box::new(mini_window));
+    }
+
+    /// Save a summary image of a completed trip.
+    ///
+    /// Returns the PNG image, so it can also be copied to the clipboard.
+    pub fn save_trip_summary(&mut self, trip: Trip) -> Option<Vec<u8>> {
+        let png = match trip.render(&self.config, self.views.map().tiles_mut()) {
+            Some(png) => png,
+            None => {
+                error!("Failed to encode trip summary image");
+                return None;
+            },
+        };
+
+        let trip_summary_tx = self.trip_summary_tx.clone();
+        let saved_png = png.clone();
+        tokio::spawn(async move {
+            match trip_summary::save(saved_png).await {
+                Ok(path) => {
+                    info!("Saved trip summary to {path:?}");
+                    let _ = trip_summary_tx.send(path);
+                },
+                Err(err) => error!("Could not save trip summary: {err}"),
+            }
+        });
+
+        Some(png)
+    }
+
+    /// Check whether a surface belongs to the main window.
+    pub fn is_main_surface(&self, surface: &WlSurface) -> bool {
+        self.xdg_window.wl_surface() == surface
+    }
+
+    /// Update the route shown by the textual directions.
+    pub fn set_directions_route(&mut self, route: Arc<Route>) {
+        for surface in &mut self.surfaces {
+            surface.set_route(route.clone());
+        }
+        self.views.directions().set_route(route);
+    }
+
+    /// Update the route progress shown by the textual directions.
+    pub fn set_directions_progress(&mut self, progress: usize) {
+        for surface in &mut self.surfaces {
+            surface.set_progress(progress);
+        }
+        self.views.directions().set_progress(progress);
+    }
+
     /// Update the window's logical size.
     pub fn set_size(&mut self, compositor: &CompositorState, size: Size) {
         if self.size == size {
@@ -211,9 +480,192 @@ impl Window {
         self.unstall();
     }
 
+    /// Handle the window entering an output.
+    pub fn output_entered(&mut self, output_state: &OutputState, output: &WlOutput) {
+        if !self.outputs.contains(output) {
+            self.outputs.push(output.clone());
+        }
+        self.update_output(output_state);
+    }
+
+    /// Handle the window leaving an output.
+    pub fn output_left(&mut self, output_state: &OutputState, output: &WlOutput) {
+        self.outputs.retain(|entered| entered != output);
+        self.update_output(output_state);
+    }
+
+    /// Get the window's output name and its current map zoom level.
+    pub fn output_zoom(&mut self) -> Option<(String, u8)> {
+        let name = self.output_name.clone()?;
+        Some((name, self.views.map().zoom()))
+    }
+
+    /// Apply the zoom levels loaded from the database.
+    ///
+    /// Since they are loaded in the background, the window might already be
+    /// shown on an output, whose zoom level is restored immediately.
+    fn set_output_zooms(&mut self, zooms: HashMap<String, u8>) {
+        // Keep zoom levels stored while the database was loading.
+        let stored = mem::replace(&mut self.output_zooms, zooms);
+
+        let current_zoom = self
+            .output_name
+            .as_ref()
+            .filter(|name| !stored.contains_key(*name))
+            .and_then(|name| self.output_zooms.get(name).copied());
+
+        self.output_zooms.extend(stored);
+
+        if let Some(zoom) = current_zoom {
+            let map = self.views.map();
+            map.goto(map.center_point(), Some(zoom));
+            self.unstall();
+        }
+    }
+
+    /// Update the output the window is primarily shown on.
+    ///
+    /// The map zoom level is stored for every output, and restored when
+    /// moving back to it.
+    fn update_output(&mut self, output_state: &OutputState) {
+        // Keep the last output while the window isn't visible on any of them.
+        let name = self.outputs.first().and_then(|output| output_state.info(output)?.name);
+        if name.is_none() || name == self.output_name {
+            return;
+        }
+
+        // Remember the zoom level of the previous output.
+        if let Some((old_name, zoom)) = self.output_zoom() {
+            self.output_zooms.insert(old_name.clone(), zoom);
+
+            let db = self.db.clone();
+            tokio::spawn(async move {
+                if let Err(err) = db.set_output_zoom(&old_name, zoom).await {
+                    error!("Failed to store output zoom level: {err}");
+                }
+            });
+        }
+
+        // Restore the zoom level of the new output.
+        if let Some(&zoom) = name.as_ref().and_then(|name| self.output_zooms.get(name)) {
+            let map = self.views.map();
+            map.goto(map.center_point(), Some(zoom));
+            self.unstall();
+        }
+
+        self.output_name = name;
+    }
+
+    /// Get the user's configuration, before applying themes and tile sources.
+    pub fn base_config(&self) -> &Config {
+        &self.base_config
+    }
+
     /// Handle config updates.
     #[cfg_attr(feature = "profiling", profiling::function)]
     pub fn update_config(&mut self, config: Config) {
+        self.theme = theme_variant(&config.theme, self.location, self.theme);
+        let tilejson_changed = self.base_config.tiles.tilejson != config.tiles.tilejson;
+        self.base_config = config;
+
+        // Switch to the last known copy of the new tile source immediately.
+        if tilejson_changed {
+            self.tilejson = TileJson::cached(&self.base_config.tiles.tilejson);
+            self.download_tilejson();
+        }
+
+        self.apply_config();
+    }
+
+    /// Update the configured TileJSON document in the background.
+    fn download_tilejson(&self) {
+        let url = self.base_config.tiles.tilejson.clone();
+        if url.is_empty() {
+            return;
+        }
+
+        let tilejson_tx = self.tilejson_tx.clone();
+        let client = self.client.clone();
+        tokio::spawn(async move {
+            match TileJson::download(&client, &url).await {
+                Ok(tilejson) => {
+                    let _ = tilejson_tx.send((url, tilejson));
+                },
+                Err(err) => error!("Failed to load TileJSON {url:?}: {err}"),
+            }
+        });
+    }
+
+    /// Apply a downloaded TileJSON document.
+    fn set_tilejson(&mut self, url: Arc<String>, tilejson: TileJson) {
+        // Ignore documents of outdated TileJSON URLs.
+        if url != self.base_config.tiles.tilejson {
+            return;
+        }
+
+        self.tilejson = Some(tilejson);
+        self.apply_config();
+    }
+
+    /// Update the theme variant for the current GPS location.
+    pub fn update_theme(&mut self, location: GeoPoint) {
+        self.location = Some(location);
+
+        let theme = theme_variant(&self.base_config.theme, self.location, self.theme);
+        if theme != self.theme {
+            self.theme = theme;
+            self.dirty = true;
+
+            self.apply_config();
+        }
+    }
+
+    /// Update the automatic distance units for the country at the GPS location.
+    pub fn update_units(&mut self, location: GeoPoint) {
+        if self.base_config.navigation.units != Units::Auto {
+            return;
+        }
+
+        // Keep the current units outside of known countries.
+        let imperial = match self.views.regions().world().country_code_at(location) {
+            Some(country_code) => view::is_imperial_country(country_code),
+            None => return,
+        };
+
+        let units = if imperial { Units::Imperial } else { Units::Metric };
+        if units != self.country_units {
+            self.country_units = units;
+            self.dirty = true;
+
+            self.apply_config();
+        }
+    }
+
+    /// Override the configured input sensitivity.
+    pub fn set_input_override(&mut self, input_override: InputOverride) {
+        self.input_override = input_override;
+        self.apply_config();
+    }
+
+    /// Enable or disable the online search and routing services.
+    pub fn set_online_services(&mut self, online_services: bool) {
+        if online_services != self.online_services {
+            self.online_services = online_services;
+            self.apply_config();
+        }
+    }
+
+    /// Apply the active theme variant to the user's configuration.
+    fn apply_config(&mut self) {
+        let config = resolve_config(
+            &self.base_config,
+            self.theme,
+            self.country_units,
+            self.tilejson.as_ref(),
+            &self.input_override,
+            self.online_services,
+        );
+
         self.canvas.update_config(&config);
 
         // Update both active and inactive views.
@@ -221,15 +673,25 @@ impl Window {
             view.update_config(&config);
         }
 
+        for surface in &mut self.surfaces {
+            surface.update_config(&config);
+        }
+
         self.config = config;
 
-        if self.views.dirty() {
+        if self.dirty() {
             self.unstall();
         }
     }
 
     /// Handle touch press.
-    pub fn touch_down(&mut self, slot: i32, time: u32, point: Point<f64>) {
+    pub fn touch_down(&mut self, surface: &WlSurface, slot: i32, time: u32, point: Point<f64>) {
+        if let Some(surface) = self.surface(surface) {
+            surface.touch_down(slot, time, point);
+            return;
+        }
+
+        self.last_touch_time = (time, Instant::now());
         self.views.touch_down(slot, time, point);
 
         if self.views.dirty() {
@@ -239,6 +701,11 @@ impl Window {
 
     /// Handle touch motion.
     pub fn touch_motion(&mut self, id: i32, point: Point<f64>) {
+        if let Some(surface) = self.touch_surface(id) {
+            surface.touch_motion(id, point);
+            return;
+        }
+
         self.views.touch_motion(id, point);
 
         if self.views.dirty() {
@@ -248,6 +715,11 @@ impl Window {
 
     /// Handle touch release.
     pub fn touch_up(&mut self, slot: i32) {
+        if let Some(surface) = self.touch_surface(slot) {
+            surface.touch_up(slot);
+            return;
+        }
+
         self.views.touch_up(slot);
 
         if self.views.dirty() {
@@ -255,8 +727,69 @@ impl Window {
         }
     }
 
+    /// Get the secondary surface owning a touch sequence.
+    fn touch_surface(&mut self, slot: i32) -> Option<&mut dyn Surface> {
+        let surface = self.surfaces.iter_mut().find(|surface| surface.has_touch(slot))?;
+        Some(surface.as_mut())
+    }
+
+    /// Handle vertical scroll wheel input.
+    ///
+    /// The distance is in fractions of 120 per wheel detent, with positive
+    /// values scrolling down.
+    pub fn scroll(&mut self, value120: i32) {
+        if self.views.active() != View::Map {
+            self.scroll_remainder = 0;
+            return;
+        }
+
+        // Zoom in for every full step scrolled upwards, and out when scrolling down.
+        self.scroll_remainder += value120;
+        let steps = self.scroll_remainder / SCROLL_ZOOM_STEP;
+        self.scroll_remainder %= SCROLL_ZOOM_STEP;
+        for _ in 0..steps.abs() {
+            self.views.map().step_zoom(steps < 0);
+        }
+
+        if self.views.dirty() {
+            self.unstall();
+        }
+    }
+
+    /// Handle a screen reader connecting to the window.
+    pub fn accessibility_activated(&mut self) {
+        self.accessibility.activate();
+        self.dirty = true;
+        self.unstall();
+    }
+
+    /// Handle actions requested by a screen reader.
+    pub fn accessibility_action(&mut self, request: ActionRequest) {
+        let point = match self.accessibility.action_point(&request) {
+            Some(point) => point / self.scale,
+            None => return,
+        };
+
+        // Estimate the compositor's current input time, since reusing the last
+        // touch's time would make screen reader taps look like multi-taps.
+        let (time, instant) = self.last_touch_time;
+        let time = time.wrapping_add(instant.elapsed().as_millis() as u32);
+
+        // Dispatch the action as a tap on the element.
+        let surface = self.xdg_window.wl_surface().clone();
+        self.touch_down(&surface, ACCESSIBILITY_TOUCH_SLOT, time, point);
+        self.touch_up(ACCESSIBILITY_TOUCH_SLOT);
+    }
+
     /// Handle keyboard focus.
-    pub fn keyboard_enter(&mut self) {
+    pub fn keyboard_enter(&mut self, surface: &WlSurface) {
+        self.keyboard_surface = Some(surface.clone());
+
+        // Secondary surfaces handle their own keyboard input.
+        if !self.is_main_surface(surface) {
+            return;
+        }
+
         for view in self.views.views_mut() {
             view.keyboard_enter();
         }
@@ -267,7 +800,15 @@ impl Window {
     }
 
     /// Handle keyboard focus loss.
-    pub fn keyboard_leave(&mut self) {
+    pub fn keyboard_leave(&mut self, surface: &WlSurface) {
+        if self.keyboard_surface.as_ref() == Some(surface) {
+            self.keyboard_surface = None;
+        }
+
+        if !self.is_main_surface(surface) {
+            return;
+        }
+
         for view in self.views.views_mut() {
             view.keyboard_leave();
         }
@@ -279,13 +820,49 @@ impl Window {
 
     /// Handle keyboard key press.
     pub fn press_key(&mut self, raw: u32, keysym: Keysym, modifiers: Modifiers) {
-        self.views.press_key(raw, keysym, modifiers);
+        // Send keys to the secondary surface with keyboard focus.
+        if let Some(wl_surface) = self.keyboard_surface.clone()
+            && let Some(surface) = self.surface(&wl_surface)
+        {
+            surface.press_key(raw, keysym, modifiers);
+            return;
+        }
+
+        // Handle global shortcuts before passing keys to the active view, unless the
+        // view needs the key itself.
+        let text_input = self.views.text_input_enabled();
+        let action = self.base_config.keys.action(keysym, modifiers, text_input);
+        match action.filter(|_| !self.views.captures_key(keysym, modifiers)) {
+            Some(action) => self.run_key_action(action),
+            None => self.views.press_key(raw, keysym, modifiers),
+        }
 
         if self.views.dirty() {
             self.unstall();
         }
     }
 
+    /// Execute a keyboard shortcut.
+    fn run_key_action(&mut self, action: KeyAction) {
+        match action {
+            KeyAction::Search => self.set_view(View::Search),
+            KeyAction::Map => self.set_view(View::Map),
+            KeyAction::Downloads => self.set_view(View::Download),
+            KeyAction::ZoomIn => self.views.map().step_zoom(true),
+            KeyAction::ZoomOut => self.views.map().step_zoom(false),
+            KeyAction::CenterGps => {
+                self.views.map().center_gps();
+                self.set_view(View::Map);
+            },
+            KeyAction::CancelRoute => {
+                self.views.map().cancel_route();
+                if let View::Route | View::Directions = self.views.active() {
+                    self.set_view(View::Map);
+                }
+            },
+        }
+    }
+
     /// Paste text into the window.
     pub fn paste(&mut self, text: &str) {
         self.views.paste(text);
@@ -348,6 +925,29 @@ impl Window {
         }
     }
 
+    /// Keep the screen awake while navigating.
+    fn update_idle_inhibit(&mut self) {
+        let navigating = self.views.map().is_navigating();
+        if navigating == self.idle_inhibitor.is_some() {
+            return;
+        }
+
+        if !navigating {
+            self.idle_inhibitor = None;
+            return;
+        }
+
+        // Prefer the Wayland protocol, since it is tied to the surface's visibility.
+        let inhibitor = match &self.idle_inhibit_manager {
+            Some(manager) => {
+                let surface = self.xdg_window.wl_surface();
+                IdleInhibitor::Wayland(manager.inhibit(&self.queue, surface))
+            },
+            None => IdleInhibitor::DBus(dbus::screensaver::inhibit(IDLE_INHIBIT_REASON)),
+        };
+        self.idle_inhibitor = Some(inhibitor);
+    }
+
     /// Apply pending text input changes.
     fn update_text_input(&mut self) {
         if !self.views.take_text_input_dirty() && !self.text_input_dirty {
@@ -421,6 +1021,60 @@ impl Window {
     }
 }
 
+/// Resolve the configuration used by the UI.
+///
+/// This applies the theme variant, the TileJSON tile source and settings
+/// changed at runtime to the user's configuration.
+fn resolve_config(
+    base_config: &Config,
+    theme: ThemeVariant,
+    country_units: Units,
+    tilejson: Option<&TileJson>,
+    input_override: &InputOverride,
+    online_services: bool,
+) -> Config {
+    let mut config = base_config.themed(theme);
+
+    // Use the current country's units for automatic unit selection.
+    if config.navigation.units == Units::Auto {
+        config.navigation.units = country_units;
+    }
+
+    if let Some(tilejson) = tilejson {
+        tilejson.apply(&mut config.tiles);
+    }
+
+    input_override.apply(&mut config.input);
+
+    // Disable online search and routing if the user prefers offline data.
+    if !online_services {
+        config.search.photon_url = Default::default();
+        config.search.valhalla_url = Default::default();
+    }
+
+    config
+}
+
+/// Get the theme variant for a location.
+///
+/// Without a known location, automatic theme selection keeps the `current`
+/// variant.
+fn theme_variant(theme: &Theme, location: Option<GeoPoint>, current: ThemeVariant) -> ThemeVariant {
+    match (theme.mode, location) {
+        (ThemeMode::Light, _) => ThemeVariant::Light,
+        (ThemeMode::Dark, _) => ThemeVariant::Dark,
+        (ThemeMode::Auto, Some(location)) => {
+            let margin = Duration::from_secs(theme.margin as u64 * 60);
+            if sun::is_daytime(location, SystemTime::now(), margin) {
+                ThemeVariant::Light
+            } else {
+                ThemeVariant::Dark
+            }
+        },
+        (ThemeMode::Auto, None) => current,
+    }
+}
+
 /// Text input with enabled-state tracking.
 #[derive(Debug)]
 pub struct TextInput {
@@ -492,3 +1146,18 @@ impl TextInput {
         self.text_input.commit();
     }
 }
+
+/// Active screen idle inhibition.
+enum IdleInhibitor {
+    Wayland(ZwpIdleInhibitorV1),
+    /// Dropping the sender releases the DBus inhibition.
+    DBus(oneshot::Sender<()>),
+}
+
+impl Drop for IdleInhibitor {
+    fn drop(&mut self) {
+        if let Self::Wayland(inhibitor) = self {
+            inhibitor.destroy();
+        }
+    }
+}
@@ -32,26 +32,34 @@ use smithay_client_toolkit::seat::pointer::{
 };
 use smithay_client_toolkit::seat::touch::TouchHandler;
 use smithay_client_toolkit::seat::{Capability, SeatHandler, SeatState};
+use smithay_client_toolkit::shell::WaylandSurface;
+use smithay_client_toolkit::shell::wlr_layer::{
+    LayerShell, LayerShellHandler, LayerSurface, LayerSurfaceConfigure,
+};
 use smithay_client_toolkit::shell::xdg::XdgShell;
 use smithay_client_toolkit::shell::xdg::window::{Window, WindowConfigure, WindowHandler};
 use smithay_client_toolkit::{
-    delegate_compositor, delegate_data_device, delegate_keyboard, delegate_output,
+    delegate_compositor, delegate_data_device, delegate_keyboard, delegate_layer, delegate_output,
     delegate_pointer, delegate_registry, delegate_seat, delegate_touch, delegate_xdg_shell,
     delegate_xdg_window, registry_handlers,
 };
 
 use crate::geometry::Size;
 use crate::wayland::fractional_scale::{FractionalScaleHandler, FractionalScaleManager};
+use crate::wayland::idle_inhibit::IdleInhibitManager;
 use crate::wayland::viewporter::Viewporter;
-use crate::{Error, KeyboardState, State};
+use crate::{Error, KeyboardState, PNG_MIME_TYPE, State};
 
 pub mod fractional_scale;
+pub mod idle_inhibit;
 pub mod viewporter;
 
 /// Wayland protocol globals.
 #[derive(Debug)]
 pub struct ProtocolStates {
     pub fractional_scale: Option<FractionalScaleManager>,
+    pub idle_inhibit: Option<IdleInhibitManager>,
+    pub layer_shell: Option<LayerShell>,
     pub data_device_manager: DataDeviceManagerState,
     pub compositor: CompositorState,
     pub data_device: DataDevice,
@@ -76,6 +84,8 @@ impl ProtocolStates {
         let viewporter = Viewporter::new(globals, queue)
             .map_err(|err| Error::WaylandProtocol("wp_viewporter", err))?;
         let fractional_scale = FractionalScaleManager::new(globals, queue).ok();
+        let idle_inhibit = IdleInhibitManager::new(globals, queue).ok();
+        let layer_shell = LayerShell::bind(globals, queue).ok();
         let seat = SeatState::new(globals, queue);
         let data_device_manager = DataDeviceManagerState::bind(globals, queue)
             .map_err(|err| Error::WaylandProtocol("wl_data_device_manager", err))?;
@@ -87,6 +97,8 @@ impl ProtocolStates {
         Ok(Self {
             data_device_manager,
             fractional_scale,
+            idle_inhibit,
+            layer_shell,
             data_device,
             text_input,
             compositor,
@@ -104,10 +116,16 @@ impl CompositorHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _surface: &WlSurface,
+        surface: &WlSurface,
         factor: i32,
     ) {
-        if self.protocol_states.fractional_scale.is_none() {
+        if self.protocol_states.fractional_scale.is_some() {
+            return;
+        }
+
+        if let Some(surface) = self.window.surface(surface) {
+            surface.set_scale_factor(factor as f64);
+        } else {
             self.window.set_scale_factor(factor as f64);
         }
     }
@@ -116,10 +134,14 @@ impl CompositorHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _surface: &WlSurface,
+        surface: &WlSurface,
         _time: u32,
     ) {
-        self.window.draw();
+        if let Some(surface) = self.window.surface(surface) {
+            surface.draw();
+        } else {
+            self.window.draw();
+        }
     }
 
     fn transform_changed(
@@ -135,18 +157,25 @@ impl CompositorHandler for State {
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &WlSurface,
-        _output: &WlOutput,
+        surface: &WlSurface,
+        output: &WlOutput,
     ) {
+        // Only the main window's output is used for zoom level tracking.
+        if self.window.is_main_surface(surface) {
+            self.window.output_entered(&self.protocol_states.output, output);
+        }
     }
 
     fn surface_leave(
         &mut self,
         _conn: &Connection,
         _qh: &QueueHandle<Self>,
-        _surface: &WlSurface,
-        _output: &WlOutput,
+        surface: &WlSurface,
+        output: &WlOutput,
     ) {
+        if self.window.is_main_surface(surface) {
+            self.window.output_left(&self.protocol_states.output, output);
+        }
     }
 }
 delegate_compositor!(State);
@@ -160,7 +189,9 @@ impl OutputHandler for State {
 
     fn update_output(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
 
-    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, _: WlOutput) {}
+    fn output_destroyed(&mut self, _: &Connection, _: &QueueHandle<Self>, output: WlOutput) {
+        self.window.output_left(&self.protocol_states.output, &output);
+    }
 }
 delegate_output!(State);
 
@@ -169,37 +200,84 @@ impl WindowHandler for State {
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _window: &Window,
+        window: &Window,
     ) {
-        self.terminated = true;
+        match self.window.surface(window.wl_surface()) {
+            Some(surface) => {
+                let kind = surface.kind();
+                self.window.close_surface(kind);
+            },
+            None => self.terminated = true,
+        }
     }
 
     fn configure(
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _window: &Window,
+        window: &Window,
         configure: WindowConfigure,
         _serial: u32,
     ) {
-        if let (Some(width), Some(height)) = configure.new_size {
-            let size = Size::new(width.get(), height.get());
-            self.window.set_size(&self.protocol_states.compositor, size);
+        let size = match configure.new_size {
+            (Some(width), Some(height)) => Some(Size::new(width.get(), height.get())),
+            _ => None,
+        };
+
+        let compositor = &self.protocol_states.compositor;
+        if let Some(surface) = self.window.surface(window.wl_surface()) {
+            surface.configure(compositor, size);
+        } else if let Some(size) = size {
+            self.window.set_size(compositor, size);
         }
     }
 }
 delegate_xdg_window!(State);
 delegate_xdg_shell!(State);
 
+impl LayerShellHandler for State {
+    fn closed(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        layer: &LayerSurface,
+    ) {
+        if let Some(surface) = self.window.surface(layer.wl_surface()) {
+            let kind = surface.kind();
+            self.window.close_surface(kind);
+        }
+    }
+
+    fn configure(
+        &mut self,
+        _connection: &Connection,
+        _queue: &QueueHandle<Self>,
+        layer: &LayerSurface,
+        configure: LayerSurfaceConfigure,
+        _serial: u32,
+    ) {
+        let compositor = &self.protocol_states.compositor;
+        if let Some(surface) = self.window.surface(layer.wl_surface()) {
+            let (width, height) = configure.new_size;
+            surface.configure(compositor, Some(Size::new(width, height)));
+        }
+    }
+}
+delegate_layer!(State);
+
 impl FractionalScaleHandler for State {
     fn scale_factor_changed(
         &mut self,
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
-        _surface: &WlSurface,
+        surface: &WlSurface,
         factor: f64,
     ) {
-        self.window.set_scale_factor(factor);
+        if let Some(surface) = self.window.surface(surface) {
+            surface.set_scale_factor(factor);
+        } else {
+            self.window.set_scale_factor(factor);
+        }
     }
 }
 
@@ -273,12 +351,12 @@ impl KeyboardHandler for State {
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
         _keyboard: &WlKeyboard,
-        _surface: &WlSurface,
+        surface: &WlSurface,
         _serial: u32,
         _raws: &[u32],
         _keysyms: &[Keysym],
     ) {
-        self.window.keyboard_enter();
+        self.window.keyboard_enter(surface);
     }
 
     fn leave(
@@ -286,7 +364,7 @@ impl KeyboardHandler for State {
         _connection: &Connection,
         _queue: &QueueHandle<Self>,
         _keyboard: &WlKeyboard,
-        _surface: &WlSurface,
+        surface: &WlSurface,
         _serial: u32,
     ) {
         let keyboard_state = match &mut self.keyboard {
@@ -297,7 +375,7 @@ impl KeyboardHandler for State {
         // Cancel active key repetition.
         keyboard_state.cancel_repeat(&self.event_loop);
 
-        self.window.keyboard_leave();
+        self.window.keyboard_leave(surface);
     }
 
     fn press_key(
@@ -396,11 +474,11 @@ impl TouchHandler for State {
         _touch: &WlTouch,
         _serial: u32,
         time: u32,
-        _surface: WlSurface,
+        surface: WlSurface,
         slot: i32,
         position: (f64, f64),
     ) {
-        self.window.touch_down(slot, time, position.into());
+        self.window.touch_down(&surface, slot, time, position.into());
     }
 
     fn motion(
@@ -466,7 +544,7 @@ impl PointerHandler for State {
                 PointerEventKind::Press { button: BTN_LEFT, time, .. } => {
                     self.pointer_down = true;
 
-                    self.window.touch_down(-1, time, event.position.into());
+                    self.window.touch_down(&event.surface, -1, time, event.position.into());
                 },
                 PointerEventKind::Motion { .. } if self.pointer_down => {
                     self.window.touch_motion(-1, event.position.into());
@@ -477,6 +555,15 @@ impl PointerHandler for State {
                     self.pointer_down = false;
                 },
                 PointerEventKind::Leave { .. } => self.pointer_down = false,
+                PointerEventKind::Axis { vertical, .. } => {
+                    // Fall back to discrete steps for compositors without high-resolution
+                    // scrolling.
+                    let value120 = match vertical.value120 {
+                        0 => vertical.discrete * 120,
+                        value120 => value120,
+                    };
+                    self.window.scroll(value120);
+                },
                 _ => (),
             }
         }
@@ -519,10 +606,14 @@ impl DataSourceHandler for State {
         _: &Connection,
         _: &QueueHandle<Self>,
         _: &WlDataSource,
-        _: String,
+        mime: String,
         mut pipe: WritePipe,
     ) {
-        let _ = pipe.write_all(self.clipboard.text.as_bytes());
+        let data = match mime.as_str() {
+            PNG_MIME_TYPE => &self.clipboard.image,
+            _ => self.clipboard.text.as_bytes(),
+        };
+        let _ = pipe.write_all(data);
     }
 
     fn cancelled(&mut self, _: &Connection, _: &QueueHandle<Self>, _: &WlDataSource) {}